@@ -268,8 +268,8 @@ pub fn tab_line(
         tabs_before_active.pop().unwrap()
     };
     let mut prefix = match hide_session_name {
-        true => tab_line_prefix(None, mode, palette, cols),
-        false => tab_line_prefix(session_name, mode, palette, cols),
+        true => tab_line_prefix(None, mode.clone(), palette, cols),
+        false => tab_line_prefix(session_name, mode.clone(), palette, cols),
     };
     let prefix_len = get_current_title_len(&prefix);
 
@@ -123,7 +123,7 @@ impl ZellijPlugin for State {
             self.mode_info.style.colors,
             self.mode_info.capabilities,
             self.mode_info.style.hide_session_name,
-            self.mode_info.mode,
+            self.mode_info.mode.clone(),
             &active_swap_layout_name,
             is_swap_layout_dirty,
         );
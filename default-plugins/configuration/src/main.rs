@@ -21,6 +21,9 @@ struct State {
     notification: Option<String>,
     is_setup_wizard: bool,
     ui_size: usize,
+    browsing_permissions: bool,
+    plugin_permissions: Vec<PluginPermission>,
+    selected_permission_index: usize,
 }
 
 impl Default for State {
@@ -51,6 +54,9 @@ impl Default for State {
             notification: None,
             is_setup_wizard: false,
             ui_size: UI_SIZE,
+            browsing_permissions: false,
+            plugin_permissions: vec![],
+            selected_permission_index: 0,
         }
     }
 }
@@ -99,6 +105,8 @@ impl ZellijPlugin for State {
             Event::Key(key) => {
                 if self.remapping_leaders {
                     should_render = self.handle_remapping_screen_key(key);
+                } else if self.browsing_permissions {
+                    should_render = self.handle_permissions_screen_key(key);
                 } else if self.is_setup_wizard {
                     should_render = self.handle_setup_wizard_key(key);
                 } else {
@@ -126,6 +134,8 @@ impl ZellijPlugin for State {
     fn render(&mut self, rows: usize, cols: usize) {
         if self.remapping_leaders {
             self.render_remapping_leaders_screen(rows, cols);
+        } else if self.browsing_permissions {
+            self.render_permissions_screen(rows, cols);
         } else if self.is_setup_wizard {
             self.render_setup_wizard_screen(rows, cols);
         } else {
@@ -263,6 +273,11 @@ impl State {
         } else if key.bare_key == BareKey::Char('l') && key.has_no_modifiers() {
             self.remapping_leaders = true;
             should_render = true;
+        } else if key.bare_key == BareKey::Char('p') && key.has_no_modifiers() {
+            self.browsing_permissions = true;
+            self.selected_permission_index = 0;
+            self.refresh_plugin_permissions();
+            should_render = true;
         } else if (key.bare_key == BareKey::Esc && key.has_no_modifiers())
             || key.is_key_with_ctrl_modifier(BareKey::Char('c'))
         {
@@ -271,6 +286,88 @@ impl State {
         }
         should_render
     }
+    fn handle_permissions_screen_key(&mut self, key: KeyWithModifier) -> bool {
+        let mut should_render = false;
+        let row_count = self.permission_rows().len();
+        if self.notification.is_some() {
+            self.notification = None;
+            should_render = true;
+        } else if key.bare_key == BareKey::Down && key.has_no_modifiers() {
+            if self.selected_permission_index < row_count.saturating_sub(1) {
+                self.selected_permission_index += 1;
+            } else {
+                self.selected_permission_index = 0;
+            }
+            should_render = true;
+        } else if key.bare_key == BareKey::Up && key.has_no_modifiers() {
+            if self.selected_permission_index > 0 {
+                self.selected_permission_index -= 1;
+            } else {
+                self.selected_permission_index = row_count.saturating_sub(1);
+            }
+            should_render = true;
+        } else if (key.bare_key == BareKey::Char('x') || key.bare_key == BareKey::Delete)
+            && key.has_no_modifiers()
+        {
+            if let Some((plugin_url, permission)) =
+                self.permission_rows().get(self.selected_permission_index)
+            {
+                // revoking an empty list of permissions revokes all of them
+                let permissions: Vec<PermissionType> = permission.iter().copied().collect();
+                revoke_plugin_permissions(plugin_url, &permissions);
+                self.notification = Some(match permission {
+                    Some(permission) => format!("Revoked {} from {}", permission, plugin_url),
+                    None => format!("Revoked all permissions from {}", plugin_url),
+                });
+                self.refresh_plugin_permissions();
+                should_render = true;
+            }
+        } else if (key.bare_key == BareKey::Esc && key.has_no_modifiers())
+            || key.is_key_with_ctrl_modifier(BareKey::Char('c'))
+        {
+            self.browsing_permissions = false;
+            should_render = true;
+        }
+        should_render
+    }
+    fn refresh_plugin_permissions(&mut self) {
+        match list_plugin_permissions() {
+            Ok(plugin_permissions) => {
+                self.plugin_permissions = plugin_permissions;
+            },
+            Err(e) => {
+                self.plugin_permissions = vec![];
+                self.notification = Some(e);
+            },
+        }
+        let row_count = self.permission_rows().len();
+        if self.selected_permission_index >= row_count {
+            self.selected_permission_index = row_count.saturating_sub(1);
+        }
+    }
+    // every plugin url followed by each of its granted permissions, a row without a permission
+    // stands for the plugin itself
+    fn permission_rows(&self) -> Vec<(String, Option<PermissionType>)> {
+        let mut rows = vec![];
+        for plugin_permission in &self.plugin_permissions {
+            rows.push((plugin_permission.name.clone(), None));
+            for permission in &plugin_permission.permissions {
+                rows.push((plugin_permission.name.clone(), Some(*permission)));
+            }
+        }
+        rows
+    }
+    fn permission_label(&self, plugin_url: &str, permission: &PermissionType) -> String {
+        let scope = self
+            .plugin_permissions
+            .iter()
+            .find(|plugin_permission| plugin_permission.name == plugin_url)
+            .and_then(|plugin_permission| plugin_permission.scopes.scope(permission));
+        match scope {
+            Some(scope) => format!("{} ({})", permission, scope.join(", ")),
+            None => permission.to_string(),
+        }
+    }
     fn handle_setup_wizard_key(&mut self, key: KeyWithModifier) -> bool {
         let mut should_render = false;
         if self.notification.is_some() {
@@ -949,8 +1046,8 @@ impl State {
         }
     }
     fn render_help_text_main(&self, rows: usize, cols: usize) {
-        let full_help_text = "Help: <↓↑> - navigate, <ENTER> - apply, <SPACE> - apply & save, <l> - leaders, <ESC> - close";
-        let short_help_text = "Help: <↓↑> / <ENTER> / <SPACE> / <l> / <ESC>";
+        let full_help_text = "Help: <↓↑> - navigate, <ENTER> - apply, <SPACE> - apply & save, <l> - leaders, <p> - permissions, <ESC> - close";
+        let short_help_text = "Help: <↓↑> / <ENTER> / <SPACE> / <l> / <p> / <ESC>";
         if cols >= full_help_text.chars().count() {
             print_text_with_coordinates(
                 Text::new(full_help_text)
@@ -958,7 +1055,8 @@ impl State {
                     .color_range(2, 23..30)
                     .color_range(2, 40..47)
                     .color_range(2, 64..67)
-                    .color_range(2, 79..84),
+                    .color_range(2, 79..82)
+                    .color_range(2, 98..103),
                 0,
                 rows,
                 None,
@@ -971,7 +1069,8 @@ impl State {
                     .color_range(2, 13..20)
                     .color_range(2, 23..30)
                     .color_range(2, 33..36)
-                    .color_range(2, 39..44),
+                    .color_range(2, 39..42)
+                    .color_range(2, 45..50),
                 0,
                 rows,
                 None,
@@ -1009,6 +1108,33 @@ impl State {
             );
         }
     }
+    fn render_help_text_permissions(&self, rows: usize, cols: usize) {
+        let full_help_text = "Help: <↓↑> - navigate, <x> - revoke, <ESC> - back";
+        let short_help_text = "Help: <↓↑> / <x> / <ESC>";
+        if cols >= full_help_text.chars().count() {
+            print_text_with_coordinates(
+                Text::new(full_help_text)
+                    .color_range(2, 6..10)
+                    .color_range(2, 23..26)
+                    .color_range(2, 37..42),
+                0,
+                rows,
+                None,
+                None,
+            );
+        } else {
+            print_text_with_coordinates(
+                Text::new(short_help_text)
+                    .color_range(2, 6..10)
+                    .color_range(2, 13..16)
+                    .color_range(2, 19..24),
+                0,
+                rows,
+                None,
+                None,
+            );
+        }
+    }
     fn render_help_text_remapping(&self, rows: usize, cols: usize) {
         let widths = self.remapping_screen_widths();
         if cols >= widths.0 {
@@ -1084,6 +1210,86 @@ impl State {
         self.render_info_line(rows, cols, &primary_modifier_key_text);
         self.render_help_text_main(rows, cols);
     }
+    fn render_permissions_screen(&mut self, rows: usize, cols: usize) {
+        let permission_rows = self.permission_rows();
+        let title = "Permissions granted to plugins:";
+        let screen_width = permission_rows
+            .iter()
+            .map(|(plugin_url, permission)| match permission {
+                Some(permission) => {
+                    self.permission_label(plugin_url, permission)
+                        .chars()
+                        .count()
+                        + 4
+                },
+                None => plugin_url.chars().count() + 2,
+            })
+            .chain(std::iter::once(title.chars().count()))
+            .max()
+            .unwrap_or(0)
+            .min(cols);
+        // title, a blank line and the info line
+        let list_height = rows.saturating_sub(4).max(1);
+        let base_x = cols.saturating_sub(screen_width) / 2;
+        let base_y = rows.saturating_sub(permission_rows.len().min(list_height) + 4) / 2;
+        print_text_with_coordinates(
+            Text::new(title).color_range(2, ..),
+            base_x,
+            base_y,
+            None,
+            None,
+        );
+        if permission_rows.is_empty() {
+            print_text_with_coordinates(
+                Text::new("No permissions were granted to plugins yet."),
+                base_x,
+                base_y + 2,
+                None,
+                None,
+            );
+        } else {
+            let first_visible_row = self
+                .selected_permission_index
+                .saturating_sub(list_height.saturating_sub(1));
+            print_nested_list_with_coordinates(
+                permission_rows
+                    .iter()
+                    .enumerate()
+                    .skip(first_visible_row)
+                    .take(list_height)
+                    .map(|(i, (plugin_url, permission))| {
+                        let item = match permission {
+                            Some(permission) => {
+                                NestedListItem::new(self.permission_label(plugin_url, permission))
+                                    .indent(1)
+                            },
+                            None => NestedListItem::new(plugin_url)
+                                .color_range(self.preset_color_index, ..),
+                        };
+                        if i == self.selected_permission_index {
+                            item.selected()
+                        } else {
+                            item
+                        }
+                    })
+                    .collect(),
+                base_x,
+                base_y + 2,
+                Some(screen_width),
+                None,
+            );
+        }
+        if let Some(notification) = &self.notification {
+            print_text_with_coordinates(
+                Text::new(notification).color_range(3, ..),
+                base_x,
+                base_y + permission_rows.len().min(list_height) + 3,
+                None,
+                None,
+            );
+        }
+        self.render_help_text_permissions(rows, cols);
+    }
     fn render_setup_wizard_screen(&mut self, rows: usize, cols: usize) {
         let primary_modifier_key_text = self.primary_modifier_text();
         let secondary_modifier_key_text = self.secondary_modifier_text();
@@ -13,6 +13,7 @@ struct State {
     received_payload: Option<String>,
     configuration: BTreeMap<String, String>,
     message_to_plugin_payload: Option<String>,
+    restored_state: Option<String>,
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -410,8 +411,17 @@ impl ZellijPlugin for State {
         should_render
     }
 
+    fn save_state(&mut self) -> Option<Vec<u8>> {
+        Some(format!("received {} events", self.received_events.len()).into_bytes())
+    }
+    fn restore_state(&mut self, saved_state: Vec<u8>) -> bool {
+        self.restored_state = Some(String::from_utf8_lossy(&saved_state).to_string());
+        true
+    }
     fn render(&mut self, rows: usize, cols: usize) {
-        if let Some(payload) = self.received_payload.as_ref() {
+        if let Some(restored_state) = self.restored_state.as_ref() {
+            println!("Restored state: {:?}", restored_state);
+        } else if let Some(payload) = self.received_payload.as_ref() {
             println!("Payload from worker: {:?}", payload);
         } else if let Some(payload) = self.message_to_plugin_payload.take() {
             println!("Payload from self: {:?}", payload);
@@ -15,7 +15,7 @@ pub struct KeyShortcut {
     pub key: Option<KeyWithModifier>,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum KeyAction {
     Unlock,
     Lock,
@@ -27,6 +27,7 @@ pub enum KeyAction {
     Session,
     Move,
     Tmux,
+    Custom(String),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -43,7 +44,7 @@ impl KeyShortcut {
     }
 
     pub fn full_text(&self) -> String {
-        match self.action {
+        match &self.action {
             KeyAction::Lock => String::from("LOCK"),
             KeyAction::Unlock => String::from("UNLOCK"),
             KeyAction::Pane => String::from("PANE"),
@@ -54,6 +55,7 @@ impl KeyShortcut {
             KeyAction::Session => String::from("SESSION"),
             KeyAction::Move => String::from("MOVE"),
             KeyAction::Tmux => String::from("TMUX"),
+            KeyAction::Custom(name) => name.to_uppercase(),
         }
     }
     pub fn with_shortened_modifiers(&self, common_modifiers: &Vec<KeyModifier>) -> String {
@@ -93,7 +95,7 @@ impl KeyShortcut {
         self.mode
     }
     pub fn get_action(&self) -> KeyAction {
-        self.action
+        self.action.clone()
     }
     pub fn is_selected(&self) -> bool {
         match self.mode {
@@ -102,7 +104,7 @@ impl KeyShortcut {
         }
     }
     pub fn short_text(&self) -> String {
-        match self.action {
+        match &self.action {
             KeyAction::Lock => String::from("Lo"),
             KeyAction::Unlock => String::from("Un"),
             KeyAction::Pane => String::from("Pa"),
@@ -113,6 +115,12 @@ impl KeyShortcut {
             KeyAction::Session => String::from("Se"),
             KeyAction::Move => String::from("Mo"),
             KeyAction::Tmux => String::from("Tm"),
+            KeyAction::Custom(name) => {
+                let mut name = name.chars();
+                name.next()
+                    .map(|first| first.to_uppercase().chain(name.take(1)).collect())
+                    .unwrap_or_default()
+            },
         }
     }
 }
@@ -596,6 +604,7 @@ fn get_key_shortcut_for_mode<'a>(
         InputMode::Move => KeyAction::Move,
         InputMode::Scroll | InputMode::Search | InputMode::EnterSearch => KeyAction::Search,
         InputMode::Session => KeyAction::Session,
+        InputMode::Custom(custom_input_mode) => KeyAction::Custom(custom_input_mode.clone()),
     };
     for shortcut in shortcuts.iter_mut() {
         if shortcut.action == key_action {
@@ -605,6 +614,36 @@ fn get_key_shortcut_for_mode<'a>(
     None
 }
 
+/// Shortcuts to the custom modes that can be switched to from the current mode (and to the current
+/// mode itself if it is a custom one).
+pub fn custom_mode_shortcuts(
+    help: &ModeInfo,
+    binds: &[(KeyWithModifier, Vec<Action>)],
+) -> Vec<KeyShortcut> {
+    let mut shortcuts = vec![];
+    for custom_input_mode in help.custom_modes() {
+        let key = to_char(action_key(
+            binds,
+            &[Action::SwitchToMode(InputMode::Custom(
+                custom_input_mode.clone(),
+            ))],
+        ));
+        if key.is_some() || help.mode == InputMode::Custom(custom_input_mode.clone()) {
+            let mode = if shortcuts.len() % 2 == 0 {
+                KeyMode::Unselected
+            } else {
+                KeyMode::UnselectedAlternate
+            };
+            shortcuts.push(KeyShortcut::new(
+                mode,
+                KeyAction::Custom(custom_input_mode),
+                key,
+            ));
+        }
+    }
+    shortcuts
+}
+
 pub fn first_line(
     help: &ModeInfo,
     tab_info: Option<&TabInfo>,
@@ -670,6 +709,8 @@ pub fn first_line(
         ),
     ];
 
+    default_keys.append(&mut custom_mode_shortcuts(help, binds));
+
     if let Some(key_shortcut) = get_key_shortcut_for_mode(&mut default_keys, &help.mode) {
         key_shortcut.mode = KeyMode::Selected;
         key_shortcut.key = to_char(action_key(binds, &[TO_NORMAL]));
@@ -19,8 +19,8 @@ use first_line::first_line;
 use one_line_ui::one_line_ui;
 use second_line::{
     floating_panes_are_visible, fullscreen_panes_to_hide, keybinds,
-    locked_floating_panes_are_visible, locked_fullscreen_panes_to_hide, system_clipboard_error,
-    text_copied_hint,
+    locked_floating_panes_are_visible, locked_fullscreen_panes_to_hide, pending_key_sequence_hint,
+    system_clipboard_error, text_copied_hint,
 };
 use tip::utils::get_cached_tip_name;
 
@@ -314,7 +314,9 @@ impl State {
     fn second_line(&self, cols: usize) -> LinePart {
         let active_tab = self.tabs.iter().find(|t| t.active);
 
-        if let Some(copy_destination) = self.text_copy_destination {
+        if !self.mode_info.pending_key_sequence.is_empty() {
+            pending_key_sequence_hint(&self.mode_info)
+        } else if let Some(copy_destination) = self.text_copy_destination {
             text_copied_hint(&self.mode_info.style.colors, copy_destination)
         } else if self.display_system_clipboard_failure {
             system_clipboard_error(&self.mode_info.style.colors)
@@ -390,6 +392,56 @@ pub fn action_key(
         .collect::<Vec<KeyWithModifier>>()
 }
 
+/// Hints for the keybindings of a custom mode, which (unlike the built-in modes) have no known
+/// meaning and are described by the actions they are bound to.
+pub fn custom_mode_keys_and_hints(
+    keymap: &[(KeyWithModifier, Vec<Action>)],
+) -> Vec<(String, String, Vec<KeyWithModifier>)> {
+    keymap
+        .iter()
+        .map(|(key, actions)| {
+            let (full, short) = describe_actions(actions);
+            (full, short, vec![key.clone()])
+        })
+        .collect()
+}
+
+// eg. `Run make deploy` and `Run` for `Run "make" "deploy"; SwitchToMode "normal";`
+fn describe_actions(actions: &[Action]) -> (String, String) {
+    if let [Action::SwitchToMode(input_mode)] = actions {
+        let mode_name = input_mode.name();
+        return (format!("Switch to {}", mode_name), mode_name);
+    }
+    let descriptions: Vec<(String, String)> = actions
+        .iter()
+        .filter(|action| !matches!(action, Action::SwitchToMode(..)))
+        .filter_map(|action| action.to_kdl())
+        .map(|node| {
+            let name = node.name().value().to_owned();
+            let arguments = node
+                .entries()
+                .iter()
+                .filter(|entry| entry.name().is_none())
+                .map(|entry| entry.value().to_string().trim_matches('"').to_owned());
+            let full = std::iter::once(name.clone())
+                .chain(arguments)
+                .collect::<Vec<_>>()
+                .join(" ");
+            (full, name)
+        })
+        .collect();
+    let full = descriptions
+        .iter()
+        .map(|(full, _)| full.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let short = descriptions
+        .first()
+        .map(|(_, short)| short.clone())
+        .unwrap_or_default();
+    (full, short)
+}
+
 /// Get multiple keys for multiple actions.
 ///
 /// An extension of [`action_key`] that iterates over all action tuples and collects the results.
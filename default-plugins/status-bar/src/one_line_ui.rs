@@ -9,7 +9,10 @@ use zellij_tile::prelude::*;
 use zellij_tile_utils::palette_match;
 
 use crate::first_line::{to_char, KeyAction, KeyMode, KeyShortcut};
-use crate::{action_key, action_key_group, color_elements, MORE_MSG, TO_NORMAL};
+use crate::second_line::pending_key_sequence_hint;
+use crate::{
+    action_key, action_key_group, color_elements, custom_mode_keys_and_hints, MORE_MSG, TO_NORMAL,
+};
 use crate::{ColoredElements, LinePart};
 use unicode_width::UnicodeWidthStr;
 
@@ -29,6 +32,14 @@ pub fn one_line_ui(
     render_mode_key_indicators(help, max_len, separator, base_mode_is_locked)
         .map(|mode_key_indicators| append(&mode_key_indicators, &mut max_len))
         .and_then(|_| match help.mode {
+            _ if !help.pending_key_sequence.is_empty() => {
+                let hint = pending_key_sequence_hint(help);
+                if hint.len <= max_len {
+                    Some(append(&hint, &mut max_len))
+                } else {
+                    None
+                }
+            },
             InputMode::Normal | InputMode::Locked => render_secondary_info(help, tab_info, max_len)
                 .map(|secondary_info| append(&secondary_info, &mut max_len)),
             _ => add_keygroup_separator(help, max_len)
@@ -415,6 +426,39 @@ fn base_mode_normal_mode_indicators(help: &ModeInfo) -> HashMap<InputMode, Vec<K
     ])
 }
 
+fn add_custom_mode_indicators(
+    help: &ModeInfo,
+    indicators: &mut HashMap<InputMode, Vec<KeyShortcut>>,
+    base_mode: InputMode,
+) {
+    let normal_binds = &help.get_keybinds_for_mode(InputMode::Normal);
+    for custom_input_mode in help.custom_modes() {
+        let mode = InputMode::Custom(custom_input_mode.clone());
+        let switch_key = to_char(action_key(
+            normal_binds,
+            &[Action::SwitchToMode(mode.clone())],
+        ));
+        if let (Some(switch_key), Some(normal_indicators)) =
+            (switch_key, indicators.get_mut(&InputMode::Normal))
+        {
+            normal_indicators.push(KeyShortcut::new(
+                KeyMode::Unselected,
+                KeyAction::Custom(custom_input_mode.clone()),
+                Some(switch_key),
+            ));
+        }
+        let custom_binds = &help.get_keybinds_for_mode(mode.clone());
+        indicators.insert(
+            mode,
+            vec![KeyShortcut::new(
+                KeyMode::Selected,
+                KeyAction::Custom(custom_input_mode),
+                to_char(action_key(custom_binds, &[to_base_mode(base_mode.clone())])),
+            )],
+        );
+    }
+}
+
 fn render_mode_key_indicators(
     help: &ModeInfo,
     max_len: usize,
@@ -424,11 +468,17 @@ fn render_mode_key_indicators(
     let mut line_part_to_render = LinePart::default();
     let supports_arrow_fonts = !help.capabilities.arrow_fonts;
     let colored_elements = color_elements(help.style.colors, !supports_arrow_fonts);
-    let default_keys = if base_mode_is_locked {
+    let mut default_keys = if base_mode_is_locked {
         base_mode_locked_mode_indicators(help)
     } else {
         base_mode_normal_mode_indicators(help)
     };
+    let base_mode = if base_mode_is_locked {
+        InputMode::Locked
+    } else {
+        InputMode::Normal
+    };
+    add_custom_mode_indicators(help, &mut default_keys, base_mode);
     match common_modifiers_in_all_modes(&default_keys) {
         Some(modifiers) => {
             if let Some(default_keys) = default_keys.get(&help.mode) {
@@ -1136,7 +1186,7 @@ fn get_keys_and_hints(mi: &ModeInfo) -> Vec<(String, String, Vec<KeyWithModifier
 
     // Find a keybinding to get back to "Normal" input mode. In this case we prefer '\n' over other
     // choices. Do it here before we dedupe the keymap below!
-    let base_mode = mi.base_mode;
+    let base_mode = mi.base_mode.clone();
     let to_basemode_keys = base_mode.map(|b| action_key(&old_keymap, &[to_base_mode(b)])).unwrap_or_else(|| action_key(&old_keymap, &[TO_NORMAL]));
     let to_basemode_key = if to_basemode_keys.contains(&KeyWithModifier::new(BareKey::Enter)) {
         vec![KeyWithModifier::new(BareKey::Enter)]
@@ -1280,7 +1330,9 @@ fn get_keys_and_hints(mi: &ModeInfo) -> Vec<(String, String, Vec<KeyWithModifier
         (s("Previous Tab"), s("Previous"), action_key(&km, &[A::GoToPreviousTab, TO_NORMAL])),
         (s("Next Tab"), s("Next"), action_key(&km, &[A::GoToNextTab, TO_NORMAL])),
         (s("Select pane"), s("Select"), to_basemode_key),
-    ]} else if matches!(mi.mode, IM::RenamePane | IM::RenameTab) { vec![
+    ]} else if mi.mode.is_custom() {
+        custom_mode_keys_and_hints(&km)
+    } else if matches!(mi.mode, IM::RenamePane | IM::RenameTab) { vec![
         (s("When done"), s("Done"), to_basemode_key),
     ]} else { vec![] }
 }
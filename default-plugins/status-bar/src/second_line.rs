@@ -8,7 +8,7 @@ use zellij_tile::prelude::*;
 use zellij_tile_utils::palette_match;
 
 use crate::{
-    action_key, action_key_group, style_key_with_modifier,
+    action_key, action_key_group, custom_mode_keys_and_hints, style_key_with_modifier,
     tip::{data::TIPS, TipFn},
     LinePart, MORE_MSG, TO_NORMAL,
 };
@@ -265,7 +265,9 @@ fn get_keys_and_hints(mi: &ModeInfo) -> Vec<(String, String, Vec<KeyWithModifier
         (s("Previous Tab"), s("Previous"), action_key(&km, &[A::GoToPreviousTab, TO_NORMAL])),
         (s("Next Tab"), s("Next"), action_key(&km, &[A::GoToNextTab, TO_NORMAL])),
         (s("Select pane"), s("Select"), to_normal_key),
-    ]} else if matches!(mi.mode, IM::RenamePane | IM::RenameTab) { vec![
+    ]} else if mi.mode.is_custom() {
+        custom_mode_keys_and_hints(&km)
+    } else if matches!(mi.mode, IM::RenamePane | IM::RenameTab) { vec![
         (s("When done"), s("Done"), to_normal_key),
         (s("Select pane"), s("Select"), action_key_group(&km, &[
             &[A::MoveFocus(Dir::Left)], &[A::MoveFocus(Dir::Down)],
@@ -370,6 +372,32 @@ pub fn text_copied_hint(palette: &Palette, copy_destination: CopyDestination) ->
     }
 }
 
+pub fn pending_key_sequence_hint(help: &ModeInfo) -> LinePart {
+    let text_color = palette_match!(match help.style.colors.theme_hue {
+        ThemeHue::Dark => help.style.colors.white,
+        ThemeHue::Light => help.style.colors.black,
+    });
+    let green_color = palette_match!(help.style.colors.green);
+    let prefix = " Key sequence: ";
+    let keys = help
+        .pending_key_sequence
+        .iter()
+        .map(|k| k.to_string())
+        .collect::<Vec<_>>()
+        .join(" + ");
+    let suffix = " + ...";
+    let len = prefix.chars().count() + keys.chars().count() + suffix.chars().count();
+    LinePart {
+        part: ANSIStrings(&[
+            Style::new().fg(text_color).bold().paint(prefix),
+            Style::new().fg(green_color).bold().paint(keys),
+            Style::new().fg(text_color).bold().paint(suffix),
+        ])
+        .to_string(),
+        len,
+    }
+}
+
 pub fn system_clipboard_error(palette: &Palette) -> LinePart {
     let hint = " Error using the system clipboard.";
     let red_color = palette_match!(palette.red);
@@ -32,19 +32,24 @@ pub fn render_tab(
         ThemeHue::Light => palette.black,
     };
     let background_color = if tab.active {
-        palette.green
-    } else if is_alternate_tab {
+        palette.tab_active_bg()
+    } else if is_alternate_tab && palette.ui.tab_inactive_bg.is_none() {
         alternate_tab_color
     } else {
-        palette.fg
+        palette.tab_inactive_bg()
     };
     let foreground_color = match palette.theme_hue {
         ThemeHue::Dark => palette.black,
         ThemeHue::Light => palette.white,
     };
+    let text_color = if tab.active {
+        palette.tab_active_fg()
+    } else {
+        palette.tab_inactive_fg()
+    };
     let left_separator = style!(foreground_color, background_color).paint(separator);
     let mut tab_text_len = text.width() + (separator_width * 2) + 2; // +2 for padding
-    let tab_styled_text = style!(foreground_color, background_color)
+    let tab_styled_text = style!(text_color, background_color)
         .bold()
         .paint(format!(" {} ", text));
 
@@ -53,12 +58,12 @@ pub fn render_tab(
         let (cursor_section, extra_length) = cursors(focused_clients, palette);
         tab_text_len += extra_length + 2; // 2 for cursor_beginning and cursor_end
         let mut s = String::new();
-        let cursor_beginning = style!(foreground_color, background_color)
+        let cursor_beginning = style!(text_color, background_color)
             .bold()
             .paint("[")
             .to_string();
         let cursor_section = ANSIStrings(&cursor_section).to_string();
-        let cursor_end = style!(foreground_color, background_color)
+        let cursor_end = style!(text_color, background_color)
             .bold()
             .paint("]")
             .to_string();
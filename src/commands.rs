@@ -18,18 +18,29 @@ use zellij_client::{
 };
 use zellij_server::{os_input_output::get_server_os_input, start_server as start_server_impl};
 use zellij_utils::{
-    cli::{CliArgs, Command, SessionCommand, Sessions},
-    data::{ConnectToSession, LayoutInfo},
+    async_std::task,
+    cli::{CliArgs, Command, PluginPermissionsAction, PluginSubcommand, SessionCommand, Sessions},
+    data::{ConnectToSession, LayoutInfo, PermissionScopes},
+    downloader::Downloader,
     envs,
+    home::get_default_data_dir,
     input::{
         actions::Action,
         config::{Config, ConfigError},
-        layout::Layout,
+        dir_local_layout::{
+            find_dir_local_layout, DirLocalLayout, TrustedLayouts,
+            DEFAULT_DIR_LOCAL_LAYOUTS_SEARCH_DEPTH,
+        },
+        layout::{Layout, RunPluginLocation},
         options::Options,
+        permission::PermissionCache,
+        plugin_lockfile::{hash_plugin_bytes, version_from_url, LockedPlugin, PluginLockfile},
+        theme_import::{themes_from_path, ThemeFormat},
     },
     miette::{Report, Result},
     nix,
-    setup::{find_default_config_dir, get_layout_dir, Setup},
+    setup::{find_default_config_dir, get_layout_dir, xdg_data_dir, Setup},
+    tempfile,
 };
 
 pub(crate) use crate::sessions::list_sessions;
@@ -290,6 +301,22 @@ pub(crate) fn convert_old_theme_file(old_theme_file: PathBuf) {
     }
 }
 
+pub(crate) fn convert_theme_file(theme_file: PathBuf, theme_format: ThemeFormat) {
+    let sourced_from_external_file = false;
+    match themes_from_path(&theme_file, theme_format, sourced_from_external_file) {
+        Ok(themes) => {
+            if let Some(themes) = themes.to_kdl() {
+                println!("{}", themes);
+            }
+            process::exit(0);
+        },
+        Err(e) => {
+            eprintln!("Failed to convert theme: {}", e);
+            process::exit(1);
+        },
+    }
+}
+
 fn attach_with_cli_client(
     cli_action: zellij_utils::cli::CliAction,
     session_name: &str,
@@ -383,13 +410,57 @@ fn attach_with_session_name(
     }
 }
 
+/// The directory-local layout to start a new session with, if any
+fn find_dir_local_layout_for_session(opts: &CliArgs) -> Option<PathBuf> {
+    if opts.layout.is_some() {
+        return None;
+    }
+    let cli_config_options: Option<Options> = match &opts.command {
+        None => None,
+        Some(Command::Options(cli_options)) => Some(cli_options.clone().into()),
+        Some(Command::Sessions(Sessions::Attach {
+            create,
+            create_background,
+            options,
+            ..
+        })) if *create || *create_background => options
+            .as_deref()
+            .map(|SessionCommand::Options(cli_options)| cli_options.clone().into()),
+        _ => return None,
+    };
+    let config_options = match Config::try_from(opts) {
+        Ok(config) => config.options,
+        Err(_) => return None, // this will be reported when the config is loaded for real
+    };
+    let options = match cli_config_options {
+        Some(cli_config_options) => {
+            if cli_config_options.default_layout.is_some() {
+                return None;
+            }
+            config_options.merge(cli_config_options)
+        },
+        None => config_options,
+    };
+    if !options.dir_local_layouts.unwrap_or(true) {
+        return None;
+    }
+    let search_depth = options
+        .dir_local_layouts_search_depth
+        .unwrap_or(DEFAULT_DIR_LOCAL_LAYOUTS_SEARCH_DEPTH);
+    std::env::current_dir()
+        .ok()
+        .and_then(|cwd| find_dir_local_layout(&cwd, search_depth))
+        .and_then(|layout_path| layout_path.canonicalize().ok())
+}
+
 pub(crate) fn start_client(opts: CliArgs) {
     // look for old YAML config/layout/theme files and convert them to KDL
     convert_old_yaml_files(&opts);
+    let dir_local_layout_path = find_dir_local_layout_for_session(&opts);
     let (
-        config,
-        layout,
-        config_options,
+        mut config,
+        mut layout,
+        mut config_options,
         mut config_without_layout,
         mut config_options_without_layout,
     ) = match Setup::from_cli_args(&opts) {
@@ -405,6 +476,36 @@ pub(crate) fn start_client(opts: CliArgs) {
         },
     };
 
+    // the layout is read only once, so the contents that are trusted are the ones that run
+    let mut dir_local_layout_to_trust = None;
+    if let Some(layout_path) = dir_local_layout_path {
+        let data_dir = opts.data_dir.clone().unwrap_or_else(xdg_data_dir);
+        let trusted_layouts = TrustedLayouts::from_data_dir(&data_dir);
+        match DirLocalLayout::read(
+            layout_path,
+            config_without_layout.clone(),
+            opts.layout_vars.iter().cloned().collect(),
+            &trusted_layouts,
+        ) {
+            Ok((dir_local_layout, layout_config))
+                if dir_local_layout.is_trusted(&trusted_layouts) =>
+            {
+                layout = dir_local_layout.layout;
+                config_options = config_options_without_layout
+                    .clone()
+                    .merge(layout_config.options.clone());
+                config = layout_config;
+            },
+            // the session starts with the default layout and asks whether to open this one
+            Ok((dir_local_layout, _layout_config)) => {
+                dir_local_layout_to_trust = Some((dir_local_layout, data_dir));
+            },
+            Err(e) => {
+                eprintln!("Failed to read the directory-local layout: {}", e);
+            },
+        }
+    }
+
     let mut reconnect_to_session: Option<ConnectToSession> = None;
     let os_input = get_os_input(get_client_os_input);
     loop {
@@ -415,6 +516,7 @@ pub(crate) fn start_client(opts: CliArgs) {
         let mut opts = opts.clone();
         let mut is_a_reconnect = false;
         let mut should_create_detached = false;
+        let dir_local_layout_to_trust = dir_local_layout_to_trust.take();
 
         if let Some(reconnect_to_session) = &reconnect_to_session {
             // this is integration code to make session reconnects work with this existing,
@@ -455,8 +557,11 @@ pub(crate) fn start_client(opts: CliArgs) {
                         Some(&PathBuf::from(layout_name)),
                         layout_dir.clone(),
                         config_without_layout.clone(),
+                        Default::default(),
                     ),
-                    LayoutInfo::Url(url) => Layout::from_url(&url, config_without_layout.clone()),
+                    LayoutInfo::Url(url) => {
+                        Layout::from_url(&url, config_without_layout.clone(), Default::default())
+                    },
                     LayoutInfo::Stringified(stringified_layout) => Layout::from_stringified_layout(
                         &stringified_layout,
                         config_without_layout.clone(),
@@ -574,6 +679,7 @@ pub(crate) fn start_client(opts: CliArgs) {
                 pane_id_to_focus,
                 is_a_reconnect,
                 should_create_detached,
+                dir_local_layout_to_trust,
             );
         } else {
             if let Some(session_name) = opts.session.clone() {
@@ -589,6 +695,7 @@ pub(crate) fn start_client(opts: CliArgs) {
                     None,
                     is_a_reconnect,
                     should_create_detached,
+                    dir_local_layout_to_trust,
                 );
             } else {
                 if let Some(session_name) = config_options.session_name.as_ref() {
@@ -630,6 +737,7 @@ pub(crate) fn start_client(opts: CliArgs) {
                                 None,
                                 is_a_reconnect,
                                 should_create_detached,
+                                dir_local_layout_to_trust,
                             );
                         },
                         _ => {
@@ -645,6 +753,7 @@ pub(crate) fn start_client(opts: CliArgs) {
                                 None,
                                 is_a_reconnect,
                                 should_create_detached,
+                                dir_local_layout_to_trust,
                             );
                         },
                     }
@@ -669,6 +778,7 @@ pub(crate) fn start_client(opts: CliArgs) {
                     None,
                     is_a_reconnect,
                     should_create_detached,
+                    dir_local_layout_to_trust,
                 );
             }
         }
@@ -723,6 +833,240 @@ pub(crate) fn list_aliases(opts: CliArgs) {
     process::exit(0);
 }
 
+pub(crate) fn manage_plugins(subcommand: PluginSubcommand, opts: &CliArgs) {
+    let plugin_dir = opts
+        .data_dir
+        .clone()
+        .unwrap_or_else(get_default_data_dir)
+        .join("plugins");
+    let mut plugin_lockfile = PluginLockfile::from_plugin_dir(&plugin_dir);
+    match subcommand {
+        PluginSubcommand::Permissions { action } => manage_plugin_permissions(action),
+        PluginSubcommand::Install {
+            url,
+            sha256,
+            alias,
+            version,
+        } => {
+            let alias = match alias.or_else(|| plugin_alias_from_url(&url)) {
+                Some(alias) if is_valid_plugin_alias(&alias) => alias,
+                Some(alias) => {
+                    eprintln!("Invalid plugin alias: {}", alias);
+                    process::exit(1);
+                },
+                None => {
+                    eprintln!(
+                        "Could not find a plugin name in {}, please provide one with --alias",
+                        url
+                    );
+                    process::exit(1);
+                },
+            };
+            if plugin_lockfile.get(&alias).is_some() {
+                eprintln!(
+                    "A plugin is already installed as {}, update it with: zellij plugin update {}",
+                    alias, alias
+                );
+                process::exit(1);
+            }
+            let locked_plugin = install_plugin(&mut plugin_lockfile, &alias, url, sha256, version);
+            println!(
+                "Installed plugin {} (sha256 {})",
+                alias, locked_plugin.sha256
+            );
+            println!("Start it with: zellij plugin -- {}", alias);
+        },
+        PluginSubcommand::List => {
+            if plugin_lockfile.locked().is_empty() {
+                println!("No plugins are installed.");
+            }
+            for (alias, locked_plugin) in plugin_lockfile.locked() {
+                match &locked_plugin.version {
+                    Some(version) => println!("{} {}", alias, version),
+                    None => println!("{}", alias),
+                }
+                println!("    url: {}", locked_plugin.url);
+                println!("    sha256: {}", locked_plugin.sha256);
+            }
+        },
+        PluginSubcommand::Update {
+            alias,
+            url,
+            sha256,
+            version,
+        } => {
+            let previously_locked = match plugin_lockfile.get(&alias) {
+                Some(locked_plugin) => locked_plugin.clone(),
+                None => {
+                    eprintln!("No plugin is installed as {}", alias);
+                    process::exit(1);
+                },
+            };
+            let url = url.unwrap_or(previously_locked.url);
+            let locked_plugin = install_plugin(&mut plugin_lockfile, &alias, url, sha256, version);
+            if locked_plugin.sha256 == previously_locked.sha256 {
+                println!(
+                    "Plugin {} is up to date (sha256 {})",
+                    alias, locked_plugin.sha256
+                );
+            } else {
+                println!("Updated plugin {} (sha256 {})", alias, locked_plugin.sha256);
+            }
+        },
+        PluginSubcommand::Remove { alias } => match plugin_lockfile.remove(&alias) {
+            Ok(Some(_)) => println!("Removed plugin {}", alias),
+            Ok(None) => {
+                eprintln!("No plugin is installed as {}", alias);
+                process::exit(1);
+            },
+            Err(e) => {
+                eprintln!("Failed to remove plugin {}: {}", alias, e);
+                process::exit(1);
+            },
+        },
+    }
+    process::exit(0);
+}
+
+// downloads the plugin, makes sure it matches the expected sha256 (if one was given) and locks it
+fn install_plugin(
+    plugin_lockfile: &mut PluginLockfile,
+    alias: &str,
+    url: String,
+    expected_sha256: Option<String>,
+    version: Option<String>,
+) -> LockedPlugin {
+    if !matches!(
+        RunPluginLocation::parse(&url, None),
+        Ok(RunPluginLocation::Remote(_))
+    ) {
+        eprintln!("Only http(s) plugin urls can be installed: {}", url);
+        process::exit(1);
+    }
+    let wasm_bytes = match download_plugin(&url) {
+        Ok(wasm_bytes) => wasm_bytes,
+        Err(e) => {
+            eprintln!("Failed to download plugin from {}: {}", url, e);
+            process::exit(1);
+        },
+    };
+    let sha256 = hash_plugin_bytes(&wasm_bytes);
+    if let Some(expected_sha256) = expected_sha256 {
+        if !expected_sha256.eq_ignore_ascii_case(&sha256) {
+            eprintln!(
+                "The plugin downloaded from {} does not match the expected sha256",
+                url
+            );
+            eprintln!("    expected: {}", expected_sha256.to_lowercase());
+            eprintln!("    got:      {}", sha256);
+            process::exit(1);
+        }
+    }
+    let locked_plugin = LockedPlugin {
+        version: version.or_else(|| version_from_url(&url)),
+        url,
+        sha256,
+    };
+    if let Err(e) = plugin_lockfile.install(alias, locked_plugin.clone(), &wasm_bytes) {
+        eprintln!("Failed to install plugin {}: {}", alias, e);
+        process::exit(1);
+    }
+    locked_plugin
+}
+
+fn download_plugin(url: &str) -> std::result::Result<Vec<u8>, String> {
+    let download_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let downloader = Downloader::new(download_dir.path().to_path_buf());
+    task::block_on(downloader.download(url, Some("plugin.wasm"))).map_err(|e| e.to_string())?;
+    let wasm_bytes =
+        std::fs::read(download_dir.path().join("plugin.wasm")).map_err(|e| e.to_string())?;
+    if !wasm_bytes.starts_with(b"\0asm") {
+        return Err("the downloaded file is not a wasm plugin".to_owned());
+    }
+    Ok(wasm_bytes)
+}
+
+fn plugin_alias_from_url(url: &str) -> Option<String> {
+    url.split(['?', '#'])
+        .next()
+        .and_then(|url| url.rsplit('/').next())
+        .map(|file_name| file_name.trim_end_matches(".wasm").to_owned())
+        .filter(|alias| !alias.is_empty())
+}
+
+fn is_valid_plugin_alias(alias: &str) -> bool {
+    !alias.is_empty()
+        && !alias.starts_with('.')
+        && !alias.contains(['/', '\\'])
+        && !alias.contains(char::is_whitespace)
+}
+
+fn manage_plugin_permissions(action: PluginPermissionsAction) {
+    let mut permission_cache = PermissionCache::from_path_or_default(None);
+    match action {
+        PluginPermissionsAction::List => {
+            let mut granted: Vec<_> = permission_cache.granted().iter().collect();
+            granted.sort_by(|a, b| a.0.cmp(b.0));
+            if granted.is_empty() {
+                println!("No plugin permissions were granted.");
+            }
+            for (plugin_url, permissions) in granted {
+                println!("{}", plugin_url);
+                if permissions.is_empty() {
+                    println!("    (no permissions)");
+                }
+                let scopes = permission_cache.get_permission_scopes(plugin_url);
+                for permission in permissions {
+                    match scopes.scope(permission) {
+                        Some(scope) => println!("    {}: {}", permission, scope.join(", ")),
+                        None => println!("    {}", permission),
+                    }
+                }
+            }
+            process::exit(0);
+        },
+        PluginPermissionsAction::Grant { url, permissions } => {
+            let cache_key = plugin_permission_cache_key(&url, &permission_cache);
+            let mut scopes = PermissionScopes::default();
+            for (permission, scope) in &permissions {
+                if !scope.is_empty() {
+                    let mut merged_scope = scopes.scope(permission).cloned().unwrap_or_default();
+                    merged_scope.extend(scope.iter().cloned());
+                    scopes.set_scope(*permission, merged_scope);
+                }
+            }
+            let permissions = permissions
+                .into_iter()
+                .map(|(permission, _scope)| permission)
+                .collect();
+            permission_cache.grant(cache_key, permissions, scopes);
+        },
+        PluginPermissionsAction::Revoke { url, permissions } => {
+            let cache_key = plugin_permission_cache_key(&url, &permission_cache);
+            if !permission_cache.revoke(&cache_key, &permissions) {
+                eprintln!("No matching permissions were granted to {}", url);
+                process::exit(1);
+            }
+        },
+    }
+    if let Err(e) = permission_cache.write_to_file() {
+        eprintln!("Failed to write plugin permissions: {}", e);
+        process::exit(1);
+    }
+    process::exit(0);
+}
+
+// permissions are cached by the plugin location as zellij displays it (eg. the bare path of a
+// file: plugin), so we accept both that and the url the plugin was launched with
+fn plugin_permission_cache_key(url: &str, permission_cache: &PermissionCache) -> String {
+    if permission_cache.granted().contains_key(url) {
+        return url.to_owned();
+    }
+    RunPluginLocation::parse(url, std::env::current_dir().ok())
+        .map(|location| location.to_string())
+        .unwrap_or_else(|_| url.to_owned())
+}
+
 fn reload_config_from_disk(
     config_without_layout: &mut Config,
     config_options_without_layout: &mut Options,
@@ -29,6 +29,10 @@ fn main() {
             name,
             close_on_exit,
             start_suspended,
+            restart,
+            max_restarts,
+            watch,
+            kill_on_change,
             x,
             y,
             width,
@@ -47,6 +51,10 @@ fn main() {
                 name,
                 close_on_exit,
                 start_suspended,
+                restart,
+                max_restarts,
+                watch,
+                kill_on_change,
                 configuration: None,
                 skip_plugin_cache,
                 x,
@@ -58,17 +66,32 @@ fn main() {
             std::process::exit(0);
         }
         if let Some(Command::Sessions(Sessions::Plugin {
-            url,
+            subcommand: Some(subcommand),
+            ..
+        })) = opts.command.clone()
+        {
+            commands::manage_plugins(subcommand, &opts);
+            std::process::exit(0);
+        }
+        if let Some(Command::Sessions(Sessions::Plugin {
+            url: Some(url),
             floating,
             in_place,
-            configuration,
+            mut configuration,
             skip_plugin_cache,
+            watch,
             x,
             y,
             width,
             height,
+            subcommand: None,
         })) = opts.command
         {
+            if watch {
+                configuration
+                    .get_or_insert_with(Default::default)
+                    .insert("reload_on_change", "true");
+            }
             let cwd = None;
             let command_cli_action = CliAction::NewPane {
                 command: vec![],
@@ -80,6 +103,10 @@ fn main() {
                 name: None,
                 close_on_exit: false,
                 start_suspended: false,
+                restart: None,
+                max_restarts: None,
+                watch: vec![],
+                kill_on_change: false,
                 configuration,
                 skip_plugin_cache,
                 x,
@@ -133,8 +160,15 @@ fn main() {
             commands::convert_old_layout_file(old_layout_file);
             std::process::exit(0);
         }
-        if let Some(Command::Sessions(Sessions::ConvertTheme { old_theme_file })) = opts.command {
-            commands::convert_old_theme_file(old_theme_file);
+        if let Some(Command::Sessions(Sessions::ConvertTheme {
+            old_theme_file,
+            from,
+        })) = opts.command
+        {
+            match from {
+                Some(theme_format) => commands::convert_theme_file(old_theme_file, theme_format),
+                None => commands::convert_old_theme_file(old_theme_file),
+            }
             std::process::exit(0);
         }
         if let Some(Command::Sessions(Sessions::Pipe {
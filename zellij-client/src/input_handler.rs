@@ -3,15 +3,17 @@ use crate::{
     os_input_output::ClientOsApi, stdin_ansi_parser::AnsiStdinInstruction, ClientId,
     ClientInstruction, CommandIsExecuting, InputInstruction,
 };
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use zellij_utils::{
     channels::{Receiver, SenderWithContext, OPENCALLS},
-    data::{InputMode, KeyWithModifier},
+    data::{InputMode, KeyModifier, KeyWithModifier},
     errors::{ContextType, ErrorContext, FatalError},
     input::{
         actions::Action,
         cast_termwiz_key,
         config::Config,
-        mouse::{MouseButton, MouseEvent},
+        macros::{Macro, MacroInstruction, Macros},
+        mouse::{mouse_event_modifiers, MouseButton, MouseButtonWithModifier, MouseEvent},
         options::Options,
     },
     ipc::{ClientToServerMsg, ExitReason},
@@ -31,6 +33,99 @@ impl Default for HeldMouseButton {
     }
 }
 
+// a recording can start from keys sent before the server asked to start it, these are kept until
+// then
+const RECENT_KEYS_TO_KEEP: usize = 32;
+
+/// A macro being recorded, with the number of each key so that the keys bound to stopping the
+/// recording can be left out once the server tells which they are
+struct MacroRecording {
+    name: String,
+    start_mode: InputMode,
+    keys: Vec<(usize, KeyWithModifier)>,
+}
+
+/// Records the keys typed by the user into macros and replays them. Keys are numbered in the order
+/// they are sent to the server, the server refers to them by these numbers since it matches keys
+/// to their bindings while more keys are typed.
+#[derive(Default)]
+struct MacroRecorder {
+    keys_sent: usize,
+    recent_keys: VecDeque<(usize, KeyWithModifier)>,
+    recording: Option<MacroRecording>,
+}
+
+impl MacroRecorder {
+    /// Numbers a key typed by the user, recording it if a recording is on
+    fn key_typed(&mut self, key: &KeyWithModifier) {
+        let key_number = self.keys_sent;
+        self.keys_sent += 1;
+        match self.recording.as_mut() {
+            Some(recording) => recording.keys.push((key_number, key.clone())),
+            None => {
+                self.recent_keys.push_back((key_number, key.clone()));
+                if self.recent_keys.len() > RECENT_KEYS_TO_KEEP {
+                    self.recent_keys.pop_front();
+                }
+            },
+        }
+    }
+    fn start_recording(&mut self, name: String, start_mode: InputMode, from_key: usize) {
+        let keys = self
+            .recent_keys
+            .drain(..)
+            .filter(|(key_number, _)| *key_number >= from_key)
+            .collect();
+        self.recording = Some(MacroRecording {
+            name,
+            start_mode,
+            keys,
+        });
+    }
+    /// Returns the name and keys of the recorded macro, if a recording was on
+    fn stop_recording(&mut self, until_key: usize) -> Option<(String, Macro)> {
+        let MacroRecording {
+            name,
+            start_mode,
+            keys,
+        } = self.recording.take()?;
+        let keys = keys
+            .into_iter()
+            .filter(|(key_number, _)| *key_number < until_key)
+            .map(|(_, key)| key)
+            .collect();
+        Some((
+            name,
+            Macro {
+                start_mode: Some(start_mode),
+                keys,
+            },
+        ))
+    }
+    /// The messages replaying the macro, its keys are numbered but not recorded (the keys that
+    /// played the macro are, if a recording is on)
+    fn replay(&mut self, macro_to_play: Macro, depth: usize) -> Vec<ClientToServerMsg> {
+        let mut messages = vec![ClientToServerMsg::MacroReplayStarted {
+            depth,
+            start_mode: macro_to_play.start_mode,
+        }];
+        for key in macro_to_play.keys {
+            self.keys_sent += 1;
+            let (raw_bytes, is_kitty_keyboard_protocol) = match key.serialize_non_kitty() {
+                Some(serialized) => (serialized.into_bytes(), false),
+                None => (key.serialize_kitty().unwrap_or_default().into_bytes(), true),
+            };
+            messages.push(ClientToServerMsg::Key(
+                key,
+                raw_bytes,
+                is_kitty_keyboard_protocol,
+            ));
+        }
+        messages.push(ClientToServerMsg::MacroReplayEnded);
+        messages
+    }
+}
+
 /// Handles the dispatching of [`Action`]s according to the current
 /// [`InputMode`], and keep tracks of the current [`InputMode`].
 struct InputHandler {
@@ -45,6 +140,7 @@ struct InputHandler {
     receive_input_instructions: Receiver<(InputInstruction, ErrorContext)>,
     holding_mouse: Option<HeldMouseButton>,
     mouse_mode_active: bool,
+    macro_recorder: MacroRecorder,
 }
 
 impl InputHandler {
@@ -69,6 +165,7 @@ impl InputHandler {
             receive_input_instructions,
             holding_mouse: None,
             mouse_mode_active: false,
+            macro_recorder: MacroRecorder::default(),
         }
     }
 
@@ -99,9 +196,10 @@ impl InputHandler {
                             self.handle_key(&key, raw_bytes, false);
                         },
                         InputEvent::Mouse(mouse_event) => {
+                            let key_modifiers = mouse_event_modifiers(&mouse_event);
                             let mouse_event =
                                 zellij_utils::input::mouse::MouseEvent::from(mouse_event);
-                            self.handle_mouse_event(&mouse_event);
+                            self.handle_mouse_event(&mouse_event, key_modifiers);
                         },
                         InputEvent::Paste(pasted_text) => {
                             if self.mode == InputMode::Normal || self.mode == InputMode::Locked {
@@ -154,6 +252,9 @@ impl InputHandler {
                         self.handle_stdin_ansi_instruction(ansi_instruction);
                     }
                 },
+                Ok((InputInstruction::Macro(macro_instruction), _error_context)) => {
+                    self.handle_macro_instruction(macro_instruction);
+                },
                 Ok((InputInstruction::StartedParsing, _error_context)) => {
                     self.send_client_instructions
                         .send(ClientInstruction::StartedParsingStdinQuery)
@@ -179,12 +280,53 @@ impl InputHandler {
     ) {
         // we interpret the keys into actions on the server side so that we can change the
         // keybinds at runtime
+        self.macro_recorder.key_typed(key);
         self.os_input.send_to_server(ClientToServerMsg::Key(
             key.clone(),
             raw_bytes,
             is_kitty_keyboard_protocol,
         ));
     }
+    fn handle_macro_instruction(&mut self, macro_instruction: MacroInstruction) {
+        match macro_instruction {
+            MacroInstruction::StartRecording {
+                name,
+                start_mode,
+                from_key,
+            } => {
+                self.macro_recorder
+                    .start_recording(name, start_mode, from_key);
+            },
+            MacroInstruction::StopRecording { until_key } => {
+                let (name, recorded_macro) = match self.macro_recorder.stop_recording(until_key) {
+                    Some(recorded) => recorded,
+                    None => {
+                        log::warn!("No macro is being recorded");
+                        return;
+                    },
+                };
+                let macros = Macros::from_data(BTreeMap::from([(name, recorded_macro)]));
+                if let Some(macros) = macros.to_kdl() {
+                    // the server adds the macro to the session's configuration so that it can be
+                    // played right away, and it is saved to be loaded in later sessions
+                    let macros = macros.to_string();
+                    self.os_input
+                        .send_to_server(ClientToServerMsg::MacroRecorded(macros.clone()));
+                    self.send_client_instructions
+                        .send(ClientInstruction::WriteMacrosToDisk { macros })
+                        .unwrap();
+                }
+            },
+            MacroInstruction::Play {
+                macro_to_play,
+                depth,
+            } => {
+                for message in self.macro_recorder.replay(macro_to_play, depth) {
+                    self.os_input.send_to_server(message);
+                }
+            },
+        }
+    }
     fn handle_stdin_ansi_instruction(&mut self, ansi_stdin_instructions: AnsiStdinInstruction) {
         match ansi_stdin_instructions {
             AnsiStdinInstruction::PixelDimensions(pixel_dimensions) => {
@@ -212,41 +354,46 @@ impl InputHandler {
                     .send(ClientInstruction::SetSynchronizedOutput(enabled))
                     .unwrap();
             },
+            AnsiStdinInstruction::ColorScheme(color_scheme) => {
+                self.os_input
+                    .send_to_server(ClientToServerMsg::ColorScheme(color_scheme));
+            },
         }
     }
-    fn handle_mouse_event(&mut self, mouse_event: &MouseEvent) {
+    fn handle_mouse_event(
+        &mut self,
+        mouse_event: &MouseEvent,
+        key_modifiers: BTreeSet<KeyModifier>,
+    ) {
         match *mouse_event {
-            MouseEvent::Press(button, point) => match button {
-                MouseButton::WheelUp => {
-                    self.dispatch_action(Action::ScrollUpAt(point), None);
-                },
-                MouseButton::WheelDown => {
-                    self.dispatch_action(Action::ScrollDownAt(point), None);
-                },
-                MouseButton::Left => {
-                    if self.holding_mouse.is_some() {
+            MouseEvent::Press(button, point) => {
+                let held_button = match button {
+                    MouseButton::Left => Some(HeldMouseButton::Left),
+                    MouseButton::Right => Some(HeldMouseButton::Right),
+                    MouseButton::Middle => Some(HeldMouseButton::Middle),
+                    MouseButton::WheelUp | MouseButton::WheelDown => None,
+                };
+                match held_button {
+                    Some(HeldMouseButton::Left) if self.holding_mouse.is_some() => {
                         self.dispatch_action(Action::MouseHoldLeft(point), None);
-                    } else {
-                        self.dispatch_action(Action::LeftClick(point), None);
-                    }
-                    self.holding_mouse = Some(HeldMouseButton::Left);
-                },
-                MouseButton::Right => {
-                    if self.holding_mouse.is_some() {
+                    },
+                    Some(HeldMouseButton::Right) if self.holding_mouse.is_some() => {
                         self.dispatch_action(Action::MouseHoldRight(point), None);
-                    } else {
-                        self.dispatch_action(Action::RightClick(point), None);
-                    }
-                    self.holding_mouse = Some(HeldMouseButton::Right);
-                },
-                MouseButton::Middle => {
-                    if self.holding_mouse.is_some() {
+                    },
+                    Some(HeldMouseButton::Middle) if self.holding_mouse.is_some() => {
                         self.dispatch_action(Action::MouseHoldMiddle(point), None);
-                    } else {
-                        self.dispatch_action(Action::MiddleClick(point), None);
-                    }
-                    self.holding_mouse = Some(HeldMouseButton::Middle);
-                },
+                    },
+                    _ => {
+                        // the server picks the actions bound to this button, if any
+                        let button =
+                            MouseButtonWithModifier::new_with_modifiers(button, key_modifiers);
+                        self.os_input
+                            .send_to_server(ClientToServerMsg::MousePress(button, point));
+                    },
+                }
+                if held_button.is_some() {
+                    self.holding_mouse = held_button;
+                }
             },
             MouseEvent::Release(point) => {
                 let button_released = self.holding_mouse.unwrap_or_default();
@@ -380,3 +527,122 @@ pub(crate) fn input_loop(
     )
     .handle_input();
 }
+
+#[cfg(test)]
+mod macro_recorder_tests {
+    use super::*;
+    use zellij_utils::data::BareKey;
+
+    fn typed_keys(recorder: &mut MacroRecorder, characters: &str) {
+        for character in characters.chars() {
+            recorder.key_typed(&KeyWithModifier::new(BareKey::Char(character)));
+        }
+    }
+
+    fn keys(characters: &str) -> Vec<KeyWithModifier> {
+        characters
+            .chars()
+            .map(|character| KeyWithModifier::new(BareKey::Char(character)))
+            .collect()
+    }
+
+    #[test]
+    pub fn macro_recording_keeps_the_keys_between_start_and_stop() {
+        let mut recorder = MacroRecorder::default();
+        typed_keys(&mut recorder, "abq"); // keys 0-2, "q" starts the recording
+        recorder.start_recording("q".into(), InputMode::Normal, 3);
+        typed_keys(&mut recorder, "lsQ"); // keys 3-5, "Q" stops it
+        let recorded = recorder.stop_recording(5);
+        assert_eq!(
+            recorded,
+            Some((
+                "q".into(),
+                Macro {
+                    start_mode: Some(InputMode::Normal),
+                    keys: keys("ls"),
+                }
+            )),
+            "only the keys typed while recording are kept, without those starting and stopping it"
+        );
+        assert_eq!(
+            recorder.stop_recording(6),
+            None,
+            "nothing is being recorded"
+        );
+    }
+
+    #[test]
+    pub fn macro_recording_includes_keys_sent_before_it_was_started() {
+        let mut recorder = MacroRecorder::default();
+        // "q" (key 0) starts the recording, "ls" were typed before the server asked to start it
+        typed_keys(&mut recorder, "qls");
+        recorder.start_recording("q".into(), InputMode::Locked, 1);
+        typed_keys(&mut recorder, "\n");
+        // a two key sequence ("Q" and "Q") stops it
+        typed_keys(&mut recorder, "QQ");
+        let (_name, recorded_macro) = recorder.stop_recording(4).unwrap();
+        assert_eq!(recorded_macro.keys, keys("ls\n"));
+        assert_eq!(recorded_macro.start_mode, Some(InputMode::Locked));
+    }
+
+    #[test]
+    pub fn replayed_keys_are_not_recorded() {
+        let mut recorder = MacroRecorder::default();
+        typed_keys(&mut recorder, "q"); // key 0
+        recorder.start_recording("q".into(), InputMode::Normal, 1);
+        typed_keys(&mut recorder, "a@"); // keys 1-2, "@" plays another macro
+        let replay = recorder.replay(
+            Macro {
+                start_mode: None,
+                keys: keys("xyz"),
+            },
+            1,
+        ); // keys 3-5
+        assert_eq!(
+            replay.len(),
+            5,
+            "the keys are sent between the replay markers"
+        );
+        typed_keys(&mut recorder, "bQ"); // keys 6-7
+        let (_name, recorded_macro) = recorder.stop_recording(7).unwrap();
+        assert_eq!(
+            recorded_macro.keys,
+            keys("a@b"),
+            "the key playing the macro is recorded rather than the keys it played"
+        );
+    }
+
+    #[test]
+    pub fn macro_replay_switches_to_the_start_mode_and_sends_the_keys() {
+        let mut recorder = MacroRecorder::default();
+        let ctrl_p = KeyWithModifier::new(BareKey::Char('p')).with_ctrl_modifier();
+        let replay = recorder.replay(
+            Macro {
+                start_mode: Some(InputMode::Locked),
+                keys: vec![ctrl_p.clone(), KeyWithModifier::new(BareKey::Char('d'))],
+            },
+            2,
+        );
+        assert!(matches!(
+            replay[0],
+            ClientToServerMsg::MacroReplayStarted {
+                depth: 2,
+                start_mode: Some(InputMode::Locked)
+            }
+        ));
+        match &replay[1] {
+            ClientToServerMsg::Key(key, raw_bytes, false) => {
+                assert_eq!(key, &ctrl_p);
+                assert_eq!(
+                    raw_bytes,
+                    &vec![16],
+                    "the key is sent as the terminal would send it"
+                );
+            },
+            message => panic!("expected a key, got {:?}", message),
+        }
+        assert!(matches!(&replay[2], ClientToServerMsg::Key(..)));
+        assert!(matches!(replay[3], ClientToServerMsg::MacroReplayEnded));
+        assert_eq!(recorder.keys_sent, 2, "replayed keys are numbered");
+    }
+}
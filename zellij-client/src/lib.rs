@@ -11,7 +11,7 @@ mod stdin_handler;
 use log::info;
 use std::env::current_exe;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -31,7 +31,10 @@ use zellij_utils::{
     data::{ClientId, ConnectToSession, KeyWithModifier, Style},
     envs,
     errors::{ClientContext, ContextType, ErrorInstruction},
-    input::{config::Config, options::Options},
+    input::{
+        config::Config, dir_local_layout::DirLocalLayout, macros::MacroInstruction,
+        options::Options,
+    },
     ipc::{ClientAttributes, ClientToServerMsg, ExitReason, ServerToClientMsg},
     pane_size::Size,
     termwiz::input::InputEvent,
@@ -57,6 +60,8 @@ pub(crate) enum ClientInstruction {
     CliPipeOutput(String, String), // String -> pipe name, String -> output
     QueryTerminalSize,
     WriteConfigToDisk { config: String },
+    WriteMacrosToDisk { macros: String },
+    Macro(MacroInstruction),
 }
 
 impl From<ServerToClientMsg> for ClientInstruction {
@@ -82,6 +87,9 @@ impl From<ServerToClientMsg> for ClientInstruction {
             ServerToClientMsg::WriteConfigToDisk { config } => {
                 ClientInstruction::WriteConfigToDisk { config }
             },
+            ServerToClientMsg::Macro(macro_instruction) => {
+                ClientInstruction::Macro(macro_instruction)
+            },
         }
     }
 }
@@ -105,6 +113,8 @@ impl From<&ClientInstruction> for ClientContext {
             ClientInstruction::CliPipeOutput(..) => ClientContext::CliPipeOutput,
             ClientInstruction::QueryTerminalSize => ClientContext::QueryTerminalSize,
             ClientInstruction::WriteConfigToDisk { .. } => ClientContext::WriteConfigToDisk,
+            ClientInstruction::WriteMacrosToDisk { .. } => ClientContext::WriteMacrosToDisk,
+            ClientInstruction::Macro(..) => ClientContext::Macro,
         }
     }
 }
@@ -158,6 +168,7 @@ pub(crate) enum InputInstruction {
     KeyEvent(InputEvent, Vec<u8>),
     KeyWithModifierEvent(KeyWithModifier, Vec<u8>),
     AnsiStdinInstructions(Vec<AnsiStdinInstruction>),
+    Macro(MacroInstruction),
     StartedParsing,
     DoneParsing,
     Exit,
@@ -174,6 +185,7 @@ pub fn start_client(
     pane_id_to_focus: Option<(u32, bool)>, // (pane_id, is_plugin)
     is_a_reconnect: bool,
     start_detached_and_exit: bool,
+    dir_local_layout_to_trust: Option<(DirLocalLayout, PathBuf)>, // PathBuf - data dir
 ) -> Option<ConnectToSession> {
     if start_detached_and_exit {
         start_server_detached(os_input, opts, config, config_options, info, layout);
@@ -190,6 +202,9 @@ pub fn start_client(
     let take_snapshot = "\u{1b}[?1049h";
     let bracketed_paste = "\u{1b}[?2004h";
     let enter_kitty_keyboard_mode = "\u{1b}[>1u";
+    // <ESC>[?2031h => report changes of the color scheme (light/dark) of the terminal
+    // <ESC>[?996n => report the current color scheme
+    let enable_color_scheme_reports = "\u{1b}[?2031h\u{1b}[?996n";
     os_input.unset_raw_mode(0).unwrap();
 
     if !is_a_reconnect {
@@ -284,8 +299,17 @@ pub fn start_client(
         },
     };
 
+    let is_new_session = matches!(first_msg, ClientToServerMsg::NewClient(..));
     os_input.connect_to_server(&*ipc_pipe);
     os_input.send_to_server(first_msg);
+    if let Some((dir_local_layout, data_dir)) = dir_local_layout_to_trust {
+        if is_new_session {
+            os_input.send_to_server(ClientToServerMsg::PromptToTrustDirLocalLayout(
+                Box::new(dir_local_layout),
+                data_dir,
+            ));
+        }
+    }
 
     let mut command_is_executing = CommandIsExecuting::new();
 
@@ -294,6 +318,10 @@ pub fn start_client(
         .get_stdout_writer()
         .write(bracketed_paste.as_bytes())
         .unwrap();
+    let _ = os_input
+        .get_stdout_writer()
+        .write(enable_color_scheme_reports.as_bytes())
+        .unwrap();
 
     let (send_client_instructions, receive_client_instructions): ChannelWithContext<
         ClientInstruction,
@@ -341,7 +369,7 @@ pub fn start_client(
             let send_client_instructions = send_client_instructions.clone();
             let command_is_executing = command_is_executing.clone();
             let os_input = os_input.clone();
-            let default_mode = config_options.default_mode.unwrap_or_default();
+            let default_mode = config_options.default_mode.clone().unwrap_or_default();
             move || {
                 input_loop(
                     os_input,
@@ -564,6 +592,15 @@ pub fn start_client(
                     },
                 }
             },
+            ClientInstruction::WriteMacrosToDisk { macros } => {
+                if let Err(e) = Config::write_macros_to_disk(macros, &opts) {
+                    log::error!("Failed to write macros to disk: {}", e);
+                }
+            },
+            ClientInstruction::Macro(macro_instruction) => {
+                // keys are recorded and replayed by the input thread
+                let _ = send_input_instructions.send(InputInstruction::Macro(macro_instruction));
+            },
             _ => {},
         }
     }
@@ -589,6 +626,10 @@ pub fn start_client(
             let _ = stdout.write(exit_kitty_keyboard_mode.as_bytes()).unwrap();
             stdout.flush().unwrap();
         }
+        let disable_color_scheme_reports = "\u{1b}[?2031l";
+        let _ = stdout
+            .write(disable_color_scheme_reports.as_bytes())
+            .unwrap();
         let _ = stdout.write(goodbye_message.as_bytes()).unwrap();
         stdout.flush().unwrap();
     } else {
@@ -2,8 +2,12 @@ use std::time::{Duration, Instant};
 
 const STARTUP_PARSE_DEADLINE_MS: u64 = 500;
 use zellij_utils::{
-    consts::ZELLIJ_STDIN_CACHE_FILE, ipc::PixelDimensions, lazy_static::lazy_static,
-    pane_size::SizeInPixels, regex::Regex,
+    consts::ZELLIJ_STDIN_CACHE_FILE,
+    data::ThemeHue,
+    ipc::PixelDimensions,
+    lazy_static::lazy_static,
+    pane_size::SizeInPixels,
+    regex::{bytes, Regex},
 };
 
 use serde::{Deserialize, Serialize};
@@ -159,6 +163,14 @@ impl StdinAnsiParser {
             } else {
                 self.raw_buffer.clear();
             }
+        } else if byte == b'n' {
+            self.raw_buffer.push(byte);
+            if let Some(ansi_sequence) =
+                AnsiStdinInstruction::color_scheme_from_bytes(&self.raw_buffer)
+            {
+                self.pending_events.push(ansi_sequence);
+                self.raw_buffer.clear();
+            }
         } else if byte == b'y' {
             self.raw_buffer.push(byte);
             if let Some(ansi_sequence) =
@@ -180,6 +192,7 @@ pub enum AnsiStdinInstruction {
     ForegroundColor(String),
     ColorRegisters(Vec<(usize, String)>),
     SynchronizedOutput(Option<SyncOutput>),
+    ColorScheme(ThemeHue),
 }
 
 impl AnsiStdinInstruction {
@@ -264,6 +277,47 @@ impl AnsiStdinInstruction {
         Some(AnsiStdinInstruction::ColorRegisters(registers))
     }
 
+    pub fn color_scheme_from_bytes(bytes: &[u8]) -> Option<Self> {
+        // eg. <ESC>[?997;1n => dark, <ESC>[?997;2n => light
+        lazy_static! {
+            static ref RE: Regex = Regex::new(r"\u{1b}\[\?997;([12])n$").unwrap();
+        }
+        let key_string = String::from_utf8_lossy(bytes);
+        let captures = RE.captures_iter(&key_string).next()?;
+        match &captures[1] {
+            "1" => Some(AnsiStdinInstruction::ColorScheme(ThemeHue::Dark)),
+            "2" => Some(AnsiStdinInstruction::ColorScheme(ThemeHue::Light)),
+            _ => None,
+        }
+    }
+    /// Takes the color scheme reports out of `bytes` read from STDIN (the terminal sends them
+    /// whenever its color scheme changes, possibly along with keystrokes), returns them along
+    /// with the remaining bytes
+    pub fn take_color_scheme_reports(bytes: &[u8]) -> (Vec<Self>, Vec<u8>) {
+        lazy_static! {
+            static ref RE: bytes::Regex = bytes::Regex::new(r"\x1b\[\?997;([12])n").unwrap();
+        }
+        let mut color_schemes = vec![];
+        let mut remaining_bytes = vec![];
+        let mut end_of_last_report = 0;
+        for captures in RE.captures_iter(bytes) {
+            let report = captures.get(0).unwrap(); // the whole match always exists
+            remaining_bytes.extend_from_slice(&bytes[end_of_last_report..report.start()]);
+            end_of_last_report = report.end();
+            color_schemes.push(match &captures[1] {
+                b"1" => AnsiStdinInstruction::ColorScheme(ThemeHue::Dark),
+                _ => AnsiStdinInstruction::ColorScheme(ThemeHue::Light),
+            });
+        }
+        remaining_bytes.extend_from_slice(&bytes[end_of_last_report..]);
+        (color_schemes, remaining_bytes)
+    }
+    /// Whether this instruction describes the terminal in a way that stays valid across
+    /// sessions, so that it can be cached
+    pub fn is_cacheable(&self) -> bool {
+        !matches!(self, AnsiStdinInstruction::ColorScheme(_))
+    }
+
     pub fn synchronized_output_from_bytes(bytes: &[u8]) -> Option<Self> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"^\u{1b}\[\?2026;([0|1|2|3|4])\$y$").unwrap();
@@ -313,3 +367,76 @@ fn color_sequence_from_bytes(bytes: &[u8]) -> Result<(usize, String), &'static s
         Err("invalid_instruction")
     }
 }
+
+#[test]
+pub fn can_parse_color_scheme_reports() {
+    assert!(
+        matches!(
+            AnsiStdinInstruction::color_scheme_from_bytes("\u{1b}[?997;1n".as_bytes()),
+            Some(AnsiStdinInstruction::ColorScheme(ThemeHue::Dark))
+        ),
+        "Can parse a dark color scheme report"
+    );
+    assert!(
+        matches!(
+            AnsiStdinInstruction::color_scheme_from_bytes("\u{1b}[?997;2n".as_bytes()),
+            Some(AnsiStdinInstruction::ColorScheme(ThemeHue::Light))
+        ),
+        "Can parse a light color scheme report"
+    );
+    assert!(
+        AnsiStdinInstruction::color_scheme_from_bytes("\u{1b}[?997;3n".as_bytes()).is_none(),
+        "Unknown color schemes are ignored"
+    );
+    assert!(
+        AnsiStdinInstruction::color_scheme_from_bytes("\u{1b}[6n".as_bytes()).is_none(),
+        "Other device status reports are ignored"
+    );
+}
+
+#[test]
+pub fn color_scheme_reports_are_taken_out_of_keystrokes() {
+    let (color_schemes, remaining_bytes) = AnsiStdinInstruction::take_color_scheme_reports(
+        "a\u{1b}[?997;1nb\u{1b}[?997;2n\u{1b}[A".as_bytes(),
+    );
+    assert!(matches!(
+        color_schemes.as_slice(),
+        [
+            AnsiStdinInstruction::ColorScheme(ThemeHue::Dark),
+            AnsiStdinInstruction::ColorScheme(ThemeHue::Light)
+        ]
+    ));
+    assert_eq!(
+        remaining_bytes,
+        "ab\u{1b}[A".as_bytes(),
+        "keystrokes around the reports are kept"
+    );
+    let (color_schemes, remaining_bytes) =
+        AnsiStdinInstruction::take_color_scheme_reports("\u{1b}[?997;3nc".as_bytes());
+    assert!(
+        color_schemes.is_empty(),
+        "unknown color schemes are ignored"
+    );
+    assert_eq!(remaining_bytes, "\u{1b}[?997;3nc".as_bytes());
+}
+
+#[test]
+pub fn color_scheme_reports_are_parsed_on_startup_but_not_cached() {
+    let mut parser = StdinAnsiParser::new();
+    let events = parser.parse("\u{1b}]11;rgb:0000/0000/0000\u{1b}\\\u{1b}[?997;2n".into());
+    assert_eq!(
+        events.len(),
+        2,
+        "both the background color and the color scheme are parsed"
+    );
+    assert!(matches!(
+        events[1],
+        AnsiStdinInstruction::ColorScheme(ThemeHue::Light)
+    ));
+    let cacheable: Vec<&AnsiStdinInstruction> =
+        events.iter().filter(|e| e.is_cacheable()).collect();
+    assert!(
+        matches!(cacheable[..], [AnsiStdinInstruction::BackgroundColor(_)]),
+        "the color scheme can change between sessions and so is not cached"
+    );
+}
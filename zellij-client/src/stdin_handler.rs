@@ -1,6 +1,6 @@
 use crate::keyboard_parser::KittyKeyboardParser;
 use crate::os_input_output::ClientOsApi;
-use crate::stdin_ansi_parser::StdinAnsiParser;
+use crate::stdin_ansi_parser::{AnsiStdinInstruction, StdinAnsiParser};
 use crate::InputInstruction;
 use std::sync::{Arc, Mutex};
 use zellij_utils::channels::SenderWithContext;
@@ -73,7 +73,8 @@ pub(crate) fn stdin_loop(
                     if stdin_ansi_parser.should_parse() {
                         let events = stdin_ansi_parser.parse(buf);
                         if !events.is_empty() {
-                            ansi_stdin_events.append(&mut events.clone());
+                            ansi_stdin_events
+                                .extend(events.iter().filter(|e| e.is_cacheable()).cloned());
                             let _ = send_input_instructions
                                 .send(InputInstruction::AnsiStdinInstructions(events));
                         }
@@ -86,6 +87,15 @@ pub(crate) fn stdin_loop(
                         .unwrap()
                         .write_cache(ansi_stdin_events.drain(..).collect());
                 }
+                // the terminal reports changes of its color scheme at any time
+                let (color_schemes, buf) = AnsiStdinInstruction::take_color_scheme_reports(&buf);
+                if !color_schemes.is_empty() {
+                    let _ = send_input_instructions
+                        .send(InputInstruction::AnsiStdinInstructions(color_schemes));
+                }
+                if buf.is_empty() {
+                    continue;
+                }
                 current_buffer.append(&mut buf.to_vec());
 
                 if !explicitly_disable_kitty_keyboard_protocol {
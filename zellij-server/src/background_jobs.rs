@@ -3,7 +3,7 @@ use zellij_utils::consts::{
     session_info_cache_file_name, session_info_folder_for_session, session_layout_cache_file_name,
     ZELLIJ_SESSION_INFO_CACHE_DIR, ZELLIJ_SOCK_DIR,
 };
-use zellij_utils::data::{Event, HttpVerb, SessionInfo};
+use zellij_utils::data::{Event, HttpVerb, InputMode, SessionInfo};
 use zellij_utils::errors::{prelude::*, BackgroundJobContext, ContextType};
 use zellij_utils::surf::{
     http::{Method, Url},
@@ -53,6 +53,8 @@ pub enum BackgroundJob {
         Vec<u8>,                  // body
         BTreeMap<String, String>, // context
     ),
+    ShowWhichKeyOverlay(ClientId, InputMode),
+    FlushPaneOutput,
     Exit,
 }
 
@@ -71,6 +73,8 @@ impl From<&BackgroundJob> for BackgroundJobContext {
             BackgroundJob::ReportLayoutInfo(..) => BackgroundJobContext::ReportLayoutInfo,
             BackgroundJob::RunCommand(..) => BackgroundJobContext::RunCommand,
             BackgroundJob::WebRequest(..) => BackgroundJobContext::WebRequest,
+            BackgroundJob::ShowWhichKeyOverlay(..) => BackgroundJobContext::ShowWhichKeyOverlay,
+            BackgroundJob::FlushPaneOutput => BackgroundJobContext::FlushPaneOutput,
             BackgroundJob::Exit => BackgroundJobContext::Exit,
         }
     }
@@ -80,6 +84,8 @@ static FLASH_DURATION_MS: u64 = 1000;
 static PLUGIN_ANIMATION_OFFSET_DURATION_MD: u64 = 500;
 static SESSION_READ_DURATION: u64 = 1000;
 static DEFAULT_SERIALIZATION_INTERVAL: u64 = 60000;
+static WHICH_KEY_OVERLAY_DELAY_MS: u64 = 500;
+static PANE_OUTPUT_FLUSH_INTERVAL_MS: u64 = 100;
 
 pub(crate) fn background_jobs_main(
     bus: Bus<BackgroundJob>,
@@ -318,6 +324,30 @@ pub(crate) fn background_jobs_main(
                     }
                 });
             },
+            BackgroundJob::ShowWhichKeyOverlay(client_id, input_mode) => {
+                task::spawn({
+                    let senders = bus.senders.clone();
+                    async move {
+                        task::sleep(std::time::Duration::from_millis(WHICH_KEY_OVERLAY_DELAY_MS))
+                            .await;
+                        let _ = senders.send_to_screen(ScreenInstruction::ShowWhichKeyOverlay(
+                            client_id, input_mode,
+                        ));
+                    }
+                });
+            },
+            BackgroundJob::FlushPaneOutput => {
+                task::spawn({
+                    let senders = bus.senders.clone();
+                    async move {
+                        task::sleep(std::time::Duration::from_millis(
+                            PANE_OUTPUT_FLUSH_INTERVAL_MS,
+                        ))
+                        .await;
+                        let _ = senders.send_to_screen(ScreenInstruction::FlushPaneOutput);
+                    }
+                });
+            },
             BackgroundJob::Exit => {
                 for loading_plugin in loading_plugins.values() {
                     loading_plugin.store(false, Ordering::SeqCst);
@@ -36,23 +36,28 @@ use crate::{
     pty::{get_default_shell, pty_thread_main, Pty, PtyInstruction},
     screen::{screen_thread_main, ScreenInstruction},
     thread_bus::{Bus, ThreadSenders},
+    ui::overlay::{prompt::Prompt, Overlay, OverlayType},
 };
-use route::route_thread_main;
+use route::{route_thread_main, ClientKeyStates};
 use zellij_utils::{
     channels::{self, ChannelWithContext, SenderWithContext},
     cli::CliArgs,
     consts::{DEFAULT_SCROLL_BUFFER_SIZE, SCROLL_BUFFER_SIZE},
-    data::{ConnectToSession, Event, InputMode, PluginCapabilities},
+    data::{
+        ConnectToSession, Event, InputMode, KeyWithModifier, Palette, PluginCapabilities, ThemeHue,
+    },
     errors::{prelude::*, ContextType, ErrorInstruction, FatalError, ServerContext},
     home::{default_layout_dir, get_default_data_dir},
     input::{
+        actions::Action,
         command::{RunCommand, TerminalAction},
         config::Config,
+        dir_local_layout::{DirLocalLayout, TrustedLayouts},
         get_mode_info,
-        keybinds::Keybinds,
+        keybinds::{FocusedPaneInfo, Keybinds},
         layout::{FloatingPaneLayout, Layout, PercentOrFixed, PluginAlias, Run, RunPluginOrAlias},
         options::Options,
-        plugins::PluginAliases,
+        plugins::{PluginAliases, PluginLimits},
     },
     ipc::{ClientAttributes, ExitReason, ServerToClientMsg},
     shared::default_palette,
@@ -109,6 +114,14 @@ pub enum ServerInstruction {
     },
     ConfigWrittenToDisk(ClientId, Config),
     FailedToWriteConfigToDisk(ClientId, Option<PathBuf>), // Pathbuf - file we failed to write
+    ChangeColorScheme(ClientId, ThemeHue), // the color scheme of the client's terminal
+    InterceptKeys(u32, Vec<KeyWithModifier>, Vec<InputMode>), // u32 - plugin_id
+    ReinjectKey(KeyWithModifier, ClientId),
+    RouteMouseActions(Vec<Action>, ClientId), // the actions of a mouse press, once its target is known
+    FocusedPaneChanged(ClientId, Option<u32>, FocusedPaneInfo), // u32 -> terminal id
+    PromptToTrustDirLocalLayout(Box<DirLocalLayout>, PathBuf, ClientId), // PathBuf - data dir
+    TrustDirLocalLayout(Box<DirLocalLayout>, PathBuf, ClientId), // PathBuf - data dir
+    IgnoreDirLocalLayout(ClientId),
 }
 
 impl From<&ServerInstruction> for ServerContext {
@@ -145,6 +158,16 @@ impl From<&ServerInstruction> for ServerContext {
             ServerInstruction::FailedToWriteConfigToDisk(..) => {
                 ServerContext::FailedToWriteConfigToDisk
             },
+            ServerInstruction::ChangeColorScheme(..) => ServerContext::ChangeColorScheme,
+            ServerInstruction::InterceptKeys(..) => ServerContext::InterceptKeys,
+            ServerInstruction::ReinjectKey(..) => ServerContext::ReinjectKey,
+            ServerInstruction::RouteMouseActions(..) => ServerContext::RouteMouseActions,
+            ServerInstruction::FocusedPaneChanged(..) => ServerContext::FocusedPaneChanged,
+            ServerInstruction::PromptToTrustDirLocalLayout(..) => {
+                ServerContext::PromptToTrustDirLocalLayout
+            },
+            ServerInstruction::TrustDirLocalLayout(..) => ServerContext::TrustDirLocalLayout,
+            ServerInstruction::IgnoreDirLocalLayout(..) => ServerContext::IgnoreDirLocalLayout,
         }
     }
 }
@@ -228,6 +251,45 @@ impl SessionConfiguration {
     }
 }
 
+/// Keys (or whole input modes) plugins asked to receive before they are routed to the focused
+/// pane, by plugin id
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyIntercepts {
+    intercepts: BTreeMap<u32, (HashSet<KeyWithModifier>, HashSet<InputMode>)>,
+}
+
+impl KeyIntercepts {
+    pub fn set(&mut self, plugin_id: u32, keys: Vec<KeyWithModifier>, input_modes: Vec<InputMode>) {
+        if keys.is_empty() && input_modes.is_empty() {
+            self.intercepts.remove(&plugin_id);
+        } else {
+            self.intercepts.insert(
+                plugin_id,
+                (
+                    keys.into_iter().collect(),
+                    input_modes.into_iter().collect(),
+                ),
+            );
+        }
+    }
+    pub fn intercepting_plugin(
+        &self,
+        key: &KeyWithModifier,
+        input_mode: &InputMode,
+    ) -> Option<u32> {
+        // a plugin asking for this specific key takes precedence over one intercepting the mode
+        self.intercepts
+            .iter()
+            .find(|(_, (keys, _))| keys.contains(key))
+            .or_else(|| {
+                self.intercepts
+                    .iter()
+                    .find(|(_, (_, input_modes))| input_modes.contains(input_mode))
+            })
+            .map(|(plugin_id, _)| *plugin_id)
+    }
+}
+
 pub(crate) struct SessionMetaData {
     pub senders: ThreadSenders,
     pub capabilities: PluginCapabilities,
@@ -235,7 +297,11 @@ pub(crate) struct SessionMetaData {
     pub default_shell: Option<TerminalAction>,
     pub layout: Box<Layout>,
     pub current_input_modes: HashMap<ClientId, InputMode>,
+    pub current_color_schemes: HashMap<ClientId, ThemeHue>,
     pub session_configuration: SessionConfiguration,
+    pub key_intercepts: KeyIntercepts,
+    /// What conditional keybindings are matched against, kept up to date by the screen thread
+    pub focused_panes: HashMap<ClientId, (Option<u32>, FocusedPaneInfo)>, // u32 -> terminal id
 
     screen_thread: Option<thread::JoinHandle<()>>,
     pty_thread: Option<thread::JoinHandle<()>>,
@@ -255,12 +321,83 @@ impl SessionMetaData {
             _ => None,
         }
     }
+    /// Switches the client to `input_mode` here and in the screen thread
+    pub fn switch_client_mode(&mut self, client_id: ClientId, input_mode: InputMode) {
+        let config = self
+            .session_configuration
+            .get_client_configuration(&client_id);
+        let mode_info = get_mode_info(
+            input_mode.clone(),
+            &self.client_attributes,
+            self.capabilities,
+            &config.keybinds,
+            Some(config.options.default_mode.unwrap_or_default()),
+        );
+        self.current_input_modes.insert(client_id, input_mode);
+        self.senders
+            .send_to_screen(ScreenInstruction::ChangeMode(mode_info, client_id))
+            .unwrap();
+    }
+    pub fn switch_client_to_default_mode(&mut self, client_id: ClientId) {
+        let default_mode = self
+            .session_configuration
+            .get_client_configuration(&client_id)
+            .options
+            .default_mode
+            .unwrap_or_default();
+        self.switch_client_mode(client_id, default_mode);
+    }
+    /// Opens the tabs of `layout` next to the existing ones
+    pub fn open_layout_in_new_tabs(&self, layout: &Layout, client_id: ClientId) {
+        let swap_layouts = (
+            layout.swap_tiled_layouts.clone(),
+            layout.swap_floating_layouts.clone(),
+        );
+        let tabs = if layout.has_tabs() {
+            layout
+                .tabs()
+                .into_iter()
+                .map(|(tab_name, tab_layout, floating_panes_layout)| {
+                    (Some(tab_layout), floating_panes_layout, tab_name)
+                })
+                .collect()
+        } else {
+            let (tab_layout, floating_panes_layout) = layout.new_tab();
+            vec![(Some(tab_layout), floating_panes_layout, None)]
+        };
+        for (tab_layout, floating_panes_layout, tab_name) in tabs {
+            self.senders
+                .send_to_screen(ScreenInstruction::NewTab(
+                    None,
+                    self.default_shell.clone(),
+                    tab_layout,
+                    floating_panes_layout,
+                    tab_name,
+                    swap_layouts.clone(),
+                    client_id,
+                ))
+                .unwrap();
+        }
+    }
     pub fn change_mode_for_all_clients(&mut self, input_mode: InputMode) {
         let all_clients: Vec<ClientId> = self.current_input_modes.keys().copied().collect();
         for client_id in all_clients {
-            self.current_input_modes.insert(client_id, input_mode);
+            self.current_input_modes
+                .insert(client_id, input_mode.clone());
         }
     }
+    /// The theme of the client: `theme_dark` or `theme_light` depending on the color scheme of
+    /// its terminal if they are configured, `theme` otherwise
+    pub fn get_client_theme(&self, client_id: &ClientId, config: &Config) -> Palette {
+        let theme_for_color_scheme = match self.current_color_schemes.get(client_id) {
+            Some(ThemeHue::Dark) => config.options.theme_dark.as_ref(),
+            Some(ThemeHue::Light) => config.options.theme_light.as_ref(),
+            None => None,
+        };
+        config
+            .theme_config(theme_for_color_scheme.or(config.options.theme.as_ref()))
+            .unwrap_or_else(|| default_palette())
+    }
     pub fn propagate_configuration_changes(&mut self, config_changes: Vec<(ClientId, Config)>) {
         for (client_id, new_config) in config_changes {
             self.default_shell = new_config.options.default_shell.as_ref().map(|shell| {
@@ -277,10 +414,9 @@ impl SessionMetaData {
                     default_mode: new_config
                         .options
                         .default_mode
+                        .clone()
                         .unwrap_or_else(Default::default),
-                    theme: new_config
-                        .theme_config(new_config.options.theme.as_ref())
-                        .unwrap_or_else(|| default_palette()),
+                    theme: self.get_client_theme(&client_id, &new_config),
                     simplified_ui: new_config.options.simplified_ui.unwrap_or(false),
                     default_shell: new_config.options.default_shell,
                     pane_frames: new_config.options.pane_frames.unwrap_or(true),
@@ -290,6 +426,8 @@ impl SessionMetaData {
                     auto_layout: new_config.options.auto_layout.unwrap_or(true),
                     rounded_corners: new_config.ui.pane_frames.rounded_corners,
                     hide_session_name: new_config.ui.pane_frames.hide_session_name,
+                    which_key_overlay: new_config.options.which_key_overlay.unwrap_or(false),
+                    dim_unfocused_panes: new_config.options.dim_unfocused_panes.unwrap_or(0),
                 })
                 .unwrap();
             self.senders
@@ -463,6 +601,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
     let to_server = SenderWithContext::new(to_server);
     let session_data: Arc<RwLock<Option<SessionMetaData>>> = Arc::new(RwLock::new(None));
     let session_state = Arc::new(RwLock::new(SessionState::new()));
+    let client_key_states = ClientKeyStates::default();
 
     std::panic::set_hook({
         use zellij_utils::errors::handle_panic;
@@ -482,6 +621,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
             let os_input = os_input.clone();
             let session_data = session_data.clone();
             let session_state = session_state.clone();
+            let client_key_states = client_key_states.clone();
             let to_server = to_server.clone();
             let socket_path = socket_path.clone();
             move || {
@@ -500,6 +640,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                             let receiver = os_input.new_client(client_id, stream).unwrap();
                             let session_data = session_data.clone();
                             let session_state = session_state.clone();
+                            let client_key_states = client_key_states.clone();
                             let to_server = to_server.clone();
                             thread::Builder::new()
                                 .name("server_router".to_string())
@@ -511,6 +652,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                                         to_server,
                                         receiver,
                                         client_id,
+                                        client_key_states,
                                     )
                                     .fatal()
                                 })
@@ -672,7 +814,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .session_configuration
                     .set_client_runtime_configuration(client_id, runtime_configuration);
 
-                let default_input_mode = config.options.default_mode.unwrap_or_default();
+                let default_input_mode = config.options.default_mode.clone().unwrap_or_default();
                 session_data
                     .current_input_modes
                     .insert(client_id, default_input_mode);
@@ -704,7 +846,7 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .unwrap();
                 let default_mode = config.options.default_mode.unwrap_or_default();
                 let mode_info = get_mode_info(
-                    default_mode,
+                    default_mode.clone(),
                     &attrs,
                     session_data.capabilities,
                     &session_data
@@ -1047,6 +1189,124 @@ pub fn start_server(mut os_input: Box<dyn ServerOsApi>, socket_path: PathBuf) {
                     .current_input_modes
                     .insert(client_id, input_mode);
             },
+            ServerInstruction::ChangeColorScheme(client_id, color_scheme) => {
+                if let Some(session_data) = session_data.write().unwrap().as_mut() {
+                    let previous_color_scheme = session_data
+                        .current_color_schemes
+                        .insert(client_id, color_scheme);
+                    let config = session_data
+                        .session_configuration
+                        .get_client_configuration(&client_id);
+                    let follows_color_scheme =
+                        config.options.theme_dark.is_some() || config.options.theme_light.is_some();
+                    if follows_color_scheme && previous_color_scheme != Some(color_scheme) {
+                        let theme = session_data.get_client_theme(&client_id, &config);
+                        session_data
+                            .senders
+                            .send_to_screen(ScreenInstruction::ChangeClientTheme(theme, client_id))
+                            .unwrap();
+                    }
+                }
+            },
+            ServerInstruction::InterceptKeys(plugin_id, keys, input_modes) => {
+                if let Some(session_data) = session_data.write().unwrap().as_mut() {
+                    session_data
+                        .key_intercepts
+                        .set(plugin_id, keys, input_modes);
+                }
+            },
+            ServerInstruction::ReinjectKey(key, client_id) => {
+                let client_key_state = client_key_states.lock().unwrap().get(&client_id).cloned();
+                if let (Some(session), Some(client_key_state)) =
+                    (session_data.read().unwrap().as_ref(), client_key_state)
+                {
+                    if let Err(e) = route::route_reinjected_key(
+                        key,
+                        client_id,
+                        session,
+                        &session_data,
+                        &client_key_state,
+                        &*os_input,
+                    ) {
+                        log::error!("Failed to re-inject key: {:?}", e);
+                    }
+                }
+            },
+            ServerInstruction::RouteMouseActions(actions, client_id) => {
+                let client_key_state = client_key_states.lock().unwrap().get(&client_id).cloned();
+                if let (Some(session), Some(client_key_state)) =
+                    (session_data.read().unwrap().as_ref(), client_key_state)
+                {
+                    if let Err(e) = route::route_mouse_actions(
+                        actions,
+                        client_id,
+                        session,
+                        &client_key_state,
+                        &*os_input,
+                    ) {
+                        log::error!("Failed to route mouse actions: {:?}", e);
+                    }
+                }
+            },
+            ServerInstruction::FocusedPaneChanged(client_id, terminal_id, focused_pane_info) => {
+                if let Some(session_data) = session_data.write().unwrap().as_mut() {
+                    session_data
+                        .focused_panes
+                        .insert(client_id, (terminal_id, focused_pane_info));
+                }
+            },
+            ServerInstruction::PromptToTrustDirLocalLayout(
+                dir_local_layout,
+                data_dir,
+                client_id,
+            ) => {
+                if let Some(session_data) = session_data.write().unwrap().as_mut() {
+                    let message = if dir_local_layout.changed {
+                        format!(
+                            " The layout {} changed since it was trusted. Layouts can run any command, use it? [Y]es / [N]o",
+                            dir_local_layout.path.display()
+                        )
+                    } else {
+                        format!(
+                            " Found the layout {}. Layouts can run any command, use it? [Y]es / [N]o",
+                            dir_local_layout.path.display()
+                        )
+                    };
+                    let prompt = Prompt::new(
+                        message,
+                        Some(Box::new(ServerInstruction::TrustDirLocalLayout(
+                            dir_local_layout,
+                            data_dir,
+                            client_id,
+                        ))),
+                        Some(Box::new(ServerInstruction::IgnoreDirLocalLayout(client_id))),
+                    );
+                    session_data
+                        .senders
+                        .send_to_screen(ScreenInstruction::AddOverlay(
+                            Overlay::new(OverlayType::Prompt(prompt)),
+                            client_id,
+                        ))
+                        .unwrap();
+                    session_data.switch_client_mode(client_id, InputMode::Prompt);
+                }
+            },
+            ServerInstruction::TrustDirLocalLayout(dir_local_layout, data_dir, client_id) => {
+                if let Some(session_data) = session_data.write().unwrap().as_mut() {
+                    let mut trusted_layouts = TrustedLayouts::from_data_dir(&data_dir);
+                    trusted_layouts.trust(dir_local_layout.path.clone(), dir_local_layout.sha256);
+                    if let Err(e) = trusted_layouts.write_to_file() {
+                        log::error!("Failed to record trusted layout: {}", e);
+                    }
+                    session_data.open_layout_in_new_tabs(&dir_local_layout.layout, client_id);
+                    session_data.switch_client_to_default_mode(client_id);
+                }
+            },
+            ServerInstruction::IgnoreDirLocalLayout(client_id) => {
+                if let Some(session_data) = session_data.write().unwrap().as_mut() {
+                    session_data.switch_client_to_default_mode(client_id);
+                }
+            },
             ServerInstruction::ChangeModeForAllClients(input_mode) => {
                 session_data
                     .write()
@@ -1193,8 +1453,9 @@ fn init_session(
         .clone()
         .unwrap_or_else(|| get_default_shell());
 
-    let default_mode = config_options.default_mode.unwrap_or_default();
+    let default_mode = config_options.default_mode.clone().unwrap_or_default();
     let default_keybinds = config.keybinds.clone();
+    let plugin_limits = PluginLimits::from_options(&config_options);
 
     let pty_thread = thread::Builder::new()
         .name("pty".to_string())
@@ -1287,6 +1548,7 @@ fn init_session(
                     plugin_aliases,
                     default_mode,
                     default_keybinds,
+                    plugin_limits,
                 )
                 .fatal()
             }
@@ -1350,6 +1612,9 @@ fn init_session(
         layout,
         session_configuration: Default::default(),
         current_input_modes: HashMap::new(),
+        current_color_schemes: HashMap::new(),
+        key_intercepts: KeyIntercepts::default(),
+        focused_panes: HashMap::new(),
         screen_thread: Some(screen_thread),
         pty_thread: Some(pty_thread),
         plugin_thread: Some(plugin_thread),
@@ -1372,11 +1637,21 @@ fn setup_wizard_floating_pane() -> FloatingPaneLayout {
 #[cfg(not(feature = "singlepass"))]
 fn get_engine() -> Engine {
     log::info!("Compiling plugins using Cranelift");
-    Engine::new(WasmtimeConfig::new().strategy(Strategy::Cranelift)).unwrap()
+    Engine::new(
+        WasmtimeConfig::new()
+            .strategy(Strategy::Cranelift)
+            .epoch_interruption(true),
+    )
+    .unwrap()
 }
 
 #[cfg(feature = "singlepass")]
 fn get_engine() -> Engine {
     log::info!("Compiling plugins using Singlepass");
-    Engine::new(WasmtimeConfig::new().strategy(Strategy::Winch)).unwrap()
+    Engine::new(
+        WasmtimeConfig::new()
+            .strategy(Strategy::Winch)
+            .epoch_interruption(true),
+    )
+    .unwrap()
 }
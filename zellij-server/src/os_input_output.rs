@@ -37,7 +37,7 @@ use std::{
     io::Write,
     os::unix::{io::RawFd, process::CommandExt},
     path::PathBuf,
-    process::{Child, Command},
+    process::{Child, Command, Stdio},
     sync::{Arc, Mutex},
 };
 
@@ -514,6 +514,11 @@ pub trait ServerOsApi: Send + Sync {
     fn get_all_cmds_by_ppid(&self) -> HashMap<String, Vec<String>> {
         HashMap::new()
     }
+    /// The name of the process in the foreground process group of the terminal's pty (eg. `vim`
+    /// rather than the shell it was started from)
+    fn get_foreground_process_name(&self, _terminal_id: u32) -> Option<String> {
+        None
+    }
     /// Writes the given buffer to a string
     fn write_to_file(&mut self, buf: String, file: Option<String>) -> Result<()>;
 
@@ -526,6 +531,8 @@ pub trait ServerOsApi: Send + Sync {
     fn clear_terminal_id(&self, terminal_id: u32) -> Result<()>;
     fn cache_resizes(&mut self) {}
     fn apply_cached_resizes(&mut self) {}
+    /// Opens the uri with the system's default handler (eg. a browser for web links)
+    fn open_hyperlink(&self, uri: &str) -> Result<()>;
 }
 
 impl ServerOsApi for ServerOsInputOutput {
@@ -802,6 +809,40 @@ impl ServerOsApi for ServerOsInputOutput {
         cmds
     }
 
+    fn get_foreground_process_name(&self, terminal_id: u32) -> Option<String> {
+        let raw_fd = self
+            .terminal_id_to_raw_fd
+            .lock()
+            .ok()?
+            .get(&terminal_id)
+            .copied()
+            .flatten()?;
+        let foreground_process_group = unistd::tcgetpgrp(raw_fd).ok()?;
+        // the process group id is the pid of its leader
+        let process_name =
+            std::fs::read_to_string(format!("/proc/{}/comm", foreground_process_group))
+                .ok()
+                .or_else(|| {
+                    Command::new("ps")
+                        .args(vec![
+                            "-o".to_owned(),
+                            "comm=".to_owned(),
+                            "-p".to_owned(),
+                            foreground_process_group.to_string(),
+                        ])
+                        .output()
+                        .ok()
+                        .map(|output| String::from_utf8_lossy(&output.stdout).to_string())
+                })?;
+        // on some systems ps prints the full path of the executable
+        let process_name = process_name.trim().rsplit('/').next()?.to_owned();
+        if process_name.is_empty() {
+            None
+        } else {
+            Some(process_name)
+        }
+    }
+
     fn write_to_file(&mut self, buf: String, name: Option<String>) -> Result<()> {
         let err_context = || "failed to write to file".to_string();
 
@@ -848,6 +889,24 @@ impl ServerOsApi for ServerOsInputOutput {
             .remove(&terminal_id);
         Ok(())
     }
+    fn open_hyperlink(&self, uri: &str) -> Result<()> {
+        #[cfg(target_os = "macos")]
+        let opener = "open";
+        #[cfg(not(target_os = "macos"))]
+        let opener = "xdg-open";
+        let mut child = Command::new(opener)
+            .arg(uri)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to open hyperlink {uri} with {opener}"))?;
+        // reap the opener so it does not linger as a zombie
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+        Ok(())
+    }
     fn cache_resizes(&mut self) {
         if self.cached_resizes.lock().unwrap().is_none() {
             *self.cached_resizes.lock().unwrap() = Some(BTreeMap::new());
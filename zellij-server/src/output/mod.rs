@@ -6,7 +6,9 @@ use crate::panes::Row;
 use crate::{
     panes::sixel::SixelImageStore,
     panes::terminal_character::{AnsiCode, CharacterStyles},
-    panes::{LinkHandler, TerminalCharacter, DEFAULT_STYLES, EMPTY_TERMINAL_CHARACTER},
+    panes::{
+        LinkHandler, TerminalCharacter, DEFAULT_STYLES, EMPTY_TERMINAL_CHARACTER, RESET_STYLES,
+    },
     ClientId,
 };
 use std::cell::RefCell;
@@ -56,6 +58,29 @@ fn adjust_styles_for_possible_selection(
         .unwrap_or(character_styles)
 }
 
+fn dim_styles(character_styles: CharacterStyles, dim_amount: u8) -> CharacterStyles {
+    // truecolor colors can be darkened by the exact amount, for the others we have no way of
+    // knowing how the terminal renders them so we ask it to draw the text faint
+    let dim_color = |color: AnsiCode| match color {
+        AnsiCode::RgbCode((r, g, b)) => {
+            let scale = |c: u8| (c as u16 * (100 - dim_amount as u16) / 100) as u8;
+            AnsiCode::RgbCode((scale(r), scale(g), scale(b)))
+        },
+        color => color,
+    };
+    let mut character_styles = character_styles;
+    match character_styles.foreground {
+        Some(AnsiCode::RgbCode(_)) => {
+            character_styles.foreground = character_styles.foreground.map(dim_color);
+        },
+        _ => {
+            character_styles.dim = Some(AnsiCode::On);
+        },
+    }
+    character_styles.background = character_styles.background.map(dim_color);
+    character_styles
+}
+
 fn write_changed_styles(
     character_styles: &mut CharacterStyles,
     current_character_styles: CharacterStyles,
@@ -115,12 +140,41 @@ fn serialize_chunks_with_newlines(
     }
     Ok(vte_output)
 }
+/// Serializes a single line of characters along with the ANSI codes of their styles, resetting
+/// the styles at the end of the line if needed
+pub fn serialize_line_with_styles(
+    terminal_characters: &[TerminalCharacter],
+    styled_underlines: bool,
+) -> Result<String> {
+    let err_context = || "failed to serialize line".to_string();
+
+    let initial_styles = RESET_STYLES.enable_styled_underlines(styled_underlines);
+    let mut character_styles = initial_styles;
+    let mut vte_output = String::new();
+    for t_character in terminal_characters {
+        write_changed_styles(
+            &mut character_styles,
+            *t_character.styles,
+            None,
+            None,
+            &mut vte_output,
+        )
+        .with_context(err_context)?;
+        vte_output.push(t_character.character);
+    }
+    if character_styles != initial_styles {
+        vte_output.push_str("\u{1b}[m");
+    }
+    Ok(vte_output)
+}
+
 fn serialize_chunks(
     character_chunks: Vec<CharacterChunk>,
     sixel_chunks: Option<&Vec<SixelImageChunk>>,
     link_handler: Option<&mut Rc<RefCell<LinkHandler>>>,
     sixel_image_store: Option<&mut SixelImageStore>,
     styled_underlines: bool,
+    dim_unfocused_panes: u8,
 ) -> Result<String> {
     let err_context = || "failed to serialize input chunks".to_string();
 
@@ -132,14 +186,19 @@ fn serialize_chunks(
         let mut character_styles = DEFAULT_STYLES.enable_styled_underlines(styled_underlines);
         vte_goto_instruction(character_chunk.x, character_chunk.y, &mut vte_output)
             .with_context(err_context)?;
+        let should_dim = character_chunk.is_dimmed() && dim_unfocused_panes > 0;
         let mut chunk_width = character_chunk.x;
         for t_character in character_chunk.terminal_characters.iter() {
-            let current_character_styles = adjust_styles_for_possible_selection(
+            let mut current_character_styles = adjust_styles_for_possible_selection(
                 character_chunk.selection_and_colors(),
                 *t_character.styles,
                 character_chunk.y,
                 chunk_width,
             );
+            if should_dim {
+                current_character_styles =
+                    dim_styles(current_character_styles, dim_unfocused_panes);
+            }
             write_changed_styles(
                 &mut character_styles,
                 current_character_styles,
@@ -246,6 +305,7 @@ pub struct Output {
     character_cell_size: Rc<RefCell<Option<SizeInPixels>>>,
     floating_panes_stack: Option<FloatingPanesStack>,
     styled_underlines: bool,
+    dim_unfocused_panes: u8, // percent
 }
 
 impl Output {
@@ -253,14 +313,19 @@ impl Output {
         sixel_image_store: Rc<RefCell<SixelImageStore>>,
         character_cell_size: Rc<RefCell<Option<SizeInPixels>>>,
         styled_underlines: bool,
+        dim_unfocused_panes: u8,
     ) -> Self {
         Output {
             sixel_image_store,
             character_cell_size,
             styled_underlines,
+            dim_unfocused_panes: dim_unfocused_panes.min(100),
             ..Default::default()
         }
     }
+    pub fn dims_unfocused_panes(&self) -> bool {
+        self.dim_unfocused_panes > 0
+    }
     pub fn add_clients(
         &mut self,
         client_ids: &HashSet<ClientId>,
@@ -421,6 +486,7 @@ impl Output {
                     self.link_handler.as_mut(),
                     Some(&mut self.sixel_image_store.borrow_mut()),
                     self.styled_underlines,
+                    self.dim_unfocused_panes,
                 )
                 .with_context(err_context)?,
             ); // TODO: less allocations?
@@ -444,6 +510,17 @@ impl Output {
             || self.client_character_chunks.values().any(|c| !c.is_empty())
             || self.sixel_chunks.values().any(|c| !c.is_empty())
     }
+    pub fn is_dirty_for_client(&self, client_id: &ClientId) -> bool {
+        self.client_character_chunks
+            .get(client_id)
+            .map(|c| !c.is_empty())
+            .unwrap_or(false)
+            || self
+                .sixel_chunks
+                .get(client_id)
+                .map(|c| !c.is_empty())
+                .unwrap_or(false)
+    }
     pub fn has_rendered_assets(&self) -> bool {
         // pre_vte and post_vte are not considered rendered assets as they should not be visible
         self.client_character_chunks.values().any(|c| !c.is_empty())
@@ -572,8 +649,9 @@ impl FloatingPanesStack {
                     .with_context(err_context)?;
                 let left_chunk_x = c_chunk_left_side;
                 let right_chunk_x = pane_right_edge + 1;
-                let left_chunk =
+                let mut left_chunk =
                     CharacterChunk::new(left_chunk_characters, left_chunk_x, c_chunk.y);
+                left_chunk.dimmed = c_chunk.dimmed;
                 c_chunk.x = right_chunk_x;
                 c_chunk.terminal_characters = right_chunk_characters;
                 return Ok(Some(left_chunk));
@@ -749,6 +827,7 @@ pub struct CharacterChunk {
     pub y: usize,
     pub changed_colors: Option<[Option<AnsiCode>; 256]>,
     selection_and_colors: Vec<(Selection, AnsiCode, Option<AnsiCode>)>, // Selection, background color, optional foreground color
+    dimmed: bool, // part of a pane that is not focused for the client it is rendered to
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -795,6 +874,23 @@ impl CharacterChunk {
     pub fn changed_colors(&self) -> Option<[Option<AnsiCode>; 256]> {
         self.changed_colors
     }
+    pub fn set_dimmed(&mut self) {
+        self.dimmed = true;
+    }
+    pub fn is_dimmed(&self) -> bool {
+        self.dimmed
+    }
+    pub fn set_default_background(&mut self, background: AnsiCode) {
+        // only characters without an explicit background, so that the colors set by the
+        // application remain visible
+        for t_character in self.terminal_characters.iter_mut() {
+            if matches!(t_character.styles.background, None | Some(AnsiCode::Reset)) {
+                t_character
+                    .styles
+                    .update(|styles| styles.background = Some(background));
+            }
+        }
+    }
     pub fn width(&self) -> usize {
         let mut width = 0;
         for t_character in &self.terminal_characters {
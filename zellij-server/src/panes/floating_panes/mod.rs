@@ -22,7 +22,7 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::rc::Rc;
 use std::time::Instant;
 use zellij_utils::{
-    data::{ModeInfo, Palette, Style},
+    data::{ModeInfo, Palette, PaneColors, Style},
     errors::prelude::*,
     input::command::RunCommand,
     input::layout::{FloatingPaneLayout, Run, RunPluginOrAlias},
@@ -313,7 +313,7 @@ impl FloatingPanes {
         }
         Ok(())
     }
-    pub fn render(&mut self, output: &mut Output) -> Result<()> {
+    pub fn render(&mut self, output: &mut Output, tab_colors: PaneColors) -> Result<()> {
         let err_context = || "failed to render output";
         let connected_clients: Vec<ClientId> =
             { self.connected_clients.borrow().iter().copied().collect() };
@@ -349,16 +349,22 @@ impl FloatingPanes {
                 false,
                 false,
                 true,
+                tab_colors,
             );
             for client_id in &connected_clients {
-                let client_mode = self
-                    .mode_info
-                    .borrow()
-                    .get(client_id)
-                    .unwrap_or(&self.default_mode_info)
-                    .mode;
+                let (client_mode, client_colors) = {
+                    let mode_info = self.mode_info.borrow();
+                    let client_mode_info =
+                        mode_info.get(client_id).unwrap_or(&self.default_mode_info);
+                    (client_mode_info.mode.clone(), client_mode_info.style.colors)
+                };
                 pane_contents_and_ui
-                    .render_pane_frame(*client_id, client_mode, self.session_is_mirrored)
+                    .render_pane_frame(
+                        *client_id,
+                        client_mode,
+                        client_colors,
+                        self.session_is_mirrored,
+                    )
                     .with_context(err_context)?;
                 if let PaneId::Plugin(..) = kind {
                     pane_contents_and_ui
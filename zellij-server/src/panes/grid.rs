@@ -11,12 +11,13 @@ use std::{
     cmp::Ordering,
     collections::{BTreeSet, VecDeque},
     fmt::{self, Debug, Formatter},
+    ops::Range,
     str,
 };
 
 use zellij_utils::{
     consts::{DEFAULT_SCROLL_BUFFER_SIZE, SCROLL_BUFFER_SIZE},
-    data::{Palette, PaletteColor},
+    data::{Palette, PaletteColor, PaneContents},
     pane_size::SizeInPixels,
     position::Position,
     vte,
@@ -24,17 +25,18 @@ use zellij_utils::{
 
 const TABSTOP_WIDTH: usize = 8; // TODO: is this always right?
 pub const MAX_TITLE_STACK_SIZE: usize = 1000;
+const MAX_COMPLETED_LINES: usize = 1000;
 
 use vte::{Params, Perform};
 use zellij_utils::{consts::VERSION, shared::version_number};
 
-use crate::output::{CharacterChunk, OutputBuffer, SixelImageChunk};
+use crate::output::{serialize_line_with_styles, CharacterChunk, OutputBuffer, SixelImageChunk};
 use crate::panes::alacritty_functions::{parse_number, xparse_color};
 use crate::panes::link_handler::LinkHandler;
 use crate::panes::search::SearchResult;
 use crate::panes::selection::Selection;
 use crate::panes::terminal_character::{
-    AnsiCode, CharsetIndex, Cursor, CursorShape, RcCharacterStyles, StandardCharset,
+    AnsiCode, CharsetIndex, Cursor, CursorShape, LinkAnchor, RcCharacterStyles, StandardCharset,
     TerminalCharacter, EMPTY_TERMINAL_CHARACTER,
 };
 use crate::ui::components::UiComponentParser;
@@ -287,6 +289,18 @@ macro_rules! dump_screen {
     }};
 }
 
+/// Groups rows into lines, a line being a canonical row followed by the rows it wrapped into
+fn join_wrapped_rows<'a>(rows: impl Iterator<Item = &'a Row>) -> Vec<Vec<&'a Row>> {
+    let mut lines: Vec<Vec<&Row>> = vec![];
+    for row in rows {
+        match lines.last_mut() {
+            Some(line) if !row.is_canonical => line.push(row),
+            _ => lines.push(vec![row]),
+        }
+    }
+    lines
+}
+
 fn utf8_mouse_coordinates(column: usize, line: isize) -> Vec<u8> {
     let mut coordinates = vec![];
     let mouse_pos_encode = |pos: usize| -> Vec<u8> {
@@ -364,7 +378,8 @@ pub struct Grid {
     styled_underlines: bool,
     pub supports_kitty_keyboard_protocol: bool, // has the app requested kitty keyboard support?
     explicitly_disable_kitty_keyboard_protocol: bool, // has kitty keyboard support been explicitly
-                                                // disabled by user config?
+    // disabled by user config?
+    completed_lines: Option<VecDeque<String>>, // None when nobody is interested in them
 }
 
 #[derive(Clone, Debug)]
@@ -511,6 +526,7 @@ impl Grid {
             lock_renders: false,
             supports_kitty_keyboard_protocol: false,
             explicitly_disable_kitty_keyboard_protocol,
+            completed_lines: None,
         }
     }
     pub fn render_full_viewport(&mut self) {
@@ -1085,14 +1101,14 @@ impl Grid {
                 .selection
                 .contains_row(character_chunk.y.saturating_sub(content_y))
             {
-                let background_color = match style.colors.bg {
+                let background_color = match style.colors.selection_bg() {
                     PaletteColor::Rgb(rgb) => AnsiCode::RgbCode(rgb),
                     PaletteColor::EightBit(col) => AnsiCode::ColorIndex(col),
                 };
                 character_chunk.add_selection_and_colors(
                     self.selection,
                     background_color,
-                    None,
+                    style.colors.selection_fg().map(AnsiCode::from),
                     content_x,
                     content_y,
                 );
@@ -1101,9 +1117,15 @@ impl Grid {
                     if res.contains_row(character_chunk.y.saturating_sub(content_y)) {
                         let (select_background_palette, select_foreground_palette) =
                             if Some(res) == self.search_results.active.as_ref() {
-                                (style.colors.orange, style.colors.black)
+                                (
+                                    style.colors.search_active_match_bg(),
+                                    style.colors.search_match_fg(),
+                                )
                             } else {
-                                (style.colors.green, style.colors.black)
+                                (
+                                    style.colors.search_match_bg(),
+                                    style.colors.search_match_fg(),
+                                )
                             };
                         let background_color = match select_background_palette {
                             PaletteColor::Rgb(rgb) => AnsiCode::RgbCode(rgb),
@@ -1167,6 +1189,97 @@ impl Grid {
         scrollback.push_str(&viewport);
         scrollback
     }
+    /// The contents of the viewport and optionally a range of lines from the whole scrollback
+    /// (lines above the viewport, the viewport and lines below it), with wrapped lines joined
+    pub fn pane_contents(&self, line_range: Option<Range<usize>>, with_ansi: bool) -> PaneContents {
+        let viewport = join_wrapped_rows(self.viewport.iter())
+            .iter()
+            .map(|line| self.line_to_string(line, with_ansi))
+            .collect();
+        let all_lines = join_wrapped_rows(
+            self.lines_above
+                .iter()
+                .chain(self.viewport.iter())
+                .chain(self.lines_below.iter()),
+        );
+        let scrollback_length = all_lines.len();
+        let (scrollback, scrollback_offset) = match line_range {
+            Some(line_range) => {
+                let start = line_range.start.min(scrollback_length);
+                let end = line_range.end.clamp(start, scrollback_length);
+                let scrollback = all_lines[start..end]
+                    .iter()
+                    .map(|line| self.line_to_string(line, with_ansi))
+                    .collect();
+                (scrollback, start)
+            },
+            None => (vec![], 0),
+        };
+        PaneContents {
+            viewport,
+            scrollback,
+            scrollback_offset,
+            scrollback_length,
+        }
+    }
+    pub fn collect_completed_lines(&mut self, should_collect: bool) {
+        match (should_collect, self.completed_lines.is_some()) {
+            (true, false) => self.completed_lines = Some(VecDeque::new()),
+            (false, true) => self.completed_lines = None,
+            _ => {},
+        }
+    }
+    pub fn drain_completed_lines(&mut self) -> Vec<String> {
+        self.completed_lines
+            .as_mut()
+            .map(|completed_lines| completed_lines.drain(..).collect())
+            .unwrap_or_default()
+    }
+    fn collect_completed_line(&mut self) {
+        // full screen applications (those using the alternate screen) do not print lines, so we
+        // only track the ones printed on the primary screen
+        if self.completed_lines.is_none() || self.alternate_screen_state.is_some() {
+            return;
+        }
+        let line = if self.cursor.y < self.viewport.len() {
+            // the line the cursor is on might have been wrapped from the rows above it
+            let mut line_start = self.cursor.y;
+            while line_start > 0 && !self.viewport[line_start].is_canonical {
+                line_start -= 1;
+            }
+            let rows: Vec<&Row> = self.viewport[line_start..=self.cursor.y].iter().collect();
+            self.line_to_string(&rows, false)
+        } else {
+            String::new()
+        };
+        if let Some(completed_lines) = self.completed_lines.as_mut() {
+            completed_lines.push_back(line);
+            if completed_lines.len() > MAX_COMPLETED_LINES {
+                completed_lines.pop_front();
+            }
+        }
+    }
+    fn line_to_string(&self, rows: &[&Row], with_ansi: bool) -> String {
+        let mut characters: Vec<TerminalCharacter> = rows
+            .iter()
+            .flat_map(|row| row.columns.iter().cloned())
+            .collect();
+        // lines are padded with spaces to the width of the pane, we remove these but keep the
+        // ones that are visible because of their background
+        while characters.last().map_or(false, |c| {
+            c.character == ' '
+                && (!with_ansi
+                    || (matches!(c.styles.background, None | Some(AnsiCode::Reset))
+                        && matches!(c.styles.reverse, None | Some(AnsiCode::Reset))))
+        }) {
+            characters.pop();
+        }
+        if with_ansi {
+            serialize_line_with_styles(&characters, self.styled_underlines).unwrap_or_default()
+        } else {
+            characters.iter().map(|c| c.character).collect()
+        }
+    }
     pub fn move_viewport_up(&mut self, count: usize) {
         for _ in 0..count {
             self.scroll_up_one_line();
@@ -1689,6 +1802,7 @@ impl Grid {
         }
     }
     fn add_newline(&mut self) {
+        self.collect_completed_line();
         self.add_canonical_line();
         self.mark_for_rerender();
     }
@@ -1921,6 +2035,35 @@ impl Grid {
             }
         }
     }
+    /// The hyperlink at the position in the viewport, either a link the program marked with OSC 8
+    /// or a url written out in the text
+    pub fn hyperlink_at(&self, position: &Position) -> Option<String> {
+        let row = self.viewport.get(usize::try_from(position.line()).ok()?)?;
+        let mut column = 0;
+        let character_index = row.columns.iter().position(|character| {
+            column += character.width();
+            column > position.column()
+        })?;
+        if let Some(LinkAnchor::Start(link_index)) = row.columns[character_index].styles.link_anchor
+        {
+            if let Some(uri) = self.link_handler.borrow().uri(link_index) {
+                return Some(uri);
+            }
+        }
+        let line: String = row.columns.iter().map(|c| c.character).collect();
+        let byte_index: usize = row
+            .columns
+            .iter()
+            .take(character_index)
+            .map(|c| c.character.len_utf8())
+            .sum();
+        let url = Regex::new(r#"(https?|file)://[^\s<>"'`]*[^\s<>"'`.,;:!?)\]}]"#).unwrap();
+        let url_at_position = url
+            .find_iter(&line)
+            .find(|url| url.range().contains(&byte_index))
+            .map(|url| url.as_str().to_owned());
+        url_at_position
+    }
     pub fn mouse_left_click_signal(&self, position: &Position, is_held: bool) -> Option<String> {
         let utf8_event = || -> Option<String> {
             let button_code = if is_held { b'@' } else { b' ' };
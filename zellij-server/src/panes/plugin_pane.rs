@@ -25,7 +25,7 @@ use zellij_utils::pane_size::{Offset, SizeInPixels};
 use zellij_utils::position::Position;
 use zellij_utils::{
     channels::SenderWithContext,
-    data::{Event, InputMode, Mouse, Palette, PaletteColor, Style},
+    data::{Event, InputMode, Mouse, Palette, PaletteColor, PaneColors, Style},
     errors::prelude::*,
     input::layout::Run,
     pane_size::PaneGeom,
@@ -91,8 +91,11 @@ pub(crate) struct PluginPane {
     prev_pane_name: String,
     frame: HashMap<ClientId, PaneFrame>,
     borderless: bool,
+    is_tab_bar: bool,
     exclude_from_sync: bool,
     pane_frame_color_override: Option<(PaletteColor, Option<String>)>,
+    is_selected: bool, // macros are replayed into the selected panes of the tab
+    pane_colors: PaneColors,
     invoked_with: Option<Run>,
     loading_indication: LoadingIndication,
     requesting_permissions: Option<PluginPermission>,
@@ -134,6 +137,7 @@ impl PluginPane {
             content_offset: Offset::default(),
             pane_title: title,
             borderless: false,
+            is_tab_bar: false,
             pane_name: pane_name.clone(),
             prev_pane_name: pane_name,
             terminal_emulator_colors,
@@ -146,6 +150,8 @@ impl PluginPane {
             grids: HashMap::new(),
             style,
             pane_frame_color_override: None,
+            is_selected: false,
+            pane_colors: PaneColors::default(),
             invoked_with,
             loading_indication,
             requesting_permissions: None,
@@ -249,17 +255,20 @@ impl Pane for PluginPane {
     ) -> Option<AdjustedInput> {
         if let Some(requesting_permissions) = &self.requesting_permissions {
             let permissions = requesting_permissions.permissions.clone();
+            let scopes = requesting_permissions.scopes.clone();
             if let Some(key_with_modifier) = key_with_modifier {
                 match key_with_modifier.bare_key {
                     BareKey::Char('y') if key_with_modifier.has_no_modifiers() => {
                         Some(AdjustedInput::PermissionRequestResult(
                             permissions,
+                            scopes,
                             PermissionStatus::Granted,
                         ))
                     },
                     BareKey::Char('n') if key_with_modifier.has_no_modifiers() => {
                         Some(AdjustedInput::PermissionRequestResult(
                             permissions,
+                            scopes,
                             PermissionStatus::Denied,
                         ))
                     },
@@ -270,16 +279,32 @@ impl Pane for PluginPane {
                     // Y or y
                     &[89] | &[121] => Some(AdjustedInput::PermissionRequestResult(
                         permissions,
+                        scopes,
                         PermissionStatus::Granted,
                     )),
                     // N or n
                     &[78] | &[110] => Some(AdjustedInput::PermissionRequestResult(
                         permissions,
+                        scopes,
                         PermissionStatus::Denied,
                     )),
                     _ => None,
                 }
             }
+        } else if self.loading_indication.is_error() && self.invoked_with.is_some() {
+            // the plugin crashed or was stopped, it can only be reloaded
+            let is_reload_key = match key_with_modifier {
+                Some(key_with_modifier) => {
+                    key_with_modifier.bare_key == BareKey::Char('r')
+                        && key_with_modifier.has_no_modifiers()
+                },
+                None => raw_input_bytes.as_slice() == [114], // r
+            };
+            if is_reload_key {
+                Some(AdjustedInput::ReloadPluginInThisPane)
+            } else {
+                None
+            }
         } else if let Some(key_with_modifier) = key_with_modifier {
             Some(AdjustedInput::WriteKeyToPlugin(key_with_modifier.clone()))
         } else if raw_input_bytes.as_slice() == BRACKETED_PASTE_BEGIN
@@ -601,6 +626,12 @@ impl Pane for PluginPane {
     fn borderless(&self) -> bool {
         self.borderless
     }
+    fn set_is_tab_bar(&mut self, is_tab_bar: bool) {
+        self.is_tab_bar = is_tab_bar;
+    }
+    fn is_tab_bar(&self) -> bool {
+        self.is_tab_bar
+    }
     fn set_exclude_from_sync(&mut self, exclude_from_sync: bool) {
         self.exclude_from_sync = exclude_from_sync;
     }
@@ -627,6 +658,19 @@ impl Pane for PluginPane {
             .as_ref()
             .map(|(color, _text)| *color)
     }
+    fn set_selected(&mut self, is_selected: bool) {
+        self.is_selected = is_selected;
+        self.set_should_render(true);
+    }
+    fn is_selected(&self) -> bool {
+        self.is_selected
+    }
+    fn set_pane_colors(&mut self, pane_colors: PaneColors) {
+        self.pane_colors = pane_colors;
+    }
+    fn pane_colors(&self) -> PaneColors {
+        self.pane_colors
+    }
     fn invoked_with(&self) -> &Option<Run> {
         &self.invoked_with
     }
@@ -735,7 +779,10 @@ impl PluginPane {
                 messages.push_str(&format!(
                     "\n\r{}. {}",
                     bold_white.paint(&format!("{}", i + 1)),
-                    orange.paint(p.display_name())
+                    orange.paint(match plugin_permission.scopes.scope(p) {
+                        Some(scope) => format!("{}: {}", p.display_name(), scope.join(", ")),
+                        None => p.display_name(),
+                    })
                 ));
             });
 
@@ -751,7 +798,10 @@ impl PluginPane {
                 orange.paint(
                     permissions
                         .iter()
-                        .map(|p| p.to_string())
+                        .map(|p| match plugin_permission.scopes.scope(p) {
+                            Some(scope) => format!("{}({})", p, scope.join(", ")),
+                            None => p.to_string(),
+                        })
                         .collect::<Vec<_>>()
                         .join(", ")
                 ),
@@ -11,14 +11,15 @@ use crate::ClientId;
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
+use std::ops::Range;
 use std::rc::Rc;
 use std::time::{self, Instant};
 use zellij_utils::input::command::RunCommand;
 use zellij_utils::pane_size::Offset;
 use zellij_utils::{
     data::{
-        BareKey, InputMode, KeyWithModifier, Palette, PaletteColor, PaneId as ZellijUtilsPaneId,
-        Style,
+        BareKey, InputMode, KeyWithModifier, Palette, PaletteColor, PaneColors, PaneContents,
+        PaneId as ZellijUtilsPaneId, Style,
     },
     errors::prelude::*,
     input::layout::Run,
@@ -128,6 +129,7 @@ pub struct TerminalPane {
     prev_pane_name: String,
     frame: HashMap<ClientId, PaneFrame>,
     borderless: bool,
+    is_tab_bar: bool,
     exclude_from_sync: bool,
     fake_cursor_locations: HashSet<(usize, usize)>, // (x, y) - these hold a record of previous fake cursors which we need to clear on render
     search_term: String,
@@ -136,7 +138,11 @@ pub struct TerminalPane {
     banner: Option<String>, // a banner to be rendered inside this TerminalPane, used for panes
     // held on startup and can possibly be used to display some errors
     pane_frame_color_override: Option<(PaletteColor, Option<String>)>,
+    is_selected: bool, // macros are replayed into the selected panes of the tab
+    pane_colors: PaneColors,
     invoked_with: Option<Run>,
+    restart_status: Option<(usize, Option<i32>)>, // (restart count, last exit status) - set when
+    // the command of this pane was restarted by its restart policy
     #[allow(dead_code)]
     arrow_fonts: bool,
 }
@@ -367,12 +373,14 @@ impl Pane for TerminalPane {
             }
             format!("SEARCHING: {}{}", self.search_term, modifier_text)
         } else if self.pane_name.is_empty() {
-            self.grid
-                .title
-                .clone()
-                .unwrap_or_else(|| self.pane_title.clone())
+            self.with_restart_status(
+                self.grid
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| self.pane_title.clone()),
+            )
         } else {
-            self.pane_name.clone()
+            self.with_restart_status(self.pane_name.clone())
         };
 
         let frame_geom = self.current_geom();
@@ -518,6 +526,19 @@ impl Pane for TerminalPane {
     fn dump_screen(&mut self, _client_id: ClientId, full: bool) -> String {
         self.grid.dump_screen(full)
     }
+    fn pane_contents(
+        &self,
+        line_range: Option<Range<usize>>,
+        with_ansi: bool,
+    ) -> Option<PaneContents> {
+        Some(self.grid.pane_contents(line_range, with_ansi))
+    }
+    fn collect_completed_lines(&mut self, should_collect: bool) {
+        self.grid.collect_completed_lines(should_collect);
+    }
+    fn drain_completed_lines(&mut self) -> Vec<String> {
+        self.grid.drain_completed_lines()
+    }
     fn clear_screen(&mut self) {
         self.grid.clear_screen()
     }
@@ -620,6 +641,12 @@ impl Pane for TerminalPane {
     fn borderless(&self) -> bool {
         self.borderless
     }
+    fn set_is_tab_bar(&mut self, is_tab_bar: bool) {
+        self.is_tab_bar = is_tab_bar;
+    }
+    fn is_tab_bar(&self) -> bool {
+        self.is_tab_bar
+    }
 
     fn set_exclude_from_sync(&mut self, exclude_from_sync: bool) {
         self.exclude_from_sync = exclude_from_sync;
@@ -632,6 +659,9 @@ impl Pane for TerminalPane {
     fn mouse_left_click(&self, position: &Position, is_held: bool) -> Option<String> {
         self.grid.mouse_left_click_signal(position, is_held)
     }
+    fn hyperlink_at(&self, position: &Position) -> Option<String> {
+        self.grid.hyperlink_at(position)
+    }
     fn mouse_left_click_release(&self, position: &Position) -> Option<String> {
         self.grid.mouse_left_click_release_signal(position)
     }
@@ -733,6 +763,19 @@ impl Pane for TerminalPane {
             .as_ref()
             .map(|(color, _text)| *color)
     }
+    fn set_selected(&mut self, is_selected: bool) {
+        self.is_selected = is_selected;
+        self.set_should_render(true);
+    }
+    fn is_selected(&self) -> bool {
+        self.is_selected
+    }
+    fn set_pane_colors(&mut self, pane_colors: PaneColors) {
+        self.pane_colors = pane_colors;
+    }
+    fn pane_colors(&self) -> PaneColors {
+        self.pane_colors
+    }
     fn invoked_with(&self) -> &Option<Run> {
         &self.invoked_with
     }
@@ -790,6 +833,10 @@ impl Pane for TerminalPane {
             run_command.clone()
         })
     }
+    fn set_restart_status(&mut self, restart_count: usize, last_exit_status: Option<i32>) {
+        self.restart_status = Some((restart_count, last_exit_status));
+        self.set_should_render(true);
+    }
     fn update_theme(&mut self, theme: Palette) {
         self.style.colors = theme.clone();
         self.grid.update_theme(theme);
@@ -860,16 +907,30 @@ impl TerminalPane {
             pane_name: pane_name.clone(),
             prev_pane_name: pane_name,
             borderless: false,
+            is_tab_bar: false,
             exclude_from_sync: false,
             fake_cursor_locations: HashSet::new(),
             search_term: String::new(),
             is_held: None,
             banner: None,
             pane_frame_color_override: None,
+            is_selected: false,
+            pane_colors: PaneColors::default(),
             invoked_with,
+            restart_status: None,
             arrow_fonts,
         }
     }
+    fn with_restart_status(&self, title: String) -> String {
+        match self.restart_status {
+            Some((restart_count, Some(last_exit_status))) => format!(
+                "{} [RESTARTS: {}, LAST EXIT CODE: {}]",
+                title, restart_count, last_exit_status
+            ),
+            Some((restart_count, None)) => format!("{} [RESTARTS: {}]", title, restart_count),
+            None => title,
+        }
+    }
     pub fn get_x(&self) -> usize {
         match self.geom_override {
             Some(position_and_size_override) => position_and_size_override.x,
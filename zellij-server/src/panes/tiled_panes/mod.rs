@@ -18,7 +18,7 @@ use crate::{
 };
 use stacked_panes::StackedPanes;
 use zellij_utils::{
-    data::{Direction, ModeInfo, Palette, PaneInfo, ResizeStrategy, Style},
+    data::{Direction, ModeInfo, Palette, PaneColors, PaneInfo, ResizeStrategy, Style},
     errors::prelude::*,
     input::{
         command::RunCommand,
@@ -635,7 +635,12 @@ impl TiledPanes {
     pub fn has_panes(&self) -> bool {
         !self.panes.is_empty()
     }
-    pub fn render(&mut self, output: &mut Output, floating_panes_are_visible: bool) -> Result<()> {
+    pub fn render(
+        &mut self,
+        output: &mut Output,
+        floating_panes_are_visible: bool,
+        tab_colors: PaneColors,
+    ) -> Result<()> {
         let err_context = || "failed to render tiled panes";
 
         let connected_clients: Vec<ClientId> =
@@ -675,14 +680,15 @@ impl TiledPanes {
                     pane_is_stacked_under,
                     pane_is_stacked_over,
                     should_draw_pane_frames,
+                    tab_colors,
                 );
                 for client_id in &connected_clients {
-                    let client_mode = self
-                        .mode_info
-                        .borrow()
-                        .get(client_id)
-                        .unwrap_or(&self.default_mode_info)
-                        .mode;
+                    let (client_mode, client_colors) = {
+                        let mode_info = self.mode_info.borrow();
+                        let client_mode_info =
+                            mode_info.get(client_id).unwrap_or(&self.default_mode_info);
+                        (client_mode_info.mode.clone(), client_mode_info.style.colors)
+                    };
                     let err_context =
                         || format!("failed to render tiled panes for client {client_id}");
                     if let PaneId::Plugin(..) = kind {
@@ -692,13 +698,23 @@ impl TiledPanes {
                     }
                     if self.draw_pane_frames {
                         pane_contents_and_ui
-                            .render_pane_frame(*client_id, client_mode, self.session_is_mirrored)
+                            .render_pane_frame(
+                                *client_id,
+                                client_mode.clone(),
+                                client_colors,
+                                self.session_is_mirrored,
+                            )
                             .with_context(err_context)?;
                     } else if pane_is_stacked {
                         // if we have no pane frames but the pane is stacked, we need to render its
                         // frame which will amount to only rendering the title line
                         pane_contents_and_ui
-                            .render_pane_frame(*client_id, client_mode, self.session_is_mirrored)
+                            .render_pane_frame(
+                                *client_id,
+                                client_mode.clone(),
+                                client_colors,
+                                self.session_is_mirrored,
+                            )
                             .with_context(err_context)?;
                         // we also need to render its boundaries as normal
                         let boundaries = client_id_to_boundaries
@@ -706,7 +722,8 @@ impl TiledPanes {
                             .or_insert_with(|| Boundaries::new(*self.viewport.borrow()));
                         pane_contents_and_ui.render_pane_boundaries(
                             *client_id,
-                            client_mode,
+                            client_mode.clone(),
+                            client_colors,
                             boundaries,
                             self.session_is_mirrored,
                         );
@@ -716,7 +733,8 @@ impl TiledPanes {
                             .or_insert_with(|| Boundaries::new(*self.viewport.borrow()));
                         pane_contents_and_ui.render_pane_boundaries(
                             *client_id,
-                            client_mode,
+                            client_mode.clone(),
+                            client_colors,
                             boundaries,
                             self.session_is_mirrored,
                         );
@@ -3900,3 +3900,101 @@ fn cannot_escape_scroll_region() {
     }
     assert_snapshot!(format!("{:?}", grid));
 }
+
+#[test]
+fn pane_contents_with_wrapped_lines_and_scrollback_range() {
+    let mut vte_parser = vte::Parser::new();
+    let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
+    let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
+    let debug = false;
+    let arrow_fonts = true;
+    let styled_underlines = true;
+    let explicitly_disable_kitty_keyboard_protocol = false;
+    let mut grid = Grid::new(
+        5,
+        10,
+        Rc::new(RefCell::new(Palette::default())),
+        terminal_emulator_color_codes,
+        Rc::new(RefCell::new(LinkHandler::new())),
+        Rc::new(RefCell::new(None)),
+        sixel_image_store,
+        Style::default(),
+        debug,
+        arrow_fonts,
+        styled_underlines,
+        explicitly_disable_kitty_keyboard_protocol,
+    );
+    let content = "line 1\r\nline 2\r\nline 3\r\nline 4\r\nline 5\r\nline 6\r\nline 7\r\n\u{1b}[31mline\u{1b}[m 8\r\n0123456789abcde";
+    for byte in content.as_bytes() {
+        vte_parser.advance(&mut grid, *byte);
+    }
+    let pane_contents = grid.pane_contents(None, false);
+    assert_eq!(
+        pane_contents.viewport,
+        vec!["line 6", "line 7", "line 8", "0123456789abcde"],
+        "wrapped lines are joined in the viewport"
+    );
+    assert!(
+        pane_contents.scrollback.is_empty(),
+        "no scrollback without a range"
+    );
+    assert_eq!(pane_contents.scrollback_length, 9);
+
+    let pane_contents = grid.pane_contents(Some(3..5), false);
+    assert_eq!(pane_contents.scrollback, vec!["line 4", "line 5"]);
+    assert_eq!(pane_contents.scrollback_offset, 3);
+
+    let pane_contents = grid.pane_contents(Some(7..100), true);
+    assert_eq!(
+        pane_contents.scrollback,
+        vec!["\u{1b}[31mline\u{1b}[m 8", "0123456789abcde"],
+        "range is clamped to the scrollback length and styles are kept"
+    );
+    assert_eq!(pane_contents.scrollback_offset, 7);
+}
+
+#[test]
+fn completed_lines_are_collected_only_when_requested() {
+    let mut vte_parser = vte::Parser::new();
+    let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
+    let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
+    let debug = false;
+    let arrow_fonts = true;
+    let styled_underlines = true;
+    let explicitly_disable_kitty_keyboard_protocol = false;
+    let mut grid = Grid::new(
+        5,
+        10,
+        Rc::new(RefCell::new(Palette::default())),
+        terminal_emulator_color_codes,
+        Rc::new(RefCell::new(LinkHandler::new())),
+        Rc::new(RefCell::new(None)),
+        sixel_image_store,
+        Style::default(),
+        debug,
+        arrow_fonts,
+        styled_underlines,
+        explicitly_disable_kitty_keyboard_protocol,
+    );
+    let mut print = |grid: &mut Grid, content: &str| {
+        for byte in content.as_bytes() {
+            vte_parser.advance(grid, *byte);
+        }
+    };
+    print(&mut grid, "not collected\r\n");
+    assert!(grid.drain_completed_lines().is_empty());
+    grid.collect_completed_lines(true);
+    print(
+        &mut grid,
+        "line 1\r\n\r\n0123456789abcde\r\n\u{1b}[?1049hfull screen\r\n\u{1b}[?1049lline 2\r\nincompl",
+    );
+    assert_eq!(
+        grid.drain_completed_lines(),
+        vec!["line 1", "", "0123456789abcde", "line 2"],
+        "wrapped lines are joined and the alternate screen is ignored"
+    );
+    assert!(grid.drain_completed_lines().is_empty(), "lines are drained");
+    grid.collect_completed_lines(false);
+    print(&mut grid, "te\r\n");
+    assert!(grid.drain_completed_lines().is_empty());
+}
@@ -1,15 +1,16 @@
 mod pipes;
+mod plugin_limiter;
 mod plugin_loader;
 mod plugin_map;
 mod plugin_worker;
 mod wasm_bridge;
-mod watch_filesystem;
+pub(crate) mod watch_filesystem;
 mod zellij_exports;
 use log::info;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::Duration,
 };
 use wasmtime::Engine;
@@ -25,15 +26,16 @@ use wasm_bridge::WasmBridge;
 use zellij_utils::{
     async_std::{channel, future::timeout, task},
     data::{
-        Event, EventType, InputMode, MessageToPlugin, PermissionStatus, PermissionType,
-        PipeMessage, PipeSource, PluginCapabilities,
+        Event, EventType, InputMode, MessageToPlugin, PermissionScopes, PermissionStatus,
+        PermissionType, PipeMessage, PipeSource, PluginCapabilities, PluginRequestTarget,
     },
     errors::{prelude::*, ContextType, PluginContext},
     input::{
         command::TerminalAction,
         keybinds::Keybinds,
         layout::{FloatingPaneLayout, Layout, Run, RunPlugin, RunPluginOrAlias, TiledPaneLayout},
-        plugins::PluginAliases,
+        plugin_lockfile::PluginLockfile,
+        plugins::{PluginAliases, PluginLimits},
     },
     ipc::ClientAttributes,
     pane_size::Size,
@@ -101,6 +103,7 @@ pub enum PluginInstruction {
         PluginId,
         Option<ClientId>,
         Vec<PermissionType>,
+        PermissionScopes,
         PermissionStatus,
         Option<PathBuf>,
     ),
@@ -144,6 +147,23 @@ pub enum PluginInstruction {
         message: MessageToPlugin,
     },
     UnblockCliPipes(Vec<PluginRenderAsset>),
+    PluginRequest {
+        source_plugin_id: PluginId,
+        source_client_id: ClientId,
+        request_id: String,
+        target: PluginRequestTarget,
+        name: String,
+        payload: Option<String>,
+    },
+    PluginResponse {
+        responding_plugin_id: PluginId,
+        request_id: String,
+        payload: Option<String>,
+    },
+    PluginRequestTimedOut(String),           // String -> request id
+    PermissionCacheChanged(Option<PathBuf>), // Option<PathBuf> -> cache path
+    PluginFileChanged(PathBuf),
+    PluginLockfileChanged,
     Reconfigure {
         client_id: ClientId,
         keybinds: Option<Keybinds>,
@@ -190,6 +210,12 @@ impl From<&PluginInstruction> for PluginContext {
             PluginInstruction::CachePluginEvents { .. } => PluginContext::CachePluginEvents,
             PluginInstruction::MessageFromPlugin { .. } => PluginContext::MessageFromPlugin,
             PluginInstruction::UnblockCliPipes { .. } => PluginContext::UnblockCliPipes,
+            PluginInstruction::PluginRequest { .. } => PluginContext::PluginRequest,
+            PluginInstruction::PluginResponse { .. } => PluginContext::PluginResponse,
+            PluginInstruction::PluginRequestTimedOut(..) => PluginContext::PluginRequestTimedOut,
+            PluginInstruction::PermissionCacheChanged(..) => PluginContext::PermissionCacheChanged,
+            PluginInstruction::PluginFileChanged(..) => PluginContext::PluginFileChanged,
+            PluginInstruction::PluginLockfileChanged => PluginContext::PluginLockfileChanged,
             PluginInstruction::WatchFilesystem => PluginContext::WatchFilesystem,
             PluginInstruction::KeybindPipe { .. } => PluginContext::KeybindPipe,
             PluginInstruction::DumpLayoutToPlugin(..) => PluginContext::DumpLayoutToPlugin,
@@ -201,6 +227,17 @@ impl From<&PluginInstruction> for PluginContext {
     }
 }
 
+// the plugins installed with `zellij plugin install` can be used by their alias, unless an alias
+// with the same name was configured
+fn with_installed_plugin_aliases(
+    configured_plugin_aliases: &PluginAliases,
+    plugin_dir: &Path,
+) -> PluginAliases {
+    let mut plugin_aliases = PluginLockfile::from_plugin_dir(plugin_dir).plugin_aliases();
+    plugin_aliases.merge(configured_plugin_aliases.clone());
+    plugin_aliases
+}
+
 pub(crate) fn plugin_thread_main(
     bus: Bus<PluginInstruction>,
     engine: Engine,
@@ -212,23 +249,26 @@ pub(crate) fn plugin_thread_main(
     capabilities: PluginCapabilities,
     client_attributes: ClientAttributes,
     default_shell: Option<TerminalAction>,
-    plugin_aliases: Box<PluginAliases>,
+    configured_plugin_aliases: Box<PluginAliases>,
     default_mode: InputMode,
     default_keybinds: Keybinds,
+    plugin_limits: PluginLimits,
 ) -> Result<()> {
     info!("Wasm main thread starts");
     let plugin_dir = data_dir.join("plugins/");
     let plugin_global_data_dir = plugin_dir.join("data");
+    let mut plugin_aliases = with_installed_plugin_aliases(&configured_plugin_aliases, &plugin_dir);
     layout.populate_plugin_aliases_in_layout(&plugin_aliases);
 
     // use this channel to ensure that tasks spawned from this thread terminate before exiting
     // https://tokio.rs/tokio/topics/shutdown#waiting-for-things-to-finish-shutting-down
     let (shutdown_send, shutdown_receive) = channel::bounded::<()>(1);
 
+    plugin_limiter::start_epoch_ticker(&engine);
     let mut wasm_bridge = WasmBridge::new(
         bus.senders.clone(),
         engine,
-        plugin_dir,
+        plugin_dir.clone(),
         path_to_default_shell,
         zellij_cwd,
         capabilities,
@@ -238,7 +278,10 @@ pub(crate) fn plugin_thread_main(
         layout_dir,
         default_mode,
         default_keybinds,
+        plugin_limits,
     );
+    wasm_bridge.start_permission_cache_watcher();
+    wasm_bridge.start_plugin_lockfile_watcher();
 
     loop {
         let (event, mut err_ctx) = bus.recv().expect("failed to receive event on channel");
@@ -489,6 +532,7 @@ pub(crate) fn plugin_thread_main(
                 plugin_id,
                 client_id,
                 permissions,
+                permission_scopes,
                 status,
                 cache_path,
             ) => {
@@ -496,6 +540,7 @@ pub(crate) fn plugin_thread_main(
                     plugin_id,
                     client_id,
                     permissions,
+                    permission_scopes,
                     status,
                     cache_path,
                 ) {
@@ -775,6 +820,55 @@ pub(crate) fn plugin_thread_main(
                         .context("failed to unblock input pipe");
                 }
             },
+            PluginInstruction::PluginRequest {
+                source_plugin_id,
+                source_client_id,
+                request_id,
+                target,
+                name,
+                payload,
+            } => {
+                let destination = plugin_request_destination(
+                    &target,
+                    source_client_id,
+                    &mut wasm_bridge,
+                    &plugin_aliases,
+                );
+                wasm_bridge.plugin_request(
+                    source_plugin_id,
+                    source_client_id,
+                    request_id,
+                    destination,
+                    name,
+                    payload,
+                    shutdown_send.clone(),
+                )?;
+            },
+            PluginInstruction::PluginResponse {
+                responding_plugin_id,
+                request_id,
+                payload,
+            } => {
+                wasm_bridge.respond_to_plugin_request(
+                    responding_plugin_id,
+                    request_id,
+                    payload,
+                    shutdown_send.clone(),
+                )?;
+            },
+            PluginInstruction::PluginRequestTimedOut(request_id) => {
+                wasm_bridge.plugin_request_timed_out(request_id, shutdown_send.clone())?;
+            },
+            PluginInstruction::PermissionCacheChanged(cache_path) => {
+                wasm_bridge.apply_permission_cache(cache_path);
+            },
+            PluginInstruction::PluginFileChanged(plugin_path) => {
+                wasm_bridge.reload_plugins_loaded_from(&plugin_path);
+            },
+            PluginInstruction::PluginLockfileChanged => {
+                plugin_aliases =
+                    with_installed_plugin_aliases(&configured_plugin_aliases, &plugin_dir);
+            },
             PluginInstruction::Reconfigure {
                 client_id,
                 keybinds,
@@ -930,6 +1024,41 @@ fn pipe_to_specific_plugins(
     }
 }
 
+// the instance of the plugin that should answer a plugin request: a plugin has an instance per
+// client, we prefer the one of the requesting client
+fn plugin_request_destination(
+    target: &PluginRequestTarget,
+    source_client_id: ClientId,
+    wasm_bridge: &mut WasmBridge,
+    plugin_aliases: &PluginAliases,
+) -> Option<(PluginId, Option<ClientId>)> {
+    let mut plugin_and_client_ids = match target {
+        PluginRequestTarget::PluginId(plugin_id) => {
+            wasm_bridge.plugin_and_client_ids_of_plugin_id(*plugin_id)
+        },
+        PluginRequestTarget::PluginUrl(plugin_url) => {
+            match RunPluginOrAlias::from_url(plugin_url, &None, Some(plugin_aliases), None)
+                .map(|run_plugin_or_alias| run_plugin_or_alias.get_run_plugin())
+            {
+                Ok(Some(run_plugin)) => {
+                    wasm_bridge.plugin_and_client_ids_of_run_plugin(&run_plugin)
+                },
+                Ok(None) => vec![],
+                Err(e) => {
+                    log::error!("Failed to parse plugin url: {}", e);
+                    vec![]
+                },
+            }
+        },
+    };
+    plugin_and_client_ids.sort();
+    plugin_and_client_ids
+        .iter()
+        .find(|(_plugin_id, client_id)| client_id == &Some(source_client_id))
+        .or_else(|| plugin_and_client_ids.first())
+        .copied()
+}
+
 const EXIT_TIMEOUT: Duration = Duration::from_secs(3);
 
 #[path = "./unit/plugin_tests.rs"]
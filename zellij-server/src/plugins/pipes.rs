@@ -3,7 +3,7 @@ use crate::plugins::plugin_map::RunningPlugin;
 use crate::plugins::wasm_bridge::PluginRenderAsset;
 use crate::plugins::zellij_exports::{wasi_read_string, wasi_write_object};
 use std::collections::{HashMap, HashSet};
-use zellij_utils::data::{PipeMessage, PipeSource};
+use zellij_utils::data::{Event, PipeMessage, PipeSource, PluginRequestError, PluginResponse};
 use zellij_utils::plugin_api::pipe_message::ProtobufPipeMessage;
 
 use zellij_utils::errors::prelude::*;
@@ -137,6 +137,113 @@ impl PendingPipeInfo {
     }
 }
 
+#[derive(Debug, Clone)]
+struct PendingPluginRequest {
+    name: String,
+    source_plugin_id: PluginId,
+    source_client_id: ClientId,
+    destination_plugin_id: PluginId,
+}
+
+/// Requests sent between plugins with `plugin_request` that were not yet answered, keyed by their
+/// correlation id
+#[derive(Debug, Clone, Default)]
+pub struct PendingPluginRequests {
+    requests: HashMap<String, PendingPluginRequest>,
+}
+
+impl PendingPluginRequests {
+    pub fn add(
+        &mut self,
+        request_id: String,
+        name: String,
+        source_plugin_id: PluginId,
+        source_client_id: ClientId,
+        destination_plugin_id: PluginId,
+    ) {
+        self.requests.insert(
+            request_id,
+            PendingPluginRequest {
+                name,
+                source_plugin_id,
+                source_client_id,
+                destination_plugin_id,
+            },
+        );
+    }
+    // returns the update to send to the requesting plugin, if this plugin is the one the request
+    // is pending on
+    pub fn respond(
+        &mut self,
+        request_id: &str,
+        responding_plugin_id: PluginId,
+        payload: Option<String>,
+    ) -> Option<(Option<PluginId>, Option<ClientId>, Event)> {
+        let is_pending_on_plugin = self
+            .requests
+            .get(request_id)
+            .map(|pending_request| pending_request.destination_plugin_id == responding_plugin_id)
+            .unwrap_or(false);
+        if !is_pending_on_plugin {
+            return None;
+        }
+        self.requests
+            .remove(request_id)
+            .map(|pending_request| pending_request.into_update(request_id, Ok(payload)))
+    }
+    // returns the update to send to the requesting plugin, if the request was not yet answered
+    pub fn time_out(
+        &mut self,
+        request_id: &str,
+    ) -> Option<(Option<PluginId>, Option<ClientId>, Event)> {
+        self.requests.remove(request_id).map(|pending_request| {
+            pending_request.into_update(request_id, Err(PluginRequestError::Timeout))
+        })
+    }
+    // returns the updates to send to the plugins whose requests will no longer be answered
+    pub fn unload_plugin(
+        &mut self,
+        plugin_id: &PluginId,
+    ) -> Vec<(Option<PluginId>, Option<ClientId>, Event)> {
+        let mut updates = vec![];
+        // requests sent by the unloaded plugin will not be answered either
+        self.requests
+            .retain(|_, pending_request| &pending_request.source_plugin_id != plugin_id);
+        let request_ids: Vec<String> = self
+            .requests
+            .iter()
+            .filter(|(_, pending_request)| &pending_request.destination_plugin_id == plugin_id)
+            .map(|(request_id, _)| request_id.clone())
+            .collect();
+        for request_id in request_ids {
+            if let Some(pending_request) = self.requests.remove(&request_id) {
+                updates.push(
+                    pending_request.into_update(&request_id, Err(PluginRequestError::NoSuchPlugin)),
+                );
+            }
+        }
+        updates
+    }
+}
+
+impl PendingPluginRequest {
+    fn into_update(
+        self,
+        request_id: &str,
+        result: Result<Option<String>, PluginRequestError>,
+    ) -> (Option<PluginId>, Option<ClientId>, Event) {
+        (
+            Some(self.source_plugin_id),
+            Some(self.source_client_id),
+            Event::PluginResponse(PluginResponse {
+                request_id: request_id.to_owned(),
+                name: self.name,
+                result,
+            }),
+        )
+    }
+}
+
 pub fn apply_pipe_message_to_plugin(
     plugin_id: PluginId,
     client_id: ClientId,
@@ -248,3 +355,7 @@ pub fn pipes_to_block_or_unblock(
     }
     pipe_state_changes
 }
+
+#[path = "./unit/pipes_tests.rs"]
+#[cfg(test)]
+mod pipes_tests;
@@ -0,0 +1,133 @@
+use crate::plugins::{wasm_bridge::handle_plugin_crash, PluginId};
+use crate::thread_bus::ThreadSenders;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use wasmtime::{CallHook, Engine, ResourceLimiter, Store, UpdateDeadline};
+use zellij_utils::{errors::prelude::*, input::plugins::PluginLimits};
+
+// how often the engine epoch advances, this is also the granularity of the call timeout
+const EPOCH_TICK: Duration = Duration::from_millis(100);
+
+/// Stops a plugin that exceeds its `PluginLimits`: growing its memory beyond the limit or a call
+/// into it (eg. `update` or `render`) running for longer than the timeout traps, the plugin is
+/// shown as errored in its pane and every call into it after that fails until it is reloaded
+#[derive(Default)]
+pub struct PluginLimiter {
+    limits: PluginLimits,
+    plugin_id: PluginId,
+    senders: ThreadSenders,
+    current_call_started_at: Option<Instant>,
+    call_depth: usize,
+    exceeded: Option<String>,
+}
+
+impl PluginLimiter {
+    pub fn new(limits: PluginLimits, plugin_id: PluginId, senders: ThreadSenders) -> Self {
+        PluginLimiter {
+            limits,
+            plugin_id,
+            senders,
+            ..Default::default()
+        }
+    }
+    pub fn call_hook(&mut self, call_hook: CallHook) -> Result<()> {
+        match call_hook {
+            CallHook::CallingWasm => {
+                if let Some(exceeded) = &self.exceeded {
+                    return Err(anyhow!("Plugin was stopped because {}", exceeded));
+                }
+                if self.call_depth == 0 {
+                    self.current_call_started_at = Some(Instant::now());
+                }
+                self.call_depth += 1;
+            },
+            CallHook::ReturningFromWasm => {
+                self.call_depth = self.call_depth.saturating_sub(1);
+                if self.call_depth == 0 {
+                    self.current_call_started_at = None;
+                }
+            },
+            CallHook::CallingHost | CallHook::ReturningFromHost => {},
+        }
+        Ok(())
+    }
+    // called by the engine every epoch tick while the plugin is running
+    pub fn epoch_deadline_reached(&mut self) -> Result<UpdateDeadline> {
+        let call_duration = self
+            .current_call_started_at
+            .map(|started_at| started_at.elapsed());
+        match (self.limits.call_timeout, call_duration) {
+            (Some(call_timeout), Some(call_duration)) if call_duration > call_timeout => Err(self
+                .stop(format!(
+                    "it ran for longer than its call timeout of {}ms",
+                    call_timeout.as_millis()
+                ))),
+            _ => Ok(UpdateDeadline::Continue(1)),
+        }
+    }
+    fn stop(&mut self, reason: String) -> anyError {
+        let error = anyhow!("Plugin was stopped because {}", reason);
+        if self.exceeded.is_none() {
+            handle_plugin_crash(self.plugin_id, format!("{}", error), self.senders.clone());
+        }
+        self.exceeded = Some(reason);
+        error
+    }
+}
+
+/// Makes the `PluginLimiter` returned by `limiter` enforce its limits on all calls into the
+/// instances of this store
+pub fn limit_store<T: 'static>(store: &mut Store<T>, limiter: fn(&mut T) -> &mut PluginLimiter) {
+    store.limiter(move |data| limiter(data));
+    store.call_hook(move |data, call_hook| limiter(data).call_hook(call_hook));
+    store.epoch_deadline_callback(move |mut store| {
+        limiter(store.data_mut()).epoch_deadline_reached()
+    });
+    store.set_epoch_deadline(1);
+}
+
+impl ResourceLimiter for PluginLimiter {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> Result<bool> {
+        match self.limits.memory {
+            Some(memory_limit) if desired > memory_limit => Err(self.stop(format!(
+                "it tried to use more than its memory limit of {}MB",
+                memory_limit / (1024 * 1024)
+            ))),
+            _ => Ok(true),
+        }
+    }
+    fn table_growing(
+        &mut self,
+        _current: u32,
+        _desired: u32,
+        _maximum: Option<u32>,
+    ) -> Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Advances the epoch of the engine in the background so that plugins running for longer than
+/// their call timeout can be interrupted, stops along with the engine
+pub fn start_epoch_ticker(engine: &Engine) {
+    let engine = engine.weak();
+    let _ = thread::Builder::new()
+        .name("plugin_epoch_ticker".to_owned())
+        .spawn(move || loop {
+            thread::sleep(EPOCH_TICK);
+            match engine.upgrade() {
+                Some(engine) => engine.increment_epoch(),
+                None => break,
+            }
+        });
+}
+
+#[path = "./unit/plugin_limiter_tests.rs"]
+#[cfg(test)]
+mod plugin_limiter_tests;
@@ -1,3 +1,4 @@
+use crate::plugins::plugin_limiter::{limit_store, PluginLimiter};
 use crate::plugins::plugin_map::{
     PluginEnv, PluginMap, RunningPlugin, VecDequeInputStream, WriteOutputStream,
 };
@@ -9,7 +10,7 @@ use log::info;
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     fs,
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
     sync::{Arc, Mutex},
 };
 use url::Url;
@@ -26,12 +27,14 @@ use crate::{
 use zellij_utils::plugin_api::action::ProtobufPluginConfiguration;
 use zellij_utils::{
     consts::{ZELLIJ_CACHE_DIR, ZELLIJ_SESSION_CACHE_DIR, ZELLIJ_TMP_DIR},
-    data::{InputMode, PluginCapabilities},
+    data::{host_relative_path, InputMode, PermissionScopes, PermissionType, PluginCapabilities},
     errors::prelude::*,
     input::command::TerminalAction,
     input::keybinds::Keybinds,
     input::layout::Layout,
-    input::plugins::PluginConfig,
+    input::permission::PermissionCache,
+    input::plugin_lockfile::PluginLockfile,
+    input::plugins::{PluginConfig, PluginLimits},
     ipc::ClientAttributes,
     pane_size::Size,
 };
@@ -49,7 +52,9 @@ macro_rules! display_loading_stage {
 }
 
 pub struct PluginLoader<'a> {
-    plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>>,
+    // the modules in memory along with the cache artifacts they were compiled to, which are named
+    // after the hash of the plugin bytes
+    plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
     plugin_path: PathBuf,
     loading_indication: &'a mut LoadingIndication,
     senders: ThreadSenders,
@@ -62,6 +67,7 @@ pub struct PluginLoader<'a> {
     plugin_own_data_dir: PathBuf,
     size: Size,
     wasm_blob_on_hd: Option<(Vec<u8>, PathBuf)>,
+    module_artifact_path: Option<PathBuf>,
     path_to_default_shell: PathBuf,
     zellij_cwd: PathBuf,
     capabilities: PluginCapabilities,
@@ -71,13 +77,14 @@ pub struct PluginLoader<'a> {
     layout_dir: Option<PathBuf>,
     default_mode: InputMode,
     keybinds: Keybinds,
+    plugin_limits: PluginLimits,
 }
 
 impl<'a> PluginLoader<'a> {
     pub fn reload_plugin_from_memory(
         plugin_id: PluginId,
         plugin_dir: PathBuf,
-        plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         senders: ThreadSenders,
         engine: Engine,
         plugin_map: Arc<Mutex<PluginMap>>,
@@ -92,6 +99,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         base_modes: &HashMap<ClientId, InputMode>,
         keybinds: &HashMap<ClientId, Keybinds>,
+        plugin_limits: PluginLimits,
     ) -> Result<()> {
         let err_context = || format!("failed to reload plugin {plugin_id} from memory");
         let mut connected_clients: Vec<ClientId> =
@@ -124,6 +132,7 @@ impl<'a> PluginLoader<'a> {
             layout_dir,
             default_mode,
             keybinds,
+            plugin_limits,
         )?;
         plugin_loader
             .load_module_from_memory()
@@ -145,7 +154,7 @@ impl<'a> PluginLoader<'a> {
         plugin: &PluginConfig,
         tab_index: Option<usize>,
         plugin_dir: PathBuf,
-        plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         senders: ThreadSenders,
         engine: Engine,
         plugin_map: Arc<Mutex<PluginMap>>,
@@ -162,6 +171,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         default_mode: InputMode,
         keybinds: Keybinds,
+        plugin_limits: PluginLimits,
     ) -> Result<()> {
         let err_context = || format!("failed to start plugin {plugin_id} for client {client_id}");
         let mut plugin_loader = PluginLoader::new(
@@ -184,6 +194,7 @@ impl<'a> PluginLoader<'a> {
             layout_dir,
             default_mode,
             keybinds,
+            plugin_limits,
         )?;
         if skip_cache {
             plugin_loader
@@ -223,7 +234,7 @@ impl<'a> PluginLoader<'a> {
     pub fn add_client(
         client_id: ClientId,
         plugin_dir: PathBuf,
-        plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         senders: ThreadSenders,
         engine: Engine,
         plugin_map: Arc<Mutex<PluginMap>>,
@@ -238,6 +249,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         default_mode: InputMode,
         keybinds: Keybinds,
+        plugin_limits: PluginLimits,
     ) -> Result<()> {
         let mut new_plugins = HashSet::new();
         for plugin_id in plugin_map.lock().unwrap().plugin_ids() {
@@ -260,8 +272,9 @@ impl<'a> PluginLoader<'a> {
                 default_shell.clone(),
                 default_layout.clone(),
                 layout_dir.clone(),
-                default_mode,
+                default_mode.clone(),
                 keybinds.clone(),
+                plugin_limits,
             )?;
             plugin_loader
                 .load_module_from_memory()
@@ -277,7 +290,7 @@ impl<'a> PluginLoader<'a> {
     pub fn reload_plugin(
         plugin_id: PluginId,
         plugin_dir: PathBuf,
-        plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         senders: ThreadSenders,
         engine: Engine,
         plugin_map: Arc<Mutex<PluginMap>>,
@@ -292,6 +305,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         base_modes: &HashMap<ClientId, InputMode>,
         keybinds: &HashMap<ClientId, Keybinds>,
+        plugin_limits: PluginLimits,
     ) -> Result<()> {
         let err_context = || format!("failed to reload plugin id {plugin_id}");
 
@@ -325,6 +339,7 @@ impl<'a> PluginLoader<'a> {
             layout_dir,
             default_mode,
             keybinds,
+            plugin_limits,
         )?;
         plugin_loader
             .compile_module()
@@ -340,7 +355,7 @@ impl<'a> PluginLoader<'a> {
         Ok(())
     }
     pub fn new(
-        plugin_cache: &Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: &Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         loading_indication: &'a mut LoadingIndication,
         senders: &ThreadSenders,
         plugin_id: PluginId,
@@ -359,6 +374,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         default_mode: InputMode,
         keybinds: Keybinds,
+        plugin_limits: PluginLimits,
     ) -> Result<Self> {
         let plugin_own_data_dir = ZELLIJ_SESSION_CACHE_DIR
             .join(Url::from(&plugin.location).to_string())
@@ -379,6 +395,7 @@ impl<'a> PluginLoader<'a> {
             plugin_own_data_dir,
             size,
             wasm_blob_on_hd: None,
+            module_artifact_path: None,
             path_to_default_shell,
             zellij_cwd,
             capabilities,
@@ -388,10 +405,11 @@ impl<'a> PluginLoader<'a> {
             layout_dir,
             default_mode,
             keybinds,
+            plugin_limits,
         })
     }
     pub fn new_from_existing_plugin_attributes(
-        plugin_cache: &Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: &Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         plugin_map: &Arc<Mutex<PluginMap>>,
         loading_indication: &'a mut LoadingIndication,
         senders: &ThreadSenders,
@@ -408,6 +426,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         default_mode: InputMode,
         keybinds: Keybinds,
+        plugin_limits: PluginLimits,
     ) -> Result<Self> {
         let err_context = || "Failed to find existing plugin";
         let (running_plugin, _subscriptions, _workers) = {
@@ -444,10 +463,11 @@ impl<'a> PluginLoader<'a> {
             layout_dir,
             default_mode,
             keybinds,
+            plugin_limits,
         )
     }
     pub fn new_from_different_client_id(
-        plugin_cache: &Arc<Mutex<HashMap<PathBuf, Module>>>,
+        plugin_cache: &Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
         plugin_map: &Arc<Mutex<PluginMap>>,
         loading_indication: &'a mut LoadingIndication,
         senders: &ThreadSenders,
@@ -464,6 +484,7 @@ impl<'a> PluginLoader<'a> {
         layout_dir: Option<PathBuf>,
         default_mode: InputMode,
         keybinds: Keybinds,
+        plugin_limits: PluginLimits,
     ) -> Result<Self> {
         let err_context = || "Failed to find existing plugin";
         let running_plugin = {
@@ -501,6 +522,7 @@ impl<'a> PluginLoader<'a> {
             layout_dir,
             default_mode,
             keybinds,
+            plugin_limits,
         )
     }
     pub fn load_module_from_memory(&mut self) -> Result<Module> {
@@ -510,12 +532,25 @@ impl<'a> PluginLoader<'a> {
             self.senders,
             self.plugin_id
         );
-        let module = self
+        let (module, module_artifact_path) = self
             .plugin_cache
             .lock()
             .unwrap()
             .remove(&self.plugin_path)
             .ok_or(anyhow!("Plugin is not stored in memory"))?;
+        // installed plugins might have been updated or removed since they were stored in memory,
+        // so they must still match their lockfile and the module must have been compiled from
+        // their current bytes
+        if PluginLockfile::from_plugin_dir(self.plugin_dir).is_installed(&self.plugin.path) {
+            let (_wasm_bytes, cached_path) = self.plugin_bytes_and_cache_path()?;
+            if cached_path != module_artifact_path {
+                return Err(anyhow!(
+                    "Plugin changed since it was stored in memory: {}",
+                    self.plugin_path.display()
+                ));
+            }
+        }
+        self.module_artifact_path = Some(module_artifact_path);
         display_loading_stage!(
             indicate_loading_plugin_from_memory_success,
             self.loading_indication,
@@ -540,6 +575,7 @@ impl<'a> PluginLoader<'a> {
         let (_wasm_bytes, cached_path) = self.plugin_bytes_and_cache_path()?;
         let timer = std::time::Instant::now();
         let module = unsafe { Module::deserialize_file(&self.engine, &cached_path)? };
+        self.module_artifact_path = Some(cached_path);
         log::info!(
             "Loaded plugin '{}' from cache folder at '{}' in {:?}",
             self.plugin_path.display(),
@@ -588,6 +624,7 @@ impl<'a> PluginLoader<'a> {
                 Ok(m)
             })
             .with_context(err_context)?;
+        self.module_artifact_path = Some(cached_path);
         Ok(module)
     }
     pub fn create_plugin_environment(
@@ -596,11 +633,13 @@ impl<'a> PluginLoader<'a> {
     ) -> Result<(Store<PluginEnv>, Instance)> {
         let (store, instance) = self.create_plugin_instance_env(&module)?;
         // Only do an insert when everything went well!
-        let cloned_plugin = self.plugin.clone();
-        self.plugin_cache
-            .lock()
-            .unwrap()
-            .insert(cloned_plugin.path, module);
+        if let Some(module_artifact_path) = self.module_artifact_path.clone() {
+            let cloned_plugin = self.plugin.clone();
+            self.plugin_cache
+                .lock()
+                .unwrap()
+                .insert(cloned_plugin.path, (module, module_artifact_path));
+        }
         Ok((store, instance))
     }
     pub fn create_plugin_instance_and_wasi_env_for_worker(
@@ -617,8 +656,8 @@ impl<'a> PluginLoader<'a> {
             .lock()
             .unwrap()
             .get(&self.plugin.path)
-            .with_context(err_context)?
-            .clone();
+            .map(|(module, _module_artifact_path)| module.clone())
+            .with_context(err_context)?;
         let (store, instance) = self.create_plugin_instance_env(&module)?;
         Ok((store, instance))
     }
@@ -756,8 +795,9 @@ impl<'a> PluginLoader<'a> {
                     self.default_shell.clone(),
                     self.default_layout.clone(),
                     self.layout_dir.clone(),
-                    self.default_mode,
+                    self.default_mode.clone(),
                     self.keybinds.clone(),
+                    self.plugin_limits,
                 )?;
                 plugin_loader_for_client
                     .load_module_from_memory()
@@ -787,6 +827,9 @@ impl<'a> PluginLoader<'a> {
                 }
                 // The plugins blob as stored on the filesystem
                 let wasm_bytes = self.plugin.resolve_wasm_bytes(&self.plugin_dir)?;
+                // plugins installed with `zellij plugin install` must still match their lockfile
+                PluginLockfile::from_plugin_dir(self.plugin_dir)
+                    .verify(&self.plugin.path, &wasm_bytes)?;
                 let hash: String = PortableHash::default()
                     .hash256(&wasm_bytes)
                     .iter()
@@ -798,6 +841,46 @@ impl<'a> PluginLoader<'a> {
             },
         }
     }
+    // the folders mounted under /host, narrowed down to the scope of the HostFilesystemAccess
+    // permission if one was granted - this happens when the plugin loads, so a scope granted
+    // while the plugin is running applies from its next load
+    fn host_dirs(&self) -> Vec<(String, PathBuf)> {
+        let permission_scopes = if self.plugin.is_builtin() {
+            PermissionScopes::default()
+        } else {
+            PermissionCache::from_path_or_default(None)
+                .get_permission_scopes(&self.plugin.location.to_string())
+        };
+        match permission_scopes.scope(&PermissionType::HostFilesystemAccess) {
+            Some(scope) => scope
+                .iter()
+                .filter_map(|scope_entry| {
+                    let relative_path = host_relative_path(scope_entry);
+                    if relative_path
+                        .components()
+                        .any(|component| component == Component::ParentDir)
+                    {
+                        log::error!(
+                            "Ignoring host filesystem scope outside of /host: {}",
+                            scope_entry
+                        );
+                        return None;
+                    }
+                    if relative_path.as_os_str().is_empty() {
+                        return Some(("/host".to_owned(), self.zellij_cwd.clone()));
+                    }
+                    Some((
+                        Path::new("/host")
+                            .join(&relative_path)
+                            .display()
+                            .to_string(),
+                        self.zellij_cwd.join(&relative_path),
+                    ))
+                })
+                .collect(),
+            None => vec![("/host".to_owned(), self.zellij_cwd.clone())],
+        }
+    }
     fn create_plugin_instance_env(&self, module: &Module) -> Result<(Store<PluginEnv>, Instance)> {
         let err_context = || {
             format!(
@@ -805,11 +888,11 @@ impl<'a> PluginLoader<'a> {
                 self.plugin_id
             )
         };
-        let dirs = vec![
-            ("/host".to_owned(), self.zellij_cwd.clone()),
+        let mut dirs = self.host_dirs();
+        dirs.append(&mut vec![
             ("/data".to_owned(), self.plugin_own_data_dir.clone()),
             ("/tmp".to_owned(), ZELLIJ_TMP_DIR.clone()),
-        ];
+        ]);
         let dirs = dirs.into_iter().filter(|(_dir_name, dir)| {
             // note that this does not protect against TOCTOU errors
             // eg. if one or more of these folders existed at the time of check but was deleted
@@ -844,6 +927,7 @@ impl<'a> PluginLoader<'a> {
             client_id: self.client_id,
             plugin: mut_plugin,
             permissions: Arc::new(Mutex::new(None)),
+            permission_scopes: Arc::new(Mutex::new(PermissionScopes::default())),
             senders: self.senders.clone(),
             wasi_ctx,
             plugin_own_data_dir: self.plugin_own_data_dir.clone(),
@@ -862,8 +946,15 @@ impl<'a> PluginLoader<'a> {
             keybinds: self.keybinds.clone(),
             stdin_pipe,
             stdout_pipe,
+            limiter: PluginLimiter::new(
+                self.plugin_limits
+                    .for_plugin(&self.plugin.userspace_configuration),
+                self.plugin_id,
+                self.senders.clone(),
+            ),
         };
         let mut store = Store::new(&self.engine, plugin_env);
+        limit_store(&mut store, |plugin_env| &mut plugin_env.limiter);
 
         let mut linker = Linker::new(&self.engine);
         wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |plugin_env: &mut PluginEnv| {
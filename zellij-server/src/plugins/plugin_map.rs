@@ -1,3 +1,4 @@
+use crate::plugins::plugin_limiter::PluginLimiter;
 use crate::plugins::plugin_worker::MessageToWorker;
 use crate::plugins::PluginId;
 use bytes::Bytes;
@@ -27,7 +28,10 @@ use zellij_utils::{
     input::plugins::PluginConfig,
     ipc::ClientAttributes,
 };
-use zellij_utils::{data::PermissionType, errors::prelude::*};
+use zellij_utils::{
+    data::{PermissionScopes, PermissionType},
+    errors::prelude::*,
+};
 
 // the idea here is to provide atomicity when adding/removing plugins from the map (eg. when a new
 // client connects) but to also allow updates/renders not to block each other
@@ -271,6 +275,7 @@ pub struct PluginEnv {
     pub plugin_id: PluginId,
     pub plugin: PluginConfig,
     pub permissions: Arc<Mutex<Option<HashSet<PermissionType>>>>,
+    pub permission_scopes: Arc<Mutex<PermissionScopes>>,
     pub senders: ThreadSenders,
     pub wasi_ctx: WasiP1Ctx,
     pub tab_index: Option<usize>,
@@ -291,6 +296,7 @@ pub struct PluginEnv {
     pub stdin_pipe: Arc<Mutex<VecDeque<u8>>>,
     pub stdout_pipe: Arc<Mutex<VecDeque<u8>>>,
     pub keybinds: Keybinds,
+    pub limiter: PluginLimiter,
 }
 
 #[derive(Clone)]
@@ -374,8 +380,13 @@ impl PluginEnv {
         )
     }
 
-    pub fn set_permissions(&mut self, permissions: HashSet<PermissionType>) {
+    pub fn set_permissions(
+        &mut self,
+        permissions: HashSet<PermissionType>,
+        permission_scopes: PermissionScopes,
+    ) {
         self.permissions.lock().unwrap().replace(permissions);
+        *self.permission_scopes.lock().unwrap() = permission_scopes;
     }
 }
 
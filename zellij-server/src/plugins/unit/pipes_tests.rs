@@ -0,0 +1,110 @@
+use super::*;
+
+const SOURCE_PLUGIN_ID: PluginId = 1;
+const SOURCE_CLIENT_ID: ClientId = 2;
+const DESTINATION_PLUGIN_ID: PluginId = 3;
+
+fn pending_request(request_id: &str) -> PendingPluginRequests {
+    let mut pending_plugin_requests = PendingPluginRequests::default();
+    pending_plugin_requests.add(
+        request_id.to_owned(),
+        "get_status".to_owned(),
+        SOURCE_PLUGIN_ID,
+        SOURCE_CLIENT_ID,
+        DESTINATION_PLUGIN_ID,
+    );
+    pending_plugin_requests
+}
+
+fn response_to_source(
+    request_id: &str,
+    result: Result<Option<String>, PluginRequestError>,
+) -> (Option<PluginId>, Option<ClientId>, Event) {
+    (
+        Some(SOURCE_PLUGIN_ID),
+        Some(SOURCE_CLIENT_ID),
+        Event::PluginResponse(PluginResponse {
+            request_id: request_id.to_owned(),
+            name: "get_status".to_owned(),
+            result,
+        }),
+    )
+}
+
+#[test]
+fn reply_is_matched_to_its_request_by_id() {
+    let mut pending_plugin_requests = pending_request("request-1");
+    pending_plugin_requests.add(
+        "request-2".to_owned(),
+        "get_status".to_owned(),
+        SOURCE_PLUGIN_ID,
+        SOURCE_CLIENT_ID,
+        DESTINATION_PLUGIN_ID,
+    );
+    assert_eq!(
+        pending_plugin_requests.respond("request-1", SOURCE_PLUGIN_ID, None),
+        None,
+        "only the plugin the request was sent to can answer it"
+    );
+    assert_eq!(
+        pending_plugin_requests.respond("request-2", DESTINATION_PLUGIN_ID, Some("ok".to_owned())),
+        Some(response_to_source("request-2", Ok(Some("ok".to_owned()))))
+    );
+    assert_eq!(
+        pending_plugin_requests.respond("request-1", DESTINATION_PLUGIN_ID, None),
+        Some(response_to_source("request-1", Ok(None)))
+    );
+}
+
+#[test]
+fn request_that_times_out_gets_an_error_response() {
+    let mut pending_plugin_requests = pending_request("request-1");
+    assert_eq!(
+        pending_plugin_requests.time_out("request-1"),
+        Some(response_to_source(
+            "request-1",
+            Err(PluginRequestError::Timeout)
+        ))
+    );
+}
+
+#[test]
+fn late_reply_after_timeout_is_dropped() {
+    let mut pending_plugin_requests = pending_request("request-1");
+    pending_plugin_requests.time_out("request-1");
+    assert_eq!(
+        pending_plugin_requests.respond("request-1", DESTINATION_PLUGIN_ID, Some("ok".to_owned())),
+        None
+    );
+    assert_eq!(
+        pending_plugin_requests.time_out("request-1"),
+        None,
+        "answered requests do not time out"
+    );
+}
+
+#[test]
+fn pending_requests_are_cleared_when_plugin_unloads() {
+    let mut pending_plugin_requests = pending_request("to-unloaded-plugin");
+    pending_plugin_requests.add(
+        "from-unloaded-plugin".to_owned(),
+        "get_status".to_owned(),
+        DESTINATION_PLUGIN_ID,
+        SOURCE_CLIENT_ID,
+        SOURCE_PLUGIN_ID,
+    );
+    assert_eq!(
+        pending_plugin_requests.unload_plugin(&DESTINATION_PLUGIN_ID),
+        vec![response_to_source(
+            "to-unloaded-plugin",
+            Err(PluginRequestError::NoSuchPlugin)
+        )],
+        "requests to the unloaded plugin are failed"
+    );
+    assert_eq!(
+        pending_plugin_requests.respond("from-unloaded-plugin", SOURCE_PLUGIN_ID, None),
+        None,
+        "requests of the unloaded plugin are dropped"
+    );
+    assert_eq!(pending_plugin_requests.time_out("to-unloaded-plugin"), None);
+}
@@ -0,0 +1,146 @@
+use super::*;
+use crate::screen::ScreenInstruction;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use wasmtime::{Config, Instance, Module};
+use zellij_utils::channels::{self, ChannelWithContext, Receiver, SenderWithContext};
+use zellij_utils::errors::ErrorContext;
+
+const PLUGIN_ID: PluginId = 1;
+
+// a plugin that spins forever when `update` is called and grows its memory when `render` is
+// called, `load` returns right away
+const FIXTURE_PLUGIN: &str = r#"
+    (module
+        (memory 1)
+        (func (export "load"))
+        (func (export "update")
+            (loop $spin (br $spin)))
+        (func (export "render") (param $pages i32)
+            (drop (memory.grow (local.get $pages)))))
+"#;
+
+struct LimitedPlugin {
+    engine: Engine,
+    store: Store<PluginLimiter>,
+    instance: Instance,
+    screen_receiver: Receiver<(ScreenInstruction, ErrorContext)>,
+}
+
+impl LimitedPlugin {
+    fn new(limits: PluginLimits) -> Self {
+        let (to_screen, screen_receiver): ChannelWithContext<ScreenInstruction> =
+            channels::unbounded();
+        let senders = ThreadSenders {
+            to_screen: Some(SenderWithContext::new(to_screen)),
+            should_silently_fail: true,
+            ..Default::default()
+        };
+        let engine = Engine::new(Config::new().epoch_interruption(true)).unwrap();
+        let module = Module::new(&engine, FIXTURE_PLUGIN).unwrap();
+        let mut store = Store::new(&engine, PluginLimiter::new(limits, PLUGIN_ID, senders));
+        limit_store(&mut store, |limiter| limiter);
+        let instance = Instance::new(&mut store, &module, &[]).unwrap();
+        LimitedPlugin {
+            engine,
+            store,
+            instance,
+            screen_receiver,
+        }
+    }
+    fn load(&mut self) -> Result<()> {
+        self.instance
+            .get_typed_func::<(), ()>(&mut self.store, "load")?
+            .call(&mut self.store, ())
+    }
+    fn update(&mut self) -> Result<()> {
+        // advance the epoch until the call returns rather than in fixed intervals, so that the
+        // outcome does not depend on timing
+        let call_returned = Arc::new(AtomicBool::new(false));
+        let ticker = thread::spawn({
+            let engine = self.engine.clone();
+            let call_returned = call_returned.clone();
+            move || {
+                while !call_returned.load(Ordering::SeqCst) {
+                    engine.increment_epoch();
+                    thread::yield_now();
+                }
+            }
+        });
+        let result = self
+            .instance
+            .get_typed_func::<(), ()>(&mut self.store, "update")?
+            .call(&mut self.store, ());
+        call_returned.store(true, Ordering::SeqCst);
+        ticker.join().unwrap();
+        result
+    }
+    fn render(&mut self, pages: i32) -> Result<()> {
+        self.instance
+            .get_typed_func::<i32, ()>(&mut self.store, "render")?
+            .call(&mut self.store, pages)
+    }
+    fn was_shown_as_errored(&self) -> bool {
+        self.screen_receiver.try_iter().any(|(instruction, _)| {
+            matches!(
+                instruction,
+                ScreenInstruction::UpdatePluginLoadingStage(plugin_id, loading_indication)
+                    if plugin_id == PLUGIN_ID && loading_indication.is_error()
+            )
+        })
+    }
+}
+
+#[test]
+fn plugin_is_stopped_and_shown_as_errored_when_growing_beyond_its_memory_limit() {
+    let mut plugin = LimitedPlugin::new(PluginLimits {
+        memory: Some(1024 * 1024),
+        call_timeout: None,
+    });
+    plugin.render(1).unwrap();
+    assert!(!plugin.was_shown_as_errored());
+    let error = plugin.render(16).unwrap_err();
+    assert!(
+        format!("{:?}", error).contains("memory limit of 1MB"),
+        "growing beyond the limit traps: {:?}",
+        error
+    );
+    assert!(plugin.was_shown_as_errored());
+    let error = plugin.load().unwrap_err();
+    assert!(
+        format!("{:?}", error).contains("memory limit of 1MB"),
+        "calls into a stopped plugin fail: {:?}",
+        error
+    );
+}
+
+#[test]
+fn plugin_is_stopped_and_shown_as_errored_when_a_call_exceeds_its_timeout() {
+    let mut plugin = LimitedPlugin::new(PluginLimits {
+        memory: None,
+        call_timeout: Some(Duration::from_nanos(1)),
+    });
+    plugin.load().unwrap();
+    assert!(
+        !plugin.was_shown_as_errored(),
+        "calls that do not reach an epoch deadline are not interrupted"
+    );
+    let error = plugin.update().unwrap_err();
+    assert!(
+        format!("{:?}", error).contains("call timeout"),
+        "a call spinning past the timeout traps: {:?}",
+        error
+    );
+    assert!(plugin.was_shown_as_errored());
+    assert!(plugin.load().is_err(), "calls into a stopped plugin fail");
+}
+
+#[test]
+fn plugin_without_limits_is_never_stopped() {
+    let mut plugin = LimitedPlugin::new(PluginLimits::default());
+    plugin.render(64).unwrap();
+    plugin.load().unwrap();
+    assert!(!plugin.was_shown_as_errored());
+}
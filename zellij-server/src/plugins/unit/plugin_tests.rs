@@ -16,7 +16,7 @@ use zellij_utils::input::layout::{
     Layout, PluginAlias, PluginUserConfiguration, RunPlugin, RunPluginLocation, RunPluginOrAlias,
 };
 use zellij_utils::input::permission::PermissionCache;
-use zellij_utils::input::plugins::PluginAliases;
+use zellij_utils::input::plugins::{PluginAliases, PluginLimits};
 use zellij_utils::ipc::ClientAttributes;
 use zellij_utils::lazy_static::lazy_static;
 use zellij_utils::pane_size::Size;
@@ -116,6 +116,7 @@ macro_rules! grant_permissions_and_log_actions_in_thread {
                                         0,
                                         Some($client_id),
                                         plugin_permission.permissions,
+                                        plugin_permission.scopes,
                                         PermissionStatus::Granted,
                                         Some(cache_path.clone()),
                                     ),
@@ -126,6 +127,7 @@ macro_rules! grant_permissions_and_log_actions_in_thread {
                                         0,
                                         Some($client_id),
                                         plugin_permission.permissions,
+                                        plugin_permission.scopes,
                                         PermissionStatus::Denied,
                                         Some(cache_path.clone()),
                                     ),
@@ -169,6 +171,7 @@ macro_rules! deny_permissions_and_log_actions_in_thread {
                                     0,
                                     Some($client_id),
                                     plugin_permission.permissions,
+                                    plugin_permission.scopes,
                                     PermissionStatus::Denied,
                                     Some(cache_path.clone()),
                                 ),
@@ -213,6 +216,7 @@ macro_rules! grant_permissions_and_log_actions_in_thread_naked_variant {
                                         0,
                                         Some($client_id),
                                         plugin_permission.permissions,
+                                        plugin_permission.scopes,
                                         PermissionStatus::Granted,
                                         Some(cache_path.clone()),
                                     ),
@@ -223,6 +227,7 @@ macro_rules! grant_permissions_and_log_actions_in_thread_naked_variant {
                                         0,
                                         Some($client_id),
                                         plugin_permission.permissions,
+                                        plugin_permission.scopes,
                                         PermissionStatus::Denied,
                                         Some(cache_path.clone()),
                                     ),
@@ -313,6 +318,7 @@ fn create_plugin_thread(
                 Box::new(plugin_aliases),
                 InputMode::Normal,
                 Keybinds::default(),
+                PluginLimits::default(),
             )
             .expect("TEST")
         })
@@ -394,6 +400,7 @@ fn create_plugin_thread_with_server_receiver(
                 Box::new(PluginAliases::default()),
                 InputMode::Normal,
                 Keybinds::default(),
+                PluginLimits::default(),
             )
             .expect("TEST");
         })
@@ -481,6 +488,7 @@ fn create_plugin_thread_with_pty_receiver(
                 Box::new(PluginAliases::default()),
                 InputMode::Normal,
                 Keybinds::default(),
+                PluginLimits::default(),
             )
             .expect("TEST")
         })
@@ -563,6 +571,7 @@ fn create_plugin_thread_with_background_jobs_receiver(
                 Box::new(PluginAliases::default()),
                 InputMode::Normal,
                 Keybinds::default(),
+                PluginLimits::default(),
             )
             .expect("TEST")
         })
@@ -3794,6 +3803,104 @@ pub fn start_or_reload_plugin() {
     assert_snapshot!(format!("{:#?}", new_tab_event));
 }
 
+#[test]
+#[ignore]
+pub fn plugin_reloaded_on_change_keeps_its_state() {
+    let temp_folder = tempdir().unwrap(); // placed explicitly in the test scope because its
+                                          // destructor removes the directory
+    let plugin_host_folder = PathBuf::from(temp_folder.path());
+    let cache_path = plugin_host_folder.join("permissions_test.kdl");
+    // the plugin watches the zellij cwd, so its file is kept elsewhere
+    let plugin_folder = tempdir().unwrap();
+    let plugin_path = plugin_folder.path().join("plugin.wasm");
+    std::fs::copy(&*PLUGIN_FIXTURE, &plugin_path).unwrap();
+    let (plugin_thread_sender, screen_receiver, teardown) =
+        create_plugin_thread(Some(plugin_host_folder));
+    let plugin_should_float = Some(false);
+    let plugin_title = Some("test_plugin".to_owned());
+    let mut configuration = BTreeMap::new();
+    configuration.insert("reload_on_change".to_owned(), "true".to_owned());
+    let run_plugin = RunPluginOrAlias::RunPlugin(RunPlugin {
+        _allow_exec_host_cmd: false,
+        location: RunPluginLocation::File(plugin_path.clone()),
+        configuration: PluginUserConfiguration::new(configuration),
+        ..Default::default()
+    });
+    let tab_index = 1;
+    let client_id = 1;
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let screen_thread = std::thread::Builder::new()
+        .name("fake_screen_thread".to_string())
+        .spawn({
+            let plugin_thread_sender = plugin_thread_sender.clone();
+            move || loop {
+                let (event, _err_ctx) = screen_receiver
+                    .recv_timeout(std::time::Duration::from_secs(30))
+                    .expect("plugin was not reloaded with its state");
+                match event {
+                    ScreenInstruction::RequestPluginPermissions(plugin_id, plugin_permission) => {
+                        let _ =
+                            plugin_thread_sender.send(PluginInstruction::PermissionRequestResult(
+                                plugin_id,
+                                Some(client_id),
+                                plugin_permission.permissions,
+                                plugin_permission.scopes,
+                                PermissionStatus::Granted,
+                                Some(cache_path.clone()),
+                            ));
+                    },
+                    ScreenInstruction::PluginBytes(plugin_render_assets) => {
+                        for plugin_render_asset in plugin_render_assets {
+                            let plugin_bytes =
+                                String::from_utf8_lossy(plugin_render_asset.bytes.as_slice())
+                                    .to_string();
+                            if plugin_bytes.contains("Restored state") {
+                                return plugin_bytes;
+                            }
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        })
+        .unwrap();
+
+    let _ = plugin_thread_sender.send(PluginInstruction::AddClient(client_id));
+    let _ = plugin_thread_sender.send(PluginInstruction::Load(
+        plugin_should_float,
+        false,
+        plugin_title,
+        run_plugin,
+        tab_index,
+        None,
+        client_id,
+        size,
+        None,
+        false,
+    ));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let _ = plugin_thread_sender.send(PluginInstruction::Update(vec![(
+        None,
+        Some(client_id),
+        Event::InputReceived,
+    )])); // will be cached and sent to the plugin once it's loaded
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    // rebuilding the plugin replaces its file
+    std::fs::copy(&*PLUGIN_FIXTURE, &plugin_path).unwrap();
+    let plugin_bytes = screen_thread.join().unwrap();
+    teardown();
+    // besides InputReceived the plugin gets eg. the permission request result
+    assert!(
+        plugin_bytes.contains("Restored state: \"received ")
+            && !plugin_bytes.contains("received 0 events"),
+        "reloaded plugin got the state it saved before the reload: {}",
+        plugin_bytes
+    );
+}
+
 #[test]
 #[ignore]
 pub fn quit_zellij_plugin_command() {
@@ -5490,6 +5597,7 @@ pub fn granted_permission_request_result() {
                                 0,
                                 Some(client_id),
                                 plugin_permission.permissions,
+                                plugin_permission.scopes,
                                 PermissionStatus::Granted,
                                 Some(cache_path.clone()),
                             ));
@@ -5539,6 +5647,112 @@ pub fn granted_permission_request_result() {
     assert_snapshot!(format!("{:#?}", permissions));
 }
 
+#[test]
+#[ignore]
+pub fn revoked_permissions_are_taken_away_from_running_plugin() {
+    let temp_folder = tempdir().unwrap();
+    let plugin_host_folder = PathBuf::from(temp_folder.path());
+    let cache_path = plugin_host_folder.join("permissions_test.kdl");
+
+    let (plugin_thread_sender, server_receiver, screen_receiver, teardown) =
+        create_plugin_thread_with_server_receiver(Some(plugin_host_folder));
+    let plugin_should_float = Some(false);
+    let plugin_title = Some("test_plugin".to_owned());
+    let run_plugin = RunPluginOrAlias::RunPlugin(RunPlugin {
+        _allow_exec_host_cmd: false,
+        location: RunPluginLocation::File(PathBuf::from(&*PLUGIN_FIXTURE)),
+        configuration: Default::default(),
+        ..Default::default()
+    });
+    let tab_index = 1;
+    let client_id = 1;
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+
+    // here we create a fake screen thread that grants the plugin's permission request along with
+    // the permissions to intercept input and read pane contents, and then waits for the plugin to
+    // stop reading pane contents once those are revoked
+    let screen_thread = std::thread::Builder::new()
+        .name("fake_screen_thread".to_string())
+        .spawn({
+            let cache_path = cache_path.clone();
+            let plugin_thread_sender = plugin_thread_sender.clone();
+            move || loop {
+                let (event, _err_ctx) = screen_receiver
+                    .recv()
+                    .expect("failed to receive event on channel");
+                match event {
+                    ScreenInstruction::RequestPluginPermissions(_, plugin_permission) => {
+                        let mut permissions = plugin_permission.permissions;
+                        permissions.push(PermissionType::InterceptInput);
+                        permissions.push(PermissionType::ReadPaneContents);
+                        let _ =
+                            plugin_thread_sender.send(PluginInstruction::PermissionRequestResult(
+                                0,
+                                Some(client_id),
+                                permissions,
+                                plugin_permission.scopes,
+                                PermissionStatus::Granted,
+                                Some(cache_path.clone()),
+                            ));
+                    },
+                    ScreenInstruction::UnsubscribeFromPaneOutput(..) | ScreenInstruction::Exit => {
+                        break;
+                    },
+                    _ => {},
+                }
+            }
+        })
+        .unwrap();
+
+    let _ = plugin_thread_sender.send(PluginInstruction::AddClient(client_id));
+    let _ = plugin_thread_sender.send(PluginInstruction::Load(
+        plugin_should_float,
+        false,
+        plugin_title,
+        run_plugin.clone(),
+        tab_index,
+        None,
+        client_id,
+        size,
+        None,
+        false,
+    ));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let _ = plugin_thread_sender.send(PluginInstruction::Update(vec![(
+        None,
+        Some(client_id),
+        Event::Key(KeyWithModifier::new(BareKey::Char('1')).with_ctrl_modifier()), // this triggers the enent in the fixture plugin
+    )]));
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    // the screen thread only stops once the plugin unsubscribed from pane output
+    let mut permission_cache = PermissionCache::from_path_or_default(Some(cache_path.clone()));
+    permission_cache.revoke(
+        &PathBuf::from(&*PLUGIN_FIXTURE).display().to_string(),
+        &[
+            PermissionType::InterceptInput,
+            PermissionType::ReadPaneContents,
+        ],
+    );
+    permission_cache.write_to_file().unwrap();
+    let _ = plugin_thread_sender.send(PluginInstruction::PermissionCacheChanged(Some(
+        cache_path.clone(),
+    )));
+    screen_thread.join().unwrap();
+    teardown();
+
+    let intercepts_were_cleared = server_receiver.try_iter().any(|(instruction, _)| {
+        matches!(
+            instruction,
+            ServerInstruction::InterceptKeys(0, keys, input_modes)
+                if keys.is_empty() && input_modes.is_empty()
+        )
+    });
+    assert!(intercepts_were_cleared);
+}
+
 #[test]
 #[ignore]
 pub fn denied_permission_request_result() {
@@ -5581,6 +5795,7 @@ pub fn denied_permission_request_result() {
                                 0,
                                 Some(client_id),
                                 plugin_permission.permissions,
+                                plugin_permission.scopes,
                                 PermissionStatus::Denied,
                                 Some(cache_path.clone()),
                             ));
@@ -6984,3 +7199,26 @@ pub fn rerun_command_pane_plugin_command() {
         .clone();
     assert_snapshot!(format!("{:#?}", rerun_command_pane_event));
 }
+
+#[test]
+pub fn crashed_plugin_stops_intercepting_keys() {
+    let (to_screen, _screen_receiver): ChannelWithContext<ScreenInstruction> =
+        channels::unbounded();
+    let (to_server, server_receiver): ChannelWithContext<ServerInstruction> = channels::unbounded();
+    let senders = crate::thread_bus::ThreadSenders {
+        to_screen: Some(SenderWithContext::new(to_screen)),
+        to_server: Some(SenderWithContext::new(to_server)),
+        should_silently_fail: true,
+        ..Default::default()
+    };
+    let plugin_id = 1;
+    crate::plugins::wasm_bridge::handle_plugin_crash(plugin_id, "Panic!".to_owned(), senders);
+    let intercepts_were_cleared = server_receiver.try_iter().any(|(instruction, _)| {
+        matches!(
+            instruction,
+            ServerInstruction::InterceptKeys(1, keys, input_modes)
+                if keys.is_empty() && input_modes.is_empty()
+        )
+    });
+    assert!(intercepts_were_cleared);
+}
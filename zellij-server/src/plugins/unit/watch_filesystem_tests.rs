@@ -0,0 +1,150 @@
+use super::*;
+use tempfile::tempdir;
+use zellij_utils::channels::{self, ChannelWithContext, Receiver, SenderWithContext};
+use zellij_utils::errors::ErrorContext;
+
+fn plugin_senders() -> (ThreadSenders, Receiver<(PluginInstruction, ErrorContext)>) {
+    let (to_plugin, plugin_receiver): ChannelWithContext<PluginInstruction> = channels::unbounded();
+    let senders = ThreadSenders {
+        to_plugin: Some(SenderWithContext::new(to_plugin)),
+        should_silently_fail: true,
+        ..Default::default()
+    };
+    (senders, plugin_receiver)
+}
+
+fn pty_senders() -> (ThreadSenders, Receiver<(PtyInstruction, ErrorContext)>) {
+    let (to_pty, pty_receiver): ChannelWithContext<PtyInstruction> = channels::unbounded();
+    let senders = ThreadSenders {
+        to_pty: Some(SenderWithContext::new(to_pty)),
+        should_silently_fail: true,
+        ..Default::default()
+    };
+    (senders, pty_receiver)
+}
+
+const TERMINAL_ID: u32 = 1;
+
+fn assert_files_changed_reported(pty_receiver: &Receiver<(PtyInstruction, ErrorContext)>) {
+    let (instruction, _) = pty_receiver
+        .recv_timeout(Duration::from_secs(10))
+        .expect("watched files change was not reported");
+    assert!(
+        matches!(
+            instruction,
+            PtyInstruction::WatchedFilesChanged(TERMINAL_ID)
+        ),
+        "unexpected instruction: {:?}",
+        instruction
+    );
+}
+
+#[test]
+fn changing_a_plugin_file_asks_to_reload_it() {
+    let plugin_folder = tempdir().unwrap();
+    let plugin_path = plugin_folder.path().join("plugin.wasm");
+    let other_path = plugin_folder.path().join("other-plugin.wasm");
+    std::fs::write(&plugin_path, "old plugin").unwrap();
+    let (senders, plugin_receiver) = plugin_senders();
+    let _watcher = watch_plugin_file(senders, &plugin_path).unwrap();
+
+    std::fs::write(&other_path, "unrelated plugin").unwrap();
+    std::fs::write(&plugin_path, "new plugin").unwrap();
+    let (instruction, _) = plugin_receiver
+        .recv_timeout(Duration::from_secs(10))
+        .expect("plugin file change was not reported");
+    assert!(
+        matches!(&instruction, PluginInstruction::PluginFileChanged(path) if path == &plugin_path),
+        "unexpected instruction: {:?}",
+        instruction
+    );
+    assert!(
+        plugin_receiver.try_recv().is_err(),
+        "changes to other files in the folder are not reported"
+    );
+}
+
+#[test]
+fn changing_a_watched_file_reruns_the_command() {
+    let cwd = tempdir().unwrap();
+    let (senders, pty_receiver) = pty_senders();
+    let debouncer =
+        watch_command_pane_files(senders, TERMINAL_ID, cwd.path(), &["*.rs".to_owned()]).unwrap();
+    let mut watcher = CommandPaneWatcher::new(debouncer, false);
+    assert!(!watcher.command_exited(), "nothing changed while running");
+
+    std::fs::write(cwd.path().join("main.rs"), "fn main() {}").unwrap();
+    assert_files_changed_reported(&pty_receiver);
+    assert_eq!(watcher.files_changed(), WatchedFilesChange::RerunNow);
+}
+
+#[test]
+fn changes_while_the_command_runs_queue_one_rerun() {
+    let cwd = tempdir().unwrap();
+    let (senders, _pty_receiver) = pty_senders();
+    let debouncer =
+        watch_command_pane_files(senders, TERMINAL_ID, cwd.path(), &["*.rs".to_owned()]).unwrap();
+    let mut watcher = CommandPaneWatcher::new(debouncer, false);
+    assert_eq!(watcher.files_changed(), WatchedFilesChange::RerunOnExit);
+    assert_eq!(watcher.files_changed(), WatchedFilesChange::AlreadyQueued);
+    assert!(watcher.command_exited(), "re-run once the command exits");
+
+    watcher.command_started();
+    assert!(!watcher.command_exited(), "the queued re-run happened once");
+}
+
+#[test]
+fn changes_while_the_command_runs_kill_it_with_kill_on_change() {
+    let cwd = tempdir().unwrap();
+    let (senders, _pty_receiver) = pty_senders();
+    let debouncer =
+        watch_command_pane_files(senders, TERMINAL_ID, cwd.path(), &["*.rs".to_owned()]).unwrap();
+    let mut watcher = CommandPaneWatcher::new(debouncer, true);
+    assert_eq!(
+        watcher.files_changed(),
+        WatchedFilesChange::KillAndRerunOnExit
+    );
+    assert_eq!(
+        watcher.files_changed(),
+        WatchedFilesChange::AlreadyQueued,
+        "the command is killed only once"
+    );
+    assert!(
+        watcher.command_exited(),
+        "re-run once the killed command exits"
+    );
+}
+
+#[test]
+fn only_files_matching_the_globs_are_reported() {
+    let cwd = tempdir().unwrap();
+    std::fs::create_dir(cwd.path().join("src")).unwrap();
+    let (senders, pty_receiver) = pty_senders();
+    let _debouncer =
+        watch_command_pane_files(senders, TERMINAL_ID, cwd.path(), &["src/*.rs".to_owned()])
+            .unwrap();
+
+    std::fs::write(cwd.path().join("main.rs"), "outside of src").unwrap();
+    std::fs::write(cwd.path().join("src").join("notes.txt"), "not rust").unwrap();
+    assert!(
+        pty_receiver.recv_timeout(Duration::from_secs(2)).is_err(),
+        "changes to files that do not match are not reported"
+    );
+
+    std::fs::write(cwd.path().join("src").join("main.rs"), "fn main() {}").unwrap();
+    assert_files_changed_reported(&pty_receiver);
+}
+
+#[test]
+fn globs_match_files_in_a_symlinked_cwd() {
+    let real_cwd = tempdir().unwrap();
+    let links = tempdir().unwrap();
+    let cwd = links.path().join("project");
+    std::os::unix::fs::symlink(real_cwd.path(), &cwd).unwrap();
+    let (senders, pty_receiver) = pty_senders();
+    let _debouncer =
+        watch_command_pane_files(senders, TERMINAL_ID, &cwd, &["*.rs".to_owned()]).unwrap();
+
+    std::fs::write(cwd.join("main.rs"), "fn main() {}").unwrap();
+    assert_files_changed_reported(&pty_receiver);
+}
@@ -1,28 +1,36 @@
 use super::{PluginId, PluginInstruction};
 use crate::plugins::pipes::{
-    apply_pipe_message_to_plugin, pipes_to_block_or_unblock, PendingPipes, PipeStateChange,
+    apply_pipe_message_to_plugin, pipes_to_block_or_unblock, PendingPipes, PendingPluginRequests,
+    PipeStateChange,
 };
 use crate::plugins::plugin_loader::PluginLoader;
 use crate::plugins::plugin_map::{AtomicEvent, PluginEnv, PluginMap, RunningPlugin, Subscriptions};
 use crate::plugins::plugin_worker::MessageToWorker;
-use crate::plugins::watch_filesystem::watch_filesystem;
-use crate::plugins::zellij_exports::{wasi_read_string, wasi_write_object};
+use crate::plugins::watch_filesystem::{
+    watch_filesystem, watch_permission_cache, watch_plugin_file, watch_plugin_lockfile,
+};
+use crate::plugins::zellij_exports::{wasi_read_bytes, wasi_read_string, wasi_write_object};
 use highway::{HighwayHash, PortableHash};
 use log::info;
 use std::{
     collections::{HashMap, HashSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{Arc, Mutex},
 };
 use wasmtime::{Engine, Module};
 use zellij_utils::async_channel::Sender;
 use zellij_utils::async_std::task::{self, JoinHandle};
-use zellij_utils::consts::ZELLIJ_CACHE_DIR;
-use zellij_utils::data::{InputMode, PermissionStatus, PermissionType, PipeMessage, PipeSource};
+use zellij_utils::consts::{ZELLIJ_CACHE_DIR, ZELLIJ_PLUGIN_PERMISSIONS_CACHE};
+use zellij_utils::data::{
+    InputMode, PermissionScopes, PermissionStatus, PermissionType, PipeMessage, PipeSource,
+    PluginRequestError, PluginResponse,
+};
 use zellij_utils::downloader::Downloader;
 use zellij_utils::input::keybinds::Keybinds;
 use zellij_utils::input::permission::PermissionCache;
+use zellij_utils::input::plugin_lockfile::PluginLockfile;
+use zellij_utils::input::plugins::PluginLimits;
 use zellij_utils::notify_debouncer_full::{notify::RecommendedWatcher, Debouncer, FileIdMap};
 use zellij_utils::plugin_api::event::ProtobufEvent;
 
@@ -80,7 +88,7 @@ pub struct WasmBridge {
     senders: ThreadSenders,
     engine: Engine,
     plugin_dir: PathBuf,
-    plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>>,
+    plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>>,
     plugin_map: Arc<Mutex<PluginMap>>,
     next_plugin_id: PluginId,
     plugin_ids_waiting_for_permission_request: HashSet<PluginId>,
@@ -94,6 +102,9 @@ pub struct WasmBridge {
     pending_plugin_reloads: HashSet<RunPlugin>,
     path_to_default_shell: PathBuf,
     watcher: Option<Debouncer<RecommendedWatcher, FileIdMap>>,
+    permission_cache_watcher: Option<Debouncer<RecommendedWatcher, FileIdMap>>,
+    plugin_file_watchers: HashMap<PathBuf, Debouncer<RecommendedWatcher, FileIdMap>>,
+    plugin_lockfile_watcher: Option<Debouncer<RecommendedWatcher, FileIdMap>>,
     zellij_cwd: PathBuf,
     capabilities: PluginCapabilities,
     client_attributes: ClientAttributes,
@@ -102,11 +113,13 @@ pub struct WasmBridge {
     cached_plugin_map:
         HashMap<RunPluginLocation, HashMap<PluginUserConfiguration, Vec<(PluginId, ClientId)>>>,
     pending_pipes: PendingPipes,
+    pending_plugin_requests: PendingPluginRequests,
     layout_dir: Option<PathBuf>,
     default_mode: InputMode,
     default_keybinds: Keybinds,
     keybinds: HashMap<ClientId, Keybinds>,
     base_modes: HashMap<ClientId, InputMode>,
+    plugin_limits: PluginLimits,
 }
 
 impl WasmBridge {
@@ -123,10 +136,11 @@ impl WasmBridge {
         layout_dir: Option<PathBuf>,
         default_mode: InputMode,
         default_keybinds: Keybinds,
+        plugin_limits: PluginLimits,
     ) -> Self {
         let plugin_map = Arc::new(Mutex::new(PluginMap::default()));
         let connected_clients: Arc<Mutex<Vec<ClientId>>> = Arc::new(Mutex::new(vec![]));
-        let plugin_cache: Arc<Mutex<HashMap<PathBuf, Module>>> =
+        let plugin_cache: Arc<Mutex<HashMap<PathBuf, (Module, PathBuf)>>> =
             Arc::new(Mutex::new(HashMap::new()));
         let watcher = None;
         WasmBridge {
@@ -138,6 +152,9 @@ impl WasmBridge {
             plugin_map,
             path_to_default_shell,
             watcher,
+            permission_cache_watcher: None,
+            plugin_file_watchers: HashMap::new(),
+            plugin_lockfile_watcher: None,
             next_plugin_id: 0,
             cached_events_for_pending_plugins: HashMap::new(),
             plugin_ids_waiting_for_permission_request: HashSet::new(),
@@ -152,11 +169,13 @@ impl WasmBridge {
             default_layout,
             cached_plugin_map: HashMap::new(),
             pending_pipes: Default::default(),
+            pending_plugin_requests: Default::default(),
             layout_dir,
             default_mode,
             default_keybinds,
             keybinds: HashMap::new(),
             base_modes: HashMap::new(),
+            plugin_limits,
         }
     }
     pub fn load_plugin(
@@ -193,6 +212,7 @@ impl WasmBridge {
                     .with_context(|| format!("failed to resolve plugin {run:?}"))
                     .with_context(err_context)?;
                 let plugin_name = run.location.to_string();
+                self.watch_plugin_file_if_needed(run);
 
                 self.cached_events_for_pending_plugins
                     .insert(plugin_id, vec![]);
@@ -216,20 +236,30 @@ impl WasmBridge {
                     let default_mode = self
                         .base_modes
                         .get(&client_id)
-                        .copied()
-                        .unwrap_or(self.default_mode);
+                        .cloned()
+                        .unwrap_or_else(|| self.default_mode.clone());
                     let keybinds = self
                         .keybinds
                         .get(&client_id)
                         .cloned()
                         .unwrap_or_else(|| self.default_keybinds.clone());
+                    let plugin_limits = self.plugin_limits;
                     async move {
                         let _ = senders.send_to_background_jobs(
                             BackgroundJob::AnimatePluginLoading(plugin_id),
                         );
                         let mut loading_indication = LoadingIndication::new(plugin_name.clone());
 
-                        if let RunPluginLocation::Remote(url) = &plugin.location {
+                        let plugin_lockfile = PluginLockfile::from_plugin_dir(&plugin_dir);
+                        let installed_alias = match &plugin.location {
+                            RunPluginLocation::Remote(url) => plugin_lockfile.alias_of_url(url),
+                            _ => None,
+                        };
+                        if let Some(installed_alias) = installed_alias {
+                            // the plugin was installed from this url, so we load the copy that was
+                            // pinned when it was installed rather than whatever the url serves now
+                            plugin.path = plugin_lockfile.installed_plugin_path(installed_alias);
+                        } else if let RunPluginLocation::Remote(url) = &plugin.location {
                             let file_name: String = PortableHash::default()
                                 .hash128(url.as_bytes())
                                 .iter()
@@ -272,6 +302,7 @@ impl WasmBridge {
                             layout_dir,
                             default_mode,
                             keybinds,
+                            plugin_limits,
                         ) {
                             Ok(_) => handle_plugin_successful_loading(&senders, plugin_id),
                             Err(e) => handle_plugin_loading_failure(
@@ -319,7 +350,15 @@ impl WasmBridge {
                 log::error!("Failed to remove cache dir for plugin: {:?}", e);
             }
         }
+        drop(plugin_map);
         self.cached_plugin_map.clear();
+        self.stop_unused_plugin_file_watchers();
+        let _ = self
+            .senders
+            .send_to_screen(ScreenInstruction::UnsubscribeFromPaneOutput(pid));
+        let _ = self
+            .senders
+            .send_to_server(ServerInstruction::InterceptKeys(pid, vec![], vec![]));
         let mut pipes_to_unblock = self.pending_pipes.unload_plugin(&pid);
         for pipe_name in pipes_to_unblock.drain(..) {
             let _ = self
@@ -327,6 +366,13 @@ impl WasmBridge {
                 .send_to_server(ServerInstruction::UnblockCliPipeInput(pipe_name))
                 .context("failed to unblock input pipe");
         }
+        let unanswered_plugin_requests = self.pending_plugin_requests.unload_plugin(&pid);
+        if !unanswered_plugin_requests.is_empty() {
+            let _ = self
+                .senders
+                .send_to_plugin(PluginInstruction::Update(unanswered_plugin_requests))
+                .context("failed to fail unanswered plugin requests");
+        }
         Ok(())
     }
     pub fn reload_plugin(&mut self, run_plugin: &RunPlugin) -> Result<()> {
@@ -347,6 +393,7 @@ impl WasmBridge {
 
         let first_plugin_id = *plugin_ids.get(0).unwrap(); // this is safe becaise the above
                                                            // methods always returns at least 1 id
+        let saved_plugin_states = self.save_plugin_states(&plugin_ids);
         let mut loading_indication = LoadingIndication::new(run_plugin.location.to_string());
         self.start_plugin_loading_indication(&plugin_ids, &loading_indication);
         let load_plugin_task = task::spawn({
@@ -365,6 +412,7 @@ impl WasmBridge {
             let layout_dir = self.layout_dir.clone();
             let base_modes = self.base_modes.clone();
             let keybinds = self.keybinds.clone();
+            let plugin_limits = self.plugin_limits;
             async move {
                 match PluginLoader::reload_plugin(
                     first_plugin_id,
@@ -384,9 +432,16 @@ impl WasmBridge {
                     layout_dir.clone(),
                     &base_modes,
                     &keybinds,
+                    plugin_limits,
                 ) {
                     Ok(_) => {
                         handle_plugin_successful_loading(&senders, first_plugin_id);
+                        restore_plugin_states(
+                            &plugin_map,
+                            first_plugin_id,
+                            &saved_plugin_states,
+                            &senders,
+                        );
                         for plugin_id in &plugin_ids {
                             if plugin_id == &first_plugin_id {
                                 // no need to reload the plugin we just reloaded
@@ -411,8 +466,17 @@ impl WasmBridge {
                                 layout_dir.clone(),
                                 &base_modes,
                                 &keybinds,
+                                plugin_limits,
                             ) {
-                                Ok(_) => handle_plugin_successful_loading(&senders, *plugin_id),
+                                Ok(_) => {
+                                    handle_plugin_successful_loading(&senders, *plugin_id);
+                                    restore_plugin_states(
+                                        &plugin_map,
+                                        *plugin_id,
+                                        &saved_plugin_states,
+                                        &senders,
+                                    );
+                                },
                                 Err(e) => handle_plugin_loading_failure(
                                     &senders,
                                     *plugin_id,
@@ -463,11 +527,12 @@ impl WasmBridge {
             self.default_shell.clone(),
             self.default_layout.clone(),
             self.layout_dir.clone(),
-            self.default_mode,
+            self.default_mode.clone(),
             self.keybinds
                 .get(&client_id)
                 .cloned()
                 .unwrap_or_else(|| self.default_keybinds.clone()),
+            self.plugin_limits,
         ) {
             Ok(_) => {
                 let _ = self
@@ -758,6 +823,116 @@ impl WasmBridge {
         }
         Ok(())
     }
+    pub fn plugin_request(
+        &mut self,
+        source_plugin_id: PluginId,
+        source_client_id: ClientId,
+        request_id: String,
+        destination: Option<(PluginId, Option<ClientId>)>,
+        name: String,
+        payload: Option<String>,
+        shutdown_sender: Sender<()>,
+    ) -> Result<()> {
+        match destination {
+            Some((destination_plugin_id, destination_client_id)) => {
+                self.pending_plugin_requests.add(
+                    request_id.clone(),
+                    name.clone(),
+                    source_plugin_id,
+                    source_client_id,
+                    destination_plugin_id,
+                );
+                let is_private = true;
+                let pipe_message = PipeMessage::new(
+                    PipeSource::Plugin(source_plugin_id),
+                    name,
+                    &payload,
+                    &None,
+                    is_private,
+                )
+                .with_request_id(request_id);
+                self.pipe_messages(
+                    vec![(
+                        Some(destination_plugin_id),
+                        destination_client_id,
+                        pipe_message,
+                    )],
+                    shutdown_sender,
+                )
+            },
+            None => self.update_plugins(
+                vec![(
+                    Some(source_plugin_id),
+                    Some(source_client_id),
+                    Event::PluginResponse(PluginResponse {
+                        request_id,
+                        name,
+                        result: Err(PluginRequestError::NoSuchPlugin),
+                    }),
+                )],
+                shutdown_sender,
+            ),
+        }
+    }
+    pub fn respond_to_plugin_request(
+        &mut self,
+        responding_plugin_id: PluginId,
+        request_id: String,
+        payload: Option<String>,
+        shutdown_sender: Sender<()>,
+    ) -> Result<()> {
+        match self
+            .pending_plugin_requests
+            .respond(&request_id, responding_plugin_id, payload)
+        {
+            Some(update) => self.update_plugins(vec![update], shutdown_sender),
+            None => {
+                log::warn!("Plugin {responding_plugin_id} responded to request {request_id} which is not pending on it (it might have timed out)");
+                Ok(())
+            },
+        }
+    }
+    pub fn plugin_request_timed_out(
+        &mut self,
+        request_id: String,
+        shutdown_sender: Sender<()>,
+    ) -> Result<()> {
+        match self.pending_plugin_requests.time_out(&request_id) {
+            Some(update) => self.update_plugins(vec![update], shutdown_sender),
+            None => Ok(()), // already answered
+        }
+    }
+    // the running (or currently loading) instances of this plugin
+    pub fn plugin_and_client_ids_of_plugin_id(
+        &self,
+        plugin_id: PluginId,
+    ) -> Vec<(PluginId, Option<ClientId>)> {
+        if self.run_plugin_of_loading_plugin_id(plugin_id).is_some() {
+            return vec![(plugin_id, None)];
+        }
+        self.all_plugin_ids()
+            .into_iter()
+            .filter(|(p_id, _client_id)| p_id == &plugin_id)
+            .map(|(p_id, client_id)| (p_id, Some(client_id)))
+            .collect()
+    }
+    // the running (or currently loading) instances of this plugin
+    pub fn plugin_and_client_ids_of_run_plugin(
+        &mut self,
+        run_plugin: &RunPlugin,
+    ) -> Vec<(PluginId, Option<ClientId>)> {
+        let plugin_and_client_ids = self.all_plugin_and_client_ids_for_plugin_location(
+            &run_plugin.location,
+            &run_plugin.configuration,
+        );
+        if plugin_and_client_ids.is_empty() {
+            self.plugin_id_of_loading_plugin(&run_plugin.location, &run_plugin.configuration)
+                .map(|plugin_id| vec![(plugin_id, None)])
+                .unwrap_or_default()
+        } else {
+            plugin_and_client_ids
+        }
+    }
     pub fn apply_cached_events(
         &mut self,
         plugin_ids: Vec<PluginId>,
@@ -807,6 +982,15 @@ impl WasmBridge {
         if let Some(watcher) = self.watcher.take() {
             watcher.stop_nonblocking();
         }
+        if let Some(permission_cache_watcher) = self.permission_cache_watcher.take() {
+            permission_cache_watcher.stop_nonblocking();
+        }
+        if let Some(plugin_lockfile_watcher) = self.plugin_lockfile_watcher.take() {
+            plugin_lockfile_watcher.stop_nonblocking();
+        }
+        for (_plugin_path, plugin_file_watcher) in self.plugin_file_watchers.drain() {
+            plugin_file_watcher.stop_nonblocking();
+        }
     }
     pub fn run_plugin_of_loading_plugin_id(&self, plugin_id: PluginId) -> Option<&RunPlugin> {
         self.loading_plugins
@@ -844,7 +1028,7 @@ impl WasmBridge {
             })
             .collect();
         if let Some(default_mode) = default_mode.as_ref() {
-            self.base_modes.insert(client_id, *default_mode);
+            self.base_modes.insert(client_id, default_mode.clone());
         }
         if let Some(keybinds) = keybinds.as_ref() {
             self.keybinds.insert(client_id, keybinds.clone());
@@ -854,6 +1038,7 @@ impl WasmBridge {
             task::spawn({
                 let running_plugin = running_plugin.clone();
                 let keybinds = keybinds.clone();
+                let default_mode = default_mode.clone();
                 let default_shell = default_shell.clone();
                 async move {
                     let mut running_plugin = running_plugin.lock().unwrap();
@@ -1129,11 +1314,218 @@ impl WasmBridge {
             };
         }
     }
+    pub fn start_permission_cache_watcher(&mut self) {
+        self.permission_cache_watcher =
+            match watch_permission_cache(self.senders.clone(), &ZELLIJ_PLUGIN_PERMISSIONS_CACHE) {
+                Ok(watcher) => Some(watcher),
+                Err(e) => {
+                    log::error!("Failed to watch plugin permission cache: {:?}", e);
+                    None
+                },
+            };
+    }
+    pub fn start_plugin_lockfile_watcher(&mut self) {
+        let plugin_lockfile = PluginLockfile::from_plugin_dir(&self.plugin_dir);
+        // the lockfile might not exist yet, but we watch its folder
+        self.plugin_lockfile_watcher =
+            match std::fs::create_dir_all(plugin_lockfile.installed_plugins_dir())
+                .map_err(anyError::new)
+                .and_then(|_| {
+                    watch_plugin_lockfile(self.senders.clone(), &plugin_lockfile.lockfile_path())
+                }) {
+                Ok(watcher) => Some(watcher),
+                Err(e) => {
+                    log::warn!("Failed to watch plugin lockfile: {:?}", e);
+                    None
+                },
+            };
+    }
+    fn watch_plugin_file_if_needed(&mut self, run_plugin: &RunPlugin) {
+        if let RunPluginLocation::File(plugin_path) = &run_plugin.location {
+            if run_plugin.configuration.reload_on_change()
+                && !self.plugin_file_watchers.contains_key(plugin_path)
+            {
+                match watch_plugin_file(self.senders.clone(), plugin_path) {
+                    Ok(watcher) => {
+                        self.plugin_file_watchers
+                            .insert(plugin_path.clone(), watcher);
+                    },
+                    Err(e) => {
+                        log::error!(
+                            "Failed to watch plugin file {}: {:?}",
+                            plugin_path.display(),
+                            e
+                        );
+                    },
+                }
+            }
+        }
+    }
+    fn plugins_reloaded_on_change(&self) -> HashSet<RunPlugin> {
+        let plugin_map = self.plugin_map.lock().unwrap();
+        let running_plugins = plugin_map
+            .plugin_ids()
+            .into_iter()
+            .filter_map(|plugin_id| plugin_map.run_plugin_of_plugin_id(plugin_id));
+        let loading_plugins = self
+            .loading_plugins
+            .keys()
+            .map(|(_plugin_id, run_plugin)| run_plugin.clone());
+        running_plugins
+            .chain(loading_plugins)
+            .filter(|run_plugin| run_plugin.configuration.reload_on_change())
+            .map(|mut run_plugin| {
+                // reloading goes by location and configuration
+                run_plugin.initial_cwd = None;
+                run_plugin
+            })
+            .collect()
+    }
+    fn stop_unused_plugin_file_watchers(&mut self) {
+        if self.plugin_file_watchers.is_empty() {
+            return;
+        }
+        let watched_plugin_locations: HashSet<RunPluginLocation> = self
+            .plugins_reloaded_on_change()
+            .into_iter()
+            .map(|run_plugin| run_plugin.location)
+            .collect();
+        self.plugin_file_watchers.retain(|plugin_path, _watcher| {
+            watched_plugin_locations.contains(&RunPluginLocation::File(plugin_path.clone()))
+        });
+    }
+    // reloads the plugins that were started with `reload_on_change` from a file that changed, the
+    // same way `zellij action start-or-reload-plugin` does
+    pub fn reload_plugins_loaded_from(&mut self, plugin_path: &Path) {
+        let plugin_location = RunPluginLocation::File(plugin_path.to_path_buf());
+        let plugins_to_reload: Vec<RunPlugin> = self
+            .plugins_reloaded_on_change()
+            .into_iter()
+            .filter(|run_plugin| run_plugin.location == plugin_location)
+            .collect();
+        for run_plugin in plugins_to_reload {
+            log::info!("Plugin file changed, reloading {}", run_plugin.location);
+            if let Err(e) = self.reload_plugin(&run_plugin) {
+                log::error!("Failed to reload plugin {}: {:?}", run_plugin.location, e);
+            }
+        }
+    }
+    fn save_plugin_states(
+        &self,
+        plugin_ids: &[PluginId],
+    ) -> HashMap<(PluginId, ClientId), Vec<u8>> {
+        let mut saved_plugin_states = HashMap::new();
+        let running_plugins = self.plugin_map.lock().unwrap().running_plugins();
+        for (plugin_id, client_id, running_plugin) in running_plugins {
+            if !plugin_ids.contains(&plugin_id) {
+                continue;
+            }
+            match save_plugin_state(&mut running_plugin.lock().unwrap()) {
+                Ok(Some(saved_state)) => {
+                    saved_plugin_states.insert((plugin_id, client_id), saved_state);
+                },
+                Ok(None) => {},
+                Err(e) => log::error!("Failed to save state of plugin {}: {:?}", plugin_id, e),
+            }
+        }
+        saved_plugin_states
+    }
+    // takes away from the running plugins the permissions (or parts of their scopes) that are no
+    // longer in the cache (eg. because they were revoked), granting new ones still goes through
+    // the plugin's next permission request
+    //
+    // plugins whose host filesystem access was narrowed are reloaded, since /host is only mounted
+    // when the plugin loads
+    pub fn apply_permission_cache(&mut self, cache_path: Option<PathBuf>) {
+        let permission_cache = PermissionCache::from_path_or_default(cache_path);
+        let running_plugins = self.plugin_map.lock().unwrap().running_plugins();
+        let mut plugin_ids_to_reload = HashSet::new();
+        for (plugin_id, _client_id, running_plugin) in running_plugins {
+            let mut running_plugin = running_plugin.lock().unwrap();
+            let plugin_env = running_plugin.store.data_mut();
+            if plugin_env.plugin.is_builtin() {
+                continue;
+            }
+            let plugin_location = plugin_env.plugin.location.to_string();
+            let cached_permissions = permission_cache
+                .get_permissions(plugin_location.clone())
+                .cloned()
+                .unwrap_or_default();
+            let current_permissions = plugin_env.permissions.lock().unwrap().clone();
+            let current_scopes = plugin_env.permission_scopes.lock().unwrap().clone();
+            if let Some(current_permissions) = current_permissions {
+                let remaining_permissions: HashSet<PermissionType> = current_permissions
+                    .iter()
+                    .filter(|p| cached_permissions.contains(p))
+                    .copied()
+                    .collect();
+                let remaining_scopes = current_scopes
+                    .narrowed_by(&permission_cache.get_permission_scopes(&plugin_location));
+                if remaining_permissions.len() != current_permissions.len()
+                    || remaining_scopes != current_scopes
+                {
+                    let revoked_permissions: HashSet<PermissionType> = current_permissions
+                        .difference(&remaining_permissions)
+                        .copied()
+                        .collect();
+                    log::info!(
+                        "Revoking permissions of plugin {}: {:?}, remaining scopes: {:?}",
+                        plugin_id,
+                        revoked_permissions,
+                        remaining_scopes
+                    );
+                    if revoked_permissions.contains(&PermissionType::InterceptInput) {
+                        let _ = self
+                            .senders
+                            .send_to_server(ServerInstruction::InterceptKeys(
+                                plugin_id,
+                                vec![],
+                                vec![],
+                            ));
+                    }
+                    if revoked_permissions.contains(&PermissionType::ReadPaneContents) {
+                        let _ = self.senders.send_to_screen(
+                            ScreenInstruction::UnsubscribeFromPaneOutput(plugin_id),
+                        );
+                    }
+                    if revoked_permissions.contains(&PermissionType::HostFilesystemAccess)
+                        || remaining_scopes.scope(&PermissionType::HostFilesystemAccess)
+                            != current_scopes.scope(&PermissionType::HostFilesystemAccess)
+                    {
+                        plugin_ids_to_reload.insert(plugin_id);
+                    }
+                    plugin_env.set_permissions(remaining_permissions, remaining_scopes);
+                }
+            }
+        }
+        let plugins_to_reload: HashSet<RunPlugin> = {
+            let plugin_map = self.plugin_map.lock().unwrap();
+            plugin_ids_to_reload
+                .into_iter()
+                .filter_map(|plugin_id| plugin_map.run_plugin_of_plugin_id(plugin_id))
+                .map(|mut run_plugin| {
+                    // reloading goes by location and configuration
+                    run_plugin.initial_cwd = None;
+                    run_plugin
+                })
+                .collect()
+        };
+        for run_plugin in plugins_to_reload {
+            log::info!(
+                "Host filesystem access narrowed, reloading {}",
+                run_plugin.location
+            );
+            if let Err(e) = self.reload_plugin(&run_plugin) {
+                log::error!("Failed to reload plugin {}: {:?}", run_plugin.location, e);
+            }
+        }
+    }
     pub fn cache_plugin_permissions(
         &mut self,
         plugin_id: PluginId,
         client_id: Option<ClientId>,
         permissions: Vec<PermissionType>,
+        permission_scopes: PermissionScopes,
         status: PermissionStatus,
         cache_path: Option<PathBuf>,
     ) -> Result<()> {
@@ -1148,21 +1540,36 @@ impl WasmBridge {
 
         let mut running_plugin = running_plugin.lock().unwrap();
 
-        let permissions = if status == PermissionStatus::Granted {
-            permissions
+        let (permissions, permission_scopes) = if status == PermissionStatus::Granted {
+            (permissions, permission_scopes)
         } else {
-            vec![]
+            (vec![], PermissionScopes::default())
         };
 
-        running_plugin
+        running_plugin.store.data_mut().set_permissions(
+            HashSet::from_iter(permissions.clone()),
+            permission_scopes.clone(),
+        );
+
+        // the plugin might have subscribed to pane output before it was allowed to read it
+        let subscribed_to_pane_output = running_plugin
             .store
-            .data_mut()
-            .set_permissions(HashSet::from_iter(permissions.clone()));
+            .data()
+            .subscriptions
+            .lock()
+            .unwrap()
+            .contains(&EventType::PaneOutput);
+        if subscribed_to_pane_output && permissions.contains(&PermissionType::ReadPaneContents) {
+            let _ = self
+                .senders
+                .send_to_screen(ScreenInstruction::SubscribeToPaneOutput(plugin_id, None));
+        }
 
         let mut permission_cache = PermissionCache::from_path_or_default(cache_path);
         permission_cache.cache(
             running_plugin.store.data().plugin.location.to_string(),
             permissions,
+            permission_scopes,
         );
 
         permission_cache.write_to_file().with_context(err_context)
@@ -1308,6 +1715,88 @@ fn handle_plugin_successful_loading(senders: &ThreadSenders, plugin_id: PluginId
     let _ = senders.send_to_screen(ScreenInstruction::RequestStateUpdateForPlugins);
 }
 
+// plugins can keep their state across reloads by exporting `save_state` and `restore_state`
+// (see `ZellijPlugin::save_state`), plugins that do not are reloaded from scratch
+fn save_plugin_state(running_plugin: &mut RunningPlugin) -> Result<Option<Vec<u8>>> {
+    let err_context = || "failed to save plugin state";
+    let save_state = match running_plugin
+        .instance
+        .clone()
+        .get_typed_func::<(), ()>(&mut running_plugin.store, "save_state")
+    {
+        Ok(save_state) => save_state,
+        Err(_) => return Ok(None),
+    };
+    save_state
+        .call(&mut running_plugin.store, ())
+        .with_context(err_context)?;
+    let saved_state = wasi_read_bytes(running_plugin.store.data()).with_context(err_context)?;
+    Ok(Some(saved_state).filter(|saved_state| !saved_state.is_empty()))
+}
+
+fn restore_plugin_states(
+    plugin_map: &Arc<Mutex<PluginMap>>,
+    plugin_id: PluginId,
+    saved_plugin_states: &HashMap<(PluginId, ClientId), Vec<u8>>,
+    senders: &ThreadSenders,
+) {
+    for ((p_id, client_id), saved_state) in saved_plugin_states {
+        if *p_id != plugin_id {
+            continue;
+        }
+        let running_plugin = plugin_map
+            .lock()
+            .unwrap()
+            .get_running_plugin(plugin_id, Some(*client_id));
+        if let Some(running_plugin) = running_plugin {
+            let mut running_plugin = running_plugin.lock().unwrap();
+            if let Err(e) = restore_plugin_state(
+                plugin_id,
+                *client_id,
+                &mut running_plugin,
+                saved_state,
+                senders,
+            ) {
+                log::error!("Failed to restore state of plugin {}: {:?}", plugin_id, e);
+            }
+        }
+    }
+}
+
+fn restore_plugin_state(
+    plugin_id: PluginId,
+    client_id: ClientId,
+    running_plugin: &mut RunningPlugin,
+    saved_state: &[u8],
+    senders: &ThreadSenders,
+) -> Result<()> {
+    let err_context = || "failed to restore plugin state";
+    let instance = running_plugin.instance;
+    let restore_state =
+        match instance.get_typed_func::<(), i32>(&mut running_plugin.store, "restore_state") {
+            Ok(restore_state) => restore_state,
+            Err(_) => return Ok(()),
+        };
+    wasi_write_object(running_plugin.store.data(), saved_state).with_context(err_context)?;
+    let should_render = restore_state
+        .call(&mut running_plugin.store, ())
+        .with_context(err_context)?;
+    let (rows, columns) = (running_plugin.rows, running_plugin.columns);
+    if should_render == 1 && rows > 0 && columns > 0 {
+        let rendered_bytes = instance
+            .get_typed_func::<(i32, i32), ()>(&mut running_plugin.store, "render")
+            .and_then(|render| {
+                render.call(&mut running_plugin.store, (rows as i32, columns as i32))
+            })
+            .and_then(|_| wasi_read_string(running_plugin.store.data()))
+            .with_context(err_context)?;
+        let plugin_render_asset =
+            PluginRenderAsset::new(plugin_id, client_id, rendered_bytes.as_bytes().to_vec());
+        let _ = senders.send_to_screen(ScreenInstruction::PluginBytes(vec![plugin_render_asset]));
+    }
+    Ok(())
+}
+
 fn handle_plugin_loading_failure(
     senders: &ThreadSenders,
     plugin_id: PluginId,
@@ -1355,6 +1844,8 @@ fn check_event_permission(
         | Event::FailedToWriteConfigToDisk(..)
         | Event::CommandPaneReRun(..)
         | Event::InputReceived => PermissionType::ReadApplicationState,
+        Event::PaneOutput(..) => PermissionType::ReadPaneContents,
+        Event::InterceptedKey(..) => PermissionType::InterceptInput,
         _ => return (PermissionStatus::Granted, None),
     };
 
@@ -1392,7 +1883,7 @@ pub fn apply_event_to_plugin(
                 // ModeInfo to an Option, but alas - this is already part of our contract and that
                 // would be a breaking change.
                 mode_info.keybinds = running_plugin.store.data().keybinds.to_keybinds_vec();
-                mode_info.base_mode = Some(running_plugin.store.data().default_mode);
+                mode_info.base_mode = Some(running_plugin.store.data().default_mode.clone());
             }
             let protobuf_event: ProtobufEvent = event
                 .clone()
@@ -1462,4 +1953,6 @@ pub fn handle_plugin_crash(plugin_id: PluginId, message: String, senders: Thread
         plugin_id,
         loading_indication,
     ));
+    // a crashed plugin cannot handle the keys it intercepted, so they are routed normally again
+    let _ = senders.send_to_server(ServerInstruction::InterceptKeys(plugin_id, vec![], vec![]));
 }
@@ -1,13 +1,15 @@
 use super::PluginInstruction;
 use std::path::PathBuf;
 
+use crate::pty::PtyInstruction;
 use crate::thread_bus::ThreadSenders;
 use std::path::Path;
 use std::time::Duration;
 
+use zellij_utils::globset::{Glob, GlobSetBuilder};
 use zellij_utils::notify_debouncer_full::{
     new_debouncer,
-    notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher},
+    notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher},
     DebounceEventResult, Debouncer, FileIdMap,
 };
 use zellij_utils::{data::Event, errors::prelude::Result};
@@ -133,3 +135,222 @@ pub fn watch_filesystem(
         .watch(zellij_cwd, RecursiveMode::Recursive)?;
     Ok(debouncer)
 }
+
+/// Watches the plugin permission cache file so that permissions revoked from outside the session
+/// (eg. with `zellij plugin permissions revoke`) are taken away from the running plugins
+pub fn watch_permission_cache(
+    senders: ThreadSenders,
+    cache_path: &Path,
+) -> Result<Debouncer<RecommendedWatcher, FileIdMap>> {
+    watch_file(senders, cache_path, || {
+        PluginInstruction::PermissionCacheChanged(None)
+    })
+}
+
+/// Watches the lockfile of the installed plugins so that plugins installed or removed from
+/// outside the session (eg. with `zellij plugin install`) can be used by their alias
+pub fn watch_plugin_lockfile(
+    senders: ThreadSenders,
+    lockfile_path: &Path,
+) -> Result<Debouncer<RecommendedWatcher, FileIdMap>> {
+    watch_file(senders, lockfile_path, || {
+        PluginInstruction::PluginLockfileChanged
+    })
+}
+
+fn watch_file(
+    senders: ThreadSenders,
+    file_path: &Path,
+    on_change: fn() -> PluginInstruction,
+) -> Result<Debouncer<RecommendedWatcher, FileIdMap>> {
+    let watched_folder = file_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| file_path.to_path_buf());
+    let file_path = file_path.to_path_buf();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(DEBOUNCE_DURATION_MS),
+        None,
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let file_changed = events.iter().any(|event| {
+                    matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    ) && event.paths.contains(&file_path)
+                });
+                if file_changed {
+                    let _ = senders.send_to_plugin(on_change());
+                }
+            },
+            Err(errors) => errors
+                .iter()
+                .for_each(|error| log::error!("watch error: {error:?}")),
+        },
+    )?;
+
+    debouncer
+        .watcher()
+        .watch(&watched_folder, RecursiveMode::NonRecursive)?;
+    Ok(debouncer)
+}
+
+/// Watches the file a plugin was loaded from (eg. the `.wasm` file of a plugin under development)
+/// so that plugins started with `reload_on_change` can be reloaded when it is rebuilt
+pub fn watch_plugin_file(
+    senders: ThreadSenders,
+    plugin_path: &Path,
+) -> Result<Debouncer<RecommendedWatcher, FileIdMap>> {
+    // build tools usually replace the file rather than write to it, so we watch its folder
+    let watched_folder = plugin_path
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| plugin_path.to_path_buf());
+    let plugin_path = plugin_path.to_path_buf();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(DEBOUNCE_DURATION_MS),
+        None,
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let plugin_file_changed = events
+                    .iter()
+                    .filter(|event| match event.kind {
+                        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+                        EventKind::Create(_) | EventKind::Modify(_) => true,
+                        _ => false,
+                    })
+                    .any(|event| event.paths.contains(&plugin_path));
+                if plugin_file_changed {
+                    let _ = senders
+                        .send_to_plugin(PluginInstruction::PluginFileChanged(plugin_path.clone()));
+                }
+            },
+            Err(errors) => errors
+                .iter()
+                .for_each(|error| log::error!("watch error: {error:?}")),
+        },
+    )?;
+
+    debouncer
+        .watcher()
+        .watch(&watched_folder, RecursiveMode::NonRecursive)?;
+    Ok(debouncer)
+}
+
+/// The files watched for a command pane and whether its command should be re-run because of them
+pub struct CommandPaneWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, FileIdMap>, // dropping this stops the watch
+    kill_on_change: bool,
+    is_running: bool,
+    rerun_on_exit: bool, // files changed while the command was running, re-run it once it exits
+}
+
+/// What to do with the command of a pane when the files it watches change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchedFilesChange {
+    /// The command is not running, re-run it now
+    RerunNow,
+    /// Kill the running command, it will be re-run once it exits
+    KillAndRerunOnExit,
+    /// Let the running command finish, it will be re-run once it exits
+    RerunOnExit,
+    /// A re-run is already queued for when the command exits
+    AlreadyQueued,
+}
+
+impl CommandPaneWatcher {
+    pub fn new(debouncer: Debouncer<RecommendedWatcher, FileIdMap>, kill_on_change: bool) -> Self {
+        CommandPaneWatcher {
+            _debouncer: debouncer,
+            kill_on_change,
+            is_running: true,
+            rerun_on_exit: false,
+        }
+    }
+    pub fn command_started(&mut self) {
+        self.is_running = true;
+    }
+    /// Returns true if the command should be re-run because files changed while it was running
+    pub fn command_exited(&mut self) -> bool {
+        self.is_running = false;
+        std::mem::take(&mut self.rerun_on_exit)
+    }
+    pub fn files_changed(&mut self) -> WatchedFilesChange {
+        if !self.is_running {
+            WatchedFilesChange::RerunNow
+        } else if self.rerun_on_exit {
+            WatchedFilesChange::AlreadyQueued
+        } else {
+            self.rerun_on_exit = true;
+            if self.kill_on_change {
+                WatchedFilesChange::KillAndRerunOnExit
+            } else {
+                WatchedFilesChange::RerunOnExit
+            }
+        }
+    }
+}
+
+/// Watches the files matching `patterns` (relative to `cwd`) and lets the pty thread know when
+/// they change, so that the command in the pane can be re-run
+pub fn watch_command_pane_files(
+    senders: ThreadSenders,
+    terminal_id: u32,
+    cwd: &Path,
+    patterns: &[String],
+) -> Result<Debouncer<RecommendedWatcher, FileIdMap>> {
+    let mut glob_set_builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        glob_set_builder.add(Glob::new(pattern)?);
+    }
+    let glob_set = glob_set_builder.build()?;
+    // the reported paths might go through symlinks or be relative, so we match canonical paths
+    let current_dir = cwd.canonicalize()?;
+    let watched_dir = current_dir.clone();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(DEBOUNCE_DURATION_MS),
+        None,
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let watched_files_changed = events
+                    .iter()
+                    .filter(|event| match event.kind {
+                        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => true,
+                        _ => false,
+                    })
+                    .flat_map(|event| event.paths.iter())
+                    .map(|p| canonicalize_changed_path(p))
+                    .any(|p| glob_set.is_match(p.strip_prefix(&current_dir).unwrap_or(&p)));
+                if watched_files_changed {
+                    let _ = senders.send_to_pty(PtyInstruction::WatchedFilesChanged(terminal_id));
+                }
+            },
+            Err(errors) => errors
+                .iter()
+                .for_each(|error| log::error!("watch error: {error:?}")),
+        },
+    )?;
+
+    debouncer
+        .watcher()
+        .watch(&watched_dir, RecursiveMode::Recursive)?;
+    Ok(debouncer)
+}
+
+// removed files can no longer be canonicalized, so we canonicalize their folder instead
+fn canonicalize_changed_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        match (
+            path.parent().and_then(|p| p.canonicalize().ok()),
+            path.file_name(),
+        ) {
+            (Some(parent), Some(file_name)) => parent.join(file_name),
+            _ => path.to_path_buf(),
+        }
+    })
+}
+
+#[path = "./unit/watch_filesystem_tests.rs"]
+#[cfg(test)]
+mod watch_filesystem_tests;
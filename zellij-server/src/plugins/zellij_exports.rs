@@ -10,20 +10,25 @@ use serde::Serialize;
 use std::{
     collections::{BTreeMap, HashSet},
     io::{Read, Write},
+    ops::Range,
     path::PathBuf,
     process,
     str::FromStr,
     thread,
     time::{Duration, Instant},
 };
+use url::Url;
+use uuid::Uuid;
 use wasmtime::{Caller, Linker};
 use zellij_utils::data::{
-    CommandType, ConnectToSession, FloatingPaneCoordinates, HttpVerb, LayoutInfo, MessageToPlugin,
-    OriginatingPlugin, PermissionStatus, PermissionType, PluginPermission,
+    CommandType, ConnectToSession, FloatingPaneCoordinates, HttpVerb, KeyWithModifier, LayoutInfo,
+    MessageToPlugin, OriginatingPlugin, PaneColors, PermissionScopes, PermissionStatus,
+    PermissionType, PluginPermission, PluginRequestTarget,
 };
 use zellij_utils::input::permission::PermissionCache;
 use zellij_utils::{
     async_std::task,
+    channels,
     interprocess::local_socket::LocalSocketStream,
     ipc::{ClientToServerMsg, IpcSenderWithContext},
 };
@@ -40,17 +45,23 @@ use zellij_utils::{
     input::{
         actions::Action,
         command::{OpenFilePayload, RunCommand, RunCommandAction, TerminalAction},
+        keybinds::Keybinds,
         layout::{Layout, RunPluginOrAlias},
         plugins::PluginType,
     },
     plugin_api::{
-        plugin_command::ProtobufPluginCommand,
+        plugin_command::{
+            ProtobufGrantedPluginPermissionsList, ProtobufPaneContentsResult,
+            ProtobufPluginCommand, ProtobufPluginRequestId,
+        },
         plugin_ids::{ProtobufPluginIds, ProtobufZellijVersion},
     },
     prost::Message,
     serde,
 };
 
+const PANE_CONTENTS_QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
 macro_rules! apply_action {
     ($action:ident, $error_message:ident, $env: ident) => {
         if let Err(e) = route_action(
@@ -212,8 +223,8 @@ fn host_run_plugin_command(caller: Caller<'_, PluginEnv>) {
                         rename_tab(env, tab_index, &new_name)
                     },
                     PluginCommand::ReportPanic(crash_payload) => report_panic(env, &crash_payload),
-                    PluginCommand::RequestPluginPermissions(permissions) => {
-                        request_permission(env, permissions)?
+                    PluginCommand::RequestPluginPermissions(permissions, scopes) => {
+                        request_permission(env, permissions, scopes)?
                     },
                     PluginCommand::SwitchSession(connect_to_session) => switch_session(
                         env,
@@ -272,6 +283,33 @@ fn host_run_plugin_command(caller: Caller<'_, PluginEnv>) {
                     PluginCommand::RerunCommandPane(terminal_pane_id) => {
                         rerun_command_pane(env, terminal_pane_id)
                     },
+                    PluginCommand::SetPaneColor(pane_id, pane_colors) => {
+                        set_pane_color(env, pane_id.into(), pane_colors)?
+                    },
+                    PluginCommand::GetPaneScrollback(pane_id, line_range, with_ansi) => {
+                        get_pane_scrollback(env, pane_id.into(), line_range, with_ansi)?
+                    },
+                    PluginCommand::SubscribeToPaneOutput(pane_ids) => subscribe_to_pane_output(
+                        env,
+                        pane_ids.into_iter().map(|p_id| p_id.into()).collect(),
+                    )?,
+                    PluginCommand::InterceptKeys(keys, input_modes) => {
+                        intercept_keys(env, keys, input_modes)?
+                    },
+                    PluginCommand::ReinjectKey(key) => reinject_key(env, key)?,
+                    PluginCommand::RegisterInputMode(name, keybinds) => {
+                        register_input_mode(env, name, keybinds)?
+                    },
+                    PluginCommand::PluginRequest(target, name, payload, timeout_seconds) => {
+                        plugin_request(env, target, name, payload, timeout_seconds)?
+                    },
+                    PluginCommand::RespondToPluginRequest(request_id, payload) => {
+                        respond_to_plugin_request(env, request_id, payload)?
+                    },
+                    PluginCommand::ListPluginPermissions => list_plugin_permissions(env)?,
+                    PluginCommand::RevokePluginPermissions(plugin_url, permissions) => {
+                        revoke_plugin_permissions(env, plugin_url, permissions)?
+                    },
                 },
                 (PermissionStatus::Denied, permission) => {
                     log::error!(
@@ -291,6 +329,30 @@ fn host_run_plugin_command(caller: Caller<'_, PluginEnv>) {
 }
 
 fn subscribe(env: &PluginEnv, event_list: HashSet<EventType>) -> Result<()> {
+    if event_list.contains(&EventType::PaneOutput) {
+        // the screen only collects pane output for plugins that are allowed to read it, if the
+        // permission is granted later on, it starts collecting it then
+        match check_command_permission(env, &PluginCommand::SubscribeToPaneOutput(vec![])) {
+            (PermissionStatus::Granted, _) => {
+                env.senders
+                    .send_to_screen(ScreenInstruction::SubscribeToPaneOutput(
+                        env.plugin_id,
+                        None,
+                    ))
+                    .context("failed to subscribe to pane output")?;
+            },
+            (PermissionStatus::Denied, permission) => {
+                log::info!(
+                    "Plugin '{}' permission '{}' not granted yet - Collecting '{:?}' once it is",
+                    env.name(),
+                    permission
+                        .map(|p| p.to_string())
+                        .unwrap_or("UNKNOWN".to_owned()),
+                    EventType::PaneOutput
+                );
+            },
+        }
+    }
     env.subscriptions
         .lock()
         .to_anyhow()?
@@ -329,7 +391,105 @@ fn message_to_plugin(env: &PluginEnv, mut message_to_plugin: MessageToPlugin) ->
         .context("failed to send message to plugin")
 }
 
+fn plugin_request(
+    env: &PluginEnv,
+    target: PluginRequestTarget,
+    name: String,
+    payload: Option<String>,
+    timeout_seconds: f64,
+) -> Result<()> {
+    let timeout = Duration::try_from_secs_f64(timeout_seconds)
+        .with_context(|| format!("invalid timeout for plugin request: {timeout_seconds}"))?;
+    let request_id = Uuid::new_v4().to_string();
+    env.subscriptions
+        .lock()
+        .to_anyhow()?
+        .insert(EventType::PluginResponse);
+    env.senders
+        .send_to_plugin(PluginInstruction::PluginRequest {
+            source_plugin_id: env.plugin_id,
+            source_client_id: env.client_id,
+            request_id: request_id.clone(),
+            target,
+            name,
+            payload,
+        })
+        .context("failed to send plugin request")?;
+    let send_plugin_instructions = env.senders.to_plugin.clone();
+    task::spawn({
+        let request_id = request_id.clone();
+        async move {
+            task::sleep(timeout).await;
+            send_plugin_instructions
+                .ok_or(anyhow!("found no sender to send plugin instruction to"))
+                .and_then(|sender| {
+                    sender
+                        .send(PluginInstruction::PluginRequestTimedOut(request_id))
+                        .to_anyhow()
+                })
+                .context("failed to time out plugin request")
+                .non_fatal();
+        }
+    });
+    wasi_write_object(env, &ProtobufPluginRequestId { request_id }.encode_to_vec())
+        .with_context(|| format!("failed to send plugin request id to plugin {}", env.name()))
+}
+
+fn respond_to_plugin_request(
+    env: &PluginEnv,
+    request_id: String,
+    payload: Option<String>,
+) -> Result<()> {
+    env.senders
+        .send_to_plugin(PluginInstruction::PluginResponse {
+            responding_plugin_id: env.plugin_id,
+            request_id,
+            payload,
+        })
+        .context("failed to respond to plugin request")
+}
+
+fn list_plugin_permissions(env: &PluginEnv) -> Result<()> {
+    let permission_cache = PermissionCache::from_path_or_default(None);
+    let mut plugin_permissions: Vec<PluginPermission> = permission_cache
+        .granted()
+        .iter()
+        .map(|(plugin_url, permissions)| {
+            PluginPermission::new(plugin_url.clone(), permissions.clone())
+                .with_scopes(permission_cache.get_permission_scopes(plugin_url))
+        })
+        .collect();
+    plugin_permissions.sort_by(|a, b| a.name.cmp(&b.name));
+    wasi_write_object(
+        env,
+        &ProtobufGrantedPluginPermissionsList::from(plugin_permissions).encode_to_vec(),
+    )
+    .context("failed to list plugin permissions")
+}
+
+fn revoke_plugin_permissions(
+    env: &PluginEnv,
+    plugin_url: String,
+    permissions: Vec<PermissionType>,
+) -> Result<()> {
+    let err_context = || format!("failed to revoke permissions of plugin {plugin_url}");
+    let mut permission_cache = PermissionCache::from_path_or_default(None);
+    if permission_cache.revoke(&plugin_url, &permissions) {
+        permission_cache.write_to_file().with_context(err_context)?;
+        // the cache file watcher would also pick this up, but we don't want to wait for it
+        env.senders
+            .send_to_plugin(PluginInstruction::PermissionCacheChanged(None))
+            .with_context(err_context)?;
+    }
+    Ok(())
+}
+
 fn unsubscribe(env: &PluginEnv, event_list: HashSet<EventType>) -> Result<()> {
+    if event_list.contains(&EventType::PaneOutput) {
+        env.senders
+            .send_to_screen(ScreenInstruction::UnsubscribeFromPaneOutput(env.plugin_id))
+            .context("failed to unsubscribe from pane output")?;
+    }
     env.subscriptions
         .lock()
         .to_anyhow()?
@@ -337,6 +497,53 @@ fn unsubscribe(env: &PluginEnv, event_list: HashSet<EventType>) -> Result<()> {
     Ok(())
 }
 
+fn intercept_keys(
+    env: &PluginEnv,
+    keys: Vec<KeyWithModifier>,
+    input_modes: Vec<InputMode>,
+) -> Result<()> {
+    if !keys.is_empty() || !input_modes.is_empty() {
+        env.subscriptions
+            .lock()
+            .to_anyhow()?
+            .insert(EventType::InterceptedKey);
+    }
+    env.senders
+        .send_to_server(ServerInstruction::InterceptKeys(
+            env.plugin_id,
+            keys,
+            input_modes,
+        ))
+        .context("failed to intercept keys")?;
+    Ok(())
+}
+
+fn reinject_key(env: &PluginEnv, key: KeyWithModifier) -> Result<()> {
+    env.senders
+        .send_to_server(ServerInstruction::ReinjectKey(key, env.client_id))
+        .context("failed to re-inject key")?;
+    Ok(())
+}
+
+fn subscribe_to_pane_output(env: &PluginEnv, pane_ids: Vec<PaneId>) -> Result<()> {
+    let pane_ids = if pane_ids.is_empty() {
+        None
+    } else {
+        Some(pane_ids.into_iter().collect())
+    };
+    env.senders
+        .send_to_screen(ScreenInstruction::SubscribeToPaneOutput(
+            env.plugin_id,
+            pane_ids,
+        ))
+        .context("failed to subscribe to pane output")?;
+    env.subscriptions
+        .lock()
+        .to_anyhow()?
+        .insert(EventType::PaneOutput);
+    Ok(())
+}
+
 fn set_selectable(env: &PluginEnv, selectable: bool) {
     match env.plugin.run {
         PluginType::Pane(Some(tab_index)) => {
@@ -365,20 +572,38 @@ fn set_selectable(env: &PluginEnv, selectable: bool) {
     }
 }
 
-fn request_permission(env: &PluginEnv, permissions: Vec<PermissionType>) -> Result<()> {
-    if PermissionCache::from_path_or_default(None)
-        .check_permissions(env.plugin.location.to_string(), &permissions)
-    {
-        log::info!("PermissionRequestResult 1");
-        return env
-            .senders
-            .send_to_plugin(PluginInstruction::PermissionRequestResult(
-                env.plugin_id,
-                Some(env.client_id),
-                permissions.to_vec(),
-                PermissionStatus::Granted,
-                None,
-            ));
+fn request_permission(
+    env: &PluginEnv,
+    permissions: Vec<PermissionType>,
+    permission_scopes: PermissionScopes,
+) -> Result<()> {
+    let permission_cache = PermissionCache::from_path_or_default(None);
+    let plugin_location = env.plugin.location.to_string();
+    if permission_cache.check_permissions(plugin_location.clone(), &permissions) {
+        // the scopes the user granted cap the ones the plugin asks for, if nothing is left of a
+        // requested scope we ask the user again
+        let granted_scopes = permission_cache
+            .get_permission_scopes(&plugin_location)
+            .narrowed_by(&permission_scopes);
+        let scopes_granted = permissions.iter().all(|permission| {
+            granted_scopes
+                .scope(permission)
+                .map(|scope| !scope.is_empty())
+                .unwrap_or(true)
+        });
+        if scopes_granted {
+            log::info!("PermissionRequestResult 1");
+            return env
+                .senders
+                .send_to_plugin(PluginInstruction::PermissionRequestResult(
+                    env.plugin_id,
+                    Some(env.client_id),
+                    permissions.to_vec(),
+                    granted_scopes,
+                    PermissionStatus::Granted,
+                    None,
+                ));
+        }
     }
 
     // we do this so that messages that have arrived while the user is seeing the permission screen
@@ -392,7 +617,7 @@ fn request_permission(env: &PluginEnv, permissions: Vec<PermissionType>) -> Resu
     env.senders
         .send_to_screen(ScreenInstruction::RequestPluginPermissions(
             env.plugin_id,
-            PluginPermission::new(env.plugin.location.to_string(), permissions),
+            PluginPermission::new(plugin_location, permissions).with_scopes(permission_scopes),
         ))
 }
 
@@ -588,6 +813,9 @@ fn open_command_pane(
         direction,
         hold_on_close,
         hold_on_start,
+        restart_policy: Default::default(),
+        rerun_on_change: vec![],
+        kill_on_change: false,
         originating_plugin: Some(OriginatingPlugin::new(
             env.plugin_id,
             env.client_id,
@@ -619,6 +847,9 @@ fn open_command_pane_floating(
         direction,
         hold_on_close,
         hold_on_start,
+        restart_policy: Default::default(),
+        rerun_on_change: vec![],
+        kill_on_change: false,
         originating_plugin: Some(OriginatingPlugin::new(
             env.plugin_id,
             env.client_id,
@@ -649,6 +880,9 @@ fn open_command_pane_in_place(
         direction,
         hold_on_close,
         hold_on_start,
+        restart_policy: Default::default(),
+        rerun_on_change: vec![],
+        kill_on_change: false,
         originating_plugin: Some(OriginatingPlugin::new(
             env.plugin_id,
             env.client_id,
@@ -682,6 +916,9 @@ fn open_command_pane_background(
         direction,
         hold_on_close,
         hold_on_start,
+        restart_policy: Default::default(),
+        rerun_on_change: vec![],
+        kill_on_change: false,
         originating_plugin: Some(OriginatingPlugin::new(
             env.plugin_id,
             env.client_id,
@@ -866,6 +1103,41 @@ fn hide_pane_with_id(env: &PluginEnv, pane_id: PaneId) -> Result<()> {
         .with_context(|| format!("failed to hide self"))
 }
 
+fn set_pane_color(env: &PluginEnv, pane_id: PaneId, pane_colors: PaneColors) -> Result<()> {
+    env.senders
+        .send_to_screen(ScreenInstruction::SetPaneColors(
+            Some(pane_id),
+            pane_colors,
+            env.client_id,
+        ))
+        .context("failed to set pane colors")
+}
+
+fn get_pane_scrollback(
+    env: &PluginEnv,
+    pane_id: PaneId,
+    line_range: Option<Range<usize>>,
+    with_ansi: bool,
+) -> Result<()> {
+    let (response_sender, response_receiver) = channels::bounded(1);
+    env.senders
+        .send_to_screen(ScreenInstruction::GetPaneContents(
+            pane_id,
+            line_range,
+            with_ansi,
+            response_sender,
+        ))
+        .context("failed to query pane contents")?;
+    let pane_contents = response_receiver
+        .recv_timeout(PANE_CONTENTS_QUERY_TIMEOUT)
+        .unwrap_or_else(|e| Err(format!("Failed to query pane contents: {}", e)));
+    wasi_write_object(
+        env,
+        &ProtobufPaneContentsResult::from(pane_contents).encode_to_vec(),
+    )
+    .with_context(|| format!("failed to send pane contents to plugin {}", env.name()))
+}
+
 fn show_self(env: &PluginEnv, should_float_if_hidden: bool) {
     let action = Action::FocusPluginPaneWithId(env.plugin_id, should_float_if_hidden);
     let error_msg = || format!("Failed to show self for plugin");
@@ -909,6 +1181,12 @@ fn reconfigure(env: &PluginEnv, new_config: String, write_config_to_disk: bool)
     Ok(())
 }
 
+fn register_input_mode(env: &PluginEnv, name: String, keybinds: String) -> Result<()> {
+    let config = Keybinds::custom_input_mode_config(&name, &keybinds)
+        .map_err(|e| anyhow!("Failed to parse keybindings of mode '{}': {:?}", name, e))?;
+    reconfigure(env, config, false)
+}
+
 fn switch_to_mode(env: &PluginEnv, input_mode: InputMode) {
     let action = Action::SwitchToMode(input_mode);
     let error_msg = || format!("failed to switch to mode in plugin {}", env.name());
@@ -922,6 +1200,7 @@ fn new_tabs_with_layout(env: &PluginEnv, raw_layout: &str) -> Result<()> {
         format!("Layout from plugin: {}", env.name()),
         None,
         None,
+        Default::default(),
     )
     .map_err(|e| anyhow!("Failed to parse layout: {:?}", e))?;
     apply_layout(env, layout);
@@ -1570,21 +1849,75 @@ fn check_command_permission(
         | PluginCommand::ShowPaneWithId(..)
         | PluginCommand::HidePaneWithId(..)
         | PluginCommand::RerunCommandPane(..)
+        | PluginCommand::SetPaneColor(..)
         | PluginCommand::KillSessions(..) => PermissionType::ChangeApplicationState,
         PluginCommand::UnblockCliPipeInput(..)
         | PluginCommand::BlockCliPipeInput(..)
         | PluginCommand::CliPipeOutput(..) => PermissionType::ReadCliPipes,
-        PluginCommand::MessageToPlugin(..) => PermissionType::MessageAndLaunchOtherPlugins,
+        PluginCommand::MessageToPlugin(..) | PluginCommand::PluginRequest(..) => {
+            PermissionType::MessageAndLaunchOtherPlugins
+        },
         PluginCommand::DumpSessionLayout => PermissionType::ReadApplicationState,
-        PluginCommand::Reconfigure(..) => PermissionType::Reconfigure,
+        PluginCommand::GetPaneScrollback(..) | PluginCommand::SubscribeToPaneOutput(..) => {
+            PermissionType::ReadPaneContents
+        },
+        PluginCommand::Reconfigure(..)
+        | PluginCommand::RegisterInputMode(..)
+        | PluginCommand::ListPluginPermissions
+        | PluginCommand::RevokePluginPermissions(..) => PermissionType::Reconfigure,
+        PluginCommand::InterceptKeys(..) | PluginCommand::ReinjectKey(..) => {
+            PermissionType::InterceptInput
+        },
+        PluginCommand::ScanHostFolder(..) => {
+            // scanning the host folder does not need a permission of its own, but it should not
+            // reach outside of the host filesystem scope if the plugin was granted one
+            let permission = PermissionType::HostFilesystemAccess;
+            if command_in_permission_scope(plugin_env, permission, command) {
+                return (PermissionStatus::Granted, None);
+            }
+            return (PermissionStatus::Denied, Some(permission));
+        },
         _ => return (PermissionStatus::Granted, None),
     };
 
     if let Some(permissions) = plugin_env.permissions.lock().unwrap().as_ref() {
-        if permissions.contains(&permission) {
+        if permissions.contains(&permission)
+            && command_in_permission_scope(plugin_env, permission, command)
+        {
             return (PermissionStatus::Granted, None);
         }
     }
 
     (PermissionStatus::Denied, Some(permission))
 }
+
+fn command_in_permission_scope(
+    plugin_env: &PluginEnv,
+    permission: PermissionType,
+    command: &PluginCommand,
+) -> bool {
+    // the part of the command a permission scope applies to: the program to run, the host to
+    // send a web request to or the folder to scan
+    let scoped_value = match command {
+        PluginCommand::OpenCommandPane(command_to_run, ..)
+        | PluginCommand::OpenCommandPaneFloating(command_to_run, ..)
+        | PluginCommand::OpenCommandPaneInPlace(command_to_run, ..)
+        | PluginCommand::OpenCommandPaneBackground(command_to_run, ..) => {
+            command_to_run.path.display().to_string()
+        },
+        PluginCommand::RunCommand(command_line, ..) | PluginCommand::ExecCmd(command_line) => {
+            command_line.first().cloned().unwrap_or_default()
+        },
+        PluginCommand::WebRequest(url, ..) => Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_owned()))
+            .unwrap_or_default(),
+        PluginCommand::ScanHostFolder(folder) => folder.display().to_string(),
+        _ => return true,
+    };
+    plugin_env
+        .permission_scopes
+        .lock()
+        .unwrap()
+        .allows(permission, &scoped_value)
+}
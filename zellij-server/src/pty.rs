@@ -2,7 +2,10 @@ use crate::background_jobs::BackgroundJob;
 use crate::terminal_bytes::TerminalBytes;
 use crate::{
     panes::PaneId,
-    plugins::{PluginId, PluginInstruction},
+    plugins::{
+        watch_filesystem::{watch_command_pane_files, CommandPaneWatcher, WatchedFilesChange},
+        PluginId, PluginInstruction,
+    },
     screen::ScreenInstruction,
     session_layout_metadata::SessionLayoutMetadata,
     thread_bus::{Bus, ThreadSenders},
@@ -14,6 +17,7 @@ use std::{
     collections::{BTreeMap, HashMap},
     os::unix::io::RawFd,
     path::PathBuf,
+    time::Instant,
 };
 use zellij_utils::nix::unistd::Pid;
 use zellij_utils::{
@@ -22,7 +26,7 @@ use zellij_utils::{
     errors::prelude::*,
     errors::{ContextType, PtyContext},
     input::{
-        command::{OpenFilePayload, RunCommand, TerminalAction},
+        command::{OpenFilePayload, RestartPolicy, RunCommand, TerminalAction},
         layout::{FloatingPaneLayout, Layout, Run, RunPluginOrAlias, TiledPaneLayout},
     },
     pane_size::Size,
@@ -73,6 +77,8 @@ pub enum PtyInstruction {
     ClosePane(PaneId),
     CloseTab(Vec<PaneId>),
     ReRunCommandInPane(PaneId, RunCommand),
+    CommandPaneExited(PaneId, Option<i32>, RunCommand, Option<usize>), // exit status, tab index
+    WatchedFilesChanged(u32),                                          // terminal id
     DropToShellInPane {
         pane_id: PaneId,
         shell: Option<PathBuf>,
@@ -120,6 +126,8 @@ impl From<&PtyInstruction> for PtyContext {
             PtyInstruction::CloseTab(_) => PtyContext::CloseTab,
             PtyInstruction::NewTab(..) => PtyContext::NewTab,
             PtyInstruction::ReRunCommandInPane(..) => PtyContext::ReRunCommandInPane,
+            PtyInstruction::CommandPaneExited(..) => PtyContext::CommandPaneExited,
+            PtyInstruction::WatchedFilesChanged(..) => PtyContext::WatchedFilesChanged,
             PtyInstruction::DropToShellInPane { .. } => PtyContext::DropToShellInPane,
             PtyInstruction::SpawnInPlaceTerminal(..) => PtyContext::SpawnInPlaceTerminal,
             PtyInstruction::DumpLayout(..) => PtyContext::DumpLayout,
@@ -141,6 +149,9 @@ pub(crate) struct Pty {
     debug_to_file: bool,
     task_handles: HashMap<u32, JoinHandle<()>>, // terminal_id to join-handle
     default_editor: Option<PathBuf>,
+    restart_counts: HashMap<u32, (usize, Instant)>, // terminal_id => (times restarted by its
+    // restart policy, when it was last restarted)
+    command_pane_watchers: HashMap<u32, CommandPaneWatcher>, // terminal_id => its file watcher
 }
 
 pub(crate) fn pty_thread_main(mut pty: Pty, layout: Box<Layout>) -> Result<()> {
@@ -620,6 +631,18 @@ pub(crate) fn pty_thread_main(mut pty: Pty, layout: Box<Layout>) -> Result<()> {
                     },
                 }
             },
+            PtyInstruction::CommandPaneExited(pane_id, exit_status, run_command, tab_index) => {
+                pty.handle_command_pane_exit(pane_id, exit_status, run_command, tab_index)
+                    .with_context(|| format!("failed to handle exit of pane {:?}", pane_id))
+                    .non_fatal();
+            },
+            PtyInstruction::WatchedFilesChanged(terminal_id) => {
+                pty.handle_watched_files_changed(terminal_id)
+                    .with_context(|| {
+                        format!("failed to handle file changes for pane {}", terminal_id)
+                    })
+                    .non_fatal();
+            },
             PtyInstruction::DropToShellInPane {
                 pane_id,
                 shell,
@@ -796,6 +819,8 @@ impl Pty {
             task_handles: HashMap::new(),
             default_editor,
             originating_plugins: HashMap::new(),
+            restart_counts: HashMap::new(),
+            command_pane_watchers: HashMap::new(),
         }
     }
     pub fn get_default_terminal(
@@ -926,11 +951,15 @@ impl Pty {
             return Ok((terminal_id, starts_held));
         }
 
+        let command_to_watch = match &terminal_action {
+            TerminalAction::RunCommand(run_command) => Some(run_command.clone()),
+            _ => None,
+        };
         let originating_command_plugin = Arc::new(originating_command_plugin.clone());
         let originating_edit_plugin = Arc::new(originating_edit_plugin.clone());
         let quit_cb = Box::new({
             let senders = self.bus.senders.clone();
-            move |pane_id, exit_status, command| {
+            move |pane_id, exit_status, command: RunCommand| {
                 // if this command originated in a plugin, we send the plugin an event letting it
                 // know the command exited and some other useful information
                 if let PaneId::Terminal(pane_id) = pane_id {
@@ -960,7 +989,14 @@ impl Pty {
                     }
                 }
 
-                if hold_on_close {
+                if command.is_supervised() {
+                    let _ = senders.send_to_pty(PtyInstruction::CommandPaneExited(
+                        pane_id,
+                        exit_status,
+                        command,
+                        None,
+                    ));
+                } else if hold_on_close {
                     let _ = senders.send_to_screen(ScreenInstruction::HoldPane(
                         pane_id,
                         exit_status,
@@ -1005,6 +1041,9 @@ impl Pty {
 
         self.task_handles.insert(terminal_id, terminal_bytes);
         self.id_to_child_pid.insert(terminal_id, child_fd);
+        if let Some(command_to_watch) = command_to_watch {
+            self.watch_command_pane_files_if_needed(terminal_id, &command_to_watch);
+        }
         let starts_held = false;
         Ok((terminal_id, starts_held))
     }
@@ -1165,8 +1204,15 @@ impl Pty {
                 let hold_on_close = command.hold_on_close;
                 let quit_cb = Box::new({
                     let senders = self.bus.senders.clone();
-                    move |pane_id, exit_status, command| {
-                        if hold_on_close {
+                    move |pane_id, exit_status, command: RunCommand| {
+                        if command.is_supervised() {
+                            let _ = senders.send_to_pty(PtyInstruction::CommandPaneExited(
+                                pane_id,
+                                exit_status,
+                                command,
+                                Some(tab_index),
+                            ));
+                        } else if hold_on_close {
                             let _ = senders.send_to_screen(ScreenInstruction::HoldPane(
                                 pane_id,
                                 exit_status,
@@ -1219,6 +1265,7 @@ impl Pty {
                     {
                         Ok((terminal_id, pid_primary, child_fd)) => {
                             self.id_to_child_pid.insert(terminal_id, child_fd);
+                            self.watch_command_pane_files_if_needed(terminal_id, &command);
                             Ok(Some((
                                 terminal_id,
                                 starts_held,
@@ -1324,6 +1371,8 @@ impl Pty {
         match id {
             PaneId::Terminal(id) => {
                 self.task_handles.remove(&id);
+                self.restart_counts.remove(&id);
+                self.command_pane_watchers.remove(&id);
                 if let Some(child_fd) = self.id_to_child_pid.remove(&id) {
                     task::block_on(async {
                         let err_context = || format!("failed to run async task for pane {id}");
@@ -1364,6 +1413,159 @@ impl Pty {
             self.active_panes.insert(client_id, pane_id);
         }
     }
+    pub fn handle_command_pane_exit(
+        &mut self,
+        pane_id: PaneId,
+        exit_status: Option<i32>,
+        run_command: RunCommand,
+        tab_index: Option<usize>,
+    ) -> Result<()> {
+        let err_context = || format!("failed to handle exit of command pane {:?}", pane_id);
+        let terminal_id = match pane_id {
+            PaneId::Terminal(terminal_id) => terminal_id,
+            PaneId::Plugin(_) => {
+                return Err(anyhow!("plugin panes do not run commands")).with_context(err_context)
+            },
+        };
+        if !self.id_to_child_pid.contains_key(&terminal_id) {
+            // the pane was closed, its command exited because it was killed
+            return Ok(());
+        }
+        let should_rerun_because_of_changes = self
+            .command_pane_watchers
+            .get_mut(&terminal_id)
+            .map(|watcher| watcher.command_exited())
+            .unwrap_or(false);
+        if should_rerun_because_of_changes {
+            self.bus
+                .senders
+                .send_to_screen(ScreenInstruction::HoldPane(
+                    pane_id,
+                    exit_status,
+                    run_command,
+                    tab_index,
+                    None,
+                ))
+                .and_then(|_| {
+                    self.bus
+                        .senders
+                        .send_to_screen(ScreenInstruction::RerunCommandPane(terminal_id))
+                })
+                .with_context(err_context)?;
+            return Ok(());
+        }
+        let restart_count = self
+            .restart_counts
+            .get(&terminal_id)
+            .map(|(restart_count, restarted_at)| {
+                RestartPolicy::restart_count_after(*restart_count, restarted_at.elapsed())
+            })
+            .unwrap_or(0);
+        let hold_on_close = run_command.hold_on_close;
+        let should_restart = run_command
+            .restart_policy
+            .should_restart(exit_status, restart_count);
+        if !should_restart && !hold_on_close {
+            self.restart_counts.remove(&terminal_id);
+            self.command_pane_watchers.remove(&terminal_id);
+            return self
+                .bus
+                .senders
+                .send_to_screen(ScreenInstruction::ClosePane(pane_id, None))
+                .with_context(err_context);
+        }
+        // we hold the pane in both cases, so that its exit status is displayed while waiting for
+        // the restart and so that it can be re-run by the user once we stop restarting it
+        self.bus
+            .senders
+            .send_to_screen(ScreenInstruction::HoldPane(
+                pane_id,
+                exit_status,
+                run_command,
+                tab_index,
+                None,
+            ))
+            .with_context(err_context)?;
+        if should_restart {
+            let restart_count = restart_count + 1;
+            let backoff = RestartPolicy::backoff(restart_count - 1);
+            self.restart_counts
+                .insert(terminal_id, (restart_count, Instant::now() + backoff));
+            let senders = self.bus.senders.clone();
+            task::spawn(async move {
+                task::sleep(backoff).await;
+                let _ = senders.send_to_screen(ScreenInstruction::RestartCommandPane(
+                    terminal_id,
+                    restart_count,
+                    exit_status,
+                ));
+            });
+        }
+        Ok(())
+    }
+    pub fn handle_watched_files_changed(&mut self, terminal_id: u32) -> Result<()> {
+        let err_context = || format!("failed to handle file changes for pane {}", terminal_id);
+        let watcher = match self.command_pane_watchers.get_mut(&terminal_id) {
+            Some(watcher) => watcher,
+            None => return Ok(()),
+        };
+        match watcher.files_changed() {
+            WatchedFilesChange::RerunNow => {
+                self.bus
+                    .senders
+                    .send_to_screen(ScreenInstruction::RerunCommandPane(terminal_id))
+                    .with_context(err_context)?;
+            },
+            WatchedFilesChange::KillAndRerunOnExit => {
+                if let Some(child_pid) = self.id_to_child_pid.get(&terminal_id) {
+                    self.bus
+                        .os_input
+                        .as_ref()
+                        .context("no OS I/O interface found")
+                        .and_then(|os_input| os_input.kill(Pid::from_raw(*child_pid)))
+                        .with_context(err_context)?;
+                }
+            },
+            WatchedFilesChange::RerunOnExit | WatchedFilesChange::AlreadyQueued => {},
+        }
+        Ok(())
+    }
+    fn watch_command_pane_files_if_needed(&mut self, terminal_id: u32, run_command: &RunCommand) {
+        if let Some(watcher) = self.command_pane_watchers.get_mut(&terminal_id) {
+            watcher.command_started();
+            return;
+        }
+        if run_command.rerun_on_change.is_empty() {
+            return;
+        }
+        let cwd = match run_command
+            .cwd
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+        {
+            Some(cwd) => cwd,
+            None => {
+                log::error!("Cannot watch files for pane {}: no cwd", terminal_id);
+                return;
+            },
+        };
+        match watch_command_pane_files(
+            self.bus.senders.clone(),
+            terminal_id,
+            &cwd,
+            &run_command.rerun_on_change,
+        ) {
+            Ok(debouncer) => {
+                self.command_pane_watchers.insert(
+                    terminal_id,
+                    CommandPaneWatcher::new(debouncer, run_command.kill_on_change),
+                );
+            },
+            Err(e) => {
+                log::error!("Failed to watch files for pane {}: {:?}", terminal_id, e);
+            },
+        }
+    }
     pub fn rerun_command_in_pane(
         &mut self,
         pane_id: PaneId,
@@ -1380,10 +1582,11 @@ impl Pty {
                 let _ = self.id_to_child_pid.remove(&id); // if all is wlel, this shouldn't be here
 
                 let hold_on_close = run_command.hold_on_close;
+                let command_to_watch = run_command.clone();
                 let originating_plugin = Arc::new(run_command.originating_plugin.clone());
                 let quit_cb = Box::new({
                     let senders = self.bus.senders.clone();
-                    move |pane_id, exit_status, command| {
+                    move |pane_id, exit_status, command: RunCommand| {
                         if let PaneId::Terminal(pane_id) = pane_id {
                             if let Some(originating_plugin) = originating_plugin.as_ref() {
                                 let update_event = Event::CommandPaneExited(
@@ -1398,7 +1601,14 @@ impl Pty {
                                 )]));
                             }
                         }
-                        if hold_on_close {
+                        if command.is_supervised() {
+                            let _ = senders.send_to_pty(PtyInstruction::CommandPaneExited(
+                                pane_id,
+                                exit_status,
+                                command,
+                                None,
+                            ));
+                        } else if hold_on_close {
                             let _ = senders.send_to_screen(ScreenInstruction::HoldPane(
                                 pane_id,
                                 exit_status,
@@ -1444,6 +1654,7 @@ impl Pty {
 
                 self.task_handles.insert(id, terminal_bytes);
                 self.id_to_child_pid.insert(id, child_fd);
+                self.watch_command_pane_files_if_needed(id, &command_to_watch);
                 if let Some(originating_plugin) = self.originating_plugins.get(&id) {
                     self.bus
                         .senders
@@ -1,10 +1,11 @@
-use std::collections::{BTreeMap, HashSet, VecDeque};
-use std::sync::{Arc, RwLock};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, RwLock};
 
 use crate::thread_bus::ThreadSenders;
 use crate::{
     os_input_output::ServerOsApi,
-    panes::PaneId,
+    panes::{xparse_color, AnsiCode, PaneId},
     plugins::PluginInstruction,
     pty::{ClientTabIndexOrPaneId, PtyInstruction},
     screen::ScreenInstruction,
@@ -14,23 +15,35 @@ use std::thread;
 use std::time::Duration;
 use uuid::Uuid;
 use zellij_utils::{
+    async_std::task,
     channels::SenderWithContext,
-    data::{Direction, Event, InputMode, PluginCapabilities, ResizeStrategy},
+    data::{
+        Direction, Event, InputMode, KeyWithModifier, PaletteColor, PluginCapabilities,
+        ResizeStrategy, ThemeHue,
+    },
     errors::prelude::*,
     input::{
         actions::{Action, SearchDirection, SearchOption},
         command::TerminalAction,
         get_mode_info,
-        keybinds::Keybinds,
+        keybinds::{FocusedPaneInfo, KeySequenceMatch, Keybinds},
         layout::Layout,
+        macros::MacroInstruction,
+        mouse::MouseButtonWithModifier,
     },
     ipc::{
         ClientAttributes, ClientToServerMsg, ExitReason, IpcReceiverWithContext, ServerToClientMsg,
     },
+    position::Position,
+    shared::detect_theme_hue,
 };
 
 use crate::ClientId;
 
+const DEFAULT_KEY_SEQUENCE_TIMEOUT_MS: u64 = 1000;
+// macros can play other macros, this stops a macro that plays itself
+const MAX_NESTED_MACROS: usize = 8;
+
 pub(crate) fn route_action(
     action: Action,
     client_id: ClientId,
@@ -96,7 +109,7 @@ pub(crate) fn route_action(
         Action::SwitchToMode(mode) => {
             let attrs = &client_attributes;
             senders
-                .send_to_server(ServerInstruction::ChangeMode(client_id, mode))
+                .send_to_server(ServerInstruction::ChangeMode(client_id, mode.clone()))
                 .with_context(err_context)?;
             senders
                 .send_to_screen(ScreenInstruction::ChangeMode(
@@ -346,17 +359,19 @@ pub(crate) fn route_action(
                     None,
                     None,
                     Event::ModeUpdate(get_mode_info(
-                        input_mode,
+                        input_mode.clone(),
                         attrs,
                         capabilities,
                         &client_keybinds,
-                        Some(default_mode),
+                        Some(default_mode.clone()),
                     )),
                 )]))
                 .with_context(err_context)?;
 
             senders
-                .send_to_server(ServerInstruction::ChangeModeForAllClients(input_mode))
+                .send_to_server(ServerInstruction::ChangeModeForAllClients(
+                    input_mode.clone(),
+                ))
                 .with_context(err_context)?;
 
             senders
@@ -463,6 +478,15 @@ pub(crate) fn route_action(
                 .send_to_screen(ScreenInstruction::UndoRenamePane(client_id))
                 .with_context(err_context)?;
         },
+        Action::SetPaneColor(pane_id, pane_colors) => {
+            senders
+                .send_to_screen(ScreenInstruction::SetPaneColors(
+                    pane_id.map(|p| p.into()),
+                    pane_colors,
+                    client_id,
+                ))
+                .with_context(err_context)?;
+        },
         Action::Run(command) => {
             let run_cmd = Some(TerminalAction::RunCommand(command.clone().into()));
             let pty_instr = match command.direction {
@@ -605,6 +629,31 @@ pub(crate) fn route_action(
                 .send_to_screen(ScreenInstruction::MiddleClick(point, client_id))
                 .with_context(err_context)?;
         },
+        Action::FocusPaneAt(point) => {
+            senders
+                .send_to_screen(ScreenInstruction::FocusPaneAt(point, client_id))
+                .with_context(err_context)?;
+        },
+        Action::OpenHyperlinkAt(point) => {
+            senders
+                .send_to_screen(ScreenInstruction::OpenHyperlinkAt(point, client_id))
+                .with_context(err_context)?;
+        },
+        Action::Paste => {
+            senders
+                .send_to_screen(ScreenInstruction::Paste(client_id))
+                .with_context(err_context)?;
+        },
+        Action::TogglePaneInSelection => {
+            senders
+                .send_to_screen(ScreenInstruction::TogglePaneInSelection(client_id))
+                .with_context(err_context)?;
+        },
+        Action::ClearPaneSelection => {
+            senders
+                .send_to_screen(ScreenInstruction::ClearPaneSelection(client_id))
+                .with_context(err_context)?;
+        },
         Action::LeftMouseRelease(point) => {
             senders
                 .send_to_screen(ScreenInstruction::LeftMouseRelease(point, client_id))
@@ -688,6 +737,9 @@ pub(crate) fn route_action(
                 .with_context(err_context)?;
         },
         Action::ToggleMouseMode => {}, // Handled client side
+        Action::StartMacroRecording(..) | Action::StopMacroRecording | Action::PlayMacro(..) => {
+            log::warn!("Macros can only be recorded and played from keybindings");
+        },
         Action::PreviousSwapLayout => {
             senders
                 .send_to_screen(ScreenInstruction::PreviousSwapLayout(client_id))
@@ -987,6 +1039,697 @@ macro_rules! send_to_screen_or_retry_queue {
     }};
 }
 
+type KeyWithRawBytes = (KeyWithModifier, Vec<u8>, bool); // bool -> is_kitty_keyboard_protocol
+
+/// The keys of a multi-key sequence a client typed so far, kept with their raw bytes so that they
+/// can be sent on to the pane unchanged if the sequence is not completed
+#[derive(Default)]
+struct PendingKeySequence {
+    keys: Vec<KeyWithRawBytes>,
+    // bumped whenever the pending keys change, so that a stale timeout does not flush them
+    generation: usize,
+}
+
+/// A macro replay the client is sending the keys of
+struct MacroReplay {
+    depth: usize,
+    // the keys of the replay are matched against the mode the replay switched to rather than the
+    // session's, which the server thread updates once it gets to it
+    input_mode: InputMode,
+}
+
+/// The client records and replays macros, it numbers the keys it sends the same way they are
+/// counted here so that recordings can be started and stopped at the keys bound to doing so
+#[derive(Default)]
+struct MacroState {
+    keys_received: usize,
+    replay: Option<MacroReplay>,
+}
+
+/// What is kept between the keys of a client, shared by its route thread and the server thread
+/// so that keys re-injected by plugins continue the same key sequence and macro replay
+#[derive(Default, Clone)]
+pub(crate) struct ClientKeyState {
+    pending_key_sequence: Arc<Mutex<PendingKeySequence>>,
+    macro_state: Arc<Mutex<MacroState>>,
+}
+
+pub(crate) type ClientKeyStates = Arc<Mutex<HashMap<ClientId, ClientKeyState>>>;
+
+// `keys` are the numbers of the keys the actions are bound to, the recording of a macro starts
+// after them or stops before them
+fn route_actions_for_client(
+    actions: Vec<Action>,
+    keys: Range<usize>,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    keybinds: &Keybinds,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+    mut seen_cli_pipes: Option<&mut HashSet<String>>,
+) -> Result<bool> {
+    let err_context = || format!("failed to route actions for client {client_id}");
+    let mut should_break = false;
+    // a recording started by these actions starts in the mode they leave the client in
+    let mode_after_actions = actions.iter().rev().find_map(|action| match action {
+        Action::SwitchToMode(input_mode) => Some(input_mode.clone()),
+        _ => None,
+    });
+    for action in actions {
+        match action {
+            Action::StartMacroRecording(name) => {
+                let macro_state = macro_state.lock().to_anyhow().with_context(err_context)?;
+                if macro_state.replay.is_some() {
+                    log::warn!("Cannot record a macro while a macro is being replayed");
+                    continue;
+                }
+                let start_mode = match mode_after_actions.as_ref() {
+                    Some(input_mode) => input_mode.clone(),
+                    None => client_input_mode(client_id, session, &macro_state)
+                        .unwrap_or(InputMode::Normal),
+                };
+                os_input
+                    .send_to_client(
+                        client_id,
+                        ServerToClientMsg::Macro(MacroInstruction::StartRecording {
+                            name,
+                            start_mode,
+                            from_key: keys.end,
+                        }),
+                    )
+                    .with_context(err_context)?;
+            },
+            Action::StopMacroRecording => {
+                os_input
+                    .send_to_client(
+                        client_id,
+                        ServerToClientMsg::Macro(MacroInstruction::StopRecording {
+                            until_key: keys.start,
+                        }),
+                    )
+                    .with_context(err_context)?;
+            },
+            Action::PlayMacro(name) => {
+                play_macro(&name, client_id, session, os_input, macro_state)?;
+            },
+            action => {
+                if let Action::SwitchToMode(input_mode) = &action {
+                    let mut macro_state =
+                        macro_state.lock().to_anyhow().with_context(err_context)?;
+                    if let Some(replay) = macro_state.replay.as_mut() {
+                        replay.input_mode = input_mode.clone();
+                    }
+                }
+                if route_action(
+                    action,
+                    client_id,
+                    None,
+                    session.senders.clone(),
+                    session.capabilities,
+                    session.client_attributes.clone(),
+                    session.default_shell.clone(),
+                    session.layout.clone(),
+                    seen_cli_pipes.as_deref_mut(),
+                    keybinds.clone(),
+                    session
+                        .session_configuration
+                        .get_client_configuration(&client_id)
+                        .options
+                        .default_mode
+                        .unwrap_or(InputMode::Normal)
+                        .clone(),
+                )? {
+                    should_break = true;
+                }
+            },
+        }
+    }
+    Ok(should_break)
+}
+
+// asks the client to replay the keys of the macro, unless it is played from too many replays
+// (eg. a macro playing itself)
+fn play_macro(
+    name: &str,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+) -> Result<()> {
+    let err_context = || format!("failed to play macro {name} for client {client_id}");
+    let macro_to_play = match session
+        .session_configuration
+        .get_client_configuration(&client_id)
+        .macros
+        .get(name)
+    {
+        Some(macro_to_play) => macro_to_play.clone(),
+        None => {
+            log::warn!("No macro named {:?}", name);
+            return Ok(());
+        },
+    };
+    let depth = macro_state
+        .lock()
+        .to_anyhow()
+        .with_context(err_context)?
+        .replay
+        .as_ref()
+        .map(|replay| replay.depth)
+        .unwrap_or(0)
+        + 1;
+    if depth > MAX_NESTED_MACROS {
+        log::warn!("Not playing macro {:?}, macros are nested too deep", name);
+        return Ok(());
+    }
+    os_input
+        .send_to_client(
+            client_id,
+            ServerToClientMsg::Macro(MacroInstruction::Play {
+                macro_to_play,
+                depth,
+            }),
+        )
+        .with_context(err_context)
+}
+
+// the keys of a replay are routed to the panes selected in the tab rather than the focused one,
+// so the screen is told when a replay starts and ends
+fn start_macro_replay(
+    depth: usize,
+    start_mode: Option<InputMode>,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+) -> Result<bool> {
+    let err_context = || format!("failed to start macro replay for client {client_id}");
+    let keys_received = {
+        let mut macro_state = macro_state.lock().to_anyhow().with_context(err_context)?;
+        let input_mode =
+            client_input_mode(client_id, session, &macro_state).unwrap_or(InputMode::Normal);
+        macro_state.replay = Some(MacroReplay { depth, input_mode });
+        macro_state.keys_received
+    };
+    session
+        .senders
+        .send_to_screen(ScreenInstruction::MacroReplayStarted(client_id))
+        .with_context(err_context)?;
+    match start_mode {
+        Some(start_mode) => route_actions_for_client(
+            vec![Action::SwitchToMode(start_mode)],
+            keys_received..keys_received,
+            client_id,
+            session,
+            &session
+                .session_configuration
+                .get_client_keybinds(&client_id),
+            os_input,
+            macro_state,
+            None,
+        ),
+        None => Ok(false),
+    }
+}
+
+fn end_macro_replay(
+    client_id: ClientId,
+    session: &SessionMetaData,
+    macro_state: &Arc<Mutex<MacroState>>,
+) -> Result<()> {
+    let err_context = || format!("failed to end macro replay for client {client_id}");
+    macro_state
+        .lock()
+        .to_anyhow()
+        .with_context(err_context)?
+        .replay = None;
+    session
+        .senders
+        .send_to_screen(ScreenInstruction::MacroReplayEnded(client_id))
+        .with_context(err_context)
+}
+
+fn client_input_mode(
+    client_id: ClientId,
+    session: &SessionMetaData,
+    macro_state: &MacroState,
+) -> Option<InputMode> {
+    match macro_state.replay.as_ref() {
+        Some(replay) => Some(replay.input_mode.clone()),
+        None => session.current_input_modes.get(&client_id).cloned(),
+    }
+}
+
+fn client_keybinds_and_mode(
+    client_id: ClientId,
+    session: &SessionMetaData,
+    macro_state: &Arc<Mutex<MacroState>>,
+) -> Result<Option<(Keybinds, InputMode)>> {
+    let replay_mode = macro_state
+        .lock()
+        .to_anyhow()
+        .context("failed to get keybindings")?
+        .replay
+        .as_ref()
+        .map(|replay| replay.input_mode.clone());
+    Ok(session
+        .get_client_keybinds_and_mode(&client_id)
+        .map(|(keybinds, input_mode)| {
+            (keybinds, replay_mode.unwrap_or_else(|| input_mode.clone()))
+        }))
+}
+
+// the focused pane as last reported by the screen thread, the foreground process of a terminal
+// pane changes without the screen knowing so it is looked up here
+fn get_focused_pane_info(
+    client_id: ClientId,
+    session: &SessionMetaData,
+    os_input: &dyn ServerOsApi,
+) -> FocusedPaneInfo {
+    match session.focused_panes.get(&client_id) {
+        Some((terminal_id, focused_pane_info)) => {
+            let mut focused_pane_info = focused_pane_info.clone();
+            focused_pane_info.running_command =
+                terminal_id.and_then(|t| os_input.get_foreground_process_name(t));
+            focused_pane_info
+        },
+        None => FocusedPaneInfo::default(),
+    }
+}
+
+// routes the actions bound to the mouse button, falling back to what the button does by default
+// (eg. focusing the pane under the mouse on a left click)
+//
+// bindings with a target need to know what is under the mouse, the screen thread picks their
+// actions and sends them back to be routed with route_mouse_actions
+fn route_mouse_press(
+    button: MouseButtonWithModifier,
+    position: Position,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+    seen_cli_pipes: Option<&mut HashSet<String>>,
+) -> Result<bool> {
+    let mouse_bindings = session
+        .session_configuration
+        .get_client_configuration(&client_id)
+        .mouse_bindings;
+    if mouse_bindings.has_targeted_bindings(&button) {
+        session
+            .senders
+            .send_to_screen(ScreenInstruction::MousePress(
+                button,
+                mouse_bindings,
+                position,
+                client_id,
+            ))
+            .with_context(|| format!("failed to route mouse press for client {client_id}"))?;
+        return Ok(false);
+    }
+    let actions = mouse_bindings.actions_for_press(&button, None, position);
+    let keys_received = macro_state
+        .lock()
+        .to_anyhow()
+        .context("failed to route mouse press")?
+        .keys_received;
+    route_actions_for_client(
+        actions,
+        keys_received..keys_received,
+        client_id,
+        session,
+        &session
+            .session_configuration
+            .get_client_keybinds(&client_id),
+        os_input,
+        macro_state,
+        seen_cli_pipes,
+    )
+}
+
+// the actions the single-key (and conditional) bindings of the mode have for the key, falling
+// back to the mode's default action (eg. writing the key to the focused pane)
+fn actions_for_key(
+    key: &KeyWithModifier,
+    raw_bytes: Vec<u8>,
+    is_kitty_keyboard_protocol: bool,
+    input_mode: &InputMode,
+    keybinds: &Keybinds,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    os_input: &dyn ServerOsApi,
+) -> Vec<Action> {
+    if keybinds.has_conditional_keybinds_for_key(input_mode, key) {
+        keybinds.get_actions_for_key_in_mode_with_focused_pane(
+            input_mode,
+            key,
+            raw_bytes,
+            is_kitty_keyboard_protocol,
+            &get_focused_pane_info(client_id, session, os_input),
+        )
+    } else {
+        keybinds.get_actions_for_key_in_mode_or_default_action(
+            input_mode,
+            key,
+            raw_bytes,
+            is_kitty_keyboard_protocol,
+        )
+    }
+}
+
+// routes each key through the single-key (and conditional) bindings of the current mode,
+// `first_key` is the number of the first of the keys
+fn route_keys(
+    keys: Vec<KeyWithRawBytes>,
+    first_key: usize,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+    mut seen_cli_pipes: Option<&mut HashSet<String>>,
+) -> Result<bool> {
+    let mut should_break = false;
+    for (key_number, (key, raw_bytes, is_kitty_keyboard_protocol)) in
+        (first_key..).zip(keys.into_iter())
+    {
+        match client_keybinds_and_mode(client_id, session, macro_state)? {
+            Some((keybinds, input_mode)) => {
+                let actions = actions_for_key(
+                    &key,
+                    raw_bytes,
+                    is_kitty_keyboard_protocol,
+                    &input_mode,
+                    &keybinds,
+                    client_id,
+                    session,
+                    os_input,
+                );
+                if route_actions_for_client(
+                    actions,
+                    key_number..key_number + 1,
+                    client_id,
+                    session,
+                    &keybinds,
+                    os_input,
+                    macro_state,
+                    seen_cli_pipes.as_deref_mut(),
+                )? {
+                    should_break = true;
+                }
+            },
+            None => {
+                log::error!("Failed to get keybindings for client");
+            },
+        }
+    }
+    Ok(should_break)
+}
+
+fn route_key_or_key_sequence(
+    key: KeyWithRawBytes,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    session_data: &Arc<RwLock<Option<SessionMetaData>>>,
+    pending_key_sequence: &Arc<Mutex<PendingKeySequence>>,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+    seen_cli_pipes: Option<&mut HashSet<String>>,
+) -> Result<bool> {
+    let err_context = || format!("failed to route key for client {client_id}");
+    let input_mode = match client_input_mode(
+        client_id,
+        session,
+        &*macro_state.lock().to_anyhow().with_context(err_context)?,
+    ) {
+        Some(input_mode) => input_mode,
+        None => {
+            log::error!("Failed to get input mode for client");
+            return Ok(false);
+        },
+    };
+    if let Some(plugin_id) = session
+        .key_intercepts
+        .intercepting_plugin(&key.0, &input_mode)
+    {
+        // the plugin decides what to do with the key, it can re-inject it if it wants it to be
+        // handled as usual
+        session
+            .senders
+            .send_to_plugin(PluginInstruction::Update(vec![(
+                Some(plugin_id),
+                Some(client_id),
+                Event::InterceptedKey(key.0),
+            )]))
+            .with_context(err_context)?;
+        return Ok(false);
+    }
+    route_key_through_key_sequences(
+        key,
+        client_id,
+        session,
+        session_data,
+        pending_key_sequence,
+        os_input,
+        macro_state,
+        seen_cli_pipes,
+    )
+}
+
+fn route_key_through_key_sequences(
+    key: KeyWithRawBytes,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    session_data: &Arc<RwLock<Option<SessionMetaData>>>,
+    pending_key_sequence: &Arc<Mutex<PendingKeySequence>>,
+    os_input: &dyn ServerOsApi,
+    macro_state: &Arc<Mutex<MacroState>>,
+    mut seen_cli_pipes: Option<&mut HashSet<String>>,
+) -> Result<bool> {
+    let err_context = || format!("failed to route key for client {client_id}");
+    let (keybinds, input_mode) = match client_keybinds_and_mode(client_id, session, macro_state)? {
+        Some(keybinds_and_mode) => keybinds_and_mode,
+        None => {
+            log::error!("Failed to get keybindings for client");
+            return Ok(false);
+        },
+    };
+    // the key was counted when it was received, so it is the last one (a re-injected key was
+    // counted when it was intercepted)
+    let keys_received = macro_state
+        .lock()
+        .to_anyhow()
+        .with_context(err_context)?
+        .keys_received;
+    let mut pending = pending_key_sequence
+        .lock()
+        .to_anyhow()
+        .with_context(err_context)?;
+    if pending.keys.is_empty() && !keybinds.has_key_sequences_in_mode(&input_mode) {
+        drop(pending);
+        return route_keys(
+            vec![key],
+            keys_received.saturating_sub(1),
+            client_id,
+            session,
+            os_input,
+            macro_state,
+            seen_cli_pipes,
+        );
+    }
+    let had_pending_keys = !pending.keys.is_empty();
+    let first_pending_key = keys_received.saturating_sub(pending.keys.len() + 1);
+    let mut keys_to_route = vec![];
+    let mut sequence_actions = vec![];
+    let typed_keys: Vec<KeyWithModifier> = pending
+        .keys
+        .iter()
+        .map(|(k, _, _)| k.clone())
+        .chain(std::iter::once(key.0.clone()))
+        .collect();
+    match keybinds.match_key_sequence(&input_mode, &typed_keys) {
+        KeySequenceMatch::Complete(actions) => {
+            pending.keys.clear();
+            sequence_actions = actions;
+        },
+        KeySequenceMatch::Partial => {
+            pending.keys.push(key);
+        },
+        KeySequenceMatch::NoMatch => {
+            // the keys typed so far go on as they are, the new key might start a new sequence
+            keys_to_route = pending.keys.drain(..).collect();
+            match keybinds.match_key_sequence(&input_mode, &[key.0.clone()]) {
+                KeySequenceMatch::Partial => pending.keys.push(key),
+                _ => keys_to_route.push(key),
+            }
+        },
+    }
+    pending.generation += 1;
+    let generation = pending.generation;
+    let pending_keys: Vec<KeyWithModifier> =
+        pending.keys.iter().map(|(k, _, _)| k.clone()).collect();
+    drop(pending);
+
+    if had_pending_keys || !pending_keys.is_empty() {
+        session
+            .senders
+            .send_to_screen(ScreenInstruction::UpdatePendingKeySequence(
+                pending_keys.clone(),
+                client_id,
+            ))
+            .with_context(err_context)?;
+    }
+    if !pending_keys.is_empty() {
+        let timeout = session
+            .session_configuration
+            .get_client_configuration(&client_id)
+            .options
+            .key_sequence_timeout
+            .unwrap_or(DEFAULT_KEY_SEQUENCE_TIMEOUT_MS);
+        flush_key_sequence_after_timeout(
+            Duration::from_millis(timeout),
+            generation,
+            client_id,
+            session_data.clone(),
+            pending_key_sequence.clone(),
+            os_input.box_clone(),
+            macro_state.clone(),
+        );
+    }
+    // the keys routed on their own are the oldest ones, a completed sequence is all of the keys
+    let mut should_break = route_keys(
+        keys_to_route,
+        first_pending_key,
+        client_id,
+        session,
+        os_input,
+        macro_state,
+        seen_cli_pipes.as_deref_mut(),
+    )?;
+    if route_actions_for_client(
+        sequence_actions,
+        first_pending_key..keys_received,
+        client_id,
+        session,
+        &keybinds,
+        os_input,
+        macro_state,
+        seen_cli_pipes,
+    )? {
+        should_break = true;
+    }
+    Ok(should_break)
+}
+
+/// Routes a key a plugin intercepted and handed back, through the client's keybindings and on to
+/// its focused pane as if it was typed (without being intercepted again)
+pub(crate) fn route_mouse_actions(
+    actions: Vec<Action>,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    client_key_state: &ClientKeyState,
+    os_input: &dyn ServerOsApi,
+) -> Result<bool> {
+    let keys_received = client_key_state
+        .macro_state
+        .lock()
+        .to_anyhow()
+        .context("failed to route mouse actions")?
+        .keys_received;
+    route_actions_for_client(
+        actions,
+        keys_received..keys_received,
+        client_id,
+        session,
+        &session
+            .session_configuration
+            .get_client_keybinds(&client_id),
+        os_input,
+        &client_key_state.macro_state,
+        None,
+    )
+}
+
+pub(crate) fn route_reinjected_key(
+    key: KeyWithModifier,
+    client_id: ClientId,
+    session: &SessionMetaData,
+    session_data: &Arc<RwLock<Option<SessionMetaData>>>,
+    client_key_state: &ClientKeyState,
+    os_input: &dyn ServerOsApi,
+) -> Result<bool> {
+    let (raw_bytes, is_kitty_keyboard_protocol) = match key.serialize_non_kitty() {
+        Some(serialized) => (serialized.into_bytes(), false),
+        None => (key.serialize_kitty().unwrap_or_default().into_bytes(), true),
+    };
+    route_key_through_key_sequences(
+        (key, raw_bytes, is_kitty_keyboard_protocol),
+        client_id,
+        session,
+        session_data,
+        &client_key_state.pending_key_sequence,
+        os_input,
+        &client_key_state.macro_state,
+        None,
+    )
+}
+
+fn flush_key_sequence_after_timeout(
+    timeout: Duration,
+    generation: usize,
+    client_id: ClientId,
+    session_data: Arc<RwLock<Option<SessionMetaData>>>,
+    pending_key_sequence: Arc<Mutex<PendingKeySequence>>,
+    os_input: Box<dyn ServerOsApi>,
+    macro_state: Arc<Mutex<MacroState>>,
+) {
+    task::spawn(async move {
+        task::sleep(timeout).await;
+        let keys: Vec<KeyWithRawBytes> = match pending_key_sequence.lock() {
+            Ok(mut pending) if pending.generation == generation => {
+                pending.generation += 1;
+                pending.keys.drain(..).collect()
+            },
+            _ => return,
+        };
+        if let Ok(session) = session_data.read() {
+            if let Some(session) = session.as_ref() {
+                let _ =
+                    session
+                        .senders
+                        .send_to_screen(ScreenInstruction::UpdatePendingKeySequence(
+                            vec![],
+                            client_id,
+                        ));
+                let keys_received = match macro_state.lock() {
+                    Ok(macro_state) => macro_state.keys_received,
+                    Err(_) => return,
+                };
+                let first_key = keys_received.saturating_sub(keys.len());
+                if let Err(e) = route_keys(
+                    keys,
+                    first_key,
+                    client_id,
+                    session,
+                    &*os_input,
+                    &macro_state,
+                    None,
+                ) {
+                    log::error!("Failed to flush timed out key sequence: {:?}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Whether the terminal has a light or a dark background, from its response to a background color
+/// query (eg. `rgb:ffff/ffff/ffff`)
+fn color_scheme_from_background_color(background_color_instruction: &str) -> Option<ThemeHue> {
+    match xparse_color(background_color_instruction.as_bytes()) {
+        Some(AnsiCode::RgbCode(rgb)) => Some(detect_theme_hue(PaletteColor::Rgb(rgb))),
+        _ => None,
+    }
+}
+
 pub(crate) fn route_thread_main(
     session_data: Arc<RwLock<Option<SessionMetaData>>>,
     session_state: Arc<RwLock<SessionState>>,
@@ -994,10 +1737,21 @@ pub(crate) fn route_thread_main(
     to_server: SenderWithContext<ServerInstruction>,
     mut receiver: IpcReceiverWithContext<ClientToServerMsg>,
     client_id: ClientId,
+    client_key_states: ClientKeyStates,
 ) -> Result<()> {
     let mut retry_queue = VecDeque::new();
     let err_context = || format!("failed to handle instruction for client {client_id}");
     let mut seen_cli_pipes = HashSet::new();
+    let client_key_state = ClientKeyState::default();
+    client_key_states
+        .lock()
+        .to_anyhow()
+        .with_context(err_context)?
+        .insert(client_id, client_key_state.clone());
+    let ClientKeyState {
+        pending_key_sequence,
+        macro_state,
+    } = client_key_state;
     'route_loop: loop {
         match receiver.recv() {
             Some((instruction, err_ctx)) => {
@@ -1011,43 +1765,68 @@ pub(crate) fn route_thread_main(
                     let mut should_break = false;
                     match instruction {
                         ClientToServerMsg::Key(key, raw_bytes, is_kitty_keyboard_protocol) => {
+                            macro_state
+                                .lock()
+                                .to_anyhow()
+                                .with_context(err_context)?
+                                .keys_received += 1;
+                            if let Some(rlocked_sessions) = rlocked_sessions.as_ref() {
+                                if route_key_or_key_sequence(
+                                    (key, raw_bytes, is_kitty_keyboard_protocol),
+                                    client_id,
+                                    rlocked_sessions,
+                                    &session_data,
+                                    &pending_key_sequence,
+                                    &*os_input,
+                                    &macro_state,
+                                    Some(&mut seen_cli_pipes),
+                                )? {
+                                    should_break = true;
+                                }
+                            }
+                        },
+                        ClientToServerMsg::MacroReplayStarted { depth, start_mode } => {
+                            if let Some(rlocked_sessions) = rlocked_sessions.as_ref() {
+                                if start_macro_replay(
+                                    depth,
+                                    start_mode,
+                                    client_id,
+                                    rlocked_sessions,
+                                    &*os_input,
+                                    &macro_state,
+                                )? {
+                                    should_break = true;
+                                }
+                            }
+                        },
+                        ClientToServerMsg::MacroReplayEnded => {
+                            if let Some(rlocked_sessions) = rlocked_sessions.as_ref() {
+                                end_macro_replay(client_id, rlocked_sessions, &macro_state)?;
+                            }
+                        },
+                        ClientToServerMsg::MacroRecorded(macros) => {
+                            // the client saves the macro to disk, it is added to the session's
+                            // configuration so that it can be played right away
+                            to_server
+                                .send(ServerInstruction::Reconfigure {
+                                    client_id,
+                                    config: macros,
+                                    write_config_to_disk: false,
+                                })
+                                .with_context(err_context)?;
+                        },
+                        ClientToServerMsg::MousePress(button, position) => {
                             if let Some(rlocked_sessions) = rlocked_sessions.as_ref() {
-                                match rlocked_sessions.get_client_keybinds_and_mode(&client_id) {
-                                    Some((keybinds, input_mode)) => {
-                                        for action in keybinds
-                                            .get_actions_for_key_in_mode_or_default_action(
-                                                &input_mode,
-                                                &key,
-                                                raw_bytes,
-                                                is_kitty_keyboard_protocol,
-                                            )
-                                        {
-                                            if route_action(
-                                                action,
-                                                client_id,
-                                                None,
-                                                rlocked_sessions.senders.clone(),
-                                                rlocked_sessions.capabilities.clone(),
-                                                rlocked_sessions.client_attributes.clone(),
-                                                rlocked_sessions.default_shell.clone(),
-                                                rlocked_sessions.layout.clone(),
-                                                Some(&mut seen_cli_pipes),
-                                                keybinds.clone(),
-                                                rlocked_sessions
-                                                    .session_configuration
-                                                    .get_client_configuration(&client_id)
-                                                    .options
-                                                    .default_mode
-                                                    .unwrap_or(InputMode::Normal)
-                                                    .clone(),
-                                            )? {
-                                                should_break = true;
-                                            }
-                                        }
-                                    },
-                                    None => {
-                                        log::error!("Failed to get keybindings for client");
-                                    },
+                                if route_mouse_press(
+                                    button,
+                                    position,
+                                    client_id,
+                                    rlocked_sessions,
+                                    &*os_input,
+                                    &macro_state,
+                                    Some(&mut seen_cli_pipes),
+                                )? {
+                                    should_break = true;
                                 }
                             }
                         },
@@ -1113,6 +1892,8 @@ pub(crate) fn route_thread_main(
                             .with_context(err_context)?;
                         },
                         ClientToServerMsg::BackgroundColor(ref background_color_instruction) => {
+                            let color_scheme =
+                                color_scheme_from_background_color(background_color_instruction);
                             send_to_screen_or_retry_queue!(
                                 rlocked_sessions,
                                 ScreenInstruction::TerminalBackgroundColor(
@@ -1122,6 +1903,22 @@ pub(crate) fn route_thread_main(
                                 retry_queue
                             )
                             .with_context(err_context)?;
+                            if let Some(color_scheme) = color_scheme {
+                                to_server
+                                    .send(ServerInstruction::ChangeColorScheme(
+                                        client_id,
+                                        color_scheme,
+                                    ))
+                                    .with_context(err_context)?;
+                            }
+                        },
+                        ClientToServerMsg::ColorScheme(color_scheme) => {
+                            to_server
+                                .send(ServerInstruction::ChangeColorScheme(
+                                    client_id,
+                                    color_scheme,
+                                ))
+                                .with_context(err_context)?;
                         },
                         ClientToServerMsg::ForegroundColor(ref foreground_color_instruction) => {
                             send_to_screen_or_retry_queue!(
@@ -1166,6 +1963,18 @@ pub(crate) fn route_thread_main(
                                 .send(new_client_instruction)
                                 .with_context(err_context)?;
                         },
+                        ClientToServerMsg::PromptToTrustDirLocalLayout(
+                            dir_local_layout,
+                            data_dir,
+                        ) => {
+                            to_server
+                                .send(ServerInstruction::PromptToTrustDirLocalLayout(
+                                    dir_local_layout,
+                                    data_dir,
+                                    client_id,
+                                ))
+                                .with_context(err_context)?;
+                        },
                         ClientToServerMsg::AttachClient(
                             client_attributes,
                             config,
@@ -1250,5 +2059,12 @@ pub(crate) fn route_thread_main(
             },
         }
     }
+    if let Ok(mut client_key_states) = client_key_states.lock() {
+        client_key_states.remove(&client_id);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+#[path = "./unit/route_tests.rs"]
+mod route_tests;
@@ -2,19 +2,23 @@
 
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::str;
 use std::time::Duration;
 
 use log::{debug, warn};
+use zellij_utils::channels::Sender;
 use zellij_utils::data::{
-    Direction, KeyWithModifier, PaneManifest, PluginPermission, Resize, ResizeStrategy, SessionInfo,
+    Direction, KeyWithModifier, PaneColors, PaneContents, PaneManifest, PluginPermission, Resize,
+    ResizeStrategy, SessionInfo,
 };
 use zellij_utils::errors::prelude::*;
 use zellij_utils::input::command::RunCommand;
 use zellij_utils::input::config::Config;
-use zellij_utils::input::keybinds::Keybinds;
+use zellij_utils::input::keybinds::{FocusedPaneInfo, Keybinds};
+use zellij_utils::input::mouse::{MouseBindings, MouseButtonWithModifier};
 use zellij_utils::input::options::Clipboard;
 use zellij_utils::pane_size::{Size, SizeInPixels};
 use zellij_utils::{
@@ -44,7 +48,7 @@ use crate::{
     thread_bus::Bus,
     ui::{
         loading_indication::LoadingIndication,
-        overlay::{Overlay, OverlayWindow},
+        overlay::{which_key::WhichKey, Overlay, OverlayType, Overlayable},
     },
     ClientId, ServerInstruction,
 };
@@ -253,9 +257,30 @@ pub enum ScreenInstruction {
     TerminalColorRegisters(Vec<(usize, String)>),
     ChangeMode(ModeInfo, ClientId),
     ChangeModeForAllClients(ModeInfo),
+    UpdatePendingKeySequence(Vec<KeyWithModifier>, ClientId),
+    MousePress(MouseButtonWithModifier, MouseBindings, Position, ClientId), // resolves the target of targeted mouse bindings
+    GetPaneContents(
+        PaneId,
+        Option<Range<usize>>,
+        bool, // with ansi
+        Sender<Result<PaneContents, String>>,
+    ),
+    SubscribeToPaneOutput(PluginId, Option<HashSet<PaneId>>), // None => all panes
+    UnsubscribeFromPaneOutput(PluginId),
+    FlushPaneOutput,
+    ShowWhichKeyOverlay(ClientId, InputMode), // InputMode - the mode the overlay was requested in
+    ChangeClientTheme(Palette, ClientId),
+    SetPaneColors(Option<PaneId>, PaneColors, ClientId), // None => the focused pane of the client
     LeftClick(Position, ClientId),
     RightClick(Position, ClientId),
     MiddleClick(Position, ClientId),
+    FocusPaneAt(Position, ClientId),
+    OpenHyperlinkAt(Position, ClientId),
+    Paste(ClientId),
+    TogglePaneInSelection(ClientId),
+    ClearPaneSelection(ClientId),
+    MacroReplayStarted(ClientId),
+    MacroReplayEnded(ClientId),
     LeftMouseRelease(Position, ClientId),
     RightMouseRelease(Position, ClientId),
     MiddleMouseRelease(Position, ClientId),
@@ -378,8 +403,11 @@ pub enum ScreenInstruction {
         auto_layout: bool,
         rounded_corners: bool,
         hide_session_name: bool,
+        which_key_overlay: bool,
+        dim_unfocused_panes: u8,
     },
-    RerunCommandPane(u32), // u32 - terminal pane id
+    RerunCommandPane(u32),                       // u32 - terminal pane id
+    RestartCommandPane(u32, usize, Option<i32>), // terminal pane id, restart count, last exit status
 }
 
 impl From<&ScreenInstruction> for ScreenContext {
@@ -491,12 +519,32 @@ impl From<&ScreenInstruction> for ScreenContext {
             ScreenInstruction::ChangeModeForAllClients(..) => {
                 ScreenContext::ChangeModeForAllClients
             },
+            ScreenInstruction::UpdatePendingKeySequence(..) => {
+                ScreenContext::UpdatePendingKeySequence
+            },
+            ScreenInstruction::MousePress(..) => ScreenContext::MousePress,
+            ScreenInstruction::ShowWhichKeyOverlay(..) => ScreenContext::ShowWhichKeyOverlay,
+            ScreenInstruction::ChangeClientTheme(..) => ScreenContext::ChangeClientTheme,
+            ScreenInstruction::SetPaneColors(..) => ScreenContext::SetPaneColors,
+            ScreenInstruction::GetPaneContents(..) => ScreenContext::GetPaneContents,
+            ScreenInstruction::SubscribeToPaneOutput(..) => ScreenContext::SubscribeToPaneOutput,
+            ScreenInstruction::UnsubscribeFromPaneOutput(..) => {
+                ScreenContext::UnsubscribeFromPaneOutput
+            },
+            ScreenInstruction::FlushPaneOutput => ScreenContext::FlushPaneOutput,
             ScreenInstruction::ToggleActiveSyncTab(..) => ScreenContext::ToggleActiveSyncTab,
             ScreenInstruction::ScrollUpAt(..) => ScreenContext::ScrollUpAt,
             ScreenInstruction::ScrollDownAt(..) => ScreenContext::ScrollDownAt,
             ScreenInstruction::LeftClick(..) => ScreenContext::LeftClick,
             ScreenInstruction::RightClick(..) => ScreenContext::RightClick,
             ScreenInstruction::MiddleClick(..) => ScreenContext::MiddleClick,
+            ScreenInstruction::FocusPaneAt(..) => ScreenContext::FocusPaneAt,
+            ScreenInstruction::OpenHyperlinkAt(..) => ScreenContext::OpenHyperlinkAt,
+            ScreenInstruction::Paste(..) => ScreenContext::Paste,
+            ScreenInstruction::TogglePaneInSelection(..) => ScreenContext::TogglePaneInSelection,
+            ScreenInstruction::ClearPaneSelection(..) => ScreenContext::ClearPaneSelection,
+            ScreenInstruction::MacroReplayStarted(..) => ScreenContext::MacroReplayStarted,
+            ScreenInstruction::MacroReplayEnded(..) => ScreenContext::MacroReplayEnded,
             ScreenInstruction::LeftMouseRelease(..) => ScreenContext::LeftMouseRelease,
             ScreenInstruction::RightMouseRelease(..) => ScreenContext::RightMouseRelease,
             ScreenInstruction::MiddleMouseRelease(..) => ScreenContext::MiddleMouseRelease,
@@ -566,6 +614,7 @@ impl From<&ScreenInstruction> for ScreenContext {
             ScreenInstruction::ListClientsMetadata(..) => ScreenContext::ListClientsMetadata,
             ScreenInstruction::Reconfigure { .. } => ScreenContext::Reconfigure,
             ScreenInstruction::RerunCommandPane { .. } => ScreenContext::RerunCommandPane,
+            ScreenInstruction::RestartCommandPane(..) => ScreenContext::RestartCommandPane,
         }
     }
 }
@@ -614,8 +663,9 @@ pub(crate) struct Screen {
     pixel_dimensions: PixelDimensions,
     character_cell_size: Rc<RefCell<Option<SizeInPixels>>>,
     sixel_image_store: Rc<RefCell<SixelImageStore>>,
-    /// The overlay that is drawn on top of [`Pane`]'s', [`Tab`]'s and the [`Screen`]
-    overlay: OverlayWindow,
+    /// The prompt overlays that are drawn on top of [`Pane`]'s', [`Tab`]'s and the [`Screen`],
+    /// only for the client that can answer them
+    prompt_overlays: BTreeMap<ClientId, Overlay>,
     terminal_emulator_colors: Rc<RefCell<Palette>>,
     terminal_emulator_color_codes: Rc<RefCell<HashMap<usize, String>>>,
     connected_clients: Rc<RefCell<HashSet<ClientId>>>,
@@ -632,6 +682,7 @@ pub(crate) struct Screen {
     scrollback_lines_to_serialize: Option<usize>,
     session_is_mirrored: bool,
     copy_options: CopyOptions,
+    copied_text: Rc<RefCell<Option<String>>>, // the text last copied in the session, for pasting
     debug: bool,
     session_name: String,
     session_infos_on_machine: BTreeMap<String, SessionInfo>, // String is the session name, can
@@ -645,6 +696,17 @@ pub(crate) struct Screen {
     layout_dir: Option<PathBuf>,
     default_layout_name: Option<String>,
     explicitly_disable_kitty_keyboard_protocol: bool,
+    which_key_overlay: bool,
+    /// The clients currently shown the which-key overlay, and whether it needs to be redrawn
+    which_key_overlays: BTreeMap<ClientId, bool>,
+    /// The focused pane of each client as last sent to the server for conditional keybindings
+    focused_pane_infos: HashMap<ClientId, (Option<u32>, FocusedPaneInfo)>, // u32 -> terminal id
+    dim_unfocused_panes: u8,
+    /// The plugins subscribed to the output of panes, None being all panes
+    pane_output_subscribers: BTreeMap<PluginId, Option<HashSet<PaneId>>>,
+    pane_output_flush_scheduled: bool,
+    /// The clients replaying a macro, their keys are written to the selected panes of their tab
+    clients_replaying_macros: HashSet<ClientId>,
 }
 
 impl Screen {
@@ -669,6 +731,8 @@ impl Screen {
         arrow_fonts: bool,
         layout_dir: Option<PathBuf>,
         explicitly_disable_kitty_keyboard_protocol: bool,
+        which_key_overlay: bool,
+        dim_unfocused_panes: u8,
     ) -> Self {
         let session_name = mode_info.session_name.clone().unwrap_or_default();
         let session_info = SessionInfo::new(session_name.clone());
@@ -686,7 +750,7 @@ impl Screen {
             connected_clients: Rc::new(RefCell::new(HashSet::new())),
             active_tab_indices: BTreeMap::new(),
             tabs: BTreeMap::new(),
-            overlay: OverlayWindow::default(),
+            prompt_overlays: BTreeMap::new(),
             terminal_emulator_colors: Rc::new(RefCell::new(Palette::default())),
             terminal_emulator_color_codes: Rc::new(RefCell::new(HashMap::new())),
             tab_history: BTreeMap::new(),
@@ -696,6 +760,7 @@ impl Screen {
             auto_layout,
             session_is_mirrored,
             copy_options,
+            copied_text: Rc::new(RefCell::new(None)),
             debug,
             session_name,
             session_infos_on_machine,
@@ -710,6 +775,13 @@ impl Screen {
             resurrectable_sessions,
             layout_dir,
             explicitly_disable_kitty_keyboard_protocol,
+            which_key_overlay,
+            which_key_overlays: BTreeMap::new(),
+            focused_pane_infos: HashMap::new(),
+            dim_unfocused_panes,
+            pane_output_subscribers: BTreeMap::new(),
+            pane_output_flush_scheduled: false,
+            clients_replaying_macros: HashSet::new(),
         }
     }
 
@@ -1123,6 +1195,7 @@ impl Screen {
             self.sixel_image_store.clone(),
             self.character_cell_size.clone(),
             self.styled_underlines,
+            self.dim_unfocused_panes,
         );
         let mut tabs_to_close = vec![];
         for (tab_index, tab) in &mut self.tabs {
@@ -1135,6 +1208,27 @@ impl Screen {
         for tab_index in tabs_to_close {
             self.close_tab_at_index(tab_index).context(err_context)?;
         }
+        for (client_id, needs_redraw) in self.which_key_overlays.iter_mut() {
+            // the overlay is drawn on top of whatever changed beneath it
+            if !*needs_redraw && !output.is_dirty_for_client(client_id) {
+                continue;
+            }
+            if let Some(mode_info) = self.mode_info.get(client_id) {
+                let which_key = WhichKey::new(mode_info.clone());
+                let vte_output = Overlay::new(OverlayType::WhichKey(which_key))
+                    .generate_overlay(self.size)
+                    .context(err_context)?;
+                output.add_post_vte_instruction_to_client(*client_id, &vte_output);
+            }
+            *needs_redraw = false;
+        }
+        for (client_id, prompt_overlay) in &self.prompt_overlays {
+            let vte_output = prompt_overlay
+                .generate_overlay(self.size)
+                .context(err_context)?;
+            output.add_post_vte_instruction_to_client(*client_id, &vte_output);
+        }
+        self.report_focused_pane_changes();
         if output.is_dirty() {
             let serialized_output = output.serialize().context(err_context)?;
             let _ = self
@@ -1202,9 +1296,20 @@ impl Screen {
         }
     }
 
-    /// Returns a mutable reference to this [`Screen`]'s active [`Overlays`].
-    pub fn get_active_overlays_mut(&mut self) -> &mut Vec<Overlay> {
-        &mut self.overlay.overlay_stack
+    /// Shows a prompt overlay to the client, replacing the one it was shown before
+    pub fn add_prompt_overlay(&mut self, client_id: ClientId, overlay: Overlay) {
+        self.prompt_overlays.insert(client_id, overlay);
+    }
+
+    /// Removes the prompt overlay of the client, rendering what was beneath it again
+    pub fn remove_prompt_overlay(&mut self, client_id: ClientId) -> Option<Overlay> {
+        let overlay = self.prompt_overlays.remove(&client_id);
+        if overlay.is_some() {
+            for tab in self.tabs.values_mut() {
+                tab.set_force_render();
+            }
+        }
+        overlay
     }
 
     /// Returns a mutable reference to this [`Screen`]'s indexed [`Tab`].
@@ -1255,6 +1360,7 @@ impl Screen {
             self.session_is_mirrored,
             client_id,
             self.copy_options.clone(),
+            self.copied_text.clone(),
             self.terminal_emulator_colors.clone(),
             self.terminal_emulator_color_codes.clone(),
             swap_layouts,
@@ -1404,6 +1510,8 @@ impl Screen {
         if self.tab_history.contains_key(&client_id) {
             self.tab_history.remove(&client_id);
         }
+        self.which_key_overlays.remove(&client_id);
+        self.prompt_overlays.remove(&client_id);
         self.connected_clients.borrow_mut().remove(&client_id);
         self.log_and_report_session_state()
             .with_context(err_context)
@@ -1750,7 +1858,7 @@ impl Screen {
             .mode_info
             .get(&client_id)
             .unwrap_or(&self.default_mode_info);
-        let previous_mode = previous_mode_info.mode;
+        let previous_mode = previous_mode_info.mode.clone();
         mode_info.style = previous_mode_info.style;
         mode_info.capabilities = previous_mode_info.capabilities;
 
@@ -1802,8 +1910,179 @@ impl Screen {
             tab.mark_active_pane_for_rerender(client_id);
             tab.update_input_modes()?;
         }
+        if self.which_key_overlays.contains_key(&client_id) {
+            if self.which_key_overlay && WhichKey::is_shown_in_mode(&mode_info) {
+                self.redraw_which_key_overlay(client_id);
+            } else {
+                self.hide_which_key_overlay(client_id);
+            }
+        } else if self.which_key_overlay && WhichKey::is_shown_in_mode(&mode_info) {
+            self.bus
+                .senders
+                .send_to_background_jobs(BackgroundJob::ShowWhichKeyOverlay(
+                    client_id,
+                    mode_info.mode.clone(),
+                ))
+                .with_context(err_context)?;
+        }
+        Ok(())
+    }
+    /// Shows the which-key overlay to the client, unless it left `input_mode` while the overlay
+    /// was being delayed
+    pub fn show_which_key_overlay(&mut self, client_id: ClientId, input_mode: InputMode) {
+        let is_still_in_mode = self
+            .mode_info
+            .get(&client_id)
+            .map(|mode_info| mode_info.mode == input_mode && WhichKey::is_shown_in_mode(mode_info))
+            .unwrap_or(false);
+        if self.which_key_overlay && is_still_in_mode {
+            self.which_key_overlays.insert(client_id, true);
+        }
+    }
+    pub fn subscribe_to_pane_output(
+        &mut self,
+        plugin_id: PluginId,
+        pane_ids: Option<HashSet<PaneId>>,
+    ) {
+        self.pane_output_subscribers.insert(plugin_id, pane_ids);
+    }
+    pub fn unsubscribe_from_pane_output(&mut self, plugin_id: PluginId) {
+        self.pane_output_subscribers.remove(&plugin_id);
+    }
+    fn pane_output_has_subscribers(&self, pane_id: PaneId) -> bool {
+        self.pane_output_subscribers
+            .values()
+            .any(|pane_ids| pane_ids.as_ref().map_or(true, |p| p.contains(&pane_id)))
+    }
+    pub fn handle_pty_bytes(&mut self, pid: u32, vte_bytes: VteBytes) -> Result<()> {
+        let collect_pane_output = self.pane_output_has_subscribers(PaneId::Terminal(pid));
+        for tab in self.tabs.values_mut() {
+            if tab.has_terminal_pid(pid) {
+                tab.collect_pane_output(pid, collect_pane_output);
+                tab.handle_pty_bytes(pid, vte_bytes)
+                    .context("failed to process pty bytes")?;
+                break;
+            }
+        }
+        if collect_pane_output && !self.pane_output_flush_scheduled {
+            // lines are sent to plugins in batches so that a chatty pane does not flood them
+            self.pane_output_flush_scheduled = true;
+            self.bus
+                .senders
+                .send_to_background_jobs(BackgroundJob::FlushPaneOutput)
+                .context("failed to schedule pane output flush")?;
+        }
+        Ok(())
+    }
+    pub fn flush_pane_output(&mut self) -> Result<()> {
+        self.pane_output_flush_scheduled = false;
+        let mut plugin_updates = vec![];
+        for tab in self.tabs.values_mut() {
+            for (pane_id, lines) in tab.drain_pane_output() {
+                for (plugin_id, pane_ids) in &self.pane_output_subscribers {
+                    if pane_ids.as_ref().map_or(true, |p| p.contains(&pane_id)) {
+                        plugin_updates.push((
+                            Some(*plugin_id),
+                            None,
+                            Event::PaneOutput(pane_id.into(), lines.clone()),
+                        ));
+                    }
+                }
+            }
+        }
+        if !plugin_updates.is_empty() {
+            self.bus
+                .senders
+                .send_to_plugin(PluginInstruction::Update(plugin_updates))
+                .context("failed to send pane output to plugins")?;
+        }
+        Ok(())
+    }
+    fn redraw_which_key_overlay(&mut self, client_id: ClientId) {
+        // the previous overlay might have been larger, so everything beneath it is rendered again
+        if let Ok(active_tab) = self.get_active_tab_mut(client_id) {
+            active_tab.set_force_render();
+        }
+        self.which_key_overlays.insert(client_id, true);
+    }
+    fn hide_which_key_overlay(&mut self, client_id: ClientId) {
+        if self.which_key_overlays.remove(&client_id).is_some() {
+            if let Ok(active_tab) = self.get_active_tab_mut(client_id) {
+                active_tab.set_force_render();
+            }
+        }
+    }
+    pub fn update_pending_key_sequence(
+        &mut self,
+        pending_key_sequence: Vec<KeyWithModifier>,
+        client_id: ClientId,
+    ) -> Result<()> {
+        let mut mode_info = self
+            .mode_info
+            .get(&client_id)
+            .unwrap_or(&self.default_mode_info)
+            .clone();
+        mode_info.pending_key_sequence = pending_key_sequence;
+        self.mode_info.insert(client_id, mode_info.clone());
+        for tab in self.tabs.values_mut() {
+            tab.change_mode_info(mode_info.clone(), client_id);
+            tab.update_input_modes().with_context(|| {
+                format!("failed to update pending key sequence for client {client_id}")
+            })?;
+        }
+        if self.which_key_overlays.contains_key(&client_id) {
+            self.redraw_which_key_overlay(client_id);
+        }
         Ok(())
     }
+    // lets the server match conditional keybindings against the focused pane without having to
+    // ask the screen on every keypress
+    fn report_focused_pane_changes(&mut self) {
+        let client_ids: Vec<ClientId> = self.active_tab_indices.keys().copied().collect();
+        for client_id in client_ids {
+            let focused_pane_info = self.get_focused_pane_info(client_id);
+            if self.focused_pane_infos.get(&client_id) != Some(&focused_pane_info) {
+                self.focused_pane_infos
+                    .insert(client_id, focused_pane_info.clone());
+                let (terminal_id, focused_pane_info) = focused_pane_info;
+                let _ = self
+                    .bus
+                    .senders
+                    .send_to_server(ServerInstruction::FocusedPaneChanged(
+                        client_id,
+                        terminal_id,
+                        focused_pane_info,
+                    ));
+            }
+        }
+    }
+    pub fn get_focused_pane_info(&self, client_id: ClientId) -> (Option<u32>, FocusedPaneInfo) {
+        let mut focused_pane_info = FocusedPaneInfo::default();
+        let focused_pane = self
+            .get_active_tab(client_id)
+            .ok()
+            .and_then(|tab| tab.get_active_pane(client_id));
+        let terminal_id = match focused_pane {
+            Some(pane) => {
+                focused_pane_info.title = Some(pane.current_title());
+                match pane.pid() {
+                    PaneId::Terminal(terminal_id) => Some(terminal_id),
+                    PaneId::Plugin(_) => {
+                        focused_pane_info.plugin_url =
+                            pane.invoked_with().as_ref().and_then(|run| match run {
+                                Run::Plugin(run_plugin_or_alias) => {
+                                    Some(run_plugin_or_alias.location_string())
+                                },
+                                _ => None,
+                            });
+                        None
+                    },
+                }
+            },
+            None => None,
+        };
+        (terminal_id, focused_pane_info)
+    }
     pub fn change_mode_for_all_clients(&mut self, mode_info: ModeInfo) -> Result<()> {
         let err_context = || {
             format!(
@@ -2206,6 +2485,8 @@ impl Screen {
         auto_layout: bool,
         rounded_corners: bool,
         hide_session_name: bool,
+        which_key_overlay: bool,
+        dim_unfocused_panes: u8,
         client_id: ClientId,
     ) -> Result<()> {
         let should_support_arrow_fonts = !simplified_ui;
@@ -2216,6 +2497,14 @@ impl Screen {
             .update_rounded_corners(rounded_corners);
         self.default_shell = default_shell.clone();
         self.auto_layout = auto_layout;
+        self.which_key_overlay = which_key_overlay;
+        self.dim_unfocused_panes = dim_unfocused_panes;
+        if !which_key_overlay {
+            let client_ids: Vec<ClientId> = self.which_key_overlays.keys().copied().collect();
+            for client_id in client_ids {
+                self.hide_which_key_overlay(client_id);
+            }
+        }
         self.copy_options.command = copy_command.clone();
         self.copy_options.copy_on_select = copy_on_select;
         self.draw_pane_frames = pane_frames;
@@ -2260,6 +2549,28 @@ impl Screen {
         }
         Ok(())
     }
+    /// Switches the theme of a client, eg. when its terminal switches between a light and a dark
+    /// color scheme. Each client sees the UI in its own theme, the panes themselves follow the
+    /// last client to switch.
+    pub fn change_client_theme(&mut self, theme: Palette, client_id: ClientId) -> Result<()> {
+        if !self.connected_clients_contains(&client_id) {
+            return Ok(());
+        }
+        let mode_info = self
+            .mode_info
+            .entry(client_id)
+            .or_insert_with(|| self.default_mode_info.clone());
+        mode_info.update_theme(theme);
+        for tab in self.tabs.values_mut() {
+            tab.update_theme(theme);
+            tab.change_mode_info(mode_info.clone(), client_id);
+            tab.set_force_render();
+        }
+        for tab in self.tabs.values_mut() {
+            tab.update_input_modes()?;
+        }
+        Ok(())
+    }
     fn unblock_input(&self) -> Result<()> {
         self.bus
             .senders
@@ -2325,6 +2636,7 @@ impl Screen {
                         pane_id,
                         p.position_and_size(),
                         p.borderless(),
+                        p.is_tab_bar(),
                         p.invoked_with().clone(),
                         p.custom_title(),
                         !focused_clients.is_empty(),
@@ -2334,6 +2646,7 @@ impl Screen {
                             None
                         },
                         focused_clients,
+                        p.pane_colors(),
                     )
                 })
                 .collect();
@@ -2363,6 +2676,7 @@ impl Screen {
                         pane_id,
                         p.position_and_size(),
                         false, // floating panes are never borderless
+                        false, // nor tab bars
                         p.invoked_with().clone(),
                         p.custom_title(),
                         !focused_clients.is_empty(),
@@ -2372,6 +2686,7 @@ impl Screen {
                             None
                         },
                         focused_clients,
+                        p.pane_colors(),
                     )
                 })
                 .collect();
@@ -2379,6 +2694,7 @@ impl Screen {
                 tab.name.clone(),
                 tab_is_focused,
                 hide_floating_panes,
+                tab.tab_colors(),
                 tiled_panes,
                 floating_panes,
             );
@@ -2436,6 +2752,8 @@ pub(crate) fn screen_thread_main(
         config_options.copy_on_select.unwrap_or(true),
     );
     let styled_underlines = config_options.styled_underlines.unwrap_or(true);
+    let which_key_overlay = config_options.which_key_overlay.unwrap_or(false);
+    let dim_unfocused_panes = config_options.dim_unfocused_panes.unwrap_or(0);
     let explicitly_disable_kitty_keyboard_protocol = config_options
         .support_kitty_keyboard_protocol
         .map(|e| !e) // this is due to the config options wording, if
@@ -2450,7 +2768,7 @@ pub(crate) fn screen_thread_main(
         &client_attributes,
         max_panes,
         get_mode_info(
-            config_options.default_mode.unwrap_or_default(),
+            config_options.default_mode.clone().unwrap_or_default(),
             &client_attributes,
             PluginCapabilities {
                 //  ¯\_(ツ)_/¯
@@ -2474,6 +2792,8 @@ pub(crate) fn screen_thread_main(
         arrow_fonts,
         layout_dir,
         explicitly_disable_kitty_keyboard_protocol,
+        which_key_overlay,
+        dim_unfocused_panes,
     );
 
     let mut pending_tab_ids: HashSet<usize> = HashSet::new();
@@ -2493,14 +2813,7 @@ pub(crate) fn screen_thread_main(
 
         match event {
             ScreenInstruction::PtyBytes(pid, vte_bytes) => {
-                let all_tabs = screen.get_tabs_mut();
-                for tab in all_tabs.values_mut() {
-                    if tab.has_terminal_pid(pid) {
-                        tab.handle_pty_bytes(pid, vte_bytes)
-                            .context("failed to process pty bytes")?;
-                        break;
-                    }
-                }
+                screen.handle_pty_bytes(pid, vte_bytes)?;
             },
             ScreenInstruction::PluginBytes(mut plugin_render_assets) => {
                 for plugin_render_asset in plugin_render_assets.iter_mut() {
@@ -2675,14 +2988,12 @@ pub(crate) fn screen_thread_main(
                 client_id,
             ) => {
                 let mut state_changed = false;
+                let replaying_macro = screen.clients_replaying_macros.contains(&client_id);
                 active_tab_and_connected_client_id!(
                     screen,
                     client_id,
                     |tab: &mut Tab, client_id: ClientId| {
-                        let write_result = match tab.is_sync_panes_active() {
-                            true => tab.write_to_terminals_on_current_tab(&key_with_modifier, raw_bytes, is_kitty_keyboard_protocol, client_id),
-                            false => tab.write_to_active_terminal(&key_with_modifier, raw_bytes, is_kitty_keyboard_protocol, client_id),
-                        };
+                        let write_result = tab.write_key(&key_with_modifier, raw_bytes, is_kitty_keyboard_protocol, client_id, replaying_macro);
                         if let Ok(true) = write_result {
                             state_changed = true;
                         }
@@ -3353,6 +3664,74 @@ pub(crate) fn screen_thread_main(
                 screen.render(None)?;
                 screen.unblock_input()?;
             },
+            ScreenInstruction::UpdatePendingKeySequence(pending_key_sequence, client_id) => {
+                screen.update_pending_key_sequence(pending_key_sequence, client_id)?;
+                screen.render(None)?;
+            },
+            ScreenInstruction::MousePress(button, mouse_bindings, position, client_id) => {
+                let mouse_target = screen
+                    .get_active_tab(client_id)
+                    .ok()
+                    .and_then(|tab| tab.get_mouse_target(&position));
+                let actions = mouse_bindings.actions_for_press(&button, mouse_target, position);
+                screen
+                    .bus
+                    .senders
+                    .send_to_server(ServerInstruction::RouteMouseActions(actions, client_id))
+                    .context("failed to route the actions of a mouse press")?;
+            },
+            ScreenInstruction::SubscribeToPaneOutput(plugin_id, pane_ids) => {
+                screen.subscribe_to_pane_output(plugin_id, pane_ids);
+            },
+            ScreenInstruction::UnsubscribeFromPaneOutput(plugin_id) => {
+                screen.unsubscribe_from_pane_output(plugin_id);
+            },
+            ScreenInstruction::FlushPaneOutput => {
+                screen.flush_pane_output()?;
+            },
+            ScreenInstruction::GetPaneContents(pane_id, line_range, with_ansi, response_sender) => {
+                let pane_contents = screen
+                    .tabs
+                    .values()
+                    .find(|tab| tab.has_pane_with_pid(&pane_id))
+                    .ok_or_else(|| format!("Pane {:?} not found", pane_id))
+                    .and_then(|tab| {
+                        tab.get_pane_contents(pane_id, line_range, with_ansi)
+                            .ok_or_else(|| format!("Pane {:?} is not a terminal pane", pane_id))
+                    });
+                let _ = response_sender.send(pane_contents);
+            },
+            ScreenInstruction::ShowWhichKeyOverlay(client_id, input_mode) => {
+                screen.show_which_key_overlay(client_id, input_mode);
+                screen.render(None)?;
+            },
+            ScreenInstruction::ChangeClientTheme(theme, client_id) => {
+                screen.change_client_theme(theme, client_id)?;
+                screen.render(None)?;
+            },
+            ScreenInstruction::SetPaneColors(pane_id, pane_colors, client_id) => {
+                let pane_id = pane_id.or_else(|| {
+                    screen
+                        .get_active_tab(client_id)
+                        .ok()
+                        .and_then(|tab| tab.get_active_pane_id(client_id))
+                });
+                match pane_id {
+                    Some(pane_id) => {
+                        for tab in screen.get_tabs_mut().values_mut() {
+                            if tab.has_pane_with_pid(&pane_id) {
+                                if let Err(e) = tab.set_pane_colors(pane_colors, pane_id) {
+                                    log::error!("Failed to set pane colors: {:?}", e);
+                                }
+                                break;
+                            }
+                        }
+                    },
+                    None => log::error!("Failed to find a pane to set the colors of"),
+                }
+                screen.render(None)?;
+                screen.unblock_input()?;
+            },
             ScreenInstruction::ToggleActiveSyncTab(client_id) => {
                 active_tab_and_connected_client_id!(
                     screen,
@@ -3384,6 +3763,56 @@ pub(crate) fn screen_thread_main(
                 screen.render(None)?;
                 screen.unblock_input()?;
             },
+            ScreenInstruction::FocusPaneAt(point, client_id) => {
+                active_tab!(screen, client_id, |tab: &mut Tab| tab
+                    .focus_pane_at(&point, client_id), ?);
+                screen.log_and_report_session_state()?;
+                screen.render(None)?;
+                screen.unblock_input()?;
+            },
+            ScreenInstruction::OpenHyperlinkAt(point, client_id) => {
+                let hyperlink = match screen.get_active_tab_mut(client_id) {
+                    Ok(tab) => tab.get_hyperlink_at(&point)?,
+                    Err(_) => None,
+                };
+                if let (Some(hyperlink), Some(os_input)) = (hyperlink, screen.bus.os_input.as_ref())
+                {
+                    os_input
+                        .open_hyperlink(&hyperlink)
+                        .with_context(|| format!("failed to open hyperlink {hyperlink}"))
+                        .non_fatal();
+                }
+                screen.unblock_input()?;
+            },
+            ScreenInstruction::Paste(client_id) => {
+                active_tab!(screen, client_id, |tab: &mut Tab| tab.paste(client_id), ?);
+                screen.render(None)?;
+                screen.unblock_input()?;
+            },
+            ScreenInstruction::TogglePaneInSelection(client_id) => {
+                active_tab_and_connected_client_id!(
+                    screen,
+                    client_id,
+                    |tab: &mut Tab, client_id: ClientId| tab.toggle_pane_in_selection(client_id)
+                );
+                screen.render(None)?;
+                screen.unblock_input()?;
+            },
+            ScreenInstruction::ClearPaneSelection(client_id) => {
+                active_tab_and_connected_client_id!(
+                    screen,
+                    client_id,
+                    |tab: &mut Tab, _client_id: ClientId| tab.clear_pane_selection()
+                );
+                screen.render(None)?;
+                screen.unblock_input()?;
+            },
+            ScreenInstruction::MacroReplayStarted(client_id) => {
+                screen.clients_replaying_macros.insert(client_id);
+            },
+            ScreenInstruction::MacroReplayEnded(client_id) => {
+                screen.clients_replaying_macros.remove(&client_id);
+            },
             ScreenInstruction::LeftMouseRelease(point, client_id) => {
                 active_tab!(screen, client_id, |tab: &mut Tab| tab
                     .handle_left_mouse_release(&point, client_id), ?);
@@ -3450,18 +3879,18 @@ pub(crate) fn screen_thread_main(
                 screen.log_and_report_session_state()?;
                 screen.render(None)?;
             },
-            ScreenInstruction::AddOverlay(overlay, _client_id) => {
-                screen.get_active_overlays_mut().pop();
-                screen.get_active_overlays_mut().push(overlay);
+            ScreenInstruction::AddOverlay(overlay, client_id) => {
+                screen.add_prompt_overlay(client_id, overlay);
+                screen.render(None)?;
                 screen.unblock_input()?;
             },
-            ScreenInstruction::RemoveOverlay(_client_id) => {
-                screen.get_active_overlays_mut().pop();
+            ScreenInstruction::RemoveOverlay(client_id) => {
+                screen.remove_prompt_overlay(client_id);
                 screen.render(None)?;
                 screen.unblock_input()?;
             },
-            ScreenInstruction::ConfirmPrompt(_client_id) => {
-                let overlay = screen.get_active_overlays_mut().pop();
+            ScreenInstruction::ConfirmPrompt(client_id) => {
+                let overlay = screen.remove_prompt_overlay(client_id);
                 let instruction = overlay.and_then(|o| o.prompt_confirm());
                 if let Some(instruction) = instruction {
                     screen
@@ -3470,10 +3899,19 @@ pub(crate) fn screen_thread_main(
                         .send_to_server(*instruction)
                         .context("failed to confirm prompt")?;
                 }
+                screen.render(None)?;
                 screen.unblock_input()?;
             },
-            ScreenInstruction::DenyPrompt(_client_id) => {
-                screen.get_active_overlays_mut().pop();
+            ScreenInstruction::DenyPrompt(client_id) => {
+                let overlay = screen.remove_prompt_overlay(client_id);
+                let instruction = overlay.and_then(|o| o.prompt_deny());
+                if let Some(instruction) = instruction {
+                    screen
+                        .bus
+                        .senders
+                        .send_to_server(*instruction)
+                        .context("failed to deny prompt")?;
+                }
                 screen.render(None)?;
                 screen.unblock_input()?;
             },
@@ -4143,6 +4581,8 @@ pub(crate) fn screen_thread_main(
                 auto_layout,
                 rounded_corners,
                 hide_session_name,
+                which_key_overlay,
+                dim_unfocused_panes,
             } => {
                 screen
                     .reconfigure(
@@ -4158,6 +4598,8 @@ pub(crate) fn screen_thread_main(
                         auto_layout,
                         rounded_corners,
                         hide_session_name,
+                        which_key_overlay,
+                        dim_unfocused_panes,
                         client_id,
                     )
                     .non_fatal();
@@ -4165,6 +4607,23 @@ pub(crate) fn screen_thread_main(
             ScreenInstruction::RerunCommandPane(terminal_pane_id) => {
                 screen.rerun_command_pane_with_id(terminal_pane_id)
             },
+            ScreenInstruction::RestartCommandPane(
+                terminal_pane_id,
+                restart_count,
+                last_exit_status,
+            ) => {
+                for tab in screen.tabs.values_mut() {
+                    if tab.has_pane_with_pid(&PaneId::Terminal(terminal_pane_id)) {
+                        tab.restart_terminal_pane_with_id(
+                            terminal_pane_id,
+                            restart_count,
+                            last_exit_status,
+                        );
+                        break;
+                    }
+                }
+                screen.render(None)?;
+            },
         }
     }
     Ok(())
@@ -5,6 +5,7 @@ use std::path::PathBuf;
 use zellij_utils::common_path::common_path_all;
 use zellij_utils::pane_size::PaneGeom;
 use zellij_utils::{
+    data::PaneColors,
     input::command::RunCommand,
     input::layout::{Layout, Run, RunPlugin, RunPluginOrAlias},
     session_serialization::{
@@ -150,6 +151,7 @@ impl SessionLayoutMetadata {
         name: String,
         is_focused: bool,
         hide_floating_panes: bool,
+        tab_colors: PaneColors,
         tiled_panes: Vec<PaneLayoutMetadata>,
         floating_panes: Vec<PaneLayoutMetadata>,
     ) {
@@ -157,6 +159,7 @@ impl SessionLayoutMetadata {
             name: Some(name),
             is_focused,
             hide_floating_panes,
+            tab_colors,
             tiled_panes,
             floating_panes,
         })
@@ -302,6 +305,7 @@ impl Into<TabLayoutManifest> for TabLayoutMetadata {
             floating_panes: self.floating_panes.into_iter().map(|t| t.into()).collect(),
             is_focused: self.is_focused,
             hide_floating_panes: self.hide_floating_panes,
+            pane_colors: self.tab_colors,
         }
     }
 }
@@ -313,9 +317,11 @@ impl Into<PaneLayoutManifest> for PaneLayoutMetadata {
             run: self.run,
             cwd: self.cwd,
             is_borderless: self.is_borderless,
+            is_tab_bar: self.is_tab_bar,
             title: self.title,
             is_focused: self.is_focused,
             pane_contents: self.pane_contents,
+            pane_colors: self.pane_colors,
         }
     }
 }
@@ -327,6 +333,7 @@ pub struct TabLayoutMetadata {
     floating_panes: Vec<PaneLayoutMetadata>,
     is_focused: bool,
     hide_floating_panes: bool,
+    tab_colors: PaneColors,
 }
 
 #[derive(Debug, Clone)]
@@ -336,10 +343,12 @@ pub struct PaneLayoutMetadata {
     run: Option<Run>,
     cwd: Option<PathBuf>,
     is_borderless: bool,
+    is_tab_bar: bool,
     title: Option<String>,
     is_focused: bool,
     pane_contents: Option<String>,
     focused_clients: Vec<ClientId>,
+    pane_colors: PaneColors,
 }
 
 impl PaneLayoutMetadata {
@@ -347,11 +356,13 @@ impl PaneLayoutMetadata {
         id: PaneId,
         geom: PaneGeom,
         is_borderless: bool,
+        is_tab_bar: bool,
         run: Option<Run>,
         title: Option<String>,
         is_focused: bool,
         pane_contents: Option<String>,
         focused_clients: Vec<ClientId>,
+        pane_colors: PaneColors,
     ) -> Self {
         PaneLayoutMetadata {
             id,
@@ -359,10 +370,12 @@ impl PaneLayoutMetadata {
             run,
             cwd: None,
             is_borderless,
+            is_tab_bar,
             title,
             is_focused,
             pane_contents,
             focused_clients,
+            pane_colors,
         }
     }
 }
@@ -302,6 +302,8 @@ impl<'a> LayoutApplier<'a> {
                 }
 
                 new_plugin.set_borderless(layout.borderless);
+                new_plugin.set_is_tab_bar(layout.tab_bar);
+                new_plugin.set_pane_colors(layout.pane_colors);
                 if let Some(exclude_from_sync) = layout.exclude_from_sync {
                     new_plugin.set_exclude_from_sync(exclude_from_sync);
                 }
@@ -340,6 +342,8 @@ impl<'a> LayoutApplier<'a> {
                         new_pane.handle_pty_bytes("\n\r".as_bytes().into());
                     }
                     new_pane.set_borderless(layout.borderless);
+                    new_pane.set_is_tab_bar(layout.tab_bar);
+                    new_pane.set_pane_colors(layout.pane_colors);
                     if let Some(exclude_from_sync) = layout.exclude_from_sync {
                         new_pane.set_exclude_from_sync(exclude_from_sync);
                     }
@@ -417,6 +421,7 @@ impl<'a> LayoutApplier<'a> {
                     new_pane.handle_pty_bytes("\n\r".as_bytes().into());
                 }
                 new_pane.set_borderless(false);
+                new_pane.set_pane_colors(floating_pane_layout.pane_colors);
                 new_pane.set_content_offset(Offset::frame(1));
                 resize_pty!(
                     new_pane,
@@ -459,6 +464,7 @@ impl<'a> LayoutApplier<'a> {
                     new_pane.handle_pty_bytes("\n\r".as_bytes().into());
                 }
                 new_pane.set_borderless(false);
+                new_pane.set_pane_colors(floating_pane_layout.pane_colors);
                 new_pane.set_content_offset(Offset::frame(1));
                 if let Some(held_command) = hold_for_command {
                     new_pane.hold(None, true, held_command.clone());
@@ -660,6 +666,10 @@ impl<'a> LayoutApplier<'a> {
             pane.set_geom(position_and_size);
         }
         pane.set_borderless(layout.borderless);
+        pane.set_is_tab_bar(layout.tab_bar);
+        if !layout.pane_colors.is_empty() {
+            pane.set_pane_colors(layout.pane_colors);
+        }
         if let Some(pane_title) = layout.name.as_ref() {
             pane.set_title(pane_title.into());
         }
@@ -672,9 +682,16 @@ impl<'a> LayoutApplier<'a> {
     ) {
         pane.set_geom(position_and_size);
         pane.set_borderless(false);
+        pane.set_is_tab_bar(false);
         if let Some(pane_title) = floating_pane_layout.and_then(|f| f.name.clone()) {
             pane.set_title(pane_title);
         }
+        if let Some(pane_colors) = floating_pane_layout
+            .map(|f| f.pane_colors)
+            .filter(|pane_colors| !pane_colors.is_empty())
+        {
+            pane.set_pane_colors(pane_colors);
+        }
         pane.set_content_offset(Offset::frame(1));
     }
     fn total_space_for_tiled_panes(&self) -> PaneGeom {
@@ -11,8 +11,8 @@ use std::env::temp_dir;
 use std::path::PathBuf;
 use uuid::Uuid;
 use zellij_utils::data::{
-    Direction, KeyWithModifier, PaneInfo, PermissionStatus, PermissionType, PluginPermission,
-    ResizeStrategy,
+    Direction, KeyWithModifier, PaneInfo, PermissionScopes, PermissionStatus, PermissionType,
+    PluginPermission, ResizeStrategy,
 };
 use zellij_utils::errors::prelude::*;
 use zellij_utils::input::command::RunCommand;
@@ -34,6 +34,7 @@ use crate::{
     panes::sixel::SixelImageStore,
     panes::{FloatingPanes, TiledPanes},
     panes::{LinkHandler, PaneId, PluginPane, TerminalPane},
+    panes::{BRACKETED_PASTE_BEGIN, BRACKETED_PASTE_END},
     plugins::PluginInstruction,
     pty::{ClientTabIndexOrPaneId, PtyInstruction, VteBytes},
     thread_bus::ThreadSenders,
@@ -45,16 +46,21 @@ use std::rc::Rc;
 use std::time::Instant;
 use std::{
     collections::{HashMap, HashSet},
+    ops::Range,
     str,
 };
 use zellij_utils::{
-    data::{Event, FloatingPaneCoordinates, InputMode, ModeInfo, Palette, PaletteColor, Style},
+    data::{
+        Event, FloatingPaneCoordinates, InputMode, ModeInfo, Palette, PaletteColor, PaneColors,
+        PaneContents, Style,
+    },
     input::{
         command::TerminalAction,
         layout::{
             FloatingPaneLayout, Run, RunPluginOrAlias, SwapFloatingLayout, SwapTiledLayout,
             TiledPaneLayout,
         },
+        mouse::MouseTarget,
         parse_keys,
     },
     pane_size::{Offset, PaneGeom, Size, SizeInPixels, Viewport},
@@ -170,6 +176,7 @@ pub(crate) struct Tab {
     pub selecting_with_mouse: bool, // this is only pub for the tests TODO: remove this once we combine write_text_to_clipboard with render
     link_handler: Rc<RefCell<LinkHandler>>,
     clipboard_provider: ClipboardProvider,
+    copied_text: Rc<RefCell<Option<String>>>, // the text last copied in the session, for pasting
     // TODO: used only to focus the pane when the layout is loaded
     // it seems that optimization is possible using `active_panes`
     focus_pane_id: Option<PaneId>,
@@ -190,6 +197,8 @@ pub(crate) struct Tab {
     arrow_fonts: bool,
     styled_underlines: bool,
     explicitly_disable_kitty_keyboard_protocol: bool,
+    tab_colors: PaneColors, // overrides the theme colors for all the panes of this tab
+    last_rendered_focus: HashMap<ClientId, Option<PaneId>>, // used for dimming unfocused panes
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -268,6 +277,17 @@ pub trait Pane {
     fn dump_screen(&mut self, _client_id: ClientId, _full: bool) -> String {
         "".to_owned()
     }
+    fn pane_contents(
+        &self,
+        _line_range: Option<Range<usize>>,
+        _with_ansi: bool,
+    ) -> Option<PaneContents> {
+        None
+    }
+    fn collect_completed_lines(&mut self, _should_collect: bool) {}
+    fn drain_completed_lines(&mut self) -> Vec<String> {
+        vec![]
+    }
     fn scroll_up(&mut self, count: usize, client_id: ClientId);
     fn scroll_down(&mut self, count: usize, client_id: ClientId);
     fn clear_scroll(&mut self);
@@ -400,6 +420,8 @@ pub trait Pane {
     fn load_pane_name(&mut self);
     fn set_borderless(&mut self, borderless: bool);
     fn borderless(&self) -> bool;
+    fn set_is_tab_bar(&mut self, is_tab_bar: bool);
+    fn is_tab_bar(&self) -> bool;
     fn set_exclude_from_sync(&mut self, exclude_from_sync: bool);
     fn exclude_from_sync(&self) -> bool;
 
@@ -408,6 +430,9 @@ pub trait Pane {
     fn mouse_left_click(&self, _position: &Position, _is_held: bool) -> Option<String> {
         None
     }
+    fn hyperlink_at(&self, _position: &Position) -> Option<String> {
+        None
+    }
     fn mouse_left_click_release(&self, _position: &Position) -> Option<String> {
         None
     }
@@ -469,6 +494,10 @@ pub trait Pane {
     fn add_red_pane_frame_color_override(&mut self, _error_text: Option<String>);
     fn clear_pane_frame_color_override(&mut self);
     fn frame_color_override(&self) -> Option<PaletteColor>;
+    fn set_selected(&mut self, is_selected: bool);
+    fn is_selected(&self) -> bool;
+    fn set_pane_colors(&mut self, pane_colors: PaneColors);
+    fn pane_colors(&self) -> PaneColors;
     fn invoked_with(&self) -> &Option<Run>;
     fn set_title(&mut self, title: String);
     fn update_loading_indication(&mut self, _loading_indication: LoadingIndication) {} // only relevant for plugins
@@ -492,6 +521,7 @@ pub trait Pane {
     fn rerun(&mut self) -> Option<RunCommand> {
         None
     } // only relevant to terminal panes
+    fn set_restart_status(&mut self, _restart_count: usize, _last_exit_status: Option<i32>) {} // only relevant to terminal panes
     fn update_theme(&mut self, _theme: Palette) {}
     fn update_arrow_fonts(&mut self, _should_support_arrow_fonts: bool) {}
     fn update_rounded_corners(&mut self, _rounded_corners: bool) {}
@@ -501,10 +531,11 @@ pub trait Pane {
 pub enum AdjustedInput {
     WriteBytesToTerminal(Vec<u8>),
     ReRunCommandInThisPane(RunCommand),
-    PermissionRequestResult(Vec<PermissionType>, PermissionStatus),
+    PermissionRequestResult(Vec<PermissionType>, PermissionScopes, PermissionStatus),
     CloseThisPane,
     DropToShellInThisPane { working_dir: Option<PathBuf> },
     WriteKeyToPlugin(KeyWithModifier),
+    ReloadPluginInThisPane,
 }
 pub fn get_next_terminal_position(
     tiled_panes: &TiledPanes,
@@ -548,6 +579,7 @@ impl Tab {
         session_is_mirrored: bool,
         client_id: ClientId,
         copy_options: CopyOptions,
+        copied_text: Rc<RefCell<Option<String>>>,
         terminal_emulator_colors: Rc<RefCell<Palette>>,
         terminal_emulator_color_codes: Rc<RefCell<HashMap<usize, String>>>,
         swap_layouts: (Vec<SwapTiledLayout>, Vec<SwapFloatingLayout>),
@@ -632,6 +664,7 @@ impl Tab {
             selecting_with_mouse: false,
             link_handler: Rc::new(RefCell::new(LinkHandler::new())),
             clipboard_provider,
+            copied_text,
             focus_pane_id: None,
             copy_on_select: copy_options.copy_on_select,
             last_mouse_hold_position: None,
@@ -647,6 +680,8 @@ impl Tab {
             arrow_fonts,
             styled_underlines,
             explicitly_disable_kitty_keyboard_protocol,
+            tab_colors: PaneColors::default(),
+            last_rendered_focus: HashMap::new(),
         }
     }
 
@@ -661,6 +696,7 @@ impl Tab {
     ) -> Result<()> {
         self.swap_layouts
             .set_base_layout((layout.clone(), floating_panes_layout.clone()));
+        self.tab_colors = layout.pane_colors;
         let should_show_floating_panes = LayoutApplier::new(
             &self.viewport,
             &self.senders,
@@ -1686,6 +1722,67 @@ impl Tab {
         Ok(should_trigger_ui_change)
     }
 
+    /// Writes a key the client typed to its focused pane (or to every pane if sync is on), a key
+    /// replayed from one of its macros goes to the selected panes if there are any
+    pub fn write_key(
+        &mut self,
+        key_with_modifier: &Option<KeyWithModifier>,
+        raw_input_bytes: Vec<u8>,
+        raw_input_bytes_are_kitty: bool,
+        client_id: ClientId,
+        is_macro_replay: bool,
+    ) -> Result<bool> {
+        if is_macro_replay && self.has_selected_panes() {
+            self.write_to_selected_panes(
+                key_with_modifier,
+                raw_input_bytes,
+                raw_input_bytes_are_kitty,
+                client_id,
+            )
+        } else if self.is_sync_panes_active() {
+            self.write_to_terminals_on_current_tab(
+                key_with_modifier,
+                raw_input_bytes,
+                raw_input_bytes_are_kitty,
+                client_id,
+            )
+        } else {
+            self.write_to_active_terminal(
+                key_with_modifier,
+                raw_input_bytes,
+                raw_input_bytes_are_kitty,
+                client_id,
+            )
+        }
+    }
+
+    pub fn write_to_selected_panes(
+        &mut self,
+        key_with_modifier: &Option<KeyWithModifier>,
+        raw_input_bytes: Vec<u8>,
+        raw_input_bytes_are_kitty: bool,
+        client_id: ClientId,
+    ) -> Result<bool> {
+        // returns true if a UI update should be triggered (eg. when closing a command pane with
+        // ctrl-c)
+        let mut should_trigger_ui_change = false;
+        for pane_id in self.selected_pane_ids() {
+            let ui_change_triggered = self
+                .write_to_pane_id(
+                    key_with_modifier,
+                    raw_input_bytes.clone(),
+                    raw_input_bytes_are_kitty,
+                    pane_id,
+                    Some(client_id),
+                )
+                .context("failed to write to selected panes")?;
+            if ui_change_triggered {
+                should_trigger_ui_change = true;
+            }
+        }
+        Ok(should_trigger_ui_change)
+    }
+
     pub fn write_to_active_terminal(
         &mut self,
         key_with_modifier: &Option<KeyWithModifier>,
@@ -1859,19 +1956,35 @@ impl Tab {
                         .send_to_plugin(PluginInstruction::Update(plugin_updates))
                         .with_context(err_context)?;
                 },
-                Some(AdjustedInput::PermissionRequestResult(permissions, status)) => {
+                Some(AdjustedInput::PermissionRequestResult(permissions, scopes, status)) => {
                     self.request_plugin_permissions(pid, None);
                     self.senders
                         .send_to_plugin(PluginInstruction::PermissionRequestResult(
                             pid,
                             client_id,
                             permissions,
+                            scopes,
                             status,
                             None,
                         ))
                         .with_context(err_context)?;
                     should_update_ui = true;
                 },
+                Some(AdjustedInput::ReloadPluginInThisPane) => {
+                    if let Some(Run::Plugin(run_plugin_or_alias)) =
+                        active_terminal.invoked_with().clone()
+                    {
+                        self.senders
+                            .send_to_plugin(PluginInstruction::Reload(
+                                None,
+                                None,
+                                run_plugin_or_alias,
+                                self.position,
+                                Size::default(),
+                            ))
+                            .with_context(err_context)?;
+                    }
+                },
                 Some(_) => {},
                 None => {},
             },
@@ -2004,6 +2117,34 @@ impl Tab {
     pub fn toggle_sync_panes_is_active(&mut self) {
         self.synchronize_is_active = !self.synchronize_is_active;
     }
+    pub fn toggle_pane_in_selection(&mut self, client_id: ClientId) {
+        if let Some(active_pane) = self.get_active_pane_mut(client_id) {
+            let is_selected = active_pane.is_selected();
+            active_pane.set_selected(!is_selected);
+        }
+    }
+    pub fn clear_pane_selection(&mut self) {
+        for pane_id in self.selected_pane_ids() {
+            if let Some(pane) = self
+                .tiled_panes
+                .get_pane_mut(pane_id)
+                .or_else(|| self.floating_panes.get_pane_mut(pane_id))
+            {
+                pane.set_selected(false);
+            }
+        }
+    }
+    pub fn selected_pane_ids(&self) -> Vec<PaneId> {
+        self.tiled_panes
+            .get_panes()
+            .chain(self.floating_panes.get_panes())
+            .filter(|(_pane_id, pane)| pane.is_selected())
+            .map(|(pane_id, _pane)| *pane_id)
+            .collect()
+    }
+    pub fn has_selected_panes(&self) -> bool {
+        !self.selected_pane_ids().is_empty()
+    }
     pub fn mark_active_pane_for_rerender(&mut self, client_id: ClientId) {
         if let Some(active_pane) = self.get_active_pane_mut(client_id) {
             active_pane.set_should_render(true);
@@ -2035,6 +2176,9 @@ impl Tab {
         }
         self.update_active_panes_in_pty_thread()
             .with_context(err_context)?;
+        if output.dims_unfocused_panes() {
+            self.rerender_panes_with_changed_focus();
+        }
 
         let floating_panes_stack = self.floating_panes.stack();
         output.add_clients(
@@ -2044,11 +2188,15 @@ impl Tab {
         );
 
         self.tiled_panes
-            .render(output, self.floating_panes.panes_are_visible())
+            .render(
+                output,
+                self.floating_panes.panes_are_visible(),
+                self.tab_colors,
+            )
             .with_context(err_context)?;
         if self.floating_panes.panes_are_visible() && self.floating_panes.has_active_panes() {
             self.floating_panes
-                .render(output)
+                .render(output, self.tab_colors)
                 .with_context(err_context)?;
         }
 
@@ -2060,6 +2208,36 @@ impl Tab {
         Ok(())
     }
 
+    fn rerender_panes_with_changed_focus(&mut self) {
+        // when dimming unfocused panes, a change of focus changes the contents of both the
+        // previously and the newly focused panes and not only their frames
+        let connected_clients: Vec<ClientId> =
+            { self.connected_clients.borrow().iter().copied().collect() };
+        let current_focus: HashMap<ClientId, Option<PaneId>> = connected_clients
+            .iter()
+            .map(|client_id| (*client_id, self.get_active_pane_id(*client_id)))
+            .collect();
+        if current_focus == self.last_rendered_focus {
+            return;
+        }
+        let panes_with_changed_focus: HashSet<PaneId> = current_focus
+            .values()
+            .chain(self.last_rendered_focus.values())
+            .filter_map(|pane_id| *pane_id)
+            .collect();
+        for pane_id in panes_with_changed_focus {
+            if let Some(pane) = self
+                .floating_panes
+                .get_pane_mut(pane_id)
+                .or_else(|| self.tiled_panes.get_pane_mut(pane_id))
+            {
+                pane.set_should_render(true);
+                pane.render_full_viewport();
+            }
+        }
+        self.last_rendered_focus = current_focus;
+    }
+
     fn hide_cursor_and_clear_display_as_needed(&mut self, output: &mut Output) {
         let hide_cursor = "\u{1b}[?25l";
         let connected_clients: Vec<ClientId> =
@@ -2989,6 +3167,35 @@ impl Tab {
         }
     }
 
+    /// The part of the tab under the mouse, for mouse bindings with a target
+    pub fn get_mouse_target(&self, point: &Position) -> Option<MouseTarget> {
+        let search_selectable = false;
+        let floating_pane = if self.floating_panes.panes_are_visible() {
+            self.floating_panes
+                .get_pane_id_at(point, search_selectable)
+                .ok()
+                .flatten()
+                .and_then(|pane_id| self.floating_panes.get_pane(pane_id))
+        } else {
+            None
+        };
+        let pane = match floating_pane {
+            Some(pane) => pane,
+            None => self
+                .get_pane_id_at(point, search_selectable)
+                .ok()
+                .flatten()
+                .and_then(|pane_id| self.tiled_panes.get_pane(pane_id))?,
+        };
+        if pane.is_tab_bar() {
+            Some(MouseTarget::TabBar)
+        } else if pane.position_is_on_frame(point) {
+            Some(MouseTarget::PaneFrame)
+        } else {
+            Some(MouseTarget::PaneBody)
+        }
+    }
+
     fn get_pane_id_at(&self, point: &Position, search_selectable: bool) -> Result<Option<PaneId>> {
         let err_context = || format!("failed to get id of pane at position {point:?}");
 
@@ -3098,6 +3305,33 @@ impl Tab {
         Ok(())
     }
 
+    /// Pastes the text last copied in the session into the focused pane
+    pub fn paste(&mut self, client_id: ClientId) -> Result<()> {
+        let err_context = || format!("failed to paste for client {client_id}");
+
+        let copied_text = self.copied_text.borrow().clone();
+        if let Some(copied_text) = copied_text {
+            for bytes in [
+                BRACKETED_PASTE_BEGIN.to_vec(),
+                copied_text.into_bytes(),
+                BRACKETED_PASTE_END.to_vec(),
+            ] {
+                self.write_to_active_terminal(&None, bytes, false, client_id)
+                    .with_context(err_context)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_hyperlink_at(&mut self, point: &Position) -> Result<Option<String>> {
+        let err_context = || format!("failed to get hyperlink at position {point:?}");
+
+        Ok(self
+            .get_pane_at(point, false)
+            .with_context(err_context)?
+            .and_then(|pane| pane.hyperlink_at(&pane.relative_position(point))))
+    }
+
     pub fn handle_middle_click(&mut self, position: &Position, client_id: ClientId) -> Result<()> {
         let err_context = || {
             format!(
@@ -3128,7 +3362,7 @@ impl Tab {
         Ok(())
     }
 
-    fn focus_pane_at(&mut self, point: &Position, client_id: ClientId) -> Result<()> {
+    pub fn focus_pane_at(&mut self, point: &Position, client_id: ClientId) -> Result<()> {
         let err_context =
             || format!("failed to focus pane at position {point:?} for client {client_id}");
 
@@ -3478,6 +3712,8 @@ impl Tab {
     fn write_selection_to_clipboard(&self, selection: &str) -> Result<()> {
         let err_context = || format!("failed to write selection to clipboard: '{}'", selection);
 
+        *self.copied_text.borrow_mut() = Some(selection.to_owned());
+
         let mut output = Output::default();
         let connected_clients: HashSet<ClientId> =
             { self.connected_clients.borrow().iter().copied().collect() };
@@ -3578,6 +3814,78 @@ impl Tab {
         Ok(())
     }
 
+    pub fn set_pane_colors(&mut self, pane_colors: PaneColors, pane_id: PaneId) -> Result<()> {
+        let err_context = || format!("failed to set colors of pane {:?}", pane_id);
+        let pane = self
+            .floating_panes
+            .get_pane_mut(pane_id)
+            .or_else(|| self.tiled_panes.get_pane_mut(pane_id))
+            .or_else(|| {
+                self.suppressed_panes
+                    .get_mut(&pane_id)
+                    .map(|s_p| &mut s_p.1)
+            })
+            .with_context(err_context)?;
+        pane.set_pane_colors(pane_colors);
+        // the background is applied to all the pane contents, so they have to be re-rendered
+        pane.render_full_viewport();
+        self.set_force_render();
+        Ok(())
+    }
+    pub fn tab_colors(&self) -> PaneColors {
+        self.tab_colors
+    }
+    pub fn get_pane_contents(
+        &self,
+        pane_id: PaneId,
+        line_range: Option<Range<usize>>,
+        with_ansi: bool,
+    ) -> Option<PaneContents> {
+        self.floating_panes
+            .get_pane(pane_id)
+            .or_else(|| self.tiled_panes.get_pane(pane_id))
+            .or_else(|| self.suppressed_panes.get(&pane_id).map(|s_p| &s_p.1))
+            .and_then(|pane| pane.pane_contents(line_range, with_ansi))
+    }
+    pub fn collect_pane_output(&mut self, pid: u32, should_collect: bool) {
+        if let Some(pane) = self
+            .tiled_panes
+            .get_pane_mut(PaneId::Terminal(pid))
+            .or_else(|| self.floating_panes.get_pane_mut(PaneId::Terminal(pid)))
+            .or_else(|| {
+                self.suppressed_panes
+                    .values_mut()
+                    .find(|s_p| s_p.1.pid() == PaneId::Terminal(pid))
+                    .map(|s_p| &mut s_p.1)
+            })
+        {
+            pane.collect_completed_lines(should_collect);
+        }
+    }
+    pub fn drain_pane_output(&mut self) -> Vec<(PaneId, Vec<String>)> {
+        let mut pane_output = vec![];
+        let pane_ids: Vec<PaneId> = self
+            .tiled_panes
+            .get_panes()
+            .chain(self.floating_panes.get_panes())
+            .map(|(pane_id, _)| *pane_id)
+            .collect();
+        for pane_id in pane_ids {
+            if let Some(pane) = self
+                .tiled_panes
+                .get_pane_mut(pane_id)
+                .or_else(|| self.floating_panes.get_pane_mut(pane_id))
+            {
+                pane_output.push((pane_id, pane.drain_completed_lines()));
+            }
+        }
+        for (_, pane) in self.suppressed_panes.values_mut() {
+            pane_output.push((pane.pid(), pane.drain_completed_lines()));
+        }
+        pane_output.retain(|(_, lines)| !lines.is_empty());
+        pane_output
+    }
+
     pub fn undo_active_rename_pane(&mut self, client_id: ClientId) -> Result<()> {
         if let Some(active_terminal_id) = self.get_active_terminal_id(client_id) {
             let active_terminal = if self.are_floating_panes_visible() {
@@ -3954,6 +4262,39 @@ impl Tab {
             },
         }
     }
+    pub fn restart_terminal_pane_with_id(
+        &mut self,
+        terminal_pane_id: u32,
+        restart_count: usize,
+        last_exit_status: Option<i32>,
+    ) {
+        let pane_id = PaneId::Terminal(terminal_pane_id);
+        if let Some(pane_to_restart) = self
+            .floating_panes
+            .get_mut(&pane_id)
+            .or_else(|| self.tiled_panes.get_pane_mut(pane_id))
+            .or_else(|| self.suppressed_panes.get_mut(&pane_id).map(|p| &mut p.1))
+        {
+            // if the pane is no longer held, it was already re-run by the user while we were
+            // waiting to restart it
+            if let Some(command_to_rerun) = pane_to_restart.rerun() {
+                pane_to_restart.set_restart_status(restart_count, last_exit_status);
+                self.pids_waiting_resize.insert(terminal_pane_id);
+                if command_to_rerun.originating_plugin.is_none() {
+                    // the originating plugin (if any) is notified when the command is re-run
+                    let _ = self.senders.send_to_plugin(PluginInstruction::Update(vec![(
+                        None,
+                        None,
+                        Event::CommandPaneReRun(terminal_pane_id, Default::default()),
+                    )]));
+                }
+                let _ = self.senders.send_to_pty(PtyInstruction::ReRunCommandInPane(
+                    pane_id,
+                    command_to_rerun,
+                ));
+            }
+        }
+    }
     pub fn update_theme(&mut self, theme: Palette) {
         self.style.colors = theme;
         self.floating_panes.update_pane_themes(theme);
@@ -14,9 +14,9 @@ use std::path::PathBuf;
 use std::sync::Mutex;
 
 use zellij_utils::channels::Receiver;
-use zellij_utils::data::Direction;
 use zellij_utils::data::Resize;
 use zellij_utils::data::ResizeStrategy;
+use zellij_utils::data::{BareKey, Direction, KeyWithModifier, PaletteColor, PaneColors};
 use zellij_utils::envs::set_session_name;
 use zellij_utils::errors::{prelude::*, ErrorContext};
 use zellij_utils::input::layout::{
@@ -134,10 +134,13 @@ impl ServerOsApi for FakeInputOutput {
     fn clear_terminal_id(&self, _terminal_id: u32) -> Result<()> {
         unimplemented!()
     }
+    fn open_hyperlink(&self, _uri: &str) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 struct MockPtyInstructionBus {
-    output: Arc<Mutex<Vec<String>>>,
+    output: Arc<Mutex<Vec<(u32, String)>>>, // u32 is the terminal id
     pty_writer_sender: SenderWithContext<PtyWriteInstruction>,
     pty_writer_receiver: Arc<Receiver<(PtyWriteInstruction, ErrorContext)>>,
     handle: Option<std::thread::JoinHandle<()>>,
@@ -170,10 +173,10 @@ impl MockPtyInstructionBus {
                         .recv()
                         .expect("failed to receive event on channel");
                     match event {
-                        PtyWriteInstruction::Write(msg, _) => output
+                        PtyWriteInstruction::Write(msg, terminal_id) => output
                             .lock()
                             .unwrap()
-                            .push(String::from_utf8_lossy(&msg).to_string()),
+                            .push((terminal_id, String::from_utf8_lossy(&msg).to_string())),
                         PtyWriteInstruction::Exit => break,
                         _ => {},
                     }
@@ -196,6 +199,15 @@ impl MockPtyInstructionBus {
     }
 
     fn clone_output(&self) -> Vec<String> {
+        self.output
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_terminal_id, output)| output.clone())
+            .collect()
+    }
+
+    fn clone_output_by_terminal(&self) -> Vec<(u32, String)> {
         self.output.lock().unwrap().clone()
     }
 }
@@ -221,6 +233,7 @@ fn create_new_tab(size: Size, default_mode: ModeInfo) -> Tab {
     let character_cell_info = Rc::new(RefCell::new(None));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let debug = false;
@@ -245,6 +258,7 @@ fn create_new_tab(size: Size, default_mode: ModeInfo) -> Tab {
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]),
@@ -300,6 +314,7 @@ fn create_new_tab_with_swap_layouts(
     let character_cell_info = Rc::new(RefCell::new(None));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let debug = false;
@@ -324,6 +339,7 @@ fn create_new_tab_with_swap_layouts(
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         swap_layouts,
@@ -381,6 +397,7 @@ fn create_new_tab_with_os_api(
     let character_cell_info = Rc::new(RefCell::new(None));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let debug = false;
@@ -405,6 +422,7 @@ fn create_new_tab_with_os_api(
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -446,9 +464,17 @@ fn create_new_tab_with_layout(size: Size, default_mode: ModeInfo, layout: &str)
     let character_cell_info = Rc::new(RefCell::new(None));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
-    let layout = Layout::from_str(layout, "layout_file_name".into(), None, None).unwrap();
+    let layout = Layout::from_str(
+        layout,
+        "layout_file_name".into(),
+        None,
+        None,
+        Default::default(),
+    )
+    .unwrap();
     let (tab_layout, floating_panes_layout) = layout.new_tab();
     let debug = false;
     let arrow_fonts = true;
@@ -472,6 +498,7 @@ fn create_new_tab_with_layout(size: Size, default_mode: ModeInfo, layout: &str)
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -529,6 +556,7 @@ fn create_new_tab_with_mock_pty_writer(
     let character_cell_info = Rc::new(RefCell::new(None));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let debug = false;
@@ -553,6 +581,7 @@ fn create_new_tab_with_mock_pty_writer(
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -602,6 +631,7 @@ fn create_new_tab_with_sixel_support(
     })));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let debug = false;
     let arrow_fonts = true;
@@ -625,6 +655,7 @@ fn create_new_tab_with_sixel_support(
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -2623,7 +2654,7 @@ fn move_floating_pane_with_sixel_image() {
         width: 8,
         height: 21,
     })));
-    let mut output = Output::new(sixel_image_store.clone(), character_cell_size, true);
+    let mut output = Output::new(sixel_image_store.clone(), character_cell_size, true, 0);
 
     tab.toggle_floating_panes(Some(client_id), None).unwrap();
     tab.new_pane(new_pane_id, None, None, None, None, false, Some(client_id))
@@ -2661,7 +2692,7 @@ fn floating_pane_above_sixel_image() {
         width: 8,
         height: 21,
     })));
-    let mut output = Output::new(sixel_image_store.clone(), character_cell_size, true);
+    let mut output = Output::new(sixel_image_store.clone(), character_cell_size, true, 0);
 
     tab.toggle_floating_panes(Some(client_id), None).unwrap();
     tab.new_pane(new_pane_id, None, None, None, None, false, Some(client_id))
@@ -8473,3 +8504,271 @@ fn can_define_expanded_pane_in_stack() {
     );
     assert_snapshot!(snapshot);
 }
+
+#[test]
+fn pane_background_color_override() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id = 1;
+    let mut tab = create_new_tab(size, ModeInfo::default());
+    let mut output = Output::default();
+    let pane_colors = PaneColors {
+        background: Some(PaletteColor::Rgb((16, 16, 32))),
+        ..Default::default()
+    };
+    tab.set_pane_colors(pane_colors, PaneId::Terminal(1))
+        .unwrap();
+    tab.handle_pty_bytes(1, Vec::from("I have a background".as_bytes()))
+        .unwrap();
+    tab.render(&mut output).unwrap();
+    let serialized_output = output.serialize().unwrap();
+    let client_output = serialized_output.get(&client_id).unwrap();
+    assert!(
+        client_output.contains("48;2;16;16;32"),
+        "pane contents are rendered with the overridden background"
+    );
+    assert!(
+        tab.set_pane_colors(pane_colors, PaneId::Terminal(100))
+            .is_err(),
+        "cannot set the colors of a pane that does not exist"
+    );
+}
+
+#[test]
+fn dim_unfocused_panes() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id = 1;
+    let mut tab = create_new_tab(size, ModeInfo::default());
+    let dim_unfocused_panes = 50;
+    let render = |tab: &mut Tab| {
+        let mut output = Output::new(
+            Rc::new(RefCell::new(SixelImageStore::default())),
+            Rc::new(RefCell::new(None)),
+            true,
+            dim_unfocused_panes,
+        );
+        tab.render(&mut output).unwrap();
+        output.serialize().unwrap().remove(&client_id).unwrap()
+    };
+    tab.vertical_split(PaneId::Terminal(2), None, client_id)
+        .unwrap();
+    tab.handle_pty_bytes(1, Vec::from("\u{1b}[38;2;200;100;50mleft pane".as_bytes()))
+        .unwrap();
+    tab.handle_pty_bytes(2, Vec::from("\u{1b}[48;2;200;100;50mright pane".as_bytes()))
+        .unwrap();
+    let client_output = render(&mut tab);
+    assert!(
+        client_output.contains("38;2;100;50;25"),
+        "the colors of the unfocused pane are dimmed"
+    );
+    assert!(
+        client_output.contains("48;2;200;100;50"),
+        "the colors of the focused pane are not dimmed"
+    );
+    tab.handle_left_click(&Position::new(5, 5), client_id)
+        .unwrap();
+    tab.handle_left_mouse_release(&Position::new(5, 5), client_id)
+        .unwrap();
+    let client_output = render(&mut tab);
+    assert!(
+        client_output.contains("38;2;200;100;50") && client_output.contains("48;2;100;50;25"),
+        "both panes are re-rendered when the focus changes"
+    );
+}
+
+#[test]
+fn paste_writes_the_last_copied_text_to_the_focused_pane() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id: u16 = 1;
+
+    let mut pty_instruction_bus = MockPtyInstructionBus::new();
+    let mut tab = create_new_tab_with_mock_pty_writer(
+        size,
+        ModeInfo::default(),
+        pty_instruction_bus.pty_write_sender(),
+    );
+    pty_instruction_bus.start();
+
+    let bracketed_paste_mode = String::from("\u{1b}[?2004h");
+    tab.handle_pty_bytes(1, bracketed_paste_mode.as_bytes().to_vec())
+        .unwrap();
+    tab.handle_pty_bytes(1, "copy me and not me".as_bytes().to_vec())
+        .unwrap();
+    tab.handle_left_click(&Position::new(1, 1), client_id)
+        .unwrap();
+    tab.handle_left_mouse_release(&Position::new(1, 8), client_id)
+        .unwrap();
+    tab.copy_selection(client_id).unwrap();
+    tab.paste(client_id).unwrap();
+
+    pty_instruction_bus.exit();
+
+    assert_eq!(
+        pty_instruction_bus.clone_output(),
+        vec!["\u{1b}[200~", "copy me", "\u{1b}[201~"]
+    );
+}
+
+#[test]
+fn paste_does_nothing_before_anything_was_copied() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id: u16 = 1;
+
+    let mut pty_instruction_bus = MockPtyInstructionBus::new();
+    let mut tab = create_new_tab_with_mock_pty_writer(
+        size,
+        ModeInfo::default(),
+        pty_instruction_bus.pty_write_sender(),
+    );
+    pty_instruction_bus.start();
+
+    tab.paste(client_id).unwrap();
+
+    pty_instruction_bus.exit();
+
+    assert!(pty_instruction_bus.clone_output().is_empty());
+}
+
+#[test]
+fn hyperlink_at_position_from_osc8_link_or_plain_url() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut tab = create_new_tab(size, ModeInfo::default());
+    let pane_content =
+        "\u{1b}]8;;https://zellij.dev\u{1b}\\zellij\u{1b}]8;;\u{1b}\\ docs: https://zellij.dev/documentation. done";
+    tab.handle_pty_bytes(1, pane_content.as_bytes().to_vec())
+        .unwrap();
+    assert_eq!(
+        tab.get_hyperlink_at(&Position::new(1, 3)).unwrap(),
+        Some("https://zellij.dev".to_owned()),
+        "osc8 link text opens its target"
+    );
+    assert_eq!(
+        tab.get_hyperlink_at(&Position::new(1, 20)).unwrap(),
+        Some("https://zellij.dev/documentation".to_owned()),
+        "plain url without its trailing punctuation"
+    );
+    assert_eq!(
+        tab.get_hyperlink_at(&Position::new(1, 50)).unwrap(),
+        None,
+        "nothing to open past the url"
+    );
+}
+
+#[test]
+fn macro_replay_is_written_to_the_pane_focused_when_it_is_played() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id: u16 = 1;
+
+    let mut pty_instruction_bus = MockPtyInstructionBus::new();
+    let mut tab = create_new_tab_with_mock_pty_writer(
+        size,
+        ModeInfo::default(),
+        pty_instruction_bus.pty_write_sender(),
+    );
+    pty_instruction_bus.start();
+    tab.new_pane(
+        PaneId::Terminal(2),
+        None,
+        None,
+        None,
+        None,
+        false,
+        Some(client_id),
+    )
+    .unwrap();
+
+    // recorded in pane 2, then replayed after moving the focus to pane 1
+    let key = KeyWithModifier::new(BareKey::Char('a'));
+    tab.write_key(&Some(key.clone()), vec![b'a'], false, client_id, false)
+        .unwrap();
+    tab.focus_pane_with_id(PaneId::Terminal(1), false, client_id)
+        .unwrap();
+    tab.write_key(&Some(key), vec![b'a'], false, client_id, true)
+        .unwrap();
+
+    pty_instruction_bus.exit();
+
+    assert_eq!(
+        pty_instruction_bus.clone_output_by_terminal(),
+        vec![(2, "a".to_string()), (1, "a".to_string())]
+    );
+}
+
+#[test]
+fn macro_replay_is_written_to_every_selected_pane() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id: u16 = 1;
+
+    let mut pty_instruction_bus = MockPtyInstructionBus::new();
+    let mut tab = create_new_tab_with_mock_pty_writer(
+        size,
+        ModeInfo::default(),
+        pty_instruction_bus.pty_write_sender(),
+    );
+    pty_instruction_bus.start();
+    for pane_id in [2, 3] {
+        tab.new_pane(
+            PaneId::Terminal(pane_id),
+            None,
+            None,
+            None,
+            None,
+            false,
+            Some(client_id),
+        )
+        .unwrap();
+    }
+    tab.focus_pane_with_id(PaneId::Terminal(1), false, client_id)
+        .unwrap();
+    tab.toggle_pane_in_selection(client_id);
+    tab.focus_pane_with_id(PaneId::Terminal(3), false, client_id)
+        .unwrap();
+    tab.toggle_pane_in_selection(client_id);
+    tab.focus_pane_with_id(PaneId::Terminal(2), false, client_id)
+        .unwrap();
+
+    let key = KeyWithModifier::new(BareKey::Char('a'));
+    tab.write_key(&Some(key.clone()), vec![b'a'], false, client_id, true)
+        .unwrap();
+    let typed_key = KeyWithModifier::new(BareKey::Char('b'));
+    tab.write_key(&Some(typed_key), vec![b'b'], false, client_id, false)
+        .unwrap();
+    tab.clear_pane_selection();
+    tab.write_key(&Some(key), vec![b'a'], false, client_id, true)
+        .unwrap();
+
+    pty_instruction_bus.exit();
+
+    let mut replayed_to_selection = pty_instruction_bus.clone_output_by_terminal()[..2].to_vec();
+    replayed_to_selection.sort();
+    assert_eq!(
+        replayed_to_selection,
+        vec![(1, "a".to_string()), (3, "a".to_string())],
+        "the replay went to the selected panes and not to the focused one"
+    );
+    assert_eq!(
+        pty_instruction_bus.clone_output_by_terminal()[2..],
+        vec![(2, "b".to_string()), (2, "a".to_string())],
+        "typed keys, and replays once the selection is cleared, go to the focused pane"
+    );
+}
@@ -11,8 +11,10 @@ use std::path::PathBuf;
 use zellij_utils::data::{Direction, Resize, ResizeStrategy};
 use zellij_utils::errors::prelude::*;
 use zellij_utils::input::layout::{SplitDirection, SplitSize, TiledPaneLayout};
+use zellij_utils::input::mouse::MouseTarget;
 use zellij_utils::ipc::IpcReceiverWithContext;
 use zellij_utils::pane_size::{Size, SizeInPixels};
+use zellij_utils::position::Position;
 
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
@@ -104,6 +106,9 @@ impl ServerOsApi for FakeInputOutput {
     fn clear_terminal_id(&self, _terminal_id: u32) -> Result<()> {
         unimplemented!()
     }
+    fn open_hyperlink(&self, _uri: &str) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 fn tab_resize_increase(tab: &mut Tab, id: ClientId) {
@@ -162,6 +167,7 @@ fn create_new_tab(size: Size) -> Tab {
     let connected_clients = Rc::new(RefCell::new(connected_clients));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let debug = false;
@@ -186,6 +192,7 @@ fn create_new_tab(size: Size) -> Tab {
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -226,6 +233,7 @@ fn create_new_tab_with_layout(size: Size, layout: TiledPaneLayout) -> Tab {
     let connected_clients = Rc::new(RefCell::new(connected_clients));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let debug = false;
@@ -250,6 +258,7 @@ fn create_new_tab_with_layout(size: Size, layout: TiledPaneLayout) -> Tab {
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -296,6 +305,7 @@ fn create_new_tab_with_cell_size(
     let connected_clients = Rc::new(RefCell::new(connected_clients));
     let terminal_emulator_colors = Rc::new(RefCell::new(Palette::default()));
     let copy_options = CopyOptions::default();
+    let copied_text = Rc::new(RefCell::new(None));
     let sixel_image_store = Rc::new(RefCell::new(SixelImageStore::default()));
     let terminal_emulator_color_codes = Rc::new(RefCell::new(HashMap::new()));
     let debug = false;
@@ -320,6 +330,7 @@ fn create_new_tab_with_cell_size(
         session_is_mirrored,
         client_id,
         copy_options,
+        copied_text,
         terminal_emulator_colors,
         terminal_emulator_color_codes,
         (vec![], vec![]), // swap layouts
@@ -14436,3 +14447,51 @@ fn correctly_resize_frameless_panes_on_pane_close() {
     let content_size = (pane.get_content_columns(), pane.get_content_rows());
     assert_eq!(content_size, (cols, rows));
 }
+
+#[test]
+fn mouse_target_is_pane_frame_or_pane_body() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut tab = create_new_tab(size);
+    tab.set_pane_frames(true);
+    assert_eq!(
+        tab.get_mouse_target(&Position::new(0, 10)),
+        Some(MouseTarget::PaneFrame),
+        "top border is on the pane frame"
+    );
+    assert_eq!(
+        tab.get_mouse_target(&Position::new(5, 10)),
+        Some(MouseTarget::PaneBody),
+        "inside the pane is on the pane body"
+    );
+    tab.set_pane_frames(false);
+    assert_eq!(
+        tab.get_mouse_target(&Position::new(0, 10)),
+        Some(MouseTarget::PaneBody),
+        "frameless panes have no frame to click on"
+    );
+}
+
+#[test]
+fn mouse_target_is_tab_bar_on_the_pane_marked_as_the_tab_bar() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut tab = create_new_tab(size);
+    tab.set_pane_frames(true);
+    tab.get_active_pane_or_floating_pane_mut(1)
+        .unwrap()
+        .set_is_tab_bar(true);
+    assert_eq!(
+        tab.get_mouse_target(&Position::new(0, 10)),
+        Some(MouseTarget::TabBar),
+        "the whole pane is the tab bar, including its frame"
+    );
+    assert_eq!(
+        tab.get_mouse_target(&Position::new(5, 10)),
+        Some(MouseTarget::TabBar),
+    );
+}
@@ -14,15 +14,15 @@ pub fn ribbon(
     let colors = style.colors;
     let (first_arrow_styles, text_style, last_arrow_styles) = if content.selected {
         (
-            character_style(colors.black, colors.green),
-            character_style(colors.black, colors.green),
-            character_style(colors.green, colors.black),
+            character_style(colors.black, colors.ribbon_selected_bg()),
+            character_style(colors.ribbon_selected_fg(), colors.ribbon_selected_bg()),
+            character_style(colors.ribbon_selected_bg(), colors.black),
         )
     } else {
         (
-            character_style(colors.black, colors.fg),
-            character_style(colors.black, colors.fg),
-            character_style(colors.fg, colors.black),
+            character_style(colors.black, colors.ribbon_unselected_bg()),
+            character_style(colors.ribbon_unselected_fg(), colors.ribbon_unselected_bg()),
+            character_style(colors.ribbon_unselected_bg(), colors.black),
         )
     };
     let (text, _text_width) =
@@ -284,6 +284,11 @@ impl Display for LoadingIndication {
                 red.bold()
                     .paint("ERROR IN PLUGIN - check logs for more info")
             ));
+            stringified.push_str(&format!(
+                "\n\r{} {}",
+                bold.paint("Press <r> to"),
+                green.paint("reload the plugin")
+            ));
         }
         write!(f, "{}", stringified)
     }
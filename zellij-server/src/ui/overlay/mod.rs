@@ -5,8 +5,11 @@
 //! prompt's:
 //!
 //! notification's:
+//!
+//! which-key's, listing the keybindings of the current input mode:
 
 pub mod prompt;
+pub mod which_key;
 
 use crate::ServerInstruction;
 use zellij_utils::errors::prelude::*;
@@ -26,6 +29,7 @@ pub trait Overlayable {
 #[derive(Clone, Debug)]
 pub enum OverlayType {
     Prompt(prompt::Prompt),
+    WhichKey(which_key::WhichKey),
 }
 
 impl Overlayable for OverlayType {
@@ -34,6 +38,9 @@ impl Overlayable for OverlayType {
             OverlayType::Prompt(prompt) => prompt
                 .generate_overlay(size)
                 .context("failed to generate VTE output from overlay type"),
+            OverlayType::WhichKey(which_key) => which_key
+                .generate_overlay(size)
+                .context("failed to generate VTE output from overlay type"),
         }
     }
 }
@@ -65,11 +72,13 @@ impl Overlay {
     pub fn prompt_confirm(self) -> Option<Box<ServerInstruction>> {
         match self.overlay_type {
             OverlayType::Prompt(p) => p.confirm(),
+            OverlayType::WhichKey(_) => None,
         }
     }
     pub fn prompt_deny(self) -> Option<Box<ServerInstruction>> {
         match self.overlay_type {
             OverlayType::Prompt(p) => p.deny(),
+            OverlayType::WhichKey(_) => None,
         }
     }
 }
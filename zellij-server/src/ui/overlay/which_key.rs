@@ -0,0 +1,474 @@
+use zellij_utils::pane_size::Size;
+
+use super::Overlayable;
+use zellij_utils::data::{InputMode, ModeInfo, PaletteColor, Resize};
+use zellij_utils::errors::prelude::*;
+use zellij_utils::input::actions::{Action, SearchDirection, SearchOption};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use unicode_width::UnicodeWidthStr;
+
+/// The categories bindings are grouped under, in the order they are listed
+const CATEGORIES: [&str; 9] = [
+    "Mode", "Pane", "Tab", "Resize", "Scroll", "Search", "Plugin", "Session", "Other",
+];
+/// Bindings wider than this are truncated so that the overlay can fit more columns
+const MAX_BINDING_WIDTH: usize = 40;
+/// The space between the columns of the overlay
+const COLUMN_GAP: usize = 3;
+/// The rows left free below the overlay, so that it does not cover the status bar
+const BOTTOM_MARGIN: usize = 2;
+
+macro_rules! style {
+    ($fg:expr) => {
+        ansi_term::Style::new().fg(match $fg {
+            PaletteColor::Rgb((r, g, b)) => ansi_term::Color::RGB(r, g, b),
+            PaletteColor::EightBit(color) => ansi_term::Color::Fixed(color),
+        })
+    };
+}
+
+/// Lists the keybindings of the current input mode, grouped by category
+#[derive(Clone, Debug)]
+pub struct WhichKey {
+    mode_info: ModeInfo,
+}
+
+enum Line {
+    Category(&'static str),
+    Binding { keys: String, description: String },
+    Blank,
+}
+
+impl Line {
+    fn width(&self, key_width: usize) -> usize {
+        match self {
+            Line::Category(category) => category.width(),
+            Line::Binding { description, .. } => {
+                (key_width + 1 + description.width()).min(MAX_BINDING_WIDTH)
+            },
+            Line::Blank => 0,
+        }
+    }
+}
+
+impl WhichKey {
+    pub fn new(mode_info: ModeInfo) -> Self {
+        WhichKey { mode_info }
+    }
+    /// Whether the overlay is shown in the input mode of `mode_info`, this is every mode except
+    /// the resting ones
+    pub fn is_shown_in_mode(mode_info: &ModeInfo) -> bool {
+        !matches!(mode_info.mode, InputMode::Normal | InputMode::Locked)
+            && Some(&mode_info.mode) != mode_info.base_mode.as_ref()
+    }
+    /// The bindings of the current mode, grouped by category, with the keys that trigger the same
+    /// actions joined together
+    pub fn categorized_bindings(&self) -> Vec<(&'static str, Vec<(String, String)>)> {
+        let mut categories: BTreeMap<usize, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        for (key, actions) in self.mode_info.get_mode_keybinds() {
+            let mut actions: Vec<&Action> = actions
+                .iter()
+                .filter(|action| !matches!(action, Action::NoOp))
+                .collect();
+            // a mode switch following other actions only returns to the base mode, listing it
+            // would just add noise
+            if actions.len() > 1 {
+                actions.retain(|action| !matches!(action, Action::SwitchToMode(_)));
+            }
+            let Some(first_action) = actions.first() else {
+                continue;
+            };
+            let category = action_category(first_action);
+            let category_index = CATEGORIES
+                .iter()
+                .position(|c| *c == category)
+                .unwrap_or(CATEGORIES.len() - 1);
+            let description = actions
+                .iter()
+                .map(|action| action_description(action))
+                .collect::<Vec<_>>()
+                .join(", ");
+            categories
+                .entry(category_index)
+                .or_default()
+                .entry(description)
+                .or_default()
+                .push(key.to_string());
+        }
+        categories
+            .into_iter()
+            .map(|(category_index, bindings)| {
+                let bindings = bindings
+                    .into_iter()
+                    .map(|(description, mut keys)| {
+                        keys.sort_by(|a, b| a.width().cmp(&b.width()).then(a.cmp(b)));
+                        (keys.join(" "), description)
+                    })
+                    .collect();
+                (CATEGORIES[category_index], bindings)
+            })
+            .collect()
+    }
+    fn title(&self) -> String {
+        let mode = format!("{:?}", self.mode_info.mode).to_uppercase();
+        if self.mode_info.pending_key_sequence.is_empty() {
+            format!(" {} ", mode)
+        } else {
+            let pending_keys = self
+                .mode_info
+                .pending_key_sequence
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(" {}: {} ... ", mode, pending_keys)
+        }
+    }
+    fn lines(&self) -> Vec<(Line, usize)> {
+        // usize - the width of the key column of the line's category
+        let mut lines = vec![];
+        for (category, bindings) in self.categorized_bindings() {
+            if !lines.is_empty() {
+                lines.push((Line::Blank, 0));
+            }
+            let key_width = bindings
+                .iter()
+                .map(|(keys, _)| keys.width())
+                .max()
+                .unwrap_or(0)
+                .min(MAX_BINDING_WIDTH / 2);
+            lines.push((Line::Category(category), key_width));
+            for (keys, description) in bindings {
+                lines.push((Line::Binding { keys, description }, key_width));
+            }
+        }
+        lines
+    }
+    fn render_line(&self, line: &Line, key_width: usize, column_width: usize) -> String {
+        let colors = self.mode_info.style.colors;
+        let rendered = match line {
+            Line::Category(category) => style!(colors.green).bold().paint(*category).to_string(),
+            Line::Binding { keys, description } => {
+                let keys = truncate(keys, key_width);
+                let keys_padding = " ".repeat(key_width.saturating_sub(keys.width()) + 1);
+                let description = truncate(description, column_width.saturating_sub(key_width + 1));
+                format!(
+                    "{}{}{}",
+                    style!(colors.orange).bold().paint(keys),
+                    keys_padding,
+                    description
+                )
+            },
+            Line::Blank => String::new(),
+        };
+        let padding = column_width.saturating_sub(line.width(key_width));
+        format!("{}{}", rendered, " ".repeat(padding))
+    }
+}
+
+impl Overlayable for WhichKey {
+    fn generate_overlay(&self, size: Size) -> Result<String> {
+        let err_context = || "failed to generate VTE output from which-key overlay";
+
+        let lines = self.lines();
+        if lines.is_empty() {
+            return Ok(String::new());
+        }
+        let max_rows = size.rows.saturating_sub(BOTTOM_MARGIN + 2).max(1);
+        let column_count = (lines.len() + max_rows - 1) / max_rows;
+        let rows_per_column = (lines.len() + column_count - 1) / column_count;
+        let mut columns: Vec<(&[(Line, usize)], usize)> = lines
+            .chunks(rows_per_column)
+            .map(|column| {
+                let width = column
+                    .iter()
+                    .map(|(line, key_width)| line.width(*key_width))
+                    .max()
+                    .unwrap_or(0);
+                (column, width)
+            })
+            .collect();
+
+        // columns that do not fit on the screen are dropped rather than squeezed
+        let title = self.title();
+        let box_width = |columns: &[(&[(Line, usize)], usize)]| {
+            let content_width = columns.iter().map(|(_, width)| width).sum::<usize>()
+                + COLUMN_GAP * columns.len().saturating_sub(1);
+            content_width.max(title.width() + 2) + 4 // borders and padding
+        };
+        while !columns.is_empty() && box_width(&columns) > size.cols {
+            columns.pop();
+        }
+        if columns.is_empty() {
+            return Ok(String::new());
+        }
+        let width = box_width(&columns);
+        let height = rows_per_column + 2;
+        let x = (size.cols - width) / 2;
+        let y = size.rows.saturating_sub(height + BOTTOM_MARGIN);
+
+        let (top_left, top_right, bottom_left, bottom_right) =
+            if self.mode_info.style.rounded_corners {
+                ("╭", "╮", "╰", "╯")
+            } else {
+                ("┌", "┐", "└", "┘")
+            };
+        let border = style!(self.mode_info.style.colors.green);
+        let inner_width = width - 2;
+
+        let mut output = String::new();
+        output.push_str("\u{1b}7"); // save the cursor position set by the tab
+        let top_border = format!(
+            "{}─{}{}{}",
+            top_left,
+            title,
+            "─".repeat(inner_width.saturating_sub(title.width() + 1)),
+            top_right
+        );
+        write!(
+            &mut output,
+            "\u{1b}[{};{}H\u{1b}[m{}",
+            y + 1,
+            x + 1,
+            border.paint(top_border)
+        )
+        .with_context(err_context)?;
+        for row in 0..rows_per_column {
+            let mut content = String::new();
+            let mut content_width = 0;
+            for (column_index, (column, column_width)) in columns.iter().enumerate() {
+                if column_index > 0 {
+                    content.push_str(&" ".repeat(COLUMN_GAP));
+                    content_width += COLUMN_GAP;
+                }
+                match column.get(row) {
+                    Some((line, key_width)) => {
+                        content.push_str(&self.render_line(line, *key_width, *column_width))
+                    },
+                    None => content.push_str(&" ".repeat(*column_width)),
+                }
+                content_width += column_width;
+            }
+            write!(
+                &mut output,
+                "\u{1b}[{};{}H\u{1b}[m{} {}{} {}",
+                y + row + 2,
+                x + 1,
+                border.paint("│"),
+                content,
+                " ".repeat(inner_width.saturating_sub(content_width + 2)),
+                border.paint("│"),
+            )
+            .with_context(err_context)?;
+        }
+        write!(
+            &mut output,
+            "\u{1b}[{};{}H\u{1b}[m{}",
+            y + height,
+            x + 1,
+            border.paint(format!(
+                "{}{}{}",
+                bottom_left,
+                "─".repeat(inner_width),
+                bottom_right
+            ))
+        )
+        .with_context(err_context)?;
+        output.push_str("\u{1b}[m\u{1b}8"); // restore the cursor position
+        Ok(output)
+    }
+}
+
+fn truncate(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_owned();
+    }
+    let mut truncated = String::new();
+    let mut width = 0;
+    for character in text.chars() {
+        let character_width = character.to_string().width();
+        if width + character_width + 1 > max_width {
+            break;
+        }
+        width += character_width;
+        truncated.push(character);
+    }
+    if max_width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+fn action_category(action: &Action) -> &'static str {
+    match action {
+        Action::SwitchToMode(_) | Action::SwitchModeForAllClients(_) => "Mode",
+        Action::Write(..)
+        | Action::WriteChars(_)
+        | Action::FocusNextPane
+        | Action::FocusPreviousPane
+        | Action::SwitchFocus
+        | Action::MoveFocus(_)
+        | Action::MovePane(_)
+        | Action::MovePaneBackwards
+        | Action::ClearScreen
+        | Action::ToggleFocusFullscreen
+        | Action::TogglePaneFrames
+        | Action::NewPane(..)
+        | Action::EditFile(..)
+        | Action::NewFloatingPane(..)
+        | Action::NewTiledPane(..)
+        | Action::NewInPlacePane(..)
+        | Action::TogglePaneEmbedOrFloating
+        | Action::ToggleFloatingPanes
+        | Action::CloseFocus
+        | Action::PaneNameInput(_)
+        | Action::UndoRenamePane
+        | Action::BreakPane
+        | Action::BreakPaneRight
+        | Action::BreakPaneLeft => "Pane",
+        Action::MoveFocusOrTab(_)
+        | Action::ToggleActiveSyncTab
+        | Action::NewTab(..)
+        | Action::GoToNextTab
+        | Action::GoToPreviousTab
+        | Action::CloseTab
+        | Action::GoToTab(_)
+        | Action::GoToTabName(..)
+        | Action::ToggleTab
+        | Action::TabNameInput(_)
+        | Action::UndoRenameTab
+        | Action::MoveTab(_)
+        | Action::PreviousSwapLayout
+        | Action::NextSwapLayout => "Tab",
+        Action::Resize(..) => "Resize",
+        Action::DumpScreen(..)
+        | Action::EditScrollback
+        | Action::ScrollUp
+        | Action::ScrollDown
+        | Action::ScrollToBottom
+        | Action::ScrollToTop
+        | Action::PageScrollUp
+        | Action::PageScrollDown
+        | Action::HalfPageScrollUp
+        | Action::HalfPageScrollDown => "Scroll",
+        Action::SearchInput(_) | Action::Search(_) | Action::SearchToggleOption(_) => "Search",
+        Action::LaunchOrFocusPlugin(..)
+        | Action::LaunchPlugin(..)
+        | Action::NewTiledPluginPane(..)
+        | Action::NewFloatingPluginPane(..)
+        | Action::NewInPlacePluginPane(..)
+        | Action::StartOrReloadPlugin(_)
+        | Action::KeybindPipe { .. } => "Plugin",
+        Action::Quit | Action::Detach | Action::RenameSession(_) | Action::ToggleMouseMode => {
+            "Session"
+        },
+        Action::SkipConfirm(action) => action_category(action),
+        _ => "Other",
+    }
+}
+
+fn action_description(action: &Action) -> String {
+    match action {
+        Action::Quit => "Quit".to_owned(),
+        Action::Write(..) => "Write to pane".to_owned(),
+        Action::WriteChars(chars) => format!("Write \"{}\"", chars),
+        Action::SwitchToMode(input_mode) | Action::SwitchModeForAllClients(input_mode) => {
+            format!("{:?} mode", input_mode)
+        },
+        Action::Resize(resize, direction) => {
+            let resize = match resize {
+                Resize::Increase => "Increase size",
+                Resize::Decrease => "Decrease size",
+            };
+            match direction {
+                Some(direction) => format!("{} {}", resize, direction),
+                None => resize.to_owned(),
+            }
+        },
+        Action::FocusNextPane => "Focus next pane".to_owned(),
+        Action::FocusPreviousPane => "Focus previous pane".to_owned(),
+        Action::SwitchFocus => "Switch focus".to_owned(),
+        Action::MoveFocus(direction) => format!("Move focus {}", direction),
+        Action::MoveFocusOrTab(direction) => format!("Move focus or tab {}", direction),
+        Action::MovePane(Some(direction)) => format!("Move pane {}", direction),
+        Action::MovePane(None) => "Move pane".to_owned(),
+        Action::MovePaneBackwards => "Move pane backwards".to_owned(),
+        Action::ClearScreen => "Clear screen".to_owned(),
+        Action::DumpScreen(..) => "Dump screen to file".to_owned(),
+        Action::EditScrollback => "Edit scrollback".to_owned(),
+        Action::ScrollUp => "Scroll up".to_owned(),
+        Action::ScrollDown => "Scroll down".to_owned(),
+        Action::ScrollToBottom => "Scroll to bottom".to_owned(),
+        Action::ScrollToTop => "Scroll to top".to_owned(),
+        Action::PageScrollUp => "Page up".to_owned(),
+        Action::PageScrollDown => "Page down".to_owned(),
+        Action::HalfPageScrollUp => "Half page up".to_owned(),
+        Action::HalfPageScrollDown => "Half page down".to_owned(),
+        Action::ToggleFocusFullscreen => "Toggle fullscreen".to_owned(),
+        Action::TogglePaneFrames => "Toggle pane frames".to_owned(),
+        Action::ToggleActiveSyncTab => "Toggle sync".to_owned(),
+        Action::NewPane(Some(direction), ..) => format!("New pane {}", direction),
+        Action::NewPane(None, ..) | Action::NewTiledPane(..) => "New pane".to_owned(),
+        Action::EditFile(..) => "Edit file".to_owned(),
+        Action::NewFloatingPane(..) => "New floating pane".to_owned(),
+        Action::NewInPlacePane(..) => "New pane in place".to_owned(),
+        Action::TogglePaneEmbedOrFloating => "Embed or float pane".to_owned(),
+        Action::ToggleFloatingPanes => "Toggle floating panes".to_owned(),
+        Action::CloseFocus => "Close pane".to_owned(),
+        Action::UndoRenamePane | Action::UndoRenameTab => "Undo rename".to_owned(),
+        Action::NewTab(..) => "New tab".to_owned(),
+        Action::GoToNextTab => "Next tab".to_owned(),
+        Action::GoToPreviousTab => "Previous tab".to_owned(),
+        Action::CloseTab => "Close tab".to_owned(),
+        Action::GoToTab(index) => format!("Go to tab {}", index),
+        Action::GoToTabName(name, _) => format!("Go to tab \"{}\"", name),
+        Action::ToggleTab => "Toggle tab".to_owned(),
+        Action::MoveTab(direction) => format!("Move tab {}", direction),
+        Action::Run(run_command) => format!("Run {}", run_command.command.display()),
+        Action::Detach => "Detach".to_owned(),
+        Action::Copy => "Copy".to_owned(),
+        Action::Confirm => "Confirm".to_owned(),
+        Action::Deny => "Deny".to_owned(),
+        Action::SkipConfirm(action) => action_description(action),
+        Action::Search(SearchDirection::Down) => "Search down".to_owned(),
+        Action::Search(SearchDirection::Up) => "Search up".to_owned(),
+        Action::SearchToggleOption(SearchOption::CaseSensitivity) => {
+            "Toggle case sensitivity".to_owned()
+        },
+        Action::SearchToggleOption(SearchOption::WholeWord) => "Toggle whole word".to_owned(),
+        Action::SearchToggleOption(SearchOption::Wrap) => "Toggle wrap".to_owned(),
+        Action::ToggleMouseMode => "Toggle mouse mode".to_owned(),
+        Action::PreviousSwapLayout => "Previous swap layout".to_owned(),
+        Action::NextSwapLayout => "Next swap layout".to_owned(),
+        Action::LaunchOrFocusPlugin(plugin, ..)
+        | Action::LaunchPlugin(plugin, ..)
+        | Action::NewTiledPluginPane(plugin, ..)
+        | Action::NewFloatingPluginPane(plugin, ..)
+        | Action::NewInPlacePluginPane(plugin, ..) => {
+            format!("Open {}", plugin.location_string())
+        },
+        Action::StartOrReloadPlugin(plugin) => format!("Reload {}", plugin.location_string()),
+        Action::KeybindPipe {
+            name: Some(name), ..
+        } => format!("Pipe \"{}\"", name),
+        Action::KeybindPipe { .. } => "Pipe to plugin".to_owned(),
+        Action::BreakPane => "Break pane to new tab".to_owned(),
+        Action::BreakPaneRight => "Break pane to tab →".to_owned(),
+        Action::BreakPaneLeft => "Break pane to tab ←".to_owned(),
+        Action::RenameSession(name) => format!("Rename session to \"{}\"", name),
+        Action::StartMacroRecording(name) => format!("Record macro \"{}\"", name),
+        Action::StopMacroRecording => "Stop recording macro".to_owned(),
+        Action::PlayMacro(name) => format!("Play macro \"{}\"", name),
+        other => {
+            // fall back to the name of the action, without its arguments
+            let debug = format!("{:?}", other);
+            debug
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or_default()
+                .to_owned()
+        },
+    }
+}
@@ -773,9 +773,9 @@ impl PaneFrame {
                 let exited_text = "EXIT CODE: ";
                 let exit_code_text = format!("{}", exit_code);
                 let exit_code_color = if exit_code == 0 {
-                    self.style.colors.green
+                    self.style.colors.exit_code_success()
                 } else {
-                    self.style.colors.red
+                    self.style.colors.exit_code_error()
                 };
                 let right_bracket = " ] ";
                 first_part.append(&mut foreground_color(left_bracket, self.color));
@@ -801,7 +801,7 @@ impl PaneFrame {
                 first_part.append(&mut foreground_color(left_bracket, self.color));
                 first_part.append(&mut foreground_color(
                     exited_text,
-                    Some(self.style.colors.red),
+                    Some(self.style.colors.exit_code_error()),
                 ));
                 first_part.append(&mut foreground_color(right_bracket, self.color));
                 (
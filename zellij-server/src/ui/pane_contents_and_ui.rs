@@ -1,4 +1,4 @@
-use crate::output::Output;
+use crate::output::{CharacterChunk, Output};
 use crate::panes::PaneId;
 use crate::tab::Pane;
 use crate::ui::boundaries::Boundaries;
@@ -6,7 +6,7 @@ use crate::ui::pane_boundaries_frame::FrameParams;
 use crate::ClientId;
 use std::collections::HashMap;
 use zellij_utils::data::{
-    client_id_to_colors, single_client_color, InputMode, PaletteColor, Style,
+    client_id_to_colors, single_client_color, InputMode, Palette, PaletteColor, PaneColors, Style,
 };
 use zellij_utils::errors::prelude::*;
 pub struct PaneContentsAndUi<'a> {
@@ -19,6 +19,7 @@ pub struct PaneContentsAndUi<'a> {
     pane_is_stacked_under: bool,
     pane_is_stacked_over: bool,
     should_draw_pane_frames: bool,
+    pane_colors: PaneColors,
 }
 
 impl<'a> PaneContentsAndUi<'a> {
@@ -32,6 +33,7 @@ impl<'a> PaneContentsAndUi<'a> {
         pane_is_stacked_under: bool,
         pane_is_stacked_over: bool,
         should_draw_pane_frames: bool,
+        tab_colors: PaneColors,
     ) -> Self {
         let mut focused_clients: Vec<ClientId> = active_panes
             .iter()
@@ -39,6 +41,7 @@ impl<'a> PaneContentsAndUi<'a> {
             .map(|(c_id, _p_id)| *c_id)
             .collect();
         focused_clients.sort_unstable();
+        let pane_colors = tab_colors.merge(&pane.pane_colors());
         PaneContentsAndUi {
             pane,
             output,
@@ -49,6 +52,7 @@ impl<'a> PaneContentsAndUi<'a> {
             pane_is_stacked_under,
             pane_is_stacked_over,
             should_draw_pane_frames,
+            pane_colors,
         }
     }
     pub fn render_pane_contents_to_multiple_clients(
@@ -57,17 +61,45 @@ impl<'a> PaneContentsAndUi<'a> {
     ) -> Result<()> {
         let err_context = "failed to render pane contents to multiple clients";
 
-        if let Some((character_chunks, raw_vte_output, sixel_image_chunks)) =
+        if let Some((mut character_chunks, raw_vte_output, sixel_image_chunks)) =
             self.pane.render(None).context(err_context)?
         {
+            self.tint_background(&mut character_chunks);
             let clients: Vec<ClientId> = clients.collect();
-            self.output
-                .add_character_chunks_to_multiple_clients(
-                    character_chunks,
-                    clients.iter().copied(),
-                    self.z_index,
-                )
-                .context(err_context)?;
+            if self.output.dims_unfocused_panes() {
+                // each client dims the pane according to its own focus
+                let (focused_clients, unfocused_clients): (Vec<ClientId>, Vec<ClientId>) = clients
+                    .iter()
+                    .partition(|client_id| self.focused_clients.contains(client_id));
+                if !unfocused_clients.is_empty() {
+                    let mut dimmed_character_chunks = character_chunks.clone();
+                    for character_chunk in dimmed_character_chunks.iter_mut() {
+                        character_chunk.set_dimmed();
+                    }
+                    self.output
+                        .add_character_chunks_to_multiple_clients(
+                            dimmed_character_chunks,
+                            unfocused_clients.iter().copied(),
+                            self.z_index,
+                        )
+                        .context(err_context)?;
+                }
+                self.output
+                    .add_character_chunks_to_multiple_clients(
+                        character_chunks,
+                        focused_clients.iter().copied(),
+                        self.z_index,
+                    )
+                    .context(err_context)?;
+            } else {
+                self.output
+                    .add_character_chunks_to_multiple_clients(
+                        character_chunks,
+                        clients.iter().copied(),
+                        self.z_index,
+                    )
+                    .context(err_context)?;
+            }
             self.output.add_sixel_image_chunks_to_multiple_clients(
                 sixel_image_chunks,
                 clients.iter().copied(),
@@ -92,11 +124,17 @@ impl<'a> PaneContentsAndUi<'a> {
     pub fn render_pane_contents_for_client(&mut self, client_id: ClientId) -> Result<()> {
         let err_context = || format!("failed to render pane contents for client {client_id}");
 
-        if let Some((character_chunks, raw_vte_output, sixel_image_chunks)) = self
+        if let Some((mut character_chunks, raw_vte_output, sixel_image_chunks)) = self
             .pane
             .render(Some(client_id))
             .with_context(err_context)?
         {
+            self.tint_background(&mut character_chunks);
+            if self.output.dims_unfocused_panes() && !self.focused_clients.contains(&client_id) {
+                for character_chunk in character_chunks.iter_mut() {
+                    character_chunk.set_dimmed();
+                }
+            }
             self.output
                 .add_character_chunks_to_client(client_id, character_chunks, self.z_index)
                 .with_context(err_context)?;
@@ -119,6 +157,13 @@ impl<'a> PaneContentsAndUi<'a> {
         }
         Ok(())
     }
+    fn tint_background(&self, character_chunks: &mut [CharacterChunk]) {
+        if let Some(background) = self.pane_colors.background {
+            for character_chunk in character_chunks.iter_mut() {
+                character_chunk.set_default_background(background.into());
+            }
+        }
+    }
     pub fn render_fake_cursor_if_needed(&mut self, client_id: ClientId) -> Result<()> {
         let pane_focused_for_client_id = self.focused_clients.contains(&client_id);
         let pane_focused_for_different_client = self
@@ -174,6 +219,7 @@ impl<'a> PaneContentsAndUi<'a> {
         &mut self,
         client_id: ClientId,
         client_mode: InputMode,
+        client_colors: Palette,
         session_is_mirrored: bool,
     ) -> Result<()> {
         let err_context = || format!("failed to render pane frame for client {client_id}");
@@ -187,7 +233,17 @@ impl<'a> PaneContentsAndUi<'a> {
             .collect();
         let pane_focused_for_differet_client = !other_focused_clients.is_empty();
 
-        let frame_color = self.frame_color(client_id, client_mode, session_is_mirrored);
+        let frame_color = self.frame_color(
+            client_id,
+            client_mode.clone(),
+            client_colors,
+            session_is_mirrored,
+        );
+        // every client sees the frames in its own theme
+        let style = Style {
+            colors: client_colors,
+            ..self.style
+        };
         let focused_client = if pane_focused_for_client_id {
             Some(client_id)
         } else if pane_focused_for_differet_client {
@@ -200,7 +256,7 @@ impl<'a> PaneContentsAndUi<'a> {
                 focused_client,
                 is_main_client: pane_focused_for_client_id,
                 other_focused_clients: vec![],
-                style: self.style,
+                style,
                 color: frame_color,
                 other_cursors_exist_in_session: false,
                 pane_is_stacked_over: self.pane_is_stacked_over,
@@ -212,7 +268,7 @@ impl<'a> PaneContentsAndUi<'a> {
                 focused_client,
                 is_main_client: pane_focused_for_client_id,
                 other_focused_clients,
-                style: self.style,
+                style,
                 color: frame_color,
                 other_cursors_exist_in_session: self.multiple_users_exist_in_session,
                 pane_is_stacked_over: self.pane_is_stacked_over,
@@ -240,36 +296,44 @@ impl<'a> PaneContentsAndUi<'a> {
         &self,
         client_id: ClientId,
         client_mode: InputMode,
+        client_colors: Palette,
         boundaries: &mut Boundaries,
         session_is_mirrored: bool,
     ) {
-        let color = self.frame_color(client_id, client_mode, session_is_mirrored);
+        let color = self.frame_color(client_id, client_mode, client_colors, session_is_mirrored);
         boundaries.add_rect(self.pane.as_ref(), color);
     }
     fn frame_color(
         &self,
         client_id: ClientId,
         mode: InputMode,
+        colors: Palette,
         session_is_mirrored: bool,
     ) -> Option<PaletteColor> {
         let pane_focused_for_client_id = self.focused_clients.contains(&client_id);
         if let Some(override_color) = self.pane.frame_color_override() {
             Some(override_color)
+        } else if self.pane.is_selected() {
+            Some(colors.magenta)
         } else if pane_focused_for_client_id {
             match mode {
                 InputMode::Normal | InputMode::Locked => {
-                    if session_is_mirrored || !self.multiple_users_exist_in_session {
-                        let colors = single_client_color(self.style.colors); // mirrored sessions only have one focused color
+                    if let Some(frame_focused) = self.pane_colors.frame_focused {
+                        Some(frame_focused)
+                    } else if session_is_mirrored || !self.multiple_users_exist_in_session {
+                        let colors = single_client_color(colors); // mirrored sessions only have one focused color
                         Some(colors.0)
                     } else {
-                        let colors = client_id_to_colors(client_id, self.style.colors);
+                        let colors = client_id_to_colors(client_id, colors);
                         colors.map(|colors| colors.0)
                     }
                 },
-                _ => Some(self.style.colors.orange),
+                _ => Some(colors.frame_highlight()),
             }
+        } else if !session_is_mirrored && !self.focused_clients.is_empty() {
+            self.pane_colors.frame.or(colors.frame_other_client())
         } else {
-            None
+            self.pane_colors.frame.or(colors.frame_unfocused())
         }
     }
 }
@@ -0,0 +1,585 @@
+use super::{
+    end_macro_replay, route_key_or_key_sequence, route_mouse_actions, route_mouse_press,
+    route_reinjected_key, start_macro_replay, ClientKeyState,
+};
+use crate::{
+    os_input_output::{AsyncReader, Pid, ServerOsApi},
+    panes::PaneId,
+    plugins::PluginInstruction,
+    screen::ScreenInstruction,
+    thread_bus::ThreadSenders,
+    ClientId, SessionMetaData,
+};
+use std::collections::HashMap;
+use std::os::unix::io::RawFd;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use zellij_utils::{
+    channels::{self, ChannelWithContext, Receiver, SenderWithContext},
+    data::{BareKey, Event, InputMode, KeyWithModifier, Palette, PluginCapabilities},
+    errors::{prelude::*, ErrorContext},
+    input::{
+        command::{RunCommand, TerminalAction},
+        config::Config,
+        layout::Layout,
+        macros::{Macro, MacroInstruction},
+        mouse::{MouseButton, MouseButtonWithModifier},
+    },
+    interprocess::local_socket::LocalSocketStream,
+    ipc::{ClientAttributes, ClientToServerMsg, IpcReceiverWithContext, ServerToClientMsg},
+    position::Position,
+};
+
+#[derive(Clone, Default)]
+struct FakeInputOutput {
+    sent_to_client: Arc<Mutex<Vec<ServerToClientMsg>>>,
+}
+
+impl ServerOsApi for FakeInputOutput {
+    fn set_terminal_size_using_terminal_id(
+        &self,
+        _id: u32,
+        _cols: u16,
+        _rows: u16,
+        _width_in_pixels: Option<u16>,
+        _height_in_pixels: Option<u16>,
+    ) -> Result<()> {
+        Ok(())
+    }
+    fn spawn_terminal(
+        &self,
+        _file_to_open: TerminalAction,
+        _quit_cb: Box<dyn Fn(PaneId, Option<i32>, RunCommand) + Send>,
+        _default_editor: Option<PathBuf>,
+    ) -> Result<(u32, RawFd, RawFd)> {
+        unimplemented!()
+    }
+    fn read_from_tty_stdout(&self, _fd: RawFd, _buf: &mut [u8]) -> Result<usize> {
+        unimplemented!()
+    }
+    fn async_file_reader(&self, _fd: RawFd) -> Box<dyn AsyncReader> {
+        unimplemented!()
+    }
+    fn write_to_tty_stdin(&self, _id: u32, _buf: &[u8]) -> Result<usize> {
+        unimplemented!()
+    }
+    fn tcdrain(&self, _id: u32) -> Result<()> {
+        unimplemented!()
+    }
+    fn kill(&self, _pid: Pid) -> Result<()> {
+        unimplemented!()
+    }
+    fn force_kill(&self, _pid: Pid) -> Result<()> {
+        unimplemented!()
+    }
+    fn box_clone(&self) -> Box<dyn ServerOsApi> {
+        Box::new((*self).clone())
+    }
+    fn send_to_client(&self, _client_id: ClientId, msg: ServerToClientMsg) -> Result<()> {
+        self.sent_to_client.lock().unwrap().push(msg);
+        Ok(())
+    }
+    fn new_client(
+        &mut self,
+        _client_id: ClientId,
+        _stream: LocalSocketStream,
+    ) -> Result<IpcReceiverWithContext<ClientToServerMsg>> {
+        unimplemented!()
+    }
+    fn remove_client(&mut self, _client_id: ClientId) -> Result<()> {
+        unimplemented!()
+    }
+    fn load_palette(&self) -> Palette {
+        unimplemented!()
+    }
+    fn get_cwd(&self, _pid: Pid) -> Option<PathBuf> {
+        unimplemented!()
+    }
+    fn write_to_file(&mut self, _buf: String, _name: Option<String>) -> Result<()> {
+        unimplemented!()
+    }
+    fn re_run_command_in_terminal(
+        &self,
+        _terminal_id: u32,
+        _run_command: RunCommand,
+        _quit_cb: Box<dyn Fn(PaneId, Option<i32>, RunCommand) + Send>,
+    ) -> Result<(RawFd, RawFd)> {
+        unimplemented!()
+    }
+    fn clear_terminal_id(&self, _terminal_id: u32) -> Result<()> {
+        unimplemented!()
+    }
+    fn open_hyperlink(&self, _uri: &str) -> Result<()> {
+        unimplemented!()
+    }
+}
+
+const CLIENT_ID: ClientId = 1;
+const PLUGIN_ID: u32 = 5;
+
+struct MockSession {
+    session_data: Arc<RwLock<Option<SessionMetaData>>>,
+    client_key_state: ClientKeyState,
+    os_input: Box<dyn ServerOsApi>,
+    sent_to_client: Arc<Mutex<Vec<ServerToClientMsg>>>,
+    screen_receiver: Receiver<(ScreenInstruction, ErrorContext)>,
+    plugin_receiver: Receiver<(PluginInstruction, ErrorContext)>,
+}
+
+impl MockSession {
+    fn new(config: &str) -> Self {
+        let (to_screen, screen_receiver): ChannelWithContext<ScreenInstruction> =
+            channels::unbounded();
+        let (to_plugin, plugin_receiver): ChannelWithContext<PluginInstruction> =
+            channels::unbounded();
+        let mut session_metadata = SessionMetaData {
+            senders: ThreadSenders {
+                to_screen: Some(SenderWithContext::new(to_screen)),
+                to_pty: None,
+                to_plugin: Some(SenderWithContext::new(to_plugin)),
+                to_pty_writer: None,
+                to_background_jobs: None,
+                to_server: None,
+                should_silently_fail: true,
+            },
+            capabilities: PluginCapabilities::default(),
+            client_attributes: ClientAttributes::default(),
+            default_shell: None,
+            layout: Box::new(Layout::default()),
+            current_input_modes: HashMap::new(),
+            current_color_schemes: HashMap::new(),
+            session_configuration: Default::default(),
+            key_intercepts: Default::default(),
+            focused_panes: HashMap::new(),
+            screen_thread: None,
+            pty_thread: None,
+            plugin_thread: None,
+            pty_writer_thread: None,
+            background_jobs_thread: None,
+        };
+        session_metadata
+            .session_configuration
+            .set_client_saved_configuration(CLIENT_ID, Config::from_kdl(config, None).unwrap());
+        session_metadata
+            .current_input_modes
+            .insert(CLIENT_ID, InputMode::Normal);
+        let os_input = FakeInputOutput::default();
+        MockSession {
+            session_data: Arc::new(RwLock::new(Some(session_metadata))),
+            client_key_state: ClientKeyState::default(),
+            sent_to_client: os_input.sent_to_client.clone(),
+            os_input: Box::new(os_input),
+            screen_receiver,
+            plugin_receiver,
+        }
+    }
+    fn intercept_keys(&self, keys: Vec<KeyWithModifier>) {
+        self.session_data
+            .write()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .key_intercepts
+            .set(PLUGIN_ID, keys, vec![]);
+    }
+    fn type_key(&self, key: KeyWithModifier) {
+        // what the route thread does when the key is received
+        self.client_key_state
+            .macro_state
+            .lock()
+            .unwrap()
+            .keys_received += 1;
+        let session = self.session_data.read().unwrap();
+        route_key_or_key_sequence(
+            (
+                key.clone(),
+                key.serialize_non_kitty().unwrap().into_bytes(),
+                false,
+            ),
+            CLIENT_ID,
+            session.as_ref().unwrap(),
+            &self.session_data,
+            &self.client_key_state.pending_key_sequence,
+            &*self.os_input,
+            &self.client_key_state.macro_state,
+            None,
+        )
+        .unwrap();
+    }
+    fn reinject_key(&self, key: KeyWithModifier) {
+        let session = self.session_data.read().unwrap();
+        route_reinjected_key(
+            key,
+            CLIENT_ID,
+            session.as_ref().unwrap(),
+            &self.session_data,
+            &self.client_key_state,
+            &*self.os_input,
+        )
+        .unwrap();
+    }
+    fn press_mouse(&self, button: MouseButtonWithModifier, position: Position) {
+        let session = self.session_data.read().unwrap();
+        route_mouse_press(
+            button,
+            position,
+            CLIENT_ID,
+            session.as_ref().unwrap(),
+            &*self.os_input,
+            &self.client_key_state.macro_state,
+            None,
+        )
+        .unwrap();
+    }
+    fn route_mouse_actions_from_screen(&self) {
+        // what the screen thread sends back once it knows what is under the mouse
+        let routed_actions: Vec<_> = self
+            .screen_receiver
+            .try_iter()
+            .filter_map(|(instruction, _)| match instruction {
+                ScreenInstruction::MousePress(button, mouse_bindings, position, client_id) => {
+                    Some((
+                        mouse_bindings.actions_for_press(&button, None, position),
+                        client_id,
+                    ))
+                },
+                _ => None,
+            })
+            .collect();
+        let session = self.session_data.read().unwrap();
+        for (actions, client_id) in routed_actions {
+            route_mouse_actions(
+                actions,
+                client_id,
+                session.as_ref().unwrap(),
+                &self.client_key_state,
+                &*self.os_input,
+            )
+            .unwrap();
+        }
+    }
+    fn start_macro_replay(&self, depth: usize, start_mode: Option<InputMode>) {
+        let session = self.session_data.read().unwrap();
+        start_macro_replay(
+            depth,
+            start_mode,
+            CLIENT_ID,
+            session.as_ref().unwrap(),
+            &*self.os_input,
+            &self.client_key_state.macro_state,
+        )
+        .unwrap();
+    }
+    fn end_macro_replay(&self) {
+        let session = self.session_data.read().unwrap();
+        end_macro_replay(
+            CLIENT_ID,
+            session.as_ref().unwrap(),
+            &self.client_key_state.macro_state,
+        )
+        .unwrap();
+    }
+    fn macro_instructions_sent_to_client(&self) -> Vec<MacroInstruction> {
+        self.sent_to_client
+            .lock()
+            .unwrap()
+            .drain(..)
+            .filter_map(|msg| match msg {
+                ServerToClientMsg::Macro(macro_instruction) => Some(macro_instruction),
+                _ => None,
+            })
+            .collect()
+    }
+    fn keys_written_to_pane(&self) -> Vec<KeyWithModifier> {
+        self.screen_receiver
+            .try_iter()
+            .filter_map(|(instruction, _)| match instruction {
+                ScreenInstruction::WriteCharacter(key, ..) => key,
+                _ => None,
+            })
+            .collect()
+    }
+    fn screen_switched_to_next_tab(&self) -> bool {
+        self.screen_receiver
+            .try_iter()
+            .any(|(instruction, _)| matches!(instruction, ScreenInstruction::SwitchTabNext(..)))
+    }
+    fn keys_sent_to_plugin(&self) -> Vec<KeyWithModifier> {
+        self.plugin_receiver
+            .try_iter()
+            .flat_map(|(instruction, _)| match instruction {
+                PluginInstruction::Update(updates) => updates,
+                _ => vec![],
+            })
+            .filter_map(|(plugin_id, _, event)| match (plugin_id, event) {
+                (Some(PLUGIN_ID), Event::InterceptedKey(key)) => Some(key),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+const NO_KEYBINDS: &str = "keybinds clear-defaults=true {}";
+
+#[test]
+fn intercepted_key_goes_to_plugin_and_not_to_pane() {
+    let session = MockSession::new(NO_KEYBINDS);
+    let key = KeyWithModifier::new(BareKey::Char('a'));
+    session.intercept_keys(vec![key.clone()]);
+    session.type_key(key.clone());
+    assert_eq!(session.keys_sent_to_plugin(), vec![key]);
+    assert_eq!(session.keys_written_to_pane(), vec![]);
+}
+
+#[test]
+fn keys_are_routed_normally_once_released() {
+    let session = MockSession::new(NO_KEYBINDS);
+    let intercepted_key = KeyWithModifier::new(BareKey::Char('a'));
+    let other_key = KeyWithModifier::new(BareKey::Char('b'));
+    session.intercept_keys(vec![intercepted_key.clone()]);
+    session.type_key(other_key.clone());
+    assert_eq!(session.keys_written_to_pane(), vec![other_key]);
+
+    session.intercept_keys(vec![]);
+    session.type_key(intercepted_key.clone());
+    assert_eq!(session.keys_sent_to_plugin(), vec![]);
+    assert_eq!(session.keys_written_to_pane(), vec![intercepted_key]);
+}
+
+#[test]
+fn intercepts_are_dropped_when_plugin_unloads() {
+    let session = MockSession::new(NO_KEYBINDS);
+    let key = KeyWithModifier::new(BareKey::Char('a'));
+    session.intercept_keys(vec![key.clone()]);
+    // what unloading a plugin sends to the server
+    session
+        .session_data
+        .write()
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .key_intercepts
+        .set(PLUGIN_ID, vec![], vec![]);
+    assert_eq!(
+        session
+            .session_data
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .key_intercepts
+            .intercepting_plugin(&key, &InputMode::Normal),
+        None
+    );
+    session.type_key(key.clone());
+    assert_eq!(session.keys_sent_to_plugin(), vec![]);
+    assert_eq!(session.keys_written_to_pane(), vec![key]);
+}
+
+#[test]
+fn reinjected_key_continues_the_key_sequence_of_the_client() {
+    let session = MockSession::new(
+        r#"
+        keybinds clear-defaults=true {
+            normal {
+                bind "Ctrl a" "c" sequence=true { GoToNextTab; }
+            }
+        }
+    "#,
+    );
+    let leader_key = KeyWithModifier::new(BareKey::Char('a')).with_ctrl_modifier();
+    let key = KeyWithModifier::new(BareKey::Char('c'));
+    session.intercept_keys(vec![key.clone()]);
+    session.type_key(leader_key);
+    session.type_key(key.clone());
+    assert_eq!(session.keys_sent_to_plugin(), vec![key.clone()]);
+    session.reinject_key(key);
+    assert!(
+        session.screen_switched_to_next_tab(),
+        "re-injected key completed the sequence"
+    );
+}
+
+#[test]
+fn untargeted_mouse_binding_is_routed_without_asking_the_screen() {
+    let session = MockSession::new(
+        r#"
+        mouse {
+            bind "MiddleClick" { FocusPaneAtMouse; Paste; }
+        }
+    "#,
+    );
+    let position = Position::new(3, 10);
+    session.press_mouse(MouseButtonWithModifier::new(MouseButton::Middle), position);
+    let screen_instructions: Vec<_> = session
+        .screen_receiver
+        .try_iter()
+        .map(|(instruction, _)| instruction)
+        .collect();
+    assert!(matches!(
+        screen_instructions.as_slice(),
+        [
+            ScreenInstruction::FocusPaneAt(focused_position, CLIENT_ID),
+            ScreenInstruction::Paste(CLIENT_ID),
+        ] if *focused_position == position
+    ));
+}
+
+#[test]
+fn targeted_mouse_binding_is_resolved_by_the_screen() {
+    let session = MockSession::new(
+        r#"
+        mouse {
+            bind "RightClick" target="tab_bar" { GoToNextTab; }
+            bind "RightClick" { GoToNextTab; }
+        }
+    "#,
+    );
+    session.press_mouse(
+        MouseButtonWithModifier::new(MouseButton::Right),
+        Position::new(0, 10),
+    );
+    // anything routed without the screen would be drained here and not seen below
+    session.route_mouse_actions_from_screen();
+    assert!(
+        session.screen_switched_to_next_tab(),
+        "actions picked by the screen were routed"
+    );
+}
+
+fn char_key(character: char) -> KeyWithModifier {
+    KeyWithModifier::new(BareKey::Char(character))
+}
+
+const MACRO_KEYBINDS: &str = r#"
+    keybinds clear-defaults=true {
+        normal {
+            bind "Alt q" { StartMacroRecording "q"; }
+            bind "Alt w" { SwitchToMode "Locked"; StartMacroRecording "w"; }
+            bind "Ctrl a" "Q" sequence=true { StopMacroRecording; }
+            bind "Alt @" { PlayMacro "q"; }
+        }
+        locked {
+            bind "x" { GoToNextTab; }
+            bind "Alt @" { PlayMacro "q"; }
+        }
+    }
+    macros {
+        q "l" "s" start_mode="normal"
+    }
+"#;
+
+#[test]
+fn recording_starts_after_and_stops_before_the_keys_bound_to_it() {
+    let session = MockSession::new(MACRO_KEYBINDS);
+    session.type_key(char_key('x')); // key 0
+    session.type_key(char_key('q').with_alt_modifier()); // key 1
+    session.type_key(char_key('l')); // key 2
+    session.type_key(char_key('s')); // key 3
+    session.type_key(char_key('a').with_ctrl_modifier()); // key 4
+    session.type_key(char_key('Q')); // key 5
+    assert_eq!(
+        session.macro_instructions_sent_to_client(),
+        vec![
+            MacroInstruction::StartRecording {
+                name: "q".into(),
+                start_mode: InputMode::Normal,
+                from_key: 2,
+            },
+            MacroInstruction::StopRecording { until_key: 4 },
+        ],
+        "the client records keys 2 and 3, the whole sequence stopping the recording is left out"
+    );
+    assert_eq!(
+        session.keys_written_to_pane(),
+        vec![char_key('x'), char_key('l'), char_key('s')]
+    );
+}
+
+#[test]
+fn recording_starts_in_the_mode_its_binding_switches_to() {
+    let session = MockSession::new(MACRO_KEYBINDS);
+    session.type_key(char_key('w').with_alt_modifier());
+    assert_eq!(
+        session.macro_instructions_sent_to_client(),
+        vec![MacroInstruction::StartRecording {
+            name: "w".into(),
+            start_mode: InputMode::Locked,
+            from_key: 1,
+        }],
+    );
+}
+
+#[test]
+fn playing_a_macro_asks_the_client_to_replay_its_keys() {
+    let session = MockSession::new(MACRO_KEYBINDS);
+    session.type_key(char_key('@').with_alt_modifier());
+    assert_eq!(
+        session.macro_instructions_sent_to_client(),
+        vec![MacroInstruction::Play {
+            macro_to_play: Macro {
+                start_mode: Some(InputMode::Normal),
+                keys: vec![char_key('l'), char_key('s')],
+            },
+            depth: 1,
+        }],
+    );
+}
+
+#[test]
+fn replayed_keys_are_matched_in_the_mode_the_replay_switched_to() {
+    let session = MockSession::new(MACRO_KEYBINDS);
+    // the session's mode is only changed by the server thread, which is not running here
+    session.start_macro_replay(1, Some(InputMode::Locked));
+    session.type_key(char_key('x'));
+    let screen_instructions: Vec<_> = session
+        .screen_receiver
+        .try_iter()
+        .map(|(instruction, _)| instruction)
+        .collect();
+    assert!(
+        matches!(
+            screen_instructions[0],
+            ScreenInstruction::MacroReplayStarted(CLIENT_ID)
+        ),
+        "the screen writes the keys of the replay to the selected panes"
+    );
+    assert!(screen_instructions
+        .iter()
+        .any(|instruction| matches!(instruction, ScreenInstruction::SwitchTabNext(..))));
+    session.end_macro_replay();
+    assert!(matches!(
+        session.screen_receiver.try_recv(),
+        Ok((ScreenInstruction::MacroReplayEnded(CLIENT_ID), _))
+    ));
+    session.type_key(char_key('x'));
+    assert_eq!(
+        session.keys_written_to_pane(),
+        vec![char_key('x')],
+        "after the replay, keys are matched in the session's mode again"
+    );
+}
+
+#[test]
+fn macros_played_from_replays_are_nested_up_to_a_limit() {
+    let session = MockSession::new(MACRO_KEYBINDS);
+    session.start_macro_replay(1, None);
+    session.type_key(char_key('@').with_alt_modifier());
+    session.type_key(char_key('q').with_alt_modifier());
+    session.end_macro_replay();
+    assert!(
+        matches!(
+            session.macro_instructions_sent_to_client()[..],
+            [MacroInstruction::Play { depth: 2, .. }]
+        ),
+        "the nested macro is played one level deeper and no recording is started from a replay"
+    );
+    session.start_macro_replay(8, None);
+    session.type_key(char_key('@').with_alt_modifier());
+    session.end_macro_replay();
+    assert_eq!(
+        session.macro_instructions_sent_to_client(),
+        vec![],
+        "a macro playing itself stops at some point"
+    );
+}
@@ -141,7 +141,7 @@ fn send_cli_action_to_server(
             default_layout.clone(),
             None,
             client_keybinds.clone(),
-            default_mode,
+            default_mode.clone(),
         )
         .unwrap();
     }
@@ -239,6 +239,9 @@ impl ServerOsApi for FakeInputOutput {
     fn clear_terminal_id(&self, _terminal_id: u32) -> Result<()> {
         unimplemented!()
     }
+    fn open_hyperlink(&self, _uri: &str) -> Result<()> {
+        unimplemented!()
+    }
 }
 
 fn create_new_screen(size: Size) -> Screen {
@@ -268,6 +271,8 @@ fn create_new_screen(size: Size) -> Screen {
     let styled_underlines = true;
     let arrow_fonts = true;
     let explicitly_disable_kitty_keyboard_protocol = false;
+    let which_key_overlay = false;
+    let dim_unfocused_panes = 0;
     let screen = Screen::new(
         bus,
         &client_attributes,
@@ -288,6 +293,8 @@ fn create_new_screen(size: Size) -> Screen {
         arrow_fonts,
         layout_dir,
         explicitly_disable_kitty_keyboard_protocol,
+        which_key_overlay,
+        dim_unfocused_panes,
     );
     screen
 }
@@ -529,6 +536,9 @@ impl MockScreen {
             session_configuration: self.session_metadata.session_configuration.clone(),
             layout,
             current_input_modes: self.session_metadata.current_input_modes.clone(),
+            current_color_schemes: self.session_metadata.current_color_schemes.clone(),
+            key_intercepts: self.session_metadata.key_intercepts.clone(),
+            focused_panes: self.session_metadata.focused_panes.clone(),
         }
     }
 }
@@ -587,6 +597,9 @@ impl MockScreen {
             layout,
             session_configuration: Default::default(),
             current_input_modes: HashMap::new(),
+            current_color_schemes: HashMap::new(),
+            key_intercepts: Default::default(),
+            focused_panes: HashMap::new(),
         };
 
         let os_input = FakeInputOutput::default();
@@ -2210,6 +2223,10 @@ pub fn send_cli_new_pane_action_with_default_parameters() {
         name: None,
         close_on_exit: false,
         start_suspended: false,
+        restart: None,
+        max_restarts: None,
+        watch: vec![],
+        kill_on_change: false,
         configuration: None,
         skip_plugin_cache: false,
         x: None,
@@ -2253,6 +2270,10 @@ pub fn send_cli_new_pane_action_with_split_direction() {
         name: None,
         close_on_exit: false,
         start_suspended: false,
+        restart: None,
+        max_restarts: None,
+        watch: vec![],
+        kill_on_change: false,
         configuration: None,
         skip_plugin_cache: false,
         x: None,
@@ -2296,6 +2317,10 @@ pub fn send_cli_new_pane_action_with_command_and_cwd() {
         name: None,
         close_on_exit: false,
         start_suspended: false,
+        restart: None,
+        max_restarts: None,
+        watch: vec![],
+        kill_on_change: false,
         configuration: None,
         skip_plugin_cache: false,
         x: None,
@@ -2339,6 +2364,10 @@ pub fn send_cli_new_pane_action_with_floating_pane_and_coordinates() {
         name: None,
         close_on_exit: false,
         start_suspended: false,
+        restart: None,
+        max_restarts: None,
+        watch: vec![],
+        kill_on_change: false,
         configuration: None,
         skip_plugin_cache: false,
         x: Some("10".to_owned()),
@@ -2642,6 +2671,7 @@ pub fn send_cli_new_tab_action_default_params() {
         name: None,
         layout: None,
         layout_dir: None,
+        layout_vars: vec![],
         cwd: None,
     };
     send_cli_action_to_server(&session_metadata, new_tab_action, client_id);
@@ -2682,6 +2712,7 @@ pub fn send_cli_new_tab_action_with_name_and_layout() {
             env!("CARGO_MANIFEST_DIR")
         ))),
         layout_dir: None,
+        layout_vars: vec![],
         cwd: None,
     };
     send_cli_action_to_server(&session_metadata, new_tab_action, client_id);
@@ -3373,6 +3404,60 @@ pub fn screen_can_break_floating_pane_to_a_new_tab() {
     assert_snapshot!(format!("{}", snapshot_count));
 }
 
+#[test]
+pub fn errored_plugin_pane_reloads_its_plugin_in_place() {
+    use crate::ui::loading_indication::LoadingIndication;
+    use zellij_utils::data::{BareKey, KeyWithModifier};
+    let size = Size { cols: 80, rows: 20 };
+    let client_id = 1;
+    let mut initial_layout = TiledPaneLayout::default();
+    initial_layout.run = Some(Run::Plugin(RunPluginOrAlias::RunPlugin(RunPlugin {
+        _allow_exec_host_cmd: false,
+        location: RunPluginLocation::File(PathBuf::from("/path/to/fake/plugin")),
+        configuration: Default::default(),
+        ..Default::default()
+    })));
+    let mut mock_screen = MockScreen::new(size);
+    let plugin_receiver = mock_screen.plugin_receiver.take().unwrap();
+    let screen_thread = mock_screen.run(Some(initial_layout), vec![]);
+    let received_plugin_instructions = Arc::new(Mutex::new(vec![]));
+    let plugin_thread = log_actions_in_thread!(
+        received_plugin_instructions,
+        PluginInstruction::Exit,
+        plugin_receiver
+    );
+    let mut loading_indication = LoadingIndication::new("fake_plugin".to_owned());
+    loading_indication
+        .indicate_loading_error("Plugin was stopped because it ran for too long".to_owned());
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::UpdatePluginLoadingStage(
+            1,
+            loading_indication,
+        ));
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::WriteCharacter(
+            Some(KeyWithModifier::new(BareKey::Char('r'))),
+            "r".as_bytes().to_vec(),
+            false,
+            client_id,
+        ));
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    mock_screen.teardown(vec![plugin_thread, screen_thread]);
+    let plugin_was_reloaded = received_plugin_instructions.lock().unwrap().iter().any(
+        |instruction| {
+            matches!(
+                instruction,
+                PluginInstruction::Reload(None, None, RunPluginOrAlias::RunPlugin(run_plugin), 0, _)
+                    if run_plugin.location
+                        == RunPluginLocation::File(PathBuf::from("/path/to/fake/plugin"))
+            )
+        },
+    );
+    assert!(plugin_was_reloaded, "plugin reloaded in its own tab");
+}
+
 #[test]
 pub fn screen_can_break_plugin_pane_to_a_new_tab() {
     let size = Size { cols: 80, rows: 20 };
@@ -3610,3 +3695,406 @@ pub fn screen_can_move_pane_to_a_new_tab_left() {
     }
     assert_snapshot!(format!("{}", snapshot_count));
 }
+
+#[test]
+fn which_key_overlay_is_shown_after_entering_a_mode_and_hidden_when_leaving_it() {
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id = 1;
+    let mut screen = create_new_screen(size);
+    screen.which_key_overlay = true;
+    new_tab(&mut screen, 1, 0);
+
+    let mut mode_info = ModeInfo::default();
+    mode_info.mode = InputMode::Pane;
+    screen.change_mode(mode_info.clone(), client_id).unwrap();
+    assert!(
+        !screen.which_key_overlays.contains_key(&client_id),
+        "overlay is only shown after a delay"
+    );
+    screen.show_which_key_overlay(client_id, InputMode::Tab);
+    assert!(
+        !screen.which_key_overlays.contains_key(&client_id),
+        "overlay is not shown for a mode the client already left"
+    );
+    screen.show_which_key_overlay(client_id, InputMode::Pane);
+    assert!(screen.which_key_overlays.contains_key(&client_id));
+
+    mode_info.mode = InputMode::Normal;
+    screen.change_mode(mode_info, client_id).unwrap();
+    assert!(!screen.which_key_overlays.contains_key(&client_id));
+}
+
+#[test]
+fn client_theme_changes_only_apply_to_that_client() {
+    use zellij_utils::data::{Palette, PaletteColor, ThemeHue};
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut screen = create_new_screen(size);
+    new_tab(&mut screen, 1, 0);
+    screen.add_client(2).expect("TEST");
+
+    let light_theme = Palette {
+        theme_hue: ThemeHue::Light,
+        fg: PaletteColor::Rgb((0, 0, 0)),
+        bg: PaletteColor::Rgb((255, 255, 255)),
+        ..Default::default()
+    };
+    screen.change_client_theme(light_theme, 1).expect("TEST");
+    assert_eq!(screen.mode_info.get(&1).unwrap().style.colors, light_theme);
+    assert_ne!(
+        screen
+            .mode_info
+            .get(&2)
+            .unwrap_or(&screen.default_mode_info)
+            .style
+            .colors,
+        light_theme,
+        "other clients keep their own theme"
+    );
+    assert_eq!(
+        screen.get_active_tab(2).unwrap().style.colors,
+        light_theme,
+        "the panes follow the last client to switch"
+    );
+}
+
+#[test]
+pub fn prompt_overlay_is_rendered_and_denying_it_sends_its_deny_instruction() {
+    use crate::ui::overlay::{prompt::Prompt, Overlay, OverlayType};
+    let size = Size { cols: 80, rows: 20 };
+    let client_id = 1;
+    let mut mock_screen = MockScreen::new(size);
+    let screen_thread = mock_screen.run(None, vec![]);
+    let received_server_instructions = Arc::new(Mutex::new(vec![]));
+    let server_receiver = mock_screen.server_receiver.take().unwrap();
+    let server_thread = log_actions_in_thread!(
+        received_server_instructions,
+        ServerInstruction::KillSession,
+        server_receiver
+    );
+    let prompt = Prompt::new(
+        " Use this layout? [Y]es / [N]o".to_owned(),
+        None,
+        Some(Box::new(ServerInstruction::IgnoreDirLocalLayout(client_id))),
+    );
+    let _ = mock_screen.to_screen.send(ScreenInstruction::AddOverlay(
+        Overlay::new(OverlayType::Prompt(prompt)),
+        client_id,
+    ));
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::DenyPrompt(client_id));
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    mock_screen.teardown(vec![server_thread, screen_thread]);
+    let received_server_instructions = received_server_instructions.lock().unwrap();
+    let prompt_was_rendered = received_server_instructions.iter().any(|instruction| {
+        matches!(
+            instruction,
+            ServerInstruction::Render(Some(output))
+                if output
+                    .get(&client_id)
+                    .map(|o| o.contains("\u{1b}[20;2H\u{1b}[48;5;238mU"))
+                    .unwrap_or(false)
+        )
+    });
+    assert!(prompt_was_rendered, "prompt rendered over the screen");
+    assert!(
+        received_server_instructions
+            .iter()
+            .any(|instruction| matches!(instruction, ServerInstruction::IgnoreDirLocalLayout(1))),
+        "deny instruction of the prompt sent to the server"
+    );
+}
+
+#[test]
+pub fn exited_on_failure_command_pane_is_restarted_and_shows_its_restart_count() {
+    use zellij_utils::input::command::{RestartCondition, RestartPolicy};
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let client_id = 1;
+    let mut mock_screen = MockScreen::new(size);
+    let pty_receiver = mock_screen.pty_receiver.take().unwrap();
+    let plugin_receiver = mock_screen.plugin_receiver.take().unwrap();
+    let server_receiver = mock_screen.server_receiver.take().unwrap();
+    let screen_thread = mock_screen.run(None, vec![]);
+    let received_pty_instructions = Arc::new(Mutex::new(vec![]));
+    let pty_thread = log_actions_in_thread!(
+        received_pty_instructions,
+        PtyInstruction::Exit,
+        pty_receiver
+    );
+    let received_plugin_instructions = Arc::new(Mutex::new(vec![]));
+    let plugin_thread = log_actions_in_thread!(
+        received_plugin_instructions,
+        PluginInstruction::Exit,
+        plugin_receiver
+    );
+    let received_server_instructions = Arc::new(Mutex::new(vec![]));
+    let server_thread = log_actions_in_thread!(
+        received_server_instructions,
+        ServerInstruction::KillSession,
+        server_receiver
+    );
+    let run_command = RunCommand {
+        command: PathBuf::from("false"),
+        restart_policy: RestartPolicy::new(RestartCondition::OnFailure, None),
+        ..Default::default()
+    };
+    let _ = mock_screen.to_screen.send(ScreenInstruction::HoldPane(
+        PaneId::Terminal(0),
+        Some(1),
+        run_command,
+        Some(0),
+        None,
+    ));
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::RestartCommandPane(0, 1, Some(1)));
+    // the pane is no longer held, so this restart is dropped
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::RestartCommandPane(0, 2, Some(1)));
+    let _ = mock_screen.to_screen.send(ScreenInstruction::Render);
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    mock_screen.teardown(vec![
+        pty_thread,
+        plugin_thread,
+        server_thread,
+        screen_thread,
+    ]);
+    let rerun_count = received_pty_instructions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|instruction| {
+            matches!(
+                instruction,
+                PtyInstruction::ReRunCommandInPane(PaneId::Terminal(0), _)
+            )
+        })
+        .count();
+    assert_eq!(rerun_count, 1, "pane restarted once");
+    let rerun_event_count = received_plugin_instructions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|instruction| {
+            matches!(
+                instruction,
+                PluginInstruction::Update(updates)
+                    if updates
+                        .iter()
+                        .any(|(_, _, event)| matches!(event, Event::CommandPaneReRun(0, _)))
+            )
+        })
+        .count();
+    assert_eq!(rerun_event_count, 1, "plugins told of the restart once");
+    let restart_count_was_rendered =
+        received_server_instructions
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|instruction| {
+                matches!(
+                    instruction,
+                    ServerInstruction::Render(Some(output))
+                        if output
+                            .get(&client_id)
+                            .map(|o| o.contains("[RESTARTS: 1, LAST EXIT CODE: 1]"))
+                            .unwrap_or(false)
+                )
+            });
+    assert!(
+        restart_count_was_rendered,
+        "restart count shown in the pane title"
+    );
+}
+
+#[test]
+fn prompt_overlay_is_only_shown_to_and_answered_by_its_client() {
+    use crate::ui::overlay::{prompt::Prompt, Overlay, OverlayType};
+    let size = Size {
+        cols: 121,
+        rows: 20,
+    };
+    let mut screen = create_new_screen(size);
+    new_tab(&mut screen, 1, 0);
+    screen.add_client(2).expect("TEST");
+    let prompt = Prompt::new(
+        " Use this layout? [Y]es / [N]o".to_owned(),
+        None,
+        Some(Box::new(ServerInstruction::IgnoreDirLocalLayout(1))),
+    );
+    screen.add_prompt_overlay(1, Overlay::new(OverlayType::Prompt(prompt)));
+    assert!(screen.prompt_overlays.contains_key(&1));
+    assert!(
+        !screen.prompt_overlays.contains_key(&2),
+        "other clients are not shown the prompt"
+    );
+    assert!(
+        screen.remove_prompt_overlay(2).is_none(),
+        "other clients cannot answer the prompt"
+    );
+    assert!(screen.remove_prompt_overlay(1).is_some());
+}
+
+#[test]
+fn which_key_overlay_groups_the_bindings_of_the_mode_by_category() {
+    use crate::ui::overlay::{which_key::WhichKey, Overlayable};
+    use zellij_utils::data::{BareKey, KeyWithModifier};
+
+    let mut mode_info = ModeInfo::default();
+    mode_info.mode = InputMode::Pane;
+    mode_info.keybinds = vec![(
+        InputMode::Pane,
+        vec![
+            (
+                KeyWithModifier::new(BareKey::Char('h')),
+                vec![Action::MoveFocus(Direction::Left)],
+            ),
+            (
+                KeyWithModifier::new(BareKey::Left),
+                vec![Action::MoveFocus(Direction::Left)],
+            ),
+            (
+                KeyWithModifier::new(BareKey::Char('n')),
+                vec![
+                    Action::NewPane(None, None, false),
+                    Action::SwitchToMode(InputMode::Normal),
+                ],
+            ),
+            (
+                KeyWithModifier::new(BareKey::Esc),
+                vec![Action::SwitchToMode(InputMode::Normal)],
+            ),
+            (KeyWithModifier::new(BareKey::Char('x')), vec![Action::NoOp]),
+        ],
+    )];
+    let which_key = WhichKey::new(mode_info);
+    assert_eq!(
+        which_key.categorized_bindings(),
+        vec![
+            ("Mode", vec![("ESC".to_owned(), "Normal mode".to_owned())]),
+            (
+                "Pane",
+                vec![
+                    ("h ←".to_owned(), "Move focus ←".to_owned()),
+                    ("n".to_owned(), "New pane".to_owned()),
+                ]
+            ),
+        ]
+    );
+    let overlay = which_key
+        .generate_overlay(Size {
+            cols: 121,
+            rows: 20,
+        })
+        .unwrap();
+    assert!(overlay.contains(" PANE "));
+    assert!(overlay.contains("Move focus ←"));
+}
+
+#[test]
+pub fn pane_output_is_sent_to_subscribed_plugins() {
+    let size = Size { cols: 80, rows: 20 };
+    let mut initial_layout = TiledPaneLayout::default();
+    initial_layout.children_split_direction = SplitDirection::Vertical;
+    initial_layout.children = vec![TiledPaneLayout::default(), TiledPaneLayout::default()];
+    let mut mock_screen = MockScreen::new(size);
+    let screen_thread = mock_screen.run(Some(initial_layout), vec![]);
+    let received_plugin_instructions = Arc::new(Mutex::new(vec![]));
+    let plugin_receiver = mock_screen.plugin_receiver.take().unwrap();
+    let plugin_thread = log_actions_in_thread!(
+        received_plugin_instructions,
+        PluginInstruction::Exit,
+        plugin_receiver
+    );
+    let received_background_jobs = Arc::new(Mutex::new(vec![]));
+    let background_jobs_receiver = mock_screen.background_jobs_receiver.take().unwrap();
+    let background_jobs_thread = log_actions_in_thread!(
+        received_background_jobs,
+        BackgroundJob::Exit,
+        background_jobs_receiver
+    );
+    let watching_one_pane = 100;
+    let watching_all_panes = 101;
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::SubscribeToPaneOutput(
+            watching_one_pane,
+            Some([PaneId::Terminal(0)].into()),
+        ));
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::SubscribeToPaneOutput(
+            watching_all_panes,
+            None,
+        ));
+    let _ = mock_screen.to_screen.send(ScreenInstruction::PtyBytes(
+        0,
+        "cargo test\r\ntest result: ok\r\nstill typi"
+            .as_bytes()
+            .to_vec(),
+    ));
+    let _ = mock_screen.to_screen.send(ScreenInstruction::PtyBytes(
+        1,
+        "thread 'main' panicked\r\n".as_bytes().to_vec(),
+    ));
+    let _ = mock_screen
+        .to_screen
+        .send(ScreenInstruction::FlushPaneOutput);
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let _ = mock_screen.to_background_jobs.send(BackgroundJob::Exit);
+    mock_screen.teardown(vec![plugin_thread, background_jobs_thread, screen_thread]);
+    let pane_output_events: Vec<(Option<u32>, Event)> = received_plugin_instructions
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|instruction| match instruction {
+            PluginInstruction::Update(updates) => Some(updates.clone()),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|(plugin_id, _client_id, event)| match event {
+            Event::PaneOutput(..) => Some((plugin_id, event)),
+            _ => None,
+        })
+        .collect();
+    let first_pane_lines = vec!["cargo test".to_owned(), "test result: ok".to_owned()];
+    let second_pane_lines = vec!["thread 'main' panicked".to_owned()];
+    assert_eq!(
+        pane_output_events,
+        vec![
+            (
+                Some(watching_one_pane),
+                Event::PaneOutput(
+                    zellij_utils::data::PaneId::Terminal(0),
+                    first_pane_lines.clone()
+                )
+            ),
+            (
+                Some(watching_all_panes),
+                Event::PaneOutput(zellij_utils::data::PaneId::Terminal(0), first_pane_lines)
+            ),
+            (
+                Some(watching_all_panes),
+                Event::PaneOutput(zellij_utils::data::PaneId::Terminal(1), second_pane_lines)
+            ),
+        ],
+        "completed lines sent only to the plugins subscribed to their pane"
+    );
+    let scheduled_flushes = received_background_jobs
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|job| matches!(job, BackgroundJob::FlushPaneOutput))
+        .count();
+    assert_eq!(scheduled_flushes, 1, "flushes are batched");
+}
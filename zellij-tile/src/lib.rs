@@ -46,6 +46,18 @@ pub trait ZellijPlugin: Default {
     /// Will be called either after an `update` that requested it, or when the plugin otherwise needs to be re-rendered (eg. on startup, or when the plugin is resized).
     /// The `rows` and `cols` values represent the "content size" of the plugin (this will not include its surrounding frame if the user has pane frames enabled).
     fn render(&mut self, rows: usize, cols: usize) {}
+    /// Will be called right before the plugin is reloaded (eg. with `zellij action start-or-reload-plugin` or
+    /// because it was started with `reload_on_change` and its file changed). The returned bytes are
+    /// given to [`restore_state`](ZellijPlugin::restore_state) of the reloaded plugin, after its `load`.
+    fn save_state(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+    /// Will be called after the plugin was reloaded with the state it returned from
+    /// [`save_state`](ZellijPlugin::save_state) before the reload.
+    /// If the plugin returns `true` from this function, Zellij will know it should be rendered and call its `render` function.
+    fn restore_state(&mut self, saved_state: Vec<u8>) -> bool {
+        false
+    } // return true if it should render
 }
 
 /// This trait is used to create workers. Workers can be used by plugins to run longer running
@@ -164,6 +176,22 @@ macro_rules! register_plugin {
             });
         }
 
+        #[no_mangle]
+        pub fn save_state() {
+            STATE.with(|state| {
+                let saved_state = state.borrow_mut().save_state().unwrap_or_default();
+                $crate::shim::object_to_stdout(&saved_state);
+            });
+        }
+
+        #[no_mangle]
+        pub fn restore_state() -> bool {
+            STATE.with(|state| {
+                let saved_state: Vec<u8> = $crate::shim::bytes_from_stdin().unwrap();
+                state.borrow_mut().restore_state(saved_state)
+            })
+        }
+
         #[no_mangle]
         pub fn plugin_version() {
             println!("{}", $crate::prelude::VERSION);
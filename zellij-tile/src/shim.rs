@@ -2,12 +2,16 @@ use serde::{de::DeserializeOwned, Serialize};
 use std::collections::{BTreeMap, HashSet};
 use std::{
     io,
+    ops::Range,
     path::{Path, PathBuf},
 };
 use zellij_utils::data::*;
 use zellij_utils::errors::prelude::*;
 pub use zellij_utils::plugin_api;
-use zellij_utils::plugin_api::plugin_command::ProtobufPluginCommand;
+use zellij_utils::plugin_api::plugin_command::{
+    ProtobufGrantedPluginPermissionsList, ProtobufPaneContentsResult, ProtobufPluginCommand,
+    ProtobufPluginRequestId,
+};
 use zellij_utils::plugin_api::plugin_ids::{ProtobufPluginIds, ProtobufZellijVersion};
 
 pub use super::ui_components::*;
@@ -44,7 +48,45 @@ pub fn set_selectable(selectable: bool) {
 }
 
 pub fn request_permission(permissions: &[PermissionType]) {
-    let plugin_command = PluginCommand::RequestPluginPermissions(permissions.into());
+    request_scoped_permission(permissions, PermissionScopes::default())
+}
+
+/// Like `request_permission`, but limits some of the permissions to a scope, eg. `RunCommands`
+/// only for `git` and `cargo` or `WebAccess` only for `api.github.com`. The user sees the scopes
+/// in the permission prompt and commands outside of them are denied.
+///
+/// A `HostFilesystemAccess` scope (eg. `/host/src`) also limits which folders are mounted under
+/// `/host`, this is decided when the plugin is loaded so a newly granted scope applies from the
+/// next time the plugin is loaded.
+pub fn request_scoped_permission(permissions: &[PermissionType], scopes: PermissionScopes) {
+    let plugin_command = PluginCommand::RequestPluginPermissions(permissions.into(), scopes);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
+/// Returns the permissions the user granted to each plugin (by plugin url). Requires the
+/// `Reconfigure` permission.
+pub fn list_plugin_permissions() -> Result<Vec<PluginPermission>, String> {
+    let plugin_command = PluginCommand::ListPluginPermissions;
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+    bytes_from_stdin()
+        .ok()
+        .and_then(|bytes| ProtobufGrantedPluginPermissionsList::decode(bytes.as_slice()).ok())
+        .map(|protobuf_list| protobuf_list.into())
+        .ok_or_else(|| {
+            "Failed to list plugin permissions, is the Reconfigure permission granted?".to_owned()
+        })
+}
+
+/// Revoke permissions granted to the plugin with this url, revoking an empty list of permissions
+/// revokes all of them. Running instances of the plugin lose these permissions immediately.
+/// Requires the `Reconfigure` permission.
+pub fn revoke_plugin_permissions(plugin_url: &str, permissions: &[PermissionType]) {
+    let plugin_command =
+        PluginCommand::RevokePluginPermissions(plugin_url.to_owned(), permissions.into());
     let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
     object_to_stdout(&protobuf_plugin_command.encode_to_vec());
     unsafe { host_run_plugin_command() };
@@ -266,6 +308,78 @@ pub fn hide_pane_with_id(pane_id: PaneId) {
     unsafe { host_run_plugin_command() };
 }
 
+/// Override the frame and background colors of the pane with the specified [PaneId], colors
+/// left as `None` fall back to the tab's colors and then to the theme
+pub fn set_pane_color(pane_id: PaneId, pane_colors: PaneColors) {
+    let plugin_command = PluginCommand::SetPaneColor(pane_id, pane_colors);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
+/// Read the lines of a terminal pane: its viewport and optionally a range of its scrollback
+/// (counted from the oldest line, wrapped lines are joined). Requires the `ReadPaneContents`
+/// permission.
+pub fn get_pane_scrollback(
+    pane_id: PaneId,
+    line_range: Option<Range<usize>>,
+    with_ansi: bool,
+) -> Result<PaneContents, String> {
+    let plugin_command = PluginCommand::GetPaneScrollback(pane_id, line_range, with_ansi);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+    bytes_from_stdin()
+        .ok()
+        .and_then(|bytes| ProtobufPaneContentsResult::decode(bytes.as_slice()).ok())
+        .ok_or_else(|| {
+            "Failed to read pane contents, is the ReadPaneContents permission granted?".to_owned()
+        })
+        .and_then(|protobuf_result| protobuf_result.into())
+}
+
+/// Subscribe to the [`Event::PaneOutput`] of the given panes only (or of all panes if the list is
+/// empty). Subscribing to [`EventType::PaneOutput`] instead will receive the output of all panes.
+/// Requires the `ReadPaneContents` permission.
+pub fn subscribe_to_pane_output(pane_ids: &[PaneId]) {
+    let plugin_command = PluginCommand::SubscribeToPaneOutput(pane_ids.to_vec());
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
+/// Receive the given keys, and all keys typed in the given input modes, as `Event::InterceptedKey`
+/// instead of having them handled by the focused pane (replaces any previous intercepts)
+pub fn intercept_keys(keys: Vec<KeyWithModifier>, input_modes: Vec<InputMode>) {
+    let plugin_command = PluginCommand::InterceptKeys(keys, input_modes);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
+/// Stop intercepting keys, letting them reach the focused pane as usual
+pub fn clear_key_intercepts() {
+    intercept_keys(vec![], vec![]);
+}
+
+/// Hand an intercepted key back to be handled as if it was typed by the user
+pub fn reinject_key(key: KeyWithModifier) {
+    let plugin_command = PluginCommand::ReinjectKey(key);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
+/// Declare (or redefine) the custom input mode `name` with the given KDL keybindings (eg.
+/// `bind "d" { Run "make" "deploy"; SwitchToMode "normal"; }`), it can then be switched to with
+/// `switch_to_input_mode`. Requires the `Reconfigure` permission.
+pub fn register_input_mode(name: &str, keybinds: &str) {
+    let plugin_command = PluginCommand::RegisterInputMode(name.to_owned(), keybinds.to_owned());
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
 /// Show the plugin pane (unsuppress it if it is suppressed), focus it and switch to its tab
 pub fn show_self(should_float_if_hidden: bool) {
     let plugin_command = PluginCommand::ShowSelf(should_float_if_hidden);
@@ -292,7 +406,7 @@ pub fn close_self() {
 
 /// Switch to the specified Input Mode (eg. `Normal`, `Tab`, `Pane`)
 pub fn switch_to_input_mode(mode: &InputMode) {
-    let plugin_command = PluginCommand::SwitchToMode(*mode);
+    let plugin_command = PluginCommand::SwitchToMode(mode.clone());
     let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
     object_to_stdout(&protobuf_plugin_command.encode_to_vec());
     unsafe { host_run_plugin_command() };
@@ -795,6 +909,39 @@ pub fn pipe_message_to_plugin(message_to_plugin: MessageToPlugin) {
     unsafe { host_run_plugin_command() };
 }
 
+/// Send a request to another (running) plugin, which receives it as a pipe message with a
+/// `request_id`. Returns the id of the request, the answer (or a timeout / no such plugin error)
+/// will arrive as an [`Event::PluginResponse`] with this id. Requires the
+/// `MessageAndLaunchOtherPlugins` permission.
+pub fn plugin_request(
+    target: PluginRequestTarget,
+    name: impl Into<String>,
+    payload: Option<String>,
+    timeout_secs: f64,
+) -> Result<String, String> {
+    let plugin_command = PluginCommand::PluginRequest(target, name.into(), payload, timeout_secs);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+    bytes_from_stdin()
+        .ok()
+        .and_then(|bytes| ProtobufPluginRequestId::decode(bytes.as_slice()).ok())
+        .map(|protobuf_plugin_request_id| protobuf_plugin_request_id.request_id)
+        .filter(|request_id| !request_id.is_empty())
+        .ok_or_else(|| {
+            "Failed to send plugin request, is the MessageAndLaunchOtherPlugins permission granted?"
+                .to_owned()
+        })
+}
+
+/// Answer a request received from another plugin (a [`PipeMessage`] with a `request_id`)
+pub fn respond_to_plugin_request(request_id: &str, payload: Option<String>) {
+    let plugin_command = PluginCommand::RespondToPluginRequest(request_id.to_owned(), payload);
+    let protobuf_plugin_command: ProtobufPluginCommand = plugin_command.try_into().unwrap();
+    object_to_stdout(&protobuf_plugin_command.encode_to_vec());
+    unsafe { host_run_plugin_command() };
+}
+
 /// Disconnect all other clients from the current session
 pub fn disconnect_other_clients() {
     let plugin_command = PluginCommand::DisconnectOtherClients;
@@ -225,6 +225,8 @@ pub struct NewPanePayload {
 pub struct SwitchToModePayload {
     #[prost(enumeration = "super::input_mode::InputMode", tag = "1")]
     pub input_mode: i32,
+    #[prost(string, optional, tag = "2")]
+    pub custom_mode_name: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -11,7 +11,7 @@ pub struct Event {
     pub name: i32,
     #[prost(
         oneof = "event::Payload",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25"
     )]
     pub payload: ::core::option::Option<event::Payload>,
 }
@@ -62,10 +62,36 @@ pub mod event {
         CommandPaneRerunPayload(super::CommandPaneReRunPayload),
         #[prost(message, tag = "22")]
         FailedToWriteConfigToDiskPayload(super::FailedToWriteConfigToDiskPayload),
+        #[prost(message, tag = "23")]
+        PaneOutputPayload(super::PaneOutputPayload),
+        #[prost(message, tag = "24")]
+        InterceptedKeyPayload(super::super::key::Key),
+        #[prost(message, tag = "25")]
+        PluginResponsePayload(super::PluginResponsePayload),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PluginResponsePayload {
+    #[prost(string, tag = "1")]
+    pub request_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "3")]
+    pub payload: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(enumeration = "PluginRequestError", optional, tag = "4")]
+    pub error: ::core::option::Option<i32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PaneOutputPayload {
+    #[prost(message, optional, tag = "1")]
+    pub pane_id: ::core::option::Option<PaneId>,
+    #[prost(string, repeated, tag = "2")]
+    pub lines: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FailedToWriteConfigToDiskPayload {
     #[prost(string, optional, tag = "1")]
     pub file_path: ::core::option::Option<::prost::alloc::string::String>,
@@ -374,6 +400,10 @@ pub struct ModeUpdatePayload {
     pub session_name: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(enumeration = "super::input_mode::InputMode", optional, tag = "6")]
     pub base_mode: ::core::option::Option<i32>,
+    #[prost(message, repeated, tag = "7")]
+    pub pending_key_sequence: ::prost::alloc::vec::Vec<super::key::Key>,
+    #[prost(string, optional, tag = "8")]
+    pub custom_mode_name: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -382,6 +412,8 @@ pub struct InputModeKeybinds {
     pub mode: i32,
     #[prost(message, repeated, tag = "2")]
     pub key_bind: ::prost::alloc::vec::Vec<KeyBind>,
+    #[prost(string, optional, tag = "3")]
+    pub custom_mode_name: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -435,6 +467,12 @@ pub enum EventType {
     EditPaneExited = 23,
     CommandPaneReRun = 24,
     FailedToWriteConfigToDisk = 25,
+    /// / Lines were completed in a terminal pane
+    PaneOutput = 26,
+    /// / A key intercepted by this plugin was pressed
+    InterceptedKey = 27,
+    /// / Another plugin answered a request of this plugin (or the request failed)
+    PluginResponse = 28,
 }
 impl EventType {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -469,6 +507,9 @@ impl EventType {
             EventType::EditPaneExited => "EditPaneExited",
             EventType::CommandPaneReRun => "CommandPaneReRun",
             EventType::FailedToWriteConfigToDisk => "FailedToWriteConfigToDisk",
+            EventType::PaneOutput => "PaneOutput",
+            EventType::InterceptedKey => "InterceptedKey",
+            EventType::PluginResponse => "PluginResponse",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -500,6 +541,9 @@ impl EventType {
             "EditPaneExited" => Some(Self::EditPaneExited),
             "CommandPaneReRun" => Some(Self::CommandPaneReRun),
             "FailedToWriteConfigToDisk" => Some(Self::FailedToWriteConfigToDisk),
+            "PaneOutput" => Some(Self::PaneOutput),
+            "InterceptedKey" => Some(Self::InterceptedKey),
+            "PluginResponse" => Some(Self::PluginResponse),
             _ => None,
         }
     }
@@ -533,6 +577,32 @@ impl PaneType {
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum PluginRequestError {
+    Timeout = 0,
+    NoSuchPlugin = 1,
+}
+impl PluginRequestError {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            PluginRequestError::Timeout => "Timeout",
+            PluginRequestError::NoSuchPlugin => "NoSuchPlugin",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "Timeout" => Some(Self::Timeout),
+            "NoSuchPlugin" => Some(Self::NoSuchPlugin),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum CopyDestination {
     Command = 0,
     Primary = 1,
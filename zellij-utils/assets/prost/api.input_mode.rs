@@ -3,6 +3,8 @@
 pub struct InputModeMessage {
     #[prost(enumeration = "InputMode", tag = "1")]
     pub input_mode: i32,
+    #[prost(string, optional, tag = "2")]
+    pub custom_mode_name: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -37,6 +39,8 @@ pub enum InputMode {
     Prompt = 12,
     /// / `Tmux` mode allows for basic tmux keybindings functionality
     Tmux = 13,
+    /// / A mode defined by the user or by a plugin, sent along with its name
+    Custom = 14,
 }
 impl InputMode {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -59,6 +63,7 @@ impl InputMode {
             InputMode::Move => "Move",
             InputMode::Prompt => "Prompt",
             InputMode::Tmux => "Tmux",
+            InputMode::Custom => "Custom",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -78,6 +83,7 @@ impl InputMode {
             "Move" => Some(Self::Move),
             "Prompt" => Some(Self::Prompt),
             "Tmux" => Some(Self::Tmux),
+            "Custom" => Some(Self::Custom),
             _ => None,
         }
     }
@@ -5,7 +5,7 @@ pub struct PluginCommand {
     pub name: i32,
     #[prost(
         oneof = "plugin_command::Payload",
-        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 60, 61, 62, 63, 64, 65, 66, 67"
+        tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76"
     )]
     pub payload: ::core::option::Option<plugin_command::Payload>,
 }
@@ -128,6 +128,24 @@ pub mod plugin_command {
         OpenCommandPaneBackgroundPayload(super::OpenCommandPanePayload),
         #[prost(message, tag = "67")]
         RerunCommandPanePayload(super::RerunCommandPanePayload),
+        #[prost(message, tag = "68")]
+        SetPaneColorPayload(super::SetPaneColorPayload),
+        #[prost(message, tag = "69")]
+        GetPaneScrollbackPayload(super::GetPaneScrollbackPayload),
+        #[prost(message, tag = "70")]
+        SubscribeToPaneOutputPayload(super::SubscribeToPaneOutputPayload),
+        #[prost(message, tag = "71")]
+        InterceptKeysPayload(super::InterceptKeysPayload),
+        #[prost(message, tag = "72")]
+        ReinjectKeyPayload(super::super::key::Key),
+        #[prost(message, tag = "73")]
+        RegisterInputModePayload(super::RegisterInputModePayload),
+        #[prost(message, tag = "74")]
+        PluginRequestPayload(super::PluginRequestPayload),
+        #[prost(message, tag = "75")]
+        RespondToPluginRequestPayload(super::RespondToPluginRequestPayload),
+        #[prost(message, tag = "76")]
+        RevokePluginPermissionsPayload(super::RevokePluginPermissionsPayload),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -146,6 +164,140 @@ pub struct RerunCommandPanePayload {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SetPaneColorPayload {
+    #[prost(message, optional, tag = "1")]
+    pub pane_id: ::core::option::Option<PaneId>,
+    #[prost(message, optional, tag = "2")]
+    pub frame_color: ::core::option::Option<super::style::Color>,
+    #[prost(message, optional, tag = "3")]
+    pub frame_color_focused: ::core::option::Option<super::style::Color>,
+    #[prost(message, optional, tag = "4")]
+    pub background: ::core::option::Option<super::style::Color>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetPaneScrollbackPayload {
+    #[prost(message, optional, tag = "1")]
+    pub pane_id: ::core::option::Option<PaneId>,
+    #[prost(message, optional, tag = "2")]
+    pub line_range: ::core::option::Option<LineRange>,
+    #[prost(bool, tag = "3")]
+    pub with_ansi: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SubscribeToPaneOutputPayload {
+    #[prost(message, repeated, tag = "1")]
+    pub pane_ids: ::prost::alloc::vec::Vec<PaneId>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InterceptKeysPayload {
+    #[prost(message, repeated, tag = "1")]
+    pub keys: ::prost::alloc::vec::Vec<super::key::Key>,
+    #[prost(enumeration = "super::input_mode::InputMode", repeated, tag = "2")]
+    pub input_modes: ::prost::alloc::vec::Vec<i32>,
+    #[prost(string, repeated, tag = "3")]
+    pub custom_input_modes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterInputModePayload {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub keybinds: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PluginRequestPayload {
+    #[prost(uint32, optional, tag = "1")]
+    pub destination_plugin_id: ::core::option::Option<u32>,
+    #[prost(string, optional, tag = "2")]
+    pub plugin_url: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "4")]
+    pub payload: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(double, tag = "5")]
+    pub timeout_seconds: f64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RespondToPluginRequestPayload {
+    #[prost(string, tag = "1")]
+    pub request_id: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub payload: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PluginRequestId {
+    #[prost(string, tag = "1")]
+    pub request_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RevokePluginPermissionsPayload {
+    #[prost(string, tag = "1")]
+    pub plugin_url: ::prost::alloc::string::String,
+    #[prost(
+        enumeration = "super::plugin_permission::PermissionType",
+        repeated,
+        tag = "2"
+    )]
+    pub permissions: ::prost::alloc::vec::Vec<i32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GrantedPluginPermissions {
+    #[prost(string, tag = "1")]
+    pub plugin_url: ::prost::alloc::string::String,
+    #[prost(
+        enumeration = "super::plugin_permission::PermissionType",
+        repeated,
+        tag = "2"
+    )]
+    pub permissions: ::prost::alloc::vec::Vec<i32>,
+    #[prost(message, repeated, tag = "3")]
+    pub scopes: ::prost::alloc::vec::Vec<super::plugin_permission::PermissionScope>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GrantedPluginPermissionsList {
+    #[prost(message, repeated, tag = "1")]
+    pub plugin_permissions: ::prost::alloc::vec::Vec<GrantedPluginPermissions>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LineRange {
+    #[prost(uint64, tag = "1")]
+    pub start: u64,
+    #[prost(uint64, tag = "2")]
+    pub end: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PaneContents {
+    #[prost(string, repeated, tag = "1")]
+    pub viewport: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(string, repeated, tag = "2")]
+    pub scrollback: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(uint64, tag = "3")]
+    pub scrollback_offset: u64,
+    #[prost(uint64, tag = "4")]
+    pub scrollback_length: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PaneContentsResult {
+    #[prost(message, optional, tag = "1")]
+    pub pane_contents: ::core::option::Option<PaneContents>,
+    #[prost(string, optional, tag = "2")]
+    pub error: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct HidePaneWithIdPayload {
     #[prost(message, optional, tag = "1")]
     pub pane_id: ::core::option::Option<PaneId>,
@@ -243,6 +395,8 @@ pub struct RequestPluginPermissionPayload {
         tag = "1"
     )]
     pub permissions: ::prost::alloc::vec::Vec<i32>,
+    #[prost(message, repeated, tag = "2")]
+    pub scopes: ::prost::alloc::vec::Vec<super::plugin_permission::PermissionScope>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -478,6 +632,16 @@ pub enum CommandName {
     ShowPaneWithId = 89,
     OpenCommandPaneBackground = 90,
     RerunCommandPane = 91,
+    SetPaneColor = 92,
+    GetPaneScrollback = 93,
+    SubscribeToPaneOutput = 94,
+    InterceptKeys = 95,
+    ReinjectKey = 96,
+    RegisterInputMode = 97,
+    PluginRequest = 98,
+    RespondToPluginRequest = 99,
+    ListPluginPermissions = 100,
+    RevokePluginPermissions = 101,
 }
 impl CommandName {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -578,6 +742,16 @@ impl CommandName {
             CommandName::ShowPaneWithId => "ShowPaneWithId",
             CommandName::OpenCommandPaneBackground => "OpenCommandPaneBackground",
             CommandName::RerunCommandPane => "RerunCommandPane",
+            CommandName::SetPaneColor => "SetPaneColor",
+            CommandName::GetPaneScrollback => "GetPaneScrollback",
+            CommandName::SubscribeToPaneOutput => "SubscribeToPaneOutput",
+            CommandName::InterceptKeys => "InterceptKeys",
+            CommandName::ReinjectKey => "ReinjectKey",
+            CommandName::RegisterInputMode => "RegisterInputMode",
+            CommandName::PluginRequest => "PluginRequest",
+            CommandName::RespondToPluginRequest => "RespondToPluginRequest",
+            CommandName::ListPluginPermissions => "ListPluginPermissions",
+            CommandName::RevokePluginPermissions => "RevokePluginPermissions",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -675,6 +849,16 @@ impl CommandName {
             "ShowPaneWithId" => Some(Self::ShowPaneWithId),
             "OpenCommandPaneBackground" => Some(Self::OpenCommandPaneBackground),
             "RerunCommandPane" => Some(Self::RerunCommandPane),
+            "SetPaneColor" => Some(Self::SetPaneColor),
+            "GetPaneScrollback" => Some(Self::GetPaneScrollback),
+            "SubscribeToPaneOutput" => Some(Self::SubscribeToPaneOutput),
+            "InterceptKeys" => Some(Self::InterceptKeys),
+            "ReinjectKey" => Some(Self::ReinjectKey),
+            "RegisterInputMode" => Some(Self::RegisterInputMode),
+            "PluginRequest" => Some(Self::PluginRequest),
+            "RespondToPluginRequest" => Some(Self::RespondToPluginRequest),
+            "ListPluginPermissions" => Some(Self::ListPluginPermissions),
+            "RevokePluginPermissions" => Some(Self::RevokePluginPermissions),
             _ => None,
         }
     }
@@ -1,3 +1,11 @@
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PermissionScope {
+    #[prost(enumeration = "PermissionType", tag = "1")]
+    pub permission_type: i32,
+    #[prost(string, repeated, tag = "2")]
+    pub scope: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum PermissionType {
@@ -11,6 +19,9 @@ pub enum PermissionType {
     ReadCliPipes = 7,
     MessageAndLaunchOtherPlugins = 8,
     Reconfigure = 9,
+    ReadPaneContents = 10,
+    InterceptInput = 11,
+    HostFilesystemAccess = 12,
 }
 impl PermissionType {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -31,6 +42,9 @@ impl PermissionType {
                 "MessageAndLaunchOtherPlugins"
             }
             PermissionType::Reconfigure => "Reconfigure",
+            PermissionType::ReadPaneContents => "ReadPaneContents",
+            PermissionType::InterceptInput => "InterceptInput",
+            PermissionType::HostFilesystemAccess => "HostFilesystemAccess",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -46,6 +60,9 @@ impl PermissionType {
             "ReadCliPipes" => Some(Self::ReadCliPipes),
             "MessageAndLaunchOtherPlugins" => Some(Self::MessageAndLaunchOtherPlugins),
             "Reconfigure" => Some(Self::Reconfigure),
+            "ReadPaneContents" => Some(Self::ReadPaneContents),
+            "InterceptInput" => Some(Self::InterceptInput),
+            "HostFilesystemAccess" => Some(Self::HostFilesystemAccess),
             _ => None,
         }
     }
@@ -47,6 +47,16 @@ pub struct Palette {
     pub pink: ::core::option::Option<Color>,
     #[prost(message, optional, tag = "18")]
     pub brown: ::core::option::Option<Color>,
+    #[prost(message, repeated, tag = "19")]
+    pub ui_colors: ::prost::alloc::vec::Vec<UiColor>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UiColor {
+    #[prost(string, tag = "1")]
+    pub role: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub color: ::core::option::Option<Color>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -1,12 +1,16 @@
-use crate::data::{Direction, InputMode, Resize};
+use crate::data::{Direction, InputMode, PaletteColor, PaneId, PermissionType, Resize};
 use crate::setup::Setup;
 use crate::{
     consts::{ZELLIJ_CONFIG_DIR_ENV, ZELLIJ_CONFIG_FILE_ENV},
-    input::{layout::PluginUserConfiguration, options::CliOptions},
+    input::{
+        command::RestartCondition, layout::PluginUserConfiguration, options::CliOptions,
+        theme_import::ThemeFormat,
+    },
 };
 use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::str::FromStr;
 use url::Url;
 
 fn validate_session(name: &str) -> Result<String, String> {
@@ -33,6 +37,33 @@ fn validate_session(name: &str) -> Result<String, String> {
     Ok(name.to_owned())
 }
 
+fn parse_scoped_permission(permission: &str) -> Result<(PermissionType, Vec<String>), String> {
+    let (permission_type, scope) = match permission.split_once('=') {
+        Some((permission_type, scope)) => (
+            permission_type,
+            scope
+                .split(',')
+                .filter(|scope_entry| !scope_entry.is_empty())
+                .map(|scope_entry| scope_entry.to_owned())
+                .collect(),
+        ),
+        None => (permission, vec![]),
+    };
+    let permission_type = PermissionType::from_str(permission_type)
+        .map_err(|_| format!("invalid permission '{}'", permission_type))?;
+    Ok((permission_type, scope))
+}
+
+fn parse_layout_var(layout_var: &str) -> Result<(String, String), String> {
+    match layout_var.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_owned(), value.to_owned())),
+        _ => Err(format!(
+            "invalid layout variable '{}', expected NAME=VALUE",
+            layout_var
+        )),
+    }
+}
+
 #[derive(Parser, Default, Debug, Clone, Serialize, Deserialize)]
 #[clap(version, name = "zellij")]
 pub struct CliArgs {
@@ -56,6 +87,10 @@ pub struct CliArgs {
     #[clap(short, long, value_parser, overrides_with = "layout")]
     pub layout: Option<PathBuf>,
 
+    /// Set a variable that the layout references as ${NAME}, can be repeated (eg. --layout-var project=api)
+    #[clap(long = "layout-var", value_name = "NAME=VALUE", value_parser = parse_layout_var)]
+    pub layout_vars: Vec<(String, String)>,
+
     /// Change where zellij looks for the configuration file
     #[clap(short, long, overrides_with = "config", env = ZELLIJ_CONFIG_FILE_ENV, value_parser)]
     pub config: Option<PathBuf>,
@@ -94,6 +129,75 @@ pub enum SessionCommand {
     Options(CliOptions),
 }
 
+#[derive(Debug, Subcommand, Clone, Serialize, Deserialize)]
+pub enum PluginSubcommand {
+    /// List, grant or revoke the permissions granted to plugins
+    Permissions {
+        #[clap(subcommand)]
+        action: PluginPermissionsAction,
+    },
+    /// Download a plugin and pin it by its sha256, it can then be started by its alias
+    Install {
+        /// Plugin URL, must start with http(s)
+        url: String,
+        /// The expected sha256 of the plugin, installing fails if the downloaded plugin does not
+        /// match it
+        #[clap(long, value_parser)]
+        sha256: Option<String>,
+        /// The alias to install the plugin as (defaults to the file name in the url)
+        #[clap(long, value_parser)]
+        alias: Option<String>,
+        /// The version to record for the plugin (defaults to the version in the url, if any)
+        #[clap(long, value_parser)]
+        version: Option<String>,
+    },
+    /// List the installed plugins along with their url, version and sha256
+    List,
+    /// Download an installed plugin again, optionally from a new url, and pin its new sha256
+    Update {
+        /// The alias the plugin was installed as
+        alias: String,
+        /// Plugin URL to update from, must start with http(s) (defaults to the installed url)
+        #[clap(long, value_parser)]
+        url: Option<String>,
+        /// The expected sha256 of the plugin, updating fails if the downloaded plugin does not
+        /// match it
+        #[clap(long, value_parser)]
+        sha256: Option<String>,
+        /// The version to record for the plugin (defaults to the version in the url, if any)
+        #[clap(long, value_parser)]
+        version: Option<String>,
+    },
+    /// Remove an installed plugin
+    Remove {
+        /// The alias the plugin was installed as
+        alias: String,
+    },
+}
+
+#[derive(Debug, Subcommand, Clone, Serialize, Deserialize)]
+pub enum PluginPermissionsAction {
+    /// List the plugins that were granted permissions along with their permissions
+    List,
+    /// Grant permissions to a plugin so that it will not prompt for them
+    Grant {
+        /// Plugin URL, can either start with http(s), file: or zellij:
+        url: String,
+        /// The permissions to grant (eg. ReadApplicationState RunCommands), a permission can be
+        /// limited to a scope with PERMISSION=SCOPE,SCOPE (eg. RunCommands=git,cargo
+        /// WebAccess=api.github.com HostFilesystemAccess=/host/src)
+        #[clap(required(true), value_name = "PERMISSION[=SCOPE]", value_parser = parse_scoped_permission)]
+        permissions: Vec<(PermissionType, Vec<String>)>,
+    },
+    /// Revoke permissions from a plugin, all of them if none are specified
+    Revoke {
+        /// Plugin URL, can either start with http(s), file: or zellij:
+        url: String,
+        /// The permissions to revoke (eg. RunCommands)
+        permissions: Vec<PermissionType>,
+    },
+}
+
 #[derive(Debug, Subcommand, Clone, Serialize, Deserialize)]
 pub enum Sessions {
     /// List active sessions
@@ -227,6 +331,30 @@ pub enum Sessions {
         #[clap(short, long, value_parser, default_value("false"), takes_value(false))]
         start_suspended: bool,
 
+        /// Restart the command when it exits (never, on-failure or always)
+        #[clap(long, value_parser)]
+        restart: Option<RestartCondition>,
+
+        /// The maximum number of times to restart the command (unlimited if not specified)
+        #[clap(long, value_parser, requires("restart"))]
+        max_restarts: Option<usize>,
+
+        /// Re-run the command when files matching this glob (relative to the cwd) change, can be
+        /// specified multiple times
+        #[clap(long, value_parser)]
+        watch: Vec<String>,
+
+        /// Kill the command if it is still running when watched files change, rather than re-run
+        /// it once it exits
+        #[clap(
+            long,
+            value_parser,
+            default_value("false"),
+            takes_value(false),
+            requires("watch")
+        )]
+        kill_on_change: bool,
+
         /// The x coordinates if the pane is floating as a bare integer (eg. 1) or percent (eg. 10%)
         #[clap(short, long, requires("floating"))]
         x: Option<String>,
@@ -240,12 +368,16 @@ pub enum Sessions {
         #[clap(long, requires("floating"))]
         height: Option<String>,
     },
-    /// Load a plugin
-    #[clap(visible_alias = "p")]
+    /// Load a plugin, or manage the permissions granted to plugins
+    #[clap(
+        visible_alias = "p",
+        subcommand_negates_reqs(true),
+        args_conflicts_with_subcommands(true)
+    )]
     Plugin {
         /// Plugin URL, can either start with http(s), file: or zellij:
         #[clap(last(true), required(true))]
-        url: String,
+        url: Option<String>,
 
         /// Plugin configuration
         #[clap(short, long, value_parser)]
@@ -269,6 +401,9 @@ pub enum Sessions {
         /// Skip the memory and HD cache and force recompile of the plugin (good for development)
         #[clap(short, long, value_parser, default_value("false"), takes_value(false))]
         skip_plugin_cache: bool,
+        /// Reload the plugin whenever its file changes, only for file: plugins (good for development)
+        #[clap(long, value_parser, default_value("false"), takes_value(false))]
+        watch: bool,
         /// The x coordinates if the pane is floating as a bare integer (eg. 1) or percent (eg. 10%)
         #[clap(short, long, requires("floating"))]
         x: Option<String>,
@@ -281,6 +416,9 @@ pub enum Sessions {
         /// The height if the pane is floating as a bare integer (eg. 1) or percent (eg. 10%)
         #[clap(long, requires("floating"))]
         height: Option<String>,
+
+        #[clap(subcommand)]
+        subcommand: Option<PluginSubcommand>,
     },
     /// Edit file with default $EDITOR / $VISUAL
     #[clap(visible_alias = "e")]
@@ -335,6 +473,10 @@ pub enum Sessions {
     },
     ConvertTheme {
         old_theme_file: PathBuf,
+        /// Convert a theme of another terminal emulator instead of an old Zellij YAML theme, one
+        /// of: alacritty, kitty, wezterm, base16
+        #[clap(long, value_parser)]
+        from: Option<ThemeFormat>,
     },
     /// Send data to one or more plugins, launch them if they are not running.
     #[clap(override_usage(
@@ -503,6 +645,26 @@ pub enum CliAction {
             requires("command")
         )]
         start_suspended: bool,
+        /// Restart the command when it exits (never, on-failure or always)
+        #[clap(long, value_parser, requires("command"))]
+        restart: Option<RestartCondition>,
+        /// The maximum number of times to restart the command (unlimited if not specified)
+        #[clap(long, value_parser, requires("restart"))]
+        max_restarts: Option<usize>,
+        /// Re-run the command when files matching this glob (relative to the cwd) change, can be
+        /// specified multiple times
+        #[clap(long, value_parser, requires("command"))]
+        watch: Vec<String>,
+        /// Kill the command if it is still running when watched files change, rather than re-run
+        /// it once it exits
+        #[clap(
+            long,
+            value_parser,
+            default_value("false"),
+            takes_value(false),
+            requires("watch")
+        )]
+        kill_on_change: bool,
         #[clap(long, value_parser)]
         configuration: Option<PluginUserConfiguration>,
         #[clap(long, value_parser)]
@@ -564,8 +726,10 @@ pub enum CliAction {
         #[clap(long, requires("floating"))]
         height: Option<String>,
     },
-    /// Switch input mode of all connected clients [locked|pane|tab|resize|move|search|session]
+    /// Switch input mode of all connected clients [locked|pane|tab|resize|move|search|session] or
+    /// to a custom mode by its name
     SwitchMode {
+        #[clap(value_parser = InputMode::from_str_or_custom)]
         input_mode: InputMode,
     },
     /// Embed focused pane if floating or float focused pane if embedded
@@ -613,6 +777,10 @@ pub enum CliAction {
         #[clap(long, value_parser, requires("layout"))]
         layout_dir: Option<PathBuf>,
 
+        /// Set a variable that the layout references as ${NAME}, can be repeated
+        #[clap(long = "layout-var", value_name = "NAME=VALUE", value_parser = parse_layout_var, requires("layout"))]
+        layout_vars: Vec<(String, String)>,
+
         /// Name of the new tab
         #[clap(short, long, value_parser)]
         name: Option<String>,
@@ -737,4 +905,20 @@ tail -f /tmp/my-live-logfile | zellij action pipe --name logs --plugin https://e
         plugin_title: Option<String>,
     },
     ListClients,
+    /// Override the frame and background colors of a pane, the colors that are not given are reset
+    /// to the ones of the theme
+    SetPaneColor {
+        /// The id of the pane (eg. terminal_1, plugin_1 or 1), defaults to the focused pane
+        #[clap(short, long, value_parser)]
+        pane_id: Option<PaneId>,
+        /// The color of the frame when the pane is not focused (eg. "#ff0000", 196 or red)
+        #[clap(short, long, value_parser)]
+        frame_color: Option<PaletteColor>,
+        /// The color of the frame when the pane is focused
+        #[clap(long, value_parser)]
+        frame_color_focused: Option<PaletteColor>,
+        /// The background of the pane contents, used wherever the application did not set one
+        #[clap(short, long, value_parser)]
+        background: Option<PaletteColor>,
+    },
 }
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::fs::Metadata;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::{self, FromStr};
 use std::time::Duration;
@@ -41,7 +42,7 @@ pub fn client_id_to_colors(
 }
 
 pub fn single_client_color(colors: Palette) -> (PaletteColor, PaletteColor) {
-    (colors.green, colors.black)
+    (colors.frame_focused(), colors.black)
 }
 
 impl FromStr for KeyWithModifier {
@@ -921,6 +922,13 @@ pub enum Event {
     EditPaneExited(u32, Option<i32>, Context), // u32 - terminal_pane_id, Option<i32> - exit code
     CommandPaneReRun(u32, Context),            // u32 - terminal_pane_id, Option<i32> -
     FailedToWriteConfigToDisk(Option<String>), // String -> the file path we failed to write
+    /// Lines that were completed in a terminal pane since the last event
+    PaneOutput(PaneId, Vec<String>), // Vec<String> -> lines
+    /// A key this plugin intercepts was pressed, it will not reach the focused pane unless the
+    /// plugin re-injects it
+    InterceptedKey(KeyWithModifier),
+    /// The answer to a request this plugin sent to another plugin with `plugin_request`
+    PluginResponse(PluginResponse),
 }
 
 #[derive(
@@ -951,6 +959,9 @@ pub enum Permission {
     ReadCliPipes,
     MessageAndLaunchOtherPlugins,
     Reconfigure,
+    ReadPaneContents,
+    InterceptInput,
+    HostFilesystemAccess,
 }
 
 impl PermissionType {
@@ -972,36 +983,147 @@ impl PermissionType {
                 "Send messages to and launch other plugins".to_owned()
             },
             PermissionType::Reconfigure => "Change Zellij runtime configuration".to_owned(),
+            PermissionType::ReadPaneContents => {
+                "Read the contents and scrollback of terminal panes".to_owned()
+            },
+            PermissionType::InterceptInput => {
+                "Intercept keys before they reach the focused pane".to_owned()
+            },
+            PermissionType::HostFilesystemAccess => {
+                "Access files on the host filesystem (/host)".to_owned()
+            },
+        }
+    }
+}
+
+/// Narrows down what a granted permission allows: the commands a plugin can run
+/// (`RunCommands`), the hosts it can make web requests to (`WebAccess`, a `*.` prefix also
+/// matches subdomains) or the folders under `/host` it can access (`HostFilesystemAccess`).
+/// Permissions without a scope are not limited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionScopes(BTreeMap<PermissionType, Vec<String>>);
+
+impl PermissionScopes {
+    pub fn new() -> Self {
+        PermissionScopes::default()
+    }
+    pub fn with_scope<S: ToString>(mut self, permission: PermissionType, scope: &[S]) -> Self {
+        self.set_scope(permission, scope.iter().map(|s| s.to_string()).collect());
+        self
+    }
+    pub fn set_scope(&mut self, permission: PermissionType, scope: Vec<String>) {
+        self.0.insert(permission, scope);
+    }
+    pub fn remove_scope(&mut self, permission: &PermissionType) {
+        self.0.remove(permission);
+    }
+    pub fn scope(&self, permission: &PermissionType) -> Option<&Vec<String>> {
+        self.0.get(permission)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&PermissionType, &Vec<String>)> {
+        self.0.iter()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Whether `value` (a command, a web host or a path under `/host`) is within the scope of
+    /// `permission`
+    pub fn allows(&self, permission: PermissionType, value: &str) -> bool {
+        match self.0.get(&permission) {
+            Some(scope) => scope
+                .iter()
+                .any(|scope_entry| scope_entry_allows(permission, scope_entry, value)),
+            None => true,
+        }
+    }
+    /// Only what is allowed by both these scopes and `other`
+    pub fn narrowed_by(&self, other: &PermissionScopes) -> PermissionScopes {
+        let mut narrowed = self.clone();
+        for (permission, other_scope) in other.iter() {
+            let scope = match self.scope(permission) {
+                Some(scope) => {
+                    let mut scope: Vec<String> = scope
+                        .iter()
+                        .filter(|scope_entry| other.allows(*permission, scope_entry))
+                        .cloned()
+                        .collect();
+                    for scope_entry in other_scope {
+                        if self.allows(*permission, scope_entry) && !scope.contains(scope_entry) {
+                            scope.push(scope_entry.clone());
+                        }
+                    }
+                    scope
+                },
+                None => other_scope.clone(),
+            };
+            narrowed.set_scope(*permission, scope);
         }
+        narrowed
     }
 }
 
+fn scope_entry_allows(permission: PermissionType, scope_entry: &str, value: &str) -> bool {
+    match permission {
+        PermissionType::WebAccess => match scope_entry.strip_prefix("*.") {
+            Some(domain) => {
+                value.eq_ignore_ascii_case(domain)
+                    || value
+                        .to_ascii_lowercase()
+                        .ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+            },
+            None => value.eq_ignore_ascii_case(scope_entry),
+        },
+        PermissionType::HostFilesystemAccess => {
+            let path = host_relative_path(value);
+            !path
+                .components()
+                .any(|component| component == std::path::Component::ParentDir)
+                && path.starts_with(host_relative_path(scope_entry))
+        },
+        // commands have to match exactly, otherwise allowing "git" would also allow running any
+        // executable named git
+        _ => value == scope_entry,
+    }
+}
+
+/// The path relative to the `/host` folder plugins see, eg. `src` for both `/host/src` and `src`
+pub fn host_relative_path(path: &str) -> PathBuf {
+    let path = Path::new(path);
+    let path = path.strip_prefix("/host").unwrap_or(path);
+    path.components()
+        .filter(|component| {
+            !matches!(
+                component,
+                std::path::Component::RootDir | std::path::Component::CurDir
+            )
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct PluginPermission {
     pub name: String,
     pub permissions: Vec<PermissionType>,
+    pub scopes: PermissionScopes,
 }
 
 impl PluginPermission {
     pub fn new(name: String, permissions: Vec<PermissionType>) -> Self {
-        PluginPermission { name, permissions }
+        PluginPermission {
+            name,
+            permissions,
+            scopes: PermissionScopes::default(),
+        }
+    }
+    pub fn with_scopes(mut self, scopes: PermissionScopes) -> Self {
+        self.scopes = scopes;
+        self
     }
 }
 
 /// Describes the different input modes, which change the way that keystrokes will be interpreted.
 #[derive(
-    Debug,
-    PartialEq,
-    Eq,
-    Hash,
-    Copy,
-    Clone,
-    EnumIter,
-    Serialize,
-    Deserialize,
-    ArgEnum,
-    PartialOrd,
-    Ord,
+    Debug, PartialEq, Eq, Hash, Clone, EnumIter, Serialize, Deserialize, ArgEnum, PartialOrd, Ord,
 )]
 pub enum InputMode {
     /// In `Normal` mode, input is always written to the terminal, except for the shortcuts leading
@@ -1048,6 +1170,35 @@ pub enum InputMode {
     /// `Tmux` mode allows for basic tmux keybindings functionality
     #[serde(alias = "tmux")]
     Tmux,
+    /// A mode defined in the keybindings (eg. `custom_mode "deploy" { ... }`) or registered by a
+    /// plugin, keys that are not bound in it are ignored
+    #[strum(disabled)]
+    #[clap(skip)]
+    Custom(String),
+}
+
+impl InputMode {
+    /// The name of the mode as it appears in the configuration (eg. `renametab` or `deploy`)
+    pub fn name(&self) -> String {
+        match self {
+            InputMode::Custom(name) => name.clone(),
+            _ => format!("{:?}", self).to_lowercase(),
+        }
+    }
+    pub fn is_custom(&self) -> bool {
+        matches!(self, InputMode::Custom(..))
+    }
+    /// Like `from_str`, but any other name is taken to be the one of a custom mode, which might
+    /// only be declared later on (eg. by a plugin)
+    pub fn from_str_or_custom(s: &str) -> Result<Self, ConversionError> {
+        s.parse().or_else(|e| {
+            if !s.is_empty() && !s.contains(char::is_whitespace) {
+                Ok(InputMode::Custom(s.to_owned()))
+            } else {
+                Err(e)
+            }
+        })
+    }
 }
 
 impl Default for InputMode {
@@ -1078,6 +1229,83 @@ impl Default for PaletteColor {
     }
 }
 
+impl FromStr for PaletteColor {
+    type Err = String;
+    /// Parses colors in the `#RRGGBB` and `#RGB` forms, an 8-bit color index or the name of
+    /// one of the 8 basic ANSI colors
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(hex) = s.strip_prefix('#') {
+            let channel = |s: &str| {
+                u8::from_str_radix(s, 16).map_err(|_| format!("Invalid hex color: {}", s))
+            };
+            return match hex.len() {
+                6 => Ok(PaletteColor::Rgb((
+                    channel(&hex[0..2])?,
+                    channel(&hex[2..4])?,
+                    channel(&hex[4..6])?,
+                ))),
+                3 => Ok(PaletteColor::Rgb((
+                    channel(&hex[0..1])? * 0x11,
+                    channel(&hex[1..2])? * 0x11,
+                    channel(&hex[2..3])? * 0x11,
+                ))),
+                _ => Err(format!("Invalid hex color: {}", s)),
+            };
+        }
+        if let Ok(color_index) = s.parse::<u8>() {
+            return Ok(PaletteColor::EightBit(color_index));
+        }
+        let ansi_colors = [
+            "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+        ];
+        ansi_colors
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(s))
+            .map(|color_index| PaletteColor::EightBit(color_index as u8))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid color: {}, expected #RRGGBB, #RGB, 0-255 or an ANSI color name",
+                    s
+                )
+            })
+    }
+}
+
+impl fmt::Display for PaletteColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaletteColor::Rgb((r, g, b)) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            PaletteColor::EightBit(color_index) => write!(f, "{}", color_index),
+        }
+    }
+}
+
+/// Colors that override the theme for a specific pane or for all the panes of a tab
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub struct PaneColors {
+    /// The frame of the pane when it is not focused
+    pub frame: Option<PaletteColor>,
+    /// The frame of the pane when it is focused
+    pub frame_focused: Option<PaletteColor>,
+    /// The background of the pane contents, used wherever the application did not set one
+    pub background: Option<PaletteColor>,
+}
+
+impl PaneColors {
+    pub fn is_empty(&self) -> bool {
+        self.frame.is_none() && self.frame_focused.is_none() && self.background.is_none()
+    }
+    /// The colors of `self` overridden by the ones set in `other`
+    pub fn merge(&self, other: &PaneColors) -> PaneColors {
+        PaneColors {
+            frame: other.frame.or(self.frame),
+            frame_focused: other.frame_focused.or(self.frame_focused),
+            background: other.background.or(self.background),
+        }
+    }
+}
+
 impl FromStr for InputMode {
     type Err = ConversionError;
 
@@ -1133,6 +1361,167 @@ pub struct Palette {
     pub silver: PaletteColor,
     pub pink: PaletteColor,
     pub brown: PaletteColor,
+    pub ui: UiColors,
+}
+
+/// The colors of specific parts of the UI. A theme can set any of them, the roles it does not set
+/// fall back to the named colors of the [`Palette`], see the methods of [`Palette`] that resolve
+/// them.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+pub struct UiColors {
+    /// The frame of the focused pane
+    pub frame_focused: Option<PaletteColor>,
+    /// The frame of the focused pane while in a mode other than normal or locked
+    pub frame_highlight: Option<PaletteColor>,
+    /// The frames of panes that are not focused
+    pub frame_unfocused: Option<PaletteColor>,
+    /// The frames of panes focused by other clients
+    pub frame_other_client: Option<PaletteColor>,
+    pub selection_fg: Option<PaletteColor>,
+    pub selection_bg: Option<PaletteColor>,
+    pub search_match_fg: Option<PaletteColor>,
+    pub search_match_bg: Option<PaletteColor>,
+    /// The search match the cursor is on
+    pub search_active_match_bg: Option<PaletteColor>,
+    pub ribbon_selected_fg: Option<PaletteColor>,
+    pub ribbon_selected_bg: Option<PaletteColor>,
+    pub ribbon_unselected_fg: Option<PaletteColor>,
+    pub ribbon_unselected_bg: Option<PaletteColor>,
+    pub table_title: Option<PaletteColor>,
+    pub tab_active_fg: Option<PaletteColor>,
+    pub tab_active_bg: Option<PaletteColor>,
+    pub tab_inactive_fg: Option<PaletteColor>,
+    pub tab_inactive_bg: Option<PaletteColor>,
+    pub exit_code_success: Option<PaletteColor>,
+    pub exit_code_error: Option<PaletteColor>,
+}
+
+impl UiColors {
+    /// The roles by their names in themes
+    pub fn roles(&self) -> [(&'static str, Option<PaletteColor>); 20] {
+        [
+            ("frame_focused", self.frame_focused),
+            ("frame_highlight", self.frame_highlight),
+            ("frame_unfocused", self.frame_unfocused),
+            ("frame_other_client", self.frame_other_client),
+            ("selection_fg", self.selection_fg),
+            ("selection_bg", self.selection_bg),
+            ("search_match_fg", self.search_match_fg),
+            ("search_match_bg", self.search_match_bg),
+            ("search_active_match_bg", self.search_active_match_bg),
+            ("ribbon_selected_fg", self.ribbon_selected_fg),
+            ("ribbon_selected_bg", self.ribbon_selected_bg),
+            ("ribbon_unselected_fg", self.ribbon_unselected_fg),
+            ("ribbon_unselected_bg", self.ribbon_unselected_bg),
+            ("table_title", self.table_title),
+            ("tab_active_fg", self.tab_active_fg),
+            ("tab_active_bg", self.tab_active_bg),
+            ("tab_inactive_fg", self.tab_inactive_fg),
+            ("tab_inactive_bg", self.tab_inactive_bg),
+            ("exit_code_success", self.exit_code_success),
+            ("exit_code_error", self.exit_code_error),
+        ]
+    }
+    /// Sets a role by its name in themes, returns false if there is no such role
+    pub fn set_role(&mut self, role: &str, color: PaletteColor) -> bool {
+        let field = match role {
+            "frame_focused" => &mut self.frame_focused,
+            "frame_highlight" => &mut self.frame_highlight,
+            "frame_unfocused" => &mut self.frame_unfocused,
+            "frame_other_client" => &mut self.frame_other_client,
+            "selection_fg" => &mut self.selection_fg,
+            "selection_bg" => &mut self.selection_bg,
+            "search_match_fg" => &mut self.search_match_fg,
+            "search_match_bg" => &mut self.search_match_bg,
+            "search_active_match_bg" => &mut self.search_active_match_bg,
+            "ribbon_selected_fg" => &mut self.ribbon_selected_fg,
+            "ribbon_selected_bg" => &mut self.ribbon_selected_bg,
+            "ribbon_unselected_fg" => &mut self.ribbon_unselected_fg,
+            "ribbon_unselected_bg" => &mut self.ribbon_unselected_bg,
+            "table_title" => &mut self.table_title,
+            "tab_active_fg" => &mut self.tab_active_fg,
+            "tab_active_bg" => &mut self.tab_active_bg,
+            "tab_inactive_fg" => &mut self.tab_inactive_fg,
+            "tab_inactive_bg" => &mut self.tab_inactive_bg,
+            "exit_code_success" => &mut self.exit_code_success,
+            "exit_code_error" => &mut self.exit_code_error,
+            _ => return false,
+        };
+        *field = Some(color);
+        true
+    }
+}
+
+impl Palette {
+    pub fn frame_focused(&self) -> PaletteColor {
+        self.ui.frame_focused.unwrap_or(self.green)
+    }
+    pub fn frame_highlight(&self) -> PaletteColor {
+        self.ui.frame_highlight.unwrap_or(self.orange)
+    }
+    /// `None` draws the frame in the default color
+    pub fn frame_unfocused(&self) -> Option<PaletteColor> {
+        self.ui.frame_unfocused
+    }
+    /// `None` draws the frame like any other unfocused frame
+    pub fn frame_other_client(&self) -> Option<PaletteColor> {
+        self.ui.frame_other_client.or(self.ui.frame_unfocused)
+    }
+    /// `None` keeps the foreground of the selected text
+    pub fn selection_fg(&self) -> Option<PaletteColor> {
+        self.ui.selection_fg
+    }
+    pub fn selection_bg(&self) -> PaletteColor {
+        self.ui.selection_bg.unwrap_or(self.bg)
+    }
+    pub fn search_match_fg(&self) -> PaletteColor {
+        self.ui.search_match_fg.unwrap_or(self.black)
+    }
+    pub fn search_match_bg(&self) -> PaletteColor {
+        self.ui.search_match_bg.unwrap_or(self.green)
+    }
+    pub fn search_active_match_bg(&self) -> PaletteColor {
+        self.ui.search_active_match_bg.unwrap_or(self.orange)
+    }
+    pub fn ribbon_selected_fg(&self) -> PaletteColor {
+        self.ui.ribbon_selected_fg.unwrap_or(self.black)
+    }
+    pub fn ribbon_selected_bg(&self) -> PaletteColor {
+        self.ui.ribbon_selected_bg.unwrap_or(self.green)
+    }
+    pub fn ribbon_unselected_fg(&self) -> PaletteColor {
+        self.ui.ribbon_unselected_fg.unwrap_or(self.black)
+    }
+    pub fn ribbon_unselected_bg(&self) -> PaletteColor {
+        self.ui.ribbon_unselected_bg.unwrap_or(self.fg)
+    }
+    pub fn table_title(&self) -> PaletteColor {
+        self.ui.table_title.unwrap_or(self.green)
+    }
+    pub fn tab_active_fg(&self) -> PaletteColor {
+        self.ui.tab_active_fg.unwrap_or(match self.theme_hue {
+            ThemeHue::Dark => self.black,
+            ThemeHue::Light => self.white,
+        })
+    }
+    pub fn tab_active_bg(&self) -> PaletteColor {
+        self.ui.tab_active_bg.unwrap_or(self.green)
+    }
+    pub fn tab_inactive_fg(&self) -> PaletteColor {
+        self.ui.tab_inactive_fg.unwrap_or(match self.theme_hue {
+            ThemeHue::Dark => self.black,
+            ThemeHue::Light => self.white,
+        })
+    }
+    pub fn tab_inactive_bg(&self) -> PaletteColor {
+        self.ui.tab_inactive_bg.unwrap_or(self.fg)
+    }
+    pub fn exit_code_success(&self) -> PaletteColor {
+        self.ui.exit_code_success.unwrap_or(self.green)
+    }
+    pub fn exit_code_error(&self) -> PaletteColor {
+        self.ui.exit_code_error.unwrap_or(self.red)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
@@ -1154,11 +1543,13 @@ pub struct ModeInfo {
     pub style: Style,
     pub capabilities: PluginCapabilities,
     pub session_name: Option<String>,
+    /// The keys typed so far of a multi-key sequence that is not yet complete
+    pub pending_key_sequence: Vec<KeyWithModifier>,
 }
 
 impl ModeInfo {
     pub fn get_mode_keybinds(&self) -> Vec<(KeyWithModifier, Vec<Action>)> {
-        self.get_keybinds_for_mode(self.mode)
+        self.get_keybinds_for_mode(self.mode.clone())
     }
 
     pub fn get_keybinds_for_mode(&self, mode: InputMode) -> Vec<(KeyWithModifier, Vec<Action>)> {
@@ -1169,6 +1560,20 @@ impl ModeInfo {
         }
         vec![]
     }
+    /// The custom modes that were defined in the configuration or registered by plugins, sorted
+    /// by name
+    pub fn custom_modes(&self) -> Vec<String> {
+        let mut custom_modes: Vec<String> = self
+            .keybinds
+            .iter()
+            .filter_map(|(input_mode, _)| match input_mode {
+                InputMode::Custom(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        custom_modes.sort();
+        custom_modes
+    }
     pub fn update_keybinds(&mut self, keybinds: Keybinds) {
         self.keybinds = keybinds.to_keybinds_vec();
     }
@@ -1342,6 +1747,21 @@ pub struct PaneInfo {
     pub is_selectable: bool,
 }
 
+/// The text contents of a terminal pane, with lines that were wrapped because they did not fit
+/// the width of the pane joined back together
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct PaneContents {
+    /// The lines currently visible in the pane, from top to bottom
+    pub viewport: Vec<String>,
+    /// The requested range of lines from the whole scrollback (the lines above the viewport, the
+    /// viewport itself and the lines below it if the pane is scrolled up), oldest first
+    pub scrollback: Vec<String>,
+    /// The index of the first line of `scrollback` in the whole scrollback
+    pub scrollback_offset: usize,
+    /// The number of lines in the whole scrollback, can be used to request further ranges
+    pub scrollback_length: usize,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
 pub struct PluginIds {
     pub plugin_id: u32,
@@ -1471,6 +1891,33 @@ pub enum PaneId {
     Plugin(u32),
 }
 
+impl FromStr for PaneId {
+    type Err = String;
+    /// Parses pane ids in the `terminal_1` and `plugin_1` forms, a bare number is the id of a
+    /// terminal pane (eg. the value of `$ZELLIJ_PANE_ID`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid_pane_id = || {
+            format!(
+                "Invalid pane id: {}, expected eg. terminal_1, plugin_1 or 1",
+                s
+            )
+        };
+        if let Some(id) = s.strip_prefix("terminal_") {
+            id.parse()
+                .map(PaneId::Terminal)
+                .map_err(|_| invalid_pane_id())
+        } else if let Some(id) = s.strip_prefix("plugin_") {
+            id.parse()
+                .map(PaneId::Plugin)
+                .map_err(|_| invalid_pane_id())
+        } else {
+            s.parse()
+                .map(PaneId::Terminal)
+                .map_err(|_| invalid_pane_id())
+        }
+    }
+}
+
 impl MessageToPlugin {
     pub fn new(message_name: impl Into<String>) -> Self {
         MessageToPlugin {
@@ -1593,6 +2040,9 @@ pub struct PipeMessage {
     pub payload: Option<String>,
     pub args: BTreeMap<String, String>,
     pub is_private: bool,
+    /// Set if this message is a request from another plugin (sent with `plugin_request`), the
+    /// plugin should answer it with `respond_to_plugin_request`
+    pub request_id: Option<String>,
 }
 
 impl PipeMessage {
@@ -1609,8 +2059,38 @@ impl PipeMessage {
             payload: payload.clone(),
             args: args.clone().unwrap_or_else(|| Default::default()),
             is_private,
+            request_id: None,
         }
     }
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+}
+
+/// The plugin a request sent with `plugin_request` is addressed to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PluginRequestTarget {
+    PluginId(u32),
+    PluginUrl(String), // the first running instance of this plugin answers the request
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PluginRequestError {
+    /// The plugin did not respond within the timeout of the request
+    Timeout,
+    /// The plugin is not running (or was unloaded before responding)
+    NoSuchPlugin,
+}
+
+/// The answer to a request sent with `plugin_request`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginResponse {
+    /// The correlation id returned by `plugin_request`
+    pub request_id: String,
+    /// The name of the request
+    pub name: String,
+    pub result: Result<Option<String>, PluginRequestError>, // Option<String> -> response payload
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, Default)]
@@ -1782,7 +2262,7 @@ pub enum PluginCommand {
     RenamePluginPane(u32, String),   // plugin pane id, new name
     RenameTab(u32, String),          // tab index, new name
     ReportPanic(String),             // stringified panic
-    RequestPluginPermissions(Vec<PermissionType>),
+    RequestPluginPermissions(Vec<PermissionType>, PermissionScopes),
     SwitchSession(ConnectToSession),
     DeleteDeadSession(String),       // String -> session name
     DeleteAllDeadSessions,           // String -> session name
@@ -1820,4 +2300,18 @@ pub enum PluginCommand {
     ShowPaneWithId(PaneId, bool), // bool -> should_float_if_hidden
     OpenCommandPaneBackground(CommandToRun, Context),
     RerunCommandPane(u32), // u32  - terminal pane id
+    SetPaneColor(PaneId, PaneColors),
+    GetPaneScrollback(PaneId, Option<Range<usize>>, bool), // bool -> with_ansi
+    SubscribeToPaneOutput(Vec<PaneId>),                    // an empty list subscribes to all panes
+    InterceptKeys(Vec<KeyWithModifier>, Vec<InputMode>),   // replaces the previous intercepts
+    ReinjectKey(KeyWithModifier),
+    RegisterInputMode(String, String), // String -> mode name, String -> stringified keybindings of
+    // the mode
+    PluginRequest(PluginRequestTarget, String, Option<String>, f64), // String -> request name,
+    // Option<String> -> payload, f64 -> timeout in seconds
+    RespondToPluginRequest(String, Option<String>), // String -> request id, Option<String> ->
+    // payload
+    ListPluginPermissions,
+    RevokePluginPermissions(String, Vec<PermissionType>), // String -> plugin url, an empty Vec
+                                                          // revokes all permissions of the plugin
 }
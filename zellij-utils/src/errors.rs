@@ -300,9 +300,24 @@ pub enum ScreenContext {
     TerminalColorRegisters,
     ChangeMode,
     ChangeModeForAllClients,
+    UpdatePendingKeySequence,
+    MousePress,
+    ShowWhichKeyOverlay,
+    ChangeClientTheme,
+    SetPaneColors,
+    GetPaneContents,
+    SubscribeToPaneOutput,
+    UnsubscribeFromPaneOutput,
+    FlushPaneOutput,
     LeftClick,
     RightClick,
     MiddleClick,
+    OpenHyperlinkAt,
+    Paste,
+    TogglePaneInSelection,
+    ClearPaneSelection,
+    MacroReplayStarted,
+    MacroReplayEnded,
     LeftMouseRelease,
     RightMouseRelease,
     MiddleMouseRelease,
@@ -355,6 +370,7 @@ pub enum ScreenContext {
     ListClientsMetadata,
     Reconfigure,
     RerunCommandPane,
+    RestartCommandPane,
 }
 
 /// Stack call representations corresponding to the different types of [`PtyInstruction`]s.
@@ -370,6 +386,8 @@ pub enum PtyContext {
     ClosePane,
     CloseTab,
     ReRunCommandInPane,
+    CommandPaneExited,
+    WatchedFilesChanged,
     DropToShellInPane,
     SpawnInPlaceTerminal,
     DumpLayout,
@@ -413,6 +431,12 @@ pub enum PluginContext {
     ListClientsMetadata,
     Reconfigure,
     FailedToWriteConfigToDisk,
+    PluginRequest,
+    PluginResponse,
+    PluginRequestTimedOut,
+    PermissionCacheChanged,
+    PluginFileChanged,
+    PluginLockfileChanged,
 }
 
 /// Stack call representations corresponding to the different types of [`ClientInstruction`]s.
@@ -437,6 +461,8 @@ pub enum ClientContext {
     CliPipeOutput,
     QueryTerminalSize,
     WriteConfigToDisk,
+    WriteMacrosToDisk,
+    Macro,
 }
 
 /// Stack call representations corresponding to the different types of [`ServerInstruction`]s.
@@ -465,6 +491,14 @@ pub enum ServerContext {
     Reconfigure,
     ConfigWrittenToDisk,
     FailedToWriteConfigToDisk,
+    ChangeColorScheme,
+    InterceptKeys,
+    ReinjectKey,
+    RouteMouseActions,
+    FocusedPaneChanged,
+    PromptToTrustDirLocalLayout,
+    TrustDirLocalLayout,
+    IgnoreDirLocalLayout,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -486,6 +520,8 @@ pub enum BackgroundJobContext {
     ReportLayoutInfo,
     RunCommand,
     WebRequest,
+    ShowWhichKeyOverlay,
+    FlushPaneOutput,
     Exit,
 }
 
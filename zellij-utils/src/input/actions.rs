@@ -1,12 +1,12 @@
 //! Definition of the actions that can be bound to keys.
 
-use super::command::{OpenFilePayload, RunCommandAction};
+use super::command::{OpenFilePayload, RestartPolicy, RunCommandAction};
 use super::layout::{
     FloatingPaneLayout, Layout, PluginAlias, RunPlugin, RunPluginLocation, RunPluginOrAlias,
     SwapFloatingLayout, SwapTiledLayout, TiledPaneLayout,
 };
 use crate::cli::CliAction;
-use crate::data::{Direction, KeyWithModifier, Resize};
+use crate::data::{Direction, KeyWithModifier, PaneColors, PaneId, Resize};
 use crate::data::{FloatingPaneCoordinates, InputMode};
 use crate::home::{find_default_config_dir, get_layout_dir};
 use crate::input::config::{Config, ConfigError, KdlError};
@@ -299,6 +299,24 @@ pub enum Action {
         pane_title: Option<String>,
     },
     ListClients,
+    /// Start recording the keys typed by this client into the macro with the given name
+    StartMacroRecording(String),
+    /// Stop recording and save the recorded macro
+    StopMacroRecording,
+    /// Replay the keys of the macro with the given name
+    PlayMacro(String),
+    /// Add the focused pane to the panes macros are replayed into, or remove it if it was added
+    TogglePaneInSelection,
+    /// Replay macros into the focused pane again rather than into the selected panes
+    ClearPaneSelection,
+    /// Override the colors of a pane, the focused one if no pane id is given
+    SetPaneColor(Option<PaneId>, PaneColors),
+    /// Paste the text last copied in this session into the focused pane
+    Paste,
+    /// Focus the pane at the position, bound to a mouse button this is the mouse position
+    FocusPaneAt(Position),
+    /// Open the hyperlink at the position, bound to a mouse button this is the mouse position
+    OpenHyperlinkAt(Position),
 }
 
 impl Action {
@@ -312,6 +330,19 @@ impl Action {
         }
     }
 
+    /// Whether this action only makes sense at the position of the mouse
+    pub fn is_positional(&self) -> bool {
+        matches!(self, Action::FocusPaneAt(..) | Action::OpenHyperlinkAt(..))
+    }
+    /// Places a positional action at the position the mouse was pressed
+    pub fn at_mouse_position(self, position: Position) -> Self {
+        match self {
+            Action::FocusPaneAt(..) => Action::FocusPaneAt(position),
+            Action::OpenHyperlinkAt(..) => Action::OpenHyperlinkAt(position),
+            action => action,
+        }
+    }
+
     pub fn actions_from_cli(
         cli_action: CliAction,
         get_current_dir: Box<dyn Fn() -> PathBuf>,
@@ -356,6 +387,10 @@ impl Action {
                 name,
                 close_on_exit,
                 start_suspended,
+                restart,
+                max_restarts,
+                watch,
+                kill_on_change,
                 configuration,
                 skip_plugin_cache,
                 x,
@@ -384,7 +419,7 @@ impl Action {
                         Err(_) => {
                             let mut plugin_alias = PluginAlias::new(
                                 &plugin,
-                                &configuration.map(|c| c.inner().clone()),
+                                &configuration.map(|c| c.inner_with_settings()),
                                 alias_cwd,
                             );
                             plugin_alias.set_caller_cwd_if_not_set(Some(current_dir));
@@ -426,6 +461,8 @@ impl Action {
                     let (command, args) = (PathBuf::from(command.remove(0)), command);
                     let hold_on_start = start_suspended;
                     let hold_on_close = !close_on_exit;
+                    let restart_policy =
+                        RestartPolicy::new(restart.unwrap_or_default(), max_restarts);
                     let run_command_action = RunCommandAction {
                         command,
                         args,
@@ -433,6 +470,9 @@ impl Action {
                         direction,
                         hold_on_close,
                         hold_on_start,
+                        restart_policy,
+                        rerun_on_change: watch,
+                        kill_on_change,
                         ..Default::default()
                     };
                     if floating {
@@ -521,6 +561,7 @@ impl Action {
                 name,
                 layout,
                 layout_dir,
+                layout_vars,
                 cwd,
             } => {
                 let current_dir = get_current_dir();
@@ -550,7 +591,7 @@ impl Action {
                         Layout::stringified_from_path_or_default(Some(&layout_path), layout_dir)
                             .map_err(|e| format!("Failed to load layout: {}", e))?
                     };
-                    let layout = Layout::from_str(&raw_layout, path_to_raw_layout, swap_layouts.as_ref().map(|(f, p)| (f.as_str(), p.as_str())), cwd).map_err(|e| {
+                    let layout = Layout::from_str(&raw_layout, path_to_raw_layout, swap_layouts.as_ref().map(|(f, p)| (f.as_str(), p.as_str())), cwd, layout_vars.into_iter().collect()).map_err(|e| {
                         let stringified_error = match e {
                             ConfigError::KdlError(kdl_error) => {
                                 let error = kdl_error.add_src(layout_path.as_path().as_os_str().to_string_lossy().to_string(), String::from(raw_layout));
@@ -621,7 +662,7 @@ impl Action {
                 let current_dir = get_current_dir();
                 let run_plugin_or_alias = RunPluginOrAlias::from_url(
                     &url,
-                    &configuration.map(|c| c.inner().clone()),
+                    &configuration.map(|c| c.inner_with_settings()),
                     None,
                     Some(current_dir),
                 )?;
@@ -638,7 +679,7 @@ impl Action {
                 let current_dir = get_current_dir();
                 let run_plugin_or_alias = RunPluginOrAlias::from_url(
                     url.as_str(),
-                    &configuration.map(|c| c.inner().clone()),
+                    &configuration.map(|c| c.inner_with_settings()),
                     None,
                     Some(current_dir),
                 )?;
@@ -660,7 +701,7 @@ impl Action {
                 let current_dir = get_current_dir();
                 let run_plugin_or_alias = RunPluginOrAlias::from_url(
                     &url.as_str(),
-                    &configuration.map(|c| c.inner().clone()),
+                    &configuration.map(|c| c.inner_with_settings()),
                     None,
                     Some(current_dir.clone()),
                 )?;
@@ -696,9 +737,9 @@ impl Action {
                     pipe_id,
                     name,
                     payload,
-                    args: args.map(|a| a.inner().clone()), // TODO: no clone somehow
+                    args: args.map(|a| a.inner_with_settings()), // TODO: no clone somehow
                     plugin,
-                    configuration: plugin_configuration.map(|a| a.inner().clone()), // TODO: no clone
+                    configuration: plugin_configuration.map(|a| a.inner_with_settings()), // TODO: no clone
                     // somehow
                     launch_new: force_launch_plugin,
                     floating: floating_plugin,
@@ -709,6 +750,19 @@ impl Action {
                 }])
             },
             CliAction::ListClients => Ok(vec![Action::ListClients]),
+            CliAction::SetPaneColor {
+                pane_id,
+                frame_color,
+                frame_color_focused,
+                background,
+            } => Ok(vec![Action::SetPaneColor(
+                pane_id,
+                PaneColors {
+                    frame: frame_color,
+                    frame_focused: frame_color_focused,
+                    background,
+                },
+            )]),
         }
     }
     pub fn launches_plugin(&self, plugin_url: &str) -> bool {
@@ -2,6 +2,13 @@
 use crate::data::{Direction, OriginatingPlugin};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+const RESTART_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const RESTART_MAX_BACKOFF: Duration = Duration::from_secs(60);
+// a command that ran this long before exiting is restarted as if it never failed before
+const RESTART_STABLE_UPTIME: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Clone)]
 pub enum TerminalAction {
@@ -58,6 +65,12 @@ pub struct RunCommand {
     #[serde(default)]
     pub hold_on_start: bool,
     #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub rerun_on_change: Vec<String>, // glob patterns, relative to cwd
+    #[serde(default)]
+    pub kill_on_change: bool,
+    #[serde(default)]
     pub originating_plugin: Option<OriginatingPlugin>,
 }
 
@@ -93,6 +106,12 @@ pub struct RunCommandAction {
     #[serde(default)]
     pub hold_on_start: bool,
     #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub rerun_on_change: Vec<String>, // glob patterns, relative to cwd
+    #[serde(default)]
+    pub kill_on_change: bool,
+    #[serde(default)]
     pub originating_plugin: Option<OriginatingPlugin>,
 }
 
@@ -104,6 +123,9 @@ impl From<RunCommandAction> for RunCommand {
             cwd: action.cwd,
             hold_on_close: action.hold_on_close,
             hold_on_start: action.hold_on_start,
+            restart_policy: action.restart_policy,
+            rerun_on_change: action.rerun_on_change,
+            kill_on_change: action.kill_on_change,
             originating_plugin: action.originating_plugin,
         }
     }
@@ -118,11 +140,100 @@ impl From<RunCommand> for RunCommandAction {
             direction: None,
             hold_on_close: run_command.hold_on_close,
             hold_on_start: run_command.hold_on_start,
+            restart_policy: run_command.restart_policy,
+            rerun_on_change: run_command.rerun_on_change,
+            kill_on_change: run_command.kill_on_change,
             originating_plugin: run_command.originating_plugin,
         }
     }
 }
 
+/// When a command pane should be restarted after its command exits
+#[derive(Clone, Copy, Debug, Deserialize, Default, Serialize, PartialEq, Eq)]
+pub enum RestartCondition {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl FromStr for RestartCondition {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" | "Never" => Ok(RestartCondition::Never),
+            "on-failure" | "on_failure" | "OnFailure" => Ok(RestartCondition::OnFailure),
+            "always" | "Always" => Ok(RestartCondition::Always),
+            _ => Err(format!(
+                "Failed to parse restart policy: {}, expected one of: never, on-failure, always",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for RestartCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestartCondition::Never => write!(f, "never"),
+            RestartCondition::OnFailure => write!(f, "on-failure"),
+            RestartCondition::Always => write!(f, "always"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Default, Serialize, PartialEq, Eq)]
+pub struct RestartPolicy {
+    #[serde(default)]
+    pub condition: RestartCondition,
+    #[serde(default)]
+    pub max_restarts: Option<usize>, // None means unlimited
+}
+
+impl RestartPolicy {
+    pub fn new(condition: RestartCondition, max_restarts: Option<usize>) -> Self {
+        RestartPolicy {
+            condition,
+            max_restarts,
+        }
+    }
+    pub fn is_set(&self) -> bool {
+        self.condition != RestartCondition::Never
+    }
+    pub fn should_restart(&self, exit_status: Option<i32>, restart_count: usize) -> bool {
+        // an exit status of None means the process was killed by a signal, which we consider a
+        // failure
+        let condition_matches = match self.condition {
+            RestartCondition::Never => false,
+            RestartCondition::OnFailure => exit_status != Some(0),
+            RestartCondition::Always => true,
+        };
+        let below_max_restarts = self
+            .max_restarts
+            .map(|max_restarts| restart_count < max_restarts)
+            .unwrap_or(true);
+        condition_matches && below_max_restarts
+    }
+    /// The time to wait before the next restart, doubling with each consecutive restart up to a
+    /// maximum
+    pub fn backoff(restart_count: usize) -> Duration {
+        let exponent = restart_count.min(16) as u32;
+        RESTART_INITIAL_BACKOFF
+            .saturating_mul(2_u32.saturating_pow(exponent))
+            .min(RESTART_MAX_BACKOFF)
+    }
+    /// The restart count to continue from once a command that was restarted `restart_count`
+    /// times exits after running for `uptime`, so that occasional crashes of a long running
+    /// command neither exhaust `max_restarts` nor keep growing the backoff
+    pub fn restart_count_after(restart_count: usize, uptime: Duration) -> usize {
+        if uptime >= RESTART_STABLE_UPTIME {
+            0
+        } else {
+            restart_count
+        }
+    }
+}
+
 impl RunCommand {
     pub fn new(command: PathBuf) -> Self {
         RunCommand {
@@ -134,4 +245,52 @@ impl RunCommand {
         self.cwd = Some(cwd);
         self
     }
+    /// Whether the pty thread should decide what happens to this command's pane once it exits,
+    /// rather than simply holding or closing it
+    pub fn is_supervised(&self) -> bool {
+        self.restart_policy.is_set() || !self.rerun_on_change.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod restart_policy_tests {
+    use super::*;
+
+    #[test]
+    fn on_failure_restarts_only_failed_commands() {
+        let policy = RestartPolicy::new(RestartCondition::OnFailure, None);
+        assert!(!policy.should_restart(Some(0), 0));
+        assert!(policy.should_restart(Some(1), 0));
+        assert!(policy.should_restart(None, 0), "killed by a signal");
+    }
+
+    #[test]
+    fn always_restarts_until_max_restarts() {
+        let policy = RestartPolicy::new(RestartCondition::Always, Some(2));
+        assert!(policy.should_restart(Some(0), 0));
+        assert!(policy.should_restart(Some(1), 1));
+        assert!(!policy.should_restart(Some(1), 2));
+        assert!(!RestartPolicy::default().should_restart(Some(1), 0));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(RestartPolicy::backoff(0), Duration::from_secs(1));
+        assert_eq!(RestartPolicy::backoff(1), Duration::from_secs(2));
+        assert_eq!(RestartPolicy::backoff(5), Duration::from_secs(32));
+        assert_eq!(RestartPolicy::backoff(6), RESTART_MAX_BACKOFF);
+        assert_eq!(RestartPolicy::backoff(usize::MAX), RESTART_MAX_BACKOFF);
+    }
+
+    #[test]
+    fn restart_count_is_reset_after_a_stable_uptime() {
+        assert_eq!(
+            RestartPolicy::restart_count_after(3, Duration::from_secs(5)),
+            3
+        );
+        assert_eq!(
+            RestartPolicy::restart_count_after(3, RESTART_STABLE_UPTIME),
+            0
+        );
+    }
 }
@@ -1,14 +1,19 @@
 use crate::data::Palette;
+use globset::GlobBuilder;
+use kdl::KdlDocument;
 use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 use std::convert::TryFrom;
 
 use super::keybinds::Keybinds;
+use super::macros::Macros;
+use super::mouse::MouseBindings;
 use super::options::Options;
 use super::plugins::{PluginAliases, PluginsConfigError};
 use super::theme::{Themes, UiConfig};
@@ -17,6 +22,8 @@ use crate::envs::EnvironmentVariables;
 use crate::{home, setup};
 
 const DEFAULT_CONFIG_FILE_NAME: &str = "config.kdl";
+const INCLUDE_NODE_NAME: &str = "include";
+const MACROS_FILE_NAME: &str = "macros.kdl";
 
 type ConfigResult = Result<Config, ConfigError>;
 
@@ -29,6 +36,8 @@ pub struct Config {
     pub plugins: PluginAliases,
     pub ui: UiConfig,
     pub env: EnvironmentVariables,
+    pub macros: Macros,
+    pub mouse_bindings: MouseBindings,
 }
 
 #[derive(Error, Debug)]
@@ -126,20 +135,25 @@ impl ConfigError {
 pub enum ConversionError {
     #[error("{0}")]
     UnknownInputMode(String),
+    #[error("{0}")]
+    InvalidTheme(String),
 }
 
 impl TryFrom<&CliArgs> for Config {
     type Error = ConfigError;
 
     fn try_from(opts: &CliArgs) -> ConfigResult {
+        let default_config = Config::from_default_assets()?;
+        // the layouts that keybindings open are loaded with the variables of the session
+        let layout_vars: BTreeMap<String, String> = opts.layout_vars.iter().cloned().collect();
+
         if let Some(ref path) = opts.config {
-            let default_config = Config::from_default_assets()?;
-            return Config::from_path(path, Some(default_config));
+            return Config::from_path_with_layout_vars(path, Some(default_config), &layout_vars);
         }
 
         if let Some(Command::Setup(ref setup)) = opts.command {
             if setup.clean {
-                return Config::from_default_assets();
+                return Ok(default_config);
             }
         }
 
@@ -151,13 +165,12 @@ impl TryFrom<&CliArgs> for Config {
         if let Some(ref config) = config_dir {
             let path = config.join(DEFAULT_CONFIG_FILE_NAME);
             if path.exists() {
-                let default_config = Config::from_default_assets()?;
-                Config::from_path(&path, Some(default_config))
+                Config::from_path_with_layout_vars(&path, Some(default_config), &layout_vars)
             } else {
-                Config::from_default_assets()
+                Ok(default_config)
             }
         } else {
-            Config::from_default_assets()
+            Ok(default_config)
         }
     }
 }
@@ -181,12 +194,37 @@ impl Config {
         }
     }
     pub fn from_path(path: &PathBuf, default_config: Option<Config>) -> ConfigResult {
+        Config::from_path_with_layout_vars(path, default_config, &BTreeMap::new())
+    }
+    pub fn from_path_with_layout_vars(
+        path: &PathBuf,
+        default_config: Option<Config>,
+        layout_vars: &BTreeMap<String, String>,
+    ) -> ConfigResult {
+        Config::from_path_with_includes(path, default_config, layout_vars, &mut vec![])
+    }
+    fn from_path_with_includes(
+        path: &PathBuf,
+        default_config: Option<Config>,
+        layout_vars: &BTreeMap<String, String>,
+        include_chain: &mut Vec<PathBuf>,
+    ) -> ConfigResult {
         match File::open(path) {
             Ok(mut file) => {
                 let mut kdl_config = String::new();
                 file.read_to_string(&mut kdl_config)
                     .map_err(|e| ConfigError::IoPath(e, path.to_path_buf()))?;
-                match Config::from_kdl(&kdl_config, default_config) {
+                let config = Config::apply_includes(
+                    &kdl_config,
+                    path,
+                    default_config,
+                    layout_vars,
+                    include_chain,
+                )
+                .and_then(|base_config| {
+                    Config::from_kdl_with_layout_vars(&kdl_config, base_config, layout_vars)
+                });
+                match config {
                     Ok(config) => Ok(config),
                     Err(ConfigError::KdlDeserializationError(kdl_error)) => {
                         let error_message = match kdl_error.kind {
@@ -213,6 +251,10 @@ impl Config {
                         };
                         Err(ConfigError::KdlError(kdl_error))
                     },
+                    Err(ConfigError::KdlError(kdl_error)) if kdl_error.src.is_some() => {
+                        // this error happened in an included file and already points to it
+                        Err(ConfigError::KdlError(kdl_error))
+                    },
                     Err(ConfigError::KdlError(kdl_error)) => {
                         Err(ConfigError::KdlError(kdl_error.add_src(
                             path.as_path().as_os_str().to_string_lossy().to_string(),
@@ -225,6 +267,63 @@ impl Config {
             Err(e) => Err(ConfigError::IoPath(e, path.into())),
         }
     }
+    /// Included files are applied in the order they are listed (the matches of a glob in
+    /// alphabetical order), each one on top of the previous one. The including file is applied
+    /// last, so its own settings take precedence over everything it includes.
+    fn apply_includes(
+        kdl_config: &str,
+        path: &Path,
+        mut base_config: Option<Config>,
+        layout_vars: &BTreeMap<String, String>,
+        include_chain: &mut Vec<PathBuf>,
+    ) -> Result<Option<Config>, ConfigError> {
+        let kdl_document: KdlDocument = kdl_config.parse()?;
+        let include_dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        include_chain.push(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        for include_node in kdl_document
+            .nodes()
+            .iter()
+            .filter(|n| n.name().value() == INCLUDE_NODE_NAME)
+        {
+            if include_node.entries().is_empty() {
+                return Err(ConfigError::new_kdl_error(
+                    "Expected the path or glob of the file(s) to include".into(),
+                    include_node.span().offset(),
+                    include_node.span().len(),
+                ));
+            }
+            for entry in include_node.entries() {
+                let included_paths = entry
+                    .value()
+                    .as_string()
+                    .ok_or_else(|| "Included files must be given as strings".to_owned())
+                    .and_then(|pattern| expand_include_pattern(&include_dir, pattern))
+                    .map_err(|e| {
+                        ConfigError::new_kdl_error(e, entry.span().offset(), entry.span().len())
+                    })?;
+                for included_path in included_paths {
+                    let canonical_path = included_path
+                        .canonicalize()
+                        .unwrap_or_else(|_| included_path.clone());
+                    if include_chain.contains(&canonical_path) {
+                        return Err(ConfigError::new_kdl_error(
+                            format!("Circular include of {}", included_path.display()),
+                            entry.span().offset(),
+                            entry.span().len(),
+                        ));
+                    }
+                    base_config = Some(Config::from_path_with_includes(
+                        &included_path,
+                        base_config,
+                        layout_vars,
+                        include_chain,
+                    )?);
+                }
+            }
+        }
+        include_chain.pop();
+        Ok(base_config)
+    }
     pub fn merge(&mut self, other: Config) -> Result<(), ConfigError> {
         self.options = self.options.merge(other.options);
         self.keybinds.merge(other.keybinds.clone());
@@ -232,6 +331,8 @@ impl Config {
         self.plugins.merge(other.plugins);
         self.ui = self.ui.merge(other.ui);
         self.env = self.env.merge(other.env);
+        self.macros.merge(other.macros);
+        self.mouse_bindings.merge(other.mouse_bindings);
         Ok(())
     }
     pub fn config_file_path(opts: &CliArgs) -> Option<PathBuf> {
@@ -284,6 +385,33 @@ impl Config {
                 }
             })
     }
+    pub fn macros_file_path(opts: &CliArgs) -> Option<PathBuf> {
+        opts.config_dir
+            .clone()
+            .or_else(home::find_default_config_dir)
+            .map(|config_dir| config_dir.join(MACROS_FILE_NAME))
+    }
+    /// Saves recorded macros next to the config file, replacing macros of the same name that were
+    /// saved there before. The file can be loaded with `include "macros.kdl"` in the config.
+    pub fn write_macros_to_disk(macros: String, opts: &CliArgs) -> Result<PathBuf, ConfigError> {
+        let macros_file_path = Config::macros_file_path(opts)
+            .ok_or_else(|| ConfigError::Std("Config directory not found".into()))?;
+        let mut saved_macros = if macros_file_path.exists() {
+            let saved_macros = std::fs::read_to_string(&macros_file_path)
+                .map_err(|e| ConfigError::IoPath(e, macros_file_path.clone()))?;
+            Config::from_kdl(&saved_macros, None)?.macros
+        } else {
+            Macros::default()
+        };
+        saved_macros.merge(Config::from_kdl(&macros, None)?.macros);
+        let macros = saved_macros
+            .to_kdl()
+            .map(|macros| macros.to_string())
+            .unwrap_or_default();
+        std::fs::write(&macros_file_path, macros.as_bytes())
+            .map_err(|e| ConfigError::IoPath(e, macros_file_path.clone()))?;
+        Ok(macros_file_path)
+    }
     // returns true if the config was not previouly written to disk and we successfully wrote it
     pub fn write_config_to_disk_if_it_does_not_exist(config: String, opts: &CliArgs) -> bool {
         match Config::config_file_path(opts) {
@@ -394,14 +522,57 @@ impl Config {
     }
 }
 
+fn expand_include_pattern(include_dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, String> {
+    let is_glob_component = |c: &str| c.contains(|c| matches!(c, '*' | '?' | '[' | '{'));
+    let pattern_path = include_dir.join(shellexpand::tilde(pattern).as_ref());
+    if !is_glob_component(pattern) {
+        return if pattern_path.is_file() {
+            Ok(vec![pattern_path])
+        } else {
+            Err(format!(
+                "Included file not found: {}",
+                pattern_path.display()
+            ))
+        };
+    }
+    let glob = GlobBuilder::new(&pattern_path.to_string_lossy())
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("Invalid include glob: {}", e))?
+        .compile_matcher();
+    // only walk the part of the tree the glob can match
+    let walk_root: PathBuf = pattern_path
+        .components()
+        .take_while(|c| !is_glob_component(&c.as_os_str().to_string_lossy()))
+        .collect();
+    let mut dirs_to_walk = vec![walk_root];
+    let mut included_paths = vec![];
+    while let Some(dir) = dirs_to_walk.pop() {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let entry_path = entry.path();
+            if entry.file_type().map(|f| f.is_dir()).unwrap_or(false) {
+                dirs_to_walk.push(entry_path);
+            } else if entry_path.is_file() && glob.is_match(&entry_path) {
+                included_paths.push(entry_path);
+            }
+        }
+    }
+    included_paths.sort();
+    Ok(included_paths)
+}
+
 #[cfg(test)]
 mod config_test {
     use super::*;
-    use crate::data::{InputMode, Palette, PaletteColor, PluginTag};
+    use crate::data::{BareKey, InputMode, KeyWithModifier, Palette, PaletteColor, PluginTag};
+    use crate::input::actions::Action;
     use crate::input::layout::{RunPlugin, RunPluginLocation};
+    use crate::input::macros::Macro;
+    use crate::input::mouse::{MouseButton, MouseButtonWithModifier, MouseTarget};
     use crate::input::options::{Clipboard, OnForceClose};
     use crate::input::plugins::{PluginConfig, PluginType};
     use crate::input::theme::{FrameConfig, Theme, Themes, UiConfig};
+    use crate::position::Position;
     use std::collections::{BTreeMap, HashMap};
     use std::io::Write;
     use tempfile::tempdir;
@@ -463,11 +634,117 @@ mod config_test {
         assert_eq!(result.unwrap(), Config::from_default_assets().unwrap());
     }
 
+    #[test]
+    fn keybindings_open_layouts_with_the_session_layout_vars() {
+        let tmp = tempdir().unwrap();
+        let layout_path = tmp.path().join("project.kdl");
+        std::fs::write(
+            &layout_path,
+            "vars { project \"default\"; }\nlayout { tab name=\"${project}\" { pane; }; }",
+        )
+        .unwrap();
+        let config_path = tmp.path().join(DEFAULT_CONFIG_FILE_NAME);
+        std::fs::write(
+            &config_path,
+            format!(
+                r#"
+                keybinds {{
+                    normal {{
+                        bind "Alt t" {{ NewTab {{ layout "{}"; }}; }}
+                    }}
+                }}
+            "#,
+                layout_path.display()
+            ),
+        )
+        .unwrap();
+        let opts = CliArgs {
+            config: Some(config_path),
+            layout_vars: vec![("project".to_owned(), "api".to_owned())],
+            ..Default::default()
+        };
+        let config = Config::try_from(&opts).unwrap();
+        let new_tab_name = config
+            .keybinds
+            .get_actions_for_key_in_mode(
+                &InputMode::Normal,
+                &KeyWithModifier::new(BareKey::Char('t')).with_alt_modifier(),
+            )
+            .and_then(|actions| match actions.first() {
+                Some(Action::NewTab(_, _, _, _, name)) => name.clone(),
+                _ => None,
+            });
+        assert_eq!(new_tab_name, Some("api".to_owned()));
+    }
+
+    #[test]
+    fn included_files_are_merged_before_the_including_file() {
+        let tmp = tempdir().unwrap();
+        std::fs::create_dir(tmp.path().join("fragments")).unwrap();
+        std::fs::write(
+            tmp.path().join("fragments").join("a-options.kdl"),
+            "simplified_ui true\ntheme \"from-a\"\nscroll_buffer_size 100",
+        )
+        .unwrap();
+        std::fs::write(
+            tmp.path().join("fragments").join("b-options.kdl"),
+            "theme \"from-b\"\nscroll_buffer_size 200",
+        )
+        .unwrap();
+        let config_path = tmp.path().join(DEFAULT_CONFIG_FILE_NAME);
+        std::fs::write(
+            &config_path,
+            "include \"fragments/*.kdl\"\nscroll_buffer_size 300",
+        )
+        .unwrap();
+        let config = Config::from_path(&config_path, None).unwrap();
+        assert_eq!(config.options.simplified_ui, Some(true));
+        assert_eq!(
+            config.options.theme,
+            Some("from-b".to_owned()),
+            "later includes override earlier ones"
+        );
+        assert_eq!(
+            config.options.scroll_buffer_size,
+            Some(300),
+            "including file overrides its includes"
+        );
+    }
+
+    #[test]
+    fn errors_in_included_files_point_to_the_included_file() {
+        let tmp = tempdir().unwrap();
+        let included_path = tmp.path().join("keybinds-team.kdl");
+        std::fs::write(&included_path, "default_mode \"no-such-mode\"").unwrap();
+        let config_path = tmp.path().join(DEFAULT_CONFIG_FILE_NAME);
+        std::fs::write(&config_path, "include \"keybinds-team.kdl\"").unwrap();
+        match Config::from_path(&config_path, None) {
+            Err(ConfigError::KdlError(kdl_error)) => {
+                assert_eq!(
+                    kdl_error.src.map(|s| s.name().to_owned()),
+                    Some(included_path.display().to_string())
+                );
+            },
+            other => panic!("expected a kdl error, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cannot_include_files_circularly() {
+        let tmp = tempdir().unwrap();
+        std::fs::write(tmp.path().join("other.kdl"), "include \"config.kdl\"").unwrap();
+        let config_path = tmp.path().join(DEFAULT_CONFIG_FILE_NAME);
+        std::fs::write(&config_path, "include \"other.kdl\"").unwrap();
+        assert!(Config::from_path(&config_path, None).is_err());
+    }
+
     #[test]
     fn can_define_options_in_configfile() {
         let config_contents = r#"
             simplified_ui true
             theme "my cool theme"
+            theme_dark "my cool dark theme"
+            theme_light "my cool light theme"
             default_mode "locked"
             default_shell "/path/to/my/shell"
             default_cwd "/path"
@@ -497,6 +774,16 @@ mod config_test {
             Some(String::from("my cool theme")),
             "Option set in config"
         );
+        assert_eq!(
+            config.options.theme_dark,
+            Some(String::from("my cool dark theme")),
+            "Option set in config"
+        );
+        assert_eq!(
+            config.options.theme_light,
+            Some(String::from("my cool light theme")),
+            "Option set in config"
+        );
         assert_eq!(
             config.options.default_mode,
             Some(InputMode::Locked),
@@ -629,6 +916,56 @@ mod config_test {
         assert_eq!(config.themes, expected_themes, "Theme defined in config");
     }
 
+    #[test]
+    fn can_define_ui_color_roles_in_themes() {
+        let config_contents = r##"
+            themes {
+                dracula {
+                    fg 248 248 242
+                    bg 40 42 54
+                    red 255 85 85
+                    green 80 250 123
+                    yellow 241 250 140
+                    blue 98 114 164
+                    magenta 255 121 198
+                    orange 255 184 108
+                    cyan 139 233 253
+                    black 0 0 0
+                    white 255 255 255
+                    frame_unfocused 98 114 164
+                    selection_bg "#44475a"
+                    tab_active_bg 189 147 249
+                }
+            }
+        "##;
+        let config = Config::from_kdl(config_contents, None).unwrap();
+        let palette = config.themes.get_theme("dracula").unwrap().palette;
+        assert_eq!(
+            palette.frame_unfocused(),
+            Some(PaletteColor::Rgb((98, 114, 164))),
+            "role defined in theme"
+        );
+        assert_eq!(
+            palette.frame_other_client(),
+            Some(PaletteColor::Rgb((98, 114, 164))),
+            "other client frames fall back to unfocused frames"
+        );
+        assert_eq!(palette.selection_bg(), PaletteColor::Rgb((68, 71, 90)));
+        assert_eq!(palette.tab_active_bg(), PaletteColor::Rgb((189, 147, 249)));
+        assert_eq!(
+            palette.frame_focused(),
+            palette.green,
+            "roles not defined in the theme fall back to the palette"
+        );
+        assert_eq!(palette.exit_code_error(), palette.red);
+        let serialized = config.themes.to_kdl().unwrap().to_string();
+        let reparsed = Config::from_kdl(&format!("{}", serialized), None).unwrap();
+        assert_eq!(
+            config.themes, reparsed.themes,
+            "ui color roles survive serialization"
+        );
+    }
+
     #[test]
     fn can_define_multiple_themes_including_hex_themes_in_configfile() {
         let config_contents = r##"
@@ -846,4 +1183,167 @@ mod config_test {
             "Env variables defined in config"
         );
     }
+
+    #[test]
+    fn can_define_macros_in_config_file() {
+        let config_contents = r#"
+            keybinds {
+                normal {
+                    bind "Alt q" { StartMacroRecording "q"; }
+                    bind "Alt Q" { StopMacroRecording; }
+                    bind "Alt @" { PlayMacro "q"; }
+                }
+            }
+            macros {
+                q "Ctrl p" "d" start_mode="normal"
+            }
+        "#;
+        let config = Config::from_kdl(config_contents, None).unwrap();
+        let expected_macro = Macro {
+            start_mode: Some(InputMode::Normal),
+            keys: vec![
+                KeyWithModifier::new(BareKey::Char('p')).with_ctrl_modifier(),
+                KeyWithModifier::new(BareKey::Char('d')),
+            ],
+        };
+        assert_eq!(
+            config.macros.get("q"),
+            Some(&expected_macro),
+            "Macro defined in config"
+        );
+        assert_eq!(
+            config.keybinds.get_actions_for_key_in_mode(
+                &InputMode::Normal,
+                &KeyWithModifier::new(BareKey::Char('@')).with_alt_modifier()
+            ),
+            Some(&vec![Action::PlayMacro("q".into())]),
+            "Macro bound to a key"
+        );
+    }
+
+    #[test]
+    fn writing_macros_to_disk_keeps_previously_saved_macros() {
+        let tmp = tempdir().unwrap();
+        let mut opts = CliArgs::default();
+        opts.config_dir = Some(tmp.path().to_path_buf());
+        std::fs::write(
+            tmp.path().join(MACROS_FILE_NAME),
+            "macros {\n    a \"x\"\n    b \"y\"\n}\n",
+        )
+        .unwrap();
+        let macros_file_path =
+            Config::write_macros_to_disk("macros { b \"z\" start_mode=\"locked\"; }".into(), &opts)
+                .unwrap();
+        let written_macros = std::fs::read_to_string(macros_file_path).unwrap();
+        let config = Config::from_kdl(&written_macros, None).unwrap();
+        assert_eq!(
+            config.macros.get("a").map(|m| m.keys.clone()),
+            Some(vec![KeyWithModifier::new(BareKey::Char('x'))]),
+            "Previously saved macro kept"
+        );
+        assert_eq!(
+            config.macros.get("b"),
+            Some(&Macro {
+                start_mode: Some(InputMode::Locked),
+                keys: vec![KeyWithModifier::new(BareKey::Char('z'))],
+            }),
+            "Macro of the same name replaced"
+        );
+    }
+
+    #[test]
+    fn can_define_mouse_bindings_in_config_file() {
+        let config_contents = r#"
+            mouse {
+                bind "Ctrl LeftClick" { NewTab; }
+                bind "RightClick" target="pane_frame" { CloseFocus; }
+                bind "RightClick" { TogglePaneFrames; }
+                bind "MiddleClick" { Detach; }
+                unbind "MiddleClick"
+            }
+        "#;
+        let config = Config::from_kdl(config_contents, None).unwrap();
+        let ctrl_left_click = MouseButtonWithModifier::new(MouseButton::Left).with_ctrl_modifier();
+        let right_click = MouseButtonWithModifier::new(MouseButton::Right);
+        assert_eq!(
+            config.mouse_bindings.get_actions(&ctrl_left_click, None),
+            Some(&vec![Action::NewTab(None, vec![], None, None, None)]),
+            "Mouse button with modifier bound"
+        );
+        assert_eq!(
+            config
+                .mouse_bindings
+                .get_actions(&right_click, Some(MouseTarget::PaneFrame)),
+            Some(&vec![Action::CloseFocus]),
+            "Binding with a matching target preferred"
+        );
+        assert_eq!(
+            config
+                .mouse_bindings
+                .get_actions(&right_click, Some(MouseTarget::PaneBody)),
+            Some(&vec![Action::TogglePaneFrames]),
+            "Binding without a target used for other targets"
+        );
+        assert!(
+            !config
+                .mouse_bindings
+                .is_bound(&MouseButtonWithModifier::new(MouseButton::Middle)),
+            "Unbound mouse button"
+        );
+        assert!(
+            !config
+                .mouse_bindings
+                .is_bound(&MouseButtonWithModifier::new(MouseButton::Left)),
+            "Modifiers are part of the binding"
+        );
+    }
+
+    #[test]
+    fn mouse_bindings_are_placed_at_the_mouse_position() {
+        let config_contents = r#"
+            mouse {
+                bind "MiddleClick" { FocusPaneAtMouse; Paste; }
+                bind "Ctrl LeftClick" { OpenHyperlinkAtMouse; }
+            }
+        "#;
+        let config = Config::from_kdl(config_contents, None).unwrap();
+        let position = Position::new(3, 10);
+        assert_eq!(
+            config.mouse_bindings.actions_for_press(
+                &MouseButtonWithModifier::new(MouseButton::Middle),
+                None,
+                position
+            ),
+            vec![Action::FocusPaneAt(position), Action::Paste],
+        );
+        assert_eq!(
+            config.mouse_bindings.actions_for_press(
+                &MouseButtonWithModifier::new(MouseButton::Left).with_ctrl_modifier(),
+                None,
+                position
+            ),
+            vec![Action::OpenHyperlinkAt(position)],
+        );
+        assert_eq!(
+            config.mouse_bindings.actions_for_press(
+                &MouseButtonWithModifier::new(MouseButton::Left),
+                None,
+                position
+            ),
+            vec![Action::LeftClick(position)],
+            "Unbound buttons do what they do by default"
+        );
+    }
+
+    #[test]
+    fn cannot_bind_keys_to_actions_at_the_mouse_position() {
+        let config_contents = r#"
+            keybinds {
+                normal {
+                    bind "Ctrl o" { OpenHyperlinkAtMouse; }
+                }
+            }
+        "#;
+        assert!(Config::from_kdl(config_contents, None).is_err());
+    }
 }
@@ -0,0 +1,212 @@
+//! Discovery of directory-local layouts (`.zellij/layout.kdl` or `.zellij.kdl`) and the record
+//! of which of them the user has trusted.
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use kdl::{KdlDocument, KdlNode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::input::config::{Config, ConfigError};
+use crate::input::layout::Layout;
+
+/// Looked for in this order in every directory
+pub const DIR_LOCAL_LAYOUT_FILES: [&str; 2] = [".zellij/layout.kdl", ".zellij.kdl"];
+pub const DEFAULT_DIR_LOCAL_LAYOUTS_SEARCH_DEPTH: usize = 10;
+pub const TRUSTED_LAYOUTS_FILE_NAME: &str = "trusted_layouts.kdl";
+
+/// Looks for a directory-local layout in `cwd` and then in up to `search_depth` of its parents
+pub fn find_dir_local_layout(cwd: &Path, search_depth: usize) -> Option<PathBuf> {
+    cwd.ancestors()
+        .take(search_depth.saturating_add(1))
+        .flat_map(|dir| DIR_LOCAL_LAYOUT_FILES.iter().map(move |f| dir.join(f)))
+        .find(|candidate| candidate.is_file())
+}
+
+pub fn hash_layout_contents(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+/// A directory-local layout hashed and parsed from a single read of its file, so that the layout
+/// which is trusted is also the one that runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirLocalLayout {
+    pub path: PathBuf,
+    pub sha256: String,
+    pub layout: Layout,
+    /// Whether an earlier version of this layout was trusted
+    pub changed: bool,
+}
+
+impl DirLocalLayout {
+    /// Returns the layout along with `config` merged with the configuration in the layout file
+    pub fn read(
+        path: PathBuf,
+        config: Config,
+        layout_vars: BTreeMap<String, String>,
+        trusted_layouts: &TrustedLayouts,
+    ) -> Result<(DirLocalLayout, Config), ConfigError> {
+        let contents = fs::read(&path).map_err(|e| ConfigError::IoPath(e, path.clone()))?;
+        let sha256 = hash_layout_contents(&contents);
+        let raw_layout = String::from_utf8(contents)?;
+        let layout = Layout::from_kdl_with_vars(
+            &raw_layout,
+            Some(path.display().to_string()),
+            None,
+            None,
+            layout_vars.clone(),
+        )?;
+        let raw_layout = Layout::substitute_vars(&raw_layout, layout_vars.clone())?;
+        let config = Config::from_kdl_with_layout_vars(&raw_layout, Some(config), &layout_vars)?;
+        let changed = trusted_layouts.was_trusted(&path);
+        Ok((
+            DirLocalLayout {
+                path,
+                sha256,
+                layout,
+                changed,
+            },
+            config,
+        ))
+    }
+    pub fn is_trusted(&self, trusted_layouts: &TrustedLayouts) -> bool {
+        trusted_layouts.is_trusted(&self.path, &self.sha256)
+    }
+}
+
+/// layout path => sha256 of the layout contents when it was trusted
+pub type TrustedLayoutHashes = BTreeMap<PathBuf, String>;
+
+#[derive(Default, Debug)]
+pub struct TrustedLayouts {
+    path: PathBuf,
+    trusted: TrustedLayoutHashes,
+}
+
+impl TrustedLayouts {
+    pub fn from_data_dir(data_dir: &Path) -> Self {
+        let path = data_dir.join(TRUSTED_LAYOUTS_FILE_NAME);
+        let trusted = match fs::read_to_string(&path) {
+            Ok(raw_string) => TrustedLayouts::from_string(raw_string).unwrap_or_default(),
+            Err(_) => TrustedLayoutHashes::default(), // nothing was trusted yet
+        };
+        TrustedLayouts { path, trusted }
+    }
+
+    pub fn is_trusted(&self, layout_path: &Path, hash: &str) -> bool {
+        self.trusted
+            .get(layout_path)
+            .map(|trusted_hash| trusted_hash == hash)
+            .unwrap_or(false)
+    }
+
+    /// Whether any version of this layout was trusted before, used to tell the user it changed
+    pub fn was_trusted(&self, layout_path: &Path) -> bool {
+        self.trusted.contains_key(layout_path)
+    }
+
+    pub fn trust(&mut self, layout_path: PathBuf, hash: String) {
+        self.trusted.insert(layout_path, hash);
+    }
+
+    pub fn write_to_file(&self) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut f = File::create(&self.path)?;
+        write!(f, "{}", TrustedLayouts::to_string(&self.trusted))?;
+        Ok(())
+    }
+
+    pub fn from_string(raw_string: String) -> Result<TrustedLayoutHashes, ConfigError> {
+        let kdl_document: KdlDocument = raw_string.parse()?;
+        let mut trusted = TrustedLayoutHashes::default();
+        for node in kdl_document.nodes() {
+            let layout_path = node.get(0).and_then(|e| e.value().as_string());
+            let hash = node.get("sha256").and_then(|e| e.value().as_string());
+            if let (Some(layout_path), Some(hash)) = (layout_path, hash) {
+                trusted.insert(PathBuf::from(layout_path), hash.to_owned());
+            }
+        }
+        Ok(trusted)
+    }
+
+    pub fn to_string(trusted: &TrustedLayoutHashes) -> String {
+        let mut kdl_document = KdlDocument::new();
+        for (layout_path, hash) in trusted {
+            let mut node = KdlNode::new("layout");
+            node.push(layout_path.display().to_string());
+            node.push(("sha256", hash.as_str()));
+            kdl_document.nodes_mut().push(node);
+        }
+        kdl_document.fmt();
+        kdl_document.to_string()
+    }
+}
+
+#[cfg(test)]
+mod dir_local_layout_tests {
+    use super::*;
+
+    #[test]
+    fn finds_layout_in_parent_directory_within_search_depth() {
+        let root = tempfile::tempdir().unwrap();
+        let nested = root.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir_all(root.path().join(".zellij")).unwrap();
+        fs::write(root.path().join(".zellij").join("layout.kdl"), "layout {}").unwrap();
+        fs::write(root.path().join(".zellij.kdl"), "layout {}").unwrap();
+        assert_eq!(
+            find_dir_local_layout(&nested, 2),
+            Some(root.path().join(".zellij").join("layout.kdl")),
+            "prefers .zellij/layout.kdl over .zellij.kdl"
+        );
+        assert_eq!(find_dir_local_layout(&nested, 1), None);
+    }
+
+    #[test]
+    fn changed_layout_is_no_longer_trusted() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let layout_path = PathBuf::from("/some/project/.zellij.kdl");
+        let original_hash = hash_layout_contents(b"layout { pane; }");
+        let mut trusted_layouts = TrustedLayouts::from_data_dir(data_dir.path());
+        trusted_layouts.trust(layout_path.clone(), original_hash.clone());
+        trusted_layouts.write_to_file().unwrap();
+
+        let trusted_layouts = TrustedLayouts::from_data_dir(data_dir.path());
+        let changed_hash = hash_layout_contents(b"layout { pane command=\"rm\"; }");
+        assert!(trusted_layouts.is_trusted(&layout_path, &original_hash));
+        assert!(!trusted_layouts.is_trusted(&layout_path, &changed_hash));
+        assert!(trusted_layouts.was_trusted(&layout_path));
+    }
+
+    #[test]
+    fn dir_local_layout_is_hashed_from_the_contents_it_was_parsed_from() {
+        let root = tempfile::tempdir().unwrap();
+        let data_dir = tempfile::tempdir().unwrap();
+        let layout_path = root.path().join(".zellij.kdl");
+        let contents = "layout { tab name=\"project\" { pane; }; }";
+        fs::write(&layout_path, contents).unwrap();
+        let mut trusted_layouts = TrustedLayouts::from_data_dir(data_dir.path());
+        let (dir_local_layout, _config) = DirLocalLayout::read(
+            layout_path.clone(),
+            Config::default(),
+            Default::default(),
+            &trusted_layouts,
+        )
+        .unwrap();
+        assert_eq!(
+            dir_local_layout.sha256,
+            hash_layout_contents(contents.as_bytes())
+        );
+        assert_eq!(dir_local_layout.layout.tabs().len(), 1);
+        assert!(!dir_local_layout.changed);
+        assert!(!dir_local_layout.is_trusted(&trusted_layouts));
+        trusted_layouts.trust(layout_path, dir_local_layout.sha256.clone());
+        assert!(dir_local_layout.is_trusted(&trusted_layouts));
+    }
+}
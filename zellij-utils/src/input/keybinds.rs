@@ -1,26 +1,211 @@
 use std::collections::{BTreeMap, HashMap};
 
 use super::actions::Action;
-use crate::data::{InputMode, KeyWithModifier, KeybindsVec};
+use crate::data::{BareKey, InputMode, KeyWithModifier, KeybindsVec};
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Used in the config struct
 #[derive(Clone, PartialEq, Deserialize, Serialize, Default)]
-pub struct Keybinds(pub HashMap<InputMode, HashMap<KeyWithModifier, Vec<Action>>>);
+pub struct Keybinds {
+    pub single: HashMap<InputMode, HashMap<KeyWithModifier, Vec<Action>>>,
+    /// Multi-key sequences (eg. `Ctrl a` followed by `c`), which are matched against the keys a
+    /// client typed before falling back to the single-key bindings
+    pub sequences: HashMap<InputMode, HashMap<Vec<KeyWithModifier>, Vec<Action>>>,
+    /// Keybindings that only apply when a [`KeybindCondition`] on the focused pane holds, in the
+    /// order they were bound. They take precedence over the single-key bindings.
+    pub conditional: HashMap<InputMode, HashMap<KeyWithModifier, ConditionalKeybinds>>,
+}
+
+/// The conditional keybindings of a key, in the order they were bound
+pub type ConditionalKeybinds = Vec<(KeybindCondition, Vec<Action>)>;
+
+/// Conditions on the focused pane for a keybinding to apply, eg.
+/// `bind "Ctrl h" if_not_running="nvim|vim" { MoveFocus "Left"; }`
+///
+/// Each condition is a regular expression that has to match the whole value (so `vim` does not
+/// match `nvim`), all conditions that are set have to hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct KeybindCondition {
+    pub if_running: Option<ConditionPattern>,
+    pub if_not_running: Option<ConditionPattern>,
+    pub if_pane_title: Option<ConditionPattern>,
+    pub if_not_pane_title: Option<ConditionPattern>,
+    pub if_plugin: Option<ConditionPattern>,
+    pub if_not_plugin: Option<ConditionPattern>,
+}
+
+/// A pattern of a [`KeybindCondition`], compiled once when the config is parsed and compared by
+/// its source text
+#[derive(Debug, Clone)]
+pub struct ConditionPattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl ConditionPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        let regex = Regex::new(&format!("^(?:{})$", pattern))?;
+        Ok(ConditionPattern {
+            pattern: pattern.to_owned(),
+            regex,
+        })
+    }
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+}
+
+impl PartialEq for ConditionPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+impl Eq for ConditionPattern {}
+
+impl PartialOrd for ConditionPattern {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ConditionPattern {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.pattern.cmp(&other.pattern)
+    }
+}
+
+impl std::hash::Hash for ConditionPattern {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.pattern.hash(state);
+    }
+}
+
+impl Serialize for ConditionPattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.pattern)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConditionPattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        ConditionPattern::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+/// What a [`KeybindCondition`] is checked against
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusedPaneInfo {
+    /// The name of the foreground process of a terminal pane
+    pub running_command: Option<String>,
+    pub title: Option<String>,
+    /// The location of a plugin pane
+    pub plugin_url: Option<String>,
+}
+
+impl KeybindCondition {
+    pub const PROPERTY_NAMES: [&'static str; 6] = [
+        "if_running",
+        "if_not_running",
+        "if_pane_title",
+        "if_not_pane_title",
+        "if_plugin",
+        "if_not_plugin",
+    ];
+    pub fn is_empty(&self) -> bool {
+        self == &KeybindCondition::default()
+    }
+    pub fn set(&mut self, property_name: &str, pattern: &str) -> Result<(), String> {
+        let pattern = ConditionPattern::new(pattern)
+            .map_err(|e| format!("Invalid pattern for {}: {}", property_name, e))?;
+        match property_name {
+            "if_running" => self.if_running = Some(pattern),
+            "if_not_running" => self.if_not_running = Some(pattern),
+            "if_pane_title" => self.if_pane_title = Some(pattern),
+            "if_not_pane_title" => self.if_not_pane_title = Some(pattern),
+            "if_plugin" => self.if_plugin = Some(pattern),
+            "if_not_plugin" => self.if_not_plugin = Some(pattern),
+            _ => return Err(format!("Unknown keybind condition: '{}'", property_name)),
+        }
+        Ok(())
+    }
+    pub fn properties(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("if_running", &self.if_running),
+            ("if_not_running", &self.if_not_running),
+            ("if_pane_title", &self.if_pane_title),
+            ("if_not_pane_title", &self.if_not_pane_title),
+            ("if_plugin", &self.if_plugin),
+            ("if_not_plugin", &self.if_not_plugin),
+        ]
+        .into_iter()
+        .filter_map(|(name, pattern)| pattern.as_ref().map(|p| (name, p.as_str())))
+        .collect()
+    }
+    pub fn matches(&self, focused_pane: &FocusedPaneInfo) -> bool {
+        let holds =
+            |pattern: &Option<ConditionPattern>, value: &Option<String>, negated: bool| -> bool {
+                match pattern {
+                    Some(pattern) => {
+                        value.as_ref().map(|v| pattern.is_match(v)).unwrap_or(false) != negated
+                    },
+                    None => true,
+                }
+            };
+        holds(&self.if_running, &focused_pane.running_command, false)
+            && holds(&self.if_not_running, &focused_pane.running_command, true)
+            && holds(&self.if_pane_title, &focused_pane.title, false)
+            && holds(&self.if_not_pane_title, &focused_pane.title, true)
+            && holds(&self.if_plugin, &focused_pane.plugin_url, false)
+            && holds(&self.if_not_plugin, &focused_pane.plugin_url, true)
+    }
+}
+
+/// The result of matching the keys a client typed so far against the bound key sequences
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeySequenceMatch {
+    /// The keys are exactly a bound sequence
+    Complete(Vec<Action>),
+    /// The keys are the beginning of at least one bound sequence
+    Partial,
+    NoMatch,
+}
 
 impl fmt::Debug for Keybinds {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut stable_sorted = BTreeMap::new();
-        for (mode, keybinds) in self.0.iter() {
+        for (mode, keybinds) in self.single.iter() {
             let mut stable_sorted_mode_keybinds = BTreeMap::new();
             for (key, actions) in keybinds {
                 stable_sorted_mode_keybinds.insert(key, actions);
             }
             stable_sorted.insert(mode, stable_sorted_mode_keybinds);
         }
-        write!(f, "{:#?}", stable_sorted)
+        write!(f, "{:#?}", stable_sorted)?;
+        let mut stable_sorted_sequences = BTreeMap::new();
+        for (mode, sequences) in self.sequences.iter().filter(|(_, s)| !s.is_empty()) {
+            let stable_sorted_mode_sequences: BTreeMap<_, _> = sequences.iter().collect();
+            stable_sorted_sequences.insert(mode, stable_sorted_mode_sequences);
+        }
+        if !stable_sorted_sequences.is_empty() {
+            write!(f, "\n{:#?}", stable_sorted_sequences)?;
+        }
+        let mut stable_sorted_conditional = BTreeMap::new();
+        for (mode, keybinds) in self.conditional.iter().filter(|(_, k)| !k.is_empty()) {
+            let stable_sorted_mode_keybinds: BTreeMap<_, _> = keybinds.iter().collect();
+            stable_sorted_conditional.insert(mode, stable_sorted_mode_keybinds);
+        }
+        if !stable_sorted_conditional.is_empty() {
+            write!(f, "\n{:#?}", stable_sorted_conditional)?;
+        }
+        Ok(())
     }
 }
 
@@ -30,7 +215,7 @@ impl Keybinds {
         mode: &InputMode,
         key: &KeyWithModifier,
     ) -> Option<&Vec<Action>> {
-        self.0
+        self.single
             .get(mode)
             .and_then(|normal_mode_keybindings| normal_mode_keybindings.get(key))
     }
@@ -41,7 +226,7 @@ impl Keybinds {
         raw_bytes: Vec<u8>,
         key_is_kitty_protocol: bool,
     ) -> Vec<Action> {
-        self.0
+        self.single
             .get(mode)
             .and_then(|normal_mode_keybindings| normal_mode_keybindings.get(key_with_modifier))
             .cloned()
@@ -58,7 +243,98 @@ impl Keybinds {
         &mut self,
         input_mode: &InputMode,
     ) -> &mut HashMap<KeyWithModifier, Vec<Action>> {
-        self.0.entry(*input_mode).or_insert_with(HashMap::new)
+        self.single.entry(input_mode.clone()).or_default()
+    }
+    pub fn get_conditional_keybinds_for_mode_mut(
+        &mut self,
+        input_mode: &InputMode,
+    ) -> &mut HashMap<KeyWithModifier, ConditionalKeybinds> {
+        self.conditional.entry(input_mode.clone()).or_default()
+    }
+    pub fn has_conditional_keybinds_for_key(
+        &self,
+        mode: &InputMode,
+        key: &KeyWithModifier,
+    ) -> bool {
+        self.conditional
+            .get(mode)
+            .and_then(|conditional_keybinds| conditional_keybinds.get(key))
+            .map(|conditional_keybinds| !conditional_keybinds.is_empty())
+            .unwrap_or(false)
+    }
+    /// The actions of the first conditional keybinding whose condition holds for the focused
+    /// pane. A key with conditional keybindings none of which hold gets the mode's default action
+    /// (eg. it is written to the focused pane), so `bind "Ctrl h" if_not_running="vim"` leaves
+    /// `Ctrl h` to vim.
+    /// Keys without conditional keybindings are the same as
+    /// [`Keybinds::get_actions_for_key_in_mode_or_default_action`]
+    pub fn get_actions_for_key_in_mode_with_focused_pane(
+        &self,
+        mode: &InputMode,
+        key_with_modifier: &KeyWithModifier,
+        raw_bytes: Vec<u8>,
+        key_is_kitty_protocol: bool,
+        focused_pane: &FocusedPaneInfo,
+    ) -> Vec<Action> {
+        match self
+            .conditional
+            .get(mode)
+            .and_then(|conditional_keybinds| conditional_keybinds.get(key_with_modifier))
+            .filter(|conditional_keybinds| !conditional_keybinds.is_empty())
+        {
+            Some(conditional_keybinds) => conditional_keybinds
+                .iter()
+                .find(|(condition, _)| condition.matches(focused_pane))
+                .map(|(_, actions)| actions.clone())
+                .unwrap_or_else(|| {
+                    vec![self.default_action_for_mode(
+                        mode,
+                        Some(key_with_modifier),
+                        raw_bytes,
+                        key_is_kitty_protocol,
+                    )]
+                }),
+            None => self.get_actions_for_key_in_mode_or_default_action(
+                mode,
+                key_with_modifier,
+                raw_bytes,
+                key_is_kitty_protocol,
+            ),
+        }
+    }
+    pub fn get_key_sequences_for_mode_mut(
+        &mut self,
+        input_mode: &InputMode,
+    ) -> &mut HashMap<Vec<KeyWithModifier>, Vec<Action>> {
+        self.sequences.entry(input_mode.clone()).or_default()
+    }
+    pub fn has_key_sequences_in_mode(&self, mode: &InputMode) -> bool {
+        self.sequences
+            .get(mode)
+            .map(|sequences| !sequences.is_empty())
+            .unwrap_or(false)
+    }
+    /// An exact match wins over longer sequences sharing the same prefix, so binding both
+    /// `"g" "g"` and `"g" "g" "x"` makes the latter unreachable
+    pub fn match_key_sequence(
+        &self,
+        mode: &InputMode,
+        keys: &[KeyWithModifier],
+    ) -> KeySequenceMatch {
+        let sequences = match self.sequences.get(mode) {
+            Some(sequences) if !keys.is_empty() => sequences,
+            _ => return KeySequenceMatch::NoMatch,
+        };
+        if let Some(actions) = sequences.get(keys) {
+            KeySequenceMatch::Complete(actions.clone())
+        } else if sequences
+            .keys()
+            .any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+        {
+            KeySequenceMatch::Partial
+        } else {
+            KeySequenceMatch::NoMatch
+        }
     }
     pub fn default_action_for_mode(
         &self,
@@ -74,30 +350,69 @@ impl Keybinds {
             InputMode::RenameTab => Action::TabNameInput(raw_bytes),
             InputMode::RenamePane => Action::PaneNameInput(raw_bytes),
             InputMode::EnterSearch => Action::SearchInput(raw_bytes),
+            // prompts can be answered without any keybindings for prompt mode
+            InputMode::Prompt => match key_with_modifier {
+                Some(key) if key.has_no_modifiers() => match key.bare_key {
+                    BareKey::Char('y') | BareKey::Char('Y') | BareKey::Enter => Action::Confirm,
+                    BareKey::Char('n') | BareKey::Char('N') | BareKey::Esc => Action::Deny,
+                    _ => Action::NoOp,
+                },
+                _ => Action::NoOp,
+            },
             _ => Action::NoOp,
         }
     }
     pub fn to_keybinds_vec(&self) -> KeybindsVec {
         let mut ret = vec![];
-        for (mode, mode_binds) in &self.0 {
+        for (mode, mode_binds) in &self.single {
             let mut mode_binds_vec: Vec<(KeyWithModifier, Vec<Action>)> = vec![];
             for (key, actions) in mode_binds {
                 mode_binds_vec.push((key.clone(), actions.clone()));
             }
-            ret.push((*mode, mode_binds_vec))
+            ret.push((mode.clone(), mode_binds_vec))
         }
         ret
     }
     pub fn merge(&mut self, mut other: Keybinds) {
-        for (other_input_mode, mut other_input_mode_keybinds) in other.0.drain() {
+        for (other_input_mode, mut other_input_mode_keybinds) in other.single.drain() {
             let input_mode_keybinds = self
-                .0
+                .single
                 .entry(other_input_mode)
                 .or_insert_with(|| Default::default());
             for (other_action, other_action_keybinds) in other_input_mode_keybinds.drain() {
                 input_mode_keybinds.insert(other_action, other_action_keybinds);
             }
         }
+        for (other_input_mode, mut other_input_mode_sequences) in other.sequences.drain() {
+            let input_mode_sequences = self.sequences.entry(other_input_mode).or_default();
+            for (sequence, actions) in other_input_mode_sequences.drain() {
+                input_mode_sequences.insert(sequence, actions);
+            }
+        }
+        for (other_input_mode, mut other_input_mode_conditional) in other.conditional.drain() {
+            let input_mode_conditional = self.conditional.entry(other_input_mode).or_default();
+            for (key, other_conditional_keybinds) in other_input_mode_conditional.drain() {
+                let conditional_keybinds = input_mode_conditional.entry(key).or_default();
+                for (condition, actions) in other_conditional_keybinds {
+                    add_conditional_keybind(conditional_keybinds, condition, actions);
+                }
+            }
+        }
+    }
+}
+
+/// Binding the same key with the same condition again replaces the previous actions
+pub fn add_conditional_keybind(
+    conditional_keybinds: &mut ConditionalKeybinds,
+    condition: KeybindCondition,
+    actions: Vec<Action>,
+) {
+    match conditional_keybinds
+        .iter_mut()
+        .find(|(c, _)| c == &condition)
+    {
+        Some((_, existing_actions)) => *existing_actions = actions,
+        None => conditional_keybinds.push((condition, actions)),
     }
 }
 
@@ -11,10 +11,10 @@
 #[cfg(not(target_family = "wasm"))]
 use crate::downloader::Downloader;
 use crate::{
-    data::{Direction, LayoutInfo},
+    data::{Direction, LayoutInfo, PaneColors},
     home::{default_layout_dir, find_default_config_dir},
     input::{
-        command::RunCommand,
+        command::{RestartCondition, RunCommand},
         config::{Config, ConfigError},
     },
     pane_size::{Constraint, Dimension, PaneGeom},
@@ -116,7 +116,7 @@ impl RunPluginOrAlias {
                         &run_plugin_alias
                             .configuration
                             .as_ref()
-                            .map(|c| c.inner().clone()),
+                            .map(|c| c.inner_with_settings()),
                     );
                     // if the alias has its own cwd, it should always override the alias
                     // value's cwd
@@ -184,9 +184,9 @@ impl RunPluginOrAlias {
                         .as_ref()
                         // we do the is_empty() checks because an empty configuration is the same as no
                         // configuration (i.e. None)
-                        .and_then(|c| if c.inner().is_empty() { None } else { Some(c) })
+                        .and_then(|c| if c.is_empty() { None } else { Some(c) })
                         == run_alias.configuration.as_ref().and_then(|c| {
-                            if c.inner().is_empty() {
+                            if c.is_empty() {
                                 None
                             } else {
                                 Some(c)
@@ -353,6 +353,37 @@ impl Run {
             }
         }
     }
+    pub fn add_restart_policy(
+        &mut self,
+        restart_condition: Option<RestartCondition>,
+        max_restarts: Option<usize>,
+    ) {
+        // overrides the restart policy of a Run::Command with the parts of it that are Some
+        if let Run::Command(run_command) = self {
+            if let Some(restart_condition) = restart_condition {
+                run_command.restart_policy.condition = restart_condition;
+            }
+            if let Some(max_restarts) = max_restarts {
+                run_command.restart_policy.max_restarts = Some(max_restarts);
+            }
+        }
+    }
+    pub fn add_rerun_on_change(
+        &mut self,
+        rerun_on_change: Option<Vec<String>>,
+        kill_on_change: Option<bool>,
+    ) {
+        // overrides the file watching behaviour of a Run::Command with the parts of it that are
+        // Some
+        if let Run::Command(run_command) = self {
+            if let Some(rerun_on_change) = rerun_on_change {
+                run_command.rerun_on_change = rerun_on_change;
+            }
+            if let Some(kill_on_change) = kill_on_change {
+                run_command.kill_on_change = kill_on_change;
+            }
+        }
+    }
     pub fn is_same_category(first: &Option<Run>, second: &Option<Run>) -> bool {
         match (first, second) {
             (Some(Run::Plugin(..)), Some(Run::Plugin(..))) => true,
@@ -491,8 +522,15 @@ impl PartialEq for RunPlugin {
 }
 impl Eq for RunPlugin {}
 
+/// Keys of a plugin's configuration that tell zellij how to run the plugin rather than configure
+/// the plugin itself, they are kept apart from the configuration the plugin gets
+pub const PLUGIN_SETTINGS: [&str; 3] = ["memory_limit", "call_timeout", "reload_on_change"];
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub struct PluginUserConfiguration(BTreeMap<String, String>);
+pub struct PluginUserConfiguration {
+    configuration: BTreeMap<String, String>,
+    settings: BTreeMap<String, String>,
+}
 
 impl PluginUserConfiguration {
     pub fn new(mut configuration: BTreeMap<String, String>) -> Self {
@@ -511,19 +549,57 @@ impl PluginUserConfiguration {
         configuration.remove("in_place");
         configuration.remove("skip_plugin_cache");
 
-        PluginUserConfiguration(configuration)
+        PluginUserConfiguration::with_settings_apart(configuration)
     }
+    fn with_settings_apart(mut configuration: BTreeMap<String, String>) -> Self {
+        let settings = PLUGIN_SETTINGS
+            .iter()
+            .filter_map(|key| configuration.remove_entry(*key))
+            .collect();
+        PluginUserConfiguration {
+            configuration,
+            settings,
+        }
+    }
+    /// The configuration the plugin gets
     pub fn inner(&self) -> &BTreeMap<String, String> {
-        &self.0
+        &self.configuration
+    }
+    /// The [`PLUGIN_SETTINGS`] of the plugin
+    pub fn settings(&self) -> &BTreeMap<String, String> {
+        &self.settings
+    }
+    /// The configuration together with the settings, as they were written (eg. to serialize them
+    /// back or pass them on to [`PluginUserConfiguration::new`])
+    pub fn inner_with_settings(&self) -> BTreeMap<String, String> {
+        let mut configuration = self.configuration.clone();
+        configuration.extend(self.settings.clone());
+        configuration
+    }
+    pub fn is_empty(&self) -> bool {
+        self.configuration.is_empty() && self.settings.is_empty()
     }
     pub fn insert(&mut self, config_key: impl Into<String>, config_value: impl Into<String>) {
-        self.0.insert(config_key.into(), config_value.into());
+        let config_key = config_key.into();
+        if PLUGIN_SETTINGS.contains(&config_key.as_str()) {
+            self.settings.insert(config_key, config_value.into());
+        } else {
+            self.configuration.insert(config_key, config_value.into());
+        }
     }
     pub fn merge(&mut self, other_config: &BTreeMap<String, String>) {
         for (key, value) in other_config {
-            self.0.insert(key.to_owned(), value.clone());
+            self.insert(key.to_owned(), value.clone());
         }
     }
+    /// Whether the plugin should be reloaded whenever its file changes (`reload_on_change true`),
+    /// only meaningful for plugins loaded with `file:`
+    pub fn reload_on_change(&self) -> bool {
+        self.settings
+            .get("reload_on_change")
+            .map(|reload_on_change| reload_on_change == "true")
+            .unwrap_or(false)
+    }
 }
 
 impl FromStr for PluginUserConfiguration {
@@ -538,7 +614,7 @@ impl FromStr for PluginUserConfiguration {
             let value = config.map(|c| c.to_owned()).collect::<Vec<_>>().join("=");
             ret.insert(key, value);
         }
-        Ok(PluginUserConfiguration(ret))
+        Ok(PluginUserConfiguration::with_settings_apart(ret))
     }
 }
 
@@ -738,6 +814,7 @@ pub struct FloatingPaneLayout {
     pub focus: Option<bool>,
     pub already_running: bool,
     pub pane_initial_contents: Option<String>,
+    pub pane_colors: PaneColors,
 }
 
 impl FloatingPaneLayout {
@@ -752,6 +829,7 @@ impl FloatingPaneLayout {
             focus: None,
             already_running: false,
             pane_initial_contents: None,
+            pane_colors: PaneColors::default(),
         }
     }
     pub fn add_cwd_to_layout(&mut self, cwd: &PathBuf) {
@@ -775,6 +853,7 @@ impl From<&TiledPaneLayout> for FloatingPaneLayout {
             name: pane_layout.name.clone(),
             run: pane_layout.run.clone(),
             focus: pane_layout.focus,
+            pane_colors: pane_layout.pane_colors,
             ..Default::default()
         }
     }
@@ -788,6 +867,7 @@ pub struct TiledPaneLayout {
     pub split_size: Option<SplitSize>,
     pub run: Option<Run>,
     pub borderless: bool,
+    pub tab_bar: bool, // mouse bindings with the tab_bar target apply to this pane
     pub focus: Option<bool>,
     pub external_children_index: Option<usize>,
     pub children_are_stacked: bool,
@@ -796,6 +876,7 @@ pub struct TiledPaneLayout {
     pub run_instructions_to_ignore: Vec<Option<Run>>,
     pub hide_floating_panes: bool, // only relevant if this is the base layout
     pub pane_initial_contents: Option<String>,
+    pub pane_colors: PaneColors, // if this is the base layout, these are the colors of the tab
 }
 
 impl TiledPaneLayout {
@@ -1227,22 +1308,29 @@ impl Layout {
         layout_path: Option<&PathBuf>,
         layout_dir: Option<PathBuf>,
         config: Config,
+        layout_vars: BTreeMap<String, String>,
     ) -> Result<(Layout, Config), ConfigError> {
         let (path_to_raw_layout, raw_layout, raw_swap_layouts) =
             Layout::stringified_from_path_or_default(layout_path, layout_dir)?;
-        let layout = Layout::from_kdl(
+        let layout = Layout::from_kdl_with_vars(
             &raw_layout,
             Some(path_to_raw_layout),
             raw_swap_layouts
                 .as_ref()
                 .map(|(r, f)| (r.as_str(), f.as_str())),
             None,
+            layout_vars.clone(),
         )?;
-        let config = Config::from_kdl(&raw_layout, Some(config))?; // this merges the two config, with
+        let raw_layout = Layout::substitute_vars(&raw_layout, layout_vars.clone())?;
+        let config = Config::from_kdl_with_layout_vars(&raw_layout, Some(config), &layout_vars)?; // this merges the two config, with
         Ok((layout, config))
     }
     #[cfg(not(target_family = "wasm"))]
-    pub fn from_url(url: &str, config: Config) -> Result<(Layout, Config), ConfigError> {
+    pub fn from_url(
+        url: &str,
+        config: Config,
+        layout_vars: BTreeMap<String, String>,
+    ) -> Result<(Layout, Config), ConfigError> {
         let raw_layout = task::block_on(async move {
             let download = Downloader::download_without_cache(url).await;
             match download {
@@ -1250,8 +1338,15 @@ impl Layout {
                 Err(e) => Err(ConfigError::DownloadError(format!("{}", e))),
             }
         })?;
-        let layout = Layout::from_kdl(&raw_layout, Some(url.into()), None, None)?;
-        let config = Config::from_kdl(&raw_layout, Some(config))?; // this merges the two config, with
+        let layout = Layout::from_kdl_with_vars(
+            &raw_layout,
+            Some(url.into()),
+            None,
+            None,
+            layout_vars.clone(),
+        )?;
+        let raw_layout = Layout::substitute_vars(&raw_layout, layout_vars.clone())?;
+        let config = Config::from_kdl_with_layout_vars(&raw_layout, Some(config), &layout_vars)?; // this merges the two config, with
         Ok((layout, config))
     }
     pub fn from_stringified_layout(
@@ -1263,7 +1358,11 @@ impl Layout {
         Ok((layout, config))
     }
     #[cfg(target_family = "wasm")]
-    pub fn from_url(url: &str, config: Config) -> Result<(Layout, Config), ConfigError> {
+    pub fn from_url(
+        _url: &str,
+        _config: Config,
+        _layout_vars: BTreeMap<String, String>,
+    ) -> Result<(Layout, Config), ConfigError> {
         Err(ConfigError::DownloadError(format!(
             "Unsupported platform, cannot download layout from the web"
         )))
@@ -1307,8 +1406,15 @@ impl Layout {
         path_to_raw_layout: String,
         swap_layouts: Option<(&str, &str)>, // Option<path_to_swap_layout, stringified_swap_layout>
         cwd: Option<PathBuf>,
+        layout_vars: BTreeMap<String, String>,
     ) -> Result<Layout, ConfigError> {
-        Layout::from_kdl(raw, Some(path_to_raw_layout), swap_layouts, cwd)
+        Layout::from_kdl_with_vars(
+            raw,
+            Some(path_to_raw_layout),
+            swap_layouts,
+            cwd,
+            layout_vars,
+        )
     }
     pub fn stringified_from_dir(
         layout: &PathBuf,
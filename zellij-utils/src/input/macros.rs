@@ -0,0 +1,53 @@
+//! Keyboard macros recorded by the user or defined in the configuration
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{InputMode, KeyWithModifier};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Macros {
+    pub macros: BTreeMap<String, Macro>,
+}
+
+/// The keys of a macro, as they were typed while it was being recorded
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct Macro {
+    /// The input mode the keys were typed in, replaying the macro first switches to it
+    pub start_mode: Option<InputMode>,
+    pub keys: Vec<KeyWithModifier>,
+}
+
+/// Sent by the server to the client, which records and replays the keys of macros. Keys are
+/// numbered in the order the client sends them to the server, starting from 0, so that the server
+/// can tell which keys a recording starts and stops at while more keys are on their way
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum MacroInstruction {
+    /// Record the keys from the one numbered `from_key` on
+    StartRecording {
+        name: String,
+        start_mode: InputMode,
+        from_key: usize,
+    },
+    /// Stop recording, the keys from the one numbered `until_key` on (those bound to stopping the
+    /// recording) are left out
+    StopRecording { until_key: usize },
+    /// Replay the keys of the macro, `depth` counts the replays this one is played from (including
+    /// itself) so that a macro playing itself stops at some point
+    Play { macro_to_play: Macro, depth: usize },
+}
+
+impl Macros {
+    pub fn merge(&mut self, other: Self) {
+        self.macros.extend(other.macros);
+    }
+    pub fn from_data(macros: BTreeMap<String, Macro>) -> Self {
+        Macros { macros }
+    }
+    pub fn get(&self, name: &str) -> Option<&Macro> {
+        self.macros.get(name)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+}
@@ -3,14 +3,19 @@ pub mod command;
 pub mod config;
 pub mod keybinds;
 pub mod layout;
+pub mod macros;
+pub mod mouse;
 pub mod options;
 pub mod permission;
 pub mod plugins;
 pub mod theme;
+pub mod theme_import;
 
-// Can't use this in wasm due to dependency on the `termwiz` crate.
 #[cfg(not(target_family = "wasm"))]
-pub mod mouse;
+pub mod dir_local_layout;
+
+#[cfg(not(target_family = "wasm"))]
+pub mod plugin_lockfile;
 
 #[cfg(not(target_family = "wasm"))]
 pub use not_wasm::*;
@@ -46,6 +51,7 @@ mod not_wasm {
             style: attributes.style,
             capabilities,
             session_name,
+            pending_key_sequence: vec![],
         }
     }
 
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
 
+use super::actions::Action;
+use crate::data::KeyModifier;
 use crate::position::Position;
 
 /// A mouse related event
@@ -19,6 +24,8 @@ pub enum MouseEvent {
     Hold(Position),
 }
 
+// Can't use this in wasm due to dependency on the `termwiz` crate.
+#[cfg(not(target_family = "wasm"))]
 impl From<termwiz::input::MouseEvent> for MouseEvent {
     fn from(event: termwiz::input::MouseEvent) -> Self {
         #[allow(clippy::if_same_then_else)]
@@ -84,7 +91,24 @@ impl From<termwiz::input::MouseEvent> for MouseEvent {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+/// The keyboard modifiers held while a mouse event happened
+#[cfg(not(target_family = "wasm"))]
+pub fn mouse_event_modifiers(event: &termwiz::input::MouseEvent) -> BTreeSet<KeyModifier> {
+    use termwiz::input::Modifiers;
+    let mut modifiers = BTreeSet::new();
+    if event.modifiers.contains(Modifiers::CTRL) {
+        modifiers.insert(KeyModifier::Ctrl);
+    }
+    if event.modifiers.contains(Modifiers::ALT) {
+        modifiers.insert(KeyModifier::Alt);
+    }
+    if event.modifiers.contains(Modifiers::SHIFT) {
+        modifiers.insert(KeyModifier::Shift);
+    }
+    modifiers
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
 pub enum MouseButton {
     /// The left mouse button.
     Left,
@@ -101,3 +125,219 @@ pub enum MouseButton {
     /// This event is typically only used with Mouse::Press.
     WheelDown,
 }
+
+impl FromStr for MouseButton {
+    type Err = Box<dyn std::error::Error>;
+    fn from_str(button_str: &str) -> Result<Self, Self::Err> {
+        match button_str.to_ascii_lowercase().as_str() {
+            "leftclick" => Ok(MouseButton::Left),
+            "rightclick" => Ok(MouseButton::Right),
+            "middleclick" => Ok(MouseButton::Middle),
+            "wheelup" => Ok(MouseButton::WheelUp),
+            "wheeldown" => Ok(MouseButton::WheelDown),
+            _ => Err("unsupported mouse button".into()),
+        }
+    }
+}
+
+impl fmt::Display for MouseButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MouseButton::Left => write!(f, "LeftClick"),
+            MouseButton::Right => write!(f, "RightClick"),
+            MouseButton::Middle => write!(f, "MiddleClick"),
+            MouseButton::WheelUp => write!(f, "WheelUp"),
+            MouseButton::WheelDown => write!(f, "WheelDown"),
+        }
+    }
+}
+
+/// A mouse button pressed while holding keyboard modifiers, eg. "Ctrl LeftClick"
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct MouseButtonWithModifier {
+    pub bare_button: MouseButton,
+    pub key_modifiers: BTreeSet<KeyModifier>,
+}
+
+impl MouseButtonWithModifier {
+    pub fn new(bare_button: MouseButton) -> Self {
+        MouseButtonWithModifier {
+            bare_button,
+            key_modifiers: BTreeSet::new(),
+        }
+    }
+    pub fn new_with_modifiers(
+        bare_button: MouseButton,
+        key_modifiers: BTreeSet<KeyModifier>,
+    ) -> Self {
+        MouseButtonWithModifier {
+            bare_button,
+            key_modifiers,
+        }
+    }
+    pub fn with_ctrl_modifier(mut self) -> Self {
+        self.key_modifiers.insert(KeyModifier::Ctrl);
+        self
+    }
+    pub fn with_alt_modifier(mut self) -> Self {
+        self.key_modifiers.insert(KeyModifier::Alt);
+        self
+    }
+    pub fn with_shift_modifier(mut self) -> Self {
+        self.key_modifiers.insert(KeyModifier::Shift);
+        self
+    }
+    /// The action a press of this button has when it is not bound
+    pub fn default_action(&self, position: Position) -> Action {
+        match self.bare_button {
+            MouseButton::Left => Action::LeftClick(position),
+            MouseButton::Right => Action::RightClick(position),
+            MouseButton::Middle => Action::MiddleClick(position),
+            MouseButton::WheelUp => Action::ScrollUpAt(position),
+            MouseButton::WheelDown => Action::ScrollDownAt(position),
+        }
+    }
+}
+
+impl FromStr for MouseButtonWithModifier {
+    type Err = Box<dyn std::error::Error>;
+    fn from_str(button_str: &str) -> Result<Self, Self::Err> {
+        let mut button_string_parts: Vec<&str> = button_str.split_ascii_whitespace().collect();
+        let bare_button =
+            MouseButton::from_str(button_string_parts.pop().ok_or("empty mouse button")?)?;
+        let mut key_modifiers: BTreeSet<KeyModifier> = BTreeSet::new();
+        for stringified_modifier in button_string_parts {
+            key_modifiers.insert(KeyModifier::from_str(stringified_modifier)?);
+        }
+        Ok(MouseButtonWithModifier {
+            bare_button,
+            key_modifiers,
+        })
+    }
+}
+
+impl fmt::Display for MouseButtonWithModifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for key_modifier in &self.key_modifiers {
+            write!(f, "{} ", key_modifier.to_string())?;
+        }
+        write!(f, "{}", self.bare_button)
+    }
+}
+
+/// The part of the screen a mouse button was pressed on
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum MouseTarget {
+    PaneBody,
+    PaneFrame,
+    TabBar,
+}
+
+impl FromStr for MouseTarget {
+    type Err = Box<dyn std::error::Error>;
+    fn from_str(target_str: &str) -> Result<Self, Self::Err> {
+        match target_str.to_ascii_lowercase().as_str() {
+            "pane_body" => Ok(MouseTarget::PaneBody),
+            "pane_frame" => Ok(MouseTarget::PaneFrame),
+            "tab_bar" => Ok(MouseTarget::TabBar),
+            _ => Err("unsupported mouse target".into()),
+        }
+    }
+}
+
+impl fmt::Display for MouseTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MouseTarget::PaneBody => write!(f, "pane_body"),
+            MouseTarget::PaneFrame => write!(f, "pane_frame"),
+            MouseTarget::TabBar => write!(f, "tab_bar"),
+        }
+    }
+}
+
+/// The actions bound to a mouse button for each target it can be pressed on
+pub type MouseTargetBindings = Vec<(Option<MouseTarget>, Vec<Action>)>;
+
+/// The actions bound to mouse buttons, a binding without a target applies wherever the button is
+/// pressed unless a binding with the matching target exists
+#[derive(Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct MouseBindings(pub BTreeMap<MouseButtonWithModifier, MouseTargetBindings>);
+
+impl fmt::Debug for MouseBindings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.0.iter()).finish()
+    }
+}
+
+impl MouseBindings {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn bind(
+        &mut self,
+        button: MouseButtonWithModifier,
+        target: Option<MouseTarget>,
+        actions: Vec<Action>,
+    ) {
+        let bindings = self.0.entry(button).or_default();
+        bindings.retain(|(bound_target, _)| *bound_target != target);
+        bindings.push((target, actions));
+    }
+    pub fn unbind(&mut self, button: &MouseButtonWithModifier, target: Option<MouseTarget>) {
+        if let Some(bindings) = self.0.get_mut(button) {
+            bindings.retain(|(bound_target, _)| *bound_target != target);
+            if bindings.is_empty() {
+                self.0.remove(button);
+            }
+        }
+    }
+    pub fn merge(&mut self, other: MouseBindings) {
+        for (button, bindings) in other.0 {
+            for (target, actions) in bindings {
+                self.bind(button.clone(), target, actions);
+            }
+        }
+    }
+    pub fn is_bound(&self, button: &MouseButtonWithModifier) -> bool {
+        self.0.contains_key(button)
+    }
+    /// Whether the target under the mouse is needed to pick the actions of this button
+    pub fn has_targeted_bindings(&self, button: &MouseButtonWithModifier) -> bool {
+        self.0.get(button).map_or(false, |bindings| {
+            bindings.iter().any(|(target, _)| target.is_some())
+        })
+    }
+    pub fn get_actions(
+        &self,
+        button: &MouseButtonWithModifier,
+        target: Option<MouseTarget>,
+    ) -> Option<&Vec<Action>> {
+        let bindings = self.0.get(button)?;
+        bindings
+            .iter()
+            .find(|(bound_target, _)| bound_target.is_some() && *bound_target == target)
+            .or_else(|| {
+                bindings
+                    .iter()
+                    .find(|(bound_target, _)| bound_target.is_none())
+            })
+            .map(|(_, actions)| actions)
+    }
+    /// The actions of a press of the button on the target, placed at the mouse position, or what
+    /// the button does by default if it is not bound there
+    pub fn actions_for_press(
+        &self,
+        button: &MouseButtonWithModifier,
+        target: Option<MouseTarget>,
+        position: Position,
+    ) -> Vec<Action> {
+        match self.get_actions(button, target) {
+            Some(actions) => actions
+                .iter()
+                .cloned()
+                .map(|action| action.at_mouse_position(position))
+                .collect(),
+            None => vec![button.default_action(position)],
+        }
+    }
+}
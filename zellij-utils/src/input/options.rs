@@ -46,6 +46,12 @@ pub struct Options {
     /// Set the default theme
     #[clap(long, value_parser)]
     pub theme: Option<String>,
+    /// Set the theme to use when the terminal has a dark background, overrides `theme`
+    #[clap(long, value_parser)]
+    pub theme_dark: Option<String>,
+    /// Set the theme to use when the terminal has a light background, overrides `theme`
+    #[clap(long, value_parser)]
+    pub theme_light: Option<String>,
     /// Set the default mode
     #[clap(long, arg_enum, hide_possible_values = true, value_parser)]
     pub default_mode: Option<InputMode>,
@@ -161,6 +167,46 @@ pub struct Options {
     #[clap(long, value_parser)]
     #[serde(default)]
     pub support_kitty_keyboard_protocol: Option<bool>,
+
+    /// Whether to look for a `.zellij/layout.kdl` or `.zellij.kdl` file in the current directory
+    /// and its parents when starting without an explicit layout, default is true
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub dir_local_layouts: Option<bool>,
+
+    /// How many parent directories to search for a directory-local layout, default is 10
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub dir_local_layouts_search_depth: Option<usize>,
+
+    /// How long to wait for the next key of a multi-key sequence (in milliseconds) before
+    /// sending the keys typed so far to the pane, default is 1000
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub key_sequence_timeout: Option<u64>,
+
+    /// Whether to show an overlay listing the available keybindings shortly after switching to an
+    /// input mode other than normal or locked, default is false
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub which_key_overlay: Option<bool>,
+
+    /// Render panes that are not focused with reduced intensity, by this percentage (0-100),
+    /// default is 0 (disabled)
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub dim_unfocused_panes: Option<u8>,
+
+    /// The most memory (in megabytes) a plugin may use before it is stopped, default is no limit
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub plugin_memory_limit: Option<usize>,
+
+    /// The longest (in milliseconds) a single call into a plugin (eg. to update or render it) may
+    /// run before the plugin is stopped, 0 for no limit, default is 10000
+    #[clap(long, value_parser)]
+    #[serde(default)]
+    pub plugin_call_timeout: Option<u64>,
 }
 
 #[derive(ArgEnum, Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
@@ -205,13 +251,15 @@ impl Options {
         let auto_layout = other.auto_layout.or(self.auto_layout);
         let mirror_session = other.mirror_session.or(self.mirror_session);
         let simplified_ui = other.simplified_ui.or(self.simplified_ui);
-        let default_mode = other.default_mode.or(self.default_mode);
+        let default_mode = other.default_mode.or_else(|| self.default_mode.clone());
         let default_shell = other.default_shell.or_else(|| self.default_shell.clone());
         let default_cwd = other.default_cwd.or_else(|| self.default_cwd.clone());
         let default_layout = other.default_layout.or_else(|| self.default_layout.clone());
         let layout_dir = other.layout_dir.or_else(|| self.layout_dir.clone());
         let theme_dir = other.theme_dir.or_else(|| self.theme_dir.clone());
         let theme = other.theme.or_else(|| self.theme.clone());
+        let theme_dark = other.theme_dark.or_else(|| self.theme_dark.clone());
+        let theme_light = other.theme_light.or_else(|| self.theme_light.clone());
         let on_force_close = other.on_force_close.or(self.on_force_close);
         let scroll_buffer_size = other.scroll_buffer_size.or(self.scroll_buffer_size);
         let copy_command = other.copy_command.or_else(|| self.copy_command.clone());
@@ -239,10 +287,21 @@ impl Options {
         let support_kitty_keyboard_protocol = other
             .support_kitty_keyboard_protocol
             .or(self.support_kitty_keyboard_protocol);
+        let dir_local_layouts = other.dir_local_layouts.or(self.dir_local_layouts);
+        let dir_local_layouts_search_depth = other
+            .dir_local_layouts_search_depth
+            .or(self.dir_local_layouts_search_depth);
+        let key_sequence_timeout = other.key_sequence_timeout.or(self.key_sequence_timeout);
+        let which_key_overlay = other.which_key_overlay.or(self.which_key_overlay);
+        let dim_unfocused_panes = other.dim_unfocused_panes.or(self.dim_unfocused_panes);
+        let plugin_memory_limit = other.plugin_memory_limit.or(self.plugin_memory_limit);
+        let plugin_call_timeout = other.plugin_call_timeout.or(self.plugin_call_timeout);
 
         Options {
             simplified_ui,
             theme,
+            theme_dark,
+            theme_light,
             default_mode,
             default_shell,
             default_cwd,
@@ -268,6 +327,13 @@ impl Options {
             serialization_interval,
             disable_session_metadata,
             support_kitty_keyboard_protocol,
+            dir_local_layouts,
+            dir_local_layouts_search_depth,
+            key_sequence_timeout,
+            which_key_overlay,
+            dim_unfocused_panes,
+            plugin_memory_limit,
+            plugin_call_timeout,
         }
     }
 
@@ -296,13 +362,15 @@ impl Options {
         let serialize_pane_viewport =
             merge_bool(other.serialize_pane_viewport, self.serialize_pane_viewport);
 
-        let default_mode = other.default_mode.or(self.default_mode);
+        let default_mode = other.default_mode.or_else(|| self.default_mode.clone());
         let default_shell = other.default_shell.or_else(|| self.default_shell.clone());
         let default_cwd = other.default_cwd.or_else(|| self.default_cwd.clone());
         let default_layout = other.default_layout.or_else(|| self.default_layout.clone());
         let layout_dir = other.layout_dir.or_else(|| self.layout_dir.clone());
         let theme_dir = other.theme_dir.or_else(|| self.theme_dir.clone());
         let theme = other.theme.or_else(|| self.theme.clone());
+        let theme_dark = other.theme_dark.or_else(|| self.theme_dark.clone());
+        let theme_light = other.theme_light.or_else(|| self.theme_light.clone());
         let on_force_close = other.on_force_close.or(self.on_force_close);
         let scroll_buffer_size = other.scroll_buffer_size.or(self.scroll_buffer_size);
         let copy_command = other.copy_command.or_else(|| self.copy_command.clone());
@@ -326,10 +394,21 @@ impl Options {
         let support_kitty_keyboard_protocol = other
             .support_kitty_keyboard_protocol
             .or(self.support_kitty_keyboard_protocol);
+        let dir_local_layouts = other.dir_local_layouts.or(self.dir_local_layouts);
+        let dir_local_layouts_search_depth = other
+            .dir_local_layouts_search_depth
+            .or(self.dir_local_layouts_search_depth);
+        let key_sequence_timeout = other.key_sequence_timeout.or(self.key_sequence_timeout);
+        let which_key_overlay = other.which_key_overlay.or(self.which_key_overlay);
+        let dim_unfocused_panes = other.dim_unfocused_panes.or(self.dim_unfocused_panes);
+        let plugin_memory_limit = other.plugin_memory_limit.or(self.plugin_memory_limit);
+        let plugin_call_timeout = other.plugin_call_timeout.or(self.plugin_call_timeout);
 
         Options {
             simplified_ui,
             theme,
+            theme_dark,
+            theme_light,
             default_mode,
             default_shell,
             default_cwd,
@@ -355,6 +434,13 @@ impl Options {
             serialization_interval,
             disable_session_metadata,
             support_kitty_keyboard_protocol,
+            dir_local_layouts,
+            dir_local_layouts_search_depth,
+            key_sequence_timeout,
+            which_key_overlay,
+            dim_unfocused_panes,
+            plugin_memory_limit,
+            plugin_call_timeout,
         }
     }
 
@@ -396,6 +482,8 @@ impl From<CliOptions> for Options {
         Self {
             simplified_ui: opts.simplified_ui,
             theme: opts.theme,
+            theme_dark: opts.theme_dark,
+            theme_light: opts.theme_light,
             default_mode: opts.default_mode,
             default_shell: opts.default_shell,
             default_cwd: opts.default_cwd,
@@ -420,6 +508,13 @@ impl From<CliOptions> for Options {
             styled_underlines: opts.styled_underlines,
             serialization_interval: opts.serialization_interval,
             support_kitty_keyboard_protocol: opts.support_kitty_keyboard_protocol,
+            dir_local_layouts: opts.dir_local_layouts,
+            dir_local_layouts_search_depth: opts.dir_local_layouts_search_depth,
+            key_sequence_timeout: opts.key_sequence_timeout,
+            which_key_overlay: opts.which_key_overlay,
+            dim_unfocused_panes: opts.dim_unfocused_panes,
+            plugin_memory_limit: opts.plugin_memory_limit,
+            plugin_call_timeout: opts.plugin_call_timeout,
             ..Default::default()
         }
     }
@@ -5,18 +5,39 @@ use std::{
     path::PathBuf,
 };
 
-use crate::{consts::ZELLIJ_PLUGIN_PERMISSIONS_CACHE, data::PermissionType};
+use crate::{
+    consts::ZELLIJ_PLUGIN_PERMISSIONS_CACHE,
+    data::{PermissionScopes, PermissionType},
+};
 
 pub type GrantedPermission = HashMap<String, Vec<PermissionType>>;
+pub type GrantedScopes = HashMap<String, PermissionScopes>;
 
 #[derive(Default, Debug)]
 pub struct PermissionCache {
     path: PathBuf,
     granted: GrantedPermission,
+    scopes: GrantedScopes,
 }
 
 impl PermissionCache {
-    pub fn cache(&mut self, plugin_name: String, permissions: Vec<PermissionType>) {
+    pub fn cache(
+        &mut self,
+        plugin_name: String,
+        permissions: Vec<PermissionType>,
+        scopes: PermissionScopes,
+    ) {
+        let mut granted_scopes = PermissionScopes::new();
+        for (permission, scope) in scopes.iter() {
+            if permissions.contains(permission) {
+                granted_scopes.set_scope(*permission, scope.clone());
+            }
+        }
+        if granted_scopes.is_empty() {
+            self.scopes.remove(&plugin_name);
+        } else {
+            self.scopes.insert(plugin_name.clone(), granted_scopes);
+        }
         self.granted.insert(plugin_name, permissions);
     }
 
@@ -24,6 +45,76 @@ impl PermissionCache {
         self.granted.get(&plugin_name)
     }
 
+    pub fn get_permission_scopes(&self, plugin_name: &str) -> PermissionScopes {
+        self.scopes.get(plugin_name).cloned().unwrap_or_default()
+    }
+
+    pub fn granted(&self) -> &GrantedPermission {
+        &self.granted
+    }
+
+    // adds the permissions to the ones already granted to this plugin, a scope given for a
+    // permission that was already granted with a scope is added to it, a permission granted
+    // without a scope is no longer limited
+    pub fn grant(
+        &mut self,
+        plugin_name: String,
+        permissions: Vec<PermissionType>,
+        scopes: PermissionScopes,
+    ) {
+        let granted = self.granted.entry(plugin_name.clone()).or_default();
+        let granted_scopes = self.scopes.entry(plugin_name.clone()).or_default();
+        for permission in permissions {
+            let already_granted = granted.contains(&permission);
+            match (scopes.scope(&permission), granted_scopes.scope(&permission)) {
+                (Some(scope), Some(granted_scope)) => {
+                    let mut scope_union = granted_scope.clone();
+                    for scope_entry in scope {
+                        if !scope_union.contains(scope_entry) {
+                            scope_union.push(scope_entry.clone());
+                        }
+                    }
+                    granted_scopes.set_scope(permission, scope_union);
+                },
+                (Some(scope), None) if !already_granted => {
+                    granted_scopes.set_scope(permission, scope.clone());
+                },
+                (Some(_), None) => {}, // already granted without limits
+                (None, _) => granted_scopes.remove_scope(&permission),
+            }
+            if !already_granted {
+                granted.push(permission);
+            }
+        }
+        if granted_scopes.is_empty() {
+            self.scopes.remove(&plugin_name);
+        }
+    }
+
+    // removes the permissions from the ones granted to this plugin, if `permissions` is empty all
+    // of them are revoked and the plugin will be prompted again the next time it asks for them
+    //
+    // returns false if the plugin had none of these permissions to begin with
+    pub fn revoke(&mut self, plugin_name: &str, permissions: &[PermissionType]) -> bool {
+        if permissions.is_empty() {
+            self.scopes.remove(plugin_name);
+            return self.granted.remove(plugin_name).is_some();
+        }
+        if let Some(granted_scopes) = self.scopes.get_mut(plugin_name) {
+            for permission in permissions {
+                granted_scopes.remove_scope(permission);
+            }
+        }
+        match self.granted.get_mut(plugin_name) {
+            Some(granted) => {
+                let granted_count = granted.len();
+                granted.retain(|p| !permissions.contains(p));
+                granted.len() != granted_count
+            },
+            None => false,
+        }
+    }
+
     pub fn check_permissions(
         &self,
         plugin_name: String,
@@ -45,23 +136,131 @@ impl PermissionCache {
     pub fn from_path_or_default(cache_path: Option<PathBuf>) -> Self {
         let cache_path = cache_path.unwrap_or(ZELLIJ_PLUGIN_PERMISSIONS_CACHE.to_path_buf());
 
-        let granted = match fs::read_to_string(cache_path.clone()) {
+        let (granted, scopes) = match fs::read_to_string(cache_path.clone()) {
             Ok(raw_string) => PermissionCache::from_string(raw_string).unwrap_or_default(),
             Err(e) => {
                 log::error!("Failed to read permission cache file: {}", e);
-                GrantedPermission::default()
+                Default::default()
             },
         };
 
         PermissionCache {
             path: cache_path,
             granted,
+            scopes,
         }
     }
 
     pub fn write_to_file(&self) -> std::io::Result<()> {
+        if let Some(parent_folder) = self.path.parent() {
+            fs::create_dir_all(parent_folder)?;
+        }
         let mut f = File::create(&self.path)?;
-        write!(f, "{}", PermissionCache::to_string(&self.granted))?;
+        write!(
+            f,
+            "{}",
+            PermissionCache::to_string(&self.granted, &self.scopes)
+        )?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod permission_tests {
+    use super::*;
+
+    #[test]
+    fn scopes_limit_what_a_permission_allows() {
+        let scopes = PermissionScopes::new()
+            .with_scope(PermissionType::RunCommands, &["git", "cargo"])
+            .with_scope(PermissionType::WebAccess, &["*.github.com"])
+            .with_scope(PermissionType::HostFilesystemAccess, &["/host/src"]);
+        assert!(scopes.allows(PermissionType::RunCommands, "git"));
+        assert!(!scopes.allows(PermissionType::RunCommands, "rm"));
+        assert!(scopes.allows(PermissionType::WebAccess, "api.GitHub.com"));
+        assert!(scopes.allows(PermissionType::WebAccess, "github.com"));
+        assert!(!scopes.allows(PermissionType::WebAccess, "notgithub.com"));
+        assert!(scopes.allows(PermissionType::HostFilesystemAccess, "/host/src/main.rs"));
+        assert!(scopes.allows(PermissionType::HostFilesystemAccess, "src/lib"));
+        assert!(!scopes.allows(PermissionType::HostFilesystemAccess, "/host/srcs"));
+        assert!(!scopes.allows(PermissionType::HostFilesystemAccess, "/host/src/../secrets"));
+        assert!(
+            scopes.allows(PermissionType::OpenFiles, "anything"),
+            "permissions without a scope are not limited"
+        );
+    }
+
+    #[test]
+    fn granted_scopes_cap_requested_scopes() {
+        let granted = PermissionScopes::new().with_scope(PermissionType::RunCommands, &["git"]);
+        let requested = PermissionScopes::new()
+            .with_scope(PermissionType::RunCommands, &["git", "cargo"])
+            .with_scope(PermissionType::WebAccess, &["api.github.com"]);
+        let narrowed = requested.narrowed_by(&granted);
+        assert_eq!(
+            narrowed.scope(&PermissionType::RunCommands),
+            Some(&vec!["git".to_owned()])
+        );
+        assert_eq!(
+            narrowed.scope(&PermissionType::WebAccess),
+            Some(&vec!["api.github.com".to_owned()])
+        );
+    }
+
+    #[test]
+    fn scopes_survive_a_round_trip_through_the_cache_file() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cache_path = cache_dir.path().join("permissions.kdl");
+        let mut permission_cache = PermissionCache::from_path_or_default(Some(cache_path.clone()));
+        permission_cache.cache(
+            "file:/path/to/plugin.wasm".to_owned(),
+            vec![PermissionType::RunCommands, PermissionType::OpenFiles],
+            PermissionScopes::new()
+                .with_scope(PermissionType::RunCommands, &["git", "cargo"])
+                .with_scope(PermissionType::WebAccess, &["api.github.com"]),
+        );
+        permission_cache.write_to_file().unwrap();
+
+        let permission_cache = PermissionCache::from_path_or_default(Some(cache_path));
+        let scopes = permission_cache.get_permission_scopes("file:/path/to/plugin.wasm");
+        assert_eq!(
+            scopes,
+            PermissionScopes::new().with_scope(PermissionType::RunCommands, &["git", "cargo"]),
+            "only scopes of granted permissions are cached"
+        );
+    }
+
+    #[test]
+    fn granting_and_revoking_scoped_permissions() {
+        let mut permission_cache = PermissionCache::default();
+        let plugin = "zellij:some-plugin";
+        permission_cache.grant(
+            plugin.to_owned(),
+            vec![PermissionType::RunCommands],
+            PermissionScopes::new().with_scope(PermissionType::RunCommands, &["git"]),
+        );
+        permission_cache.grant(
+            plugin.to_owned(),
+            vec![PermissionType::RunCommands],
+            PermissionScopes::new().with_scope(PermissionType::RunCommands, &["cargo"]),
+        );
+        assert_eq!(
+            permission_cache
+                .get_permission_scopes(plugin)
+                .scope(&PermissionType::RunCommands),
+            Some(&vec!["git".to_owned(), "cargo".to_owned()])
+        );
+        permission_cache.grant(
+            plugin.to_owned(),
+            vec![PermissionType::RunCommands],
+            PermissionScopes::default(),
+        );
+        assert!(
+            permission_cache.get_permission_scopes(plugin).is_empty(),
+            "granting without a scope lifts the limit"
+        );
+        assert!(permission_cache.revoke(plugin, &[PermissionType::RunCommands]));
+        assert!(!permission_cache
+            .check_permissions(plugin.to_owned(), &vec![PermissionType::RunCommands]));
+    }
+}
@@ -0,0 +1,319 @@
+//! Plugins installed with `zellij plugin install` and the lockfile pinning the url, version and
+//! sha256 of each of them.
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use kdl::{KdlDocument, KdlNode};
+use sha2::{Digest, Sha256};
+
+use crate::errors::prelude::*;
+use crate::input::config::ConfigError;
+use crate::input::layout::{RunPlugin, RunPluginLocation};
+use crate::input::plugins::PluginAliases;
+
+/// Relative to the plugin dir (`<data dir>/plugins`), holds the installed plugins and the lockfile
+pub const INSTALLED_PLUGINS_DIR_NAME: &str = "installed";
+pub const PLUGIN_LOCKFILE_NAME: &str = "plugins.lock.kdl";
+
+pub fn hash_plugin_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// The version of a plugin as it appears in its url, eg. the tag of a GitHub release
+/// (`.../releases/download/v0.1.0/plugin.wasm`)
+pub fn version_from_url(url: &str) -> Option<String> {
+    url.split('/').find_map(|segment| {
+        let version = segment.strip_prefix('v').unwrap_or(segment);
+        let numbers = version.split('-').next().unwrap_or(version);
+        let is_version = numbers.contains('.')
+            && numbers
+                .split('.')
+                .all(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if is_version {
+            Some(segment.to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPlugin {
+    pub url: String,
+    pub version: Option<String>,
+    pub sha256: String,
+}
+
+/// alias => locked plugin
+pub type LockedPlugins = BTreeMap<String, LockedPlugin>;
+
+#[derive(Default, Debug)]
+pub struct PluginLockfile {
+    installed_plugins_dir: PathBuf,
+    locked: LockedPlugins,
+}
+
+impl PluginLockfile {
+    pub fn from_plugin_dir(plugin_dir: &Path) -> Self {
+        let installed_plugins_dir = plugin_dir.join(INSTALLED_PLUGINS_DIR_NAME);
+        let lockfile_path = installed_plugins_dir.join(PLUGIN_LOCKFILE_NAME);
+        let locked = match fs::read_to_string(&lockfile_path) {
+            Ok(raw_string) => PluginLockfile::from_string(raw_string).unwrap_or_else(|e| {
+                log::error!(
+                    "Failed to parse plugin lockfile {}: {}",
+                    lockfile_path.display(),
+                    e
+                );
+                LockedPlugins::default()
+            }),
+            Err(_) => LockedPlugins::default(), // nothing was installed yet
+        };
+        PluginLockfile {
+            installed_plugins_dir,
+            locked,
+        }
+    }
+
+    pub fn installed_plugins_dir(&self) -> &Path {
+        &self.installed_plugins_dir
+    }
+
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.installed_plugins_dir.join(PLUGIN_LOCKFILE_NAME)
+    }
+
+    pub fn installed_plugin_path(&self, alias: &str) -> PathBuf {
+        self.installed_plugins_dir.join(format!("{}.wasm", alias))
+    }
+
+    pub fn locked(&self) -> &LockedPlugins {
+        &self.locked
+    }
+
+    pub fn get(&self, alias: &str) -> Option<&LockedPlugin> {
+        self.locked.get(alias)
+    }
+
+    /// The alias a plugin was installed as from this url, if it was
+    pub fn alias_of_url(&self, url: &str) -> Option<&str> {
+        self.locked
+            .iter()
+            .find(|(_alias, locked_plugin)| locked_plugin.url == url)
+            .map(|(alias, _locked_plugin)| alias.as_str())
+    }
+
+    /// Writes the plugin to the installed plugins folder and locks it, replacing a plugin that was
+    /// previously installed under the same alias
+    pub fn install(
+        &mut self,
+        alias: &str,
+        locked_plugin: LockedPlugin,
+        wasm_bytes: &[u8],
+    ) -> std::io::Result<()> {
+        fs::create_dir_all(&self.installed_plugins_dir)?;
+        let plugin_path = self.installed_plugin_path(alias);
+        let plugin_part_path = self
+            .installed_plugins_dir
+            .join(format!("{}.wasm.part", alias));
+        fs::write(&plugin_part_path, wasm_bytes)?;
+        fs::rename(&plugin_part_path, plugin_path)?;
+        self.locked.insert(alias.to_owned(), locked_plugin);
+        self.write_to_file()
+    }
+
+    pub fn remove(&mut self, alias: &str) -> std::io::Result<Option<LockedPlugin>> {
+        let removed = self.locked.remove(alias);
+        if removed.is_some() {
+            match fs::remove_file(self.installed_plugin_path(alias)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {},
+            }
+            self.write_to_file()?;
+        }
+        Ok(removed)
+    }
+
+    /// Makes the installed plugins available by their alias (eg. `zellij plugin -- my-plugin`)
+    pub fn plugin_aliases(&self) -> PluginAliases {
+        PluginAliases::from_data(
+            self.locked
+                .keys()
+                .map(|alias| {
+                    let run_plugin = RunPlugin {
+                        location: RunPluginLocation::File(self.installed_plugin_path(alias)),
+                        ..Default::default()
+                    };
+                    (alias.clone(), run_plugin)
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether `plugin_path` is the one of an installed plugin, which has to match its lockfile
+    pub fn is_installed(&self, plugin_path: &Path) -> bool {
+        plugin_path.parent() == Some(self.installed_plugins_dir.as_path())
+    }
+
+    /// Refuses the plugin bytes about to be loaded from `plugin_path` if it is an installed plugin
+    /// that no longer matches the hash it was locked with (eg. because the file was replaced)
+    pub fn verify(&self, plugin_path: &Path, wasm_bytes: &[u8]) -> Result<()> {
+        if !self.is_installed(plugin_path) {
+            return Ok(());
+        }
+        let alias = plugin_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match self.locked.get(&alias) {
+            Some(locked_plugin) if locked_plugin.sha256 == hash_plugin_bytes(wasm_bytes) => Ok(()),
+            Some(_) => Err(anyhow!(
+                "Plugin '{}' does not match the sha256 it was installed with, it can be reinstalled with: zellij plugin update {}",
+                alias,
+                alias
+            )),
+            None => Err(anyhow!(
+                "Plugin '{}' is not in the plugin lockfile at {}",
+                alias,
+                self.lockfile_path().display()
+            )),
+        }
+    }
+
+    pub fn write_to_file(&self) -> std::io::Result<()> {
+        fs::create_dir_all(&self.installed_plugins_dir)?;
+        let mut f = File::create(self.lockfile_path())?;
+        write!(f, "{}", PluginLockfile::to_string(&self.locked))?;
+        Ok(())
+    }
+
+    pub fn from_string(raw_string: String) -> Result<LockedPlugins, ConfigError> {
+        let kdl_document: KdlDocument = raw_string.parse()?;
+        let mut locked = LockedPlugins::default();
+        for node in kdl_document.nodes() {
+            let alias = node.get(0).and_then(|e| e.value().as_string());
+            let url = node.get("url").and_then(|e| e.value().as_string());
+            let version = node.get("version").and_then(|e| e.value().as_string());
+            let sha256 = node.get("sha256").and_then(|e| e.value().as_string());
+            if let (Some(alias), Some(url), Some(sha256)) = (alias, url, sha256) {
+                locked.insert(
+                    alias.to_owned(),
+                    LockedPlugin {
+                        url: url.to_owned(),
+                        version: version.map(|v| v.to_owned()),
+                        sha256: sha256.to_owned(),
+                    },
+                );
+            }
+        }
+        Ok(locked)
+    }
+
+    pub fn to_string(locked: &LockedPlugins) -> String {
+        let mut kdl_document = KdlDocument::new();
+        for (alias, locked_plugin) in locked {
+            let mut node = KdlNode::new("plugin");
+            node.push(alias.as_str());
+            node.push(("url", locked_plugin.url.as_str()));
+            if let Some(version) = &locked_plugin.version {
+                node.push(("version", version.as_str()));
+            }
+            node.push(("sha256", locked_plugin.sha256.as_str()));
+            kdl_document.nodes_mut().push(node);
+        }
+        kdl_document.fmt();
+        kdl_document.to_string()
+    }
+}
+
+#[cfg(test)]
+mod plugin_lockfile_tests {
+    use super::*;
+
+    fn locked_plugin(wasm_bytes: &[u8]) -> LockedPlugin {
+        LockedPlugin {
+            url: "https://example.com/releases/download/v0.2.1/my-plugin.wasm".to_owned(),
+            version: Some("v0.2.1".to_owned()),
+            sha256: hash_plugin_bytes(wasm_bytes),
+        }
+    }
+
+    #[test]
+    fn installed_plugin_is_locked_and_aliased() {
+        let plugin_dir = tempfile::tempdir().unwrap();
+        let mut plugin_lockfile = PluginLockfile::from_plugin_dir(plugin_dir.path());
+        plugin_lockfile
+            .install("my-plugin", locked_plugin(b"wasm"), b"wasm")
+            .unwrap();
+
+        let plugin_lockfile = PluginLockfile::from_plugin_dir(plugin_dir.path());
+        let installed_plugin_path = plugin_lockfile.installed_plugin_path("my-plugin");
+        assert_eq!(
+            plugin_lockfile.get("my-plugin"),
+            Some(&locked_plugin(b"wasm"))
+        );
+        assert_eq!(fs::read(&installed_plugin_path).unwrap(), b"wasm");
+        assert_eq!(
+            plugin_lockfile
+                .plugin_aliases()
+                .aliases
+                .get("my-plugin")
+                .map(|run_plugin| run_plugin.location.clone()),
+            Some(RunPluginLocation::File(installed_plugin_path))
+        );
+        assert_eq!(
+            plugin_lockfile
+                .alias_of_url("https://example.com/releases/download/v0.2.1/my-plugin.wasm"),
+            Some("my-plugin")
+        );
+    }
+
+    #[test]
+    fn changed_installed_plugin_is_refused() {
+        let plugin_dir = tempfile::tempdir().unwrap();
+        let mut plugin_lockfile = PluginLockfile::from_plugin_dir(plugin_dir.path());
+        plugin_lockfile
+            .install("my-plugin", locked_plugin(b"wasm"), b"wasm")
+            .unwrap();
+        let installed_plugin_path = plugin_lockfile.installed_plugin_path("my-plugin");
+        assert!(plugin_lockfile.is_installed(&installed_plugin_path));
+        assert!(!plugin_lockfile.is_installed(Path::new("/some/other/plugin.wasm")));
+        assert!(plugin_lockfile
+            .verify(&installed_plugin_path, b"wasm")
+            .is_ok());
+        assert!(plugin_lockfile
+            .verify(&installed_plugin_path, b"tampered wasm")
+            .is_err());
+        assert!(
+            plugin_lockfile
+                .verify(Path::new("/some/other/plugin.wasm"), b"anything")
+                .is_ok(),
+            "plugins that were not installed are not verified"
+        );
+
+        plugin_lockfile.remove("my-plugin").unwrap();
+        assert!(!installed_plugin_path.exists());
+        assert!(plugin_lockfile
+            .verify(&installed_plugin_path, b"wasm")
+            .is_err());
+    }
+
+    #[test]
+    fn version_is_taken_from_url() {
+        assert_eq!(
+            version_from_url("https://github.com/o/r/releases/download/v1.2.0/plugin.wasm"),
+            Some("v1.2.0".to_owned())
+        );
+        assert_eq!(
+            version_from_url("https://example.com/plugin/0.3.1-rc1/plugin.wasm"),
+            Some("0.3.1-rc1".to_owned())
+        );
+        assert_eq!(
+            version_from_url("https://github.com/o/r/releases/latest/download/plugin.wasm"),
+            None
+        );
+    }
+}
@@ -2,12 +2,14 @@
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use thiserror::Error;
 
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use super::layout::{PluginUserConfiguration, RunPlugin, RunPluginLocation};
+use super::options::Options;
 #[cfg(not(target_family = "wasm"))]
 use crate::consts::ASSET_MAP;
 pub use crate::data::PluginTag;
@@ -222,6 +224,70 @@ impl Default for PluginType {
     }
 }
 
+/// The resources a plugin may use before it is stopped, `None` means no limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PluginLimits {
+    /// The size (in bytes) the memory of the plugin may grow to
+    pub memory: Option<usize>,
+    /// How long a single call into the plugin (eg. its `update` or `render`) may run
+    pub call_timeout: Option<Duration>,
+}
+
+impl PluginLimits {
+    const DEFAULT_CALL_TIMEOUT_MS: u64 = 10_000;
+    pub fn from_options(options: &Options) -> Self {
+        PluginLimits::from_values(
+            options.plugin_memory_limit,
+            Some(
+                options
+                    .plugin_call_timeout
+                    .unwrap_or(Self::DEFAULT_CALL_TIMEOUT_MS),
+            ),
+        )
+    }
+    /// The limits of a single plugin, a `memory_limit` (in megabytes) or `call_timeout` (in
+    /// milliseconds) in its configuration take precedence over these
+    pub fn for_plugin(&self, configuration: &PluginUserConfiguration) -> Self {
+        let configured_value = |key: &str| -> Option<u64> {
+            let value = configuration.settings().get(key)?;
+            match value.parse() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    log::error!("Ignoring invalid plugin {}: {}", key, value);
+                    None
+                },
+            }
+        };
+        let memory_limit = configured_value("memory_limit");
+        let call_timeout = configured_value("call_timeout");
+        PluginLimits {
+            memory: match memory_limit {
+                Some(memory_limit) => {
+                    PluginLimits::from_values(Some(memory_limit as usize), None).memory
+                },
+                None => self.memory,
+            },
+            call_timeout: match call_timeout {
+                Some(call_timeout) => {
+                    PluginLimits::from_values(None, Some(call_timeout)).call_timeout
+                },
+                None => self.call_timeout,
+            },
+        }
+    }
+    // 0 means no limit for both
+    fn from_values(memory_limit_mb: Option<usize>, call_timeout_ms: Option<u64>) -> Self {
+        PluginLimits {
+            memory: memory_limit_mb
+                .filter(|memory_limit_mb| *memory_limit_mb > 0)
+                .map(|memory_limit_mb| memory_limit_mb.saturating_mul(1024 * 1024)),
+            call_timeout: call_timeout_ms
+                .filter(|call_timeout_ms| *call_timeout_ms > 0)
+                .map(Duration::from_millis),
+        }
+    }
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum PluginsConfigError {
     #[error("Duplication in plugin tag names is not allowed: '{}'", String::from(.0.clone()))]
@@ -233,3 +299,63 @@ pub enum PluginsConfigError {
     #[error("Could not find plugin at the path: '{0:?}'")]
     InvalidPluginLocation(PathBuf),
 }
+
+#[cfg(test)]
+mod plugin_limits_tests {
+    use super::*;
+
+    #[test]
+    fn plugin_configuration_overrides_global_limits() {
+        let options = Options {
+            plugin_memory_limit: Some(512),
+            ..Default::default()
+        };
+        let global_limits = PluginLimits::from_options(&options);
+        assert_eq!(global_limits.memory, Some(512 * 1024 * 1024));
+        assert_eq!(global_limits.call_timeout, Some(Duration::from_secs(10)));
+
+        let mut configuration = BTreeMap::new();
+        configuration.insert("call_timeout".to_owned(), "0".to_owned());
+        configuration.insert("memory_limit".to_owned(), "64".to_owned());
+        let plugin_limits = global_limits.for_plugin(&PluginUserConfiguration::new(configuration));
+        assert_eq!(plugin_limits.memory, Some(64 * 1024 * 1024));
+        assert_eq!(plugin_limits.call_timeout, None, "0 means no limit");
+
+        let mut configuration = BTreeMap::new();
+        configuration.insert("memory_limit".to_owned(), "lots".to_owned());
+        let plugin_limits = global_limits.for_plugin(&PluginUserConfiguration::new(configuration));
+        assert_eq!(plugin_limits, global_limits, "invalid values are ignored");
+    }
+
+    #[test]
+    fn plugin_limits_are_not_passed_to_the_plugin() {
+        let mut configuration = BTreeMap::new();
+        configuration.insert("memory_limit".to_owned(), "64".to_owned());
+        configuration.insert("call_timeout".to_owned(), "100".to_owned());
+        configuration.insert("my_key".to_owned(), "my_value".to_owned());
+        let configuration = PluginUserConfiguration::new(configuration);
+        assert_eq!(
+            configuration.inner().keys().collect::<Vec<_>>(),
+            vec!["my_key"]
+        );
+        assert_eq!(
+            configuration.settings().keys().collect::<Vec<_>>(),
+            vec!["call_timeout", "memory_limit"]
+        );
+    }
+
+    #[test]
+    fn negative_plugin_limits_are_config_errors() {
+        use crate::input::config::Config;
+        assert!(Config::from_kdl("plugin_memory_limit -1", None).is_err());
+        assert!(Config::from_kdl("plugin_call_timeout -1", None).is_err());
+        let plugin_with_negative_limit = r#"
+            plugins {
+                my-plugin location="file:/path/to/plugin.wasm" {
+                    call_timeout -100
+                }
+            }
+        "#;
+        assert!(Config::from_kdl(plugin_with_negative_limit, None).is_err());
+    }
+}
@@ -0,0 +1,350 @@
+//! Imports the colorschemes of other terminal emulators as Zellij themes.
+use crate::data::{Palette, PaletteColor};
+use crate::input::config::{ConfigError, ConversionError};
+use crate::input::theme::{Theme, Themes};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A colorscheme format of another terminal emulator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeFormat {
+    /// The `colors` section of an Alacritty TOML or YAML configuration
+    Alacritty,
+    /// The color keys of a kitty `.conf` file
+    Kitty,
+    /// A WezTerm color scheme TOML file
+    WezTerm,
+    /// A base16 scheme YAML file
+    Base16,
+}
+
+impl FromStr for ThemeFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "alacritty" => Ok(ThemeFormat::Alacritty),
+            "kitty" => Ok(ThemeFormat::Kitty),
+            "wezterm" => Ok(ThemeFormat::WezTerm),
+            "base16" => Ok(ThemeFormat::Base16),
+            _ => Err(format!(
+                "Unknown theme format: {}, expected one of: alacritty, kitty, wezterm, base16",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ThemeFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThemeFormat::Alacritty => write!(f, "alacritty"),
+            ThemeFormat::Kitty => write!(f, "kitty"),
+            ThemeFormat::WezTerm => write!(f, "wezterm"),
+            ThemeFormat::Base16 => write!(f, "base16"),
+        }
+    }
+}
+
+impl ThemeFormat {
+    /// Guesses the format of a theme file from its extension and contents, returns `None` for
+    /// files that do not look like a theme of any of the supported formats
+    pub fn detect(path: &Path, contents: &str) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "conf" => {
+                let colors = flatten_kitty(contents);
+                (colors.contains_key("foreground") || colors.contains_key("color0"))
+                    .then_some(ThemeFormat::Kitty)
+            },
+            "toml" => {
+                let colors = flatten_toml(contents).ok()?;
+                if colors.contains_key("colors.ansi.0") {
+                    Some(ThemeFormat::WezTerm)
+                } else if colors.contains_key("colors.normal.black") {
+                    Some(ThemeFormat::Alacritty)
+                } else {
+                    None
+                }
+            },
+            "yml" | "yaml" => {
+                let colors = flatten_yaml(contents).ok()?;
+                if colors.contains_key("base00") || colors.contains_key("palette.base00") {
+                    Some(ThemeFormat::Base16)
+                } else if colors.contains_key("colors.normal.black") {
+                    Some(ThemeFormat::Alacritty)
+                } else {
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+    /// Reads a theme in this format into a [`Palette`]
+    pub fn palette_from_str(&self, contents: &str) -> Result<Palette, String> {
+        match self {
+            ThemeFormat::Alacritty => {
+                // Alacritty supports both TOML (newer) and YAML (older) configuration files
+                let colors = flatten_toml(contents).or_else(|_| flatten_yaml(contents))?;
+                palette_from_colors(
+                    &colors,
+                    "colors.primary.foreground",
+                    "colors.primary.background",
+                    |color| format!("colors.normal.{}", color),
+                )
+            },
+            ThemeFormat::Kitty => {
+                let colors = flatten_kitty(contents);
+                palette_from_colors(&colors, "foreground", "background", |color| {
+                    format!("color{}", ansi_index(color))
+                })
+            },
+            ThemeFormat::WezTerm => {
+                let colors = flatten_toml(contents)?;
+                palette_from_colors(&colors, "colors.foreground", "colors.background", |color| {
+                    format!("colors.ansi.{}", ansi_index(color))
+                })
+            },
+            ThemeFormat::Base16 => {
+                let mut colors = flatten_yaml(contents)?;
+                // the newer tinted-theming schemes nest the colors under "palette"
+                let nested: Vec<(String, String)> = colors
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        key.strip_prefix("palette.")
+                            .map(|key| (key.to_owned(), value.clone()))
+                    })
+                    .collect();
+                colors.extend(nested);
+                let color = |key: &str| lookup_color(&colors, key);
+                Ok(Palette {
+                    fg: color("base05")?,
+                    bg: color("base00")?,
+                    black: color("base00")?,
+                    red: color("base08")?,
+                    green: color("base0b")?,
+                    yellow: color("base0a")?,
+                    blue: color("base0d")?,
+                    magenta: color("base0e")?,
+                    cyan: color("base0c")?,
+                    white: color("base05")?,
+                    orange: color("base09")?,
+                    ..Default::default()
+                })
+            },
+        }
+    }
+}
+
+/// Reads the theme file at `path`, naming the theme after the file
+pub fn themes_from_path(
+    path: &Path,
+    format: ThemeFormat,
+    sourced_from_external_file: bool,
+) -> Result<Themes, ConfigError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| ConfigError::IoPath(e, path.to_path_buf()))?;
+    themes_from_str(path, &contents, format, sourced_from_external_file)
+}
+
+pub(crate) fn themes_from_str(
+    path: &Path,
+    contents: &str,
+    format: ThemeFormat,
+    sourced_from_external_file: bool,
+) -> Result<Themes, ConfigError> {
+    let palette = format.palette_from_str(contents).map_err(|e| {
+        ConfigError::ConversionError(ConversionError::InvalidTheme(format!(
+            "Failed to import {} theme {}: {}",
+            format,
+            path.display(),
+            e
+        )))
+    })?;
+    let theme_name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| format.to_string());
+    let mut themes = HashMap::new();
+    themes.insert(
+        theme_name,
+        Theme {
+            palette,
+            sourced_from_external_file,
+        },
+    );
+    Ok(Themes::from_data(themes))
+}
+
+const ANSI_COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+fn ansi_index(color: &str) -> usize {
+    ANSI_COLORS.iter().position(|c| *c == color).unwrap_or(0)
+}
+
+fn palette_from_colors(
+    colors: &BTreeMap<String, String>,
+    fg_key: &str,
+    bg_key: &str,
+    ansi_key: impl Fn(&str) -> String,
+) -> Result<Palette, String> {
+    let color = |key: &str| lookup_color(colors, key);
+    let red = color(&ansi_key("red"))?;
+    let yellow = color(&ansi_key("yellow"))?;
+    Ok(Palette {
+        fg: color(fg_key)?,
+        bg: color(bg_key)?,
+        black: color(&ansi_key("black"))?,
+        red,
+        green: color(&ansi_key("green"))?,
+        yellow,
+        blue: color(&ansi_key("blue"))?,
+        magenta: color(&ansi_key("magenta"))?,
+        cyan: color(&ansi_key("cyan"))?,
+        white: color(&ansi_key("white"))?,
+        // terminal colorschemes have no orange, so we blend it from red and yellow
+        orange: blend(red, yellow),
+        ..Default::default()
+    })
+}
+
+fn lookup_color(colors: &BTreeMap<String, String>, key: &str) -> Result<PaletteColor, String> {
+    let value = colors
+        .get(key)
+        .ok_or_else(|| format!("missing color: {}", key))?;
+    parse_color(value).ok_or_else(|| format!("invalid color for {}: {}", key, value))
+}
+
+fn blend(first: PaletteColor, second: PaletteColor) -> PaletteColor {
+    match (first, second) {
+        (PaletteColor::Rgb((r1, g1, b1)), PaletteColor::Rgb((r2, g2, b2))) => {
+            let mix = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+            PaletteColor::Rgb((mix(r1, r2), mix(g1, g2), mix(b1, b2)))
+        },
+        (first, _) => first,
+    }
+}
+
+/// Parses colors in the `#RRGGBB`, `#RGB`, `0xRRGGBB` and `RRGGBB` forms
+fn parse_color(color: &str) -> Option<PaletteColor> {
+    let color = color.trim().trim_matches(|c| c == '"' || c == '\'');
+    let hex = color
+        .strip_prefix('#')
+        .or_else(|| color.strip_prefix("0x"))
+        .or_else(|| color.strip_prefix("0X"))
+        .unwrap_or(color);
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        6 => Some(PaletteColor::Rgb((
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ))),
+        3 => Some(PaletteColor::Rgb((
+            channel(&hex[0..1])? * 0x11,
+            channel(&hex[1..2])? * 0x11,
+            channel(&hex[2..3])? * 0x11,
+        ))),
+        _ => None,
+    }
+}
+
+/// Reads the `key value` lines of a kitty configuration, ignoring comments
+fn flatten_kitty(contents: &str) -> BTreeMap<String, String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?.to_lowercase();
+            let value = parts.next()?.to_owned();
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// Flattens a TOML document into dotted keys, with array items keyed by their index
+fn flatten_toml(contents: &str) -> Result<BTreeMap<String, String>, String> {
+    fn flatten(prefix: String, value: &toml::Value, flat: &mut BTreeMap<String, String>) {
+        let key = |k: &str| {
+            if prefix.is_empty() {
+                k.to_lowercase()
+            } else {
+                format!("{}.{}", prefix, k.to_lowercase())
+            }
+        };
+        match value {
+            toml::Value::Table(table) => {
+                for (k, v) in table {
+                    flatten(key(k), v, flat);
+                }
+            },
+            toml::Value::Array(array) => {
+                for (i, v) in array.iter().enumerate() {
+                    flatten(key(&i.to_string()), v, flat);
+                }
+            },
+            toml::Value::String(s) => {
+                flat.insert(prefix, s.clone());
+            },
+            other => {
+                flat.insert(prefix, other.to_string());
+            },
+        }
+    }
+    let value: toml::Value = contents.parse().map_err(|e| format!("{}", e))?;
+    let mut flat = BTreeMap::new();
+    flatten(String::new(), &value, &mut flat);
+    Ok(flat)
+}
+
+/// Flattens a YAML document into dotted keys, with sequence items keyed by their index
+fn flatten_yaml(contents: &str) -> Result<BTreeMap<String, String>, String> {
+    fn flatten(prefix: String, value: &serde_yaml::Value, flat: &mut BTreeMap<String, String>) {
+        let key = |k: &str| {
+            if prefix.is_empty() {
+                k.to_lowercase()
+            } else {
+                format!("{}.{}", prefix, k.to_lowercase())
+            }
+        };
+        match value {
+            serde_yaml::Value::Mapping(mapping) => {
+                for (k, v) in mapping {
+                    let k = match k {
+                        serde_yaml::Value::String(s) => s.clone(),
+                        serde_yaml::Value::Number(n) => n.to_string(),
+                        _ => continue,
+                    };
+                    flatten(key(&k), v, flat);
+                }
+            },
+            serde_yaml::Value::Sequence(sequence) => {
+                for (i, v) in sequence.iter().enumerate() {
+                    flatten(key(&i.to_string()), v, flat);
+                }
+            },
+            serde_yaml::Value::String(s) => {
+                flat.insert(prefix, s.clone());
+            },
+            serde_yaml::Value::Number(n) => {
+                flat.insert(prefix, n.to_string());
+            },
+            _ => {},
+        }
+    }
+    let value: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| format!("{}", e))?;
+    let mut flat = BTreeMap::new();
+    flatten(String::new(), &value, &mut flat);
+    Ok(flat)
+}
+
+#[cfg(test)]
+#[path = "./unit/theme_import_test.rs"]
+mod theme_import_test;
@@ -554,3 +554,385 @@ fn error_received_on_unknown_key_instruction() {
     let config_error = Config::from_kdl(config_contents, None).unwrap_err();
     assert_snapshot!(format!("{:?}", config_error));
 }
+
+#[test]
+fn can_define_key_sequences() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl a" "c" sequence=true { NewTab; }
+                bind "Ctrl a" "Ctrl a" sequence=true { SwitchToMode "Locked"; }
+            }
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let ctrl_a = KeyWithModifier::new(BareKey::Char('a')).with_ctrl_modifier();
+    let c = KeyWithModifier::new(BareKey::Char('c'));
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Normal, &[ctrl_a.clone()]),
+        KeySequenceMatch::Partial,
+        "First key of a sequence is a partial match"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Normal, &[ctrl_a.clone(), c.clone()]),
+        KeySequenceMatch::Complete(vec![Action::NewTab(None, vec![], None, None, None)]),
+        "Full sequence is a complete match"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Normal, &[ctrl_a.clone(), ctrl_a.clone()]),
+        KeySequenceMatch::Complete(vec![Action::SwitchToMode(InputMode::Locked)]),
+        "Sequence can repeat the same key"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Normal, &[c.clone()]),
+        KeySequenceMatch::NoMatch,
+        "Key that does not start a sequence does not match"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Pane, &[ctrl_a.clone(), c.clone()]),
+        KeySequenceMatch::NoMatch,
+        "Sequence is only bound in its own mode"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .get_actions_for_key_in_mode(&InputMode::Normal, &ctrl_a),
+        None,
+        "Sequence does not bind its keys individually"
+    );
+}
+
+#[test]
+fn can_unbind_key_sequences() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl a" "c" sequence=true { NewTab; }
+                bind "Ctrl a" "x" sequence=true { CloseFocus; }
+                unbind "Ctrl a" "c" sequence=true
+            }
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let ctrl_a = KeyWithModifier::new(BareKey::Char('a')).with_ctrl_modifier();
+    assert_eq!(
+        config.keybinds.match_key_sequence(
+            &InputMode::Normal,
+            &[ctrl_a.clone(), KeyWithModifier::new(BareKey::Char('c'))]
+        ),
+        KeySequenceMatch::NoMatch,
+        "Sequence was unbound"
+    );
+    assert_eq!(
+        config.keybinds.match_key_sequence(
+            &InputMode::Normal,
+            &[ctrl_a.clone(), KeyWithModifier::new(BareKey::Char('x'))]
+        ),
+        KeySequenceMatch::Complete(vec![Action::CloseFocus]),
+        "Other sequence with the same prefix is still bound"
+    );
+}
+
+#[test]
+fn unbinding_a_key_unbinds_the_sequences_it_starts() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl x" "2" sequence=true { NewPane "Down"; }
+                bind "Ctrl a" "c" sequence=true { NewTab; }
+                unbind "Ctrl a"
+            }
+            unbind "Ctrl x"
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let ctrl_x = KeyWithModifier::new(BareKey::Char('x')).with_ctrl_modifier();
+    let ctrl_a = KeyWithModifier::new(BareKey::Char('a')).with_ctrl_modifier();
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Normal, &[ctrl_x]),
+        KeySequenceMatch::NoMatch,
+        "Globally unbound key is no longer the prefix of a sequence"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .match_key_sequence(&InputMode::Normal, &[ctrl_a]),
+        KeySequenceMatch::NoMatch,
+        "Key unbound in a mode is no longer the prefix of a sequence"
+    );
+}
+
+#[test]
+fn error_received_on_key_sequence_with_a_single_key() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl a" sequence=true { NewTab; }
+            }
+        }
+    "#;
+    let config_error = Config::from_kdl(config_contents, None).unwrap_err();
+    assert_snapshot!(format!("{:?}", config_error));
+}
+
+#[test]
+fn can_define_conditional_keybinds() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl h" if_not_running="nvim|vim" { MoveFocus "Left"; }
+                bind "Ctrl q" { Quit; }
+                bind "Ctrl q" if_plugin="zellij:strider" if_pane_title="Strider.*" { CloseFocus; }
+            }
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let ctrl_h = KeyWithModifier::new(BareKey::Char('h')).with_ctrl_modifier();
+    let ctrl_q = KeyWithModifier::new(BareKey::Char('q')).with_ctrl_modifier();
+    let running = |command: &str| FocusedPaneInfo {
+        running_command: Some(command.to_owned()),
+        title: Some(String::from("Pane #1")),
+        plugin_url: None,
+    };
+    let strider = FocusedPaneInfo {
+        running_command: None,
+        title: Some(String::from("Strider - /tmp")),
+        plugin_url: Some(String::from("zellij:strider")),
+    };
+    let actions_for = |key: &KeyWithModifier, focused_pane: &FocusedPaneInfo| {
+        config
+            .keybinds
+            .get_actions_for_key_in_mode_with_focused_pane(
+                &InputMode::Normal,
+                key,
+                vec![8],
+                false,
+                focused_pane,
+            )
+    };
+    assert_eq!(
+        actions_for(&ctrl_h, &running("bash")),
+        vec![Action::MoveFocus(Direction::Left)],
+        "Condition holds"
+    );
+    assert_eq!(
+        actions_for(&ctrl_h, &running("nvim")),
+        vec![Action::Write(Some(ctrl_h.clone()), vec![8], false)],
+        "Key is written to the pane when the condition does not hold"
+    );
+    assert_eq!(
+        actions_for(&ctrl_h, &running("gvim")),
+        vec![Action::MoveFocus(Direction::Left)],
+        "Pattern has to match the whole process name"
+    );
+    assert_eq!(
+        actions_for(&ctrl_q, &strider),
+        vec![Action::CloseFocus],
+        "All conditions hold"
+    );
+    assert_eq!(
+        actions_for(&ctrl_q, &running("bash")),
+        vec![Action::Write(Some(ctrl_q.clone()), vec![8], false)],
+        "Key is written to the pane when none of its conditions hold"
+    );
+}
+
+#[test]
+fn error_received_on_invalid_keybind_condition_pattern() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl h" if_not_running="(nvim|vim" { MoveFocus "Left"; }
+            }
+        }
+    "#;
+    let config_error = Config::from_kdl(config_contents, None).unwrap_err();
+    assert_snapshot!(format!("{:?}", config_error));
+}
+
+#[test]
+fn unbind_removes_conditional_keybinds() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl h" if_not_running="vim" { MoveFocus "Left"; }
+                unbind "Ctrl h"
+            }
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let ctrl_h = KeyWithModifier::new(BareKey::Char('h')).with_ctrl_modifier();
+    assert!(
+        !config
+            .keybinds
+            .has_conditional_keybinds_for_key(&InputMode::Normal, &ctrl_h),
+        "Conditional keybind was unbound"
+    );
+}
+
+#[test]
+fn error_received_on_unknown_keybind_condition() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl h" if_runing="vim" { MoveFocus "Left"; }
+            }
+        }
+    "#;
+    let config_error = Config::from_kdl(config_contents, None).unwrap_err();
+    assert_snapshot!(format!("{:?}", config_error));
+}
+
+#[test]
+fn can_define_custom_input_modes() {
+    let config_contents = r#"
+        keybinds {
+            normal {
+                bind "Ctrl d" { SwitchToMode "deploy"; }
+            }
+            custom_mode "deploy" {
+                bind "s" { SwitchToMode "Normal"; }
+            }
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let deploy = InputMode::Custom("deploy".to_owned());
+    let ctrl_d_normal_mode_action = config.keybinds.get_actions_for_key_in_mode(
+        &InputMode::Normal,
+        &KeyWithModifier::new(BareKey::Char('d')).with_ctrl_modifier(),
+    );
+    let s_deploy_mode_action = config
+        .keybinds
+        .get_actions_for_key_in_mode(&deploy, &KeyWithModifier::new(BareKey::Char('s')));
+    assert_eq!(
+        ctrl_d_normal_mode_action,
+        Some(&vec![Action::SwitchToMode(deploy.clone())]),
+        "Can switch to a custom mode"
+    );
+    assert_eq!(
+        s_deploy_mode_action,
+        Some(&vec![Action::SwitchToMode(InputMode::Normal)]),
+        "Keys are bound in the custom mode"
+    );
+    assert_eq!(
+        config
+            .keybinds
+            .get_actions_for_key_in_mode_or_default_action(
+                &deploy,
+                &KeyWithModifier::new(BareKey::Char('x')),
+                vec![],
+                true,
+            ),
+        vec![Action::NoOp],
+        "Unbound keys are ignored in custom modes"
+    );
+}
+
+#[test]
+fn can_include_custom_modes_in_shared_among_blocks() {
+    let config_contents = r#"
+        keybinds {
+            shared_among "normal" "deploy" {
+                bind "Alt b" { Run "make" "build"; }
+            }
+            custom_mode "deploy" {
+                bind "s" { SwitchToMode "Normal"; }
+            }
+        }
+    "#;
+    let config = Config::from_kdl(config_contents, None).unwrap();
+    let alt_b_deploy_mode_action = config.keybinds.get_actions_for_key_in_mode(
+        &InputMode::Custom("deploy".to_owned()),
+        &KeyWithModifier::new(BareKey::Char('b')).with_alt_modifier(),
+    );
+    assert!(
+        alt_b_deploy_mode_action.is_some(),
+        "Keys are bound in custom modes included in shared_among blocks"
+    );
+}
+
+#[test]
+fn can_switch_to_custom_mode_from_cli() {
+    use crate::cli::{CliArgs, Command, Sessions};
+    use clap::Parser;
+    let cli_args = CliArgs::try_parse_from(["zellij", "action", "switch-mode", "deploy"]).unwrap();
+    let cli_action = match cli_args.command {
+        Some(Command::Sessions(Sessions::Action(cli_action))) => cli_action,
+        command => panic!("Unexpected command: {:?}", command),
+    };
+    let actions =
+        Action::actions_from_cli(cli_action, Box::new(|| std::path::PathBuf::new()), None);
+    assert_eq!(
+        actions,
+        Ok(vec![Action::SwitchModeForAllClients(InputMode::Custom(
+            "deploy".to_owned()
+        ))]),
+        "Custom modes can be switched to from the cli"
+    );
+}
+
+#[test]
+fn error_received_on_custom_mode_with_builtin_name() {
+    let config_contents = r#"
+        keybinds {
+            custom_mode "pane" {
+                bind "s" { SwitchToMode "Normal"; }
+            }
+        }
+    "#;
+    let config_error = Config::from_kdl(config_contents, None).unwrap_err();
+    assert_snapshot!(format!("{:?}", config_error));
+}
+
+#[test]
+fn can_declare_custom_input_mode_from_stringified_keybinds() {
+    let stringified_config =
+        Keybinds::custom_input_mode_config("git", r#"bind "c" { SwitchToMode "Normal"; }"#)
+            .unwrap();
+    let config = Config::from_kdl(&stringified_config, None).unwrap();
+    let git = InputMode::Custom("git".to_owned());
+    assert_eq!(
+        config
+            .keybinds
+            .get_actions_for_key_in_mode(&git, &KeyWithModifier::new(BareKey::Char('c'))),
+        Some(&vec![Action::SwitchToMode(InputMode::Normal)]),
+        "Custom mode declared from plugin keybindings"
+    );
+}
+
+#[test]
+fn prompts_can_be_answered_without_prompt_mode_keybindings() {
+    let keybinds = Keybinds::default();
+    let actions_for_key = |key: KeyWithModifier| {
+        keybinds.get_actions_for_key_in_mode_or_default_action(
+            &InputMode::Prompt,
+            &key,
+            vec![],
+            false,
+        )
+    };
+    assert_eq!(
+        actions_for_key(KeyWithModifier::new(BareKey::Char('y'))),
+        vec![Action::Confirm]
+    );
+    assert_eq!(
+        actions_for_key(KeyWithModifier::new(BareKey::Esc)),
+        vec![Action::Deny]
+    );
+    assert_eq!(
+        actions_for_key(KeyWithModifier::new(BareKey::Char('y')).with_ctrl_modifier()),
+        vec![Action::NoOp]
+    );
+}
@@ -524,6 +524,168 @@ fn layout_with_command_panes_and_start_suspended() {
     assert_snapshot!(format!("{:#?}", layout));
 }
 
+#[test]
+fn layout_with_command_panes_and_restart_policy() {
+    let kdl_layout = r#"
+        layout {
+            pane command="cargo" restart="on-failure" {
+                args "run"
+                max_restarts 5
+            }
+            pane command="htop" {
+                restart "always"
+            }
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None).unwrap();
+    assert_snapshot!(format!("{:#?}", layout));
+}
+
+#[test]
+fn cannot_define_restart_policy_without_command() {
+    let kdl_layout = r#"
+        layout {
+            pane restart="always"
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "error provided");
+}
+
+#[test]
+fn cannot_define_invalid_restart_policy() {
+    let kdl_layout = r#"
+        layout {
+            pane command="htop" restart="sometimes"
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "error provided");
+}
+
+#[test]
+fn layout_with_command_panes_and_rerun_on_change() {
+    let kdl_layout = r#"
+        layout {
+            pane command="cargo" rerun_on_change="src/**/*.rs" {
+                args "test"
+            }
+            pane command="make" {
+                rerun_on_change "src/**" "Makefile"
+                kill_on_change true
+            }
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None).unwrap();
+    assert_snapshot!(format!("{:#?}", layout));
+}
+
+#[test]
+fn cannot_define_rerun_on_change_with_invalid_glob() {
+    let kdl_layout = r#"
+        layout {
+            pane command="cargo" rerun_on_change="src/[*.rs"
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "error provided");
+}
+
+#[test]
+fn layout_with_layout_vars() {
+    let kdl_layout = r#"
+        vars {
+            project "api"
+            port 8080
+            project_dir "/home/user/${project}"
+        }
+        layout {
+            pane name="${project}" cwd="${project_dir}" command="cargo" {
+                args "run" "--" "--port" "${port}" "--log-dir" "${env.ZELLIJ_LAYOUT_VARS_TEST_LOG_DIR}"
+            }
+            pane command="bash" {
+                args "-c" "echo $${HOME}"
+            }
+        }
+    "#;
+    std::env::set_var("ZELLIJ_LAYOUT_VARS_TEST_LOG_DIR", "/tmp/logs");
+    let layout_vars = vec![("port".to_owned(), "9090".to_owned())]
+        .into_iter()
+        .collect();
+    let layout = Layout::from_kdl_with_vars(
+        kdl_layout,
+        Some("layout_file_name".into()),
+        None,
+        None,
+        layout_vars,
+    )
+    .unwrap();
+    assert_snapshot!(format!("{:#?}", layout));
+}
+
+#[test]
+fn config_in_layout_uses_layout_vars() {
+    let layout_dir = tempfile::tempdir().unwrap();
+    let layout_path = layout_dir.path().join("layout.kdl");
+    std::fs::write(
+        &layout_path,
+        r#"
+        vars {
+            theme "dracula"
+        }
+        theme "${theme}"
+        layout {
+            pane
+        }
+    "#,
+    )
+    .unwrap();
+    let (_layout, config) = Layout::from_path_or_default(
+        Some(&layout_path),
+        None,
+        Config::default(),
+        Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        config.options.theme,
+        Some("dracula".to_owned()),
+        "default from the vars block"
+    );
+    let layout_vars = vec![("theme".to_owned(), "gruvbox".to_owned())]
+        .into_iter()
+        .collect();
+    let (_layout, config) =
+        Layout::from_path_or_default(Some(&layout_path), None, Config::default(), layout_vars)
+            .unwrap();
+    assert_eq!(config.options.theme, Some("gruvbox".to_owned()));
+}
+
+#[test]
+fn cannot_reference_undefined_layout_var() {
+    let kdl_layout = r#"
+        layout {
+            pane name="${project}"
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "error provided");
+}
+
+#[test]
+fn cannot_define_layout_var_without_default_value() {
+    let kdl_layout = r#"
+        vars {
+            project
+        }
+        layout {
+            pane name="${project}"
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "error provided");
+}
+
 #[test]
 fn layout_with_plugin_panes() {
     let kdl_layout = r#"
@@ -574,7 +736,9 @@ fn layout_with_plugin_panes() {
                         run: Some(Run::Plugin(RunPluginOrAlias::RunPlugin(RunPlugin {
                             location: RunPluginLocation::Zellij(PluginTag::new("status-bar")),
                             _allow_exec_host_cmd: false,
-                            configuration: PluginUserConfiguration(expected_plugin_configuration),
+                            configuration: PluginUserConfiguration::new(
+                                expected_plugin_configuration,
+                            ),
                             ..Default::default()
                         }))),
                         ..Default::default()
@@ -589,6 +753,80 @@ fn layout_with_plugin_panes() {
     assert_eq!(layout, expected_layout);
 }
 
+#[test]
+fn layout_with_plugin_reloaded_on_change() {
+    let kdl_layout = r#"
+        layout {
+            pane {
+                plugin location="file:/path/to/my/plugin.wasm" {
+                    reload_on_change true
+                }
+            }
+            pane {
+                plugin location="file:/path/to/my/other/plugin.wasm"
+            }
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None).unwrap();
+    let plugins_reloaded_on_change: Vec<bool> = layout
+        .template
+        .unwrap()
+        .0
+        .children
+        .iter()
+        .map(|pane| match &pane.run {
+            Some(Run::Plugin(RunPluginOrAlias::RunPlugin(run_plugin))) => {
+                assert!(
+                    run_plugin.configuration.inner().is_empty(),
+                    "reload_on_change is not passed to the plugin"
+                );
+                run_plugin.configuration.reload_on_change()
+            },
+            _ => panic!("expected a plugin pane"),
+        })
+        .collect();
+    assert_eq!(plugins_reloaded_on_change, vec![true, false]);
+}
+
+#[test]
+fn layout_with_a_tab_bar_pane() {
+    let kdl_layout = r#"
+        layout {
+            pane_template name="bar" tab_bar=true
+            pane tab_bar=true {
+                plugin location="zellij:tab-bar"
+            }
+            pane
+            bar {
+                plugin location="zellij:compact-bar"
+            }
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None).unwrap();
+    let tab_bar_panes: Vec<bool> = layout
+        .template
+        .unwrap()
+        .0
+        .children
+        .iter()
+        .map(|pane| pane.tab_bar)
+        .collect();
+    assert_eq!(tab_bar_panes, vec![true, false, true]);
+}
+
+#[test]
+fn cannot_mark_a_floating_pane_as_the_tab_bar() {
+    let kdl_layout = r#"
+        layout {
+            floating_panes {
+                pane tab_bar=true
+            }
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "invalid floating pane property");
+}
+
 #[test]
 fn layout_with_borderless_panes() {
     let kdl_layout = r#"
@@ -2242,3 +2480,33 @@ fn env_var_missing() {
     let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
     assert!(layout.is_err(), "invalid env var lookup should fail");
 }
+
+#[test]
+fn layout_with_pane_and_tab_colors() {
+    let kdl_layout = r##"
+        layout {
+            tab frame_color="#1e1e2e" {
+                pane frame_color="red" frame_color_focused="#0f0" background=235
+                pane {
+                    background "#101020"
+                }
+                floating_panes {
+                    pane frame_color_focused="yellow"
+                }
+            }
+        }
+    "##;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert_snapshot!(format!("{:#?}", layout));
+}
+
+#[test]
+fn layout_with_invalid_pane_color() {
+    let kdl_layout = r#"
+        layout {
+            pane frame_color="not-a-color"
+        }
+    "#;
+    let layout = Layout::from_kdl(kdl_layout, Some("layout_file_name".into()), None, None);
+    assert!(layout.is_err(), "invalid color should fail");
+}
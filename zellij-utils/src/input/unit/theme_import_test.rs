@@ -0,0 +1,92 @@
+use super::*;
+use crate::data::PaletteColor;
+use std::path::{Path, PathBuf};
+
+fn foreign_theme_dir() -> PathBuf {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    root.join("src/input/unit/fixtures/foreign-themes")
+}
+
+fn import(file_name: &str, format: ThemeFormat) -> Palette {
+    let path = foreign_theme_dir().join(file_name);
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        ThemeFormat::detect(&path, &contents),
+        Some(format),
+        "format detected from the file"
+    );
+    format.palette_from_str(&contents).unwrap()
+}
+
+#[test]
+fn can_import_alacritty_toml_theme() {
+    let palette = import("alacritty-tomorrow.toml", ThemeFormat::Alacritty);
+    assert_eq!(palette.fg, PaletteColor::Rgb((0xc5, 0xc8, 0xc6)));
+    assert_eq!(palette.bg, PaletteColor::Rgb((0x1d, 0x1f, 0x21)));
+    assert_eq!(palette.red, PaletteColor::Rgb((0xcc, 0x66, 0x66)));
+    assert_eq!(palette.white, PaletteColor::Rgb((0xc5, 0xc8, 0xc6)));
+    // blended from red and yellow
+    assert_eq!(palette.orange, PaletteColor::Rgb((0xde, 0x96, 0x6d)));
+}
+
+#[test]
+fn can_import_alacritty_yaml_theme() {
+    let toml_palette = import("alacritty-tomorrow.toml", ThemeFormat::Alacritty);
+    let yaml_palette = import("alacritty-tomorrow-old.yml", ThemeFormat::Alacritty);
+    assert_eq!(toml_palette, yaml_palette);
+}
+
+#[test]
+fn can_import_kitty_theme() {
+    let palette = import("kitty-gruvbox.conf", ThemeFormat::Kitty);
+    assert_eq!(palette.fg, PaletteColor::Rgb((0xeb, 0xdb, 0xb2)));
+    assert_eq!(palette.bg, PaletteColor::Rgb((0x28, 0x28, 0x28)));
+    assert_eq!(palette.green, PaletteColor::Rgb((0x98, 0x97, 0x1a)));
+    assert_eq!(palette.white, PaletteColor::Rgb((0xa8, 0x99, 0x84)));
+}
+
+#[test]
+fn can_import_wezterm_theme() {
+    let palette = import("wezterm-nord.toml", ThemeFormat::WezTerm);
+    assert_eq!(palette.fg, PaletteColor::Rgb((0xd8, 0xde, 0xe9)));
+    assert_eq!(palette.black, PaletteColor::Rgb((0x3b, 0x42, 0x52)));
+    assert_eq!(palette.cyan, PaletteColor::Rgb((0x88, 0xc0, 0xd0)));
+}
+
+#[test]
+fn can_import_base16_theme() {
+    let palette = import("base16-ocean.yaml", ThemeFormat::Base16);
+    assert_eq!(palette.bg, PaletteColor::Rgb((0x2b, 0x30, 0x3b)));
+    assert_eq!(palette.fg, PaletteColor::Rgb((0xc0, 0xc5, 0xce)));
+    assert_eq!(palette.yellow, PaletteColor::Rgb((0xeb, 0xcb, 0x8b)));
+    assert_eq!(palette.orange, PaletteColor::Rgb((0xd0, 0x87, 0x70)));
+}
+
+#[test]
+fn importing_a_theme_with_missing_colors_is_err() {
+    let palette = ThemeFormat::Kitty.palette_from_str("foreground #ffffff\ncolor0 #000000");
+    assert!(palette.is_err());
+}
+
+#[test]
+fn foreign_themes_are_loaded_from_theme_dir() {
+    let themes = Themes::from_dir(foreign_theme_dir()).unwrap();
+    let mut theme_names: Vec<&String> = themes.inner().keys().collect();
+    theme_names.sort();
+    assert_eq!(
+        theme_names,
+        vec![
+            "alacritty-tomorrow",
+            "alacritty-tomorrow-old",
+            "base16-ocean",
+            "kitty-gruvbox",
+            "wezterm-nord"
+        ]
+    );
+    assert!(
+        themes
+            .get_theme("kitty-gruvbox")
+            .unwrap()
+            .sourced_from_external_file
+    );
+}
@@ -1,12 +1,17 @@
 //! IPC stuff for starting to split things into a client and server model.
 use crate::{
     cli::CliArgs,
-    data::{ClientId, ConnectToSession, KeyWithModifier, Style},
+    data::{ClientId, ConnectToSession, InputMode, KeyWithModifier, Style, ThemeHue},
     errors::{get_current_ctx, prelude::*, ErrorContext},
     input::config::Config,
     input::keybinds::Keybinds,
-    input::{actions::Action, layout::Layout, options::Options, plugins::PluginAliases},
+    input::{
+        actions::Action, dir_local_layout::DirLocalLayout, layout::Layout,
+        macros::MacroInstruction, mouse::MouseButtonWithModifier, options::Options,
+        plugins::PluginAliases,
+    },
     pane_size::{Size, SizeInPixels},
+    position::Position,
 };
 use interprocess::local_socket::LocalSocketStream;
 use log::warn;
@@ -71,6 +76,7 @@ pub enum ClientToServerMsg {
     BackgroundColor(String),
     ForegroundColor(String),
     ColorRegisters(Vec<(usize, String)>),
+    ColorScheme(ThemeHue), // reported by the terminal when it switches between light and dark
     TerminalResize(Size),
     NewClient(
         ClientAttributes,
@@ -90,12 +96,23 @@ pub enum ClientToServerMsg {
     ),
     Action(Action, Option<u32>, Option<ClientId>), // u32 is the terminal id
     Key(KeyWithModifier, Vec<u8>, bool),           // key, raw_bytes, is_kitty_keyboard_protocol
+    MousePress(MouseButtonWithModifier, Position),
     ClientExited,
     KillSession,
     ConnStatus,
     ListClients,
     ConfigWrittenToDisk(Config),
     FailedToWriteConfigToDisk(Option<PathBuf>),
+    // the keys between these are replayed from a macro, the replay first switches to start_mode
+    MacroReplayStarted {
+        depth: usize,
+        start_mode: Option<InputMode>,
+    },
+    MacroReplayEnded,
+    MacroRecorded(String), // the macro as a kdl string
+    // sent right after NewClient, the layout is opened in the session once the user trusts it
+    PromptToTrustDirLocalLayout(Box<DirLocalLayout>, PathBuf), // PathBuf - the data dir in which
+                                                               // the trust is recorded
 }
 
 // Types of messages sent from the server to the client
@@ -113,6 +130,7 @@ pub enum ServerToClientMsg {
     CliPipeOutput(String, String), // String -> pipe name, String -> Output
     QueryTerminalSize,
     WriteConfigToDisk { config: String },
+    Macro(MacroInstruction),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -1,5 +1,6 @@
+use crate::data::{PaletteColor, PaneColors};
 use crate::input::{
-    command::RunCommand,
+    command::{RestartCondition, RestartPolicy, RunCommand},
     config::ConfigError,
     layout::{
         FloatingPaneLayout, Layout, LayoutConstraint, PercentOrFixed, PluginUserConfiguration, Run,
@@ -8,6 +9,7 @@ use crate::input::{
     },
 };
 
+use globset::Glob;
 use kdl::*;
 
 use std::collections::{BTreeMap, HashMap, HashSet};
@@ -41,6 +43,7 @@ pub struct KdlLayoutParser<'a> {
     default_tab_template: Option<(TiledPaneLayout, Vec<FloatingPaneLayout>, KdlNode)>,
     new_tab_template: Option<(TiledPaneLayout, Vec<FloatingPaneLayout>)>,
     file_name: Option<PathBuf>,
+    layout_vars: BTreeMap<String, String>, // variables from the cli take precedence over the vars block
 }
 
 impl<'a> KdlLayoutParser<'a> {
@@ -48,6 +51,7 @@ impl<'a> KdlLayoutParser<'a> {
         raw_layout: &'a str,
         global_cwd: Option<PathBuf>,
         file_name: Option<String>,
+        layout_vars: BTreeMap<String, String>,
     ) -> Self {
         KdlLayoutParser {
             raw_layout,
@@ -57,8 +61,130 @@ impl<'a> KdlLayoutParser<'a> {
             new_tab_template: None,
             global_cwd,
             file_name: file_name.map(|f| PathBuf::from(f)),
+            layout_vars,
         }
     }
+    fn populate_layout_vars(&mut self, kdl_document: &KdlDocument) -> Result<(), ConfigError> {
+        // vars blocks only provide defaults, so they never override a variable that was already
+        // set (eg. from the command line)
+        for vars_node in kdl_document
+            .nodes()
+            .iter()
+            .filter(|n| kdl_name!(n) == "vars")
+        {
+            for var_node in kdl_children_nodes!(vars_node).unwrap_or(&[]) {
+                let var_name = kdl_name!(var_node);
+                if self.layout_vars.contains_key(var_name) {
+                    continue;
+                }
+                let (default_value, entry) = var_node
+                    .entries()
+                    .iter()
+                    .next()
+                    .and_then(|entry| {
+                        let value = entry.value();
+                        value
+                            .as_string()
+                            .map(|s| s.to_owned())
+                            .or_else(|| value.as_i64().map(|i| i.to_string()))
+                            .or_else(|| value.as_f64().map(|f| f.to_string()))
+                            .or_else(|| value.as_bool().map(|b| b.to_string()))
+                            .map(|default_value| (default_value, entry))
+                    })
+                    .ok_or(ConfigError::new_layout_kdl_error(
+                        format!(
+                            "Layout variable '{}' has no default value and was not set with --layout-var",
+                            var_name
+                        ),
+                        var_node.span().offset(),
+                        var_node.span().len(),
+                    ))?;
+                let default_value = self.substitute_layout_vars_in_str(&default_value).map_err(
+                    |undefined_var| {
+                        ConfigError::new_layout_kdl_error(
+                            format!("Undefined layout variable: '{}'", undefined_var),
+                            entry.span().offset(),
+                            entry.span().len(),
+                        )
+                    },
+                )?;
+                self.layout_vars.insert(var_name.to_owned(), default_value);
+            }
+        }
+        Ok(())
+    }
+    fn substitute_layout_vars(&self, kdl_document: &mut KdlDocument) -> Result<(), ConfigError> {
+        for node in kdl_document.nodes_mut() {
+            if kdl_name!(node) == "vars" {
+                continue;
+            }
+            self.substitute_layout_vars_in_node(node)?;
+        }
+        Ok(())
+    }
+    fn substitute_layout_vars_in_node(&self, kdl_node: &mut KdlNode) -> Result<(), ConfigError> {
+        for entry in kdl_node.entries_mut() {
+            let substituted = match entry.value().as_string() {
+                Some(value) if value.contains("${") => {
+                    self.substitute_layout_vars_in_str(value)
+                        .map_err(|undefined_var| {
+                            ConfigError::new_layout_kdl_error(
+                                format!(
+                                    "Undefined layout variable: '{}' (define it in a \"vars\" block or pass --layout-var {}=<value>)",
+                                    undefined_var, undefined_var
+                                ),
+                                entry.span().offset(),
+                                entry.span().len(),
+                            )
+                        })?
+                },
+                _ => continue,
+            };
+            // the representation is what the document is printed with (eg. for its config)
+            let substituted = KdlValue::String(substituted);
+            entry.set_value_repr(substituted.to_string());
+            entry.set_value(substituted);
+        }
+        if let Some(children) = kdl_node.children_mut() {
+            for child in children.nodes_mut() {
+                self.substitute_layout_vars_in_node(child)?;
+            }
+        }
+        Ok(())
+    }
+    fn substitute_layout_vars_in_str(&self, raw: &str) -> Result<String, String> {
+        // returns the name of the first undefined variable as the error
+        // "$${" is an escape for a literal "${"
+        let mut substituted = String::new();
+        let mut rest = raw;
+        while let Some(start) = rest.find("${") {
+            if rest[..start].ends_with('$') {
+                substituted.push_str(&rest[..start]);
+                substituted.push('{');
+                rest = &rest[start + 2..];
+                continue;
+            }
+            substituted.push_str(&rest[..start]);
+            let after_start = &rest[start + 2..];
+            match after_start.find('}') {
+                Some(end) => {
+                    let var_name = &after_start[..end];
+                    let value = match var_name.strip_prefix("env.") {
+                        Some(env_var_name) => std::env::var(env_var_name).ok(),
+                        None => self.layout_vars.get(var_name).cloned(),
+                    };
+                    substituted.push_str(&value.ok_or_else(|| var_name.to_owned())?);
+                    rest = &after_start[end + 1..];
+                },
+                None => {
+                    substituted.push_str(&rest[start..]);
+                    rest = "";
+                },
+            }
+        }
+        substituted.push_str(rest);
+        Ok(substituted)
+    }
     fn is_a_reserved_word(&self, word: &str) -> bool {
         // note that it's important that none of these words happens to also be a config property,
         // otherwise they might collide
@@ -76,7 +202,12 @@ impl<'a> KdlLayoutParser<'a> {
             || word == "args"
             || word == "close_on_exit"
             || word == "start_suspended"
+            || word == "restart"
+            || word == "max_restarts"
+            || word == "rerun_on_change"
+            || word == "kill_on_change"
             || word == "borderless"
+            || word == "tab_bar"
             || word == "focus"
             || word == "name"
             || word == "size"
@@ -86,9 +217,13 @@ impl<'a> KdlLayoutParser<'a> {
             || word == "swap_floating_layout"
             || word == "hide_floating_panes"
             || word == "contents_file"
+            || word == "frame_color"
+            || word == "frame_color_focused"
+            || word == "background"
     }
     fn is_a_valid_pane_property(&self, property_name: &str) -> bool {
         property_name == "borderless"
+            || property_name == "tab_bar"
             || property_name == "focus"
             || property_name == "name"
             || property_name == "size"
@@ -99,6 +234,10 @@ impl<'a> KdlLayoutParser<'a> {
             || property_name == "args"
             || property_name == "close_on_exit"
             || property_name == "start_suspended"
+            || property_name == "restart"
+            || property_name == "max_restarts"
+            || property_name == "rerun_on_change"
+            || property_name == "kill_on_change"
             || property_name == "split_direction"
             || property_name == "pane"
             || property_name == "children"
@@ -106,6 +245,9 @@ impl<'a> KdlLayoutParser<'a> {
             || property_name == "expanded"
             || property_name == "exclude_from_sync"
             || property_name == "contents_file"
+            || property_name == "frame_color"
+            || property_name == "frame_color_focused"
+            || property_name == "background"
     }
     fn is_a_valid_floating_pane_property(&self, property_name: &str) -> bool {
         property_name == "borderless"
@@ -118,11 +260,18 @@ impl<'a> KdlLayoutParser<'a> {
             || property_name == "args"
             || property_name == "close_on_exit"
             || property_name == "start_suspended"
+            || property_name == "restart"
+            || property_name == "max_restarts"
+            || property_name == "rerun_on_change"
+            || property_name == "kill_on_change"
             || property_name == "x"
             || property_name == "y"
             || property_name == "width"
             || property_name == "height"
             || property_name == "contents_file"
+            || property_name == "frame_color"
+            || property_name == "frame_color_focused"
+            || property_name == "background"
     }
     fn is_a_valid_tab_property(&self, property_name: &str) -> bool {
         property_name == "focus"
@@ -135,6 +284,9 @@ impl<'a> KdlLayoutParser<'a> {
             || property_name == "min_panes"
             || property_name == "exact_panes"
             || property_name == "hide_floating_panes"
+            || property_name == "frame_color"
+            || property_name == "frame_color_focused"
+            || property_name == "background"
     }
     pub fn is_a_reserved_plugin_property(property_name: &str) -> bool {
         property_name == "location"
@@ -323,7 +475,7 @@ impl<'a> KdlLayoutParser<'a> {
         let cwd = self.cwd_prefix(initial_cwd.as_ref())?;
         let run_plugin_or_alias = RunPluginOrAlias::from_url(
             &string_url,
-            &Some(configuration.inner().clone()),
+            &Some(configuration.inner_with_settings()),
             None,
             cwd.clone(),
         )
@@ -384,6 +536,20 @@ impl<'a> KdlLayoutParser<'a> {
                 configuration.insert(config_entry_name.into(), config_entry_value);
             }
         }
+        for limit in ["memory_limit", "call_timeout"] {
+            if let Some(value) = configuration.get(limit) {
+                if value.parse::<u64>().is_err() {
+                    return Err(ConfigError::new_kdl_error(
+                        format!(
+                            "{} must be a positive number (or 0 for no limit), found: {}",
+                            limit, value
+                        ),
+                        plugin_block.span().offset(),
+                        plugin_block.span().len(),
+                    ));
+                }
+            }
+        }
         Ok(PluginUserConfiguration::new(configuration))
     }
     fn parse_args(&self, pane_node: &KdlNode) -> Result<Option<Vec<String>>, ConfigError> {
@@ -423,6 +589,72 @@ impl<'a> KdlLayoutParser<'a> {
             None => Ok(None),
         }
     }
+    fn parse_restart_policy(
+        &self,
+        kdl_node: &KdlNode,
+    ) -> Result<(Option<RestartCondition>, Option<usize>), ConfigError> {
+        let restart_condition =
+            match kdl_get_string_property_or_child_value_with_error!(kdl_node, "restart") {
+                Some(restart) => Some(
+                    RestartCondition::from_str(restart)
+                        .map_err(|e| kdl_parsing_error!(e, kdl_node))?,
+                ),
+                None => None,
+            };
+        let max_restarts = match kdl_get_int_property_or_child_value!(kdl_node, "max_restarts") {
+            Some(max_restarts) if max_restarts < 0 => {
+                return Err(kdl_parsing_error!(
+                    format!("max_restarts cannot be negative, found: {}", max_restarts),
+                    kdl_node
+                ));
+            },
+            Some(max_restarts) => Some(max_restarts as usize),
+            None => None,
+        };
+        Ok((restart_condition, max_restarts))
+    }
+    fn parse_rerun_on_change(
+        &self,
+        kdl_node: &KdlNode,
+    ) -> Result<(Option<Vec<String>>, Option<bool>), ConfigError> {
+        let rerun_on_change = match kdl_node.get("rerun_on_change") {
+            Some(_) => {
+                kdl_get_string_property_or_child_value_with_error!(kdl_node, "rerun_on_change")
+                    .map(|pattern| vec![pattern.to_owned()])
+            },
+            None => match kdl_get_child!(kdl_node, "rerun_on_change") {
+                Some(patterns_node) => {
+                    if patterns_node.entries().is_empty() {
+                        return Err(kdl_parsing_error!(format!("rerun_on_change cannot be empty and should contain one or more glob patterns (eg. rerun_on_change \"src/**/*.rs\")"), patterns_node));
+                    }
+                    Some(
+                        kdl_string_arguments!(patterns_node)
+                            .iter()
+                            .map(|s| String::from(*s))
+                            .collect(),
+                    )
+                },
+                None => None,
+            },
+        };
+        if let Some(rerun_on_change) = rerun_on_change.as_ref() {
+            for pattern in rerun_on_change {
+                if let Err(e) = Glob::new(pattern) {
+                    let (offset, len) = kdl_get_property_or_child!(kdl_node, "rerun_on_change")
+                        .map(|e| (e.span().offset(), e.span().len()))
+                        .unwrap_or_else(|| (kdl_node.span().offset(), kdl_node.span().len()));
+                    return Err(ConfigError::new_layout_kdl_error(
+                        format!("Invalid glob pattern in rerun_on_change: {}", e),
+                        offset,
+                        len,
+                    ));
+                }
+            }
+        }
+        let kill_on_change =
+            kdl_get_bool_property_or_child_value_with_error!(kdl_node, "kill_on_change");
+        Ok((rerun_on_change, kill_on_change))
+    }
     fn parse_pane_command(
         &self,
         pane_node: &KdlNode,
@@ -436,6 +668,8 @@ impl<'a> KdlLayoutParser<'a> {
             kdl_get_bool_property_or_child_value_with_error!(pane_node, "close_on_exit");
         let start_suspended =
             kdl_get_bool_property_or_child_value_with_error!(pane_node, "start_suspended");
+        let (restart_condition, max_restarts) = self.parse_restart_policy(pane_node)?;
+        let (rerun_on_change, kill_on_change) = self.parse_rerun_on_change(pane_node)?;
         if !is_template {
             self.assert_no_bare_attributes_in_pane_node(
                 &command,
@@ -444,6 +678,21 @@ impl<'a> KdlLayoutParser<'a> {
                 &start_suspended,
                 pane_node,
             )?;
+            if command.is_none() && (restart_condition.is_some() || max_restarts.is_some()) {
+                return Err(ConfigError::new_layout_kdl_error(
+                    "restart and max_restarts can only be set if a command was specified".into(),
+                    pane_node.span().offset(),
+                    pane_node.span().len(),
+                ));
+            }
+            if command.is_none() && (rerun_on_change.is_some() || kill_on_change.is_some()) {
+                return Err(ConfigError::new_layout_kdl_error(
+                    "rerun_on_change and kill_on_change can only be set if a command was specified"
+                        .into(),
+                    pane_node.span().offset(),
+                    pane_node.span().len(),
+                ));
+            }
         }
         let hold_on_close = close_on_exit.map(|c| !c).unwrap_or(true);
         let hold_on_start = start_suspended.map(|c| c).unwrap_or(false);
@@ -455,6 +704,12 @@ impl<'a> KdlLayoutParser<'a> {
                 cwd,
                 hold_on_close,
                 hold_on_start,
+                restart_policy: RestartPolicy::new(
+                    restart_condition.unwrap_or_default(),
+                    max_restarts,
+                ),
+                rerun_on_change: rerun_on_change.unwrap_or_default(),
+                kill_on_change: kill_on_change.unwrap_or(false),
                 ..Default::default()
             }))),
             (None, Some(edit), Some(cwd)) => {
@@ -526,6 +781,7 @@ impl<'a> KdlLayoutParser<'a> {
         let is_expanded_in_stack =
             kdl_get_bool_property_or_child_value_with_error!(kdl_node, "expanded").unwrap_or(false);
         let borderless = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "borderless");
+        let tab_bar = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "tab_bar");
         let focus = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "focus");
         let name = kdl_get_string_property_or_child_value_with_error!(kdl_node, "name")
             .map(|name| name.to_string());
@@ -535,6 +791,7 @@ impl<'a> KdlLayoutParser<'a> {
             kdl_get_string_property_or_child_value_with_error!(kdl_node, "contents_file");
         let split_size = self.parse_split_size(kdl_node)?;
         let run = self.parse_command_plugin_or_edit_block(kdl_node)?;
+        let pane_colors = self.parse_pane_colors(kdl_node)?;
         let children_split_direction = self.parse_split_direction(kdl_node)?;
         let (external_children_index, children) = match kdl_children_nodes!(kdl_node) {
             Some(children) => {
@@ -573,6 +830,7 @@ impl<'a> KdlLayoutParser<'a> {
         });
         Ok(TiledPaneLayout {
             borderless: borderless.unwrap_or_default(),
+            tab_bar: tab_bar.unwrap_or_default(),
             focus,
             name,
             split_size,
@@ -584,6 +842,7 @@ impl<'a> KdlLayoutParser<'a> {
             children_are_stacked,
             is_expanded_in_stack,
             pane_initial_contents,
+            pane_colors,
             ..Default::default()
         })
     }
@@ -602,6 +861,7 @@ impl<'a> KdlLayoutParser<'a> {
             .map(|name| name.to_string());
         let contents_file =
             kdl_get_string_property_or_child_value_with_error!(kdl_node, "contents_file");
+        let pane_colors = self.parse_pane_colors(kdl_node)?;
         self.assert_no_mixed_children_and_properties(kdl_node)?;
         let pane_initial_contents = contents_file.and_then(|contents_file| {
             self.file_name
@@ -620,6 +880,7 @@ impl<'a> KdlLayoutParser<'a> {
             run,
             focus,
             pane_initial_contents,
+            pane_colors,
             ..Default::default()
         })
     }
@@ -700,6 +961,7 @@ impl<'a> KdlLayoutParser<'a> {
             | PaneOrFloatingPane::Either(mut pane_template) => {
                 let borderless =
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "borderless");
+                let tab_bar = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "tab_bar");
                 let focus = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "focus");
                 let name = kdl_get_string_property_or_child_value_with_error!(kdl_node, "name")
                     .map(|name| name.to_string());
@@ -712,10 +974,13 @@ impl<'a> KdlLayoutParser<'a> {
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "close_on_exit");
                 let start_suspended =
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "start_suspended");
+                let (restart_condition, max_restarts) = self.parse_restart_policy(kdl_node)?;
+                let (rerun_on_change, kill_on_change) = self.parse_rerun_on_change(kdl_node)?;
                 let split_size = self.parse_split_size(kdl_node)?;
                 let run = self.parse_command_plugin_or_edit_block_for_template(kdl_node)?;
                 let exclude_from_sync =
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "exclude_from_sync");
+                let pane_colors = self.parse_pane_colors(kdl_node)?;
 
                 let external_children_index = if should_mark_external_children_index {
                     self.populate_external_children_index(kdl_node)?
@@ -730,6 +995,15 @@ impl<'a> KdlLayoutParser<'a> {
                     &start_suspended,
                     kdl_node,
                 )?;
+                self.assert_no_bare_command_policies_in_pane_node_with_template(
+                    &run,
+                    &pane_template.run,
+                    restart_condition.is_some()
+                        || max_restarts.is_some()
+                        || rerun_on_change.is_some()
+                        || kill_on_change.is_some(),
+                    kdl_node,
+                )?;
                 self.insert_children_to_pane_template(
                     kdl_node,
                     &mut pane_template,
@@ -742,10 +1016,16 @@ impl<'a> KdlLayoutParser<'a> {
                     pane_template_run_command.add_args(args);
                     pane_template_run_command.add_close_on_exit(close_on_exit);
                     pane_template_run_command.add_start_suspended(start_suspended);
+                    pane_template_run_command.add_restart_policy(restart_condition, max_restarts);
+                    pane_template_run_command
+                        .add_rerun_on_change(rerun_on_change.clone(), kill_on_change);
                 };
                 if let Some(borderless) = borderless {
                     pane_template.borderless = borderless;
                 }
+                if let Some(tab_bar) = tab_bar {
+                    pane_template.tab_bar = tab_bar;
+                }
                 if let Some(focus) = focus {
                     pane_template.focus = Some(focus);
                 }
@@ -758,6 +1038,7 @@ impl<'a> KdlLayoutParser<'a> {
                 if let Some(split_size) = split_size {
                     pane_template.split_size = Some(split_size);
                 }
+                pane_template.pane_colors = pane_template.pane_colors.merge(&pane_colors);
                 if let Some(index_of_children) = pane_template.external_children_index {
                     pane_template.children.insert(
                         index_of_children,
@@ -818,6 +1099,8 @@ impl<'a> KdlLayoutParser<'a> {
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "close_on_exit");
                 let start_suspended =
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "start_suspended");
+                let (restart_condition, max_restarts) = self.parse_restart_policy(kdl_node)?;
+                let (rerun_on_change, kill_on_change) = self.parse_rerun_on_change(kdl_node)?;
                 let run = self.parse_command_plugin_or_edit_block_for_template(kdl_node)?;
                 self.assert_no_bare_attributes_in_pane_node_with_template(
                     &run,
@@ -827,6 +1110,15 @@ impl<'a> KdlLayoutParser<'a> {
                     &start_suspended,
                     kdl_node,
                 )?;
+                self.assert_no_bare_command_policies_in_pane_node_with_template(
+                    &run,
+                    &pane_template.run,
+                    restart_condition.is_some()
+                        || max_restarts.is_some()
+                        || rerun_on_change.is_some()
+                        || kill_on_change.is_some(),
+                    kdl_node,
+                )?;
                 pane_template.run = Run::merge(&pane_template.run, &run);
                 if let Some(pane_template_run_command) = pane_template.run.as_mut() {
                     // we need to do this because panes consuming a pane_template
@@ -834,6 +1126,9 @@ impl<'a> KdlLayoutParser<'a> {
                     pane_template_run_command.add_args(args);
                     pane_template_run_command.add_close_on_exit(close_on_exit);
                     pane_template_run_command.add_start_suspended(start_suspended);
+                    pane_template_run_command.add_restart_policy(restart_condition, max_restarts);
+                    pane_template_run_command
+                        .add_rerun_on_change(rerun_on_change.clone(), kill_on_change);
                 };
                 if let Some(focus) = focus {
                     pane_template.focus = Some(focus);
@@ -841,6 +1136,8 @@ impl<'a> KdlLayoutParser<'a> {
                 if let Some(name) = name {
                     pane_template.name = Some(name);
                 }
+                let pane_colors = self.parse_pane_colors(kdl_node)?;
+                pane_template.pane_colors = pane_template.pane_colors.merge(&pane_colors);
                 let height = self.parse_percent_or_fixed(kdl_node, "height", false)?;
                 let width = self.parse_percent_or_fixed(kdl_node, "width", false)?;
                 let x = self.parse_percent_or_fixed(kdl_node, "x", true)?;
@@ -869,6 +1166,8 @@ impl<'a> KdlLayoutParser<'a> {
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "close_on_exit");
                 let start_suspended =
                     kdl_get_bool_property_or_child_value_with_error!(kdl_node, "start_suspended");
+                let (restart_condition, max_restarts) = self.parse_restart_policy(kdl_node)?;
+                let (rerun_on_change, kill_on_change) = self.parse_rerun_on_change(kdl_node)?;
                 let run = self.parse_command_plugin_or_edit_block_for_template(kdl_node)?;
                 self.assert_no_bare_attributes_in_pane_node_with_template(
                     &run,
@@ -878,6 +1177,15 @@ impl<'a> KdlLayoutParser<'a> {
                     &start_suspended,
                     kdl_node,
                 )?;
+                self.assert_no_bare_command_policies_in_pane_node_with_template(
+                    &run,
+                    &pane_template.run,
+                    restart_condition.is_some()
+                        || max_restarts.is_some()
+                        || rerun_on_change.is_some()
+                        || kill_on_change.is_some(),
+                    kdl_node,
+                )?;
                 pane_template.run = Run::merge(&pane_template.run, &run);
                 if let Some(pane_template_run_command) = pane_template.run.as_mut() {
                     // we need to do this because panes consuming a pane_template
@@ -885,6 +1193,9 @@ impl<'a> KdlLayoutParser<'a> {
                     pane_template_run_command.add_args(args);
                     pane_template_run_command.add_close_on_exit(close_on_exit);
                     pane_template_run_command.add_start_suspended(start_suspended);
+                    pane_template_run_command.add_restart_policy(restart_condition, max_restarts);
+                    pane_template_run_command
+                        .add_rerun_on_change(rerun_on_change.clone(), kill_on_change);
                 };
                 if let Some(focus) = focus {
                     pane_template.focus = Some(focus);
@@ -892,6 +1203,8 @@ impl<'a> KdlLayoutParser<'a> {
                 if let Some(name) = name {
                     pane_template.name = Some(name);
                 }
+                let pane_colors = self.parse_pane_colors(kdl_node)?;
+                pane_template.pane_colors = pane_template.pane_colors.merge(&pane_colors);
                 let height = self.parse_percent_or_fixed(kdl_node, "height", false)?;
                 let width = self.parse_percent_or_fixed(kdl_node, "width", false)?;
                 let x = self.parse_percent_or_fixed(kdl_node, "x", true)?;
@@ -928,12 +1241,37 @@ impl<'a> KdlLayoutParser<'a> {
             None => Ok(SplitDirection::default()),
         }
     }
+    fn parse_pane_colors(&self, kdl_node: &KdlNode) -> Result<PaneColors, ConfigError> {
+        let parse_color = |color_name: &str| -> Result<Option<PaletteColor>, ConfigError> {
+            match kdl_property_or_child_value_node!(kdl_node, color_name) {
+                Some(entry) => {
+                    let color = match (entry.value().as_string(), entry.value().as_i64()) {
+                        (Some(color), _) => PaletteColor::from_str(color),
+                        (None, Some(color_index)) if (0..=255).contains(&color_index) => {
+                            Ok(PaletteColor::EightBit(color_index as u8))
+                        },
+                        _ => Err(format!("Invalid color: {}", entry.value())),
+                    };
+                    color
+                        .map(Some)
+                        .map_err(|e| kdl_parsing_error!(format!("{}: {}", color_name, e), entry))
+                },
+                None => Ok(None),
+            }
+        };
+        Ok(PaneColors {
+            frame: parse_color("frame_color")?,
+            frame_focused: parse_color("frame_color_focused")?,
+            background: parse_color("background")?,
+        })
+    }
     fn has_only_neutral_pane_template_properties(
         &self,
         kdl_node: &KdlNode,
     ) -> Result<bool, ConfigError> {
         // pane properties
         let borderless = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "borderless");
+        let tab_bar = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "tab_bar");
         let children_are_stacked =
             kdl_get_bool_property_or_child_value_with_error!(kdl_node, "stacked");
         let is_expanded_in_stack =
@@ -950,6 +1288,7 @@ impl<'a> KdlLayoutParser<'a> {
         let y = self.parse_percent_or_fixed(kdl_node, "y", true)?;
 
         let has_pane_properties = borderless.is_some()
+            || tab_bar.is_some()
             || split_size.is_some()
             || split_direction.is_some()
             || children_are_stacked.is_some()
@@ -971,6 +1310,7 @@ impl<'a> KdlLayoutParser<'a> {
 
         // pane properties
         let borderless = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "borderless");
+        let tab_bar = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "tab_bar");
         let children_are_stacked =
             kdl_get_bool_property_or_child_value_with_error!(kdl_node, "stacked");
         let is_expanded_in_stack =
@@ -987,6 +1327,7 @@ impl<'a> KdlLayoutParser<'a> {
         let y = self.parse_percent_or_fixed(kdl_node, "y", true)?;
 
         let has_pane_properties = borderless.is_some()
+            || tab_bar.is_some()
             || split_size.is_some()
             || split_direction.is_some()
             || children_are_stacked.is_some()
@@ -1000,6 +1341,9 @@ impl<'a> KdlLayoutParser<'a> {
             if borderless.is_some() {
                 pane_properties.push("borderless");
             }
+            if tab_bar.is_some() {
+                pane_properties.push("tab_bar");
+            }
             if children_are_stacked.is_some() {
                 pane_properties.push("stacked");
             }
@@ -1099,6 +1443,7 @@ impl<'a> KdlLayoutParser<'a> {
             // pane properties
             let borderless =
                 kdl_get_bool_property_or_child_value_with_error!(kdl_node, "borderless");
+            let tab_bar = kdl_get_bool_property_or_child_value_with_error!(kdl_node, "tab_bar");
             let children_are_stacked =
                 kdl_get_bool_property_or_child_value_with_error!(kdl_node, "stacked")
                     .unwrap_or(false);
@@ -1119,6 +1464,7 @@ impl<'a> KdlLayoutParser<'a> {
                 (
                     PaneOrFloatingPane::Pane(TiledPaneLayout {
                         borderless: borderless.unwrap_or_default(),
+                        tab_bar: tab_bar.unwrap_or_default(),
                         focus,
                         split_size,
                         run,
@@ -1156,6 +1502,7 @@ impl<'a> KdlLayoutParser<'a> {
         let is_focused = kdl_get_bool_property_or_child_value!(kdl_node, "focus").unwrap_or(false);
         let hide_floating_panes =
             kdl_get_bool_property_or_child_value!(kdl_node, "hide_floating_panes").unwrap_or(false);
+        let pane_colors = self.parse_pane_colors(kdl_node)?;
         let children_split_direction = self.parse_split_direction(kdl_node)?;
         let mut child_floating_panes = vec![];
         let children = match kdl_children_nodes!(kdl_node) {
@@ -1173,6 +1520,7 @@ impl<'a> KdlLayoutParser<'a> {
             children_split_direction,
             children,
             hide_floating_panes,
+            pane_colors,
             ..Default::default()
         };
         if let Some(cwd_prefix) = &self.cwd_prefix(tab_cwd.as_ref())? {
@@ -1332,6 +1680,21 @@ impl<'a> KdlLayoutParser<'a> {
         }
         Ok(())
     }
+    fn assert_no_bare_command_policies_in_pane_node_with_template(
+        &self,
+        pane_run: &Option<Run>,
+        pane_template_run: &Option<Run>,
+        has_command_policies: bool,
+        pane_node: &KdlNode,
+    ) -> Result<(), ConfigError> {
+        if let (None, None, true) = (pane_run, pane_template_run, has_command_policies) {
+            return Err(kdl_parsing_error!(
+                format!("restart, max_restarts, rerun_on_change and kill_on_change can only be specified if a command was specified either in the pane_template or in the pane"),
+                pane_node
+            ));
+        }
+        Ok(())
+    }
     fn assert_no_bare_attributes_in_pane_node(
         &self,
         command: &Option<PathBuf>,
@@ -1484,6 +1847,8 @@ impl<'a> KdlLayoutParser<'a> {
     ) -> Result<(), ConfigError> {
         let has_borderless_prop =
             kdl_get_bool_property_or_child_value_with_error!(kdl_node, "borderless").is_some();
+        let has_tab_bar_prop =
+            kdl_get_bool_property_or_child_value_with_error!(kdl_node, "tab_bar").is_some();
         let has_cwd_prop = self.parse_path(kdl_node, "cwd")?.is_some();
         let has_non_cwd_run_prop = self
             .parse_command_plugin_or_edit_block(kdl_node)?
@@ -1494,12 +1859,15 @@ impl<'a> KdlLayoutParser<'a> {
             .unwrap_or(false);
         let has_nested_nodes_or_children_block = self.has_child_panes_tabs_or_templates(kdl_node);
         if has_nested_nodes_or_children_block
-            && (has_borderless_prop || has_non_cwd_run_prop || has_cwd_prop)
+            && (has_borderless_prop || has_tab_bar_prop || has_non_cwd_run_prop || has_cwd_prop)
         {
             let mut offending_nodes = vec![];
             if has_borderless_prop {
                 offending_nodes.push("borderless");
             }
+            if has_tab_bar_prop {
+                offending_nodes.push("tab_bar");
+            }
             if has_non_cwd_run_prop {
                 offending_nodes.push("command/edit/plugin");
             }
@@ -1556,6 +1924,8 @@ impl<'a> KdlLayoutParser<'a> {
             kdl_get_string_property_or_child_value!(kdl_node, "name").map(|s| s.to_string());
         let tab_cwd = self.parse_path(kdl_node, "cwd")?;
         let is_focused = kdl_get_bool_property_or_child_value!(kdl_node, "focus").unwrap_or(false);
+        let pane_colors = self.parse_pane_colors(kdl_node)?;
+        tab_layout.pane_colors = tab_layout.pane_colors.merge(&pane_colors);
         let children_split_direction = self.parse_split_direction(kdl_node)?;
         match kdl_children_nodes!(kdl_node) {
             Some(children) => {
@@ -1649,6 +2019,7 @@ impl<'a> KdlLayoutParser<'a> {
         kdl_node: &KdlNode,
     ) -> Result<(TiledPaneLayout, Vec<FloatingPaneLayout>), ConfigError> {
         self.assert_valid_tab_properties(kdl_node)?;
+        let pane_colors = self.parse_pane_colors(kdl_node)?;
         let children_split_direction = self.parse_split_direction(kdl_node)?;
         let mut tab_children = vec![];
         let mut tab_floating_children = vec![];
@@ -1704,6 +2075,7 @@ impl<'a> KdlLayoutParser<'a> {
                 children_split_direction,
                 children: tab_children,
                 external_children_index,
+                pane_colors,
                 ..Default::default()
             },
             tab_floating_children,
@@ -2307,7 +2679,9 @@ impl<'a> KdlLayoutParser<'a> {
         raw_swap_layouts: &str,
         mut existing_layout: Layout,
     ) -> Result<Layout, ConfigError> {
-        let kdl_swap_layout: KdlDocument = raw_swap_layouts.parse()?;
+        let mut kdl_swap_layout: KdlDocument = raw_swap_layouts.parse()?;
+        self.populate_layout_vars(&kdl_swap_layout)?;
+        self.substitute_layout_vars(&mut kdl_swap_layout)?;
         let mut swap_tiled_layouts = vec![];
         let mut swap_floating_layouts = vec![];
 
@@ -2350,8 +2724,16 @@ impl<'a> KdlLayoutParser<'a> {
             .append(&mut swap_floating_layouts);
         Ok(existing_layout)
     }
+    pub fn substituted_raw_layout(&mut self) -> Result<String, ConfigError> {
+        let mut kdl_layout: KdlDocument = self.raw_layout.parse()?;
+        self.populate_layout_vars(&kdl_layout)?;
+        self.substitute_layout_vars(&mut kdl_layout)?;
+        Ok(kdl_layout.to_string())
+    }
     pub fn parse(&mut self) -> Result<Layout, ConfigError> {
-        let kdl_layout: KdlDocument = self.raw_layout.parse()?;
+        let mut kdl_layout: KdlDocument = self.raw_layout.parse()?;
+        self.populate_layout_vars(&kdl_layout)?;
+        self.substitute_layout_vars(&mut kdl_layout)?;
         let layout_node = kdl_layout
             .nodes()
             .iter()
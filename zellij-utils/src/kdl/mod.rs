@@ -1,19 +1,25 @@
 mod kdl_layout_parser;
 use crate::data::{
     BareKey, Direction, FloatingPaneCoordinates, InputMode, KeyWithModifier, LayoutInfo, Palette,
-    PaletteColor, PaneInfo, PaneManifest, PermissionType, Resize, SessionInfo, TabInfo,
+    PaletteColor, PaneInfo, PaneManifest, PermissionScopes, PermissionType, Resize, SessionInfo,
+    TabInfo, UiColors,
 };
 use crate::envs::EnvironmentVariables;
 use crate::home::{find_default_config_dir, get_layout_dir};
 use crate::input::config::{Config, ConfigError, KdlError};
-use crate::input::keybinds::Keybinds;
+use crate::input::keybinds::{
+    add_conditional_keybind, ConditionalKeybinds, KeybindCondition, Keybinds,
+};
 use crate::input::layout::{
     Layout, PluginUserConfiguration, RunPlugin, RunPluginOrAlias, SplitSize,
 };
+use crate::input::macros::{Macro, Macros};
+use crate::input::mouse::{MouseBindings, MouseButtonWithModifier, MouseTarget};
 use crate::input::options::{Clipboard, OnForceClose, Options};
-use crate::input::permission::{GrantedPermission, PermissionCache};
+use crate::input::permission::{GrantedPermission, GrantedScopes, PermissionCache};
 use crate::input::plugins::PluginAliases;
 use crate::input::theme::{FrameConfig, Theme, Themes, UiConfig};
+use crate::input::theme_import::{themes_from_str, ThemeFormat};
 use kdl_layout_parser::KdlLayoutParser;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use strum::IntoEnumIterator;
@@ -27,7 +33,8 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 use crate::input::actions::{Action, SearchDirection, SearchOption};
-use crate::input::command::RunCommandAction;
+use crate::input::command::{RestartCondition, RestartPolicy, RunCommandAction};
+use crate::position::Position;
 
 #[macro_export]
 macro_rules! parse_kdl_action_arguments {
@@ -74,6 +81,14 @@ macro_rules! parse_kdl_action_arguments {
                 "PreviousSwapLayout" => Ok(Action::PreviousSwapLayout),
                 "NextSwapLayout" => Ok(Action::NextSwapLayout),
                 "Clear" => Ok(Action::ClearScreen),
+                "StopMacroRecording" => Ok(Action::StopMacroRecording),
+                "TogglePaneInSelection" => Ok(Action::TogglePaneInSelection),
+                "ClearPaneSelection" => Ok(Action::ClearPaneSelection),
+                "Paste" => Ok(Action::Paste),
+                // the position is filled in when the mouse button the action is bound to is
+                // pressed
+                "FocusPaneAtMouse" => Ok(Action::FocusPaneAt(Position::new(0, 0))),
+                "OpenHyperlinkAtMouse" => Ok(Action::OpenHyperlinkAt(Position::new(0, 0))),
                 _ => Err(ConfigError::new_kdl_error(
                     format!("Unsupported action: {:?}", $action_name),
                     $action_node.span().offset(),
@@ -319,7 +334,19 @@ macro_rules! kdl_document_name {
 #[macro_export]
 macro_rules! keys_from_kdl {
     ( $kdl_node:expr ) => {
-        kdl_string_arguments!($kdl_node)
+        $kdl_node
+            .entries()
+            .iter()
+            // properties (eg. if_running="vim") are not keys
+            .filter(|e| e.name().is_none())
+            .map(|e| {
+                e.value().as_string().ok_or(ConfigError::new_kdl_error(
+                    "Not a string".into(),
+                    e.span().offset(),
+                    e.span().len(),
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?
             .iter()
             .map(|k| {
                 KeyWithModifier::from_str(k).map_err(|_| {
@@ -336,14 +363,30 @@ macro_rules! keys_from_kdl {
 
 #[macro_export]
 macro_rules! actions_from_kdl {
-    ( $kdl_node:expr, $config_options:expr ) => {
+    ( $kdl_node:expr, $config_options:expr, $layout_vars:expr ) => {
         kdl_children_nodes_or_error!($kdl_node, "no actions found for key_block")
             .iter()
-            .map(|kdl_action| Action::try_from((kdl_action, $config_options)))
+            .map(|kdl_action| Action::try_from((kdl_action, $config_options, $layout_vars)))
             .collect::<Result<_, _>>()?
     };
 }
 
+#[macro_export]
+macro_rules! key_actions_from_kdl {
+    ( $kdl_node:expr, $config_options:expr, $layout_vars:expr ) => {{
+        // keys have no position to place positional actions at
+        let actions: Vec<Action> = actions_from_kdl!($kdl_node, $config_options, $layout_vars);
+        if actions.iter().any(|action| action.is_positional()) {
+            return Err(ConfigError::new_kdl_error(
+                "Actions at the mouse position (eg. FocusPaneAtMouse) can only be bound to mouse buttons".into(),
+                $kdl_node.span().offset(),
+                $kdl_node.span().len(),
+            ));
+        }
+        actions
+    }};
+}
+
 pub fn kdl_arguments_that_are_strings<'a>(
     arguments: impl Iterator<Item = &'a KdlEntry>,
 ) -> Result<Vec<String>, ConfigError> {
@@ -404,6 +447,16 @@ pub fn kdl_child_bool_value_for_entry<'a>(
         .and_then(|cwd_value| cwd_value.value().as_bool())
 }
 
+pub fn kdl_child_int_value_for_entry<'a>(
+    command_metadata: &'a KdlDocument,
+    entry_name: &'a str,
+) -> Option<i64> {
+    command_metadata
+        .get(entry_name)
+        .and_then(|cwd| cwd.entries().iter().next())
+        .and_then(|cwd_value| cwd_value.value().as_i64())
+}
+
 impl Action {
     pub fn new_from_bytes(
         action_name: &str,
@@ -439,7 +492,7 @@ impl Action {
     ) -> Result<Self, ConfigError> {
         match action_name {
             "WriteChars" => Ok(Action::WriteChars(string)),
-            "SwitchToMode" => match InputMode::from_str(string.as_str()) {
+            "SwitchToMode" => match InputMode::from_str_or_custom(string.as_str()) {
                 Ok(input_mode) => Ok(Action::SwitchToMode(input_mode)),
                 Err(_e) => {
                     return Err(ConfigError::new_kdl_error(
@@ -564,6 +617,15 @@ impl Action {
                 Ok(Action::Search(search_direction))
             },
             "RenameSession" => Ok(Action::RenameSession(string)),
+            "StartMacroRecording" | "PlayMacro" if string.is_empty() => {
+                Err(ConfigError::new_kdl_error(
+                    format!("{} requires the name of a macro", action_name),
+                    action_node.span().offset(),
+                    action_node.span().len(),
+                ))
+            },
+            "StartMacroRecording" => Ok(Action::StartMacroRecording(string)),
+            "PlayMacro" => Ok(Action::PlayMacro(string)),
             _ => Err(ConfigError::new_kdl_error(
                 format!("Unsupported action: {}", action_name),
                 action_node.span().offset(),
@@ -588,7 +650,7 @@ impl Action {
             },
             Action::SwitchToMode(input_mode) => {
                 let mut node = KdlNode::new("SwitchToMode");
-                node.push(format!("{:?}", input_mode).to_lowercase());
+                node.push(input_mode.name());
                 Some(node)
             },
             Action::Resize(resize, resize_direction) => {
@@ -754,6 +816,16 @@ impl Action {
                         hoc_node.push(KdlValue::Bool(false));
                         node_children.nodes_mut().push(hoc_node);
                     }
+                    if run_command_action.restart_policy.is_set() {
+                        let mut restart_node = KdlNode::new("restart");
+                        restart_node.push(run_command_action.restart_policy.condition.to_string());
+                        node_children.nodes_mut().push(restart_node);
+                    }
+                    if let Some(max_restarts) = run_command_action.restart_policy.max_restarts {
+                        let mut max_restarts_node = KdlNode::new("max_restarts");
+                        max_restarts_node.push(max_restarts as i64);
+                        node_children.nodes_mut().push(max_restarts_node);
+                    }
                 }
                 if let Some(name) = name {
                     let mut name_node = KdlNode::new("name");
@@ -802,6 +874,16 @@ impl Action {
                         hoc_node.push(KdlValue::Bool(false));
                         node_children.nodes_mut().push(hoc_node);
                     }
+                    if run_command_action.restart_policy.is_set() {
+                        let mut restart_node = KdlNode::new("restart");
+                        restart_node.push(run_command_action.restart_policy.condition.to_string());
+                        node_children.nodes_mut().push(restart_node);
+                    }
+                    if let Some(max_restarts) = run_command_action.restart_policy.max_restarts {
+                        let mut max_restarts_node = KdlNode::new("max_restarts");
+                        max_restarts_node.push(max_restarts as i64);
+                        node_children.nodes_mut().push(max_restarts_node);
+                    }
                 }
                 if let Some(floating_pane_coordinates) = floating_pane_coordinates {
                     if let Some(x) = floating_pane_coordinates.x {
@@ -889,6 +971,16 @@ impl Action {
                         hoc_node.push(KdlValue::Bool(false));
                         node_children.nodes_mut().push(hoc_node);
                     }
+                    if run_command_action.restart_policy.is_set() {
+                        let mut restart_node = KdlNode::new("restart");
+                        restart_node.push(run_command_action.restart_policy.condition.to_string());
+                        node_children.nodes_mut().push(restart_node);
+                    }
+                    if let Some(max_restarts) = run_command_action.restart_policy.max_restarts {
+                        let mut max_restarts_node = KdlNode::new("max_restarts");
+                        max_restarts_node.push(max_restarts as i64);
+                        node_children.nodes_mut().push(max_restarts_node);
+                    }
                 }
                 if let Some(name) = name {
                     let mut name_node = KdlNode::new("name");
@@ -933,7 +1025,7 @@ impl Action {
                     node_children.nodes_mut().push(skip_plugin_cache_node);
                 }
                 if let Some(configuration) = run_plugin_or_alias.get_configuration() {
-                    for (config_key, config_value) in configuration.inner().iter() {
+                    for (config_key, config_value) in configuration.inner_with_settings().iter() {
                         let mut node = KdlNode::new(config_key.clone());
                         node.push(config_value.clone());
                         node_children.nodes_mut().push(node);
@@ -980,7 +1072,7 @@ impl Action {
                     node_children.nodes_mut().push(cwd_node);
                 }
                 if let Some(configuration) = run_plugin_or_alias.get_configuration() {
-                    for (config_key, config_value) in configuration.inner().iter() {
+                    for (config_key, config_value) in configuration.inner_with_settings().iter() {
                         let mut node = KdlNode::new(config_key.clone());
                         node.push(config_value.clone());
                         node_children.nodes_mut().push(node);
@@ -1019,6 +1111,22 @@ impl Action {
             Action::BreakPane => Some(KdlNode::new("BreakPane")),
             Action::BreakPaneRight => Some(KdlNode::new("BreakPaneRight")),
             Action::BreakPaneLeft => Some(KdlNode::new("BreakPaneLeft")),
+            Action::StartMacroRecording(macro_name) => {
+                let mut node = KdlNode::new("StartMacroRecording");
+                node.push(macro_name.clone());
+                Some(node)
+            },
+            Action::StopMacroRecording => Some(KdlNode::new("StopMacroRecording")),
+            Action::TogglePaneInSelection => Some(KdlNode::new("TogglePaneInSelection")),
+            Action::ClearPaneSelection => Some(KdlNode::new("ClearPaneSelection")),
+            Action::Paste => Some(KdlNode::new("Paste")),
+            Action::FocusPaneAt(..) => Some(KdlNode::new("FocusPaneAtMouse")),
+            Action::OpenHyperlinkAt(..) => Some(KdlNode::new("OpenHyperlinkAtMouse")),
+            Action::PlayMacro(macro_name) => {
+                let mut node = KdlNode::new("PlayMacro");
+                node.push(macro_name.clone());
+                Some(node)
+            },
             Action::KeybindPipe {
                 name,
                 payload,
@@ -1081,7 +1189,7 @@ impl Action {
                     // we do this because the constructor removes the relevant config fields from
                     // above, otherwise we would have duplicates
                     let configuration = PluginUserConfiguration::new(configuration.clone());
-                    let configuration = configuration.inner();
+                    let configuration = configuration.inner_with_settings();
                     for (config_key, config_value) in configuration.iter() {
                         let mut node = KdlNode::new(config_key.clone());
                         node.push(config_value.clone());
@@ -1232,9 +1340,11 @@ impl PaletteColor {
     }
 }
 
-impl TryFrom<(&KdlNode, &Options)> for Action {
+impl TryFrom<(&KdlNode, &Options, &BTreeMap<String, String>)> for Action {
     type Error = ConfigError;
-    fn try_from((kdl_action, config_options): (&KdlNode, &Options)) -> Result<Self, Self::Error> {
+    fn try_from(
+        (kdl_action, config_options, layout_vars): (&KdlNode, &Options, &BTreeMap<String, String>),
+    ) -> Result<Self, Self::Error> {
         let action_name = kdl_name!(kdl_action);
         let action_arguments: Vec<&KdlEntry> = kdl_argument_values!(kdl_action);
         let action_children: Vec<&KdlDocument> = kdl_children!(kdl_action);
@@ -1416,6 +1526,7 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
                     path_to_raw_layout,
                     swap_layouts.as_ref().map(|(f, p)| (f.as_str(), p.as_str())),
                     cwd,
+                    layout_vars.clone(),
                 )
                 .map_err(|e| {
                     ConfigError::new_kdl_error(
@@ -1498,6 +1609,21 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
                 let hold_on_start = command_metadata
                     .and_then(|c_m| kdl_child_bool_value_for_entry(c_m, "start_suspended"))
                     .unwrap_or(false);
+                let restart_condition = match command_metadata
+                    .and_then(|c_m| kdl_child_string_value_for_entry(c_m, "restart"))
+                {
+                    Some(restart) => RestartCondition::from_str(restart).map_err(|e| {
+                        ConfigError::new_kdl_error(
+                            e,
+                            kdl_action.span().offset(),
+                            kdl_action.span().len(),
+                        )
+                    })?,
+                    None => RestartCondition::default(),
+                };
+                let max_restarts = command_metadata
+                    .and_then(|c_m| kdl_child_int_value_for_entry(c_m, "max_restarts"))
+                    .map(|max_restarts| max_restarts.max(0) as usize);
                 let floating = command_metadata
                     .and_then(|c_m| kdl_child_bool_value_for_entry(c_m, "floating"))
                     .unwrap_or(false);
@@ -1511,6 +1637,7 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
                     direction,
                     hold_on_close,
                     hold_on_start,
+                    restart_policy: RestartPolicy::new(restart_condition, max_restarts),
                     ..Default::default()
                 };
                 let x = command_metadata
@@ -1572,7 +1699,7 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
                     .map(|s| PathBuf::from(s));
                 let run_plugin_or_alias = RunPluginOrAlias::from_url(
                     &plugin_path,
-                    &Some(configuration.inner().clone()),
+                    &Some(configuration.inner_with_settings()),
                     None,
                     Some(current_dir),
                 )
@@ -1618,7 +1745,7 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
                 let configuration = KdlLayoutParser::parse_plugin_user_configuration(&kdl_action)?;
                 let run_plugin_or_alias = RunPluginOrAlias::from_url(
                     &plugin_path,
-                    &Some(configuration.inner().clone()),
+                    &Some(configuration.inner_with_settings()),
                     None,
                     Some(current_dir),
                 )
@@ -1643,6 +1770,19 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
             "BreakPane" => Ok(Action::BreakPane),
             "BreakPaneRight" => Ok(Action::BreakPaneRight),
             "BreakPaneLeft" => Ok(Action::BreakPaneLeft),
+            "StartMacroRecording" | "PlayMacro" => parse_kdl_action_char_or_string_arguments!(
+                action_name,
+                action_arguments,
+                kdl_action
+            ),
+            "StopMacroRecording"
+            | "TogglePaneInSelection"
+            | "ClearPaneSelection"
+            | "Paste"
+            | "FocusPaneAtMouse"
+            | "OpenHyperlinkAtMouse" => {
+                parse_kdl_action_arguments!(action_name, action_arguments, kdl_action)
+            },
             "RenameSession" => parse_kdl_action_char_or_string_arguments!(
                 action_name,
                 action_arguments,
@@ -1677,10 +1817,10 @@ impl TryFrom<(&KdlNode, &Options)> for Action {
                     .and_then(|c_m| kdl_child_string_value_for_entry(c_m, "title"))
                     .map(|t| t.to_owned());
                 let configuration = KdlLayoutParser::parse_plugin_user_configuration(&kdl_action)?;
-                let configuration = if configuration.inner().is_empty() {
+                let configuration = if configuration.is_empty() {
                     None
                 } else {
-                    Some(configuration.inner().clone())
+                    Some(configuration.inner_with_settings())
                 };
                 let cwd = kdl_get_string_property_or_child_value!(kdl_action, "cwd")
                     .map(|s| PathBuf::from(s));
@@ -2159,6 +2299,10 @@ impl Options {
             kdl_property_first_arg_as_bool_or_error!(kdl_options, "auto_layout").map(|(v, _)| v);
         let theme = kdl_property_first_arg_as_string_or_error!(kdl_options, "theme")
             .map(|(theme, _entry)| theme.to_string());
+        let theme_dark = kdl_property_first_arg_as_string_or_error!(kdl_options, "theme_dark")
+            .map(|(theme, _entry)| theme.to_string());
+        let theme_light = kdl_property_first_arg_as_string_or_error!(kdl_options, "theme_light")
+            .map(|(theme, _entry)| theme.to_string());
         let default_mode =
             match kdl_property_first_arg_as_string_or_error!(kdl_options, "default_mode") {
                 Some((string, entry)) => Some(InputMode::from_str(string).map_err(|_| {
@@ -2225,9 +2369,46 @@ impl Options {
             "support_kitty_keyboard_protocol"
         )
         .map(|(v, _)| v);
+        let dir_local_layouts =
+            kdl_property_first_arg_as_bool_or_error!(kdl_options, "dir_local_layouts")
+                .map(|(v, _)| v);
+        let dir_local_layouts_search_depth =
+            kdl_property_first_arg_as_i64_or_error!(kdl_options, "dir_local_layouts_search_depth")
+                .map(|(v, _)| v as usize);
+        let key_sequence_timeout =
+            kdl_property_first_arg_as_i64_or_error!(kdl_options, "key_sequence_timeout")
+                .map(|(v, _)| v as u64);
+        let which_key_overlay =
+            kdl_property_first_arg_as_bool_or_error!(kdl_options, "which_key_overlay")
+                .map(|(v, _)| v);
+        let dim_unfocused_panes =
+            kdl_property_first_arg_as_i64_or_error!(kdl_options, "dim_unfocused_panes")
+                .map(|(v, _)| v.clamp(0, 100) as u8);
+        let plugin_memory_limit =
+            match kdl_property_first_arg_as_i64_or_error!(kdl_options, "plugin_memory_limit") {
+                Some((v, entry)) => Some(usize::try_from(v).map_err(|_| {
+                    kdl_parsing_error!(
+                        format!("plugin_memory_limit cannot be negative: {}", v),
+                        entry
+                    )
+                })?),
+                None => None,
+            };
+        let plugin_call_timeout =
+            match kdl_property_first_arg_as_i64_or_error!(kdl_options, "plugin_call_timeout") {
+                Some((v, entry)) => Some(u64::try_from(v).map_err(|_| {
+                    kdl_parsing_error!(
+                        format!("plugin_call_timeout cannot be negative: {}", v),
+                        entry
+                    )
+                })?),
+                None => None,
+            };
         Ok(Options {
             simplified_ui,
             theme,
+            theme_dark,
+            theme_light,
             default_mode,
             default_shell,
             default_cwd,
@@ -2253,6 +2434,13 @@ impl Options {
             serialization_interval,
             disable_session_metadata,
             support_kitty_keyboard_protocol,
+            dir_local_layouts,
+            dir_local_layouts_search_depth,
+            key_sequence_timeout,
+            which_key_overlay,
+            dim_unfocused_panes,
+            plugin_memory_limit,
+            plugin_call_timeout,
         })
     }
     pub fn from_string(stringified_keybindings: &String) -> Result<Self, ConfigError> {
@@ -2289,6 +2477,63 @@ impl Options {
             None
         }
     }
+    fn theme_dark_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// Choose the theme to use when the terminal has a dark background, Zellij switches",
+            "// between theme_dark and theme_light as the terminal changes its color scheme",
+            "// Default: the theme option",
+            "// ",
+        );
+
+        let create_node = |node_value: &str| -> KdlNode {
+            let mut node = KdlNode::new("theme_dark");
+            node.push(node_value.to_owned());
+            node
+        };
+        if let Some(theme_dark) = &self.theme_dark {
+            let mut node = create_node(theme_dark);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node("dracula");
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
+        }
+    }
+    fn theme_light_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}",
+            " ",
+            "// Choose the theme to use when the terminal has a light background",
+            "// Default: the theme option",
+            "// ",
+        );
+
+        let create_node = |node_value: &str| -> KdlNode {
+            let mut node = KdlNode::new("theme_light");
+            node.push(node_value.to_owned());
+            node
+        };
+        if let Some(theme_light) = &self.theme_light {
+            let mut node = create_node(theme_light);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node("gruvbox-light");
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
+        }
+    }
     fn theme_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
         let comment_text = format!(
             "{}\n{}\n{}\n{}",
@@ -3052,36 +3297,245 @@ impl Options {
             None
         }
     }
-    pub fn to_kdl(&self, add_comments: bool) -> Vec<KdlNode> {
-        let mut nodes = vec![];
-        if let Some(simplified_ui_node) = self.simplified_ui_to_kdl(add_comments) {
-            nodes.push(simplified_ui_node);
-        }
-        if let Some(theme_node) = self.theme_to_kdl(add_comments) {
-            nodes.push(theme_node);
-        }
-        if let Some(default_mode) = self.default_mode_to_kdl(add_comments) {
-            nodes.push(default_mode);
-        }
-        if let Some(default_shell) = self.default_shell_to_kdl(add_comments) {
-            nodes.push(default_shell);
+    fn dir_local_layouts_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!("{}\n{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// Look for a `.zellij/layout.kdl` or `.zellij.kdl` file in the current directory and its",
+            "// parents when starting without an explicit layout. A trust prompt is shown the first time",
+            "// such a file is used and whenever it changes",
+            "// Default: true",
+            "// ",
+        );
+
+        let create_node = |node_value: bool| -> KdlNode {
+            let mut node = KdlNode::new("dir_local_layouts");
+            node.push(KdlValue::Bool(node_value));
+            node
+        };
+        if let Some(dir_local_layouts) = self.dir_local_layouts {
+            let mut node = create_node(dir_local_layouts);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(false);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
         }
-        if let Some(default_cwd) = self.default_cwd_to_kdl(add_comments) {
-            nodes.push(default_cwd);
+    }
+    fn dir_local_layouts_search_depth_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}",
+            " ",
+            "// How many parent directories to search for a directory-local layout",
+            "// Default: 10",
+            "// ",
+        );
+
+        let create_node = |node_value: usize| -> KdlNode {
+            let mut node = KdlNode::new("dir_local_layouts_search_depth");
+            node.push(KdlValue::Base10(node_value as i64));
+            node
+        };
+        if let Some(dir_local_layouts_search_depth) = self.dir_local_layouts_search_depth {
+            let mut node = create_node(dir_local_layouts_search_depth);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(10);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
         }
-        if let Some(default_layout) = self.default_layout_to_kdl(add_comments) {
-            nodes.push(default_layout);
+    }
+    fn dim_unfocused_panes_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// Render the panes that are not focused with reduced intensity, by this percentage",
+            "// (0-100, each client dims according to its own focused pane)",
+            "// Default: 0 (disabled)",
+            "// ",
+        );
+
+        let create_node = |node_value: u8| -> KdlNode {
+            let mut node = KdlNode::new("dim_unfocused_panes");
+            node.push(KdlValue::Base10(node_value as i64));
+            node
+        };
+        if let Some(dim_unfocused_panes) = self.dim_unfocused_panes {
+            let mut node = create_node(dim_unfocused_panes);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(30);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
         }
-        if let Some(layout_dir) = self.layout_dir_to_kdl(add_comments) {
-            nodes.push(layout_dir);
+    }
+    fn plugin_memory_limit_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// The most memory (in megabytes) a plugin may use before it is stopped, can be set",
+            "// for a single plugin with a memory_limit in its configuration",
+            "// Default: no limit",
+            "// ",
+        );
+
+        let create_node = |node_value: usize| -> KdlNode {
+            let mut node = KdlNode::new("plugin_memory_limit");
+            node.push(KdlValue::Base10(node_value as i64));
+            node
+        };
+        if let Some(plugin_memory_limit) = self.plugin_memory_limit {
+            let mut node = create_node(plugin_memory_limit);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(512);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
         }
-        if let Some(theme_dir) = self.theme_dir_to_kdl(add_comments) {
-            nodes.push(theme_dir);
+    }
+    fn plugin_call_timeout_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// The longest (in milliseconds) a single call into a plugin (eg. to update or render it)",
+            "// may run before the plugin is stopped, 0 for no limit, can be set for a single",
+            "// plugin with a call_timeout in its configuration",
+            "// Default: 10000",
+            "// ",
+        );
+
+        let create_node = |node_value: u64| -> KdlNode {
+            let mut node = KdlNode::new("plugin_call_timeout");
+            node.push(KdlValue::Base10(node_value as i64));
+            node
+        };
+        if let Some(plugin_call_timeout) = self.plugin_call_timeout {
+            let mut node = create_node(plugin_call_timeout);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(10000);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
         }
-        if let Some(mouse_mode) = self.mouse_mode_to_kdl(add_comments) {
-            nodes.push(mouse_mode);
+    }
+    fn which_key_overlay_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// Show an overlay listing the available keybindings shortly after switching to an",
+            "// input mode other than normal or locked",
+            "// Default: false",
+            "// ",
+        );
+
+        let create_node = |node_value: bool| -> KdlNode {
+            let mut node = KdlNode::new("which_key_overlay");
+            node.push(KdlValue::Bool(node_value));
+            node
+        };
+        if let Some(which_key_overlay) = self.which_key_overlay {
+            let mut node = create_node(which_key_overlay);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(true);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
         }
-        if let Some(pane_frames) = self.pane_frames_to_kdl(add_comments) {
+    }
+    fn key_sequence_timeout_to_kdl(&self, add_comments: bool) -> Option<KdlNode> {
+        let comment_text = format!(
+            "{}\n{}\n{}\n{}\n{}",
+            " ",
+            "// How long to wait (in milliseconds) for the next key of a multi-key sequence",
+            "// before the keys typed so far are sent to the pane",
+            "// Default: 1000",
+            "// ",
+        );
+
+        let create_node = |node_value: u64| -> KdlNode {
+            let mut node = KdlNode::new("key_sequence_timeout");
+            node.push(KdlValue::Base10(node_value as i64));
+            node
+        };
+        if let Some(key_sequence_timeout) = self.key_sequence_timeout {
+            let mut node = create_node(key_sequence_timeout);
+            if add_comments {
+                node.set_leading(format!("{}\n", comment_text));
+            }
+            Some(node)
+        } else if add_comments {
+            let mut node = create_node(500);
+            node.set_leading(format!("{}\n// ", comment_text));
+            Some(node)
+        } else {
+            None
+        }
+    }
+    pub fn to_kdl(&self, add_comments: bool) -> Vec<KdlNode> {
+        let mut nodes = vec![];
+        if let Some(simplified_ui_node) = self.simplified_ui_to_kdl(add_comments) {
+            nodes.push(simplified_ui_node);
+        }
+        if let Some(theme_node) = self.theme_to_kdl(add_comments) {
+            nodes.push(theme_node);
+        }
+        if let Some(theme_dark) = self.theme_dark_to_kdl(add_comments) {
+            nodes.push(theme_dark);
+        }
+        if let Some(theme_light) = self.theme_light_to_kdl(add_comments) {
+            nodes.push(theme_light);
+        }
+        if let Some(default_mode) = self.default_mode_to_kdl(add_comments) {
+            nodes.push(default_mode);
+        }
+        if let Some(default_shell) = self.default_shell_to_kdl(add_comments) {
+            nodes.push(default_shell);
+        }
+        if let Some(default_cwd) = self.default_cwd_to_kdl(add_comments) {
+            nodes.push(default_cwd);
+        }
+        if let Some(default_layout) = self.default_layout_to_kdl(add_comments) {
+            nodes.push(default_layout);
+        }
+        if let Some(layout_dir) = self.layout_dir_to_kdl(add_comments) {
+            nodes.push(layout_dir);
+        }
+        if let Some(theme_dir) = self.theme_dir_to_kdl(add_comments) {
+            nodes.push(theme_dir);
+        }
+        if let Some(mouse_mode) = self.mouse_mode_to_kdl(add_comments) {
+            nodes.push(mouse_mode);
+        }
+        if let Some(pane_frames) = self.pane_frames_to_kdl(add_comments) {
             nodes.push(pane_frames);
         }
         if let Some(mirror_session) = self.mirror_session_to_kdl(add_comments) {
@@ -3139,6 +3593,29 @@ impl Options {
         {
             nodes.push(support_kitty_keyboard_protocol);
         }
+        if let Some(dir_local_layouts) = self.dir_local_layouts_to_kdl(add_comments) {
+            nodes.push(dir_local_layouts);
+        }
+        if let Some(dir_local_layouts_search_depth) =
+            self.dir_local_layouts_search_depth_to_kdl(add_comments)
+        {
+            nodes.push(dir_local_layouts_search_depth);
+        }
+        if let Some(key_sequence_timeout) = self.key_sequence_timeout_to_kdl(add_comments) {
+            nodes.push(key_sequence_timeout);
+        }
+        if let Some(which_key_overlay) = self.which_key_overlay_to_kdl(add_comments) {
+            nodes.push(which_key_overlay);
+        }
+        if let Some(dim_unfocused_panes) = self.dim_unfocused_panes_to_kdl(add_comments) {
+            nodes.push(dim_unfocused_panes);
+        }
+        if let Some(plugin_memory_limit) = self.plugin_memory_limit_to_kdl(add_comments) {
+            nodes.push(plugin_memory_limit);
+        }
+        if let Some(plugin_call_timeout) = self.plugin_call_timeout_to_kdl(add_comments) {
+            nodes.push(plugin_call_timeout);
+        }
         nodes
     }
 }
@@ -3150,7 +3627,31 @@ impl Layout {
         raw_swap_layouts: Option<(&str, &str)>, // raw_swap_layouts swap_layouts_file_name
         cwd: Option<PathBuf>,
     ) -> Result<Self, ConfigError> {
-        let mut kdl_layout_parser = KdlLayoutParser::new(raw_layout, cwd, file_name.clone());
+        Layout::from_kdl_with_vars(
+            raw_layout,
+            file_name,
+            raw_swap_layouts,
+            cwd,
+            BTreeMap::new(),
+        )
+    }
+    /// The raw layout with its variables substituted, so that the configuration it contains (eg.
+    /// `theme "${theme}"`) can be parsed with the same values as the layout itself
+    pub fn substitute_vars(
+        raw_layout: &str,
+        layout_vars: BTreeMap<String, String>,
+    ) -> Result<String, ConfigError> {
+        KdlLayoutParser::new(raw_layout, None, None, layout_vars).substituted_raw_layout()
+    }
+    pub fn from_kdl_with_vars(
+        raw_layout: &str,
+        file_name: Option<String>,
+        raw_swap_layouts: Option<(&str, &str)>, // raw_swap_layouts swap_layouts_file_name
+        cwd: Option<PathBuf>,
+        layout_vars: BTreeMap<String, String>,
+    ) -> Result<Self, ConfigError> {
+        let mut kdl_layout_parser =
+            KdlLayoutParser::new(raw_layout, cwd, file_name.clone(), layout_vars);
         let layout = kdl_layout_parser.parse().map_err(|e| match e {
             ConfigError::KdlError(kdl_error) => ConfigError::KdlError(kdl_error.add_src(
                 file_name.unwrap_or_else(|| "N/A".to_owned()),
@@ -3259,18 +3760,75 @@ impl EnvironmentVariables {
 impl Keybinds {
     fn bind_keys_in_block(
         block: &KdlNode,
-        input_mode_keybinds: &mut HashMap<KeyWithModifier, Vec<Action>>,
+        keybinds_from_config: &mut Keybinds,
+        input_mode: &InputMode,
         config_options: &Options,
+        layout_vars: &BTreeMap<String, String>,
     ) -> Result<(), ConfigError> {
         let all_nodes = kdl_children_nodes_or_error!(block, "no keybinding block for mode");
         let bind_nodes = all_nodes.iter().filter(|n| kdl_name!(n) == "bind");
         let unbind_nodes = all_nodes.iter().filter(|n| kdl_name!(n) == "unbind");
         for key_block in bind_nodes {
-            Keybinds::bind_actions_for_each_key(key_block, input_mode_keybinds, config_options)?;
+            let condition = Keybinds::keybind_condition_from_kdl(key_block)?;
+            if !condition.is_empty() {
+                if kdl_arg_is_truthy!(key_block, "sequence") {
+                    return Err(ConfigError::new_kdl_error(
+                        "Key sequences cannot have conditions".into(),
+                        key_block.span().offset(),
+                        key_block.span().len(),
+                    ));
+                }
+                let keys: Vec<KeyWithModifier> = keys_from_kdl!(key_block);
+                let actions: Vec<Action> =
+                    key_actions_from_kdl!(key_block, config_options, layout_vars);
+                let conditional_keybinds =
+                    keybinds_from_config.get_conditional_keybinds_for_mode_mut(input_mode);
+                for key in keys {
+                    add_conditional_keybind(
+                        conditional_keybinds.entry(key).or_default(),
+                        condition.clone(),
+                        actions.clone(),
+                    );
+                }
+            } else if kdl_arg_is_truthy!(key_block, "sequence") {
+                Keybinds::bind_actions_for_key_sequence(
+                    key_block,
+                    keybinds_from_config.get_key_sequences_for_mode_mut(input_mode),
+                    config_options,
+                    layout_vars,
+                )?;
+            } else {
+                Keybinds::bind_actions_for_each_key(
+                    key_block,
+                    keybinds_from_config.get_input_mode_mut(input_mode),
+                    config_options,
+                    layout_vars,
+                )?;
+            }
         }
         // we loop a second time so that the unbinds always happen after the binds
         for key_block in unbind_nodes {
-            Keybinds::unbind_keys(key_block, input_mode_keybinds)?;
+            if kdl_arg_is_truthy!(key_block, "sequence") {
+                let sequence = Keybinds::key_sequence_from_kdl(key_block)?;
+                keybinds_from_config
+                    .get_key_sequences_for_mode_mut(input_mode)
+                    .remove(&sequence);
+            } else {
+                let keys: Vec<KeyWithModifier> = keys_from_kdl!(key_block);
+                let conditional_keybinds =
+                    keybinds_from_config.get_conditional_keybinds_for_mode_mut(input_mode);
+                for key in &keys {
+                    conditional_keybinds.remove(key);
+                }
+                Keybinds::unbind_key_sequences_starting_with(
+                    &keys,
+                    keybinds_from_config.get_key_sequences_for_mode_mut(input_mode),
+                );
+                Keybinds::unbind_keys(
+                    key_block,
+                    keybinds_from_config.get_input_mode_mut(input_mode),
+                )?;
+            }
         }
         for key_block in all_nodes {
             if kdl_name!(key_block) != "bind" && kdl_name!(key_block) != "unbind" {
@@ -3287,6 +3845,7 @@ impl Keybinds {
         kdl_keybinds: &KdlNode,
         base_keybinds: Keybinds,
         config_options: &Options,
+        layout_vars: &BTreeMap<String, String>,
     ) -> Result<Self, ConfigError> {
         let clear_defaults = kdl_arg_is_truthy!(kdl_keybinds, "clear-defaults");
         let mut keybinds_from_config = if clear_defaults {
@@ -3294,6 +3853,14 @@ impl Keybinds {
         } else {
             base_keybinds
         };
+        // custom modes are declared before anything is bound so that shared blocks can include
+        // them, regardless of where they appear in the config
+        let mut custom_modes = vec![];
+        for block in kdl_children_nodes_or_error!(kdl_keybinds, "keybindings with no children") {
+            if kdl_name!(block) == "custom_mode" {
+                custom_modes.push(Keybinds::custom_input_mode_from_kdl(block)?);
+            }
+        }
         for block in kdl_children_nodes_or_error!(kdl_keybinds, "keybindings with no children") {
             if kdl_name!(block) == "shared_except" || kdl_name!(block) == "shared" {
                 let mut modes_to_exclude = vec![];
@@ -3310,21 +3877,35 @@ impl Keybinds {
                     if modes_to_exclude.contains(&mode) {
                         continue;
                     }
-                    let mut input_mode_keybinds = keybinds_from_config.get_input_mode_mut(&mode);
-                    Keybinds::bind_keys_in_block(block, &mut input_mode_keybinds, config_options)?;
+                    Keybinds::bind_keys_in_block(
+                        block,
+                        &mut keybinds_from_config,
+                        &mode,
+                        config_options,
+                        layout_vars,
+                    )?;
                 }
             }
             if kdl_name!(block) == "shared_among" {
                 let mut modes_to_include = vec![];
                 for mode_name in kdl_string_arguments!(block) {
-                    modes_to_include.push(InputMode::from_str(mode_name)?);
-                }
-                for mode in InputMode::iter() {
-                    if !modes_to_include.contains(&mode) {
-                        continue;
+                    let custom_mode = custom_modes
+                        .iter()
+                        .find(|custom_mode| custom_mode.name() == mode_name);
+                    match custom_mode {
+                        Some(custom_mode) => modes_to_include.push(custom_mode.clone()),
+                        None => modes_to_include.push(InputMode::from_str(mode_name)?),
                     }
-                    let mut input_mode_keybinds = keybinds_from_config.get_input_mode_mut(&mode);
-                    Keybinds::bind_keys_in_block(block, &mut input_mode_keybinds, config_options)?;
+                }
+                // unlike the other shared blocks, this one can include custom modes
+                for mode in modes_to_include {
+                    Keybinds::bind_keys_in_block(
+                        block,
+                        &mut keybinds_from_config,
+                        &mode,
+                        config_options,
+                        layout_vars,
+                    )?;
                 }
             }
         }
@@ -3336,9 +3917,14 @@ impl Keybinds {
             {
                 continue;
             }
-            let mut input_mode_keybinds =
-                Keybinds::input_mode_keybindings(mode, &mut keybinds_from_config)?;
-            Keybinds::bind_keys_in_block(mode, &mut input_mode_keybinds, config_options)?;
+            let input_mode = Keybinds::input_mode_keybindings(mode, &mut keybinds_from_config)?;
+            Keybinds::bind_keys_in_block(
+                mode,
+                &mut keybinds_from_config,
+                &input_mode,
+                config_options,
+                layout_vars,
+            )?;
         }
         if let Some(global_unbind) = kdl_keybinds.children().and_then(|c| c.get("unbind")) {
             Keybinds::unbind_keys_in_all_modes(global_unbind, &mut keybinds_from_config)?;
@@ -3349,14 +3935,85 @@ impl Keybinds {
         key_block: &KdlNode,
         input_mode_keybinds: &mut HashMap<KeyWithModifier, Vec<Action>>,
         config_options: &Options,
+        layout_vars: &BTreeMap<String, String>,
     ) -> Result<(), ConfigError> {
         let keys: Vec<KeyWithModifier> = keys_from_kdl!(key_block);
-        let actions: Vec<Action> = actions_from_kdl!(key_block, config_options);
+        let actions: Vec<Action> = key_actions_from_kdl!(key_block, config_options, layout_vars);
         for key in keys {
             input_mode_keybinds.insert(key, actions.clone());
         }
         Ok(())
     }
+    fn bind_actions_for_key_sequence(
+        key_block: &KdlNode,
+        input_mode_sequences: &mut HashMap<Vec<KeyWithModifier>, Vec<Action>>,
+        config_options: &Options,
+        layout_vars: &BTreeMap<String, String>,
+    ) -> Result<(), ConfigError> {
+        let sequence = Keybinds::key_sequence_from_kdl(key_block)?;
+        let actions: Vec<Action> = key_actions_from_kdl!(key_block, config_options, layout_vars);
+        input_mode_sequences.insert(sequence, actions);
+        Ok(())
+    }
+    // eg. bind "Ctrl h" if_not_running="nvim|vim" { MoveFocus "Left"; }
+    fn keybind_condition_from_kdl(key_block: &KdlNode) -> Result<KeybindCondition, ConfigError> {
+        let mut condition = KeybindCondition::default();
+        for entry in key_block.entries() {
+            let property_name = match entry.name() {
+                Some(name) => name.value(),
+                None => continue,
+            };
+            if property_name == "sequence" {
+                continue;
+            }
+            if !KeybindCondition::PROPERTY_NAMES.contains(&property_name) {
+                return Err(ConfigError::new_kdl_error(
+                    format!(
+                        "Unknown keybind property: '{}', expected one of: sequence, {}",
+                        property_name,
+                        KeybindCondition::PROPERTY_NAMES.join(", ")
+                    ),
+                    entry.span().offset(),
+                    entry.span().len(),
+                ));
+            }
+            let pattern = entry.value().as_string().ok_or(ConfigError::new_kdl_error(
+                format!("{} must be a string", property_name),
+                entry.span().offset(),
+                entry.span().len(),
+            ))?;
+            condition.set(property_name, pattern).map_err(|e| {
+                ConfigError::new_kdl_error(e, entry.span().offset(), entry.span().len())
+            })?;
+        }
+        Ok(condition)
+    }
+    // eg. bind "Ctrl a" "c" sequence=true { NewTab; }
+    fn key_sequence_from_kdl(key_block: &KdlNode) -> Result<Vec<KeyWithModifier>, ConfigError> {
+        let mut sequence = vec![];
+        for entry in key_block.entries().iter().filter(|e| e.name().is_none()) {
+            let key = entry.value().as_string().ok_or(ConfigError::new_kdl_error(
+                "Not a string".into(),
+                entry.span().offset(),
+                entry.span().len(),
+            ))?;
+            sequence.push(KeyWithModifier::from_str(key).map_err(|_| {
+                ConfigError::new_kdl_error(
+                    format!("Invalid key: '{}'", key),
+                    entry.span().offset(),
+                    entry.span().len(),
+                )
+            })?);
+        }
+        if sequence.len() < 2 {
+            return Err(ConfigError::new_kdl_error(
+                "A key sequence must have at least two keys".into(),
+                key_block.span().offset(),
+                key_block.span().len(),
+            ));
+        }
+        Ok(sequence)
+    }
     fn unbind_keys(
         key_block: &KdlNode,
         input_mode_keybinds: &mut HashMap<KeyWithModifier, Vec<Action>>,
@@ -3372,40 +4029,112 @@ impl Keybinds {
         keybinds_from_config: &mut Keybinds,
     ) -> Result<(), ConfigError> {
         let keys: Vec<KeyWithModifier> = keys_from_kdl!(global_unbind);
-        for mode in keybinds_from_config.0.values_mut() {
+        for mode in keybinds_from_config.single.values_mut() {
+            for key in &keys {
+                mode.remove(key);
+            }
+        }
+        for mode in keybinds_from_config.sequences.values_mut() {
+            Keybinds::unbind_key_sequences_starting_with(&keys, mode);
+        }
+        for mode in keybinds_from_config.conditional.values_mut() {
             for key in &keys {
-                mode.remove(&key);
+                mode.remove(key);
             }
         }
         Ok(())
     }
-    fn input_mode_keybindings<'a>(
+    // an unbound key can no longer start a sequence, otherwise it would still be held back as the
+    // beginning of one
+    fn unbind_key_sequences_starting_with(
+        keys: &[KeyWithModifier],
+        input_mode_sequences: &mut HashMap<Vec<KeyWithModifier>, Vec<Action>>,
+    ) {
+        input_mode_sequences.retain(|sequence, _actions| {
+            sequence
+                .first()
+                .map(|first_key| !keys.contains(first_key))
+                .unwrap_or(true)
+        });
+    }
+    fn input_mode_keybindings(
         mode: &KdlNode,
-        keybinds_from_config: &'a mut Keybinds,
-    ) -> Result<&'a mut HashMap<KeyWithModifier, Vec<Action>>, ConfigError> {
+        keybinds_from_config: &mut Keybinds,
+    ) -> Result<InputMode, ConfigError> {
         let mode_name = kdl_name!(mode);
-        let input_mode = InputMode::from_str(mode_name).map_err(|_| {
+        let input_mode = if mode_name == "custom_mode" {
+            let input_mode = Keybinds::custom_input_mode_from_kdl(mode)?;
+            // make sure the mode is listed even if nothing is bound in it
+            keybinds_from_config.get_input_mode_mut(&input_mode);
+            input_mode
+        } else {
+            InputMode::from_str(mode_name).map_err(|_| {
+                ConfigError::new_kdl_error(
+                    format!("Invalid mode: '{}'", mode_name),
+                    mode.name().span().offset(),
+                    mode.name().span().len(),
+                )
+            })?
+        };
+        let clear_defaults_for_mode = kdl_arg_is_truthy!(mode, "clear-defaults");
+        if clear_defaults_for_mode {
+            keybinds_from_config.get_input_mode_mut(&input_mode).clear();
+            keybinds_from_config
+                .get_key_sequences_for_mode_mut(&input_mode)
+                .clear();
+            keybinds_from_config
+                .get_conditional_keybinds_for_mode_mut(&input_mode)
+                .clear();
+        }
+        Ok(input_mode)
+    }
+    // eg. custom_mode "deploy" { bind "b" { Run "make" "build"; } }
+    fn custom_input_mode_from_kdl(mode: &KdlNode) -> Result<InputMode, ConfigError> {
+        let name = kdl_first_entry_as_string!(mode).ok_or_else(|| {
             ConfigError::new_kdl_error(
-                format!("Invalid mode: '{}'", mode_name),
-                mode.name().span().offset(),
-                mode.name().span().len(),
+                "Custom modes must have a name, eg. custom_mode \"deploy\"".into(),
+                mode.span().offset(),
+                mode.span().len(),
             )
         })?;
-        let input_mode_keybinds = keybinds_from_config.get_input_mode_mut(&input_mode);
-        let clear_defaults_for_mode = kdl_arg_is_truthy!(mode, "clear-defaults");
-        if clear_defaults_for_mode {
-            input_mode_keybinds.clear();
+        let name_is_taken = InputMode::from_str(name)
+            .map(|input_mode| !input_mode.is_custom())
+            .unwrap_or(false);
+        if name_is_taken || name == "custom_mode" {
+            return Err(ConfigError::new_kdl_error(
+                format!("'{}' is already the name of a built-in mode", name),
+                mode.span().offset(),
+                mode.span().len(),
+            ));
         }
-        Ok(input_mode_keybinds)
+        Ok(InputMode::Custom(name.to_owned()))
+    }
+    /// A configuration (to be applied with eg. `Reconfigure`) declaring the custom mode `name`
+    /// with the given keybindings (eg. `bind "d" { Run "make" "deploy"; }`)
+    pub fn custom_input_mode_config(
+        name: &str,
+        stringified_keybindings: &str,
+    ) -> Result<String, ConfigError> {
+        let mode_keybinds: KdlDocument = stringified_keybindings.parse()?;
+        let mut mode_node = KdlNode::new("custom_mode");
+        mode_node.push(name);
+        mode_node.set_children(mode_keybinds);
+        let mut keybinds_children = KdlDocument::new();
+        keybinds_children.nodes_mut().push(mode_node);
+        let mut keybinds_node = KdlNode::new("keybinds");
+        keybinds_node.set_children(keybinds_children);
+        keybinds_node.fmt();
+        Ok(keybinds_node.to_string())
     }
     pub fn from_string(
         stringified_keybindings: String,
         base_keybinds: Keybinds,
         config_options: &Options,
+        layout_vars: &BTreeMap<String, String>,
     ) -> Result<Self, ConfigError> {
         let document: KdlDocument = stringified_keybindings.parse()?;
         if let Some(kdl_keybinds) = document.get("keybinds") {
-            Keybinds::from_kdl(&kdl_keybinds, base_keybinds, config_options)
+            Keybinds::from_kdl(kdl_keybinds, base_keybinds, config_options, layout_vars)
         } else {
             Err(ConfigError::new_kdl_error(
                 format!("Could not find keybinds node"),
@@ -3421,17 +4150,19 @@ impl Keybinds {
     ) -> BTreeMap<BTreeSet<InputMode>, BTreeMap<KeyWithModifier, Vec<Action>>> {
         let mut minimized: BTreeMap<BTreeSet<InputMode>, BTreeMap<KeyWithModifier, Vec<Action>>> =
             BTreeMap::new();
+        // custom modes are serialized on their own
         let mut flattened: Vec<BTreeMap<KeyWithModifier, Vec<Action>>> = self
-            .0
+            .single
             .iter()
+            .filter(|(input_mode, _keybind)| !input_mode.is_custom())
             .map(|(_input_mode, keybind)| keybind.clone().into_iter().collect())
             .collect();
         for keybind in flattened.drain(..) {
             for (key, actions) in keybind.into_iter() {
                 let mut appears_in_modes: BTreeSet<InputMode> = BTreeSet::new();
-                for (input_mode, keybinds) in self.0.iter() {
+                for (input_mode, keybinds) in self.single.iter().filter(|(m, _)| !m.is_custom()) {
                     if keybinds.get(&key) == Some(&actions) {
-                        appears_in_modes.insert(*input_mode);
+                        appears_in_modes.insert(input_mode.clone());
                     }
                 }
                 minimized
@@ -3469,6 +4200,16 @@ impl Keybinds {
             node
         }
     }
+    fn serialize_mode_node(input_mode: &InputMode) -> KdlNode {
+        match input_mode {
+            InputMode::Custom(name) => {
+                let mut node = KdlNode::new("custom_mode");
+                node.push(name.as_str());
+                node
+            },
+            _ => KdlNode::new(input_mode.name()),
+        }
+    }
     fn serialize_mode_keybinds(
         &self,
         keybinds: &BTreeMap<KeyWithModifier, Vec<Action>>,
@@ -3477,28 +4218,67 @@ impl Keybinds {
         for keybind in keybinds {
             let mut keybind_node = KdlNode::new("bind");
             keybind_node.push(keybind.0.to_kdl());
-            let mut actions = KdlDocument::new();
-            let mut actions_have_children = false;
-            for action in keybind.1 {
-                if let Some(kdl_action) = action.to_kdl() {
-                    if kdl_action.children().is_some() {
-                        actions_have_children = true;
-                    }
-                    actions.nodes_mut().push(kdl_action);
+            keybind_node.set_children(Keybinds::serialize_keybind_actions(keybind.1));
+            mode_keybinds.nodes_mut().push(keybind_node);
+        }
+        mode_keybinds
+    }
+    fn serialize_mode_key_sequences(
+        &self,
+        sequences: &HashMap<Vec<KeyWithModifier>, Vec<Action>>,
+    ) -> Vec<KdlNode> {
+        let stable_sorted: BTreeMap<_, _> = sequences.iter().collect();
+        let mut sequence_nodes = vec![];
+        for (sequence, actions) in stable_sorted {
+            let mut sequence_node = KdlNode::new("bind");
+            for key in sequence {
+                sequence_node.push(key.to_kdl());
+            }
+            sequence_node.insert("sequence", true);
+            sequence_node.set_children(Keybinds::serialize_keybind_actions(actions));
+            sequence_nodes.push(sequence_node);
+        }
+        sequence_nodes
+    }
+    fn serialize_mode_conditional_keybinds(
+        &self,
+        conditional_keybinds: &HashMap<KeyWithModifier, ConditionalKeybinds>,
+    ) -> Vec<KdlNode> {
+        let stable_sorted: BTreeMap<_, _> = conditional_keybinds.iter().collect();
+        let mut conditional_keybind_nodes = vec![];
+        for (key, conditional_keybinds) in stable_sorted {
+            for (condition, actions) in conditional_keybinds {
+                let mut keybind_node = KdlNode::new("bind");
+                keybind_node.push(key.to_kdl());
+                for (property_name, pattern) in condition.properties() {
+                    keybind_node.insert(property_name, pattern.to_owned());
                 }
+                keybind_node.set_children(Keybinds::serialize_keybind_actions(actions));
+                conditional_keybind_nodes.push(keybind_node);
             }
-            if !actions_have_children {
-                for action in actions.nodes_mut() {
-                    action.set_leading("");
-                    action.set_trailing("; ");
+        }
+        conditional_keybind_nodes
+    }
+    fn serialize_keybind_actions(keybind_actions: &Vec<Action>) -> KdlDocument {
+        let mut actions = KdlDocument::new();
+        let mut actions_have_children = false;
+        for action in keybind_actions {
+            if let Some(kdl_action) = action.to_kdl() {
+                if kdl_action.children().is_some() {
+                    actions_have_children = true;
                 }
-                actions.set_leading(" ");
-                actions.set_trailing("");
+                actions.nodes_mut().push(kdl_action);
             }
-            keybind_node.set_children(actions);
-            mode_keybinds.nodes_mut().push(keybind_node);
         }
-        mode_keybinds
+        if !actions_have_children {
+            for action in actions.nodes_mut() {
+                action.set_leading("");
+                action.set_trailing("; ");
+            }
+            actions.set_leading(" ");
+            actions.set_trailing("");
+        }
+        actions
     }
     pub fn to_kdl(&self, should_clear_defaults: bool) -> KdlNode {
         let mut keybinds_node = KdlNode::new("keybinds");
@@ -3541,6 +4321,60 @@ impl Keybinds {
             mode_node.set_children(mode_keybinds);
             keybinds_children.nodes_mut().push(mode_node);
         }
+        let mut custom_modes: Vec<InputMode> = self
+            .single
+            .keys()
+            .chain(self.sequences.keys())
+            .chain(self.conditional.keys())
+            .filter(|input_mode| input_mode.is_custom())
+            .cloned()
+            .collect();
+        custom_modes.sort_by_key(|input_mode| input_mode.name());
+        custom_modes.dedup();
+        for input_mode in &custom_modes {
+            let mut mode_node = Keybinds::serialize_mode_node(input_mode);
+            let keybinds = self
+                .single
+                .get(input_mode)
+                .map(|keybinds| keybinds.clone().into_iter().collect())
+                .unwrap_or_default();
+            mode_node.set_children(self.serialize_mode_keybinds(&keybinds));
+            keybinds_children.nodes_mut().push(mode_node);
+        }
+        // key sequences and conditional keybinds are not shared between modes, they are appended
+        // to the single mode node
+        for input_mode in InputMode::iter().chain(custom_modes) {
+            let mut sequence_nodes = match self.sequences.get(&input_mode) {
+                Some(sequences) => self.serialize_mode_key_sequences(sequences),
+                None => vec![],
+            };
+            if let Some(conditional_keybinds) = self.conditional.get(&input_mode) {
+                sequence_nodes
+                    .append(&mut self.serialize_mode_conditional_keybinds(conditional_keybinds));
+            }
+            if sequence_nodes.is_empty() {
+                continue;
+            }
+            let mode_node = Keybinds::serialize_mode_node(&input_mode);
+            let existing_mode_node = keybinds_children
+                .nodes_mut()
+                .iter_mut()
+                .find(|n| n.name() == mode_node.name() && n.entries() == mode_node.entries());
+            match existing_mode_node {
+                Some(mode_node) => {
+                    if let Some(children) = mode_node.children_mut() {
+                        children.nodes_mut().extend(sequence_nodes);
+                    }
+                },
+                None => {
+                    let mut mode_node = mode_node;
+                    let mut mode_keybinds = KdlDocument::new();
+                    mode_keybinds.nodes_mut().extend(sequence_nodes);
+                    mode_node.set_children(mode_keybinds);
+                    keybinds_children.nodes_mut().push(mode_node);
+                },
+            }
+        }
         keybinds_node.set_children(keybinds_children);
         keybinds_node
     }
@@ -3596,6 +4430,15 @@ impl BareKey {
 
 impl Config {
     pub fn from_kdl(kdl_config: &str, base_config: Option<Config>) -> Result<Config, ConfigError> {
+        Config::from_kdl_with_layout_vars(kdl_config, base_config, &BTreeMap::new())
+    }
+    /// Like `from_kdl`, with the layouts that keybindings open (eg. `NewTab`) loaded with the
+    /// given variables (eg. the `--layout-var`s the session was started with)
+    pub fn from_kdl_with_layout_vars(
+        kdl_config: &str,
+        base_config: Option<Config>,
+        layout_vars: &BTreeMap<String, String>,
+    ) -> Result<Config, ConfigError> {
         let mut config = base_config.unwrap_or_else(|| Config::default());
         let kdl_config: KdlDocument = kdl_config.parse()?;
 
@@ -3605,7 +4448,8 @@ impl Config {
         // TODO: handle cases where we have more than one of these blocks (eg. two "keybinds")
         // this should give an informative parsing error
         if let Some(kdl_keybinds) = kdl_config.get("keybinds") {
-            config.keybinds = Keybinds::from_kdl(&kdl_keybinds, config.keybinds, &config.options)?;
+            config.keybinds =
+                Keybinds::from_kdl(kdl_keybinds, config.keybinds, &config.options, layout_vars)?;
         }
         if let Some(kdl_themes) = kdl_config.get("themes") {
             let sourced_from_external_file = false;
@@ -3624,6 +4468,18 @@ impl Config {
             let config_env = EnvironmentVariables::from_kdl(&env_config)?;
             config.env = config.env.merge(config_env);
         }
+        if let Some(kdl_mouse_bindings) = kdl_config.get("mouse") {
+            config.mouse_bindings = MouseBindings::from_kdl(
+                kdl_mouse_bindings,
+                config.mouse_bindings,
+                &config.options,
+                layout_vars,
+            )?;
+        }
+        if let Some(kdl_macros) = kdl_config.get("macros") {
+            let config_macros = Macros::from_kdl(kdl_macros)?;
+            config.macros.merge(config_macros);
+        }
         Ok(config)
     }
     pub fn to_string(&self, add_comments: bool) -> String {
@@ -3648,6 +4504,14 @@ impl Config {
             document.nodes_mut().push(env);
         }
 
+        if let Some(mouse_bindings) = self.mouse_bindings.to_kdl() {
+            document.nodes_mut().push(mouse_bindings);
+        }
+
+        if let Some(macros) = self.macros.to_kdl() {
+            document.nodes_mut().push(macros);
+        }
+
         document
             .nodes_mut()
             .append(&mut self.options.to_kdl(add_comments));
@@ -3656,6 +4520,180 @@ impl Config {
     }
 }
 
+impl MouseBindings {
+    pub fn from_kdl(
+        kdl_mouse_bindings: &KdlNode,
+        base_mouse_bindings: MouseBindings,
+        config_options: &Options,
+        layout_vars: &BTreeMap<String, String>,
+    ) -> Result<MouseBindings, ConfigError> {
+        let mut mouse_bindings = base_mouse_bindings;
+        if kdl_arg_is_truthy!(kdl_mouse_bindings, "clear-defaults") {
+            mouse_bindings = MouseBindings::default();
+        }
+        for binding in kdl_children_nodes_or_error!(kdl_mouse_bindings, "empty mouse block") {
+            let buttons = MouseBindings::mouse_buttons_from_kdl(binding)?;
+            let target = MouseBindings::mouse_target_from_kdl(binding)?;
+            match kdl_name!(binding) {
+                "bind" => {
+                    let actions: Vec<Action> =
+                        actions_from_kdl!(binding, config_options, layout_vars);
+                    for button in buttons {
+                        mouse_bindings.bind(button, target, actions.clone());
+                    }
+                },
+                "unbind" => {
+                    for button in buttons {
+                        mouse_bindings.unbind(&button, target);
+                    }
+                },
+                node_name => {
+                    return Err(ConfigError::new_kdl_error(
+                        format!(
+                            "Unknown mouse binding node: '{}', expected bind or unbind",
+                            node_name
+                        ),
+                        binding.span().offset(),
+                        binding.span().len(),
+                    ));
+                },
+            }
+        }
+        Ok(mouse_bindings)
+    }
+    // eg. bind "Ctrl LeftClick" "MiddleClick" { ... }
+    fn mouse_buttons_from_kdl(
+        binding: &KdlNode,
+    ) -> Result<Vec<MouseButtonWithModifier>, ConfigError> {
+        let mut buttons = vec![];
+        for entry in binding.entries().iter().filter(|e| e.name().is_none()) {
+            let button = entry.value().as_string().ok_or(ConfigError::new_kdl_error(
+                "Not a string".into(),
+                entry.span().offset(),
+                entry.span().len(),
+            ))?;
+            buttons.push(MouseButtonWithModifier::from_str(button).map_err(|_| {
+                ConfigError::new_kdl_error(
+                    format!(
+                        "Invalid mouse button: '{}', expected eg. LeftClick, RightClick, MiddleClick, WheelUp or WheelDown",
+                        button
+                    ),
+                    entry.span().offset(),
+                    entry.span().len(),
+                )
+            })?);
+        }
+        Ok(buttons)
+    }
+    // eg. bind "RightClick" target="pane_frame" { ... }
+    fn mouse_target_from_kdl(binding: &KdlNode) -> Result<Option<MouseTarget>, ConfigError> {
+        match binding.get("target") {
+            Some(entry) => {
+                let target = entry.value().as_string().ok_or(ConfigError::new_kdl_error(
+                    "target must be a string".into(),
+                    entry.span().offset(),
+                    entry.span().len(),
+                ))?;
+                MouseTarget::from_str(target).map(Some).map_err(|_| {
+                    ConfigError::new_kdl_error(
+                        format!(
+                            "Invalid mouse target: '{}', expected one of: pane_body, pane_frame, tab_bar",
+                            target
+                        ),
+                        entry.span().offset(),
+                        entry.span().len(),
+                    )
+                })
+            },
+            None => Ok(None),
+        }
+    }
+    pub fn to_kdl(&self) -> Option<KdlNode> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut mouse_bindings = KdlNode::new("mouse");
+        let mut bindings = KdlDocument::new();
+        for (button, button_bindings) in &self.0 {
+            for (target, actions) in button_bindings {
+                let mut binding = KdlNode::new("bind");
+                binding.push(button.to_string());
+                if let Some(target) = target {
+                    binding.insert("target", target.to_string());
+                }
+                binding.set_children(Keybinds::serialize_keybind_actions(actions));
+                bindings.nodes_mut().push(binding);
+            }
+        }
+        mouse_bindings.set_children(bindings);
+        Some(mouse_bindings)
+    }
+}
+
+impl Macros {
+    pub fn from_kdl(kdl_macros: &KdlNode) -> Result<Macros, ConfigError> {
+        let mut macros: BTreeMap<String, Macro> = BTreeMap::new();
+        if let Some(kdl_macros) = kdl_children_nodes!(kdl_macros) {
+            for macro_definition in kdl_macros {
+                let macro_name = kdl_name!(macro_definition);
+                let start_mode =
+                    match kdl_get_string_property_or_child_value!(macro_definition, "start_mode") {
+                        Some(start_mode) => {
+                            Some(InputMode::from_str(start_mode).map_err(|_| {
+                                ConfigError::new_kdl_error(
+                                    format!("Invalid input mode: '{}'", start_mode),
+                                    macro_definition.span().offset(),
+                                    macro_definition.span().len(),
+                                )
+                            })?)
+                        },
+                        None => None,
+                    };
+                let mut keys = vec![];
+                for entry in macro_definition
+                    .entries()
+                    .iter()
+                    .filter(|e| e.name().is_none())
+                {
+                    let key = entry.value().as_string().ok_or(ConfigError::new_kdl_error(
+                        "Not a string".into(),
+                        entry.span().offset(),
+                        entry.span().len(),
+                    ))?;
+                    keys.push(KeyWithModifier::from_str(key).map_err(|_| {
+                        ConfigError::new_kdl_error(
+                            format!("Invalid key: '{}'", key),
+                            entry.span().offset(),
+                            entry.span().len(),
+                        )
+                    })?);
+                }
+                macros.insert(macro_name.to_owned(), Macro { start_mode, keys });
+            }
+        }
+        Ok(Macros::from_data(macros))
+    }
+    pub fn to_kdl(&self) -> Option<KdlNode> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut macros = KdlNode::new("macros");
+        let mut macro_definitions = KdlDocument::new();
+        for (macro_name, macro_definition) in &self.macros {
+            let mut node = KdlNode::new(macro_name.clone());
+            for key in &macro_definition.keys {
+                node.push(key.to_kdl());
+            }
+            if let Some(start_mode) = &macro_definition.start_mode {
+                node.insert("start_mode", format!("{:?}", start_mode).to_lowercase());
+            }
+            macro_definitions.nodes_mut().push(node);
+        }
+        macros.set_children(macro_definitions);
+        Some(macros)
+    }
+}
+
 impl PluginAliases {
     pub fn from_kdl(kdl_plugin_aliases: &KdlNode) -> Result<PluginAliases, ConfigError> {
         let mut aliases: BTreeMap<String, RunPlugin> = BTreeMap::new();
@@ -3671,7 +4709,7 @@ impl PluginAliases {
                         kdl_get_string_property_or_child_value!(alias_definition, "cwd")
                             .map(|s| PathBuf::from(s));
                     let run_plugin = RunPlugin::from_url(string_url)?
-                        .with_configuration(configuration.inner().clone())
+                        .with_configuration(configuration.inner_with_settings())
                         .with_initial_cwd(initial_cwd);
                     aliases.insert(alias_name.to_owned(), run_plugin);
                 }
@@ -3696,7 +4734,7 @@ impl PluginAliases {
                 cwd_node.push(cwd.display().to_string());
                 plugin_alias_children.nodes_mut().push(cwd_node);
             }
-            let configuration = plugin_alias.configuration.inner();
+            let configuration = plugin_alias.configuration.inner_with_settings();
             if !configuration.is_empty() {
                 has_children = true;
                 for (config_key, config_value) in configuration {
@@ -3784,6 +4822,13 @@ impl Themes {
         for theme_config in kdl_children_nodes_or_error!(themes_from_kdl, "no themes found") {
             let theme_name = kdl_name!(theme_config);
             let theme_colors = kdl_children_or_error!(theme_config, "empty theme");
+            let mut ui = UiColors::default();
+            for (role, _) in UiColors::default().roles() {
+                // the ui roles are optional and fall back to the colors of the palette
+                if theme_colors.get(role).is_some() {
+                    ui.set_role(role, PaletteColor::try_from((role, theme_colors))?);
+                }
+            }
             let theme = Theme {
                 palette: Palette {
                     fg: PaletteColor::try_from(("fg", theme_colors))?,
@@ -3797,6 +4842,7 @@ impl Themes {
                     cyan: PaletteColor::try_from(("cyan", theme_colors))?,
                     black: PaletteColor::try_from(("black", theme_colors))?,
                     white: PaletteColor::try_from(("white", theme_colors))?,
+                    ui,
                     ..Default::default()
                 },
                 sourced_from_external_file,
@@ -3844,6 +4890,16 @@ impl Themes {
             if let Some(extension) = path.extension() {
                 if extension == "kdl" {
                     themes = themes.merge(Themes::from_path(path)?);
+                } else if ["conf", "toml", "yml", "yaml"]
+                    .iter()
+                    .any(|e| extension == *e)
+                {
+                    // themes of other terminal emulators are imported as they are
+                    let contents = std::fs::read_to_string(&path)
+                        .map_err(|e| ConfigError::IoPath(e, path.clone()))?;
+                    if let Some(format) = ThemeFormat::detect(&path, &contents) {
+                        themes = themes.merge(themes_from_str(&path, &contents, format, true)?);
+                    }
                 }
             }
         }
@@ -3896,6 +4952,13 @@ impl Themes {
             current_theme_node_children
                 .nodes_mut()
                 .push(theme.palette.white.to_kdl("white"));
+            for (role, color) in theme.palette.ui.roles() {
+                if let Some(color) = color {
+                    current_theme_node_children
+                        .nodes_mut()
+                        .push(color.to_kdl(role));
+                }
+            }
             current_theme_node.set_children(current_theme_node_children);
             themes.nodes_mut().push(current_theme_node);
         }
@@ -3909,40 +4972,58 @@ impl Themes {
 }
 
 impl PermissionCache {
-    pub fn from_string(raw_string: String) -> Result<GrantedPermission, ConfigError> {
+    pub fn from_string(
+        raw_string: String,
+    ) -> Result<(GrantedPermission, GrantedScopes), ConfigError> {
         let kdl_document: KdlDocument = raw_string.parse()?;
 
         let mut granted_permission = GrantedPermission::default();
+        let mut granted_scopes = GrantedScopes::default();
 
         for node in kdl_document.nodes() {
             if let Some(children) = node.children() {
                 let key = kdl_name!(node);
-                let permissions: Vec<PermissionType> = children
-                    .nodes()
-                    .iter()
-                    .filter_map(|p| {
-                        let v = kdl_name!(p);
-                        PermissionType::from_str(v).ok()
-                    })
-                    .collect();
+                let mut permissions: Vec<PermissionType> = vec![];
+                let mut scopes = PermissionScopes::new();
+                for permission_node in children.nodes() {
+                    let permission = match PermissionType::from_str(kdl_name!(permission_node)) {
+                        Ok(permission) => permission,
+                        Err(_) => continue,
+                    };
+                    // the arguments of a permission are its scope, eg. RunCommands "git" "cargo"
+                    let scope = kdl_arguments_that_are_strings(permission_node.entries().iter())?;
+                    if !scope.is_empty() {
+                        scopes.set_scope(permission, scope);
+                    }
+                    permissions.push(permission);
+                }
 
+                if !scopes.is_empty() {
+                    granted_scopes.insert(key.into(), scopes);
+                }
                 granted_permission.insert(key.into(), permissions);
             }
         }
 
-        Ok(granted_permission)
+        Ok((granted_permission, granted_scopes))
     }
 
-    pub fn to_string(granted: &GrantedPermission) -> String {
+    pub fn to_string(granted: &GrantedPermission, scopes: &GrantedScopes) -> String {
         let mut kdl_doucment = KdlDocument::new();
 
         granted.iter().for_each(|(k, v)| {
             let mut node = KdlNode::new(k.as_str());
             let mut children = KdlDocument::new();
+            let plugin_scopes = scopes.get(k);
 
             let permissions: HashSet<PermissionType> = v.clone().into_iter().collect();
             permissions.iter().for_each(|f| {
-                let n = KdlNode::new(f.to_string().as_str());
+                let mut n = KdlNode::new(f.to_string().as_str());
+                if let Some(scope) = plugin_scopes.and_then(|s| s.scope(f)) {
+                    for scope_entry in scope {
+                        n.push(scope_entry.as_str());
+                    }
+                }
                 children.nodes_mut().push(n);
             });
 
@@ -4578,6 +5659,7 @@ fn keybinds_to_string() {
         document.get("keybinds").unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     let clear_defaults = true;
@@ -4591,6 +5673,128 @@ fn keybinds_to_string() {
             .unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    insta::assert_snapshot!(serialized.to_string());
+    assert_eq!(
+        deserialized, deserialized_from_serialized,
+        "Deserialized serialized config equals original config"
+    );
+}
+
+#[test]
+fn keybinds_to_string_with_key_sequences() {
+    let fake_config = r#"
+        keybinds {
+            normal {
+                bind "Ctrl g" { SwitchToMode "Locked"; }
+                bind "Ctrl a" "c" sequence=true { NewTab; }
+            }
+            pane {
+                bind "g" "x" sequence=true { CloseFocus; SwitchToMode "Normal"; }
+            }
+        }"#;
+    let document: KdlDocument = fake_config.parse().unwrap();
+    let deserialized = Keybinds::from_kdl(
+        document.get("keybinds").unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    let clear_defaults = true;
+    let serialized = Keybinds::to_kdl(&deserialized, clear_defaults);
+    let deserialized_from_serialized = Keybinds::from_kdl(
+        serialized
+            .to_string()
+            .parse::<KdlDocument>()
+            .unwrap()
+            .get("keybinds")
+            .unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    insta::assert_snapshot!(serialized.to_string());
+    assert_eq!(
+        deserialized, deserialized_from_serialized,
+        "Deserialized serialized config equals original config"
+    );
+}
+
+#[test]
+fn keybinds_to_string_with_custom_modes() {
+    let fake_config = r#"
+        keybinds {
+            normal {
+                bind "Ctrl d" { SwitchToMode "deploy"; }
+            }
+            custom_mode "deploy" {
+                bind "s" { Run "make" "staging"; SwitchToMode "Normal"; }
+                bind "Esc" { SwitchToMode "Normal"; }
+            }
+        }"#;
+    let document: KdlDocument = fake_config.parse().unwrap();
+    let deserialized = Keybinds::from_kdl(
+        document.get("keybinds").unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    let clear_defaults = true;
+    let serialized = Keybinds::to_kdl(&deserialized, clear_defaults);
+    let deserialized_from_serialized = Keybinds::from_kdl(
+        serialized
+            .to_string()
+            .parse::<KdlDocument>()
+            .unwrap()
+            .get("keybinds")
+            .unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    insta::assert_snapshot!(serialized.to_string());
+    assert_eq!(
+        deserialized, deserialized_from_serialized,
+        "Deserialized serialized config equals original config"
+    );
+}
+
+#[test]
+fn keybinds_to_string_with_conditions() {
+    let fake_config = r#"
+        keybinds {
+            normal {
+                bind "Ctrl h" { MoveFocus "Left"; }
+                bind "Ctrl h" if_not_running="nvim|vim" if_not_pane_title=".*vim.*" { MoveFocus "Left"; }
+                bind "Ctrl q" if_plugin="zellij:strider" { CloseFocus; }
+            }
+        }"#;
+    let document: KdlDocument = fake_config.parse().unwrap();
+    let deserialized = Keybinds::from_kdl(
+        document.get("keybinds").unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    let clear_defaults = true;
+    let serialized = Keybinds::to_kdl(&deserialized, clear_defaults);
+    let deserialized_from_serialized = Keybinds::from_kdl(
+        serialized
+            .to_string()
+            .parse::<KdlDocument>()
+            .unwrap()
+            .get("keybinds")
+            .unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     insta::assert_snapshot!(serialized.to_string());
@@ -4613,6 +5817,7 @@ fn keybinds_to_string_without_clearing_defaults() {
         document.get("keybinds").unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     let clear_defaults = false;
@@ -4626,6 +5831,7 @@ fn keybinds_to_string_without_clearing_defaults() {
             .unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     insta::assert_snapshot!(serialized.to_string());
@@ -4648,6 +5854,7 @@ fn keybinds_to_string_with_multiple_actions() {
         document.get("keybinds").unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     let clear_defaults = true;
@@ -4661,6 +5868,7 @@ fn keybinds_to_string_with_multiple_actions() {
             .unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     assert_eq!(
@@ -4809,6 +6017,7 @@ fn keybinds_to_string_with_all_actions() {
         document.get("keybinds").unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     let clear_defaults = true;
@@ -4822,6 +6031,7 @@ fn keybinds_to_string_with_all_actions() {
             .unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     // uncomment the below lines for more easily debugging a failed assertion here
@@ -4865,6 +6075,7 @@ fn keybinds_to_string_with_shared_modes() {
         document.get("keybinds").unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     let clear_defaults = true;
@@ -4878,6 +6089,7 @@ fn keybinds_to_string_with_shared_modes() {
             .unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     assert_eq!(
@@ -4914,6 +6126,7 @@ fn keybinds_to_string_with_multiple_multiline_actions() {
         document.get("keybinds").unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     let clear_defaults = true;
@@ -4927,6 +6140,7 @@ fn keybinds_to_string_with_multiple_multiline_actions() {
             .unwrap(),
         Default::default(),
         &Default::default(),
+        &Default::default(),
     )
     .unwrap();
     assert_eq!(
@@ -5290,6 +6504,80 @@ fn env_vars_to_string_with_no_env_vars() {
     assert_eq!(EnvironmentVariables::to_kdl(&deserialized), None);
 }
 
+#[test]
+fn mouse_bindings_to_string() {
+    let fake_config = r##"
+        mouse {
+            bind "MiddleClick" { FocusPaneAtMouse; Paste; }
+            bind "Ctrl LeftClick" { OpenHyperlinkAtMouse; }
+            bind "RightClick" target="pane_frame" { FocusPaneAtMouse; SwitchToMode "pane"; }
+            bind "Alt WheelUp" "Alt WheelDown" target="tab_bar" { GoToNextTab; }
+        }"##;
+    let document: KdlDocument = fake_config.parse().unwrap();
+    let deserialized = MouseBindings::from_kdl(
+        document.get("mouse").unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    let serialized = MouseBindings::to_kdl(&deserialized).unwrap();
+    let deserialized_from_serialized = MouseBindings::from_kdl(
+        serialized
+            .to_string()
+            .parse::<KdlDocument>()
+            .unwrap()
+            .get("mouse")
+            .unwrap(),
+        Default::default(),
+        &Default::default(),
+        &Default::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        deserialized, deserialized_from_serialized,
+        "Deserialized serialized config equals original config"
+    );
+    insta::assert_snapshot!(serialized.to_string());
+}
+
+#[test]
+fn macros_to_string() {
+    let fake_config = r##"
+        macros {
+            q "Ctrl c" "Up" "Enter" start_mode="normal"
+            "1" "Ctrl p" "d" start_mode="locked"
+            no_start_mode "a" "b"
+        }"##;
+    let document: KdlDocument = fake_config.parse().unwrap();
+    let deserialized = Macros::from_kdl(document.get("macros").unwrap()).unwrap();
+    let serialized = Macros::to_kdl(&deserialized).unwrap();
+    let deserialized_from_serialized = Macros::from_kdl(
+        serialized
+            .to_string()
+            .parse::<KdlDocument>()
+            .unwrap()
+            .get("macros")
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        deserialized, deserialized_from_serialized,
+        "Deserialized serialized config equals original config"
+    );
+    insta::assert_snapshot!(serialized.to_string());
+}
+
+#[test]
+fn macros_to_string_with_no_macros() {
+    let fake_config = r##"
+        macros {
+        }"##;
+    let document: KdlDocument = fake_config.parse().unwrap();
+    let deserialized = Macros::from_kdl(document.get("macros").unwrap()).unwrap();
+    assert_eq!(Macros::to_kdl(&deserialized), None);
+}
+
 #[test]
 fn config_options_to_string() {
     let fake_config = r##"
@@ -13,6 +13,7 @@ pub use super::generated_api::api::{
     input_mode::InputMode as ProtobufInputMode,
     resize::{Resize as ProtobufResize, ResizeDirection as ProtobufResizeDirection},
 };
+use super::input_mode::{custom_input_mode_name, input_mode_from_protobuf};
 use crate::data::{Direction, InputMode, ResizeStrategy};
 use crate::errors::prelude::*;
 use crate::input::actions::Action;
@@ -49,10 +50,11 @@ impl TryFrom<ProtobufAction> for Action {
             },
             Some(ProtobufActionName::SwitchToMode) => match protobuf_action.optional_payload {
                 Some(OptionalPayload::SwitchToModePayload(switch_to_mode_payload)) => {
-                    let input_mode: InputMode =
-                        ProtobufInputMode::from_i32(switch_to_mode_payload.input_mode)
-                            .ok_or("Malformed input mode for SwitchToMode Action")?
-                            .try_into()?;
+                    let input_mode: InputMode = input_mode_from_protobuf(
+                        switch_to_mode_payload.input_mode,
+                        switch_to_mode_payload.custom_mode_name,
+                    )
+                    .map_err(|_| "Malformed input mode for SwitchToMode Action")?;
                     Ok(Action::SwitchToMode(input_mode))
                 },
                 _ => Err("Wrong payload for Action::SwitchToModePayload"),
@@ -62,10 +64,11 @@ impl TryFrom<ProtobufAction> for Action {
                     Some(OptionalPayload::SwitchModeForAllClientsPayload(
                         switch_to_mode_payload,
                     )) => {
-                        let input_mode: InputMode =
-                            ProtobufInputMode::from_i32(switch_to_mode_payload.input_mode)
-                                .ok_or("Malformed input mode for SwitchToMode Action")?
-                                .try_into()?;
+                        let input_mode: InputMode = input_mode_from_protobuf(
+                            switch_to_mode_payload.input_mode,
+                            switch_to_mode_payload.custom_mode_name,
+                        )
+                        .map_err(|_| "Malformed input mode for SwitchToMode Action")?;
                         Ok(Action::SwitchModeForAllClients(input_mode))
                     },
                     _ => Err("Wrong payload for Action::SwitchModeForAllClients"),
@@ -409,7 +412,7 @@ impl TryFrom<ProtobufAction> for Action {
                             .unwrap_or_default();
                         let run_plugin_or_alias = RunPluginOrAlias::from_url(
                             &payload.plugin_url.as_str(),
-                            &Some(configuration.inner().clone()),
+                            &Some(configuration.inner_with_settings()),
                             None,
                             None,
                         )
@@ -437,7 +440,7 @@ impl TryFrom<ProtobufAction> for Action {
                         .unwrap_or_default();
                     let run_plugin_or_alias = RunPluginOrAlias::from_url(
                         &payload.plugin_url.as_str(),
-                        &Some(configuration.inner().clone()),
+                        &Some(configuration.inner_with_settings()),
                         None,
                         None,
                     )
@@ -732,23 +735,27 @@ impl TryFrom<Action> for ProtobufAction {
                 })),
             }),
             Action::SwitchToMode(input_mode) => {
+                let custom_mode_name = custom_input_mode_name(&input_mode);
                 let input_mode: ProtobufInputMode = input_mode.try_into()?;
                 Ok(ProtobufAction {
                     name: ProtobufActionName::SwitchToMode as i32,
                     optional_payload: Some(OptionalPayload::SwitchToModePayload(
                         SwitchToModePayload {
                             input_mode: input_mode as i32,
+                            custom_mode_name,
                         },
                     )),
                 })
             },
             Action::SwitchModeForAllClients(input_mode) => {
+                let custom_mode_name = custom_input_mode_name(&input_mode);
                 let input_mode: ProtobufInputMode = input_mode.try_into()?;
                 Ok(ProtobufAction {
                     name: ProtobufActionName::SwitchModeForAllClients as i32,
                     optional_payload: Some(OptionalPayload::SwitchModeForAllClientsPayload(
                         SwitchToModePayload {
                             input_mode: input_mode as i32,
+                            custom_mode_name,
                         },
                     )),
                 })
@@ -1293,7 +1300,16 @@ impl TryFrom<Action> for ProtobufAction {
             | Action::DumpLayout
             | Action::CliPipe { .. }
             | Action::ListClients
-            | Action::SkipConfirm(..) => Err("Unsupported action"),
+            | Action::SkipConfirm(..)
+            | Action::StartMacroRecording(..)
+            | Action::StopMacroRecording
+            | Action::PlayMacro(..)
+            | Action::TogglePaneInSelection
+            | Action::ClearPaneSelection
+            | Action::SetPaneColor(..)
+            | Action::Paste
+            | Action::FocusPaneAt(..)
+            | Action::OpenHyperlinkAt(..) => Err("Unsupported action"),
         }
     }
 }
@@ -1448,7 +1464,7 @@ impl TryFrom<PluginUserConfiguration> for ProtobufPluginConfiguration {
     type Error = &'static str;
     fn try_from(plugin_configuration: PluginUserConfiguration) -> Result<Self, &'static str> {
         let mut converted = vec![];
-        for (name, value) in plugin_configuration.inner() {
+        for (name, value) in plugin_configuration.inner_with_settings() {
             let name_and_value = ProtobufNameAndValue {
                 name: name.to_owned(),
                 value: value.to_owned(),
@@ -8,6 +8,8 @@ pub use super::generated_api::api::{
         LayoutInfo as ProtobufLayoutInfo, ModeUpdatePayload as ProtobufModeUpdatePayload,
         PaneId as ProtobufPaneId, PaneInfo as ProtobufPaneInfo,
         PaneManifest as ProtobufPaneManifest, PaneType as ProtobufPaneType,
+        PluginRequestError as ProtobufPluginRequestError,
+        PluginResponsePayload as ProtobufPluginResponsePayload,
         ResurrectableSession as ProtobufResurrectableSession,
         SessionManifest as ProtobufSessionManifest, TabInfo as ProtobufTabInfo, *,
     },
@@ -15,11 +17,12 @@ pub use super::generated_api::api::{
     key::Key as ProtobufKey,
     style::Style as ProtobufStyle,
 };
+use super::input_mode::{custom_input_mode_name, input_mode_from_protobuf};
 #[allow(hidden_glob_reexports)]
 use crate::data::{
     CopyDestination, Event, EventType, FileMetadata, InputMode, KeyWithModifier, LayoutInfo,
     ModeInfo, Mouse, PaneId, PaneInfo, PaneManifest, PermissionStatus, PluginCapabilities,
-    SessionInfo, Style, TabInfo,
+    PluginRequestError, PluginResponse, SessionInfo, Style, TabInfo,
 };
 
 use crate::errors::prelude::*;
@@ -320,6 +323,30 @@ impl TryFrom<ProtobufEvent> for Event {
                 )),
                 _ => Err("Malformed payload for the FailedToWriteConfigToDisk Event"),
             },
+            Some(ProtobufEventType::PaneOutput) => match protobuf_event.payload {
+                Some(ProtobufEventPayload::PaneOutputPayload(pane_output_payload)) => {
+                    let pane_id = pane_output_payload
+                        .pane_id
+                        .ok_or("Malformed payload for the PaneOutput Event")?;
+                    Ok(Event::PaneOutput(
+                        PaneId::try_from(pane_id)?,
+                        pane_output_payload.lines,
+                    ))
+                },
+                _ => Err("Malformed payload for the PaneOutput Event"),
+            },
+            Some(ProtobufEventType::InterceptedKey) => match protobuf_event.payload {
+                Some(ProtobufEventPayload::InterceptedKeyPayload(protobuf_key)) => {
+                    Ok(Event::InterceptedKey(protobuf_key.try_into()?))
+                },
+                _ => Err("Malformed payload for the InterceptedKey Event"),
+            },
+            Some(ProtobufEventType::PluginResponse) => match protobuf_event.payload {
+                Some(ProtobufEventPayload::PluginResponsePayload(plugin_response_payload)) => {
+                    Ok(Event::PluginResponse(plugin_response_payload.try_into()?))
+                },
+                _ => Err("Malformed payload for the PluginResponse Event"),
+            },
             None => Err("Unknown Protobuf Event"),
         }
     }
@@ -634,6 +661,23 @@ impl TryFrom<Event> for ProtobufEvent {
                     FailedToWriteConfigToDiskPayload { file_path },
                 )),
             }),
+            Event::PaneOutput(pane_id, lines) => Ok(ProtobufEvent {
+                name: ProtobufEventType::PaneOutput as i32,
+                payload: Some(event::Payload::PaneOutputPayload(PaneOutputPayload {
+                    pane_id: Some(pane_id.try_into()?),
+                    lines,
+                })),
+            }),
+            Event::InterceptedKey(key) => Ok(ProtobufEvent {
+                name: ProtobufEventType::InterceptedKey as i32,
+                payload: Some(event::Payload::InterceptedKeyPayload(key.try_into()?)),
+            }),
+            Event::PluginResponse(plugin_response) => Ok(ProtobufEvent {
+                name: ProtobufEventType::PluginResponse as i32,
+                payload: Some(event::Payload::PluginResponsePayload(
+                    plugin_response.try_into()?,
+                )),
+            }),
         }
     }
 }
@@ -767,6 +811,51 @@ impl TryFrom<ProtobufCopyDestination> for CopyDestination {
     }
 }
 
+impl TryFrom<ProtobufPluginResponsePayload> for PluginResponse {
+    type Error = &'static str;
+    fn try_from(
+        protobuf_plugin_response_payload: ProtobufPluginResponsePayload,
+    ) -> Result<Self, &'static str> {
+        let result = match protobuf_plugin_response_payload.error {
+            Some(error) => Err(
+                match ProtobufPluginRequestError::from_i32(error)
+                    .ok_or("Unknown plugin request error")?
+                {
+                    ProtobufPluginRequestError::Timeout => PluginRequestError::Timeout,
+                    ProtobufPluginRequestError::NoSuchPlugin => PluginRequestError::NoSuchPlugin,
+                },
+            ),
+            None => Ok(protobuf_plugin_response_payload.payload),
+        };
+        Ok(PluginResponse {
+            request_id: protobuf_plugin_response_payload.request_id,
+            name: protobuf_plugin_response_payload.name,
+            result,
+        })
+    }
+}
+
+impl TryFrom<PluginResponse> for ProtobufPluginResponsePayload {
+    type Error = &'static str;
+    fn try_from(plugin_response: PluginResponse) -> Result<Self, &'static str> {
+        let (payload, error) = match plugin_response.result {
+            Ok(payload) => (payload, None),
+            Err(PluginRequestError::Timeout) => {
+                (None, Some(ProtobufPluginRequestError::Timeout as i32))
+            },
+            Err(PluginRequestError::NoSuchPlugin) => {
+                (None, Some(ProtobufPluginRequestError::NoSuchPlugin as i32))
+            },
+        };
+        Ok(ProtobufPluginResponsePayload {
+            request_id: plugin_response.request_id,
+            name: plugin_response.name,
+            payload,
+            error,
+        })
+    }
+}
+
 impl TryFrom<MouseEventPayload> for Mouse {
     type Error = &'static str;
     fn try_from(mouse_event_payload: MouseEventPayload) -> Result<Self, &'static str> {
@@ -988,10 +1077,11 @@ impl TryFrom<ProtobufModeUpdatePayload> for ModeInfo {
     fn try_from(
         mut protobuf_mode_update_payload: ProtobufModeUpdatePayload,
     ) -> Result<Self, &'static str> {
-        let current_mode: InputMode =
-            ProtobufInputMode::from_i32(protobuf_mode_update_payload.current_mode)
-                .ok_or("Malformed InputMode in the ModeUpdate Event")?
-                .try_into()?;
+        let current_mode: InputMode = input_mode_from_protobuf(
+            protobuf_mode_update_payload.current_mode,
+            protobuf_mode_update_payload.custom_mode_name.take(),
+        )
+        .map_err(|_| "Malformed InputMode in the ModeUpdate Event")?;
         let base_mode: Option<InputMode> = protobuf_mode_update_payload
             .base_mode
             .and_then(|b_m| ProtobufInputMode::from_i32(b_m)?.try_into().ok());
@@ -1000,11 +1090,8 @@ impl TryFrom<ProtobufModeUpdatePayload> for ModeInfo {
                 .keybinds
                 .iter_mut()
                 .filter_map(|k| {
-                    let input_mode: InputMode = ProtobufInputMode::from_i32(k.mode)
-                        .ok_or("Malformed InputMode in the ModeUpdate Event")
-                        .ok()?
-                        .try_into()
-                        .ok()?;
+                    let input_mode: InputMode =
+                        input_mode_from_protobuf(k.mode, k.custom_mode_name.take()).ok()?;
                     let mut keybinds: Vec<(KeyWithModifier, Vec<Action>)> = vec![];
                     for mut protobuf_keybind in k.key_bind.drain(..) {
                         let key: KeyWithModifier = protobuf_keybind.key.unwrap().try_into().ok()?;
@@ -1027,6 +1114,11 @@ impl TryFrom<ProtobufModeUpdatePayload> for ModeInfo {
         let capabilities = PluginCapabilities {
             arrow_fonts: protobuf_mode_update_payload.arrow_fonts_support,
         };
+        let pending_key_sequence: Vec<KeyWithModifier> = protobuf_mode_update_payload
+            .pending_key_sequence
+            .drain(..)
+            .filter_map(|k| k.try_into().ok())
+            .collect();
         let mode_info = ModeInfo {
             mode: current_mode,
             keybinds,
@@ -1034,6 +1126,7 @@ impl TryFrom<ProtobufModeUpdatePayload> for ModeInfo {
             capabilities,
             session_name,
             base_mode,
+            pending_key_sequence,
         };
         Ok(mode_info)
     }
@@ -1042,6 +1135,7 @@ impl TryFrom<ProtobufModeUpdatePayload> for ModeInfo {
 impl TryFrom<ModeInfo> for ProtobufModeUpdatePayload {
     type Error = &'static str;
     fn try_from(mode_info: ModeInfo) -> Result<Self, &'static str> {
+        let custom_mode_name = custom_input_mode_name(&mode_info.mode);
         let current_mode: ProtobufInputMode = mode_info.mode.try_into()?;
         let base_mode: Option<ProtobufInputMode> = mode_info
             .base_mode
@@ -1051,6 +1145,7 @@ impl TryFrom<ModeInfo> for ProtobufModeUpdatePayload {
         let session_name = mode_info.session_name;
         let mut protobuf_input_mode_keybinds: Vec<ProtobufInputModeKeybinds> = vec![];
         for (input_mode, input_mode_keybinds) in mode_info.keybinds {
+            let custom_mode_name = custom_input_mode_name(&input_mode);
             let mode: ProtobufInputMode = input_mode.try_into()?;
             let mut keybinds: Vec<ProtobufKeyBind> = vec![];
             for (key, actions) in input_mode_keybinds {
@@ -1070,9 +1165,14 @@ impl TryFrom<ModeInfo> for ProtobufModeUpdatePayload {
             let input_mode_keybind = ProtobufInputModeKeybinds {
                 mode: mode as i32,
                 key_bind: keybinds,
+                custom_mode_name,
             };
             protobuf_input_mode_keybinds.push(input_mode_keybind);
         }
+        let mut pending_key_sequence: Vec<ProtobufKey> = vec![];
+        for key in mode_info.pending_key_sequence {
+            pending_key_sequence.push(key.try_into()?);
+        }
         Ok(ProtobufModeUpdatePayload {
             current_mode: current_mode as i32,
             style: Some(style),
@@ -1080,6 +1180,8 @@ impl TryFrom<ModeInfo> for ProtobufModeUpdatePayload {
             arrow_fonts_support,
             session_name,
             base_mode: base_mode.map(|b_m| b_m as i32),
+            pending_key_sequence,
+            custom_mode_name,
         })
     }
 }
@@ -1144,6 +1246,9 @@ impl TryFrom<ProtobufEventType> for EventType {
             ProtobufEventType::EditPaneExited => EventType::EditPaneExited,
             ProtobufEventType::CommandPaneReRun => EventType::CommandPaneReRun,
             ProtobufEventType::FailedToWriteConfigToDisk => EventType::FailedToWriteConfigToDisk,
+            ProtobufEventType::PaneOutput => EventType::PaneOutput,
+            ProtobufEventType::InterceptedKey => EventType::InterceptedKey,
+            ProtobufEventType::PluginResponse => EventType::PluginResponse,
         })
     }
 }
@@ -1178,6 +1283,9 @@ impl TryFrom<EventType> for ProtobufEventType {
             EventType::EditPaneExited => ProtobufEventType::EditPaneExited,
             EventType::CommandPaneReRun => ProtobufEventType::CommandPaneReRun,
             EventType::FailedToWriteConfigToDisk => ProtobufEventType::FailedToWriteConfigToDisk,
+            EventType::PaneOutput => ProtobufEventType::PaneOutput,
+            EventType::InterceptedKey => ProtobufEventType::InterceptedKey,
+            EventType::PluginResponse => ProtobufEventType::PluginResponse,
         })
     }
 }
@@ -1248,6 +1356,43 @@ fn serialize_mode_update_event() {
     );
 }
 
+#[test]
+fn serialize_mode_update_event_with_custom_mode() {
+    use crate::data::BareKey;
+    use prost::Message;
+    let deploy = InputMode::Custom("deploy".to_owned());
+    let mode_update_event = Event::ModeUpdate(ModeInfo {
+        mode: deploy.clone(),
+        base_mode: Some(InputMode::Normal),
+        keybinds: vec![
+            (
+                InputMode::Normal,
+                vec![(
+                    KeyWithModifier::new(BareKey::Char('d')).with_ctrl_modifier(),
+                    vec![Action::SwitchToMode(deploy.clone())],
+                )],
+            ),
+            (
+                deploy,
+                vec![(
+                    KeyWithModifier::new(BareKey::Esc),
+                    vec![Action::SwitchToMode(InputMode::Normal)],
+                )],
+            ),
+        ],
+        ..Default::default()
+    });
+    let protobuf_event: ProtobufEvent = mode_update_event.clone().try_into().unwrap();
+    let serialized_protobuf_event = protobuf_event.encode_to_vec();
+    let deserialized_protobuf_event: ProtobufEvent =
+        Message::decode(serialized_protobuf_event.as_slice()).unwrap();
+    let deserialized_event: Event = deserialized_protobuf_event.try_into().unwrap();
+    assert_eq!(
+        mode_update_event, deserialized_event,
+        "Event properly serialized/deserialized without change"
+    );
+}
+
 #[test]
 fn serialize_mode_update_event_with_non_default_values() {
     use crate::data::{BareKey, Palette, PaletteColor, ThemeHue};
@@ -1307,6 +1452,11 @@ fn serialize_mode_update_event_with_non_default_values() {
                 silver: PaletteColor::EightBit(2),
                 pink: PaletteColor::EightBit(2),
                 brown: PaletteColor::Rgb((222, 221, 220)),
+                ui: crate::data::UiColors {
+                    frame_unfocused: Some(PaletteColor::EightBit(8)),
+                    tab_active_bg: Some(PaletteColor::Rgb((10, 20, 30))),
+                    ..Default::default()
+                },
             },
             rounded_corners: true,
             hide_session_name: false,
@@ -1314,6 +1464,10 @@ fn serialize_mode_update_event_with_non_default_values() {
         capabilities: PluginCapabilities { arrow_fonts: false },
         session_name: Some("my awesome test session".to_owned()),
         base_mode: Some(InputMode::Locked),
+        pending_key_sequence: vec![
+            KeyWithModifier::new(BareKey::Char('a')).with_ctrl_modifier(),
+            KeyWithModifier::new(BareKey::Char('c')),
+        ],
     });
     let protobuf_event: ProtobufEvent = mode_update_event.clone().try_into().unwrap();
     let serialized_protobuf_event = protobuf_event.encode_to_vec();
@@ -1743,6 +1897,79 @@ fn serialize_session_update_event_with_non_default_values() {
     );
 }
 
+#[test]
+fn serialize_pane_output_event() {
+    use prost::Message;
+    let pane_output_event = Event::PaneOutput(
+        PaneId::Terminal(1),
+        vec!["test result: ok".to_owned(), "".to_owned()],
+    );
+    let protobuf_event: ProtobufEvent = pane_output_event.clone().try_into().unwrap();
+    let serialized_protobuf_event = protobuf_event.encode_to_vec();
+    let deserialized_protobuf_event: ProtobufEvent =
+        Message::decode(serialized_protobuf_event.as_slice()).unwrap();
+    let deserialized_event: Event = deserialized_protobuf_event.try_into().unwrap();
+    assert_eq!(
+        pane_output_event, deserialized_event,
+        "Event properly serialized/deserialized without change"
+    );
+}
+
+#[test]
+fn serialize_intercepted_key_event() {
+    use crate::data::BareKey;
+    use prost::Message;
+    let intercepted_key_event =
+        Event::InterceptedKey(KeyWithModifier::new(BareKey::Char('j')).with_ctrl_modifier());
+    let protobuf_event: ProtobufEvent = intercepted_key_event.clone().try_into().unwrap();
+    let serialized_protobuf_event = protobuf_event.encode_to_vec();
+    let deserialized_protobuf_event: ProtobufEvent =
+        Message::decode(serialized_protobuf_event.as_slice()).unwrap();
+    let deserialized_event: Event = deserialized_protobuf_event.try_into().unwrap();
+    assert_eq!(
+        intercepted_key_event, deserialized_event,
+        "Event properly serialized/deserialized without change"
+    );
+}
+
+#[test]
+fn serialize_plugin_response_event() {
+    use prost::Message;
+    let plugin_response_events = vec![
+        Event::PluginResponse(PluginResponse {
+            request_id: "0e0dbc5c-6e8d-4e3b-9b8b-2a8c1f6d2b4e".to_owned(),
+            name: "git_status".to_owned(),
+            result: Ok(Some("clean".to_owned())),
+        }),
+        Event::PluginResponse(PluginResponse {
+            request_id: "0e0dbc5c-6e8d-4e3b-9b8b-2a8c1f6d2b4e".to_owned(),
+            name: "git_status".to_owned(),
+            result: Ok(None),
+        }),
+        Event::PluginResponse(PluginResponse {
+            request_id: "0e0dbc5c-6e8d-4e3b-9b8b-2a8c1f6d2b4e".to_owned(),
+            name: "git_status".to_owned(),
+            result: Err(PluginRequestError::Timeout),
+        }),
+        Event::PluginResponse(PluginResponse {
+            request_id: "0e0dbc5c-6e8d-4e3b-9b8b-2a8c1f6d2b4e".to_owned(),
+            name: "git_status".to_owned(),
+            result: Err(PluginRequestError::NoSuchPlugin),
+        }),
+    ];
+    for plugin_response_event in plugin_response_events {
+        let protobuf_event: ProtobufEvent = plugin_response_event.clone().try_into().unwrap();
+        let serialized_protobuf_event = protobuf_event.encode_to_vec();
+        let deserialized_protobuf_event: ProtobufEvent =
+            Message::decode(serialized_protobuf_event.as_slice()).unwrap();
+        let deserialized_event: Event = deserialized_protobuf_event.try_into().unwrap();
+        assert_eq!(
+            plugin_response_event, deserialized_event,
+            "Event properly serialized/deserialized without change"
+        );
+    }
+}
+
 // note: ProtobufPaneId and ProtobufPaneType are not the same as the ones defined in plugin_command.rs
 // this is a duplicate type - we are forced to do this because protobuffs do not support recursive
 // imports
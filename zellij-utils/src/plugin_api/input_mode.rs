@@ -23,6 +23,7 @@ impl TryFrom<ProtobufInputMode> for InputMode {
             ProtobufInputMode::Move => Ok(InputMode::Move),
             ProtobufInputMode::Prompt => Ok(InputMode::Prompt),
             ProtobufInputMode::Tmux => Ok(InputMode::Tmux),
+            ProtobufInputMode::Custom => Err("Custom input modes must be sent with their name"),
         }
     }
 }
@@ -45,6 +46,7 @@ impl TryFrom<InputMode> for ProtobufInputMode {
             InputMode::Move => ProtobufInputMode::Move,
             InputMode::Prompt => ProtobufInputMode::Prompt,
             InputMode::Tmux => ProtobufInputMode::Tmux,
+            InputMode::Custom(..) => ProtobufInputMode::Custom,
         })
     }
 }
@@ -52,18 +54,42 @@ impl TryFrom<InputMode> for ProtobufInputMode {
 impl TryFrom<ProtobufInputModeMessage> for InputMode {
     type Error = &'static str;
     fn try_from(protobuf_input_mode: ProtobufInputModeMessage) -> Result<Self, &'static str> {
-        ProtobufInputMode::from_i32(protobuf_input_mode.input_mode)
-            .and_then(|p| p.try_into().ok())
-            .ok_or("Invalid input mode")
+        input_mode_from_protobuf(
+            protobuf_input_mode.input_mode,
+            protobuf_input_mode.custom_mode_name,
+        )
     }
 }
 
 impl TryFrom<InputMode> for ProtobufInputModeMessage {
     type Error = &'static str;
     fn try_from(input_mode: InputMode) -> Result<Self, &'static str> {
+        let custom_mode_name = custom_input_mode_name(&input_mode);
         let protobuf_input_mode: ProtobufInputMode = input_mode.try_into()?;
         Ok(ProtobufInputModeMessage {
             input_mode: protobuf_input_mode as i32,
+            custom_mode_name,
         })
     }
 }
+
+/// Custom input modes are sent as `ProtobufInputMode::Custom` along with their name
+pub fn input_mode_from_protobuf(
+    input_mode: i32,
+    custom_mode_name: Option<String>,
+) -> Result<InputMode, &'static str> {
+    match (ProtobufInputMode::from_i32(input_mode), custom_mode_name) {
+        (Some(ProtobufInputMode::Custom), Some(custom_mode_name)) => {
+            Ok(InputMode::Custom(custom_mode_name))
+        },
+        (Some(protobuf_input_mode), _) => protobuf_input_mode.try_into(),
+        (None, _) => Err("Invalid input mode"),
+    }
+}
+
+pub fn custom_input_mode_name(input_mode: &InputMode) -> Option<String> {
+    match input_mode {
+        InputMode::Custom(name) => Some(name.clone()),
+        _ => None,
+    }
+}
@@ -6,22 +6,32 @@ pub use super::generated_api::api::{
         plugin_command::Payload, CliPipeOutputPayload, CommandName, ContextItem, EnvVariable,
         ExecCmdPayload, FixedOrPercent as ProtobufFixedOrPercent,
         FixedOrPercentValue as ProtobufFixedOrPercentValue,
-        FloatingPaneCoordinates as ProtobufFloatingPaneCoordinates, HidePaneWithIdPayload,
-        HttpVerb as ProtobufHttpVerb, IdAndNewName, KillSessionsPayload, MessageToPluginPayload,
-        MovePayload, NewPluginArgs as ProtobufNewPluginArgs, NewTabsWithLayoutInfoPayload,
-        OpenCommandPanePayload, OpenFilePayload, PaneId as ProtobufPaneId,
+        FloatingPaneCoordinates as ProtobufFloatingPaneCoordinates, GetPaneScrollbackPayload,
+        GrantedPluginPermissions as ProtobufGrantedPluginPermissions,
+        GrantedPluginPermissionsList as ProtobufGrantedPluginPermissionsList,
+        HidePaneWithIdPayload, HttpVerb as ProtobufHttpVerb, IdAndNewName, InterceptKeysPayload,
+        KillSessionsPayload, LineRange as ProtobufLineRange, MessageToPluginPayload, MovePayload,
+        NewPluginArgs as ProtobufNewPluginArgs, NewTabsWithLayoutInfoPayload,
+        OpenCommandPanePayload, OpenFilePayload, PaneContents as ProtobufPaneContents,
+        PaneContentsResult as ProtobufPaneContentsResult, PaneId as ProtobufPaneId,
         PaneType as ProtobufPaneType, PluginCommand as ProtobufPluginCommand, PluginMessagePayload,
-        ReconfigurePayload, RequestPluginPermissionPayload, RerunCommandPanePayload, ResizePayload,
-        RunCommandPayload, SetTimeoutPayload, ShowPaneWithIdPayload, SubscribePayload,
-        SwitchSessionPayload, SwitchTabToPayload, UnsubscribePayload, WebRequestPayload,
+        PluginRequestId as ProtobufPluginRequestId, PluginRequestPayload, ReconfigurePayload,
+        RegisterInputModePayload, RequestPluginPermissionPayload, RerunCommandPanePayload,
+        ResizePayload, RespondToPluginRequestPayload, RevokePluginPermissionsPayload,
+        RunCommandPayload, SetPaneColorPayload, SetTimeoutPayload, ShowPaneWithIdPayload,
+        SubscribePayload, SubscribeToPaneOutputPayload, SwitchSessionPayload, SwitchTabToPayload,
+        UnsubscribePayload, WebRequestPayload,
     },
     plugin_permission::PermissionType as ProtobufPermissionType,
     resize::ResizeAction as ProtobufResizeAction,
+    style::Color as ProtobufColor,
 };
+use super::input_mode::{custom_input_mode_name, input_mode_from_protobuf};
 
 use crate::data::{
-    ConnectToSession, FloatingPaneCoordinates, HttpVerb, MessageToPlugin, NewPluginArgs, PaneId,
-    PermissionType, PluginCommand,
+    ConnectToSession, FloatingPaneCoordinates, HttpVerb, InputMode, MessageToPlugin, NewPluginArgs,
+    PaletteColor, PaneColors, PaneContents, PaneId, PermissionType, PluginCommand,
+    PluginPermission, PluginRequestTarget,
 };
 use crate::input::layout::SplitSize;
 
@@ -381,11 +391,12 @@ impl TryFrom<ProtobufPluginCommand> for PluginCommand {
             },
             Some(CommandName::SwitchToMode) => match protobuf_plugin_command.payload {
                 Some(Payload::SwitchToModePayload(switch_to_mode_payload)) => {
-                    match ProtobufInputMode::from_i32(switch_to_mode_payload.input_mode) {
-                        Some(protobuf_input_mode) => {
-                            Ok(PluginCommand::SwitchToMode(protobuf_input_mode.try_into()?))
-                        },
-                        None => Err("Malformed switch to mode payload"),
+                    match input_mode_from_protobuf(
+                        switch_to_mode_payload.input_mode,
+                        switch_to_mode_payload.custom_mode_name,
+                    ) {
+                        Ok(input_mode) => Ok(PluginCommand::SwitchToMode(input_mode)),
+                        Err(_) => Err("Malformed switch to mode payload"),
                     }
                 },
                 _ => Err("Mismatched payload for SwitchToMode"),
@@ -700,6 +711,7 @@ impl TryFrom<ProtobufPluginCommand> for PluginCommand {
                             .filter_map(|p| ProtobufPermissionType::from_i32(*p))
                             .filter_map(|p| PermissionType::try_from(p).ok())
                             .collect(),
+                        payload.scopes.into(),
                     ))
                 },
                 _ => Err("Mismatched payload for RequestPluginPermission"),
@@ -991,6 +1003,135 @@ impl TryFrom<ProtobufPluginCommand> for PluginCommand {
                 ),
                 _ => Err("Mismatched payload for RerunCommandPane"),
             },
+            Some(CommandName::SetPaneColor) => match protobuf_plugin_command.payload {
+                Some(Payload::SetPaneColorPayload(set_pane_color_payload)) => {
+                    let pane_id = set_pane_color_payload
+                        .pane_id
+                        .and_then(|p_id| PaneId::try_from(p_id).ok())
+                        .ok_or("Failed to parse SetPaneColor command")?;
+                    let to_palette_color = |color: Option<ProtobufColor>| {
+                        color.and_then(|c| PaletteColor::try_from(c).ok())
+                    };
+                    Ok(PluginCommand::SetPaneColor(
+                        pane_id,
+                        PaneColors {
+                            frame: to_palette_color(set_pane_color_payload.frame_color),
+                            frame_focused: to_palette_color(
+                                set_pane_color_payload.frame_color_focused,
+                            ),
+                            background: to_palette_color(set_pane_color_payload.background),
+                        },
+                    ))
+                },
+                _ => Err("Mismatched payload for SetPaneColor"),
+            },
+            Some(CommandName::GetPaneScrollback) => match protobuf_plugin_command.payload {
+                Some(Payload::GetPaneScrollbackPayload(get_pane_scrollback_payload)) => {
+                    let pane_id = get_pane_scrollback_payload
+                        .pane_id
+                        .and_then(|p_id| PaneId::try_from(p_id).ok())
+                        .ok_or("Failed to parse GetPaneScrollback command")?;
+                    let line_range = get_pane_scrollback_payload
+                        .line_range
+                        .map(|line_range| line_range.start as usize..line_range.end as usize);
+                    Ok(PluginCommand::GetPaneScrollback(
+                        pane_id,
+                        line_range,
+                        get_pane_scrollback_payload.with_ansi,
+                    ))
+                },
+                _ => Err("Mismatched payload for GetPaneScrollback"),
+            },
+            Some(CommandName::SubscribeToPaneOutput) => match protobuf_plugin_command.payload {
+                Some(Payload::SubscribeToPaneOutputPayload(subscribe_to_pane_output_payload)) => {
+                    Ok(PluginCommand::SubscribeToPaneOutput(
+                        subscribe_to_pane_output_payload
+                            .pane_ids
+                            .into_iter()
+                            .filter_map(|p_id| PaneId::try_from(p_id).ok())
+                            .collect(),
+                    ))
+                },
+                _ => Err("Mismatched payload for SubscribeToPaneOutput"),
+            },
+            Some(CommandName::InterceptKeys) => match protobuf_plugin_command.payload {
+                Some(Payload::InterceptKeysPayload(intercept_keys_payload)) => {
+                    let mut keys = vec![];
+                    for protobuf_key in intercept_keys_payload.keys {
+                        keys.push(protobuf_key.try_into()?);
+                    }
+                    let mut input_modes = vec![];
+                    for input_mode in intercept_keys_payload.input_modes {
+                        let protobuf_input_mode = ProtobufInputMode::from_i32(input_mode)
+                            .ok_or("Malformed input mode for InterceptKeys")?;
+                        input_modes.push(protobuf_input_mode.try_into()?);
+                    }
+                    for custom_mode_name in intercept_keys_payload.custom_input_modes {
+                        input_modes.push(InputMode::Custom(custom_mode_name));
+                    }
+                    Ok(PluginCommand::InterceptKeys(keys, input_modes))
+                },
+                _ => Err("Mismatched payload for InterceptKeys"),
+            },
+            Some(CommandName::ReinjectKey) => match protobuf_plugin_command.payload {
+                Some(Payload::ReinjectKeyPayload(protobuf_key)) => {
+                    Ok(PluginCommand::ReinjectKey(protobuf_key.try_into()?))
+                },
+                _ => Err("Mismatched payload for ReinjectKey"),
+            },
+            Some(CommandName::RegisterInputMode) => match protobuf_plugin_command.payload {
+                Some(Payload::RegisterInputModePayload(register_input_mode_payload)) => {
+                    Ok(PluginCommand::RegisterInputMode(
+                        register_input_mode_payload.name,
+                        register_input_mode_payload.keybinds,
+                    ))
+                },
+                _ => Err("Mismatched payload for RegisterInputMode"),
+            },
+            Some(CommandName::PluginRequest) => match protobuf_plugin_command.payload {
+                Some(Payload::PluginRequestPayload(plugin_request_payload)) => {
+                    let target = match (
+                        plugin_request_payload.destination_plugin_id,
+                        plugin_request_payload.plugin_url,
+                    ) {
+                        (Some(plugin_id), _) => PluginRequestTarget::PluginId(plugin_id),
+                        (None, Some(plugin_url)) => PluginRequestTarget::PluginUrl(plugin_url),
+                        (None, None) => return Err("PluginRequest must have a destination"),
+                    };
+                    Ok(PluginCommand::PluginRequest(
+                        target,
+                        plugin_request_payload.name,
+                        plugin_request_payload.payload,
+                        plugin_request_payload.timeout_seconds,
+                    ))
+                },
+                _ => Err("Mismatched payload for PluginRequest"),
+            },
+            Some(CommandName::RespondToPluginRequest) => match protobuf_plugin_command.payload {
+                Some(Payload::RespondToPluginRequestPayload(respond_to_plugin_request_payload)) => {
+                    Ok(PluginCommand::RespondToPluginRequest(
+                        respond_to_plugin_request_payload.request_id,
+                        respond_to_plugin_request_payload.payload,
+                    ))
+                },
+                _ => Err("Mismatched payload for RespondToPluginRequest"),
+            },
+            Some(CommandName::ListPluginPermissions) => {
+                if protobuf_plugin_command.payload.is_some() {
+                    Err("ListPluginPermissions should not have a payload")
+                } else {
+                    Ok(PluginCommand::ListPluginPermissions)
+                }
+            },
+            Some(CommandName::RevokePluginPermissions) => match protobuf_plugin_command.payload {
+                Some(Payload::RevokePluginPermissionsPayload(payload)) => {
+                    Ok(PluginCommand::RevokePluginPermissions(
+                        payload.plugin_url,
+                        permission_types_from_protobuf(&payload.permissions),
+                    ))
+                },
+                _ => Err("Mismatched payload for RevokePluginPermissions"),
+            },
             None => Err("Unrecognized plugin command"),
         }
     }
@@ -1143,6 +1284,7 @@ impl TryFrom<PluginCommand> for ProtobufPluginCommand {
             PluginCommand::SwitchToMode(input_mode) => Ok(ProtobufPluginCommand {
                 name: CommandName::SwitchToMode as i32,
                 payload: Some(Payload::SwitchToModePayload(SwitchToModePayload {
+                    custom_mode_name: custom_input_mode_name(&input_mode),
                     input_mode: ProtobufInputMode::try_from(input_mode)? as i32,
                 })),
             }),
@@ -1363,18 +1505,21 @@ impl TryFrom<PluginCommand> for ProtobufPluginCommand {
                 name: CommandName::ReportCrash as i32,
                 payload: Some(Payload::ReportCrashPayload(payload)),
             }),
-            PluginCommand::RequestPluginPermissions(permissions) => Ok(ProtobufPluginCommand {
-                name: CommandName::RequestPluginPermissions as i32,
-                payload: Some(Payload::RequestPluginPermissionPayload(
-                    RequestPluginPermissionPayload {
-                        permissions: permissions
-                            .iter()
-                            .filter_map(|p| ProtobufPermissionType::try_from(*p).ok())
-                            .map(|p| p as i32)
-                            .collect(),
-                    },
-                )),
-            }),
+            PluginCommand::RequestPluginPermissions(permissions, scopes) => {
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::RequestPluginPermissions as i32,
+                    payload: Some(Payload::RequestPluginPermissionPayload(
+                        RequestPluginPermissionPayload {
+                            permissions: permissions
+                                .iter()
+                                .filter_map(|p| ProtobufPermissionType::try_from(*p).ok())
+                                .map(|p| p as i32)
+                                .collect(),
+                            scopes: (&scopes).into(),
+                        },
+                    )),
+                })
+            },
             PluginCommand::SwitchSession(switch_to_session) => Ok(ProtobufPluginCommand {
                 name: CommandName::SwitchSession as i32,
                 payload: Some(Payload::SwitchSessionPayload(SwitchSessionPayload {
@@ -1605,6 +1750,244 @@ impl TryFrom<PluginCommand> for ProtobufPluginCommand {
                     terminal_pane_id,
                 })),
             }),
+            PluginCommand::SetPaneColor(pane_id, pane_colors) => {
+                let to_protobuf_color = |color: Option<PaletteColor>| {
+                    color.and_then(|c| ProtobufColor::try_from(c).ok())
+                };
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::SetPaneColor as i32,
+                    payload: Some(Payload::SetPaneColorPayload(SetPaneColorPayload {
+                        pane_id: ProtobufPaneId::try_from(pane_id).ok(),
+                        frame_color: to_protobuf_color(pane_colors.frame),
+                        frame_color_focused: to_protobuf_color(pane_colors.frame_focused),
+                        background: to_protobuf_color(pane_colors.background),
+                    })),
+                })
+            },
+            PluginCommand::GetPaneScrollback(pane_id, line_range, with_ansi) => {
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::GetPaneScrollback as i32,
+                    payload: Some(Payload::GetPaneScrollbackPayload(
+                        GetPaneScrollbackPayload {
+                            pane_id: ProtobufPaneId::try_from(pane_id).ok(),
+                            line_range: line_range.map(|line_range| ProtobufLineRange {
+                                start: line_range.start as u64,
+                                end: line_range.end as u64,
+                            }),
+                            with_ansi,
+                        },
+                    )),
+                })
+            },
+            PluginCommand::SubscribeToPaneOutput(pane_ids) => Ok(ProtobufPluginCommand {
+                name: CommandName::SubscribeToPaneOutput as i32,
+                payload: Some(Payload::SubscribeToPaneOutputPayload(
+                    SubscribeToPaneOutputPayload {
+                        pane_ids: pane_ids
+                            .into_iter()
+                            .filter_map(|p_id| ProtobufPaneId::try_from(p_id).ok())
+                            .collect(),
+                    },
+                )),
+            }),
+            PluginCommand::InterceptKeys(keys, input_modes) => {
+                let mut protobuf_keys = vec![];
+                for key in keys {
+                    protobuf_keys.push(key.try_into()?);
+                }
+                let mut protobuf_input_modes = vec![];
+                let mut custom_input_modes = vec![];
+                for input_mode in input_modes {
+                    match input_mode {
+                        InputMode::Custom(name) => custom_input_modes.push(name),
+                        input_mode => {
+                            let protobuf_input_mode: ProtobufInputMode = input_mode.try_into()?;
+                            protobuf_input_modes.push(protobuf_input_mode as i32);
+                        },
+                    }
+                }
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::InterceptKeys as i32,
+                    payload: Some(Payload::InterceptKeysPayload(InterceptKeysPayload {
+                        keys: protobuf_keys,
+                        input_modes: protobuf_input_modes,
+                        custom_input_modes,
+                    })),
+                })
+            },
+            PluginCommand::ReinjectKey(key) => Ok(ProtobufPluginCommand {
+                name: CommandName::ReinjectKey as i32,
+                payload: Some(Payload::ReinjectKeyPayload(key.try_into()?)),
+            }),
+            PluginCommand::RegisterInputMode(name, keybinds) => Ok(ProtobufPluginCommand {
+                name: CommandName::RegisterInputMode as i32,
+                payload: Some(Payload::RegisterInputModePayload(
+                    RegisterInputModePayload { name, keybinds },
+                )),
+            }),
+            PluginCommand::PluginRequest(target, name, payload, timeout_seconds) => {
+                let (destination_plugin_id, plugin_url) = match target {
+                    PluginRequestTarget::PluginId(plugin_id) => (Some(plugin_id), None),
+                    PluginRequestTarget::PluginUrl(plugin_url) => (None, Some(plugin_url)),
+                };
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::PluginRequest as i32,
+                    payload: Some(Payload::PluginRequestPayload(PluginRequestPayload {
+                        destination_plugin_id,
+                        plugin_url,
+                        name,
+                        payload,
+                        timeout_seconds,
+                    })),
+                })
+            },
+            PluginCommand::RespondToPluginRequest(request_id, payload) => {
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::RespondToPluginRequest as i32,
+                    payload: Some(Payload::RespondToPluginRequestPayload(
+                        RespondToPluginRequestPayload {
+                            request_id,
+                            payload,
+                        },
+                    )),
+                })
+            },
+            PluginCommand::ListPluginPermissions => Ok(ProtobufPluginCommand {
+                name: CommandName::ListPluginPermissions as i32,
+                payload: None,
+            }),
+            PluginCommand::RevokePluginPermissions(plugin_url, permissions) => {
+                Ok(ProtobufPluginCommand {
+                    name: CommandName::RevokePluginPermissions as i32,
+                    payload: Some(Payload::RevokePluginPermissionsPayload(
+                        RevokePluginPermissionsPayload {
+                            plugin_url,
+                            permissions: permission_types_to_protobuf(&permissions),
+                        },
+                    )),
+                })
+            },
+        }
+    }
+}
+
+impl From<PaneContents> for ProtobufPaneContents {
+    fn from(pane_contents: PaneContents) -> Self {
+        ProtobufPaneContents {
+            viewport: pane_contents.viewport,
+            scrollback: pane_contents.scrollback,
+            scrollback_offset: pane_contents.scrollback_offset as u64,
+            scrollback_length: pane_contents.scrollback_length as u64,
+        }
+    }
+}
+
+impl From<ProtobufPaneContents> for PaneContents {
+    fn from(protobuf_pane_contents: ProtobufPaneContents) -> Self {
+        PaneContents {
+            viewport: protobuf_pane_contents.viewport,
+            scrollback: protobuf_pane_contents.scrollback,
+            scrollback_offset: protobuf_pane_contents.scrollback_offset as usize,
+            scrollback_length: protobuf_pane_contents.scrollback_length as usize,
+        }
+    }
+}
+
+impl From<Result<PaneContents, String>> for ProtobufPaneContentsResult {
+    fn from(result: Result<PaneContents, String>) -> Self {
+        match result {
+            Ok(pane_contents) => ProtobufPaneContentsResult {
+                pane_contents: Some(pane_contents.into()),
+                error: None,
+            },
+            Err(error) => ProtobufPaneContentsResult {
+                pane_contents: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+impl From<ProtobufPaneContentsResult> for Result<PaneContents, String> {
+    fn from(protobuf_result: ProtobufPaneContentsResult) -> Self {
+        match (protobuf_result.pane_contents, protobuf_result.error) {
+            (_, Some(error)) => Err(error),
+            (Some(pane_contents), None) => Ok(pane_contents.into()),
+            (None, None) => Err("No pane contents received".to_owned()),
+        }
+    }
+}
+
+fn permission_types_from_protobuf(permissions: &[i32]) -> Vec<PermissionType> {
+    permissions
+        .iter()
+        .filter_map(|p| ProtobufPermissionType::from_i32(*p))
+        .filter_map(|p| PermissionType::try_from(p).ok())
+        .collect()
+}
+
+fn permission_types_to_protobuf(permissions: &[PermissionType]) -> Vec<i32> {
+    permissions
+        .iter()
+        .filter_map(|p| ProtobufPermissionType::try_from(*p).ok())
+        .map(|p| p as i32)
+        .collect()
+}
+
+impl From<Vec<PluginPermission>> for ProtobufGrantedPluginPermissionsList {
+    fn from(plugin_permissions: Vec<PluginPermission>) -> Self {
+        ProtobufGrantedPluginPermissionsList {
+            plugin_permissions: plugin_permissions
+                .into_iter()
+                .map(|plugin_permission| ProtobufGrantedPluginPermissions {
+                    permissions: permission_types_to_protobuf(&plugin_permission.permissions),
+                    scopes: (&plugin_permission.scopes).into(),
+                    plugin_url: plugin_permission.name,
+                })
+                .collect(),
         }
     }
 }
+
+impl From<ProtobufGrantedPluginPermissionsList> for Vec<PluginPermission> {
+    fn from(protobuf_list: ProtobufGrantedPluginPermissionsList) -> Self {
+        protobuf_list
+            .plugin_permissions
+            .into_iter()
+            .map(|granted| {
+                PluginPermission::new(
+                    granted.plugin_url,
+                    permission_types_from_protobuf(&granted.permissions),
+                )
+                .with_scopes(granted.scopes.into())
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn serialize_set_pane_color_plugin_command() {
+    use prost::Message;
+    let pane_colors = PaneColors {
+        frame: Some(PaletteColor::Rgb((255, 0, 0))),
+        frame_focused: Some(PaletteColor::EightBit(2)),
+        background: None,
+    };
+    let set_pane_color = PluginCommand::SetPaneColor(PaneId::Terminal(1), pane_colors);
+    let protobuf_plugin_command: ProtobufPluginCommand = set_pane_color.try_into().unwrap();
+    let serialized_protobuf_plugin_command = protobuf_plugin_command.encode_to_vec();
+    let deserialized_protobuf_plugin_command: ProtobufPluginCommand =
+        Message::decode(serialized_protobuf_plugin_command.as_slice()).unwrap();
+    let deserialized_plugin_command: PluginCommand =
+        deserialized_protobuf_plugin_command.try_into().unwrap();
+    match deserialized_plugin_command {
+        PluginCommand::SetPaneColor(pane_id, deserialized_pane_colors) => {
+            assert_eq!(pane_id, PaneId::Terminal(1));
+            assert_eq!(
+                deserialized_pane_colors, pane_colors,
+                "Pane colors properly serialized/deserialized without change"
+            );
+        },
+        other => panic!("Expected SetPaneColor, got: {:?}", other),
+    }
+}
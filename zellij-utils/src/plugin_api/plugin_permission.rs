@@ -1,5 +1,7 @@
-pub use super::generated_api::api::plugin_permission::PermissionType as ProtobufPermissionType;
-use crate::data::PermissionType;
+pub use super::generated_api::api::plugin_permission::{
+    PermissionScope as ProtobufPermissionScope, PermissionType as ProtobufPermissionType,
+};
+use crate::data::{PermissionScopes, PermissionType};
 
 use std::convert::TryFrom;
 
@@ -25,6 +27,11 @@ impl TryFrom<ProtobufPermissionType> for PermissionType {
                 Ok(PermissionType::MessageAndLaunchOtherPlugins)
             },
             ProtobufPermissionType::Reconfigure => Ok(PermissionType::Reconfigure),
+            ProtobufPermissionType::ReadPaneContents => Ok(PermissionType::ReadPaneContents),
+            ProtobufPermissionType::InterceptInput => Ok(PermissionType::InterceptInput),
+            ProtobufPermissionType::HostFilesystemAccess => {
+                Ok(PermissionType::HostFilesystemAccess)
+            },
         }
     }
 }
@@ -51,6 +58,41 @@ impl TryFrom<PermissionType> for ProtobufPermissionType {
                 Ok(ProtobufPermissionType::MessageAndLaunchOtherPlugins)
             },
             PermissionType::Reconfigure => Ok(ProtobufPermissionType::Reconfigure),
+            PermissionType::ReadPaneContents => Ok(ProtobufPermissionType::ReadPaneContents),
+            PermissionType::InterceptInput => Ok(ProtobufPermissionType::InterceptInput),
+            PermissionType::HostFilesystemAccess => {
+                Ok(ProtobufPermissionType::HostFilesystemAccess)
+            },
+        }
+    }
+}
+
+impl From<&PermissionScopes> for Vec<ProtobufPermissionScope> {
+    fn from(scopes: &PermissionScopes) -> Self {
+        scopes
+            .iter()
+            .filter_map(|(permission, scope)| {
+                let permission_type = ProtobufPermissionType::try_from(*permission).ok()?;
+                Some(ProtobufPermissionScope {
+                    permission_type: permission_type as i32,
+                    scope: scope.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<ProtobufPermissionScope>> for PermissionScopes {
+    fn from(protobuf_scopes: Vec<ProtobufPermissionScope>) -> Self {
+        let mut scopes = PermissionScopes::new();
+        for protobuf_scope in protobuf_scopes {
+            if let Some(permission) =
+                ProtobufPermissionType::from_i32(protobuf_scope.permission_type)
+                    .and_then(|p| PermissionType::try_from(p).ok())
+            {
+                scopes.set_scope(permission, protobuf_scope.scope);
+            }
         }
+        scopes
     }
 }
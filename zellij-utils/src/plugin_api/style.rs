@@ -1,9 +1,9 @@
 use super::generated_api::api::style::{
     color::Payload as ProtobufColorPayload, Color as ProtobufColor, ColorType as ProtobufColorType,
     Palette as ProtobufPalette, RgbColorPayload as ProtobufRgbColorPayload, Style as ProtobufStyle,
-    ThemeHue as ProtobufThemeHue,
+    ThemeHue as ProtobufThemeHue, UiColor as ProtobufUiColor,
 };
-use crate::data::{Palette, PaletteColor, Style, ThemeHue};
+use crate::data::{Palette, PaletteColor, Style, ThemeHue, UiColors};
 use crate::errors::prelude::*;
 
 use std::convert::TryFrom;
@@ -108,6 +108,7 @@ impl TryFrom<ProtobufPalette> for Palette {
                 .brown
                 .ok_or("malformed palette payload")?
                 .try_into()?,
+            ui: protobuf_palette.ui_colors.try_into()?,
             ..Default::default()
         })
     }
@@ -139,11 +140,43 @@ impl TryFrom<Palette> for ProtobufPalette {
             silver: Some(palette.silver.try_into()?),
             pink: Some(palette.pink.try_into()?),
             brown: Some(palette.brown.try_into()?),
-            ..Default::default()
+            ui_colors: palette.ui.try_into()?,
         })
     }
 }
 
+impl TryFrom<Vec<ProtobufUiColor>> for UiColors {
+    type Error = &'static str;
+    fn try_from(protobuf_ui_colors: Vec<ProtobufUiColor>) -> Result<Self, &'static str> {
+        let mut ui_colors = UiColors::default();
+        for protobuf_ui_color in protobuf_ui_colors {
+            let color = protobuf_ui_color
+                .color
+                .ok_or("malformed ui color payload")?
+                .try_into()?;
+            // roles unknown to this version are ignored
+            ui_colors.set_role(&protobuf_ui_color.role, color);
+        }
+        Ok(ui_colors)
+    }
+}
+
+impl TryFrom<UiColors> for Vec<ProtobufUiColor> {
+    type Error = &'static str;
+    fn try_from(ui_colors: UiColors) -> Result<Self, &'static str> {
+        let mut protobuf_ui_colors = vec![];
+        for (role, color) in ui_colors.roles() {
+            if let Some(color) = color {
+                protobuf_ui_colors.push(ProtobufUiColor {
+                    role: role.to_owned(),
+                    color: Some(color.try_into()?),
+                });
+            }
+        }
+        Ok(protobuf_ui_colors)
+    }
+}
+
 impl TryFrom<ProtobufColor> for PaletteColor {
     type Error = &'static str;
     fn try_from(protobuf_color: ProtobufColor) -> Result<Self, &'static str> {
@@ -2,6 +2,7 @@ use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use crate::{
+    data::PaneColors,
     input::layout::PluginUserConfiguration,
     input::layout::{
         FloatingPaneLayout, Layout, PercentOrFixed, Run, RunPluginOrAlias, SplitDirection,
@@ -40,6 +41,7 @@ pub struct TabLayoutManifest {
     pub floating_panes: Vec<PaneLayoutManifest>,
     pub is_focused: bool,
     pub hide_floating_panes: bool,
+    pub pane_colors: PaneColors,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -48,9 +50,11 @@ pub struct PaneLayoutManifest {
     pub run: Option<Run>,
     pub cwd: Option<PathBuf>,
     pub is_borderless: bool,
+    pub is_tab_bar: bool,
     pub title: Option<String>,
     pub is_focused: bool,
     pub pane_contents: Option<String>,
+    pub pane_colors: PaneColors,
 }
 
 pub fn serialize_session_layout(
@@ -90,6 +94,7 @@ fn stringify_tab(
     tab_name: String,
     is_focused: bool,
     hide_floating_panes: bool,
+    tab_colors: &PaneColors,
     tiled_panes: &Vec<PaneLayoutManifest>,
     floating_panes: &Vec<PaneLayoutManifest>,
     pane_contents: &mut BTreeMap<String, String>,
@@ -113,6 +118,7 @@ fn stringify_tab(
             if hide_floating_panes {
                 tab_attributes.push(format!("hide_floating_panes=true"));
             }
+            tab_attributes.append(&mut stringify_pane_colors(tab_colors));
             kdl_string.push_str(&kdl_string_from_tab(
                 &tiled_panes,
                 &floating_panes_layout,
@@ -182,6 +188,7 @@ fn kdl_string_from_tiled_pane(
         cwd,
         layout.focus,
         &layout.pane_initial_contents,
+        &layout.pane_colors,
         pane_contents,
         has_children,
     );
@@ -263,6 +270,7 @@ fn stringify_pane_title_and_attributes(
     cwd: Option<PathBuf>,
     focus: Option<bool>,
     initial_pane_contents: &Option<String>,
+    pane_colors: &PaneColors,
     pane_contents: &mut BTreeMap<String, String>,
     has_children: bool,
 ) -> String {
@@ -290,9 +298,26 @@ fn stringify_pane_title_and_attributes(
             pane_contents.insert(file_name.to_string(), initial_pane_contents.clone());
         }
     }
+    for attribute in stringify_pane_colors(pane_colors) {
+        kdl_string.push_str(&format!(" {}", attribute));
+    }
     kdl_string
 }
 
+fn stringify_pane_colors(pane_colors: &PaneColors) -> Vec<String> {
+    let mut attributes = vec![];
+    if let Some(frame_color) = pane_colors.frame {
+        attributes.push(format!("frame_color=\"{}\"", frame_color));
+    }
+    if let Some(frame_color_focused) = pane_colors.frame_focused {
+        attributes.push(format!("frame_color_focused=\"{}\"", frame_color_focused));
+    }
+    if let Some(background) = pane_colors.background {
+        attributes.push(format!("background=\"{}\"", background));
+    }
+    attributes
+}
+
 fn stringify_args(args: Vec<String>, kdl_string: &mut String) {
     if !args.is_empty() {
         let args = args
@@ -311,13 +336,13 @@ fn stringify_plugin(
 ) {
     if let Some(plugin) = plugin {
         if let Some(plugin_config) =
-            plugin_config.and_then(|p| if p.inner().is_empty() { None } else { Some(p) })
+            plugin_config.and_then(|p| if p.is_empty() { None } else { Some(p) })
         {
             kdl_string.push_str(&indent(
                 &format!("plugin location=\"{}\" {{\n", plugin),
                 INDENT,
             ));
-            for (config_key, config_value) in plugin_config.inner() {
+            for (config_key, config_value) in plugin_config.inner_with_settings() {
                 kdl_string.push_str(&indent(
                     &format!("{} \"{}\"\n", config_key, config_value),
                     INDENT,
@@ -348,6 +373,9 @@ fn stringify_tiled_layout_attributes(
     if layout.borderless {
         kdl_string.push_str(&" borderless=true");
     }
+    if layout.tab_bar {
+        kdl_string.push_str(" tab_bar=true");
+    }
     if layout.children_are_stacked {
         kdl_string.push_str(&" stacked=true");
     }
@@ -530,6 +558,7 @@ fn stringify_multiple_tabs(
             tab_name.clone(),
             tab_layout_manifest.is_focused,
             hide_floating_panes,
+            &tab_layout_manifest.pane_colors,
             &tiled_panes,
             &floating_panes,
             pane_contents,
@@ -562,6 +591,7 @@ fn kdl_string_from_floating_pane(
         cwd,
         layout.focus,
         &layout.pane_initial_contents,
+        &layout.pane_colors,
         pane_contents,
         has_children,
     );
@@ -578,7 +608,16 @@ fn tiled_pane_layout_from_manifest(
     manifest: Option<&PaneLayoutManifest>,
     split_size: Option<SplitSize>,
 ) -> TiledPaneLayout {
-    let (run, borderless, is_expanded_in_stack, name, focus, pane_initial_contents) = manifest
+    let (
+        run,
+        borderless,
+        tab_bar,
+        is_expanded_in_stack,
+        name,
+        focus,
+        pane_initial_contents,
+        pane_colors,
+    ) = manifest
         .map(|g| {
             let mut run = g.run.clone();
             if let Some(cwd) = &g.cwd {
@@ -591,21 +630,34 @@ fn tiled_pane_layout_from_manifest(
             (
                 run,
                 g.is_borderless,
+                g.is_tab_bar,
                 g.geom.is_stacked && g.geom.rows.inner > 1,
                 g.title.clone(),
                 Some(g.is_focused),
                 g.pane_contents.clone(),
+                g.pane_colors,
             )
         })
-        .unwrap_or((None, false, false, None, None, None));
+        .unwrap_or((
+            None,
+            false,
+            false,
+            false,
+            None,
+            None,
+            None,
+            PaneColors::default(),
+        ));
     TiledPaneLayout {
         split_size,
         run,
         borderless,
+        tab_bar,
         is_expanded_in_stack,
         name,
         focus,
         pane_initial_contents,
+        pane_colors,
         ..Default::default()
     }
 }
@@ -695,6 +747,7 @@ fn get_floating_panes_layout_from_panegeoms(
                 focus: Some(m.is_focused),
                 already_running: false,
                 pane_initial_contents: m.pane_contents.clone(),
+                pane_colors: m.pane_colors,
             }
         })
         .collect()
@@ -921,6 +974,7 @@ fn get_split_sizes(constraints: &Vec<Constraint>) -> Vec<Option<SplitSize>> {
 mod tests {
 
     use super::*;
+    use crate::data::PaletteColor;
     use crate::pane_size::Dimension;
     use expect_test::expect;
     use serde_json::Value;
@@ -1124,6 +1178,44 @@ mod tests {
 }"#]]
         .assert_eq(&kdl.0);
     }
+
+    #[test]
+    fn pane_colors() {
+        let mut tiled_panes: Vec<PaneLayoutManifest> = PANEGEOMS_JSON[0]
+            .iter()
+            .map(|pg| parse_panegeom_from_json(pg))
+            .map(|geom| PaneLayoutManifest {
+                geom,
+                ..Default::default()
+            })
+            .collect();
+        tiled_panes[1].pane_colors = PaneColors {
+            frame: Some(PaletteColor::Rgb((255, 0, 0))),
+            frame_focused: Some(PaletteColor::EightBit(2)),
+            background: None,
+        };
+        let tab_layout_manifest = TabLayoutManifest {
+            tiled_panes,
+            pane_colors: PaneColors {
+                background: Some(PaletteColor::Rgb((16, 16, 32))),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let global_layout_manifest = GlobalLayoutManifest {
+            tabs: vec![("Tab #1".to_owned(), tab_layout_manifest)],
+            ..Default::default()
+        };
+        let kdl = serialize_session_layout(global_layout_manifest).unwrap();
+        expect![[r##"layout {
+    tab name="Tab #1" background="#101020" {
+        pane frame_color="#ff0000" frame_color_focused="2" size=1
+        pane
+        pane size=2
+    }
+}"##]]
+        .assert_eq(&kdl.0);
+    }
     // utility functions
     fn parse_panegeom_from_json(data_str: &str) -> PaneGeom {
         //
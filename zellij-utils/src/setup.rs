@@ -319,6 +319,11 @@ pub struct Setup {
     #[clap(long, value_parser)]
     pub dump_config: bool,
 
+    /// With --dump-config, dump the current configuration (with all its includes merged) instead
+    /// of the default one
+    #[clap(long, value_parser, requires("dump-config"))]
+    pub merged: bool,
+
     /// Disables loading of configuration file at default location,
     /// loads the defaults that zellij ships with
     #[clap(long, value_parser)]
@@ -372,6 +377,13 @@ impl Setup {
         // note that this can potentially exit the process
         Setup::handle_setup_commands(cli_args);
         let config = Config::try_from(cli_args)?;
+        if let Some(Command::Setup(ref setup)) = &cli_args.command {
+            if setup.dump_config && setup.merged {
+                let add_comments = false;
+                println!("{}", config.to_string(add_comments));
+                process::exit(0);
+            }
+        }
         let cli_config_options: Option<Options> =
             if let Some(Command::Options(options)) = cli_args.command.clone() {
                 Some(options.into())
@@ -439,7 +451,7 @@ impl Setup {
             return Ok(());
         }
 
-        if self.dump_config {
+        if self.dump_config && !self.merged {
             dump_default_config()?;
             std::process::exit(0);
         }
@@ -686,6 +698,7 @@ impl Setup {
                     .and_then(|cli_options| cli_options.default_layout.clone())
             })
             .or_else(|| config.options.default_layout.clone());
+        let layout_vars = cli_args.layout_vars.iter().cloned().collect();
         if let Some(layout_url) = chosen_layout
             .as_ref()
             .and_then(|l| l.to_str())
@@ -697,11 +710,16 @@ impl Setup {
                 }
             })
         {
-            Layout::from_url(layout_url, config)
+            Layout::from_url(layout_url, config, layout_vars)
         } else {
             // we merge-override the config here because the layout might contain configuration
             // that needs to take precedence
-            Layout::from_path_or_default(chosen_layout.as_ref(), layout_dir.clone(), config)
+            Layout::from_path_or_default(
+                chosen_layout.as_ref(),
+                layout_dir.clone(),
+                config,
+                layout_vars,
+            )
         }
     }
     fn handle_setup_commands(cli_args: &CliArgs) {
@@ -108,6 +108,7 @@ pub fn default_palette() -> Palette {
         silver: PaletteColor::EightBit(colors::SILVER),
         pink: PaletteColor::EightBit(colors::PINK),
         brown: PaletteColor::EightBit(colors::BROWN),
+        ui: Default::default(),
     }
 }
 